@@ -0,0 +1,559 @@
+//! Typed wasm client for the backend API.
+
+use crate::{
+    AdminCrawlRunResponse, AdminCtrStats, AdminDeleteVideoResponse, AdminEnqueueRequest,
+    AdminEnqueueResponse, AdminLoginRequest, AdminLoginResponse, AdminLookupResponse,
+    AdminQueueResponse, AdminSettings, AdminStats, AdminTaskStatus, AdminVideoDetail,
+    AdminVideoListResponse, BatchVideoRequest, BatchVideoResponse, CaptionLanguage,
+    CaptionReportRequest, CrawlHistoryEntry, HealthAlert, IndexPreview, IndexingLogResponse,
+    MonitorTag, MonitorTagUpdate, MonitoredChannelStats, MonitoredPlaylistStats, OptOutChannel,
+    OptOutRequest, OptOutResponse, OversizedCaptionReport, PhraseFrequencyResponse, ProxyStatus,
+    PublicChannel, PublicConfig, QueueBatchResponse, RandomQuote, RelevancePreview,
+    RelevancePreviewRequest, RunningMaintenance, SearchFeedbackRequest, SearchResponse,
+    ServiceHealth, SynonymSet, TagCheckResponse, TrendingVideo, VideoMetadata, VideoMetadataDiff,
+    VideoReportCount, VideoTranscript,
+};
+use gloo_net::http::{Request, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+thread_local! {
+    static NETWORK_ERROR_HOOK: RefCell<Option<Rc<dyn Fn()>>> = RefCell::new(None);
+}
+
+/// Registers `hook` to run whenever a request gets no response at all, so the app can
+/// notice that the backend is unreachable. Replaces an earlier hook.
+pub fn on_network_error(hook: impl Fn() + 'static) {
+    NETWORK_ERROR_HOOK.with(|cell| *cell.borrow_mut() = Some(Rc::new(hook)));
+}
+
+/// Runs the hook registered with `on_network_error`, for requests made without the client
+pub fn report_network_error() {
+    // Cloned out first, the hook may itself send requests
+    if let Some(hook) = NETWORK_ERROR_HOOK.with(|cell| cell.borrow().clone()) {
+        hook();
+    }
+}
+
+fn network_error(e: gloo_net::Error) -> ClientError {
+    report_network_error();
+    ClientError::Network(e.to_string())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientError {
+    Request(String),
+    Network(String),
+    Http(u16),
+    Parse(String),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Request(e) => write!(f, "Request error: {}", e),
+            ClientError::Network(e) => write!(f, "Network error: {}", e),
+            ClientError::Http(status) => write!(f, "HTTP error: {}", status),
+            ClientError::Parse(e) => write!(f, "JSON parse error: {}", e),
+        }
+    }
+}
+
+impl From<ClientError> for String {
+    fn from(e: ClientError) -> Self {
+        e.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiClient {
+    base_url: String,
+    token: Option<String>,
+}
+
+impl ApiClient {
+    pub fn new(base_url: &str) -> Self {
+        ApiClient {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: None,
+        }
+    }
+
+    /// Sends the admin token as bearer auth on every request
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.token {
+            Some(token) => request.header("Authorization", &format!("Bearer {}", token)),
+            None => request,
+        }
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Response, ClientError> {
+        let response = self
+            .authorize(request)
+            .send()
+            .await
+            .map_err(network_error)?;
+        check_status(response)
+    }
+
+    async fn send_json<B: Serialize>(
+        &self,
+        request: RequestBuilder,
+        body: &B,
+    ) -> Result<Response, ClientError> {
+        let response = self
+            .authorize(request)
+            .json(body)
+            .map_err(|e| ClientError::Request(e.to_string()))?
+            .send()
+            .await
+            .map_err(network_error)?;
+        check_status(response)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, ClientError> {
+        parse(self.send(Request::get(&self.url(path))).await?).await
+    }
+
+    // Public
+
+    pub async fn health(&self) -> Result<ServiceHealth, ClientError> {
+        self.get("/health").await
+    }
+
+    pub async fn public_config(&self) -> Result<PublicConfig, ClientError> {
+        self.get("/config/public").await
+    }
+
+    /// `params` is the already encoded query string without the leading '?'
+    pub async fn search(&self, params: &str) -> Result<SearchResponse, ClientError> {
+        self.get(&format!("/search/?{}", params)).await
+    }
+
+    /// Matches of `query` per `interval` ("week", "month" or "year") of upload dates
+    pub async fn phrase_frequency(
+        &self,
+        query: &str,
+        interval: &str,
+    ) -> Result<PhraseFrequencyResponse, ClientError> {
+        let request = Request::get(&self.url("/search/frequency"))
+            .query([("q", query), ("interval", interval)]);
+        parse(self.send(request).await?).await
+    }
+
+    /// A random caption, matching `query` if given. The `seed` of a quote gets it again.
+    pub async fn random_quote(
+        &self,
+        query: Option<&str>,
+        seed: Option<u64>,
+    ) -> Result<RandomQuote, ClientError> {
+        let seed = seed.map(|seed| seed.to_string());
+        let request = Request::get(&self.url("/search/random"))
+            .query(query.map(|query| ("q", query)))
+            .query(seed.as_deref().map(|seed| ("seed", seed)));
+        parse(self.send(request).await?).await
+    }
+
+    pub async fn search_feedback(
+        &self,
+        feedback: &SearchFeedbackRequest,
+    ) -> Result<(), ClientError> {
+        self.send_json(Request::post(&self.url("/search/feedback")), feedback)
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn report_caption(&self, report: &CaptionReportRequest) -> Result<(), ClientError> {
+        self.send_json(Request::post(&self.url("/search/report")), report)
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn video_metadata(
+        &self,
+        video_id: &str,
+    ) -> Result<Option<VideoMetadata>, ClientError> {
+        self.get(&format!("/video/{}", video_id)).await
+    }
+
+    pub async fn videos_metadata(
+        &self,
+        video_ids: &[String],
+    ) -> Result<BatchVideoResponse, ClientError> {
+        let body = BatchVideoRequest {
+            video_ids: video_ids.to_vec(),
+        };
+        parse(
+            self.send_json(Request::post(&self.url("/video/batch")), &body)
+                .await?,
+        )
+        .await
+    }
+
+    pub async fn trending_matches(&self, limit: usize) -> Result<Vec<TrendingVideo>, ClientError> {
+        self.get(&format!("/video/trending-matches?limit={}", limit))
+            .await
+    }
+
+    pub async fn public_channels(&self) -> Result<Vec<PublicChannel>, ClientError> {
+        self.get("/video/channels").await
+    }
+
+    /// Public videos of a channel, newest first
+    pub async fn channel_videos(
+        &self,
+        channel_id: &str,
+    ) -> Result<Vec<VideoMetadata>, ClientError> {
+        self.get(&format!("/video/channels/{}/videos", channel_id))
+            .await
+    }
+
+    pub async fn video_languages(
+        &self,
+        video_id: &str,
+    ) -> Result<Vec<CaptionLanguage>, ClientError> {
+        self.get(&format!("/video/{}/languages", video_id)).await
+    }
+
+    /// Captions of the language with the most indexed captions
+    pub async fn transcript(&self, video_id: &str) -> Result<Option<VideoTranscript>, ClientError> {
+        self.get(&format!("/video/{}/transcript", video_id)).await
+    }
+
+    /// One segment of a transcript, in the language with the most indexed captions
+    /// unless `language` is given
+    pub async fn transcript_segment(
+        &self,
+        video_id: &str,
+        language: Option<&str>,
+        segment: i64,
+    ) -> Result<Option<VideoTranscript>, ClientError> {
+        let segment = segment.to_string();
+        let request = Request::get(&self.url(&format!("/video/{}/transcript", video_id)))
+            .query([("segment", segment.as_str())])
+            .query(language.map(|language| ("language", language)));
+        parse(self.send(request).await?).await
+    }
+
+    // Admin
+
+    pub async fn ctr_stats(&self, days: i64) -> Result<AdminCtrStats, ClientError> {
+        self.get(&format!("/admin/stats/ctr?days={}", days)).await
+    }
+
+    pub async fn login(&self, token: &str) -> Result<AdminLoginResponse, ClientError> {
+        let body = AdminLoginRequest {
+            token: token.to_string(),
+        };
+        parse(
+            self.send_json(Request::post(&self.url("/admin/login")), &body)
+                .await?,
+        )
+        .await
+    }
+
+    pub async fn admin_stats(&self) -> Result<AdminStats, ClientError> {
+        self.get("/admin/stats").await
+    }
+
+    pub async fn settings(&self) -> Result<AdminSettings, ClientError> {
+        self.get("/admin/settings").await
+    }
+
+    pub async fn update_settings(
+        &self,
+        settings: &AdminSettings,
+    ) -> Result<AdminSettings, ClientError> {
+        parse(
+            self.send_json(Request::put(&self.url("/admin/settings")), settings)
+                .await?,
+        )
+        .await
+    }
+
+    pub async fn queue(&self) -> Result<AdminQueueResponse, ClientError> {
+        self.get("/admin/queue").await
+    }
+
+    pub async fn enqueue(&self, url: &str) -> Result<AdminEnqueueResponse, ClientError> {
+        let body = AdminEnqueueRequest {
+            url: url.to_string(),
+        };
+        parse(
+            self.send_json(Request::post(&self.url("/admin/queue")), &body)
+                .await?,
+        )
+        .await
+    }
+
+    pub async fn remove_queue_item(&self, item_id: &str) -> Result<(), ClientError> {
+        self.send(Request::delete(
+            &self.url(&format!("/admin/queue/{}", item_id)),
+        ))
+        .await
+        .map(|_| ())
+    }
+
+    /// Resets failed queue items to pending, only those that failed with `kind` if given
+    pub async fn retry_failed(
+        &self,
+        kind: Option<&str>,
+    ) -> Result<QueueBatchResponse, ClientError> {
+        let mut request = Request::post(&self.url("/admin/queue/retry-failed"));
+        if let Some(kind) = kind {
+            request = request.query([("kind", kind)]);
+        }
+        parse(self.send(request).await?).await
+    }
+
+    /// Removes every queue item with `status`, which must be "failed"
+    pub async fn clear_queue(&self, status: &str) -> Result<QueueBatchResponse, ClientError> {
+        let request = Request::post(&self.url("/admin/queue/clear")).query([("status", status)]);
+        parse(self.send(request).await?).await
+    }
+
+    /// Ranks the top videos of a query with the current and a candidate relevance config
+    pub async fn preview_relevance(
+        &self,
+        request: &RelevancePreviewRequest,
+    ) -> Result<RelevancePreview, ClientError> {
+        parse(
+            self.send_json(
+                Request::post(&self.url("/admin/relevance/preview")),
+                request,
+            )
+            .await?,
+        )
+        .await
+    }
+
+    pub async fn synonyms(&self) -> Result<SynonymSet, ClientError> {
+        self.get("/admin/synonyms").await
+    }
+
+    /// Replaces the synonym set, returns it as stored: lowercased, without duplicates
+    /// and groups of less than two terms
+    pub async fn update_synonyms(&self, synonyms: &SynonymSet) -> Result<SynonymSet, ClientError> {
+        parse(
+            self.send_json(Request::put(&self.url("/admin/synonyms")), synonyms)
+                .await?,
+        )
+        .await
+    }
+
+    /// What indexing `video`, a YouTube URL or video ID, would do, without indexing it
+    pub async fn preview(&self, video: &str) -> Result<IndexPreview, ClientError> {
+        let request = Request::post(&self.url("/admin/preview")).query([("video_id", video)]);
+        parse(self.send(request).await?).await
+    }
+
+    pub async fn run_crawl(&self) -> Result<AdminCrawlRunResponse, ClientError> {
+        parse(
+            self.send(Request::post(&self.url("/admin/crawl/run")))
+                .await?,
+        )
+        .await
+    }
+
+    pub async fn videos(
+        &self,
+        page: i64,
+        per_page: i64,
+    ) -> Result<AdminVideoListResponse, ClientError> {
+        self.get(&format!(
+            "/admin/videos?page={}&per_page={}",
+            page, per_page
+        ))
+        .await
+    }
+
+    pub async fn delete_video(
+        &self,
+        video_id: &str,
+    ) -> Result<AdminDeleteVideoResponse, ClientError> {
+        parse(
+            self.send(Request::delete(
+                &self.url(&format!("/admin/video/{}", video_id)),
+            ))
+            .await?,
+        )
+        .await
+    }
+
+    pub async fn oversized_captions(&self) -> Result<OversizedCaptionReport, ClientError> {
+        self.get("/admin/captions/oversized").await
+    }
+
+    pub async fn task_status(&self, task_id: &str) -> Result<AdminTaskStatus, ClientError> {
+        self.get(&format!("/admin/tasks/{}", task_id)).await
+    }
+
+    /// The maintenance operation holding the backend's throttle, if any
+    pub async fn current_maintenance(&self) -> Result<Option<RunningMaintenance>, ClientError> {
+        self.get("/admin/maintenance/current").await
+    }
+
+    pub async fn video_detail(&self, video_id: &str) -> Result<AdminVideoDetail, ClientError> {
+        self.get(&format!("/admin/video/{}", video_id)).await
+    }
+
+    pub async fn video_history(
+        &self,
+        video_id: &str,
+    ) -> Result<Vec<CrawlHistoryEntry>, ClientError> {
+        self.get(&format!("/admin/video/{}/history", video_id))
+            .await
+    }
+
+    /// Crawls the worker finished, newest first. `filters` are `video_id`, `channel_id`
+    /// and `status` query parameters.
+    pub async fn indexing_log(
+        &self,
+        page: i64,
+        per_page: i64,
+        filters: &[(&str, &str)],
+    ) -> Result<IndexingLogResponse, ClientError> {
+        let page = page.to_string();
+        let per_page = per_page.to_string();
+        let request = Request::get(&self.url("/admin/indexing-log"))
+            .query([("page", page.as_str()), ("per_page", per_page.as_str())])
+            .query(filters.iter().copied());
+        parse(self.send(request).await?).await
+    }
+
+    pub async fn video_diff(&self, video_id: &str) -> Result<VideoMetadataDiff, ClientError> {
+        self.get(&format!("/admin/video/{}/diff", video_id)).await
+    }
+
+    pub async fn recrawl_video(&self, video_id: &str) -> Result<AdminEnqueueResponse, ClientError> {
+        let path = format!("/admin/video/{}/recrawl", video_id);
+        parse(self.send(Request::post(&self.url(&path))).await?).await
+    }
+
+    pub async fn opt_outs(&self) -> Result<Vec<OptOutChannel>, ClientError> {
+        self.get("/admin/optout").await
+    }
+
+    /// Opts a channel out, or with `dry_run` only counts what would be deleted
+    pub async fn add_opt_out(
+        &self,
+        request: &OptOutRequest,
+        dry_run: bool,
+    ) -> Result<OptOutResponse, ClientError> {
+        let path = format!("/admin/optout?dry_run={}", dry_run);
+        parse(
+            self.send_json(Request::post(&self.url(&path)), request)
+                .await?,
+        )
+        .await
+    }
+
+    pub async fn remove_opt_out(&self, channel_id: &str) -> Result<(), ClientError> {
+        self.send(Request::delete(
+            &self.url(&format!("/admin/optout/{}", channel_id)),
+        ))
+        .await
+        .map(|_| ())
+    }
+
+    pub async fn proxies(&self) -> Result<Vec<ProxyStatus>, ClientError> {
+        self.get("/admin/proxies").await
+    }
+
+    /// Unacknowledged health alerts
+    pub async fn alerts(&self) -> Result<Vec<HealthAlert>, ClientError> {
+        self.get("/admin/alerts").await
+    }
+
+    pub async fn ack_alert(&self, alert_id: &str) -> Result<(), ClientError> {
+        let path = format!("/admin/alerts/{}/ack", alert_id);
+        self.send(Request::post(&self.url(&path))).await.map(|_| ())
+    }
+
+    pub async fn reports(&self) -> Result<Vec<VideoReportCount>, ClientError> {
+        self.get("/admin/reports").await
+    }
+
+    /// Queue items, videos and monitors matching `query`. Video hits link to pages of
+    /// `per_page` videos.
+    pub async fn lookup(
+        &self,
+        query: &str,
+        per_page: i64,
+    ) -> Result<AdminLookupResponse, ClientError> {
+        let per_page = per_page.to_string();
+        let request = Request::get(&self.url("/admin/lookup"))
+            .query([("q", query), ("per_page", per_page.as_str())]);
+        parse(self.send(request).await?).await
+    }
+
+    // Monitor
+
+    pub async fn channels(&self) -> Result<Vec<MonitoredChannelStats>, ClientError> {
+        self.get("/monitor/channel").await
+    }
+
+    pub async fn playlists(&self) -> Result<Vec<MonitoredPlaylistStats>, ClientError> {
+        self.get("/monitor/playlist").await
+    }
+
+    pub async fn monitor_tags(&self) -> Result<Vec<MonitorTag>, ClientError> {
+        self.get("/monitor/tag").await
+    }
+
+    pub async fn set_tag_public(&self, tag: &str, public: bool) -> Result<MonitorTag, ClientError> {
+        let url = self.url(&format!("/monitor/tag/{}", encode_segment(tag)));
+        parse(
+            self.send_json(Request::put(&url), &MonitorTagUpdate { public })
+                .await?,
+        )
+        .await
+    }
+
+    /// Queues a check of every active channel and playlist tagged with `tag`
+    pub async fn check_tag(&self, tag: &str) -> Result<TagCheckResponse, ClientError> {
+        let request = Request::post(&self.url("/monitor/check")).query([("tag", tag)]);
+        parse(self.send(request).await?).await
+    }
+}
+
+/// Percent-encodes everything but unreserved characters, for user input in a path
+fn encode_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn check_status(response: Response) -> Result<Response, ClientError> {
+    if response.ok() {
+        Ok(response)
+    } else {
+        Err(ClientError::Http(response.status()))
+    }
+}
+
+async fn parse<T: DeserializeOwned>(response: Response) -> Result<T, ClientError> {
+    response
+        .json::<T>()
+        .await
+        .map_err(|e| ClientError::Parse(e.to_string()))
+}
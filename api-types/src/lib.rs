@@ -0,0 +1,1140 @@
+//! Request and response types shared by the backend and the frontend.
+//! Changing a type here breaks compilation on both sides instead of JSON parsing at runtime.
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "client")]
+pub mod client;
+
+// Admin
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminLoginRequest {
+    pub token: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminLoginResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AdminStats {
+    pub total_videos: i64,
+    pub total_captions: i64,
+    pub last_crawl_time: Option<i64>,
+    pub active_monitors: i32,
+    pub queue_size: usize,
+    #[serde(default)]
+    pub crawl_running: bool,
+    #[serde(default)]
+    pub captions_paused_until: Option<i64>, // unix millis, set while YouTube throttles us
+    #[serde(default)]
+    pub total_duration_hours: f64,
+    #[serde(default)]
+    pub videos_missing_duration: i64, // counted as zero hours, need a recrawl
+    #[serde(default)]
+    pub videos_skipped_too_long: i64, // captions skipped for exceeding the maximum duration
+    #[serde(default)]
+    pub elasticsearch_circuit: String, // "closed", "open" or "half_open", see `ServiceHealth`
+    #[serde(default)]
+    pub transcripts_upgraded: i64, // auto-generated captions replaced by a manual track on recrawl
+}
+
+/// Settings the admin can change at runtime, they reset to the configured values on restart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminSettings {
+    pub search_deadline_ms: u64, // overall budget of a search, context is dropped beyond it
+}
+
+/// Boosts of the clauses of the caption query. Searches use the default, the admin can
+/// preview a candidate against it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelevanceConfig {
+    pub exact_phrase_boost: f64, // phrase on the unstemmed field, natural searches with +terms
+    pub phrase_boost: f64,       // natural phrase, sloppy phrase of wide searches
+    pub stemmed_phrase_boost: f64,
+    pub phrase_slop: u32, // words allowed between the terms of the sloppy phrase
+    pub all_terms_boost: f64,
+    pub fuzzy_all_terms_boost: f64,
+    pub partial_terms_boost: f64, // most terms present
+    pub fuzzy_partial_terms_boost: f64,
+    pub synonym_boost: f64, // the query with a synonym swapped in, relative to the query
+}
+
+impl Default for RelevanceConfig {
+    fn default() -> Self {
+        RelevanceConfig {
+            exact_phrase_boost: 4.0,
+            phrase_boost: 3.0,
+            stemmed_phrase_boost: 1.0,
+            phrase_slop: 3,
+            all_terms_boost: 2.5,
+            fuzzy_all_terms_boost: 2.0,
+            partial_terms_boost: 1.5,
+            fuzzy_partial_terms_boost: 1.0,
+            synonym_boost: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelevancePreviewRequest {
+    pub query: String,
+    #[serde(default)]
+    pub search_type: Option<String>, // "natural" (default) or "wide"
+    pub candidate: RelevanceConfig,
+}
+
+/// The top videos of a query ranked with the current and with a candidate config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelevancePreview {
+    pub current_config: RelevanceConfig,
+    pub current: Vec<RankedVideo>,
+    pub candidate: Vec<RankedVideo>,
+    pub moves: Vec<RankMove>, // videos ranked differently, by candidate rank
+    pub unchanged: usize,
+}
+
+/// A video of a preview ranking and the scores of its matching captions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RankedVideo {
+    pub video_id: String,
+    pub title: Option<String>, // None if the metadata is missing
+    pub avg_score: f64,        // ranked by
+    pub max_score: f64,
+    pub match_count: i64,
+}
+
+/// 1-based ranks of a video, None if it is outside the previewed top
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RankMove {
+    pub video_id: String,
+    pub current_rank: Option<usize>,
+    pub candidate_rank: Option<usize>,
+}
+
+/// Terms searches treat as equivalent, e.g. ["rsi", "roberts space industries"]. A query
+/// containing one term of a group also matches the others, with a lower score.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SynonymSet {
+    pub groups: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminEnqueueRequest {
+    pub url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminEnqueueResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminCrawlRunResponse {
+    pub success: bool,
+    pub message: String,
+    pub attempting: usize,
+}
+
+/// One page of an admin list endpoint, pages are 1-based
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Paginated<T> {
+    // The list was called "videos" or "entries" before, accepted until the next release
+    #[serde(alias = "videos", alias = "entries")]
+    pub items: Vec<T>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+    #[serde(default)]
+    pub total_pages: i64,
+}
+
+impl<T> Paginated<T> {
+    pub fn new(items: Vec<T>, total: i64, page: i64, per_page: i64) -> Self {
+        let total_pages = if per_page > 0 {
+            (total + per_page - 1) / per_page
+        } else {
+            0
+        };
+        Paginated {
+            items,
+            total,
+            page,
+            per_page,
+            total_pages,
+        }
+    }
+
+    /// A list that is never paged, everything on the first page
+    pub fn single_page(items: Vec<T>) -> Self {
+        let total = items.len() as i64;
+        Paginated::new(items, total, 1, total.max(1))
+    }
+}
+
+/// The whole queue, on a single page
+pub type AdminQueueResponse = Paginated<QueueItem>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueueItem {
+    pub id: String,
+    pub video_id: String,
+    pub status: String,
+    pub added_at: String,
+    pub processed_at: Option<String>,
+    #[serde(default)]
+    pub added_at_ms: i64, // unix millis
+    #[serde(default)]
+    pub processed_at_ms: Option<i64>, // unix millis
+    #[serde(default)]
+    pub started_at_ms: Option<i64>, // unix millis, set while processing
+    pub error_message: Option<String>,
+    #[serde(default)]
+    pub failure_kind: Option<String>, // set while failed, e.g. "timeout" or "metadata_fetch"
+    #[serde(default)]
+    pub retries: u32, // times the item was reset from failed to pending
+    #[serde(default)]
+    pub source: Option<CheckSource>, // None for manually enqueued videos
+}
+
+/// Result of a batch operation on the queue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueueBatchResponse {
+    pub affected: usize,
+}
+
+/// The monitor that discovered a video: a channel (through its uploads playlist) or a
+/// monitored playlist
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "id", rename_all = "snake_case")]
+pub enum CheckSource {
+    Channel(String),
+    Playlist(String),
+}
+
+pub type AdminVideoListResponse = Paginated<VideoMetadata>;
+
+/// Deleting a video only starts the deletion of its captions in the background
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminDeleteVideoResponse {
+    pub video_id: String,
+    pub task_id: String,
+}
+
+/// Captions longer than the split limit, indexed before long captions were split.
+/// Recrawling their videos splits them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OversizedCaptionReport {
+    pub max_chars: usize,
+    pub captions: i64,
+    pub videos: i64,
+    pub longest_chars: i64,
+    pub video_ids: Vec<String>, // most affected first, at most 50
+}
+
+/// Status of a background Elasticsearch task
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminTaskStatus {
+    pub task_id: String,
+    pub completed: bool,
+    pub total: i64,   // documents the task will process
+    pub deleted: i64, // documents deleted so far
+    pub error: Option<String>,
+}
+
+/// Heavy maintenance operation holding the maintenance throttle, only one runs at a time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunningMaintenance {
+    pub name: String,
+    pub started_at: i64, // unix seconds
+    pub done: i64,
+    pub total: i64, // 0 while unknown
+}
+
+impl AdminTaskStatus {
+    /// Share of processed documents between 0 and 1
+    pub fn progress(&self) -> f64 {
+        if self.completed {
+            1.0
+        } else if self.total > 0 {
+            (self.deleted as f64 / self.total as f64).min(1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoMetadataDiff {
+    pub video_id: String,
+    pub previous_crawl_date: Option<i64>, // None if the video was crawled only once
+    pub latest_crawl_date: i64,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Everything known about one video, gathered for the admin detail drawer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminVideoDetail {
+    pub video: VideoMetadata,
+    /// Monitor the video came from, the source of its first logged crawl if it predates
+    /// `discovered_by`. None for manually enqueued videos.
+    pub source: Option<CheckSource>,
+    pub source_name: Option<String>,
+    pub playlists: Vec<PlaylistMembership>,
+    pub caption_count: i64,
+    pub caption_languages: Vec<String>,
+    pub indexing_log: Vec<IndexingLogEntry>,  // newest first
+    pub channel_monitor_active: Option<bool>, // None if the channel isn't monitored
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistMembership {
+    pub playlist_id: String,
+    pub playlist_name: Option<String>, // None unless the playlist is monitored
+}
+
+/// What crawling a video would index, fetched without writing anything
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexPreview {
+    pub video_id: String,
+    pub metadata: Option<VideoMetadata>, // None if fetching it failed
+    pub exclusions: Vec<PreviewExclusion>,
+    pub caption_tracks: Vec<CaptionTrack>,
+    pub selected_track: Option<CaptionTrack>, // None if no track is in a prioritized language
+    pub caption_count: usize,
+    pub captions: Vec<TranscriptLine>, // the first ones only
+    #[serde(default)]
+    pub captions_deferred: bool, // transcript fetching is throttled or timed out, try again later
+}
+
+/// A filter that would keep a video or its captions out of the index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PreviewExclusion {
+    pub filter: String, // "opt_out" or "max_duration"
+    pub reason: String,
+    pub monitor: Option<CheckSource>, // the monitor whose setting applies, None for global ones
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub previous: String,
+    pub current: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoReportCount {
+    pub video_id: String,
+    pub title: Option<String>,
+    pub report_count: i64,
+    pub reported_captions: i64,
+    pub last_reported_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub key_hash: String,
+    pub label: String,
+    pub daily_quota: i64,
+    pub created_at: i64, // unix
+    pub usage_day: String,
+    pub usage_today: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiKeyCreateRequest {
+    pub label: String,
+    pub daily_quota: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiKeyCreateResponse {
+    pub key: String, // only returned once
+    pub label: String,
+    pub daily_quota: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminNotificationResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProxyStatus {
+    pub url: String, // credentials redacted
+    pub healthy: bool,
+    pub requests: u64,
+    pub failures: u64,
+    pub disabled_until: Option<i64>, // unix millis
+}
+
+/// Where an admin lookup hit was found, decides the admin page it links to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LookupKind {
+    Queue,
+    Video,
+    Channel,
+    Playlist,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LookupHit {
+    pub kind: LookupKind,
+    pub id: String, // queue item, video, channel or playlist id
+    pub label: String,
+    pub detail: Option<String>,
+    pub page: Option<i64>, // page of the admin video list the video is on
+    pub link: String,      // admin page with the row highlighted
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AdminLookupResponse {
+    pub queue: Vec<LookupHit>,
+    pub videos: Vec<LookupHit>,
+    pub monitors: Vec<LookupHit>,
+}
+
+impl AdminLookupResponse {
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty() && self.videos.is_empty() && self.monitors.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthAlert {
+    pub id: String,
+    pub kind: String, // "monitor_stalled", "queue_stalled" or "crawl_failing"
+    pub message: String,
+    pub created_at: i64, // unix millis
+    pub acknowledged: bool,
+}
+
+/// Click-through of one rank or search type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CtrRow {
+    pub key: String, // rank or search type
+    pub impressions: i64,
+    pub clicks: i64,
+    pub expands: i64,
+}
+
+impl CtrRow {
+    /// Clicks and expands per impression
+    pub fn ctr(&self) -> f64 {
+        if self.impressions > 0 {
+            (self.clicks + self.expands) as f64 / self.impressions as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Impressions by rank are the result pages that reached the rank,
+/// by search type they are the result pages shown
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminCtrStats {
+    pub days: i64,
+    pub by_rank: Vec<CtrRow>,
+    pub by_search_type: Vec<CtrRow>,
+}
+
+// Opt-out
+
+/// Channel whose creator asked to be removed. None of its videos are indexed again,
+/// whichever way they are enqueued.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptOutChannel {
+    pub channel_id: String,
+    pub reason: String,
+    pub created_at_ms: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptOutRequest {
+    pub channel_id: String,
+    #[serde(default)]
+    pub reason: String,
+    /// Also deletes the channel's videos and captions that are already indexed
+    #[serde(default)]
+    pub delete_content: bool,
+}
+
+/// What an opt-out did, or with `dry_run` would do: the counts are the channel's indexed
+/// content, `task_id` is the caption deletion if content is deleted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptOutResponse {
+    pub channel_id: String,
+    pub dry_run: bool,
+    pub videos: i64,
+    pub captions: i64,
+    pub task_id: Option<String>,
+}
+
+// Monitor
+
+/// How removing a monitor treats its indexed videos, `?purge=` on the monitor deletion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MonitorPurge {
+    Videos,  // remove the monitor and delete its videos
+    Preview, // only count what `Videos` would remove, the monitor stays
+}
+
+impl MonitorPurge {
+    pub const ALL: [MonitorPurge; 2] = [MonitorPurge::Videos, MonitorPurge::Preview];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            MonitorPurge::Videos => "videos",
+            MonitorPurge::Preview => "preview",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|purge| purge.key() == key)
+    }
+}
+
+/// Videos removed with a monitor. For a channel all its videos are deleted, for a playlist
+/// only those no other playlist or monitored channel brought in, the others just leave it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitorPurgeResponse {
+    pub monitor_id: String,
+    pub dry_run: bool,
+    pub videos: i64,   // deleted
+    pub captions: i64, // of the deleted videos
+    pub untagged_videos: i64,
+    pub task_id: Option<String>, // caption deletion, None if no video was deleted
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewChannel {
+    pub input: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewPlaylist {
+    pub input: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BulkMonitorRequest {
+    pub inputs: Vec<String>,
+}
+
+/// Outcome of one input of a bulk add, either `added` or `error` is set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BulkMonitorResult {
+    pub input: String,
+    pub added: Option<MonitorAddResponse>,
+    pub error: Option<String>,
+}
+
+/// Partial update of a monitor, only the fields that are set are changed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MonitorUpdate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hidden: Option<bool>, // channels only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enqueue_order: Option<EnqueueOrder>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discovery_mode: Option<DiscoveryMode>, // channels only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_duration_seconds: Option<i64>, // 0 removes the override
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>, // replaces all tags, empty removes them
+}
+
+impl MonitorUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.active.is_none()
+            && self.hidden.is_none()
+            && self.enqueue_order.is_none()
+            && self.discovery_mode.is_none()
+            && self.max_duration_seconds.is_none()
+            && self.tags.is_none()
+    }
+}
+
+/// A tag used on monitors. Public tags are shown on the channels of the public directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitorTag {
+    pub tag: String,
+    pub public: bool,
+    pub channels: usize,
+    pub playlists: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitorTagUpdate {
+    pub public: bool,
+}
+
+/// Monitors a tag check started on, checked in the background
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagCheckResponse {
+    pub tag: String,
+    pub channels: usize,
+    pub playlists: usize,
+}
+
+/// Order in which a monitor check enqueues the videos that aren't indexed yet
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnqueueOrder {
+    #[default]
+    NewestFirst,
+    OldestFirst,
+    PlaylistOrder, // as returned by the API, oldest first for uploads playlists
+}
+
+impl EnqueueOrder {
+    pub const ALL: [EnqueueOrder; 3] = [
+        EnqueueOrder::NewestFirst,
+        EnqueueOrder::OldestFirst,
+        EnqueueOrder::PlaylistOrder,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            EnqueueOrder::NewestFirst => "newest_first",
+            EnqueueOrder::OldestFirst => "oldest_first",
+            EnqueueOrder::PlaylistOrder => "playlist_order",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|order| order.key() == key)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EnqueueOrder::NewestFirst => "Newest first",
+            EnqueueOrder::OldestFirst => "Oldest first",
+            EnqueueOrder::PlaylistOrder => "Playlist order",
+        }
+    }
+}
+
+/// How the scheduled check of a channel finds new uploads. A manual check always
+/// uses the API, the feed only lists the latest uploads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscoveryMode {
+    #[default]
+    Api, // uploads playlist via the YouTube Data API, costs quota
+    Rss, // public Atom feed of the ~15 latest uploads, free
+}
+
+impl DiscoveryMode {
+    pub const ALL: [DiscoveryMode; 2] = [DiscoveryMode::Api, DiscoveryMode::Rss];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            DiscoveryMode::Api => "api",
+            DiscoveryMode::Rss => "rss",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.key() == key)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DiscoveryMode::Api => "YouTube API",
+            DiscoveryMode::Rss => "RSS feed (latest uploads only)",
+        }
+    }
+}
+
+/// Validation error of a single request field, returned with status 422
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// What an add-monitor request resolved to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitorAddResponse {
+    pub id: String,
+    pub name: String,
+    pub canonical_url: String,
+    /// The created monitor, so lists can append it without reloading. Only the one
+    /// matching the added kind is set.
+    #[serde(default)]
+    pub channel: Option<MonitoredChannelStats>,
+    #[serde(default)]
+    pub playlist: Option<MonitoredPlaylistStats>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitoredChannelStats {
+    pub channel_id: String,
+    pub channel_name: String,
+    pub active: bool,
+    pub created_at: String,
+    #[serde(default)]
+    pub created_at_ms: i64, // unix millis
+    pub videos_indexed: i32,
+    pub videos_uploaded: i64,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub indexed_hours: f64,
+    #[serde(default)]
+    pub backfilling: bool, // too many videos missing, older ones are enqueued a share per cycle
+    #[serde(default)]
+    pub backfill_eta_ms: Option<i64>, // at last week's indexing rate, None without progress
+    #[serde(default)]
+    pub enqueue_order: EnqueueOrder,
+    #[serde(default)]
+    pub discovery_mode: DiscoveryMode,
+    #[serde(default)]
+    pub max_duration_seconds: Option<i64>, // None uses MAX_VIDEO_DURATION_SECONDS
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitoredPlaylistStats {
+    pub playlist_id: String,
+    pub playlist_name: String,
+    pub active: bool,
+    pub created_at: String,
+    #[serde(default)]
+    pub created_at_ms: i64, // unix millis
+    pub videos_indexed: i32,
+    pub videos_added: i64,
+    #[serde(default)]
+    pub backfilling: bool,
+    #[serde(default)]
+    pub backfill_eta_ms: Option<i64>,
+    #[serde(default)]
+    pub enqueue_order: EnqueueOrder,
+    #[serde(default)]
+    pub max_duration_seconds: Option<i64>, // None uses MAX_VIDEO_DURATION_SECONDS
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+// Search
+
+/// Response header of search and video routes carrying the archive generation. It
+/// changes whenever videos are crawled or deleted, cached metadata of older generations
+/// may be stale.
+pub const GENERATION_HEADER: &str = "X-Archive-Generation";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchResponse {
+    pub results: Vec<SearchResult>,
+    pub total_videos: usize,
+    pub total_captions: usize,
+    pub page: usize,
+    pub page_size: usize,
+    pub total_pages: usize,
+    #[serde(default)]
+    pub clamped: bool, // the requested page was out of range, `page` is the last one instead
+    /// Parts of the search that degraded, e.g. "metadata unavailable" when the
+    /// sort had to fall back to relevance
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+/// A single matching caption of `/search/cursor`, without grouping or neighbor context
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaptionHit {
+    pub video_id: String,
+    pub start_time: f64,
+    pub end_time: f64,
+    pub text: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CursorSearchResponse {
+    pub hits: Vec<CaptionHit>,
+    /// Opaque, pass it as `cursor` with the same query for the next page. None once
+    /// all matches were returned.
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub video_id: String,
+    pub start_time: f64,
+    pub end_time: f64,
+    pub snippet_html: String,
+    /// Only set for `plain=true` requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet_text: Option<String>,
+    /// Matching captions of the video, only set for `collapse=true` requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_count: Option<i64>,
+}
+
+/// A random caption with its context and video, see `GET /search/random`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RandomQuote {
+    pub result: SearchResult,
+    pub video: VideoMetadata,
+    pub seed: u64, // picks the same quote again as long as the archive doesn't change
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaptionReportRequest {
+    pub video_id: String,
+    pub start_time: f64,
+    pub query: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedbackAction {
+    /// A result page was shown, `rank` is the last rank on it
+    View,
+    /// The YouTube link of a result was opened
+    Click,
+    /// A video group was expanded
+    Expand,
+}
+
+impl FeedbackAction {
+    pub fn key(&self) -> &'static str {
+        match self {
+            FeedbackAction::View => "view",
+            FeedbackAction::Click => "click",
+            FeedbackAction::Expand => "expand",
+        }
+    }
+}
+
+/// Click data for relevance tuning. The query is only stored as a hash.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchFeedbackRequest {
+    pub query: String,
+    pub search_type: String, // "natural" or "wide"
+    #[serde(default)]
+    pub video_id: String, // empty for views
+    pub rank: usize,         // 1-based position of the video across all pages
+    pub action: FeedbackAction,
+}
+
+// Video
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VideoMetadata {
+    pub title: String,
+    pub channel_name: String,
+    pub channel_id: String,
+    pub upload_date: i64, // unix
+    pub crawl_date: i64,  // unix
+    pub duration: i64,    // in seconds
+    pub likes: i64,
+    pub views: i64,
+    pub comment_count: i64,
+    pub has_captions: bool,
+    pub tags: Vec<String>,
+    pub video_id: String,
+    pub playlists: Vec<String>,
+    #[serde(default)]
+    pub crawl_history: Vec<CrawlHistoryEntry>,
+    #[serde(default)]
+    pub hidden: bool, // excluded from public endpoints
+    #[serde(default)]
+    pub deleting: bool, // captions are being deleted, excluded from all listings
+    #[serde(default)]
+    pub caption_tracks: Vec<CaptionTrack>, // all tracks YouTube offered at crawl time
+    #[serde(default)]
+    pub caption_source: Option<String>, // "manual" or "generated", track the captions came from
+    #[serde(default)]
+    pub discovered_by: Option<CheckSource>, // monitor that first found the video
+    #[serde(default)]
+    pub previous: Option<MetadataSnapshot>, // mutable fields as of the crawl before
+    #[serde(default)]
+    pub skip_reason: Option<String>, // why captions weren't indexed, "too_long"
+    #[serde(default)]
+    pub caption_coverage: Option<f64>, // share of the duration with captions, None if unknown
+}
+
+/// Mutable fields of a video at one crawl
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetadataSnapshot {
+    pub crawl_date: i64, // unix
+    pub title: String,
+    pub duration: i64,
+    pub likes: i64,
+    pub views: i64,
+    pub comment_count: i64,
+    pub tags: Vec<String>,
+}
+
+impl From<&VideoMetadata> for MetadataSnapshot {
+    fn from(metadata: &VideoMetadata) -> Self {
+        MetadataSnapshot {
+            crawl_date: metadata.crawl_date,
+            title: metadata.title.clone(),
+            duration: metadata.duration,
+            likes: metadata.likes,
+            views: metadata.views,
+            comment_count: metadata.comment_count,
+            tags: metadata.tags.clone(),
+        }
+    }
+}
+
+impl VideoMetadata {
+    /// Whether the title changed since the crawl before
+    pub fn is_renamed(&self) -> bool {
+        self.previous
+            .as_ref()
+            .is_some_and(|previous| previous.title != self.title)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CaptionTrack {
+    pub language_code: String,
+    pub language: String,
+    pub is_generated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaptionLanguage {
+    pub language_code: String,
+    pub language: String,
+    /// None for captions indexed before tracks were recorded
+    pub is_generated: Option<bool>,
+    pub caption_count: i64,
+}
+
+/// All indexed captions of a video in one language, ordered by time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoTranscript {
+    pub video_id: String,
+    pub language_code: String,
+    pub lines: Vec<TranscriptLine>,
+    #[serde(default)]
+    pub segment: Option<i64>, // the segment `lines` are from, None for the whole transcript
+    #[serde(default)]
+    pub segment_count: i64,
+}
+
+/// Length of the time buckets captions are grouped into, so long transcripts can be
+/// loaded one segment at a time
+pub const CAPTION_SEGMENT_SECONDS: f64 = 600.0;
+
+/// Segment of a caption starting at `start_time`
+pub fn caption_segment(start_time: f64) -> i64 {
+    (start_time / CAPTION_SEGMENT_SECONDS).floor().max(0.0) as i64
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptLine {
+    pub text: String,
+    pub start_time: f64,
+    pub end_time: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CrawlHistoryEntry {
+    pub crawl_date: i64, // unix
+    pub views: i64,
+    pub likes: i64,
+    pub caption_count: i64,
+}
+
+/// One crawl of a video as the worker finished it, kept in the indexing log long after
+/// its queue item is gone
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexingLogEntry {
+    pub video_id: String,
+    pub title: String,
+    pub channel_id: String,
+    pub channel_name: String,
+    pub source: Option<CheckSource>,
+    /// "completed" or "failed"
+    pub status: String,
+    /// What went wrong for failed crawls: "metadata_fetch", "captions_deferred", "timeout"
+    /// or "opted_out"
+    pub failure_kind: Option<String>,
+    /// Why a completed crawl indexed no captions on purpose, "skipped: too long"
+    #[serde(default)]
+    pub note: Option<String>,
+    pub captions_indexed: i64,
+    pub processing_ms: i64,
+    pub finished_at: i64, // unix millis
+}
+
+pub type IndexingLogResponse = Paginated<IndexingLogEntry>;
+
+/// How often a phrase was said over time: caption matches per bucket of the videos'
+/// upload dates, oldest bucket first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhraseFrequencyResponse {
+    pub query: String,
+    pub interval: String, // "week", "month" or "year"
+    pub buckets: Vec<FrequencyBucket>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrequencyBucket {
+    pub start: i64, // unix seconds
+    pub captions: i64,
+    pub videos: i64,
+}
+
+/// Video that often matched the popular queries of the last week
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrendingVideo {
+    pub video_id: String,
+    pub title: String,
+    pub channel_name: String,
+    pub recent_match_score: i64, // distinct popular queries it matched
+}
+
+/// Channel with public videos, offered as a search filter
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PublicChannel {
+    pub channel_id: String,
+    pub channel_name: String,
+    pub video_count: i64,
+    #[serde(default)]
+    pub tags: Vec<String>, // public tags of the channel's monitor
+    #[serde(default)]
+    pub upload_histogram: Vec<UploadMonth>, // oldest first, months without uploads included
+}
+
+/// Public videos of a channel uploaded in one calendar month
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadMonth {
+    pub month: String, // "2024-03"
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchVideoRequest {
+    pub video_ids: Vec<String>,
+}
+
+/// `videos` is in the order of the request, None for unknown, hidden and deleted
+/// videos as well as for the ids listed in `errors`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BatchVideoResponse {
+    pub videos: Vec<Option<VideoMetadata>>,
+    #[serde(default)]
+    pub errors: Vec<BatchVideoError>,
+}
+
+/// A video whose metadata couldn't be loaded, as opposed to one that doesn't exist
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchVideoError {
+    pub video_id: String,
+    pub error: String,
+}
+
+// Config
+
+/// Answer of `/health`. The backend is up whenever it answers, searches fail fast while
+/// the Elasticsearch circuit isn't closed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceHealth {
+    pub status: String,                // "ok" or "degraded"
+    pub elasticsearch_circuit: String, // "closed", "open" or "half_open"
+}
+
+/// Backend settings the frontend discovers at startup instead of assuming them.
+/// Public contract: fields may be added, never renamed or removed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PublicConfig {
+    pub app_name: String,
+    pub features: PublicFeatures,
+    pub max_page_size: usize,           // largest page_size a search accepts
+    pub caption_languages: Vec<String>, // language codes captions are indexed in, by priority
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PublicFeatures {
+    pub wide_search: bool,
+    pub exact_terms: bool,
+    pub transcripts: bool,     // /video/<id>/transcript
+    pub api_keys: bool,        // X-Api-Key raises the anonymous rate limit
+    pub thumbnail_proxy: bool, // thumbnails are served from /video/<id>/thumbnail
+    #[serde(default)]
+    pub trending_matches: bool, // /video/trending-matches, needs the search log
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn pages_are_counted_from_the_total() {
+        let page = Paginated::new(vec!["a"], 41, 3, 20);
+        assert_eq!(page.total_pages, 3);
+        assert_eq!(Paginated::new(Vec::<&str>::new(), 0, 1, 20).total_pages, 0);
+        assert_eq!(Paginated::new(vec!["a"], 40, 1, 20).total_pages, 2);
+        assert_eq!(Paginated::new(vec!["a"], 5, 1, 0).total_pages, 0);
+
+        let single = Paginated::single_page(vec!["a", "b"]);
+        assert_eq!(
+            (single.page, single.per_page, single.total_pages),
+            (1, 2, 1)
+        );
+        let empty = Paginated::single_page(Vec::<&str>::new());
+        assert_eq!((empty.total, empty.per_page, empty.total_pages), (0, 1, 0));
+    }
+
+    #[test]
+    fn pages_serialize_with_the_new_field_names() {
+        let page = Paginated::new(vec!["a".to_string()], 1, 1, 20);
+        assert_eq!(
+            serde_json::to_value(&page).unwrap(),
+            json!({ "items": ["a"], "total": 1, "page": 1, "per_page": 20, "total_pages": 1 })
+        );
+        let parsed: Paginated<String> =
+            serde_json::from_value(serde_json::to_value(&page).unwrap()).unwrap();
+        assert_eq!(parsed, page);
+    }
+
+    #[test]
+    fn old_list_names_still_deserialize() {
+        // AdminVideoListResponse and IndexingLogResponse before they became Paginated
+        for list in ["videos", "entries"] {
+            let parsed: Paginated<String> = serde_json::from_value(json!({
+                list: ["a", "b"],
+                "total": 42,
+                "page": 2,
+                "per_page": 20
+            }))
+            .unwrap_or_else(|e| panic!("{list}: {e}"));
+            assert_eq!(parsed.items, ["a", "b"], "{list}");
+            assert_eq!((parsed.total, parsed.page, parsed.per_page), (42, 2, 20));
+            assert_eq!(parsed.total_pages, 0, "missing before, defaulted");
+        }
+    }
+
+    #[test]
+    fn unknown_list_names_are_rejected() {
+        let parsed = serde_json::from_value::<Paginated<String>>(json!({
+            "rows": ["a"],
+            "total": 1,
+            "page": 1,
+            "per_page": 20
+        }));
+        assert!(parsed.is_err());
+    }
+}
@@ -1,13 +1,30 @@
 use log::info;
 use rocket::http::Status;
 use rocket::serde::json::Json;
-use rocket::{delete, get, post, State};
+use rocket::{delete, get, post, put, Responder, State};
+use std::time::Duration;
 
+use crate::config::{APP_NAME, CRAWL_BURST_MAX, SMTP_CONFIG};
 use crate::models::{
-    AdminEnqueueRequest, AdminEnqueueResponse, AdminLoginRequest, AdminLoginResponse,
-    AdminQueueResponse, AdminStats, AdminToken, AdminVideoListResponse,
+    AdminCrawlRunResponse, AdminCtrStats, AdminDeleteVideoResponse, AdminEnqueueRequest,
+    AdminEnqueueResponse, AdminLoginRequest, AdminLoginResponse, AdminLookupResponse,
+    AdminNotificationResponse, AdminQueueResponse, AdminSettings, AdminStats, AdminTaskStatus,
+    AdminToken, AdminVideoDetail, AdminVideoListResponse, ApiKey, ApiKeyCreateRequest,
+    ApiKeyCreateResponse, CrawlHistoryEntry, HealthAlert, IndexPreview, IndexingLogResponse,
+    OptOutChannel, OptOutRequest, OptOutResponse, OversizedCaptionReport, ProxyStatus,
+    QueueBatchResponse, RelevancePreview, RelevancePreviewRequest, RunningMaintenance, SynonymSet,
+    VideoMetadataDiff, VideoReportCount,
 };
-use crate::services::admin_service;
+use crate::services::es_breaker::ES_BREAKER;
+use crate::services::raw_capture::{RawCapture, RAW_CAPTURES};
+use crate::services::search_service::{SearchOptions, SortBy, SortOrder};
+use crate::services::youtube_api::YOUTUBE_PROXIES;
+use crate::services::{
+    admin_service, api_key_service, feedback_service, health_service, indexing_log_service,
+    notification_service, optout_service, relevance_service, report_service, search_service,
+    synonym_service, video_service,
+};
+use crate::utils;
 use crate::AppState;
 
 #[post("/login", data = "<login_request>")]
@@ -39,22 +56,66 @@ pub async fn admin_stats(_token: AdminToken, state: &State<AppState>) -> Json<Ad
                 last_crawl_time: None,
                 active_monitors: 0,
                 queue_size: 0,
+                crawl_running: state.video_queue.is_crawl_running(),
+                captions_paused_until: None,
+                total_duration_hours: 0.0,
+                videos_missing_duration: 0,
+                videos_skipped_too_long: 0,
+                elasticsearch_circuit: ES_BREAKER.state().name().to_string(),
+                transcripts_upgraded: 0,
             })
         }
     }
 }
 
+#[get("/settings")]
+pub async fn get_settings(_token: AdminToken) -> Json<AdminSettings> {
+    Json(AdminSettings {
+        search_deadline_ms: search_service::search_deadline().as_millis() as u64,
+    })
+}
+
+/// Applies the settings right away, values out of their bounds are clamped. Returns
+/// the applied settings.
+#[put("/settings", data = "<settings>")]
+pub async fn put_settings(
+    _token: AdminToken,
+    settings: Json<AdminSettings>,
+) -> Json<AdminSettings> {
+    let deadline =
+        search_service::set_search_deadline(Duration::from_millis(settings.search_deadline_ms));
+    Json(AdminSettings {
+        search_deadline_ms: deadline.as_millis() as u64,
+    })
+}
+
+/// Click-through of search results over the last `days` days, 30 by default
+#[get("/stats/ctr?<days>")]
+pub async fn get_ctr_stats(
+    _token: AdminToken,
+    state: &State<AppState>,
+    days: Option<i64>,
+) -> Result<Json<AdminCtrStats>, Status> {
+    let days = days.unwrap_or(30).clamp(1, 365);
+    match feedback_service::get_ctr_stats(&state.es_client, days).await {
+        Ok(stats) => Ok(Json(stats)),
+        Err(e) => {
+            log::error!("Failed to get click-through stats: {e:?}");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
 #[get("/queue")]
-pub async fn get_queue(_token: AdminToken, state: &State<AppState>) -> Json<AdminQueueResponse> {
+pub async fn get_queue(
+    _token: AdminToken,
+    state: &State<AppState>,
+) -> Result<Json<AdminQueueResponse>, Status> {
     match admin_service::get_admin_queue(&state.video_queue).await {
-        Ok(response) => Json(response),
+        Ok(response) => Ok(Json(response)),
         Err(e) => {
             log::error!("Failed to get admin queue: {e:?}");
-            Json(AdminQueueResponse {
-                success: false,
-                message: "Failed to retrieve queue".to_string(),
-                items: vec![],
-            })
+            Err(Status::InternalServerError)
         }
     }
 }
@@ -80,6 +141,138 @@ pub async fn admin_enqueue(
     }
 }
 
+/// Ranks the top videos of a query with the current relevance config and a candidate
+#[post("/relevance/preview", data = "<request>")]
+pub async fn preview_relevance(
+    _token: AdminToken,
+    state: &State<AppState>,
+    request: Json<RelevancePreviewRequest>,
+) -> Result<Json<RelevancePreview>, Status> {
+    let query = request.query.trim();
+    if query.is_empty() {
+        return Err(Status::UnprocessableEntity);
+    }
+    let options = match request.search_type.as_deref() {
+        None | Some("natural") => SearchOptions::natural(SortBy::Relevance, SortOrder::Desc),
+        Some("wide") => SearchOptions::wide(SortBy::Relevance, SortOrder::Desc),
+        Some(_) => return Err(Status::UnprocessableEntity),
+    };
+    match relevance_service::preview(&state.es_client, query, &options, &request.candidate).await {
+        Ok(preview) => Ok(Json(preview)),
+        Err(e) => {
+            log::error!("Failed to preview the relevance config: {e:?}");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[get("/synonyms")]
+pub async fn get_synonyms(_token: AdminToken) -> Json<SynonymSet> {
+    Json(synonym_service::synonyms())
+}
+
+/// Replaces the synonym set, searches expand queries with it right away. Returns the
+/// normalized set.
+#[put("/synonyms", data = "<synonyms>")]
+pub async fn put_synonyms(
+    _token: AdminToken,
+    state: &State<AppState>,
+    synonyms: Json<SynonymSet>,
+) -> Result<Json<SynonymSet>, Status> {
+    match synonym_service::set_synonyms(&state.es_client, &synonyms).await {
+        Ok(synonyms) => Ok(Json(synonyms)),
+        Err(e) => {
+            log::error!("Failed to store the synonyms: {e:?}");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// Dry run of indexing a single video, `video_id` may also be a YouTube URL
+#[post("/preview?<video_id>")]
+pub async fn preview_index(
+    _token: AdminToken,
+    state: &State<AppState>,
+    video_id: &str,
+) -> Result<Json<IndexPreview>, Status> {
+    let video_id =
+        utils::extract_youtube_video_id(video_id).map_err(|_| Status::UnprocessableEntity)?;
+    Ok(Json(
+        admin_service::preview_video(&state.es_client, &video_id).await,
+    ))
+}
+
+#[post("/crawl/run?<max>")]
+pub async fn run_crawl(
+    _token: AdminToken,
+    state: &State<AppState>,
+    max: Option<i32>,
+) -> Result<Json<AdminCrawlRunResponse>, Status> {
+    let max = max.unwrap_or(*CRAWL_BURST_MAX);
+
+    match admin_service::start_crawl_pass(
+        &state.es_client,
+        &state.video_queue,
+        &state.generation,
+        max,
+    ) {
+        Some(attempting) => {
+            info!("Manual crawl pass started for up to {} items", attempting);
+            Ok(Json(AdminCrawlRunResponse {
+                success: true,
+                message: format!("Crawling up to {} queued videos", attempting),
+                attempting,
+            }))
+        }
+        None => Err(Status::Conflict),
+    }
+}
+
+/// A heavy maintenance operation, refused with the running one while another holds the
+/// maintenance throttle. The one 409 response of all endpoints that take the throttle.
+#[derive(Responder)]
+pub enum MaintenanceError {
+    #[response(status = 409)]
+    Busy(Json<RunningMaintenance>),
+    Failed(Status),
+}
+
+impl From<RunningMaintenance> for MaintenanceError {
+    fn from(running: RunningMaintenance) -> Self {
+        MaintenanceError::Busy(Json(running))
+    }
+}
+
+impl From<Status> for MaintenanceError {
+    fn from(status: Status) -> Self {
+        MaintenanceError::Failed(status)
+    }
+}
+
+/// The maintenance operation holding the throttle, null while none runs
+#[get("/maintenance/current")]
+pub async fn get_current_maintenance(
+    _token: AdminToken,
+    state: &State<AppState>,
+) -> Json<Option<RunningMaintenance>> {
+    Json(state.maintenance.current())
+}
+
+/// Stitches the captions of every video into the sentence index anew, in the background
+#[post("/sentences/rebuild")]
+pub async fn rebuild_sentences(
+    _token: AdminToken,
+    state: &State<AppState>,
+) -> Result<Json<AdminEnqueueResponse>, MaintenanceError> {
+    let run = state.maintenance.try_start("rebuild sentences")?;
+    admin_service::start_sentence_rebuild(&state.es_client, run);
+    info!("Sentence rebuild started");
+    Ok(Json(AdminEnqueueResponse {
+        success: true,
+        message: "Rebuilding the sentence index".to_string(),
+    }))
+}
+
 #[delete("/queue/<id>")]
 pub async fn remove_queue_item(
     _token: AdminToken,
@@ -104,19 +297,85 @@ pub async fn remove_queue_item(
     }
 }
 
+/// Resets failed queue items to pending, only those that failed with `kind` if given
+#[post("/queue/retry-failed?<kind>")]
+pub async fn retry_failed_queue_items(
+    _token: AdminToken,
+    state: &State<AppState>,
+    kind: Option<&str>,
+) -> Json<QueueBatchResponse> {
+    let kind = kind.map(str::trim).filter(|kind| !kind.is_empty());
+    Json(QueueBatchResponse {
+        affected: admin_service::retry_failed_queue_items(&state.video_queue, kind),
+    })
+}
+
+/// Removes every queue item with `status`, which must be "failed"
+#[post("/queue/clear?<status>")]
+pub async fn clear_queue(
+    _token: AdminToken,
+    state: &State<AppState>,
+    status: &str,
+) -> Result<Json<QueueBatchResponse>, Status> {
+    match admin_service::clear_queue(&state.video_queue, status) {
+        Ok(affected) => Ok(Json(QueueBatchResponse { affected })),
+        Err(e) => {
+            log::warn!("Rejected clearing the queue: {e}");
+            Err(Status::UnprocessableEntity)
+        }
+    }
+}
+
 #[delete("/video/<video_id>")]
 pub async fn delete_video_endpoint(
     _token: AdminToken,
     state: &State<AppState>,
     video_id: &str,
-) -> Result<Status, Status> {
-    match admin_service::delete_video(&state.es_client, video_id).await {
-        Ok(_) => {
-            info!("Video deleted successfully: {}", video_id);
-            Ok(Status::Ok)
+) -> Result<Json<AdminDeleteVideoResponse>, MaintenanceError> {
+    let run = state
+        .maintenance
+        .try_start(&format!("delete video {video_id}"))?;
+    match admin_service::delete_video(&state.es_client, &state.generation, video_id, run).await {
+        Ok(task_id) => {
+            info!("Deleting video {} in task {}", video_id, task_id);
+            Ok(Json(AdminDeleteVideoResponse {
+                video_id: video_id.to_string(),
+                task_id,
+            }))
         }
         Err(e) => {
             log::error!("Failed to delete video: {e:?}");
+            Err(Status::InternalServerError.into())
+        }
+    }
+}
+
+/// How many captions are longer than MAX_CAPTION_CHARS, to plan recrawling their videos
+#[get("/captions/oversized")]
+pub async fn get_oversized_captions(
+    _token: AdminToken,
+    state: &State<AppState>,
+) -> Result<Json<OversizedCaptionReport>, Status> {
+    match admin_service::oversized_caption_report(&state.es_client).await {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => {
+            log::error!("Failed to count oversized captions: {e:?}");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[get("/tasks/<task_id>")]
+pub async fn get_task(
+    _token: AdminToken,
+    state: &State<AppState>,
+    task_id: &str,
+) -> Result<Json<AdminTaskStatus>, Status> {
+    match admin_service::get_task_status(&state.es_client, task_id).await {
+        Ok(Some(status)) => Ok(Json(status)),
+        Ok(None) => Err(Status::NotFound),
+        Err(e) => {
+            log::error!("Failed to get task status: {e:?}");
             Err(Status::InternalServerError)
         }
     }
@@ -129,25 +388,335 @@ pub async fn get_videos(
     page: Option<i64>,
     per_page: Option<i64>,
 ) -> Json<AdminVideoListResponse> {
-    let page = page.unwrap_or(1);
-    let per_page = per_page.unwrap_or(20);
+    let page = page.unwrap_or(1).max(1);
+    let per_page = per_page.unwrap_or(20).max(1);
 
     match admin_service::get_videos_paginated(&state.es_client, page, per_page).await {
         Ok(response) => {
             info!(
                 "Retrieved {} videos for page {}",
-                response.videos.len(),
+                response.items.len(),
                 page
             );
             Json(response)
         }
         Err(e) => {
             log::error!("Failed to get videos: {e:?}");
-            Json(AdminVideoListResponse {
-                videos: vec![],
-                total: 0,
-                page,
-                per_page,
+            Json(AdminVideoListResponse::new(vec![], 0, page, per_page))
+        }
+    }
+}
+
+#[get("/lookup?<q>&<per_page>")]
+pub async fn admin_lookup(
+    _token: AdminToken,
+    state: &State<AppState>,
+    q: &str,
+    per_page: Option<i64>,
+) -> Result<Json<AdminLookupResponse>, Status> {
+    let per_page = per_page.unwrap_or(20);
+
+    match admin_service::lookup(&state.es_client, &state.video_queue, q, per_page).await {
+        Ok(response) => Ok(Json(response)),
+        Err(e) => {
+            log::error!("Admin lookup for '{}' failed: {e:?}", q);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[get("/reports")]
+pub async fn get_reports(
+    _token: AdminToken,
+    state: &State<AppState>,
+) -> Result<Json<Vec<VideoReportCount>>, Status> {
+    match report_service::get_report_counts(&state.es_client).await {
+        Ok(reports) => Ok(Json(reports)),
+        Err(e) => {
+            log::error!("Failed to get caption reports: {e:?}");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[post("/video/<video_id>/recrawl")]
+pub async fn recrawl_video(
+    _token: AdminToken,
+    state: &State<AppState>,
+    video_id: &str,
+) -> Result<Json<AdminEnqueueResponse>, Status> {
+    if !utils::is_video_id(video_id) {
+        return Err(Status::BadRequest);
+    }
+    match video_service::is_indexed_video(&state.es_client, video_id).await {
+        Ok(true) => {}
+        Ok(false) => return Err(Status::NotFound),
+        Err(e) => {
+            log::error!("Failed to look up video {video_id} for a recrawl: {e:?}");
+            return Err(Status::InternalServerError);
+        }
+    }
+
+    state.video_queue.add_video(video_id.to_string());
+    info!("Video enqueued for recrawl: {}", video_id);
+    Ok(Json(AdminEnqueueResponse {
+        success: true,
+        message: format!("Video {} added to queue for recrawl", video_id),
+    }))
+}
+
+#[get("/video/<video_id>")]
+pub async fn get_video_detail(
+    _token: AdminToken,
+    state: &State<AppState>,
+    video_id: &str,
+) -> Result<Json<AdminVideoDetail>, Status> {
+    match admin_service::get_video_detail(&state.es_client, video_id).await {
+        Ok(Some(detail)) => Ok(Json(detail)),
+        Ok(None) => Err(Status::NotFound),
+        Err(e) => {
+            log::error!("Failed to get video detail: {e:?}");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[get("/video/<video_id>/history")]
+pub async fn get_video_history(
+    _token: AdminToken,
+    state: &State<AppState>,
+    video_id: &str,
+) -> Result<Json<Vec<CrawlHistoryEntry>>, Status> {
+    match admin_service::get_crawl_history(&state.es_client, video_id).await {
+        Ok(Some(history)) => Ok(Json(history)),
+        Ok(None) => Err(Status::NotFound),
+        Err(e) => {
+            log::error!("Failed to get crawl history: {e:?}");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// Crawls the worker finished, newest first, optionally of one video, one channel or
+/// with one status ("completed" or "failed")
+#[get("/indexing-log?<page>&<per_page>&<video_id>&<channel_id>&<status>")]
+pub async fn get_indexing_log(
+    _token: AdminToken,
+    state: &State<AppState>,
+    page: Option<i64>,
+    per_page: Option<i64>,
+    video_id: Option<&str>,
+    channel_id: Option<&str>,
+    status: Option<&str>,
+) -> Result<Json<IndexingLogResponse>, Status> {
+    let page = page.unwrap_or(1).max(1);
+    let per_page = per_page.unwrap_or(20).clamp(1, 100);
+    if status.is_some_and(|status| {
+        status != indexing_log_service::STATUS_COMPLETED
+            && status != indexing_log_service::STATUS_FAILED
+    }) {
+        return Err(Status::BadRequest);
+    }
+
+    match indexing_log_service::get_entries(
+        &state.es_client,
+        video_id,
+        channel_id,
+        status,
+        page,
+        per_page,
+    )
+    .await
+    {
+        Ok(response) => Ok(Json(response)),
+        Err(e) => {
+            log::error!("Failed to get indexing log: {e:?}");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[get("/video/<video_id>/diff")]
+pub async fn get_video_diff(
+    _token: AdminToken,
+    state: &State<AppState>,
+    video_id: &str,
+) -> Result<Json<VideoMetadataDiff>, Status> {
+    match admin_service::get_metadata_diff(&state.es_client, video_id).await {
+        Ok(Some(diff)) => Ok(Json(diff)),
+        Ok(None) => Err(Status::NotFound),
+        Err(e) => {
+            log::error!("Failed to diff video metadata: {e:?}");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[get("/optout")]
+pub async fn get_opt_outs(_token: AdminToken) -> Json<Vec<OptOutChannel>> {
+    Json(optout_service::list_opt_outs().await)
+}
+
+/// Opts a channel out. With `dry_run` only the channel's indexed content is counted, so
+/// the deletion can be confirmed before anything is stored.
+#[post("/optout?<dry_run>", data = "<request>")]
+pub async fn add_opt_out(
+    _token: AdminToken,
+    state: &State<AppState>,
+    request: Json<OptOutRequest>,
+    dry_run: Option<bool>,
+) -> Result<Json<OptOutResponse>, MaintenanceError> {
+    let channel_id = request.channel_id.trim();
+    if !utils::is_channel_id(channel_id) {
+        return Err(Status::BadRequest.into());
+    }
+    let dry_run = dry_run.unwrap_or(false);
+    let run = if request.delete_content && !dry_run {
+        Some(
+            state
+                .maintenance
+                .try_start(&format!("delete content of channel {channel_id}"))?,
+        )
+    } else {
+        None
+    };
+
+    match optout_service::add_opt_out(&state.es_client, &state.generation, &request, dry_run, run)
+        .await
+    {
+        Ok(response) => Ok(Json(response)),
+        Err(e) => {
+            log::error!("Failed to opt out channel {}: {e:?}", request.channel_id);
+            Err(Status::InternalServerError.into())
+        }
+    }
+}
+
+#[delete("/optout/<channel_id>")]
+pub async fn remove_opt_out(
+    _token: AdminToken,
+    state: &State<AppState>,
+    channel_id: &str,
+) -> Status {
+    match optout_service::remove_opt_out(&state.es_client, channel_id).await {
+        Ok(true) => Status::NoContent,
+        Ok(false) => Status::NotFound,
+        Err(e) => {
+            log::error!("Failed to remove opt-out: {e:?}");
+            Status::InternalServerError
+        }
+    }
+}
+
+#[get("/proxies")]
+pub async fn get_proxies(_token: AdminToken) -> Json<Vec<ProxyStatus>> {
+    Json(YOUTUBE_PROXIES.status())
+}
+
+/// Raw YouTube API responses captured for a video, one per endpoint. 404 if none were
+/// captured, capturing is off unless `CAPTURE_RAW_RESPONSES` is set.
+#[get("/raw/<video_id>")]
+pub async fn get_raw_captures(
+    _token: AdminToken,
+    video_id: &str,
+) -> Result<Json<Vec<RawCapture>>, Status> {
+    let captures = RAW_CAPTURES.get(video_id);
+    if captures.is_empty() {
+        return Err(Status::NotFound);
+    }
+    Ok(Json(captures))
+}
+
+#[get("/alerts?<all>")]
+pub async fn get_alerts(
+    _token: AdminToken,
+    state: &State<AppState>,
+    all: Option<bool>,
+) -> Result<Json<Vec<HealthAlert>>, Status> {
+    match health_service::list_alerts(&state.es_client, all.unwrap_or(false)).await {
+        Ok(alerts) => Ok(Json(alerts)),
+        Err(e) => {
+            log::error!("Failed to list health alerts: {e:?}");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[post("/alerts/<alert_id>/ack")]
+pub async fn ack_alert(
+    _token: AdminToken,
+    state: &State<AppState>,
+    alert_id: &str,
+) -> Result<Status, Status> {
+    match health_service::acknowledge_alert(&state.es_client, alert_id).await {
+        Ok(true) => Ok(Status::NoContent),
+        Ok(false) => Err(Status::NotFound),
+        Err(e) => {
+            log::error!("Failed to acknowledge health alert {alert_id}: {e:?}");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[post("/apikeys", data = "<request>")]
+pub async fn create_api_key(
+    _token: AdminToken,
+    state: &State<AppState>,
+    request: Json<ApiKeyCreateRequest>,
+) -> Result<Json<ApiKeyCreateResponse>, Status> {
+    if request.label.trim().is_empty() || request.daily_quota <= 0 {
+        return Err(Status::BadRequest);
+    }
+
+    match api_key_service::create_api_key(
+        &state.es_client,
+        request.label.trim(),
+        request.daily_quota,
+    )
+    .await
+    {
+        Ok(response) => Ok(Json(response)),
+        Err(e) => {
+            log::error!("Failed to create API key: {e:?}");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[get("/apikeys")]
+pub async fn get_api_keys(
+    _token: AdminToken,
+    state: &State<AppState>,
+) -> Result<Json<Vec<ApiKey>>, Status> {
+    match api_key_service::list_api_keys(&state.es_client, &state.api_key_usage).await {
+        Ok(keys) => Ok(Json(keys)),
+        Err(e) => {
+            log::error!("Failed to list API keys: {e:?}");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[post("/notifications/test")]
+pub async fn test_notification(_token: AdminToken) -> Json<AdminNotificationResponse> {
+    let Some(smtp_config) = &*SMTP_CONFIG else {
+        return Json(AdminNotificationResponse {
+            success: false,
+            message: "Email notifications are not configured".to_string(),
+        });
+    };
+
+    let email = notification_service::compose_test_email(&APP_NAME);
+    match notification_service::send_email(smtp_config, &email).await {
+        Ok(()) => Json(AdminNotificationResponse {
+            success: true,
+            message: format!("Test email sent to {}", smtp_config.to.join(", ")),
+        }),
+        Err(e) => {
+            log::error!("Failed to send test email: {e:?}");
+            Json(AdminNotificationResponse {
+                success: false,
+                message: format!("Failed to send test email: {}", e),
             })
         }
     }
@@ -0,0 +1,20 @@
+use crate::models::ServiceHealth;
+use crate::services::es_breaker::{BreakerState, ES_BREAKER};
+use rocket::get;
+use rocket::serde::json::Json;
+
+/// Up whenever it answers, "degraded" while searches are rejected because Elasticsearch
+/// is unreachable
+#[get("/")]
+pub async fn get_health() -> Json<ServiceHealth> {
+    let circuit = ES_BREAKER.state();
+    Json(ServiceHealth {
+        status: if circuit == BreakerState::Closed {
+            "ok"
+        } else {
+            "degraded"
+        }
+        .to_string(),
+        elasticsearch_circuit: circuit.name().to_string(),
+    })
+}
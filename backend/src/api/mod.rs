@@ -1,9 +1,13 @@
 pub mod admin;
+mod health;
 mod monitor;
+mod public_config;
 pub mod search;
 pub mod video;
 
 pub use admin::*;
+pub use health::*;
 pub use monitor::*;
+pub use public_config::*;
 pub use search::*;
 pub use video::*;
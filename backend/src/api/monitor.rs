@@ -1,54 +1,129 @@
-use crate::models::AdminToken;
+use crate::api::admin::MaintenanceError;
+use crate::models::{
+    AdminToken, BulkMonitorRequest, BulkMonitorResult, CheckSource, FieldError, MonitorAddResponse,
+    MonitorPurge, MonitorPurgeResponse, MonitorTag, MonitorTagUpdate, MonitorUpdate,
+    MonitoredChannelStats, MonitoredPlaylistStats, NewChannel, NewPlaylist, RunningMaintenance,
+    TagCheckResponse,
+};
+use crate::services::maintenance_throttle::MaintenanceRun;
 use crate::services::monitoring_service::{
-    add_monitored_channel, add_monitored_playlist, check_channel_for_new_videos,
-    check_playlist_for_new_videos, get_monitored_channels_list, get_monitored_playlist_list,
-    remove_monitored_channel, remove_monitored_playlist, set_channel_active, set_playlist_active,
+    add_monitored_bulk, add_monitored_channel, add_monitored_playlist,
+    check_channel_for_new_videos, check_source_for_new_videos, get_monitored_channel_stats,
+    get_monitored_channels_list, get_monitored_playlist_list, get_monitored_playlist_stats,
+    remove_monitored_channel, remove_monitored_playlist, set_channel_active, set_channel_hidden,
+    set_playlist_active, start_tag_check, update_channel, update_playlist, validate_monitor_update,
 };
+use crate::services::{admin_service, monitor_tag_service};
 use crate::AppState;
 use rocket::http::Status;
 use rocket::serde::json::Json;
-use rocket::{delete, get, post, State};
-use serde::{Deserialize, Serialize};
+use rocket::{delete, get, patch, post, put, Responder, State};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct NewChannel {
-    input: String,
+#[post("/channel", data = "<channel>")]
+pub async fn add_channel(
+    _token: AdminToken,
+    channel: Json<NewChannel>,
+    state: &State<AppState>,
+) -> Result<(Status, Json<MonitorAddResponse>), Status> {
+    match add_monitored_channel(&channel.into_inner().input, &state.es_client).await {
+        Ok(added) => Ok((Status::Created, Json(added))),
+        Err(_) => Err(Status::InternalServerError),
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct NewPlaylist {
-    input: String,
+const MAX_BULK_INPUTS: usize = 200;
+
+#[post("/channel/bulk", data = "<request>")]
+pub async fn add_channels_bulk(
+    _token: AdminToken,
+    request: Json<BulkMonitorRequest>,
+    state: &State<AppState>,
+) -> Result<Json<Vec<BulkMonitorResult>>, Status> {
+    if request.inputs.len() > MAX_BULK_INPUTS {
+        return Err(Status::PayloadTooLarge);
+    }
+    Ok(Json(
+        add_monitored_bulk(&request.inputs, false, &state.es_client).await,
+    ))
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct MonitoredChannelStats {
-    pub channel_id: String,
-    pub channel_name: String,
-    pub active: bool,
-    pub created_at: String,
-    pub videos_indexed: i32,
-    pub videos_uploaded: i64,
+#[derive(Responder)]
+pub enum MonitorUpdateError {
+    #[response(status = 422)]
+    Invalid(Json<Vec<FieldError>>),
+    Refused(MaintenanceError),
 }
-#[derive(Debug, Serialize, Deserialize)]
-pub struct MonitoredPlaylistStats {
-    pub playlist_id: String,
-    pub playlist_name: String,
-    pub active: bool,
-    pub created_at: String,
-    pub videos_indexed: i32,
-    pub videos_added: i64,
+
+impl From<RunningMaintenance> for MonitorUpdateError {
+    fn from(running: RunningMaintenance) -> Self {
+        MonitorUpdateError::Refused(running.into())
+    }
 }
 
-#[post("/channel", data = "<channel>")]
-pub async fn add_channel(
+impl From<Status> for MonitorUpdateError {
+    fn from(status: Status) -> Self {
+        MonitorUpdateError::Refused(status.into())
+    }
+}
+
+#[patch("/channel/<channel_id>", data = "<update>")]
+pub async fn patch_channel(
     _token: AdminToken,
-    channel: Json<NewChannel>,
+    channel_id: &str,
+    update: Json<MonitorUpdate>,
     state: &State<AppState>,
-) -> Result<Status, Status> {
-    match add_monitored_channel(&channel.into_inner().input, &state.es_client).await {
-        Ok(_) => Ok(Status::Created),
-        Err(_) => Err(Status::InternalServerError),
+) -> Result<Json<MonitoredChannelStats>, MonitorUpdateError> {
+    let errors = validate_monitor_update(&update, false);
+    if !errors.is_empty() {
+        return Err(MonitorUpdateError::Invalid(Json(errors)));
     }
+
+    // Hiding rewrites every document of the channel
+    let run = match update.hidden {
+        Some(_) => Some(
+            state
+                .maintenance
+                .try_start(&format!("update channel {channel_id}"))?,
+        ),
+        None => None,
+    };
+    match update_channel(
+        channel_id,
+        &update,
+        &state.es_client,
+        &state.generation,
+        run.as_ref(),
+    )
+    .await
+    {
+        Ok(true) => Ok(channel_response(channel_id, state).await?),
+        Ok(false) => Err(Status::NotFound.into()),
+        Err(e) => {
+            log::error!("Failed to update channel {}: {}", channel_id, e);
+            Err(Status::InternalServerError.into())
+        }
+    }
+}
+
+/// The monitor as it is after a change, so the admin panel can replace it in place
+async fn channel_response(
+    channel_id: &str,
+    state: &State<AppState>,
+) -> Result<Json<MonitoredChannelStats>, Status> {
+    get_monitored_channel_stats(&state.es_client, channel_id)
+        .await
+        .map(Json)
+        .ok_or(Status::NotFound)
+}
+
+async fn playlist_response(
+    playlist_id: &str,
+    state: &State<AppState>,
+) -> Result<Json<MonitoredPlaylistStats>, Status> {
+    get_monitored_playlist_stats(&state.es_client, playlist_id)
+        .await
+        .map(Json)
+        .ok_or(Status::NotFound)
 }
 
 #[get("/channel")]
@@ -59,19 +134,68 @@ pub async fn get_channels(
     Ok(Json(get_monitored_channels_list(&state.es_client).await))
 }
 
-#[delete("/channel/<channel_id>")]
+/// A removed monitor, with `?purge=` also what was or would be deleted with it
+#[derive(Responder)]
+pub enum MonitorRemoval {
+    Removed(Status),
+    Purged(Json<MonitorPurgeResponse>),
+}
+
+fn parse_purge(purge: Option<&str>) -> Result<Option<MonitorPurge>, Status> {
+    purge
+        .map(|key| MonitorPurge::from_key(key).ok_or(Status::BadRequest))
+        .transpose()
+}
+
+/// The maintenance run a purge deletes under, taken before the monitor is removed so a
+/// busy throttle leaves it in place. None for a preview.
+fn start_purge(
+    state: &State<AppState>,
+    purge: MonitorPurge,
+    monitor: &str,
+) -> Result<Option<MaintenanceRun>, MaintenanceError> {
+    if purge == MonitorPurge::Preview {
+        return Ok(None);
+    }
+    Ok(Some(
+        state.maintenance.try_start(&format!("purge {monitor}"))?,
+    ))
+}
+
+/// Removes the monitor, `?purge=videos` also deletes its videos and `?purge=preview`
+/// only counts them without removing anything
+#[delete("/channel/<channel_id>?<purge>")]
 pub async fn remove_channel(
     _token: AdminToken,
     channel_id: &str,
+    purge: Option<&str>,
     state: &State<AppState>,
-) -> Result<Status, Status> {
+) -> Result<MonitorRemoval, MaintenanceError> {
     if channel_id.is_empty() {
-        return Err(Status::BadRequest);
+        return Err(Status::BadRequest.into());
     }
+    let purge = parse_purge(purge)?;
+    let run = purge
+        .map(|purge| start_purge(state, purge, &format!("channel {channel_id}")))
+        .transpose()?
+        .flatten();
 
-    match remove_monitored_channel(&channel_id, &state.es_client).await {
-        Ok(_) => Ok(Status::NoContent),
-        Err(_) => Err(Status::InternalServerError),
+    if purge != Some(MonitorPurge::Preview) {
+        remove_monitored_channel(channel_id, &state.es_client)
+            .await
+            .map_err(|_| Status::InternalServerError)?;
+    }
+    if purge.is_none() {
+        return Ok(MonitorRemoval::Removed(Status::NoContent));
+    }
+    match admin_service::purge_channel_videos(&state.es_client, &state.generation, channel_id, run)
+        .await
+    {
+        Ok(purged) => Ok(MonitorRemoval::Purged(Json(purged))),
+        Err(e) => {
+            log::error!("Failed to purge the videos of channel {channel_id}: {e:?}");
+            Err(Status::InternalServerError.into())
+        }
     }
 }
 
@@ -80,9 +204,9 @@ pub async fn activate_channel(
     _token: AdminToken,
     channel_id: &str,
     state: &State<AppState>,
-) -> Result<Status, Status> {
-    match set_channel_active(&channel_id, true, &state.es_client).await {
-        Ok(_) => Ok(Status::Ok),
+) -> Result<Json<MonitoredChannelStats>, Status> {
+    match set_channel_active(channel_id, true, &state.es_client).await {
+        Ok(_) => channel_response(channel_id, state).await,
         Err(_) => Err(Status::InternalServerError),
     }
 }
@@ -92,25 +216,99 @@ pub async fn deactivate_channel(
     _token: AdminToken,
     channel_id: &str,
     state: &State<AppState>,
-) -> Result<Status, Status> {
-    match set_channel_active(&channel_id, false, &state.es_client).await {
-        Ok(_) => Ok(Status::Ok),
+) -> Result<Json<MonitoredChannelStats>, Status> {
+    match set_channel_active(channel_id, false, &state.es_client).await {
+        Ok(_) => channel_response(channel_id, state).await,
         Err(_) => Err(Status::InternalServerError),
     }
 }
 
+#[post("/channel/<channel_id>/hide")]
+pub async fn hide_channel(
+    _token: AdminToken,
+    channel_id: &str,
+    state: &State<AppState>,
+) -> Result<Json<MonitoredChannelStats>, MaintenanceError> {
+    let run = state
+        .maintenance
+        .try_start(&format!("hide channel {channel_id}"))?;
+    match set_channel_hidden(channel_id, true, &state.es_client, &state.generation, &run).await {
+        Ok(true) => Ok(channel_response(channel_id, state).await?),
+        Ok(false) => Err(Status::NotFound.into()),
+        Err(e) => {
+            log::error!("Failed to hide channel: {}", e);
+            Err(Status::InternalServerError.into())
+        }
+    }
+}
+
+#[post("/channel/<channel_id>/unhide")]
+pub async fn unhide_channel(
+    _token: AdminToken,
+    channel_id: &str,
+    state: &State<AppState>,
+) -> Result<Json<MonitoredChannelStats>, MaintenanceError> {
+    let run = state
+        .maintenance
+        .try_start(&format!("unhide channel {channel_id}"))?;
+    match set_channel_hidden(channel_id, false, &state.es_client, &state.generation, &run).await {
+        Ok(true) => Ok(channel_response(channel_id, state).await?),
+        Ok(false) => Err(Status::NotFound.into()),
+        Err(e) => {
+            log::error!("Failed to unhide channel: {}", e);
+            Err(Status::InternalServerError.into())
+        }
+    }
+}
+
 #[post("/playlist", data = "<playlist>")]
 pub async fn add_playlist(
     _token: AdminToken,
     playlist: Json<NewPlaylist>,
     state: &State<AppState>,
-) -> Result<Status, Status> {
+) -> Result<(Status, Json<MonitorAddResponse>), Status> {
     match add_monitored_playlist(&playlist.into_inner().input, &state.es_client).await {
-        Ok(_) => Ok(Status::Created),
+        Ok(added) => Ok((Status::Created, Json(added))),
         Err(_) => Err(Status::InternalServerError),
     }
 }
 
+#[post("/playlist/bulk", data = "<request>")]
+pub async fn add_playlists_bulk(
+    _token: AdminToken,
+    request: Json<BulkMonitorRequest>,
+    state: &State<AppState>,
+) -> Result<Json<Vec<BulkMonitorResult>>, Status> {
+    if request.inputs.len() > MAX_BULK_INPUTS {
+        return Err(Status::PayloadTooLarge);
+    }
+    Ok(Json(
+        add_monitored_bulk(&request.inputs, true, &state.es_client).await,
+    ))
+}
+
+#[patch("/playlist/<playlist_id>", data = "<update>")]
+pub async fn patch_playlist(
+    _token: AdminToken,
+    playlist_id: &str,
+    update: Json<MonitorUpdate>,
+    state: &State<AppState>,
+) -> Result<Json<MonitoredPlaylistStats>, MonitorUpdateError> {
+    let errors = validate_monitor_update(&update, true);
+    if !errors.is_empty() {
+        return Err(MonitorUpdateError::Invalid(Json(errors)));
+    }
+
+    match update_playlist(playlist_id, &update, &state.es_client).await {
+        Ok(true) => Ok(playlist_response(playlist_id, state).await?),
+        Ok(false) => Err(Status::NotFound.into()),
+        Err(e) => {
+            log::error!("Failed to update playlist {}: {}", playlist_id, e);
+            Err(Status::InternalServerError.into())
+        }
+    }
+}
+
 #[get("/playlist")]
 pub async fn get_playlists(
     _token: AdminToken,
@@ -119,19 +317,44 @@ pub async fn get_playlists(
     Ok(Json(get_monitored_playlist_list(&state.es_client).await))
 }
 
-#[delete("/playlist/<playlist_id>")]
+/// Like `remove_channel`, purging only deletes the videos no other source brought in
+#[delete("/playlist/<playlist_id>?<purge>")]
 pub async fn remove_playlist(
     _token: AdminToken,
     playlist_id: &str,
+    purge: Option<&str>,
     state: &State<AppState>,
-) -> Result<Status, Status> {
+) -> Result<MonitorRemoval, MaintenanceError> {
     if playlist_id.is_empty() {
-        return Err(Status::BadRequest);
+        return Err(Status::BadRequest.into());
     }
+    let purge = parse_purge(purge)?;
+    let run = purge
+        .map(|purge| start_purge(state, purge, &format!("playlist {playlist_id}")))
+        .transpose()?
+        .flatten();
 
-    match remove_monitored_playlist(&playlist_id, &state.es_client).await {
-        Ok(_) => Ok(Status::NoContent),
-        Err(_) => Err(Status::InternalServerError),
+    if purge != Some(MonitorPurge::Preview) {
+        remove_monitored_playlist(playlist_id, &state.es_client)
+            .await
+            .map_err(|_| Status::InternalServerError)?;
+    }
+    if purge.is_none() {
+        return Ok(MonitorRemoval::Removed(Status::NoContent));
+    }
+    match admin_service::purge_playlist_videos(
+        &state.es_client,
+        &state.generation,
+        playlist_id,
+        run,
+    )
+    .await
+    {
+        Ok(purged) => Ok(MonitorRemoval::Purged(Json(purged))),
+        Err(e) => {
+            log::error!("Failed to purge the videos of playlist {playlist_id}: {e:?}");
+            Err(Status::InternalServerError.into())
+        }
     }
 }
 
@@ -140,9 +363,9 @@ pub async fn activate_playlist(
     _token: AdminToken,
     playlist_id: &str,
     state: &State<AppState>,
-) -> Result<Status, Status> {
-    match set_playlist_active(&playlist_id, true, &state.es_client).await {
-        Ok(_) => Ok(Status::Ok),
+) -> Result<Json<MonitoredPlaylistStats>, Status> {
+    match set_playlist_active(playlist_id, true, &state.es_client).await {
+        Ok(_) => playlist_response(playlist_id, state).await,
         Err(_) => Err(Status::InternalServerError),
     }
 }
@@ -152,9 +375,9 @@ pub async fn deactivate_playlist(
     _token: AdminToken,
     playlist_id: &str,
     state: &State<AppState>,
-) -> Result<Status, Status> {
-    match set_playlist_active(&playlist_id, false, &state.es_client).await {
-        Ok(_) => Ok(Status::Ok),
+) -> Result<Json<MonitoredPlaylistStats>, Status> {
+    match set_playlist_active(playlist_id, false, &state.es_client).await {
+        Ok(_) => playlist_response(playlist_id, state).await,
         Err(_) => Err(Status::InternalServerError),
     }
 }
@@ -165,7 +388,7 @@ pub async fn check_channel(
     channel_id: &str,
     state: &State<AppState>,
 ) -> Result<Status, Status> {
-    check_channel_for_new_videos(&channel_id, &state.es_client, &state.video_queue).await;
+    check_channel_for_new_videos(channel_id, &state.es_client, &state.video_queue).await;
     Ok(Default::default())
 }
 
@@ -175,14 +398,8 @@ pub async fn check_playlist(
     playlist_id: &str,
     state: &State<AppState>,
 ) -> Result<Status, Status> {
-    match check_playlist_for_new_videos(
-        &playlist_id,
-        &state.es_client,
-        &state.video_queue,
-        Some(playlist_id.to_string()),
-    )
-    .await
-    {
+    let source = CheckSource::Playlist(playlist_id.to_string());
+    match check_source_for_new_videos(&source, &state.es_client, &state.video_queue).await {
         Ok(_) => Ok(Status::Ok),
         Err(e) => {
             log::error!("Failed to check playlist: {}", e);
@@ -190,3 +407,50 @@ pub async fn check_playlist(
         }
     }
 }
+
+/// Checks every active monitor tagged with `tag` in the background
+#[post("/check?<tag>")]
+pub async fn check_tag(
+    _token: AdminToken,
+    tag: &str,
+    state: &State<AppState>,
+) -> Result<Json<TagCheckResponse>, Status> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() {
+        return Err(Status::UnprocessableEntity);
+    }
+    let response = start_tag_check(&tag, &state.es_client, &state.video_queue).await;
+    if response.channels == 0 && response.playlists == 0 {
+        return Err(Status::NotFound);
+    }
+    Ok(Json(response))
+}
+
+#[get("/tag")]
+pub async fn get_tags(_token: AdminToken) -> Json<Vec<MonitorTag>> {
+    Json(monitor_tag_service::list_tags().await)
+}
+
+#[put("/tag/<tag>", data = "<update>")]
+pub async fn put_tag(
+    _token: AdminToken,
+    tag: &str,
+    update: Json<MonitorTagUpdate>,
+    state: &State<AppState>,
+) -> Result<Json<MonitorTag>, Status> {
+    if tag.trim().is_empty() {
+        return Err(Status::UnprocessableEntity);
+    }
+    if let Err(e) = monitor_tag_service::set_tag_public(&state.es_client, tag, update.public).await
+    {
+        log::error!("Failed to update tag {}: {}", tag, e);
+        return Err(Status::InternalServerError);
+    }
+    let tag = tag.trim().to_lowercase();
+    monitor_tag_service::list_tags()
+        .await
+        .into_iter()
+        .find(|t| t.tag == tag)
+        .map(Json)
+        .ok_or(Status::NotFound)
+}
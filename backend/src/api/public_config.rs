@@ -0,0 +1,82 @@
+use crate::api::search::MAX_PAGE_SIZE;
+use crate::config::{APP_NAME, LANGUAGE_PRIORITY, SEARCH_LOG_ENABLED, THUMBNAIL_PROXY};
+use crate::models::{PublicConfig, PublicFeatures};
+use rocket::get;
+use rocket::serde::json::Json;
+
+/// Settings and optional features the frontend adapts to, see `PublicConfig`
+#[get("/public")]
+pub async fn get_public_config() -> Json<PublicConfig> {
+    Json(PublicConfig {
+        app_name: APP_NAME.clone(),
+        features: PublicFeatures {
+            wide_search: true,
+            exact_terms: true,
+            transcripts: true,
+            api_keys: true,
+            thumbnail_proxy: *THUMBNAIL_PROXY,
+            trending_matches: *SEARCH_LOG_ENABLED,
+        },
+        max_page_size: MAX_PAGE_SIZE,
+        caption_languages: LANGUAGE_PRIORITY.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsupport::init_env;
+    use rocket::http::Status;
+    use rocket::local::blocking::Client;
+    use rocket::routes;
+    use serde_json::Value;
+
+    fn keys(value: &Value) -> Vec<&str> {
+        let mut keys: Vec<&str> = value
+            .as_object()
+            .expect("an object")
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    /// The document is a public contract, fields may be added but never renamed or removed
+    #[test]
+    fn public_config_shape_is_stable() {
+        init_env();
+        let rocket = rocket::build().mount("/config", routes![get_public_config]);
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+        let response = client.get("/config/public").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let body: Value = response.into_json().expect("a JSON body");
+
+        assert_eq!(
+            keys(&body),
+            ["app_name", "caption_languages", "features", "max_page_size"]
+        );
+        assert_eq!(
+            keys(&body["features"]),
+            [
+                "api_keys",
+                "exact_terms",
+                "thumbnail_proxy",
+                "transcripts",
+                "trending_matches",
+                "wide_search"
+            ]
+        );
+        assert!(body["features"]
+            .as_object()
+            .unwrap()
+            .values()
+            .all(Value::is_boolean));
+        assert!(body["app_name"].is_string());
+        assert_eq!(body["max_page_size"], MAX_PAGE_SIZE);
+        assert_eq!(
+            body["caption_languages"],
+            serde_json::json!(["en", "en-GB", "de"])
+        );
+    }
+}
@@ -1,70 +1,979 @@
-use crate::models::{ErrorResponse, SearchResponse};
-use crate::services::search_service::SortBy::{
-    CaptionMatches, Duration, Likes, Relevance, UploadDate, Views,
+use crate::api::video::fetch_video_metadata;
+use crate::config::LANGUAGE_PRIORITY;
+use crate::models::{
+    ApiAccess, CaptionReportRequest, CursorSearchResponse, ErrorResponse, PhraseFrequencyResponse,
+    RandomQuote, RelevanceConfig, SearchFeedbackRequest, SearchResponse,
 };
-use crate::services::search_service::SortOrder::{Asc, Desc};
-use crate::services::search_service::{search_captions_with_pagination, SearchOptions};
+use crate::services::cursor_search_service::{
+    self, CursorError, CURSOR_PAGE_SIZE, MAX_CURSOR_PAGE_SIZE,
+};
+use crate::services::es_breaker;
+use crate::services::frequency_service::{self, FrequencyInterval};
+use crate::services::notification_service::ACTIVITY;
+use crate::services::search_service::{
+    self, search_captions_with_pagination, Granularity, HighlightTags, SearchOptions, SortBy,
+    SortOrder,
+};
+use crate::services::{feedback_service, report_service, trending_service, video_service};
+use crate::utils;
 use crate::AppState;
+use rocket::http::Status;
 use rocket::serde::json::Json;
-use rocket::{get, State};
+use rocket::{get, post, FromForm, Responder, State};
+use std::net::IpAddr;
 
 static PAGE_SIZE: usize = 10;
+/// Also announced in the public config
+pub static MAX_PAGE_SIZE: usize = 50;
 static MIN_QUERY_SIZE: usize = 3;
+static MAX_FILTER_CHANNELS: usize = 50;
+/// Random captions drawn per quote, the first of a public video is returned
+static RANDOM_QUOTE_CANDIDATES: usize = 5;
 
-#[get("/?<query>&<type>&<sort>&<order>&<page>")]
-pub async fn search_captions(
-    query: String,
-    r#type: Option<String>,
-    sort: Option<String>,
-    order: Option<String>,
-    page: Option<usize>,
-    state: &State<AppState>,
-) -> Result<Json<SearchResponse>, ErrorResponse> {
-    if query.len() < MIN_QUERY_SIZE {
-        eprintln!("Search error: Query too short");
-        return Err(ErrorResponse {
+const SEARCH_TYPES: &[&str] = &["natural", "wide"];
+const GRANULARITIES: &[&str] = &["caption", "sentence"];
+const FREQUENCY_INTERVALS: &[&str] = &["week", "month", "year"];
+const SORT_KEYS: &[&str] = &[
+    "relevance",
+    "upload_date",
+    "duration",
+    "views",
+    "likes",
+    "caption_matches",
+    "view_velocity",
+];
+const ORDER_KEYS: &[&str] = &["asc", "desc"];
+/// Markers safe for any consumer. An empty pre and post marker disables highlighting.
+const HIGHLIGHT_MARKERS: &[&str] = &[
+    "<strong>",
+    "</strong>",
+    "<em>",
+    "</em>",
+    "<mark>",
+    "</mark>",
+    "**",
+    "__",
+    "[[",
+    "]]",
+];
+
+/// Raw query parameters of `/search`. Use `parse` to validate them into `SearchOptions`.
+#[derive(Debug, FromForm)]
+pub struct SearchRequestParams {
+    pub query: String,
+    #[field(name = "type")]
+    pub search_type: Option<String>,
+    pub sort: Option<String>,
+    pub order: Option<String>,
+    // Parsed manually so malformed values get the same 422 body as other parameters
+    pub page: Option<String>,
+    pub page_size: Option<String>,
+    pub exact_terms: Option<bool>,
+    pub highlight_pre: Option<String>,
+    pub highlight_post: Option<String>,
+    pub plain: Option<bool>,
+    pub collapse: Option<bool>,
+    pub exclude_auto: Option<bool>,
+    pub exclude_channels: Option<String>, // comma separated channel IDs, none of `channel_ids`
+    pub channel_id: Option<String>,
+    pub channel_ids: Vec<String>, // repeatable, each may be comma separated
+    pub granularity: Option<String>,
+    pub lang: Option<String>, // caption language code, one of LANGUAGE_PRIORITY
+}
+
+#[derive(Debug)]
+pub struct ParsedSearchRequest {
+    pub query: String,
+    pub page: usize,
+    pub page_size: usize,
+    pub options: SearchOptions,
+}
+
+#[derive(Debug, Responder)]
+pub enum SearchError {
+    BadRequest(ErrorResponse),
+    #[response(status = 422)]
+    Unprocessable(Json<ErrorResponse>),
+    /// Elasticsearch is unreachable, see `es_breaker`
+    #[response(status = 503)]
+    Unavailable(Json<ErrorResponse>),
+    /// The primary query didn't finish within the search deadline
+    #[response(status = 504)]
+    Timeout(Json<ErrorResponse>),
+    /// The point in time behind a search cursor expired
+    #[response(status = 410)]
+    Gone(Json<ErrorResponse>),
+    /// Elasticsearch answered with an error or a response that couldn't be parsed
+    #[response(status = 500)]
+    InternalError(Json<ErrorResponse>),
+}
+
+impl SearchError {
+    fn invalid_value(param: &str, value: &str, valid: &[&str]) -> Self {
+        SearchError::Unprocessable(Json(ErrorResponse {
+            error: format!("Invalid {}", param),
+            message: format!(
+                "'{}' is not a valid value for '{}'. Valid options: {}",
+                value,
+                param,
+                valid.join(", ")
+            ),
+        }))
+    }
+
+    fn unavailable() -> Self {
+        SearchError::Unavailable(Json(ErrorResponse {
+            error: "Search temporarily unavailable".to_string(),
+            message: "The search index can't be reached right now. Please try again in a minute."
+                .to_string(),
+        }))
+    }
+
+    fn timeout() -> Self {
+        SearchError::Timeout(Json(ErrorResponse {
+            error: "Search timed out".to_string(),
+            message: "The search took too long. Try a more specific query.".to_string(),
+        }))
+    }
+
+    fn internal(message: &str) -> Self {
+        SearchError::InternalError(Json(ErrorResponse {
+            error: "Internal server error".to_string(),
+            message: message.to_string(),
+        }))
+    }
+
+    fn query_too_short() -> Self {
+        SearchError::BadRequest(ErrorResponse {
             error: "Query too short".to_string(),
             message: format!(
                 "Search query must be at least {} characters long.",
                 MIN_QUERY_SIZE
             ),
-        });
+        })
     }
+}
 
-    let sort_by = match sort.as_deref() {
-        Some("relevance") => Relevance,
-        Some("upload_date") => UploadDate,
-        Some("duration") => Duration,
-        Some("views") => Views,
-        Some("likes") => Likes,
-        Some("caption_matches") => CaptionMatches,
-        _ => Relevance,
-    };
+impl SearchRequestParams {
+    /// Validates and normalizes all parameters. Enum values are matched case-insensitively,
+    /// missing values fall back to the defaults and unknown values are rejected.
+    pub fn parse(self) -> Result<ParsedSearchRequest, SearchError> {
+        let query = self.query.trim().to_string();
+        if query.len() < MIN_QUERY_SIZE {
+            return Err(SearchError::query_too_short());
+        }
 
-    let ordering = match order.as_deref() {
-        Some("asc") => Asc,
-        Some("desc") => Desc,
-        _ => Desc,
+        let sort_by = match normalize(&self.sort).as_deref() {
+            None | Some("relevance") => SortBy::Relevance,
+            Some("upload_date") => SortBy::UploadDate,
+            Some("duration") => SortBy::Duration,
+            Some("views") => SortBy::Views,
+            Some("likes") => SortBy::Likes,
+            Some("caption_matches") => SortBy::CaptionMatches,
+            Some("view_velocity") => SortBy::ViewVelocity,
+            Some(other) => return Err(SearchError::invalid_value("sort", other, SORT_KEYS)),
+        };
+
+        let sort_order = match normalize(&self.order).as_deref() {
+            Some("asc") => SortOrder::Asc,
+            None | Some("desc") => SortOrder::Desc,
+            Some(other) => return Err(SearchError::invalid_value("order", other, ORDER_KEYS)),
+        };
+
+        let granularity = match normalize(&self.granularity).as_deref() {
+            None | Some("caption") => Granularity::Caption,
+            Some("sentence") => Granularity::Sentence,
+            Some(other) => {
+                return Err(SearchError::invalid_value(
+                    "granularity",
+                    other,
+                    GRANULARITIES,
+                ))
+            }
+        };
+
+        let language = parse_language(&self.lang)?;
+        // `channel_id` is the single channel shorthand of `channel_ids`
+        let channel_ids = self
+            .channel_id
+            .iter()
+            .chain(&self.channel_ids)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let channels = parse_channel_ids("channel_ids", Some(channel_ids.as_str()))?;
+        let exclude_channels =
+            parse_channel_ids("exclude_channels", self.exclude_channels.as_deref())?;
+        // Included channels win over exclusions, a channel in both would match nothing
+        if let Some(conflict) = exclude_channels.iter().find(|id| channels.contains(id)) {
+            return Err(SearchError::Unprocessable(Json(ErrorResponse {
+                error: "Conflicting channel filters".to_string(),
+                message: format!(
+                    "channel_ids and exclude_channels both contain {}.",
+                    conflict
+                ),
+            })));
+        }
+
+        let options = search_options(&self.search_type, sort_by, sort_order)?
+            .with_exact_terms(self.exact_terms.unwrap_or(false))
+            .with_highlight_tags(parse_highlight_tags(
+                self.highlight_pre,
+                self.highlight_post,
+            )?)
+            .with_plain_text(self.plain.unwrap_or(false))
+            .with_collapse(self.collapse.unwrap_or(false))
+            .with_exclude_auto(self.exclude_auto.unwrap_or(false))
+            .with_granularity(granularity)
+            .with_language(language)
+            .with_exclude_channels(exclude_channels)
+            .with_channels(channels);
+
+        Ok(ParsedSearchRequest {
+            query,
+            page: parse_count("page", self.page.as_deref(), 0)?,
+            page_size: parse_count("page_size", self.page_size.as_deref(), PAGE_SIZE)?
+                .clamp(1, MAX_PAGE_SIZE),
+            options,
+        })
+    }
+}
+
+/// Raw query parameters of `/search/cursor`
+#[derive(Debug, FromForm)]
+pub struct CursorRequestParams {
+    pub q: String,
+    pub cursor: Option<String>,
+    pub size: Option<String>,
+    #[field(name = "type")]
+    pub search_type: Option<String>,
+    pub exclude_auto: Option<bool>,
+    pub lang: Option<String>,
+}
+
+fn search_options(
+    search_type: &Option<String>,
+    sort_by: SortBy,
+    sort_order: SortOrder,
+) -> Result<SearchOptions, SearchError> {
+    match normalize(search_type).as_deref() {
+        None | Some("natural") => Ok(SearchOptions::natural(sort_by, sort_order)),
+        Some("wide") => Ok(SearchOptions::wide(sort_by, sort_order)),
+        Some(other) => Err(SearchError::invalid_value("type", other, SEARCH_TYPES)),
+    }
+}
+
+/// Captions store the code as configured, "en-GB" rather than "en-gb"
+fn parse_language(lang: &Option<String>) -> Result<Option<String>, SearchError> {
+    let Some(lang) = normalize(lang) else {
+        return Ok(None);
     };
+    match LANGUAGE_PRIORITY
+        .iter()
+        .find(|code| code.eq_ignore_ascii_case(&lang))
+    {
+        Some(code) => Ok(Some(code.clone())),
+        None => {
+            let valid: Vec<&str> = LANGUAGE_PRIORITY.iter().map(String::as_str).collect();
+            Err(SearchError::invalid_value("lang", &lang, &valid))
+        }
+    }
+}
 
-    let page = page.unwrap_or(0);
+/// Parses a non-negative integer parameter, `default` if missing
+fn parse_count(param: &str, value: Option<&str>, default: usize) -> Result<usize, SearchError> {
+    match value.map(str::trim) {
+        None | Some("") => Ok(default),
+        Some(value) => value.parse::<usize>().map_err(|_| {
+            SearchError::Unprocessable(Json(ErrorResponse {
+                error: format!("Invalid {}", param),
+                message: format!(
+                    "'{}' is not a valid value for '{}'. Expected a non-negative integer.",
+                    value, param
+                ),
+            }))
+        }),
+    }
+}
+
+/// Parses a comma separated list of channel IDs, duplicates removed
+fn parse_channel_ids(param: &str, value: Option<&str>) -> Result<Vec<String>, SearchError> {
+    let mut channel_ids: Vec<String> = Vec::new();
+    for channel_id in value.unwrap_or_default().split(',').map(str::trim) {
+        if channel_id.is_empty() || channel_ids.iter().any(|id| id == channel_id) {
+            continue;
+        }
+        if !utils::is_channel_id(channel_id) {
+            return Err(SearchError::Unprocessable(Json(ErrorResponse {
+                error: format!("Invalid {}", param),
+                message: format!("'{}' is not a valid channel ID.", channel_id),
+            })));
+        }
+        channel_ids.push(channel_id.to_string());
+    }
 
-    let search_type_string = r#type.unwrap_or_else(|| "natural".to_string());
-    let options = match search_type_string.as_str() {
-        "natural" => SearchOptions::natural(sort_by, ordering),
-        "wide" => SearchOptions::wide(sort_by, ordering),
-        _ => SearchOptions::natural(sort_by, ordering),
+    if channel_ids.len() > MAX_FILTER_CHANNELS {
+        return Err(SearchError::Unprocessable(Json(ErrorResponse {
+            error: format!("Invalid {}", param),
+            message: format!(
+                "At most {} channels can be given in '{}'.",
+                MAX_FILTER_CHANNELS, param
+            ),
+        })));
+    }
+    Ok(channel_ids)
+}
+
+fn parse_highlight_tags(
+    pre: Option<String>,
+    post: Option<String>,
+) -> Result<HighlightTags, SearchError> {
+    let (pre, post) = match (pre, post) {
+        (None, None) => return Ok(HighlightTags::default()),
+        (Some(pre), Some(post)) => (pre, post),
+        _ => {
+            return Err(SearchError::Unprocessable(Json(ErrorResponse {
+                error: "Invalid highlight tags".to_string(),
+                message: "'highlight_pre' and 'highlight_post' must be given together.".to_string(),
+            })))
+        }
     };
 
-    match search_captions_with_pagination(&state.es_client, &query, page, PAGE_SIZE, &options).await
-    {
+    for (param, marker) in [("highlight_pre", &pre), ("highlight_post", &post)] {
+        if !marker.is_empty() && !HIGHLIGHT_MARKERS.contains(&marker.as_str()) {
+            return Err(SearchError::invalid_value(param, marker, HIGHLIGHT_MARKERS));
+        }
+    }
+
+    if pre.is_empty() != post.is_empty() {
+        return Err(SearchError::Unprocessable(Json(ErrorResponse {
+            error: "Invalid highlight tags".to_string(),
+            message: "Highlighting can only be disabled by leaving both markers empty.".to_string(),
+        })));
+    }
+
+    Ok(HighlightTags { pre, post })
+}
+
+/// Trims and lowercases an optional parameter, treating empty values as missing
+fn normalize(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(|v| v.trim().to_ascii_lowercase())
+        .filter(|v| !v.is_empty())
+}
+
+#[get("/?<params..>")]
+pub async fn search_captions(
+    params: SearchRequestParams,
+    _access: ApiAccess,
+    state: &State<AppState>,
+) -> Result<Json<SearchResponse>, SearchError> {
+    let request = params.parse()?;
+    ACTIVITY.record_search(&request.query);
+    // Paging through the results doesn't make a query more popular
+    if request.page == 0 {
+        trending_service::log_search(&state.es_client, &request.query);
+    }
+
+    let result = es_breaker::guarded(
+        &state.es_client,
+        search_captions_with_pagination(
+            &state.es_client,
+            &request.query,
+            request.page,
+            request.page_size,
+            &request.options,
+            &RelevanceConfig::default(),
+        ),
+    )
+    .await;
+
+    match result {
         Ok(response) => Ok(Json(response)),
+        Err(e) if es_breaker::is_unavailable(&e) => {
+            eprintln!("Search error: {}", e);
+            Err(SearchError::unavailable())
+        }
+        Err(e) if search_service::is_search_timeout(&e) => {
+            eprintln!("Search error: {}", e);
+            Err(SearchError::timeout())
+        }
         Err(e) => {
             eprintln!("Search error: {}", e);
-            Err(ErrorResponse {
-                error: "Internal server error".to_string(),
-                message: "An error occurred while processing your search request.".to_string(),
-            })
+            Err(SearchError::internal(
+                "An error occurred while processing your search request.",
+            ))
+        }
+    }
+}
+
+/// Every matching caption of a query, one flat page at a time. Hits are sorted by score,
+/// then video and start time. Pass `next_cursor` of a page as `cursor` with the same
+/// `q`, `type`, `lang` and `exclude_auto` to get the next one, it is missing once all
+/// matches were returned. A cursor continued with other parameters answers 422.
+///
+/// A walk sees the index as it was on its first page, so captions indexed meanwhile
+/// don't shift it and every match comes exactly once. The cursor stays valid for five
+/// minutes after each page, an expired one answers 410 and the walk has to start over.
+#[get("/cursor?<params..>")]
+pub async fn search_cursor(
+    params: CursorRequestParams,
+    _access: ApiAccess,
+    state: &State<AppState>,
+) -> Result<Json<CursorSearchResponse>, SearchError> {
+    let query = params.q.trim().to_string();
+    if query.len() < MIN_QUERY_SIZE {
+        return Err(SearchError::query_too_short());
+    }
+    let size = parse_count("size", params.size.as_deref(), CURSOR_PAGE_SIZE)?
+        .clamp(1, MAX_CURSOR_PAGE_SIZE);
+    let options = search_options(&params.search_type, SortBy::Relevance, SortOrder::Desc)?
+        .with_exclude_auto(params.exclude_auto.unwrap_or(false))
+        .with_language(parse_language(&params.lang)?);
+
+    let result = es_breaker::guarded(
+        &state.es_client,
+        cursor_search_service::search_cursor(
+            &state.es_client,
+            &query,
+            params.cursor.as_deref().filter(|cursor| !cursor.is_empty()),
+            size,
+            &options,
+        ),
+    )
+    .await;
+
+    match result {
+        Ok(response) => Ok(Json(response)),
+        Err(e) => match cursor_search_service::cursor_error(&e) {
+            Some(CursorError::Invalid) => Err(SearchError::Unprocessable(Json(ErrorResponse {
+                error: "Invalid cursor".to_string(),
+                message: "The cursor is malformed or belongs to a different query or options."
+                    .to_string(),
+            }))),
+            Some(CursorError::Expired) => Err(SearchError::Gone(Json(ErrorResponse {
+                error: "Cursor expired".to_string(),
+                message: "The cursor expired, start again without one.".to_string(),
+            }))),
+            None if es_breaker::is_unavailable(&e) => {
+                log::error!("Cursor search error: {}", e);
+                Err(SearchError::unavailable())
+            }
+            None => {
+                log::error!("Cursor search error: {}", e);
+                Err(SearchError::internal(
+                    "An error occurred while processing your search request.",
+                ))
+            }
+        },
+    }
+}
+
+/// Caption matches of a phrase per week, month or year of the videos' upload dates
+#[get("/frequency?<q>&<interval>")]
+pub async fn phrase_frequency(
+    q: &str,
+    interval: Option<String>,
+    _access: ApiAccess,
+    state: &State<AppState>,
+) -> Result<Json<PhraseFrequencyResponse>, SearchError> {
+    let query = q.trim();
+    if query.len() < MIN_QUERY_SIZE {
+        return Err(SearchError::query_too_short());
+    }
+    let interval = match normalize(&interval).as_deref() {
+        Some("week") => FrequencyInterval::Week,
+        None | Some("month") => FrequencyInterval::Month,
+        Some("year") => FrequencyInterval::Year,
+        Some(other) => {
+            return Err(SearchError::invalid_value(
+                "interval",
+                other,
+                FREQUENCY_INTERVALS,
+            ))
+        }
+    };
+
+    let result = es_breaker::guarded(
+        &state.es_client,
+        frequency_service::phrase_frequency(&state.es_client, &state.generation, query, interval),
+    )
+    .await;
+
+    match result {
+        Ok(frequency) => Ok(Json(frequency)),
+        Err(e) if es_breaker::is_unavailable(&e) => {
+            log::error!("Phrase frequency error: {}", e);
+            Err(SearchError::unavailable())
+        }
+        Err(e) => {
+            log::error!("Phrase frequency error: {}", e);
+            Err(SearchError::internal(
+                "An error occurred while counting the phrase.",
+            ))
+        }
+    }
+}
+
+/// One random caption with its context and video, for discovering the archive. `q`,
+/// `channel` and `lang` constrain the pick. The response's `seed` picks the same quote
+/// again, so a quote can be shared. 404 if nothing matches.
+#[get("/random?<q>&<channel>&<lang>&<seed>")]
+pub async fn random_quote(
+    q: Option<&str>,
+    channel: Option<&str>,
+    lang: Option<String>,
+    seed: Option<&str>,
+    _access: ApiAccess,
+    state: &State<AppState>,
+) -> Result<Option<Json<RandomQuote>>, SearchError> {
+    let query = q.map(str::trim).filter(|q| !q.is_empty());
+    if query.is_some_and(|q| q.len() < MIN_QUERY_SIZE) {
+        return Err(SearchError::query_too_short());
+    }
+    let channel_ids = parse_channel_ids("channel", channel)?;
+    if channel_ids.len() > 1 {
+        return Err(SearchError::Unprocessable(Json(ErrorResponse {
+            error: "Invalid channel".to_string(),
+            message: "Only one channel can be given in 'channel'.".to_string(),
+        })));
+    }
+    let seed = match seed.map(str::trim).filter(|seed| !seed.is_empty()) {
+        Some(seed) => seed.parse::<u64>().map_err(|_| {
+            SearchError::Unprocessable(Json(ErrorResponse {
+                error: "Invalid seed".to_string(),
+                message: format!(
+                    "'{}' is not a valid value for 'seed'. Expected a non-negative integer.",
+                    seed
+                ),
+            }))
+        })?,
+        // Kept within what JavaScript numbers represent exactly, for sharing
+        None => rand::random::<u64>() >> 11,
+    };
+    let options = SearchOptions::natural(SortBy::Relevance, SortOrder::Desc)
+        .with_language(parse_language(&lang)?)
+        .with_channels(channel_ids);
+
+    let result = es_breaker::guarded(
+        &state.es_client,
+        pick_random_quote(state, query, seed, &options),
+    )
+    .await;
+
+    match result {
+        Ok(quote) => Ok(quote.map(Json)),
+        Err(e) if es_breaker::is_unavailable(&e) => {
+            log::error!("Random quote error: {}", e);
+            Err(SearchError::unavailable())
+        }
+        Err(e) => {
+            log::error!("Random quote error: {}", e);
+            Err(SearchError::internal(
+                "An error occurred while picking a quote.",
+            ))
+        }
+    }
+}
+
+/// Captions of videos that are being deleted or hidden may still be indexed, so a few
+/// candidates are drawn and the first one of a public video wins
+async fn pick_random_quote(
+    state: &AppState,
+    query: Option<&str>,
+    seed: u64,
+    options: &SearchOptions,
+) -> anyhow::Result<Option<RandomQuote>> {
+    let candidates = search_service::random_captions(
+        &state.es_client,
+        query,
+        RANDOM_QUOTE_CANDIDATES,
+        seed,
+        options,
+    )
+    .await?;
+    for result in candidates {
+        if let Some(video) = fetch_video_metadata(&state.es_client, &result.video_id).await? {
+            return Ok(Some(RandomQuote {
+                result,
+                video,
+                seed,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+#[post("/report", data = "<report>")]
+pub async fn report_caption(
+    report: Json<CaptionReportRequest>,
+    client_ip: Option<IpAddr>,
+    state: &State<AppState>,
+) -> Result<Status, Status> {
+    if !utils::is_video_id(&report.video_id) {
+        return Err(Status::BadRequest);
+    }
+
+    let ip_key = client_ip
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    if !state.report_limiter.check(&ip_key) {
+        return Err(Status::TooManyRequests);
+    }
+
+    match video_service::is_public_video(&state.es_client, &report.video_id).await {
+        Ok(true) => {}
+        Ok(false) => return Err(Status::NotFound),
+        Err(e) => {
+            log::error!(
+                "Failed to look up reported video {}: {e:?}",
+                report.video_id
+            );
+            return Err(Status::InternalServerError);
+        }
+    }
+
+    match report_service::submit_caption_report(&state.es_client, &report).await {
+        Ok(true) => Ok(Status::Created),
+        Ok(false) => Ok(Status::Ok), // already reported today
+        Err(e) => {
+            log::error!("Failed to store caption report: {e:?}");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// Views, clicks and expands of search results, feeding the click-through stats
+#[post("/feedback", data = "<feedback>")]
+pub async fn search_feedback(
+    feedback: Json<SearchFeedbackRequest>,
+    client_ip: Option<IpAddr>,
+    state: &State<AppState>,
+) -> Result<Status, Status> {
+    if !feedback_service::is_valid_feedback(&feedback) {
+        return Err(Status::BadRequest);
+    }
+
+    let ip_key = client_ip
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    if !state.feedback_limiter.check(&ip_key) {
+        return Err(Status::TooManyRequests);
+    }
+
+    match feedback_service::submit_feedback(&state.es_client, &feedback, client_ip).await {
+        Ok(()) => Ok(Status::NoContent),
+        Err(e) => {
+            log::error!("Failed to store search feedback: {e:?}");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::search_service::SearchType;
+    use crate::testsupport;
+
+    const CHANNEL_A: &str = "UCaaaaaaaaaaaaaaaaaaaaaa";
+    const CHANNEL_B: &str = "UCbbbbbbbbbbbbbbbbbbbbbb";
+    const CHANNEL_C: &str = "UCcccccccccccccccccccccc";
+
+    fn params(query: &str) -> SearchRequestParams {
+        // LANGUAGE_PRIORITY is read once from the environment
+        testsupport::init_env();
+        SearchRequestParams {
+            query: query.to_string(),
+            search_type: None,
+            sort: None,
+            order: None,
+            page: None,
+            page_size: None,
+            exact_terms: None,
+            highlight_pre: None,
+            highlight_post: None,
+            plain: None,
+            collapse: None,
+            exclude_auto: None,
+            exclude_channels: None,
+            channel_id: None,
+            channel_ids: Vec::new(),
+            granularity: None,
+            lang: None,
+        }
+    }
+
+    fn some(value: &str) -> Option<String> {
+        Some(value.to_string())
+    }
+
+    /// The parameter named in a 422 response
+    fn unprocessable(result: Result<ParsedSearchRequest, SearchError>) -> String {
+        match result {
+            Err(SearchError::Unprocessable(Json(error))) => error.error,
+            other => panic!("expected 422, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn defaults() {
+        let parsed = params("  hello world  ").parse().unwrap();
+        assert_eq!(parsed.query, "hello world");
+        assert_eq!(parsed.page, 0);
+        assert_eq!(parsed.page_size, PAGE_SIZE);
+        let options = parsed.options;
+        assert!(matches!(options.search_type, SearchType::Natural));
+        assert!(matches!(options.sort_by, SortBy::Relevance));
+        assert!(matches!(options.sort_order, SortOrder::Desc));
+        assert!(matches!(options.granularity, Granularity::Caption));
+        assert_eq!(options.highlight_tags, HighlightTags::default());
+        assert!(!options.exact_terms && !options.plain_text && !options.collapse);
+        assert!(!options.exclude_auto);
+        assert_eq!(options.language, None);
+        assert!(options.channels.is_empty() && options.exclude_channels.is_empty());
+    }
+
+    #[test]
+    fn query_shorter_than_minimum_is_bad_request() {
+        for query in ["", "ab", "  ab  "] {
+            assert!(matches!(
+                params(query).parse(),
+                Err(SearchError::BadRequest(_))
+            ));
+        }
+        assert!(params("abc").parse().is_ok());
+    }
+
+    #[test]
+    fn search_type_is_case_insensitive() {
+        for (value, wide) in [
+            ("natural", false),
+            ("NATURAL", false),
+            ("wide", true),
+            (" Wide ", true),
+            ("", false),
+        ] {
+            let mut request = params("hello");
+            request.search_type = some(value);
+            let options = request.parse().unwrap().options;
+            assert_eq!(
+                matches!(options.search_type, SearchType::Wide),
+                wide,
+                "{value}"
+            );
+        }
+
+        let mut request = params("hello");
+        request.search_type = some("fuzzy");
+        assert_eq!(unprocessable(request.parse()), "Invalid type");
+    }
+
+    #[test]
+    fn every_sort_key_parses() {
+        for key in SORT_KEYS {
+            for value in [key.to_string(), key.to_uppercase()] {
+                let mut request = params("hello");
+                request.sort = Some(value.clone());
+                let sort_by = request.parse().unwrap().options.sort_by;
+                let expected = match *key {
+                    "relevance" => matches!(sort_by, SortBy::Relevance),
+                    "upload_date" => matches!(sort_by, SortBy::UploadDate),
+                    "duration" => matches!(sort_by, SortBy::Duration),
+                    "views" => matches!(sort_by, SortBy::Views),
+                    "likes" => matches!(sort_by, SortBy::Likes),
+                    "caption_matches" => matches!(sort_by, SortBy::CaptionMatches),
+                    "view_velocity" => matches!(sort_by, SortBy::ViewVelocity),
+                    other => panic!("untested sort key {other}"),
+                };
+                assert!(expected, "{value} parsed as {sort_by:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_sort_lists_the_valid_keys() {
+        let mut request = params("hello");
+        request.sort = some("popularity");
+        match request.parse() {
+            Err(SearchError::Unprocessable(Json(error))) => {
+                assert_eq!(error.error, "Invalid sort");
+                assert!(error.message.contains("'popularity'"));
+                for key in SORT_KEYS {
+                    assert!(error.message.contains(key), "{key} missing");
+                }
+            }
+            other => panic!("expected 422, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn order() {
+        for (value, asc) in [("asc", true), ("ASC", true), ("desc", false), (" ", false)] {
+            let mut request = params("hello");
+            request.order = some(value);
+            let order = request.parse().unwrap().options.sort_order;
+            assert_eq!(matches!(order, SortOrder::Asc), asc, "{value}");
+        }
+
+        let mut request = params("hello");
+        request.order = some("up");
+        assert_eq!(unprocessable(request.parse()), "Invalid order");
+    }
+
+    #[test]
+    fn granularity() {
+        let mut request = params("hello");
+        request.granularity = some("Sentence");
+        assert!(matches!(
+            request.parse().unwrap().options.granularity,
+            Granularity::Sentence
+        ));
+
+        let mut request = params("hello");
+        request.granularity = some("word");
+        assert_eq!(unprocessable(request.parse()), "Invalid granularity");
+    }
+
+    #[test]
+    fn page_and_page_size() {
+        let mut request = params("hello");
+        request.page = some(" 3 ");
+        request.page_size = some("20");
+        let parsed = request.parse().unwrap();
+        assert_eq!((parsed.page, parsed.page_size), (3, 20));
+
+        for (value, expected) in [("0", 1), ("1000", MAX_PAGE_SIZE), ("", PAGE_SIZE)] {
+            let mut request = params("hello");
+            request.page_size = some(value);
+            assert_eq!(request.parse().unwrap().page_size, expected, "{value}");
+        }
+
+        for value in ["-1", "1.5", "ten"] {
+            let mut request = params("hello");
+            request.page = some(value);
+            assert_eq!(unprocessable(request.parse()), "Invalid page");
+
+            let mut request = params("hello");
+            request.page_size = some(value);
+            assert_eq!(unprocessable(request.parse()), "Invalid page_size");
+        }
+    }
+
+    #[test]
+    fn flags() {
+        let mut request = params("hello");
+        request.exact_terms = Some(true);
+        request.plain = Some(true);
+        request.collapse = Some(true);
+        request.exclude_auto = Some(true);
+        let options = request.parse().unwrap().options;
+        assert!(options.exact_terms && options.plain_text && options.collapse);
+        assert!(options.exclude_auto);
+    }
+
+    #[test]
+    fn language_uses_the_configured_spelling() {
+        let mut request = params("hello");
+        request.lang = some("EN-gb");
+        assert_eq!(
+            request.parse().unwrap().options.language.as_deref(),
+            Some("en-GB")
+        );
+
+        let mut request = params("hello");
+        request.lang = some("fr");
+        assert_eq!(unprocessable(request.parse()), "Invalid lang");
+    }
+
+    #[test]
+    fn channel_filters_are_merged_and_deduplicated() {
+        let mut request = params("hello");
+        request.channel_id = some(CHANNEL_A);
+        request.channel_ids = vec![
+            format!("{CHANNEL_B}, {CHANNEL_A}"),
+            format!(",{CHANNEL_B},"),
+        ];
+        request.exclude_channels = Some(format!("{CHANNEL_C},{CHANNEL_C}"));
+        let options = request.parse().unwrap().options;
+        assert_eq!(options.channels, vec![CHANNEL_A, CHANNEL_B]);
+        assert_eq!(options.exclude_channels, vec![CHANNEL_C]);
+    }
+
+    #[test]
+    fn channels_both_included_and_excluded_are_rejected() {
+        let mut request = params("hello");
+        request.channel_id = some(CHANNEL_A);
+        request.channel_ids = vec![CHANNEL_B.to_string()];
+        request.exclude_channels = Some(format!("{CHANNEL_C},{CHANNEL_B}"));
+        assert_eq!(
+            unprocessable(request.parse()),
+            "Conflicting channel filters"
+        );
+    }
+
+    #[test]
+    fn invalid_channel_ids_are_rejected() {
+        let mut request = params("hello");
+        request.channel_ids = vec!["not-a-channel".to_string()];
+        assert_eq!(unprocessable(request.parse()), "Invalid channel_ids");
+
+        let mut request = params("hello");
+        request.exclude_channels = some("UCshort");
+        assert_eq!(unprocessable(request.parse()), "Invalid exclude_channels");
+    }
+
+    #[test]
+    fn too_many_channels_are_rejected() {
+        let channels: Vec<String> = (0..=MAX_FILTER_CHANNELS)
+            .map(|i| format!("UC{i:0>22}"))
+            .collect();
+        let mut request = params("hello");
+        request.channel_ids = vec![channels[..MAX_FILTER_CHANNELS].join(",")];
+        assert_eq!(
+            request.parse().unwrap().options.channels.len(),
+            MAX_FILTER_CHANNELS
+        );
+
+        let mut request = params("hello");
+        request.channel_ids = vec![channels.join(",")];
+        assert_eq!(unprocessable(request.parse()), "Invalid channel_ids");
+    }
+
+    #[test]
+    fn highlight_tags() {
+        let mut request = params("hello");
+        request.highlight_pre = some("<mark>");
+        request.highlight_post = some("</mark>");
+        let tags = request.parse().unwrap().options.highlight_tags;
+        assert_eq!(
+            (tags.pre.as_str(), tags.post.as_str()),
+            ("<mark>", "</mark>")
+        );
+
+        let mut request = params("hello");
+        request.highlight_pre = some("");
+        request.highlight_post = some("");
+        assert_eq!(
+            request.parse().unwrap().options.highlight_tags,
+            HighlightTags::none()
+        );
+
+        for (pre, post) in [
+            (some("<mark>"), None),
+            (None, some("</mark>")),
+            (some("<mark>"), some("")),
+            (some("<script>"), some("</script>")),
+        ] {
+            let mut request = params("hello");
+            request.highlight_pre = pre.clone();
+            request.highlight_post = post.clone();
+            assert!(
+                matches!(request.parse(), Err(SearchError::Unprocessable(_))),
+                "{pre:?} {post:?}"
+            );
         }
     }
 }
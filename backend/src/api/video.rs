@@ -1,14 +1,21 @@
-use crate::models::VideoMetadata;
-use crate::services::video_service;
+use crate::config::{INDEX_NAMES, SEARCH_LOG_ENABLED, THUMBNAIL_PROXY};
+use crate::models::{
+    ApiAccess, BatchVideoError, BatchVideoRequest, BatchVideoResponse, CaptionLanguage,
+    PublicChannel, TrendingVideo, VideoMetadata, VideoTranscript,
+};
+use crate::services::thumbnail_service::{thumbnail_url, THUMBNAIL_CACHE};
+use crate::services::{es_breaker, trending_service, video_service};
 use crate::AppState;
+use elasticsearch::Elasticsearch;
 use log::{error, info};
+use rocket::http::{ContentType, Header, Status};
+use rocket::response::Redirect;
 use rocket::serde::json::Json;
-use rocket::{get, State};
-use serde::Deserialize;
+use rocket::{get, post, Responder, State};
 use serde_json::Value;
 
 #[get("/")]
-pub async fn list_videos(state: &State<AppState>) -> Json<Vec<String>> {
+pub async fn list_videos(_access: ApiAccess, state: &State<AppState>) -> Json<Vec<String>> {
     match video_service::list_all_videos(&state.es_client).await {
         Ok(video_ids) => {
             info!("Found {} registered videos.", video_ids.len());
@@ -21,77 +28,346 @@ pub async fn list_videos(state: &State<AppState>) -> Json<Vec<String>> {
     }
 }
 
-#[get("/<id>")]
-pub async fn get_video_metadata(state: &State<AppState>, id: &str) -> Json<Option<VideoMetadata>> {
-    match state
-        .es_client
-        .get(elasticsearch::GetParts::IndexId("youtube_videos", id))
-        .send()
-        .await
-    {
-        Ok(response) => {
-            if response.status_code().is_success() {
-                match response.json::<Value>().await {
-                    Ok(json_response) => {
-                        if let Some(source) = json_response.get("_source") {
-                            if let Ok(metadata) = serde_json::from_value(source.clone()) {
-                                return Json(Some(metadata));
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!("Failed to parse Elasticsearch response: {e:?}");
-                    }
-                }
+/// Videos that most often matched the popular queries of the last week, see
+/// `trending_service`. 404 if the search log is disabled.
+#[get("/trending-matches?<limit>")]
+pub async fn get_trending_matches(
+    _access: ApiAccess,
+    state: &State<AppState>,
+    limit: Option<usize>,
+) -> Result<Json<Vec<TrendingVideo>>, Status> {
+    if !*SEARCH_LOG_ENABLED {
+        return Err(Status::NotFound);
+    }
+    match trending_service::get_trending_matches(&state.es_client, limit.unwrap_or(10)).await {
+        Ok(videos) => Ok(Json(videos)),
+        Err(e) => {
+            error!("Failed to get trending matches: {e:?}");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// Channels with public videos, for the search filters. `tag` limits them to the
+/// channels with that public tag.
+#[get("/channels?<tag>")]
+pub async fn get_public_channels(
+    _access: ApiAccess,
+    state: &State<AppState>,
+    tag: Option<&str>,
+) -> Result<Json<Vec<PublicChannel>>, Status> {
+    match video_service::list_public_channels(&state.es_client, &state.generation).await {
+        Ok(mut channels) => {
+            if let Some(tag) = tag {
+                let tag = tag.trim().to_lowercase();
+                channels.retain(|channel| channel.tags.contains(&tag));
             }
+            Ok(Json(channels))
         }
         Err(e) => {
-            error!("Failed to fetch video metadata: {e:?}");
+            error!("Failed to list channels: {e:?}");
+            Err(Status::InternalServerError)
         }
     }
-    Json(None)
 }
 
-#[derive(Debug, Deserialize)]
-pub struct BatchVideoRequest {
-    pub video_ids: Vec<String>,
+/// Public videos of a channel, newest first, for its page
+#[get("/channels/<channel_id>/videos")]
+pub async fn get_public_channel_videos(
+    _access: ApiAccess,
+    state: &State<AppState>,
+    channel_id: &str,
+) -> Result<Json<Vec<VideoMetadata>>, Status> {
+    let result = es_breaker::guarded(
+        &state.es_client,
+        video_service::list_public_channel_videos(&state.es_client, channel_id),
+    )
+    .await;
+    match result {
+        Ok(videos) => Ok(Json(videos)),
+        Err(e) => {
+            error!("Failed to list the videos of channel {channel_id}: {e:?}");
+            Err(error_status(&e))
+        }
+    }
+}
+
+/// 503 while Elasticsearch is unreachable, 500 for other failures
+fn error_status(error: &anyhow::Error) -> Status {
+    if es_breaker::is_unavailable(error) {
+        Status::ServiceUnavailable
+    } else {
+        Status::InternalServerError
+    }
+}
+
+#[get("/<id>")]
+pub async fn get_video_metadata(
+    _access: ApiAccess,
+    state: &State<AppState>,
+    id: &str,
+) -> Result<Json<Option<VideoMetadata>>, Status> {
+    let result =
+        es_breaker::guarded(&state.es_client, fetch_video_metadata(&state.es_client, id)).await;
+    match result {
+        Ok(metadata) => Ok(Json(metadata)),
+        Err(e) if es_breaker::is_unavailable(&e) => {
+            error!("Failed to fetch video metadata: {e:?}");
+            Err(Status::ServiceUnavailable)
+        }
+        Err(e) => {
+            error!("Failed to fetch video metadata: {e:?}");
+            Ok(Json(None))
+        }
+    }
 }
 
 #[get("/batch", data = "<request>")]
 pub async fn get_videos_metadata(
+    _access: ApiAccess,
     state: &State<AppState>,
     request: Json<BatchVideoRequest>,
-) -> Json<Vec<Option<VideoMetadata>>> {
-    let mut results = Vec::new();
-    for id in &request.video_ids {
-        match state
-            .es_client
-            .get(elasticsearch::GetParts::IndexId("youtube_videos", id))
-            .send()
-            .await
-        {
-            Ok(response) => {
-                if response.status_code().is_success() {
-                    match response.json::<Value>().await {
-                        Ok(json_response) => {
-                            if let Some(source) = json_response.get("_source") {
-                                if let Ok(metadata) = serde_json::from_value(source.clone()) {
-                                    results.push(Some(metadata));
-                                    continue;
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            error!("Failed to parse Elasticsearch response: {e:?}");
-                        }
-                    }
-                }
-            }
+) -> Json<BatchVideoResponse> {
+    Json(fetch_videos_metadata(&state.es_client, &request.video_ids).await)
+}
+
+/// Same as `GET /batch`, browsers can't send a body with GET requests
+#[post("/batch", data = "<request>")]
+pub async fn post_videos_metadata(
+    _access: ApiAccess,
+    state: &State<AppState>,
+    request: Json<BatchVideoRequest>,
+) -> Json<BatchVideoResponse> {
+    Json(fetch_videos_metadata(&state.es_client, &request.video_ids).await)
+}
+
+/// Metadata in the order of `video_ids`, None for unknown, hidden and deleted videos.
+/// Videos that failed to load are listed in `errors` so the rest of the batch still arrives.
+async fn fetch_videos_metadata(
+    es_client: &Elasticsearch,
+    video_ids: &[String],
+) -> BatchVideoResponse {
+    let mut response = BatchVideoResponse::default();
+    for id in video_ids {
+        match fetch_video_metadata(es_client, id).await {
+            Ok(metadata) => response.videos.push(metadata),
             Err(e) => {
-                error!("Failed to fetch video metadata: {e:?}");
+                error!("Failed to fetch video metadata of {id}: {e:?}");
+                response.videos.push(None);
+                response.errors.push(BatchVideoError {
+                    video_id: id.clone(),
+                    error: e.to_string(),
+                });
             }
         }
-        results.push(None);
     }
-    Json(results)
+    response
+}
+
+/// None if the video doesn't exist or is hidden. A missing or unavailable index is
+/// an error, not an unknown video.
+pub(crate) async fn fetch_video_metadata(
+    es_client: &Elasticsearch,
+    video_id: &str,
+) -> anyhow::Result<Option<VideoMetadata>> {
+    let response = es_client
+        .get(elasticsearch::GetParts::IndexId(
+            INDEX_NAMES.videos,
+            video_id,
+        ))
+        .send()
+        .await?;
+    let status = response.status_code();
+    let json_response: Value = response.json().await?;
+    if let Some(error) = json_response.get("error") {
+        return Err(anyhow::anyhow!(
+            "{}",
+            error["type"].as_str().unwrap_or("metadata unavailable")
+        ));
+    }
+    if status.as_u16() == 404 {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        return Err(anyhow::anyhow!("Elasticsearch returned {status}"));
+    }
+
+    match json_response.get("_source") {
+        Some(source) => {
+            let metadata = serde_json::from_value::<VideoMetadata>(source.clone())?;
+            Ok(Some(metadata).filter(|m| !m.hidden && !m.deleting))
+        }
+        None => Ok(None),
+    }
+}
+
+#[get("/<id>/languages")]
+pub async fn get_video_languages(
+    _access: ApiAccess,
+    state: &State<AppState>,
+    id: &str,
+) -> Json<Vec<CaptionLanguage>> {
+    match video_service::get_video_languages(&state.es_client, id).await {
+        Ok(languages) => Json(languages),
+        Err(e) => {
+            error!("Failed to list caption languages of video {id}: {e:?}");
+            Json(vec![])
+        }
+    }
+}
+
+/// Whole transcript, or with `segment` only the captions of that segment
+#[get("/<id>/transcript?<language>&<segment>")]
+pub async fn get_video_transcript(
+    _access: ApiAccess,
+    state: &State<AppState>,
+    id: &str,
+    language: Option<&str>,
+    segment: Option<i64>,
+) -> Result<Json<Option<VideoTranscript>>, Status> {
+    let result = es_breaker::guarded(
+        &state.es_client,
+        video_service::get_transcript(&state.es_client, id, language, segment),
+    )
+    .await;
+    match result {
+        Ok(transcript) => Ok(Json(transcript)),
+        Err(e) if es_breaker::is_unavailable(&e) => {
+            error!("Failed to load the transcript of video {id}: {e:?}");
+            Err(Status::ServiceUnavailable)
+        }
+        Err(e) => {
+            error!("Failed to load the transcript of video {id}: {e:?}");
+            Ok(Json(None))
+        }
+    }
+}
+
+/// Served thumbnail, or a redirect to YouTube while the proxy is disabled
+#[derive(Responder)]
+pub enum ThumbnailResponse {
+    Image(Vec<u8>, ContentType, Header<'static>),
+    Redirect(Redirect),
+}
+
+/// Thumbnail of a video through the caching proxy, so visitors never hit YouTube directly
+#[get("/<id>/thumbnail")]
+pub async fn get_video_thumbnail(
+    state: &State<AppState>,
+    id: &str,
+) -> Result<ThumbnailResponse, Status> {
+    if !*THUMBNAIL_PROXY {
+        return Ok(ThumbnailResponse::Redirect(Redirect::found(thumbnail_url(
+            id,
+        ))));
+    }
+
+    // Only indexed videos, the proxy must not fetch arbitrary thumbnails
+    match video_service::is_public_video(&state.es_client, id).await {
+        Ok(true) => {}
+        Ok(false) => return Err(Status::NotFound),
+        Err(e) => {
+            error!("Failed to look up video {id} for its thumbnail: {e:?}");
+            return Err(Status::InternalServerError);
+        }
+    }
+
+    match THUMBNAIL_CACHE.get(id).await {
+        Ok(thumbnail) => Ok(ThumbnailResponse::Image(
+            thumbnail.bytes,
+            ContentType::parse_flexible(&thumbnail.content_type).unwrap_or(ContentType::JPEG),
+            Header::new("Cache-Control", "public, max-age=604800"),
+        )),
+        Err(e) => {
+            error!("Failed to load thumbnail of video {id}: {e:?}");
+            Err(Status::BadGateway)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::simulation;
+    use crate::testsupport::MockElasticsearch;
+    use serde_json::json;
+
+    fn video_doc(video_id: &str) -> String {
+        format!("/{}/_doc/{video_id}", INDEX_NAMES.videos)
+    }
+
+    /// What Elasticsearch answers while the videos index is missing
+    fn index_not_found() -> Value {
+        json!({
+            "error": {
+                "root_cause": [{ "type": "index_not_found_exception", "reason": "no such index" }],
+                "type": "index_not_found_exception",
+                "reason": "no such index"
+            },
+            "status": 404
+        })
+    }
+
+    async fn stub_indexed_video(es: &MockElasticsearch, metadata: &VideoMetadata) {
+        es.stub(
+            "GET",
+            &video_doc(&metadata.video_id),
+            json!({
+                "_index": INDEX_NAMES.videos,
+                "_id": metadata.video_id,
+                "found": true,
+                "_source": metadata
+            }),
+        )
+        .await;
+    }
+
+    #[rocket::async_test]
+    async fn missing_index_is_an_error_not_an_unknown_video() {
+        let es = MockElasticsearch::start().await;
+        es.stub_status("GET", &video_doc("aaaaaaaaaaa"), 404, index_not_found())
+            .await;
+        es.stub_status(
+            "GET",
+            &video_doc("bbbbbbbbbbb"),
+            404,
+            json!({ "_index": INDEX_NAMES.videos, "_id": "bbbbbbbbbbb", "found": false }),
+        )
+        .await;
+
+        let error = fetch_video_metadata(&es.client, "aaaaaaaaaaa")
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "index_not_found_exception");
+        assert_eq!(
+            fetch_video_metadata(&es.client, "bbbbbbbbbbb")
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[rocket::async_test]
+    async fn batch_metadata_returns_partial_results() {
+        let es = MockElasticsearch::start().await;
+        let indexed = simulation::video_metadata("aaaaaaaaaaa");
+        stub_indexed_video(&es, &indexed).await;
+        let mut hidden = simulation::video_metadata("ccccccccccc");
+        hidden.hidden = true;
+        stub_indexed_video(&es, &hidden).await;
+        es.stub_status("GET", &video_doc("bbbbbbbbbbb"), 404, index_not_found())
+            .await;
+
+        let video_ids = ["aaaaaaaaaaa", "bbbbbbbbbbb", "ccccccccccc"].map(String::from);
+        let response = fetch_videos_metadata(&es.client, &video_ids).await;
+
+        assert_eq!(response.videos, [Some(indexed), None, None]);
+        assert_eq!(
+            response.errors,
+            [BatchVideoError {
+                video_id: "bbbbbbbbbbb".to_string(),
+                error: "index_not_found_exception".to_string(),
+            }]
+        );
+    }
 }
@@ -0,0 +1,238 @@
+use crate::config::create_elasticsearch_client;
+use crate::migrations;
+use crate::services::elasticsearch_service::create_es_index;
+use crate::services::maintenance_throttle::MaintenanceThrottle;
+use crate::services::{maintenance_service, stemming};
+use clap::{Parser, Subcommand};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+#[derive(Parser)]
+#[command(name = "starchive-backend", about = "Starchive backend server")]
+pub struct Cli {
+    /// Starts the HTTP server if omitted
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Runs a single maintenance task against the configured Elasticsearch and exits
+    Maintenance {
+        #[command(subcommand)]
+        task: MaintenanceTask,
+    },
+    /// Runs the pending schema migrations, including the expensive ones the server
+    /// refuses to start with
+    Migrate,
+}
+
+#[derive(Subcommand)]
+pub enum MaintenanceTask {
+    /// Fetches captions for every indexed video that has none
+    BackfillCaptions,
+    /// Sets the transcript segment of captions indexed before segments existed
+    BackfillSegments,
+    /// Copies the upload date of videos onto captions indexed before it was stored there
+    BackfillUploadDates,
+    /// Copies the channel of videos onto captions indexed before it was stored there
+    BackfillChannelIds,
+    /// Stitches the captions of every video into the sentence index anew
+    RebuildSentences,
+    /// Analyzes all captions again, needed after adding a language to LANGUAGE_PRIORITY
+    ReanalyzeCaptions,
+    /// Deletes captions whose video no longer exists
+    PruneOrphans,
+    /// Checks the indices for inconsistencies, exits with 1 if any are found. Recomputes
+    /// the caption coverage of every video on the way.
+    Verify,
+    /// Writes all videos, captions, monitors and reports to an NDJSON file
+    Export {
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Indexes the documents of an NDJSON export
+    Import {
+        #[arg(long = "in")]
+        input: PathBuf,
+    },
+}
+
+pub async fn run_maintenance(task: MaintenanceTask) -> ExitCode {
+    let es_client = match create_elasticsearch_client() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Failed to create the Elasticsearch client: {e:?}");
+            return ExitCode::FAILURE;
+        }
+    };
+    create_es_index(&es_client).await;
+
+    // The task runs in a process of its own, the throttle paces it the way the server
+    // paces its maintenance
+    let maintenance = Arc::new(MaintenanceThrottle::from_config());
+    let run = match maintenance.try_start("command line maintenance") {
+        Ok(run) => run,
+        Err(running) => {
+            eprintln!("Maintenance {} is already running", running.name);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = match task {
+        MaintenanceTask::BackfillCaptions => {
+            maintenance_service::backfill_captions(
+                &es_client,
+                |done, total| println!("[{done}/{total}] captions fetched"),
+                &run,
+            )
+            .await
+            .map(|report| {
+                println!(
+                    "Backfilled captions of {} of {} videos, {} deferred because of throttling or timeouts",
+                    report.completed, report.attempted, report.deferred
+                );
+                report.deferred == 0
+            })
+        }
+        MaintenanceTask::BackfillSegments => {
+            maintenance_service::backfill_caption_segments(&es_client, &run)
+                .await
+                .map(|updated| {
+                    println!("Backfilled the segment of {updated} captions");
+                    true
+                })
+        }
+        MaintenanceTask::BackfillUploadDates => {
+            maintenance_service::backfill_caption_upload_dates(&es_client, &run)
+                .await
+                .map(|updated| {
+                    println!("Backfilled the upload date of {updated} captions and sentences");
+                    true
+                })
+        }
+        MaintenanceTask::BackfillChannelIds => {
+            maintenance_service::backfill_caption_channel_ids(&es_client, &run)
+                .await
+                .map(|updated| {
+                    println!("Backfilled the channel of {updated} captions and sentences");
+                    true
+                })
+        }
+        MaintenanceTask::RebuildSentences => {
+            maintenance_service::rebuild_sentences(
+                &es_client,
+                |done, total| println!("[{done}/{total}] videos stitched"),
+                Some(&run),
+            )
+            .await
+            .map(|total| {
+                println!("Rebuilt {total} sentences");
+                true
+            })
+        }
+        MaintenanceTask::ReanalyzeCaptions => stemming::reanalyze(&es_client, &run)
+            .await
+            .map(|updated| {
+                println!("Reanalyzed {updated} captions and sentences");
+                true
+            }),
+        MaintenanceTask::PruneOrphans => maintenance_service::prune_orphaned_captions(&es_client, &run)
+            .await
+            .map(|orphans| {
+                for video_id in &orphans {
+                    println!("Pruned captions of {video_id}");
+                }
+                println!("Pruned captions of {} orphaned videos", orphans.len());
+                true
+            }),
+        MaintenanceTask::Verify => maintenance_service::verify(&es_client).await.map(|report| {
+            println!("Videos: {}", report.videos);
+            println!("Captions: {}", report.captions);
+            println!(
+                "Videos without captions: {}",
+                report.videos_without_captions
+            );
+            println!(
+                "Videos with orphaned captions: {}",
+                report.orphaned_caption_videos.len()
+            );
+            for video_id in &report.orphaned_caption_videos {
+                println!("  {video_id}");
+            }
+            println!(
+                "Videos with recomputed caption coverage: {}",
+                report.recomputed_coverage
+            );
+            report.is_consistent()
+        }),
+        MaintenanceTask::Export { out } => match File::create(&out) {
+            Ok(file) => {
+                let mut writer = BufWriter::new(file);
+                maintenance_service::export_documents(&es_client, &mut writer, |index, count| {
+                    println!("Exported {count} documents of {index}");
+                })
+                .await
+                .map(|total| {
+                    println!("Exported {total} documents to {}", out.display());
+                    true
+                })
+            }
+            Err(e) => Err(anyhow::anyhow!("Can't create {}: {e}", out.display())),
+        },
+        MaintenanceTask::Import { input } => match File::open(&input) {
+            Ok(file) => {
+                maintenance_service::import_documents(&es_client, BufReader::new(file), |count| {
+                    println!("Imported {count} documents")
+                })
+                .await
+                .map(|total| {
+                    println!("Imported {total} documents from {}", input.display());
+                    true
+                })
+            }
+            Err(e) => Err(anyhow::anyhow!("Can't open {}: {e}", input.display())),
+        },
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("Maintenance task failed: {e:?}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+pub async fn run_migrate() -> ExitCode {
+    let es_client = match create_elasticsearch_client() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Failed to create the Elasticsearch client: {e:?}");
+            return ExitCode::FAILURE;
+        }
+    };
+    create_es_index(&es_client).await;
+
+    let maintenance = Arc::new(MaintenanceThrottle::from_config());
+    match migrations::migrate(&es_client, &maintenance).await {
+        Ok(applied) if applied.is_empty() => {
+            println!("No pending migrations");
+            ExitCode::SUCCESS
+        }
+        Ok(applied) => {
+            for version in &applied {
+                println!("Applied migration {version}");
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Migration failed: {e:?}");
+            ExitCode::from(2)
+        }
+    }
+}
@@ -1,7 +1,22 @@
-use crate::models::AdminToken;
+use crate::index_names::IndexNames;
+use crate::migrations;
+use crate::models::{AdminToken, ApiAccess, GENERATION_HEADER};
+use crate::schedule::Schedule;
+use crate::services::api_key_service::{self, ApiKeyCheck, ApiKeyUsageTracker};
+use crate::services::archive_generation::ArchiveGeneration;
+use crate::services::caption_update_service;
 use crate::services::crawler::{crawl_youtube_video, split_language_codes, VideoQueue};
 use crate::services::elasticsearch_service::create_es_index;
+use crate::services::health_service;
+use crate::services::maintenance_throttle::MaintenanceThrottle;
+use crate::services::monitor_tag_service;
 use crate::services::monitoring_service::setup_monitoring;
+use crate::services::notification_service::{self, SmtpConfig};
+use crate::services::optout_service::load_opt_outs;
+use crate::services::rate_limiter::RateLimiter;
+use crate::services::sentence_service;
+use crate::services::synonym_service;
+use crate::services::trending_service;
 use crate::AppState;
 use anyhow::Result;
 use elasticsearch::{
@@ -10,19 +25,56 @@ use elasticsearch::{
 };
 use env_logger::Builder;
 use lazy_static::lazy_static;
-use log::{info, LevelFilter};
-use rocket::http::{Method, Status};
+use log::{error, info, LevelFilter};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{Header, Method, Status};
 use rocket::request::{FromRequest, Outcome};
-use rocket::Request;
+use rocket::{Request, Response, State};
 use rocket_cors::{AllowedHeaders, AllowedOrigins, CorsOptions};
 use std::env;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 use tokio_cron_scheduler::{Job, JobScheduler};
 
+/// Self plus the YouTube domains needed for the embedded player and thumbnails
+const DEFAULT_CSP: &str = "default-src 'self'; \
+    img-src 'self' data: https://i.ytimg.com https://yt3.ggpht.com; \
+    frame-src https://www.youtube.com https://www.youtube-nocookie.com; \
+    object-src 'none'; base-uri 'self'; frame-ancestors 'none'";
+
+/// Periodic jobs may not fire more often than this
+const MIN_SCHEDULE_INTERVAL: Duration = Duration::from_secs(60);
+/// The queue is crawled in small bursts, so its schedule may be tighter
+const MIN_CRAWL_QUEUE_INTERVAL: Duration = Duration::from_secs(10);
+
 lazy_static! {
-    pub static ref YOUTUBE_API_KEY: String =
-        env::var("YOUTUBE_API_KEY").expect("YOUTUBE_API_KEY environment variable must be set");
+    pub static ref YOUTUBE_API_KEY: String = env::var("YOUTUBE_API_KEY").unwrap_or_else(|_| {
+        // Nothing calls YouTube in simulation mode
+        if *SIMULATION_MODE {
+            String::new()
+        } else {
+            panic!("YOUTUBE_API_KEY environment variable must be set")
+        }
+    });
+    pub static ref DEBUG: bool = env::var("DEBUG")
+        .map(|v| v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    pub static ref SIMULATION_MODE: bool = {
+        let enabled = env::var("SIMULATION_MODE")
+            .map(|v| v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        if enabled && !*DEBUG {
+            panic!("SIMULATION_MODE requires DEBUG=true, it fakes all YouTube data");
+        }
+        enabled
+    };
+    pub static ref SIMULATION_PLAYLIST: Vec<String> = env::var("SIMULATION_PLAYLIST")
+        .unwrap_or_default()
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
     pub static ref ADMIN_TOKEN: String =
         env::var("ADMIN_TOKEN").expect("ADMIN_TOKEN environment variable must be set");
     pub static ref ELASTICSEARCH_URL: String =
@@ -35,10 +87,158 @@ lazy_static! {
         .unwrap_or_else(|_| "1".to_string())
         .parse::<i32>()
         .unwrap_or(1);
-    pub static ref MONITOR_CHECK_SCHEDULE: String =
-        env::var("MONITOR_CHECK_SCHEDULE").unwrap_or_else(|_| "0 */10 * * * *".to_string());
-    pub static ref CRAWL_QUEUE_SCHEDULE: String =
-        env::var("CRAWL_QUEUE_SCHEDULE").unwrap_or_else(|_| "*/30 * * * * *".to_string());
+    pub static ref MONITOR_CHECK_SCHEDULE: Schedule = Schedule::from_env(
+        "MONITOR_CHECK_SCHEDULE",
+        "0 */10 * * * *",
+        MIN_SCHEDULE_INTERVAL
+    );
+    pub static ref BACKFILL_SCHEDULE: Schedule = Schedule::from_env(
+        "BACKFILL_SCHEDULE",
+        "0 */30 * * * *",
+        MIN_SCHEDULE_INTERVAL
+    );
+    /// Monitors missing more videos than this are backfilling: their older videos are
+    /// enqueued a share per backfill cycle instead of all at once
+    pub static ref BACKFILL_THRESHOLD: i64 = env::var("BACKFILL_THRESHOLD")
+        .unwrap_or_else(|_| "50".to_string())
+        .parse::<i64>()
+        .unwrap_or(50);
+    pub static ref BACKFILL_VIDEOS_PER_CYCLE: usize = env::var("BACKFILL_VIDEOS_PER_CYCLE")
+        .unwrap_or_else(|_| "50".to_string())
+        .parse::<usize>()
+        .unwrap_or(50);
+    pub static ref BACKFILL_MAX_QUEUE: usize = env::var("BACKFILL_MAX_QUEUE")
+        .unwrap_or_else(|_| "200".to_string())
+        .parse::<usize>()
+        .unwrap_or(200);
+    pub static ref ANONYMOUS_RATE_LIMIT: usize = env::var("ANONYMOUS_RATE_LIMIT")
+        .unwrap_or_else(|_| "300".to_string())
+        .parse::<usize>()
+        .unwrap_or(300);
+    pub static ref CRAWL_QUEUE_SCHEDULE: Schedule = Schedule::from_env(
+        "CRAWL_QUEUE_SCHEDULE",
+        "*/30 * * * * *",
+        MIN_CRAWL_QUEUE_INTERVAL
+    );
+    pub static ref CRAWL_ITEM_TIMEOUT_MINUTES: u64 = env::var("CRAWL_ITEM_TIMEOUT_MINUTES")
+        .unwrap_or_else(|_| "15".to_string())
+        .parse::<u64>()
+        .unwrap_or(15);
+    /// Videos longer than this are indexed without captions, None if unset or 0
+    pub static ref MAX_VIDEO_DURATION_SECONDS: Option<i64> = env::var("MAX_VIDEO_DURATION_SECONDS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|&seconds| seconds > 0);
+    pub static ref INDEX_METADATA_FOR_SKIPPED: bool = env::var("INDEX_METADATA_FOR_SKIPPED")
+        .map(|v| !v.eq_ignore_ascii_case("false"))
+        .unwrap_or(true);
+    pub static ref YOUTUBE_PROXY_URLS: Vec<String> = env::var("YOUTUBE_PROXY_URL")
+        .unwrap_or_default()
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    pub static ref YOUTUBE_REQUEST_TIMEOUT_SECONDS: u64 =
+        env::var("YOUTUBE_REQUEST_TIMEOUT_SECONDS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse::<u64>()
+            .unwrap_or(60);
+    pub static ref MAX_CAPTION_CHARS: usize = env::var("MAX_CAPTION_CHARS")
+        .unwrap_or_else(|_| "500".to_string())
+        .parse::<usize>()
+        .unwrap_or(500);
+    pub static ref TRANSCRIPT_MIN_DELAY_MS: u64 = env::var("TRANSCRIPT_MIN_DELAY_MS")
+        .unwrap_or_else(|_| "2000".to_string())
+        .parse::<u64>()
+        .unwrap_or(2000);
+    pub static ref MAINTENANCE_BATCH_SIZE: usize = env::var("MAINTENANCE_BATCH_SIZE")
+        .unwrap_or_else(|_| "1000".to_string())
+        .parse::<usize>()
+        .unwrap_or(1000);
+    pub static ref MAINTENANCE_BATCH_DELAY_MS: u64 = env::var("MAINTENANCE_BATCH_DELAY_MS")
+        .unwrap_or_else(|_| "500".to_string())
+        .parse::<u64>()
+        .unwrap_or(500);
+    pub static ref REFERRER_POLICY: String = env::var("REFERRER_POLICY")
+        .unwrap_or_else(|_| "strict-origin-when-cross-origin".to_string());
+    pub static ref CONTENT_SECURITY_POLICY: String =
+        env::var("CONTENT_SECURITY_POLICY").unwrap_or_else(|_| DEFAULT_CSP.to_string());
+    pub static ref APP_NAME: String = env::var("APP_NAME")
+        .unwrap_or_else(|_| "Paulsenik's StarCitizen Content Search".to_string());
+    pub static ref THUMBNAIL_PROXY: bool = env::var("THUMBNAIL_PROXY")
+        .map(|v| v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    pub static ref THUMBNAIL_CACHE_DIR: String =
+        env::var("THUMBNAIL_CACHE_DIR").unwrap_or_else(|_| "thumbnail_cache".to_string());
+    pub static ref THUMBNAIL_CACHE_MAX_MB: u64 = env::var("THUMBNAIL_CACHE_MAX_MB")
+        .unwrap_or_else(|_| "512".to_string())
+        .parse::<u64>()
+        .unwrap_or(512);
+    pub static ref CAPTURE_RAW_RESPONSES: bool = env::var("CAPTURE_RAW_RESPONSES")
+        .map(|v| v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    pub static ref RAW_CAPTURE_DIR: String =
+        env::var("RAW_CAPTURE_DIR").unwrap_or_else(|_| "raw_capture".to_string());
+    pub static ref RAW_CAPTURE_MAX_MB: u64 = env::var("RAW_CAPTURE_MAX_MB")
+        .unwrap_or_else(|_| "64".to_string())
+        .parse::<u64>()
+        .unwrap_or(64);
+    pub static ref SEARCH_LOG_ENABLED: bool = env::var("SEARCH_LOG_ENABLED")
+        .map(|v| v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    pub static ref TRENDING_MATCHES_SCHEDULE: Schedule = Schedule::from_env(
+        "TRENDING_MATCHES_SCHEDULE",
+        "0 0 4 * * Mon",
+        MIN_SCHEDULE_INTERVAL
+    );
+    /// Process-global rather than on `AppState`, see the `index_names` module docs
+    pub static ref INDEX_NAMES: IndexNames =
+        IndexNames::with_prefix(&env::var("INDEX_PREFIX").unwrap_or_default());
+    pub static ref SENTENCE_COMPACTION_SCHEDULE: Schedule = Schedule::from_env(
+        "SENTENCE_COMPACTION_SCHEDULE",
+        "0 0 3 * * *",
+        MIN_SCHEDULE_INTERVAL
+    );
+    pub static ref CAPTION_UPDATE_SCHEDULE: Schedule = Schedule::from_env(
+        "CAPTION_UPDATE_SCHEDULE",
+        "0 0 5 * * Sun",
+        MIN_SCHEDULE_INTERVAL
+    );
+    /// Auto-generated captions are checked for a manual track once they are this old
+    pub static ref CAPTION_UPDATE_MIN_AGE_MONTHS: i64 = env::var("CAPTION_UPDATE_MIN_AGE_MONTHS")
+        .unwrap_or_else(|_| "6".to_string())
+        .parse::<i64>()
+        .unwrap_or(6);
+    pub static ref CAPTION_UPDATE_SAMPLE_SIZE: usize = env::var("CAPTION_UPDATE_SAMPLE_SIZE")
+        .unwrap_or_else(|_| "50".to_string())
+        .parse::<usize>()
+        .unwrap_or(50);
+    pub static ref SMTP_CONFIG: Option<SmtpConfig> = SmtpConfig::from_env();
+    pub static ref DIGEST_SCHEDULE: Schedule =
+        Schedule::from_env("DIGEST_SCHEDULE", "0 0 6 * * *", MIN_SCHEDULE_INTERVAL);
+    pub static ref HEALTH_CHECK_SCHEDULE: Schedule = Schedule::from_env(
+        "HEALTH_CHECK_SCHEDULE",
+        "0 */5 * * * *",
+        MIN_SCHEDULE_INTERVAL
+    );
+    pub static ref HEALTH_MAX_CRAWL_FAILURES: u32 = env::var("HEALTH_MAX_CRAWL_FAILURES")
+        .unwrap_or_else(|_| "5".to_string())
+        .parse::<u32>()
+        .unwrap_or(5);
+    /// Initial overall budget of a search, admins can change it at runtime
+    pub static ref SEARCH_DEADLINE_MS: u64 = env::var("SEARCH_DEADLINE_MS")
+        .unwrap_or_else(|_| "8000".to_string())
+        .parse::<u64>()
+        .unwrap_or(8000);
+    /// Consecutive Elasticsearch transport failures that open the circuit breaker
+    pub static ref ES_BREAKER_FAILURES: u32 = env::var("ES_BREAKER_FAILURES")
+        .unwrap_or_else(|_| "3".to_string())
+        .parse::<u32>()
+        .unwrap_or(3);
+    pub static ref ES_BREAKER_COOLDOWN_SECONDS: u64 = env::var("ES_BREAKER_COOLDOWN_SECONDS")
+        .unwrap_or_else(|_| "30".to_string())
+        .parse::<u64>()
+        .unwrap_or(30);
     pub static ref LANGUAGE_PRIORITY: Vec<String> = split_language_codes(
         env::var("LANGUAGE_PRIORITY")
             .expect("LANGUAGE_PRIORITY environment variable must be set")
@@ -57,11 +257,83 @@ pub fn init_logger() {
 pub fn load_environment() {
     dotenv::dotenv().ok();
 
+    info!("DEBUG: {}", &*DEBUG);
+    if *SIMULATION_MODE {
+        info!(
+            "SIMULATION_MODE: enabled, YouTube is faked, SIMULATION_PLAYLIST: {} videos",
+            SIMULATION_PLAYLIST.len()
+        );
+    }
     info!("YOUTUBE_API_KEY: {}", &*YOUTUBE_API_KEY);
+    info!("INDEX_PREFIX: \"{}\"", INDEX_NAMES.prefix);
     info!("CRAWL_QUEUE_SCHEDULE: {}", &*CRAWL_QUEUE_SCHEDULE);
     info!("MONITOR_CHECK_SCHEDULE: {}", &*MONITOR_CHECK_SCHEDULE);
     info!("LANGUAGE_PRIORITY: {:?}", &*LANGUAGE_PRIORITY);
     info!("CRAWL_BURST_MAX: {}", &*CRAWL_BURST_MAX);
+    info!(
+        "CRAWL_ITEM_TIMEOUT_MINUTES: {}",
+        &*CRAWL_ITEM_TIMEOUT_MINUTES
+    );
+    info!("ANONYMOUS_RATE_LIMIT: {}", &*ANONYMOUS_RATE_LIMIT);
+    info!("SEARCH_DEADLINE_MS: {}", &*SEARCH_DEADLINE_MS);
+    match &*SMTP_CONFIG {
+        Some(config) => info!(
+            "SMTP: {}:{} -> {:?}, DIGEST_SCHEDULE: {}",
+            config.host, config.port, config.to, &*DIGEST_SCHEDULE
+        ),
+        None => info!("SMTP: disabled"),
+    }
+    info!(
+        "HEALTH_CHECK_SCHEDULE: {}, HEALTH_MAX_CRAWL_FAILURES: {}",
+        &*HEALTH_CHECK_SCHEDULE, &*HEALTH_MAX_CRAWL_FAILURES
+    );
+    if *SEARCH_LOG_ENABLED {
+        info!(
+            "SEARCH_LOG: enabled, TRENDING_MATCHES_SCHEDULE: {}",
+            &*TRENDING_MATCHES_SCHEDULE
+        );
+    } else {
+        info!("SEARCH_LOG: disabled");
+    }
+    info!(
+        "SENTENCE_COMPACTION_SCHEDULE: {}",
+        &*SENTENCE_COMPACTION_SCHEDULE
+    );
+    info!(
+        "CAPTION_UPDATE_SCHEDULE: {}, CAPTION_UPDATE_MIN_AGE_MONTHS: {}, CAPTION_UPDATE_SAMPLE_SIZE: {}",
+        &*CAPTION_UPDATE_SCHEDULE, &*CAPTION_UPDATE_MIN_AGE_MONTHS, &*CAPTION_UPDATE_SAMPLE_SIZE
+    );
+    info!("YOUTUBE_PROXY_URL: {} proxies", YOUTUBE_PROXY_URLS.len());
+    info!("TRANSCRIPT_MIN_DELAY_MS: {}", &*TRANSCRIPT_MIN_DELAY_MS);
+    info!("MAX_CAPTION_CHARS: {}", &*MAX_CAPTION_CHARS);
+    info!(
+        "MAINTENANCE_BATCH_SIZE: {}, MAINTENANCE_BATCH_DELAY_MS: {}",
+        &*MAINTENANCE_BATCH_SIZE, &*MAINTENANCE_BATCH_DELAY_MS
+    );
+    info!(
+        "ES_BREAKER_FAILURES: {}, ES_BREAKER_COOLDOWN_SECONDS: {}",
+        &*ES_BREAKER_FAILURES, &*ES_BREAKER_COOLDOWN_SECONDS
+    );
+    info!(
+        "YOUTUBE_REQUEST_TIMEOUT_SECONDS: {}",
+        &*YOUTUBE_REQUEST_TIMEOUT_SECONDS
+    );
+    if *THUMBNAIL_PROXY {
+        info!(
+            "THUMBNAIL_PROXY: enabled, THUMBNAIL_CACHE_DIR: {}, THUMBNAIL_CACHE_MAX_MB: {}",
+            &*THUMBNAIL_CACHE_DIR, &*THUMBNAIL_CACHE_MAX_MB
+        );
+    } else {
+        info!("THUMBNAIL_PROXY: disabled");
+    }
+    if *CAPTURE_RAW_RESPONSES {
+        info!(
+            "CAPTURE_RAW_RESPONSES: enabled, RAW_CAPTURE_DIR: {}, RAW_CAPTURE_MAX_MB: {}",
+            &*RAW_CAPTURE_DIR, &*RAW_CAPTURE_MAX_MB
+        );
+    }
+    info!("REFERRER_POLICY: {}", &*REFERRER_POLICY);
+    info!("CONTENT_SECURITY_POLICY: {}", &*CONTENT_SECURITY_POLICY);
     info!("ELASTICSEARCH_URL: {}", &*ELASTICSEARCH_URL);
     info!("BACKEND_URL: {}", &*BACKEND_URL);
     info!("FRONTEND_URL: {}", &*FRONTEND_URL);
@@ -69,7 +341,11 @@ pub fn load_environment() {
 }
 
 pub fn create_elasticsearch_client() -> Result<Elasticsearch> {
-    let es_url = &*ELASTICSEARCH_URL;
+    create_elasticsearch_client_for(&ELASTICSEARCH_URL)
+}
+
+/// Client for the cluster at `es_url`, e.g. a stub server instead of `ELASTICSEARCH_URL`
+pub fn create_elasticsearch_client_for(es_url: &str) -> Result<Elasticsearch> {
     info!("Connecting to Elasticsearch at: {es_url}");
 
     let transport =
@@ -81,20 +357,22 @@ pub fn create_elasticsearch_client() -> Result<Elasticsearch> {
 pub async fn setup_queue_scheduler(
     es_client: Elasticsearch,
     video_queue: Arc<VideoQueue>,
+    generation: Arc<ArchiveGeneration>,
 ) -> Result<JobScheduler> {
     let scheduler = JobScheduler::new().await?;
     let es_client_clone = es_client.clone();
     let video_queue_clone = video_queue.clone();
-    let craw_burst_max = CRAWL_BURST_MAX.clone();
+    let craw_burst_max = *CRAWL_BURST_MAX;
 
-    let crawl_job = Job::new_async(CRAWL_QUEUE_SCHEDULE.as_str(), move |_uuid, _l| {
+    let crawl_job = Job::new_async(CRAWL_QUEUE_SCHEDULE.cron(), move |_uuid, _l| {
         let es_client_for_job = es_client_clone.clone();
         let queue = video_queue_clone.clone();
+        let generation = generation.clone();
         Box::pin(async move {
             if queue.get_size() == 0 {
                 return;
             }
-            crawl_youtube_video(&es_client_for_job, &queue, craw_burst_max).await;
+            crawl_youtube_video(&es_client_for_job, &queue, &generation, craw_burst_max).await;
         })
     })?;
 
@@ -105,17 +383,194 @@ pub async fn setup_queue_scheduler(
     Ok(scheduler)
 }
 
+/// Flushes the in-memory API key usage counters to ES once a minute
+pub async fn setup_api_key_usage_flush(
+    scheduler: &JobScheduler,
+    es_client: Elasticsearch,
+    api_key_usage: Arc<ApiKeyUsageTracker>,
+) -> Result<()> {
+    let flush_job = Job::new_async("0 * * * * *", move |_uuid, _l| {
+        let es_client = es_client.clone();
+        let api_key_usage = api_key_usage.clone();
+        Box::pin(async move {
+            api_key_service::flush_usage(&es_client, &api_key_usage).await;
+        })
+    })?;
+
+    scheduler.add(flush_job).await?;
+    Ok(())
+}
+
+/// Sends the daily digest email. Not scheduled at all if SMTP is not configured.
+pub async fn setup_daily_digest(
+    scheduler: &JobScheduler,
+    es_client: Elasticsearch,
+    api_key_usage: Arc<ApiKeyUsageTracker>,
+) -> Result<()> {
+    let Some(smtp_config) = (*SMTP_CONFIG).clone() else {
+        info!("SMTP not configured, daily digest disabled.");
+        return Ok(());
+    };
+
+    let digest_job = Job::new_async(DIGEST_SCHEDULE.cron(), move |_uuid, _l| {
+        let es_client = es_client.clone();
+        let api_key_usage = api_key_usage.clone();
+        let smtp_config = smtp_config.clone();
+        Box::pin(async move {
+            notification_service::send_daily_digest(
+                &es_client,
+                &api_key_usage,
+                &smtp_config,
+                &APP_NAME,
+            )
+            .await;
+        })
+    })?;
+
+    scheduler.add(digest_job).await?;
+    info!("Daily digest scheduled: {}", DIGEST_SCHEDULE.cron());
+    Ok(())
+}
+
+/// Raises health alerts when monitor checks or crawls stop making progress
+pub async fn setup_health_check(
+    scheduler: &JobScheduler,
+    es_client: Elasticsearch,
+    video_queue: Arc<VideoQueue>,
+) -> Result<()> {
+    let health_job = Job::new_async(HEALTH_CHECK_SCHEDULE.cron(), move |_uuid, _l| {
+        let es_client = es_client.clone();
+        let video_queue = video_queue.clone();
+        Box::pin(async move {
+            health_service::run_health_check(&es_client, &video_queue).await;
+        })
+    })?;
+
+    scheduler.add(health_job).await?;
+    info!("Health check scheduled: {}", HEALTH_CHECK_SCHEDULE.cron());
+    Ok(())
+}
+
+/// Scores videos by the week's popular queries. Not scheduled at all if the search log is off.
+pub async fn setup_trending_matches(
+    scheduler: &JobScheduler,
+    es_client: Elasticsearch,
+    maintenance: Arc<MaintenanceThrottle>,
+) -> Result<()> {
+    if !*SEARCH_LOG_ENABLED {
+        info!("Search log disabled, trending matches disabled.");
+        return Ok(());
+    }
+
+    let trending_job = Job::new_async(TRENDING_MATCHES_SCHEDULE.cron(), move |_uuid, _l| {
+        let es_client = es_client.clone();
+        let maintenance = maintenance.clone();
+        Box::pin(async move {
+            trending_service::update_recent_match_scores(&es_client, &maintenance).await;
+        })
+    })?;
+
+    scheduler.add(trending_job).await?;
+    info!(
+        "Trending matches scheduled: {}",
+        TRENDING_MATCHES_SCHEDULE.cron()
+    );
+    Ok(())
+}
+
+/// Stitches the captions of recently crawled videos into the sentence index
+pub async fn setup_sentence_compaction(
+    scheduler: &JobScheduler,
+    es_client: Elasticsearch,
+    maintenance: Arc<MaintenanceThrottle>,
+) -> Result<()> {
+    let compaction_job = Job::new_async(SENTENCE_COMPACTION_SCHEDULE.cron(), move |_uuid, _l| {
+        let es_client = es_client.clone();
+        let maintenance = maintenance.clone();
+        Box::pin(async move {
+            // Skipped for a night while other maintenance, e.g. a full rebuild, runs
+            let run = match maintenance.try_start("sentence compaction") {
+                Ok(run) => run,
+                Err(running) => {
+                    info!("Skipping the sentence compaction during {}", running.name);
+                    return;
+                }
+            };
+            sentence_service::compact_recent(&es_client, &run).await;
+        })
+    })?;
+
+    scheduler.add(compaction_job).await?;
+    info!(
+        "Sentence compaction scheduled: {}",
+        SENTENCE_COMPACTION_SCHEDULE.cron()
+    );
+    Ok(())
+}
+
+/// Looks for videos whose auto-generated captions got a manual track since
+pub async fn setup_caption_update_sweep(
+    scheduler: &JobScheduler,
+    es_client: Elasticsearch,
+    video_queue: Arc<VideoQueue>,
+    maintenance: Arc<MaintenanceThrottle>,
+) -> Result<()> {
+    let sweep_job = Job::new_async(CAPTION_UPDATE_SCHEDULE.cron(), move |_uuid, _l| {
+        let es_client = es_client.clone();
+        let video_queue = video_queue.clone();
+        let maintenance = maintenance.clone();
+        Box::pin(async move {
+            // The sweep waits for the next run while other maintenance holds the throttle
+            let run = match maintenance.try_start("caption update sweep") {
+                Ok(run) => run,
+                Err(running) => {
+                    info!("Skipping the caption update sweep during {}", running.name);
+                    return;
+                }
+            };
+            caption_update_service::run_caption_update_sweep(&es_client, &video_queue, &run).await;
+        })
+    })?;
+
+    scheduler.add(sweep_job).await?;
+    info!(
+        "Caption update sweep scheduled: {}",
+        CAPTION_UPDATE_SCHEDULE.cron()
+    );
+    Ok(())
+}
+
 pub async fn create_app_state() -> Result<AppState> {
     let es_client = create_elasticsearch_client()?;
     let video_queue = Arc::new(VideoQueue::new());
+    let api_key_usage = Arc::new(ApiKeyUsageTracker::new());
+    let maintenance = Arc::new(MaintenanceThrottle::from_config());
+    let generation = Arc::new(ArchiveGeneration::new());
 
     create_es_index(&es_client).await;
+    migrations::migrate_on_startup(&es_client, &maintenance).await?;
+    load_opt_outs(&es_client).await;
+    monitor_tag_service::load_public_tags(&es_client).await;
+    synonym_service::load_synonyms(&es_client).await;
 
-    let scheduler = setup_queue_scheduler(es_client.clone(), video_queue.clone()).await?;
+    let scheduler =
+        setup_queue_scheduler(es_client.clone(), video_queue.clone(), generation.clone()).await?;
+    setup_api_key_usage_flush(&scheduler, es_client.clone(), api_key_usage.clone()).await?;
+    setup_daily_digest(&scheduler, es_client.clone(), api_key_usage.clone()).await?;
+    setup_health_check(&scheduler, es_client.clone(), video_queue.clone()).await?;
+    setup_trending_matches(&scheduler, es_client.clone(), maintenance.clone()).await?;
+    setup_sentence_compaction(&scheduler, es_client.clone(), maintenance.clone()).await?;
+    setup_caption_update_sweep(
+        &scheduler,
+        es_client.clone(),
+        video_queue.clone(),
+        maintenance.clone(),
+    )
+    .await?;
 
     let es_client_arc = Arc::new(es_client.clone());
 
-    setup_monitoring(es_client_arc, video_queue.clone())
+    setup_monitoring(es_client_arc, video_queue.clone(), maintenance.clone())
         .await
         .expect("Monitoring setup failed.");
 
@@ -123,6 +578,12 @@ pub async fn create_app_state() -> Result<AppState> {
         es_client,
         scheduler: Mutex::new(scheduler),
         video_queue,
+        report_limiter: RateLimiter::new(10, 60 * 60), // 10 reports per IP per hour
+        feedback_limiter: RateLimiter::new(120, 60),   // 120 feedback events per IP per minute
+        anonymous_limiter: RateLimiter::new(*ANONYMOUS_RATE_LIMIT, 60), // requests per IP per minute
+        api_key_usage,
+        generation,
+        maintenance,
     })
 }
 
@@ -136,6 +597,7 @@ pub fn create_cors() -> Result<rocket_cors::Cors> {
                 Method::Get,
                 Method::Post,
                 Method::Put,
+                Method::Patch,
                 Method::Delete,
                 Method::Options,
             ]
@@ -147,7 +609,9 @@ pub fn create_cors() -> Result<rocket_cors::Cors> {
             "Authorization",
             "Accept",
             "Content-Type",
+            "X-Api-Key",
         ]))
+        .expose_headers([GENERATION_HEADER.to_string()].into_iter().collect())
         .allow_credentials(true)
         .to_cors()
         .map_err(|e| anyhow::anyhow!("Failed to create CORS options: {}", e))?;
@@ -167,7 +631,7 @@ impl<'r> FromRequest<'r> for AdminToken {
 
         match token {
             Some(t) => {
-                if t == &*ADMIN_TOKEN {
+                if t == *ADMIN_TOKEN {
                     Outcome::Success(AdminToken(t.to_string()))
                 } else {
                     Outcome::Error((Status::Unauthorized, "Invalid token"))
@@ -177,3 +641,177 @@ impl<'r> FromRequest<'r> for AdminToken {
         }
     }
 }
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ApiAccess {
+    type Error = &'static str;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let state = match request.guard::<&State<AppState>>().await {
+            Outcome::Success(state) => state,
+            _ => return Outcome::Error((Status::InternalServerError, "Missing app state")),
+        };
+
+        match request.headers().get_one("X-Api-Key") {
+            Some(key) => {
+                match api_key_service::check_api_key(&state.es_client, &state.api_key_usage, key)
+                    .await
+                {
+                    Ok(ApiKeyCheck::Allowed(key_hash)) => {
+                        Outcome::Success(ApiAccess::Keyed(key_hash))
+                    }
+                    Ok(ApiKeyCheck::QuotaExceeded) => {
+                        Outcome::Error((Status::TooManyRequests, "API key quota exceeded"))
+                    }
+                    Ok(ApiKeyCheck::Unknown) => {
+                        Outcome::Error((Status::Unauthorized, "Invalid API key"))
+                    }
+                    Err(e) => {
+                        error!("Failed to check API key: {e:?}");
+                        Outcome::Error((Status::InternalServerError, "Failed to check API key"))
+                    }
+                }
+            }
+            None => {
+                let ip_key = request
+                    .client_ip()
+                    .map(|ip| ip.to_string())
+                    .unwrap_or_else(|| "unknown".to_string());
+                if state.anonymous_limiter.check(&ip_key) {
+                    Outcome::Success(ApiAccess::Anonymous)
+                } else {
+                    Outcome::Error((Status::TooManyRequests, "Rate limit exceeded"))
+                }
+            }
+        }
+    }
+}
+
+/// Sets `X-Archive-Generation` on search and video responses, so clients can drop the
+/// metadata they cached once videos were crawled or deleted
+pub struct GenerationHeader;
+
+#[rocket::async_trait]
+impl Fairing for GenerationHeader {
+    fn info(&self) -> Info {
+        Info {
+            name: "Archive generation header",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let path = request.uri().path();
+        if !(path.starts_with("/search") || path.starts_with("/video")) {
+            return;
+        }
+        if let Some(state) = request.rocket().state::<AppState>() {
+            response.set_header(Header::new(
+                GENERATION_HEADER,
+                state.generation.current().to_string(),
+            ));
+        }
+    }
+}
+
+/// Sets security headers on every response. The CSP only applies to HTML responses,
+/// JSON is never rendered by the browser.
+pub struct SecurityHeaders;
+
+#[rocket::async_trait]
+impl Fairing for SecurityHeaders {
+    fn info(&self) -> Info {
+        Info {
+            name: "Security headers",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, _request: &'r Request<'_>, response: &mut Response<'r>) {
+        response.set_header(Header::new("X-Content-Type-Options", "nosniff"));
+        response.set_header(Header::new("X-Frame-Options", "DENY"));
+        response.set_header(Header::new("Referrer-Policy", REFERRER_POLICY.as_str()));
+
+        if response.content_type().is_some_and(|c| c.is_html()) {
+            response.set_header(Header::new(
+                "Content-Security-Policy",
+                CONTENT_SECURITY_POLICY.as_str(),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::http::ContentType;
+    use rocket::local::blocking::Client;
+    use rocket::response::content::RawHtml;
+    use rocket::serde::json::Json;
+    use rocket::{get, routes};
+
+    #[get("/json")]
+    fn json_response() -> Json<Vec<u32>> {
+        Json(vec![1, 2, 3])
+    }
+
+    #[get("/page")]
+    fn html_response() -> RawHtml<&'static str> {
+        RawHtml("<!doctype html><title>Archive</title>")
+    }
+
+    fn client() -> Client {
+        let rocket = rocket::build()
+            .attach(SecurityHeaders)
+            .mount("/", routes![json_response, html_response]);
+        Client::tracked(rocket).expect("valid rocket instance")
+    }
+
+    #[test]
+    fn api_responses_get_the_security_headers_without_csp() {
+        let client = client();
+        let response = client.get("/json").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+        let headers = response.headers();
+        assert_eq!(headers.get_one("X-Content-Type-Options"), Some("nosniff"));
+        assert_eq!(headers.get_one("X-Frame-Options"), Some("DENY"));
+        assert_eq!(
+            headers.get_one("Referrer-Policy"),
+            Some(REFERRER_POLICY.as_str())
+        );
+        assert_eq!(headers.get_one("Content-Security-Policy"), None);
+    }
+
+    #[test]
+    fn html_responses_get_the_csp() {
+        let client = client();
+        let response = client.get("/page").dispatch();
+        assert_eq!(response.content_type(), Some(ContentType::HTML));
+
+        let headers = response.headers();
+        assert_eq!(headers.get_one("X-Content-Type-Options"), Some("nosniff"));
+        assert_eq!(
+            headers.get_one("Content-Security-Policy"),
+            Some(CONTENT_SECURITY_POLICY.as_str())
+        );
+    }
+
+    #[test]
+    fn error_responses_get_the_security_headers() {
+        let client = client();
+        let response = client.get("/missing").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(response.headers().get_one("X-Frame-Options"), Some("DENY"));
+    }
+
+    #[test]
+    fn default_csp_allows_only_self_and_youtube() {
+        assert!(DEFAULT_CSP.starts_with("default-src 'self';"));
+        assert!(DEFAULT_CSP
+            .contains("frame-src https://www.youtube.com https://www.youtube-nocookie.com;"));
+        assert!(DEFAULT_CSP.contains("img-src 'self' data: https://i.ytimg.com"));
+        assert!(DEFAULT_CSP.contains("frame-ancestors 'none'"));
+    }
+}
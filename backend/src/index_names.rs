@@ -0,0 +1,91 @@
+//! Names of the Elasticsearch indices, all prefixed with INDEX_PREFIX so staging and
+//! production can share a cluster. The base names are private to this module: code
+//! addresses an index only through `config::INDEX_NAMES`, never through a string literal.
+//!
+//! Unlike the Elasticsearch client and the other per-server state, the names are not
+//! part of `AppState`: they are a process-global `lazy_static` like the rest of the
+//! environment configuration in `config`, because nearly every service function, the
+//! CLI maintenance tasks and the migrations address indices without a state at hand.
+//! The prefix is therefore fixed for the lifetime of the process, and the names are
+//! leaked `&'static str`s so they can be used wherever a string literal could be.
+
+/// Unprefixed names, also used in exports so they can be imported into any environment
+mod base {
+    pub(super) const VIDEOS: &str = "youtube_videos";
+    pub(super) const CAPTIONS: &str = "youtube_captions";
+    pub(super) const SENTENCES: &str = "youtube_sentences";
+    pub(super) const MONITORED_CHANNELS: &str = "monitored_channels";
+    pub(super) const MONITORED_PLAYLISTS: &str = "monitored_playlists";
+    pub(super) const CAPTION_REPORTS: &str = "caption_reports";
+    pub(super) const API_KEYS: &str = "api_keys";
+    pub(super) const ALERTS: &str = "alerts";
+    pub(super) const SEARCH_FEEDBACK: &str = "search_feedback";
+    pub(super) const SEARCH_LOG: &str = "search_log";
+    pub(super) const INDEXING_LOG: &str = "indexing_log";
+    pub(super) const OPTOUT_CHANNELS: &str = "optout_channels";
+    pub(super) const MONITOR_TAGS: &str = "monitor_tags";
+    pub(super) const SYNONYMS: &str = "search_synonyms";
+    pub(super) const SCHEMA_META: &str = "schema_meta";
+}
+
+/// Index names of one environment. Each name is an alias of a versioned index, see
+/// `elasticsearch_service::create_es_index`.
+#[derive(Debug)]
+pub struct IndexNames {
+    pub prefix: &'static str,
+    pub videos: &'static str,
+    pub captions: &'static str,
+    pub sentences: &'static str,
+    pub monitored_channels: &'static str,
+    pub monitored_playlists: &'static str,
+    pub caption_reports: &'static str,
+    pub api_keys: &'static str,
+    pub alerts: &'static str,
+    pub search_feedback: &'static str,
+    pub search_log: &'static str,
+    pub indexing_log: &'static str,
+    pub optout_channels: &'static str,
+    pub monitor_tags: &'static str,
+    pub synonyms: &'static str,
+    pub schema_meta: &'static str,
+}
+
+/// Names live as long as the process, they are built once from the environment
+fn prefixed(prefix: &str, name: &str) -> &'static str {
+    Box::leak(format!("{prefix}{name}").into_boxed_str())
+}
+
+impl IndexNames {
+    pub fn with_prefix(prefix: &str) -> Self {
+        IndexNames {
+            prefix: prefixed(prefix, ""),
+            videos: prefixed(prefix, base::VIDEOS),
+            captions: prefixed(prefix, base::CAPTIONS),
+            sentences: prefixed(prefix, base::SENTENCES),
+            monitored_channels: prefixed(prefix, base::MONITORED_CHANNELS),
+            monitored_playlists: prefixed(prefix, base::MONITORED_PLAYLISTS),
+            caption_reports: prefixed(prefix, base::CAPTION_REPORTS),
+            api_keys: prefixed(prefix, base::API_KEYS),
+            alerts: prefixed(prefix, base::ALERTS),
+            search_feedback: prefixed(prefix, base::SEARCH_FEEDBACK),
+            search_log: prefixed(prefix, base::SEARCH_LOG),
+            indexing_log: prefixed(prefix, base::INDEXING_LOG),
+            optout_channels: prefixed(prefix, base::OPTOUT_CHANNELS),
+            monitor_tags: prefixed(prefix, base::MONITOR_TAGS),
+            synonyms: prefixed(prefix, base::SYNONYMS),
+            schema_meta: prefixed(prefix, base::SCHEMA_META),
+        }
+    }
+
+    /// Indices written by `maintenance export` and accepted by `import`, as pairs of
+    /// unprefixed and prefixed name
+    pub fn exported(&self) -> [(&'static str, &'static str); 5] {
+        [
+            (base::VIDEOS, self.videos),
+            (base::CAPTIONS, self.captions),
+            (base::MONITORED_CHANNELS, self.monitored_channels),
+            (base::MONITORED_PLAYLISTS, self.monitored_playlists),
+            (base::CAPTION_REPORTS, self.caption_reports),
+        ]
+    }
+}
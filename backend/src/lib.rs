@@ -0,0 +1,289 @@
+//! The backend server as a library, so the binary and the integration tests build the
+//! same Rocket instance.
+
+use elasticsearch::Elasticsearch;
+use rocket::{routes, Build, Rocket, Route};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio_cron_scheduler::JobScheduler;
+
+pub mod api;
+pub mod cli;
+pub mod config;
+pub mod index_names;
+pub mod migrations;
+pub mod models;
+pub mod schedule;
+pub mod services;
+#[cfg(test)]
+mod testsupport;
+pub mod utils;
+
+use crate::api::{
+    activate_channel, activate_playlist, add_channel, add_channels_bulk, add_playlist,
+    add_playlists_bulk, check_channel, check_playlist, check_tag, deactivate_channel,
+    deactivate_playlist, get_channels, get_playlists, get_tags, get_videos_metadata, hide_channel,
+    patch_channel, patch_playlist, put_tag, remove_channel, remove_playlist, unhide_channel,
+};
+use api::{
+    ack_alert, add_opt_out, admin_enqueue, admin_login, admin_lookup, admin_stats, clear_queue,
+    create_api_key, delete_video_endpoint, get_alerts, get_api_keys, get_ctr_stats,
+    get_current_maintenance, get_health, get_indexing_log, get_opt_outs, get_oversized_captions,
+    get_proxies, get_public_channel_videos, get_public_channels, get_public_config, get_queue,
+    get_raw_captures, get_reports, get_settings, get_synonyms, get_task, get_trending_matches,
+    get_video_detail, get_video_diff, get_video_history, get_video_languages, get_video_metadata,
+    get_video_thumbnail, get_video_transcript, get_videos, list_videos, phrase_frequency,
+    post_videos_metadata, preview_index, preview_relevance, put_settings, put_synonyms,
+    random_quote, rebuild_sentences, recrawl_video, remove_opt_out, remove_queue_item,
+    report_caption, retry_failed_queue_items, run_crawl, search_captions, search_cursor,
+    search_feedback, test_notification,
+};
+use config::{create_app_state, create_cors, GenerationHeader, SecurityHeaders};
+use services::api_key_service::ApiKeyUsageTracker;
+use services::archive_generation::ArchiveGeneration;
+use services::crawler::VideoQueue;
+use services::maintenance_throttle::MaintenanceThrottle;
+use services::rate_limiter::RateLimiter;
+
+pub struct AppState {
+    pub es_client: Elasticsearch,
+    pub scheduler: Mutex<JobScheduler>,
+    pub video_queue: Arc<VideoQueue>,
+    pub report_limiter: RateLimiter,
+    pub feedback_limiter: RateLimiter,
+    pub anonymous_limiter: RateLimiter,
+    pub api_key_usage: Arc<ApiKeyUsageTracker>,
+    pub generation: Arc<ArchiveGeneration>,
+    pub maintenance: Arc<MaintenanceThrottle>,
+}
+
+/// The server with all routes mounted, its state is set up from the environment
+pub async fn rocket() -> Rocket<Build> {
+    let app_state = create_app_state()
+        .await
+        .expect("Failed to create application state");
+
+    let cors = create_cors().expect("Failed to create CORS configuration");
+
+    mount_handlers(rocket::build().manage(app_state))
+        .attach(cors)
+        .attach(SecurityHeaders)
+        .attach(GenerationHeader)
+}
+
+/// Routes of the given handlers, each paired with the path of its handler function
+macro_rules! handlers {
+    ($($handler:ident),* $(,)?) => {
+        vec![$((std::any::type_name::<$handler>(), routes![$handler])),*]
+    };
+}
+
+/// A mount point with the type path and routes of each of its handlers
+type Mount = (&'static str, Vec<(&'static str, Vec<Route>)>);
+
+/// Every mount point with its handlers. The handler paths let the tests check that
+/// only handlers from `api` are mounted.
+fn mounts() -> Vec<Mount> {
+    vec![
+        (
+            "/search",
+            handlers![
+                search_captions,
+                search_cursor,
+                phrase_frequency,
+                random_quote,
+                report_caption,
+                search_feedback
+            ],
+        ),
+        (
+            "/video",
+            handlers![
+                list_videos,
+                get_trending_matches,
+                get_public_channels,
+                get_public_channel_videos,
+                get_video_metadata,
+                get_videos_metadata,
+                post_videos_metadata,
+                get_video_languages,
+                get_video_transcript,
+                get_video_thumbnail
+            ],
+        ),
+        ("/config", handlers![get_public_config]),
+        ("/health", handlers![get_health]),
+        (
+            "/monitor",
+            handlers![
+                add_channel,
+                add_channels_bulk,
+                get_channels,
+                patch_channel,
+                remove_channel,
+                activate_channel,
+                deactivate_channel,
+                check_channel,
+                hide_channel,
+                unhide_channel,
+                add_playlist,
+                add_playlists_bulk,
+                get_playlists,
+                patch_playlist,
+                remove_playlist,
+                activate_playlist,
+                deactivate_playlist,
+                check_playlist,
+                check_tag,
+                get_tags,
+                put_tag,
+            ],
+        ),
+        (
+            "/admin",
+            handlers![
+                admin_login, // Public for login!!
+                admin_stats,
+                get_settings,
+                put_settings,
+                get_ctr_stats,
+                get_queue,
+                admin_enqueue,
+                preview_index,
+                preview_relevance,
+                get_synonyms,
+                put_synonyms,
+                run_crawl,
+                rebuild_sentences,
+                get_current_maintenance,
+                remove_queue_item,
+                retry_failed_queue_items,
+                clear_queue,
+                delete_video_endpoint,
+                get_task,
+                get_oversized_captions,
+                get_videos,
+                get_reports,
+                admin_lookup,
+                recrawl_video,
+                get_video_detail,
+                get_video_history,
+                get_video_diff,
+                get_indexing_log,
+                get_opt_outs,
+                add_opt_out,
+                remove_opt_out,
+                get_proxies,
+                get_raw_captures,
+                get_alerts,
+                ack_alert,
+                create_api_key,
+                get_api_keys,
+                test_notification,
+            ],
+        ),
+    ]
+}
+
+fn mount_handlers(mut rocket: Rocket<Build>) -> Rocket<Build> {
+    for (base, handlers) in mounts() {
+        let routes: Vec<Route> = handlers
+            .into_iter()
+            .flat_map(|(_, routes)| routes)
+            .collect();
+        rocket = rocket.mount(base, routes);
+    }
+    rocket
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::INDEX_NAMES;
+    use crate::models::RunningMaintenance;
+    use crate::testsupport::{app_state, MockElasticsearch};
+    use rocket::http::{Header, Status};
+    use rocket::local::asynchronous::{Client, LocalResponse};
+
+    const ADMIN: &str = "Bearer test-admin-token";
+
+    async fn current_maintenance(client: &Client) -> Option<RunningMaintenance> {
+        client
+            .get("/admin/maintenance/current")
+            .header(Header::new("Authorization", ADMIN))
+            .dispatch()
+            .await
+            .into_json()
+            .await
+            .expect("a JSON body")
+    }
+
+    async fn assert_busy(response: LocalResponse<'_>, running: &str) {
+        assert_eq!(response.status(), Status::Conflict);
+        let body: RunningMaintenance = response.into_json().await.expect("the running task");
+        assert_eq!(body.name, running);
+    }
+
+    #[rocket::async_test]
+    async fn heavy_endpoints_answer_409_with_the_running_task() {
+        let es = MockElasticsearch::start().await;
+        let state = app_state(es.client.clone()).await;
+        let maintenance = state.maintenance.clone();
+        let client = Client::tracked(mount_handlers(rocket::build().manage(state)))
+            .await
+            .expect("valid rocket instance");
+
+        let run = maintenance
+            .try_start("rebuild sentences")
+            .expect("a new throttle is free");
+        let deletion = client
+            .delete("/admin/video/dQw4w9WgXcQ")
+            .header(Header::new("Authorization", ADMIN));
+        let hiding = client
+            .post("/monitor/channel/UCuAXFkgsw1L7xaCfnd5JJOw/hide")
+            .header(Header::new("Authorization", ADMIN));
+        let (deleted, hidden) = tokio::join!(deletion.dispatch(), hiding.dispatch());
+        assert_busy(deleted, "rebuild sentences").await;
+        assert_busy(hidden, "rebuild sentences").await;
+
+        let current = current_maintenance(&client).await.expect("a running task");
+        assert_eq!(current.name, "rebuild sentences");
+        assert!(es
+            .bodies(
+                "POST",
+                &format!("/{}/_update/dQw4w9WgXcQ", INDEX_NAMES.videos)
+            )
+            .await
+            .is_empty());
+
+        drop(run);
+        assert_eq!(current_maintenance(&client).await, None);
+    }
+
+    #[test]
+    fn only_api_handlers_are_mounted() {
+        let api = format!("{}::api::", env!("CARGO_CRATE_NAME"));
+        let handlers: Vec<&str> = mounts()
+            .into_iter()
+            .flat_map(|(_, handlers)| handlers.into_iter().map(|(handler, _)| handler))
+            .collect();
+
+        let rocket = mount_handlers(rocket::build());
+        let mut mounted = 0;
+        for route in rocket.routes() {
+            let name = route.name.as_deref().unwrap_or_default();
+            let handler = handlers
+                .iter()
+                .find(|handler| handler.rsplit("::").next() == Some(name))
+                .unwrap_or_else(|| panic!("{} {} has no known handler", route.method, route.uri));
+            assert!(
+                handler.starts_with(&api),
+                "{} {} resolves to {handler}, outside of api",
+                route.method,
+                route.uri
+            );
+            mounted += 1;
+        }
+        assert_eq!(mounted, handlers.len());
+    }
+}
@@ -0,0 +1,570 @@
+//! Numbered mapping and data migrations. The version of the last applied migration is
+//! stored in the schema_meta index. At startup cheap migrations run on their own, the
+//! server refuses to start while an expensive one is pending: those are run by the
+//! operator with `starchive-backend migrate`.
+
+use crate::config::INDEX_NAMES;
+use crate::services::maintenance_throttle::{MaintenanceRun, MaintenanceThrottle};
+use crate::services::{maintenance_service, stemming};
+use anyhow::{Context, Result};
+use elasticsearch::indices::{IndicesGetMappingParts, IndicesPutMappingParts};
+use elasticsearch::params::Refresh;
+use elasticsearch::{CountParts, Elasticsearch, GetParts, IndexParts};
+use log::info;
+use serde_json::{json, Map, Value};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Document of the schema_meta index holding the applied version
+const SCHEMA_DOC_ID: &str = "schema";
+
+type MigrationFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationKind {
+    /// Runs at startup, takes seconds at most
+    Cheap,
+    /// Touches every document, only run by `starchive-backend migrate`
+    Expensive,
+}
+
+/// One step of the schema. Every step must be safe to run again, a run interrupted
+/// before its version was recorded is repeated.
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub kind: MigrationKind,
+    run: for<'a> fn(&'a Elasticsearch, &'a MaintenanceRun) -> MigrationFuture<'a>,
+}
+
+/// All migrations, ordered by version. Append only: a released version never changes.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "map-caption-fields",
+        kind: MigrationKind::Cheap,
+        run: map_caption_fields,
+    },
+    Migration {
+        version: 2,
+        name: "backfill-caption-segments",
+        kind: MigrationKind::Expensive,
+        run: backfill_caption_segments,
+    },
+    Migration {
+        version: 3,
+        name: "backfill-caption-upload-dates",
+        kind: MigrationKind::Expensive,
+        run: backfill_caption_upload_dates,
+    },
+    Migration {
+        version: 4,
+        name: "build-sentences",
+        kind: MigrationKind::Expensive,
+        run: build_sentences,
+    },
+    Migration {
+        version: 5,
+        name: "reanalyze-stemmed-text",
+        kind: MigrationKind::Expensive,
+        run: reanalyze_stemmed_text,
+    },
+    Migration {
+        version: 6,
+        name: "normalize-caption-text",
+        kind: MigrationKind::Expensive,
+        run: normalize_caption_text,
+    },
+    Migration {
+        version: 7,
+        name: "map-caption-channel-ids",
+        kind: MigrationKind::Cheap,
+        run: map_caption_fields,
+    },
+    Migration {
+        version: 8,
+        name: "backfill-caption-channel-ids",
+        kind: MigrationKind::Expensive,
+        run: backfill_caption_channel_ids,
+    },
+];
+
+fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/// Caption fields added after the captions index was first created. Deployments from
+/// before got them mapped dynamically on first use, those mappings are kept as they are.
+const CAPTION_FIELDS: &[(&str, &str)] = &[
+    ("language", "keyword"),
+    ("is_generated", "boolean"),
+    ("segment", "integer"),
+    ("upload_date", "long"),
+    ("channel_id", "keyword"),
+];
+
+fn map_caption_fields<'a>(
+    es_client: &'a Elasticsearch,
+    _run: &'a MaintenanceRun,
+) -> MigrationFuture<'a> {
+    Box::pin(map_caption_fields_of(es_client))
+}
+
+async fn map_caption_fields_of(es_client: &Elasticsearch) -> Result<()> {
+    for index in [INDEX_NAMES.captions, INDEX_NAMES.sentences] {
+        let response = es_client
+            .indices()
+            .get_mapping(IndicesGetMappingParts::Index(&[index]))
+            .send()
+            .await?;
+        if !response.status_code().is_success() {
+            return Err(anyhow::anyhow!(
+                "Reading the mapping of {index} failed: {}",
+                response.status_code()
+            ));
+        }
+        // Keyed by the concrete index behind the alias
+        let json_response: Value = response.json().await?;
+        let mapped: Vec<&str> = json_response
+            .as_object()
+            .into_iter()
+            .flat_map(|indices| indices.values())
+            .filter_map(|mapping| mapping["mappings"]["properties"].as_object())
+            .flat_map(|properties| properties.keys().map(String::as_str))
+            .collect();
+
+        let mut missing = Map::new();
+        for (field, field_type) in CAPTION_FIELDS {
+            if !mapped.contains(field) {
+                missing.insert(field.to_string(), json!({ "type": field_type }));
+            }
+        }
+        if missing.is_empty() {
+            continue;
+        }
+
+        let response = es_client
+            .indices()
+            .put_mapping(IndicesPutMappingParts::Index(&[index]))
+            .body(json!({ "properties": missing }))
+            .send()
+            .await?;
+        if !response.status_code().is_success() {
+            return Err(anyhow::anyhow!(
+                "Updating the mapping of {index} failed: {}",
+                response.text().await.unwrap_or_default()
+            ));
+        }
+        info!("Mapped {} new fields of {index}", missing.len());
+    }
+    Ok(())
+}
+
+fn backfill_caption_segments<'a>(
+    es_client: &'a Elasticsearch,
+    run: &'a MaintenanceRun,
+) -> MigrationFuture<'a> {
+    Box::pin(async move {
+        maintenance_service::backfill_caption_segments(es_client, run).await?;
+        Ok(())
+    })
+}
+
+fn backfill_caption_upload_dates<'a>(
+    es_client: &'a Elasticsearch,
+    run: &'a MaintenanceRun,
+) -> MigrationFuture<'a> {
+    Box::pin(async move {
+        maintenance_service::backfill_caption_upload_dates(es_client, run).await?;
+        Ok(())
+    })
+}
+
+fn backfill_caption_channel_ids<'a>(
+    es_client: &'a Elasticsearch,
+    run: &'a MaintenanceRun,
+) -> MigrationFuture<'a> {
+    Box::pin(async move {
+        maintenance_service::backfill_caption_channel_ids(es_client, run).await?;
+        Ok(())
+    })
+}
+
+/// Sentences of videos crawled before the sentence index existed
+fn build_sentences<'a>(
+    es_client: &'a Elasticsearch,
+    run: &'a MaintenanceRun,
+) -> MigrationFuture<'a> {
+    Box::pin(async move {
+        maintenance_service::rebuild_sentences(
+            es_client,
+            |done, total| {
+                if done % 100 == 0 || done == total {
+                    info!("[{done}/{total}] videos stitched into sentences");
+                }
+            },
+            Some(run),
+        )
+        .await?;
+        Ok(())
+    })
+}
+
+/// Stemmed subfields per caption language, replacing the single English one
+fn reanalyze_stemmed_text<'a>(
+    es_client: &'a Elasticsearch,
+    run: &'a MaintenanceRun,
+) -> MigrationFuture<'a> {
+    Box::pin(async move {
+        stemming::reanalyze(es_client, run).await?;
+        Ok(())
+    })
+}
+
+/// Captions indexed with the text as fetched, before it was normalized at index time
+fn normalize_caption_text<'a>(
+    es_client: &'a Elasticsearch,
+    run: &'a MaintenanceRun,
+) -> MigrationFuture<'a> {
+    Box::pin(async move {
+        maintenance_service::normalize_caption_texts(es_client, run).await?;
+        Ok(())
+    })
+}
+
+/// The recorded version, None if the cluster predates migrations or is new
+async fn recorded_version(es_client: &Elasticsearch) -> Result<Option<u32>> {
+    let response = es_client
+        .get(GetParts::IndexId(INDEX_NAMES.schema_meta, SCHEMA_DOC_ID))
+        .send()
+        .await
+        .context("Reading the schema version failed")?;
+    if response.status_code().as_u16() == 404 {
+        return Ok(None);
+    }
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Reading the schema version failed: {}",
+            response.status_code()
+        ));
+    }
+
+    let json_response: Value = response.json().await?;
+    Ok(json_response["_source"]["version"]
+        .as_u64()
+        .map(|version| version as u32))
+}
+
+async fn record_version(es_client: &Elasticsearch, version: u32) -> Result<()> {
+    let response = es_client
+        .index(IndexParts::IndexId(INDEX_NAMES.schema_meta, SCHEMA_DOC_ID))
+        .refresh(Refresh::True)
+        .body(json!({
+            "version": version,
+            "updated_at": chrono::Utc::now().timestamp(),
+        }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Recording schema version {version} failed: {}",
+            response.status_code()
+        ));
+    }
+    Ok(())
+}
+
+async fn caption_count(es_client: &Elasticsearch) -> Result<i64> {
+    let response = es_client
+        .count(CountParts::Index(&[INDEX_NAMES.captions]))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Counting captions failed: {}",
+            response.status_code()
+        ));
+    }
+    let json_response: Value = response.json().await?;
+    Ok(json_response["count"].as_i64().unwrap_or(0))
+}
+
+/// The applied version. A cluster without captions and without a recorded version was
+/// just created with the current mappings, it starts at the latest version.
+async fn current_version(es_client: &Elasticsearch) -> Result<u32> {
+    if let Some(version) = recorded_version(es_client).await? {
+        return Ok(version);
+    }
+    if caption_count(es_client).await? == 0 {
+        let version = latest_version();
+        record_version(es_client, version).await?;
+        info!("New installation, schema version set to {version}");
+        return Ok(version);
+    }
+    Ok(0)
+}
+
+/// Runs a migration under the maintenance throttle, so it is paced like other heavy
+/// maintenance and shows up as the running task
+async fn apply(
+    es_client: &Elasticsearch,
+    migration: &Migration,
+    maintenance: &Arc<MaintenanceThrottle>,
+) -> Result<()> {
+    info!(
+        "Running migration {} ({})",
+        migration.version, migration.name
+    );
+    let run = maintenance
+        .try_start(&format!("migration {}", migration.name))
+        .map_err(|running| {
+            anyhow::anyhow!(
+                "Migration {} can't run during {}",
+                migration.version,
+                running.name
+            )
+        })?;
+    (migration.run)(es_client, &run).await.with_context(|| {
+        format!(
+            "Migration {} ({}) failed",
+            migration.version, migration.name
+        )
+    })?;
+    record_version(es_client, migration.version).await?;
+    info!("Schema version is now {}", migration.version);
+    Ok(())
+}
+
+/// Migrations newer than `version`, in order. Collected up front so no iterator over
+/// `MIGRATIONS` is held across the awaits of the runners.
+fn pending_migrations(version: u32) -> Vec<&'static Migration> {
+    MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version > version)
+        .collect()
+}
+
+/// Startup check: runs the pending cheap migrations up to the first expensive one, fails
+/// if any migration is still pending after that
+pub async fn migrate_on_startup(
+    es_client: &Elasticsearch,
+    maintenance: &Arc<MaintenanceThrottle>,
+) -> Result<()> {
+    let pending = pending_migrations(current_version(es_client).await?);
+    let cheap = pending
+        .iter()
+        .take_while(|migration| migration.kind == MigrationKind::Cheap)
+        .count();
+    for migration in &pending[..cheap] {
+        apply(es_client, migration, maintenance).await?;
+    }
+
+    let blocked: Vec<String> = pending[cheap..]
+        .iter()
+        .map(|migration| format!("{} ({})", migration.version, migration.name))
+        .collect();
+    if !blocked.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Pending migrations need to be run first, stop the server and run \
+             `starchive-backend migrate`: {}",
+            blocked.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// `starchive-backend migrate`: runs every pending migration in order. Returns the
+/// versions that were applied.
+pub async fn migrate(
+    es_client: &Elasticsearch,
+    maintenance: &Arc<MaintenanceThrottle>,
+) -> Result<Vec<u32>> {
+    let mut applied = Vec::new();
+    for migration in pending_migrations(current_version(es_client).await?) {
+        apply(es_client, migration, maintenance).await?;
+        applied.push(migration.version);
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsupport::{unpaced_maintenance, MockElasticsearch};
+
+    fn schema_doc() -> String {
+        format!("/{}/_doc/{SCHEMA_DOC_ID}", INDEX_NAMES.schema_meta)
+    }
+
+    /// A cluster with `captions` captions and the given recorded version
+    async fn cluster(recorded: Option<u32>, captions: i64) -> MockElasticsearch {
+        let es = MockElasticsearch::start().await;
+        match recorded {
+            Some(version) => {
+                es.stub(
+                    "GET",
+                    &schema_doc(),
+                    json!({ "found": true, "_source": { "version": version } }),
+                )
+                .await
+            }
+            None => {
+                es.stub_status("GET", &schema_doc(), 404, json!({ "found": false }))
+                    .await
+            }
+        }
+        es.stub(
+            "GET",
+            &format!("/{}/_count", INDEX_NAMES.captions),
+            json!({ "count": captions }),
+        )
+        .await;
+        for method in ["PUT", "POST"] {
+            es.stub(method, &schema_doc(), json!({ "result": "updated" }))
+                .await;
+        }
+        es
+    }
+
+    /// Versions written to schema_meta, oldest first
+    async fn recorded_versions(es: &MockElasticsearch) -> Vec<u64> {
+        let mut bodies = es.bodies("PUT", &schema_doc()).await;
+        bodies.extend(es.bodies("POST", &schema_doc()).await);
+        bodies
+            .iter()
+            .filter_map(|body| body["version"].as_u64())
+            .collect()
+    }
+
+    /// Caption and sentence mappings with every field but `unmapped`
+    async fn stub_mappings(es: &MockElasticsearch, unmapped: &[&str]) {
+        let properties: Map<String, Value> = CAPTION_FIELDS
+            .iter()
+            .filter(|(field, _)| !unmapped.contains(field))
+            .map(|(field, field_type)| (field.to_string(), json!({ "type": field_type })))
+            .collect();
+        for index in [INDEX_NAMES.captions, INDEX_NAMES.sentences] {
+            es.stub(
+                "GET",
+                &format!("/{index}/_mapping"),
+                json!({ format!("{index}_v1"): { "mappings": { "properties": properties } } }),
+            )
+            .await;
+            es.stub(
+                "PUT",
+                &format!("/{index}/_mapping"),
+                json!({ "acknowledged": true }),
+            )
+            .await;
+        }
+    }
+
+    #[test]
+    fn migrations_are_numbered_in_order() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, index as u32 + 1, "{}", migration.name);
+        }
+        assert_eq!(latest_version(), MIGRATIONS.len() as u32);
+    }
+
+    #[rocket::async_test]
+    async fn new_installations_start_at_the_latest_version() {
+        let es = cluster(None, 0).await;
+        assert_eq!(current_version(&es.client).await.unwrap(), latest_version());
+        assert_eq!(recorded_versions(&es).await, [latest_version() as u64]);
+    }
+
+    #[rocket::async_test]
+    async fn legacy_clusters_start_at_version_zero() {
+        let es = cluster(None, 4200).await;
+        assert_eq!(current_version(&es.client).await.unwrap(), 0);
+        assert!(recorded_versions(&es).await.is_empty());
+    }
+
+    #[rocket::async_test]
+    async fn the_recorded_version_wins() {
+        let es = cluster(Some(3), 0).await;
+        assert_eq!(current_version(&es.client).await.unwrap(), 3);
+        assert!(recorded_versions(&es).await.is_empty());
+    }
+
+    #[rocket::async_test]
+    async fn an_unreadable_version_is_an_error() {
+        let es = MockElasticsearch::start().await;
+        es.stub_status("GET", &schema_doc(), 503, json!({ "error": "unavailable" }))
+            .await;
+        assert!(current_version(&es.client).await.is_err());
+    }
+
+    #[rocket::async_test]
+    async fn startup_stops_at_the_first_expensive_migration() {
+        let es = cluster(None, 4200).await;
+        stub_mappings(&es, &[]).await;
+
+        let error = migrate_on_startup(&es.client, &unpaced_maintenance())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_eq!(recorded_versions(&es).await, [1]);
+        assert!(error.contains("starchive-backend migrate"), "{error}");
+        for migration in &MIGRATIONS[1..] {
+            let pending = format!("{} ({})", migration.version, migration.name);
+            assert!(error.contains(&pending), "{error}");
+        }
+        // Nothing was missing from the mappings
+        for index in [INDEX_NAMES.captions, INDEX_NAMES.sentences] {
+            assert!(es
+                .bodies("PUT", &format!("/{index}/_mapping"))
+                .await
+                .is_empty());
+        }
+    }
+
+    #[rocket::async_test]
+    async fn startup_runs_cheap_migrations_after_the_recorded_version() {
+        let es = cluster(Some(6), 4200).await;
+        stub_mappings(&es, &["channel_id"]).await;
+
+        let error = migrate_on_startup(&es.client, &unpaced_maintenance())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_eq!(recorded_versions(&es).await, [7]);
+        assert!(
+            error.contains("8 (backfill-caption-channel-ids)"),
+            "{error}"
+        );
+        assert!(!error.contains("7 (map-caption-channel-ids)"), "{error}");
+        for index in [INDEX_NAMES.captions, INDEX_NAMES.sentences] {
+            assert_eq!(
+                es.bodies("PUT", &format!("/{index}/_mapping")).await,
+                [json!({ "properties": { "channel_id": { "type": "keyword" } } })]
+            );
+        }
+    }
+
+    #[rocket::async_test]
+    async fn startup_passes_on_an_up_to_date_cluster() {
+        let es = cluster(Some(latest_version()), 4200).await;
+        migrate_on_startup(&es.client, &unpaced_maintenance())
+            .await
+            .unwrap();
+        assert!(recorded_versions(&es).await.is_empty());
+    }
+
+    #[rocket::async_test]
+    async fn migrations_wait_for_running_maintenance() {
+        let es = cluster(Some(6), 4200).await;
+        stub_mappings(&es, &["channel_id"]).await;
+        let maintenance = unpaced_maintenance();
+        let _run = maintenance.try_start("rebuild sentences").unwrap();
+
+        let error = migrate_on_startup(&es.client, &maintenance)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("during rebuild sentences"), "{error}");
+        assert!(recorded_versions(&es).await.is_empty());
+    }
+}
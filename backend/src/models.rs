@@ -5,107 +5,58 @@ use rocket::serde::{Deserialize, Serialize};
 use rocket::{response, Response};
 use std::io::Cursor;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AdminToken(pub String);
-
-#[derive(Serialize, Deserialize)]
-pub struct AdminLoginRequest {
-    pub token: String,
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct AdminLoginResponse {
-    pub success: bool,
-    pub message: String,
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct AdminStats {
-    pub total_videos: i64,
-    pub total_captions: i64,
-    pub last_crawl_time: Option<i64>,
-    pub active_monitors: i32,
-    pub queue_size: usize,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AdminEnqueueRequest {
-    pub url: String,
-}
+// Wire types shared with the frontend
+pub use starchive_api_types::{
+    caption_segment, AdminCrawlRunResponse, AdminCtrStats, AdminDeleteVideoResponse,
+    AdminEnqueueRequest, AdminEnqueueResponse, AdminLoginRequest, AdminLoginResponse,
+    AdminLookupResponse, AdminNotificationResponse, AdminQueueResponse, AdminSettings, AdminStats,
+    AdminTaskStatus, AdminVideoDetail, AdminVideoListResponse, ApiKey, ApiKeyCreateRequest,
+    ApiKeyCreateResponse, BatchVideoError, BatchVideoRequest, BatchVideoResponse,
+    BulkMonitorRequest, BulkMonitorResult, CaptionHit, CaptionLanguage, CaptionReportRequest,
+    CaptionTrack, CheckSource, CrawlHistoryEntry, CtrRow, CursorSearchResponse, DiscoveryMode,
+    EnqueueOrder, FeedbackAction, FieldChange, FieldError, FrequencyBucket, HealthAlert,
+    IndexPreview, IndexingLogEntry, IndexingLogResponse, LookupHit, LookupKind, MetadataSnapshot,
+    MonitorAddResponse, MonitorPurge, MonitorPurgeResponse, MonitorTag, MonitorTagUpdate,
+    MonitorUpdate, MonitoredChannelStats, MonitoredPlaylistStats, NewChannel, NewPlaylist,
+    OptOutChannel, OptOutRequest, OptOutResponse, OversizedCaptionReport, PhraseFrequencyResponse,
+    PlaylistMembership, PreviewExclusion, ProxyStatus, PublicChannel, PublicConfig, PublicFeatures,
+    QueueBatchResponse, QueueItem, RandomQuote, RankMove, RankedVideo, RelevanceConfig,
+    RelevancePreview, RelevancePreviewRequest, RunningMaintenance, SearchFeedbackRequest,
+    SearchResponse, SearchResult, ServiceHealth, SynonymSet, TagCheckResponse, TranscriptLine,
+    TrendingVideo, UploadMonth, VideoMetadata, VideoMetadataDiff, VideoReportCount,
+    VideoTranscript, CAPTION_SEGMENT_SECONDS, GENERATION_HEADER,
+};
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct AdminEnqueueResponse {
-    pub success: bool,
-    pub message: String,
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct AdminQueueResponse {
-    pub success: bool,
-    pub message: String,
-    pub items: Vec<QueueItem>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AdminVideoListResponse {
-    pub videos: Vec<VideoMetadata>,
-    pub total: i64,
-    pub page: i64,
-    pub per_page: i64,
-}
+pub struct AdminToken(pub String);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct QueueItem {
-    pub id: String,
-    pub video_id: String,
-    pub status: String,
-    pub added_at: String,
-    pub processed_at: Option<String>,
-    pub error_message: Option<String>,
-    pub playlist_id: Option<String>,
+/// Who is calling a public endpoint: an API key (by hash) or an anonymous client
+#[derive(Debug)]
+pub enum ApiAccess {
+    Keyed(String),
+    Anonymous,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Caption {
     pub video_id: String,
-    pub text: String,
+    pub text: String, // `utils::normalize_caption_text` of raw_text, what is searched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_text: Option<String>, // as fetched from YouTube, not indexed
     pub start_time: f64,
     pub end_time: f64,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SearchResponse {
-    pub results: Vec<SearchResult>,
-    pub total_videos: usize,
-    pub total_captions: usize,
-    pub page: usize,
-    pub page_size: usize,
-    pub total_pages: usize,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SearchResult {
-    pub video_id: String,
-    pub start_time: f64,
-    pub end_time: f64,
-    pub snippet_html: String,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
-pub struct VideoMetadata {
-    pub title: String,
-    pub channel_name: String,
-    pub channel_id: String,
-    pub upload_date: i64, // unix
-    pub crawl_date: i64,  // unix
-    pub duration: i64,    // in seconds
-    pub likes: i64,
-    pub views: i64,
-    pub comment_count: i64,
-    pub has_captions: bool,
-    pub tags: Vec<String>,
-    pub video_id: String,
-    pub playlists: Vec<String>,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub language: Option<String>, // language code of the track
+    #[serde(default)]
+    pub is_generated: bool, // auto-generated instead of uploaded by the creator
+    #[serde(default)]
+    pub segment: i64, // `caption_segment` of start_time, missing on captions indexed before it
+    #[serde(default)]
+    pub upload_date: Option<i64>, // of the video, unix seconds, to bucket matches over time
+    #[serde(default)]
+    pub channel_id: Option<String>, // of the video, to filter searches by channel
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -114,7 +65,19 @@ pub struct MonitoredChannel {
     pub channel_name: String,
     pub active: bool,
     pub created_at: String,
+    #[serde(default)]
+    pub created_at_ms: i64, // unix millis, missing on monitors stored before it was added
     pub videos_uploaded: i64,
+    #[serde(default)]
+    pub hidden: bool, // hides the channel's videos from public search
+    #[serde(default)]
+    pub enqueue_order: EnqueueOrder,
+    #[serde(default)]
+    pub discovery_mode: DiscoveryMode,
+    #[serde(default)]
+    pub max_duration_seconds: Option<i64>, // overrides MAX_VIDEO_DURATION_SECONDS
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -123,10 +86,27 @@ pub struct MonitoredPlaylist {
     pub playlist_name: String,
     pub active: bool,
     pub created_at: String,
+    #[serde(default)]
+    pub created_at_ms: i64, // unix millis, missing on monitors stored before it was added
     pub videos_added: i64,
+    #[serde(default)]
+    pub enqueue_order: EnqueueOrder,
+    #[serde(default)]
+    pub max_duration_seconds: Option<i64>, // overrides MAX_VIDEO_DURATION_SECONDS
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CaptionReport {
+    pub video_id: String,
+    pub start_time: f64,
+    pub query: String,
+    pub day: String,
+    pub reported_at: i64, // unix
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
     pub error: String,
     pub message: String,
@@ -142,3 +122,129 @@ impl<'r> Responder<'r, 'static> for ErrorResponse {
             .ok()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::parse_iso8601_to_millis;
+    use serde_json::json;
+
+    #[test]
+    fn legacy_queue_items_deserialize() {
+        // Stored before the millis, retries and sources existed
+        let item: QueueItem = serde_json::from_value(json!({
+            "id": "1700000000_dQw4w9WgXcQ",
+            "video_id": "dQw4w9WgXcQ",
+            "status": "pending",
+            "added_at": "2023-11-14T22:13:20+00:00",
+            "processed_at": null,
+            "error_message": null
+        }))
+        .unwrap();
+        assert_eq!(item.added_at_ms, 0);
+        assert_eq!(item.processed_at_ms, None);
+        assert_eq!(item.started_at_ms, None);
+        assert_eq!(item.retries, 0);
+        assert_eq!(item.source, None);
+    }
+
+    #[test]
+    fn queue_items_round_trip() {
+        let item = QueueItem {
+            id: "1700000000_dQw4w9WgXcQ".to_string(),
+            video_id: "dQw4w9WgXcQ".to_string(),
+            status: "completed".to_string(),
+            added_at: "2023-11-14T22:13:20+00:00".to_string(),
+            processed_at: Some("2023-11-14T22:14:00+00:00".to_string()),
+            added_at_ms: 1_700_000_000_000,
+            processed_at_ms: Some(1_700_000_040_000),
+            started_at_ms: Some(1_700_000_030_000),
+            error_message: None,
+            failure_kind: None,
+            retries: 1,
+            source: Some(CheckSource::Channel("UCaaaaaaaaaaaaaaaaaaaaaa".to_string())),
+        };
+        let json = serde_json::to_value(&item).unwrap();
+        assert_eq!(json["added_at_ms"], 1_700_000_000_000i64);
+        assert_eq!(
+            json["source"],
+            json!({ "type": "channel", "id": "UCaaaaaaaaaaaaaaaaaaaaaa" })
+        );
+        assert_eq!(serde_json::from_value::<QueueItem>(json).unwrap(), item);
+    }
+
+    #[test]
+    fn legacy_monitors_deserialize() {
+        let channel: MonitoredChannel = serde_json::from_value(json!({
+            "channel_id": "UCaaaaaaaaaaaaaaaaaaaaaa",
+            "channel_name": "Channel",
+            "active": true,
+            "created_at": "2023-11-14T23:13:20+01:00",
+            "videos_uploaded": 12
+        }))
+        .unwrap();
+        assert_eq!(channel.created_at_ms, 0);
+        assert!(!channel.hidden);
+        assert_eq!(channel.enqueue_order, EnqueueOrder::NewestFirst);
+        assert_eq!(channel.discovery_mode, DiscoveryMode::Api);
+        assert_eq!(channel.max_duration_seconds, None);
+        assert!(channel.tags.is_empty());
+
+        let playlist: MonitoredPlaylist = serde_json::from_value(json!({
+            "playlist_id": "PLaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+            "playlist_name": "Playlist",
+            "active": false,
+            "created_at": "2023-11-14T22:13:20Z",
+            "videos_added": 3
+        }))
+        .unwrap();
+        assert_eq!(playlist.created_at_ms, 0);
+        assert_eq!(playlist.enqueue_order, EnqueueOrder::NewestFirst);
+    }
+
+    #[test]
+    fn monitors_round_trip() {
+        let channel: MonitoredChannel = serde_json::from_value(json!({
+            "channel_id": "UCaaaaaaaaaaaaaaaaaaaaaa",
+            "channel_name": "Channel",
+            "active": true,
+            "created_at": "2023-11-14T22:13:20+00:00",
+            "created_at_ms": 1_700_000_000_000i64,
+            "videos_uploaded": 12,
+            "hidden": true,
+            "enqueue_order": "oldest_first",
+            "discovery_mode": "rss",
+            "max_duration_seconds": 3600,
+            "tags": ["science"]
+        }))
+        .unwrap();
+        let json = serde_json::to_value(&channel).unwrap();
+        let again: MonitoredChannel = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&again).unwrap(), json);
+        assert_eq!(again.created_at_ms, 1_700_000_000_000);
+        assert_eq!(again.discovery_mode, DiscoveryMode::Rss);
+    }
+
+    #[test]
+    fn stored_timestamps_convert_to_utc_millis() {
+        // Legacy monitors get their millis from the stored string, whatever its offset
+        for created_at in [
+            "2023-11-14T22:13:20+00:00",
+            "2023-11-14T22:13:20Z",
+            "2023-11-14T23:13:20+01:00",
+            "2023-11-14T17:13:20-05:00",
+        ] {
+            assert_eq!(
+                parse_iso8601_to_millis(created_at),
+                1_700_000_000_000,
+                "{created_at}"
+            );
+        }
+        assert_eq!(
+            parse_iso8601_to_millis("2023-11-14T22:13:20.250+00:00"),
+            1_700_000_000_250
+        );
+        assert_eq!(parse_iso8601_to_millis("yesterday"), 0);
+        assert_eq!(parse_iso8601_to_millis(""), 0);
+    }
+}
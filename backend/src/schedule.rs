@@ -0,0 +1,308 @@
+use chrono::{DateTime, Utc};
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Upcoming runs checked against the minimum interval
+const INTERVAL_SAMPLES: usize = 10;
+
+/// Schedule of a periodic job, either a cron expression with seconds
+/// (`0 */10 * * * *`) or an `every 15m` shorthand (`15m`, `every: 1h`, ...)
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    source: String,
+    cron: String,
+    schedule: cron::Schedule,
+}
+
+#[derive(Debug)]
+pub enum ScheduleError {
+    Shorthand {
+        value: String,
+        reason: String,
+    },
+    Cron {
+        value: String,
+        reason: String,
+    },
+    NeverFires {
+        value: String,
+    },
+    TooFrequent {
+        value: String,
+        interval: Duration,
+        min_interval: Duration,
+    },
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleError::Shorthand { value, reason } => {
+                write!(f, "'{value}' is not a valid interval: {reason}")
+            }
+            ScheduleError::Cron { value, reason } => write!(
+                f,
+                "'{value}' is not a valid cron expression ({reason}). \
+                 Expected 6 fields 'sec min hour day month weekday' or a shorthand like '15m'"
+            ),
+            ScheduleError::NeverFires { value } => write!(f, "'{value}' never fires"),
+            ScheduleError::TooFrequent {
+                value,
+                interval,
+                min_interval,
+            } => write!(
+                f,
+                "'{value}' fires every {}s, at most once every {}s is allowed",
+                interval.as_secs(),
+                min_interval.as_secs()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ScheduleError {}
+
+impl Schedule {
+    /// Parses a cron expression or shorthand and rejects schedules that fire more
+    /// often than `min_interval`
+    pub fn parse(value: &str, min_interval: Duration) -> Result<Self, ScheduleError> {
+        let value = value.trim();
+        let cron = match shorthand_to_cron(value) {
+            Some(result) => result?,
+            None => value.to_string(),
+        };
+        let schedule = cron::Schedule::from_str(&cron).map_err(|e| ScheduleError::Cron {
+            value: value.to_string(),
+            reason: e.to_string(),
+        })?;
+
+        let upcoming: Vec<DateTime<Utc>> = schedule.upcoming(Utc).take(INTERVAL_SAMPLES).collect();
+        if upcoming.is_empty() {
+            return Err(ScheduleError::NeverFires {
+                value: value.to_string(),
+            });
+        }
+        let shortest = upcoming
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).to_std().unwrap_or_default())
+            .min();
+        if let Some(interval) = shortest.filter(|interval| *interval < min_interval) {
+            return Err(ScheduleError::TooFrequent {
+                value: value.to_string(),
+                interval,
+                min_interval,
+            });
+        }
+
+        Ok(Schedule {
+            source: value.to_string(),
+            cron,
+            schedule,
+        })
+    }
+
+    /// Schedule from the environment variable `name`, `default` if it is unset.
+    /// Panics with a readable message if the schedule is invalid, like other required config.
+    pub fn from_env(name: &str, default: &str, min_interval: Duration) -> Self {
+        let value = env::var(name).unwrap_or_else(|_| default.to_string());
+        Schedule::parse(&value, min_interval).unwrap_or_else(|e| panic!("{name}: {e}"))
+    }
+
+    /// Cron expression of the schedule, shorthands translated
+    pub fn cron(&self) -> &str {
+        &self.cron
+    }
+
+    pub fn upcoming(&self, count: usize) -> Vec<DateTime<Utc>> {
+        self.schedule.upcoming(Utc).take(count).collect()
+    }
+
+    /// Milliseconds between the next two runs
+    pub fn interval_millis(&self) -> Option<i64> {
+        let upcoming = self.upcoming(2);
+        Some((*upcoming.get(1)? - *upcoming.first()?).num_milliseconds())
+    }
+}
+
+impl fmt::Display for Schedule {
+    /// The configured value with its cron translation and the next three runs, for the startup log
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)?;
+        if self.cron != self.source {
+            write!(f, " ({})", self.cron)?;
+        }
+        let next: Vec<String> = self
+            .upcoming(3)
+            .iter()
+            .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+            .collect();
+        write!(f, ", next runs: {}", next.join(", "))
+    }
+}
+
+/// Translates `15m`, `every 15m` or `every: 15m` into a cron expression. None if the
+/// value is no shorthand, so it is parsed as cron. Supported units are s, m, h and d,
+/// the amount has to divide the next larger unit evenly.
+fn shorthand_to_cron(value: &str) -> Option<Result<String, ScheduleError>> {
+    let interval = value
+        .strip_prefix("every")
+        .map(|rest| rest.trim_start_matches(':').trim())
+        .unwrap_or(value);
+    let unit_at = interval.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = interval.split_at(unit_at);
+    if amount.is_empty() || !matches!(unit, "s" | "m" | "h" | "d") {
+        return if interval.len() == value.len() {
+            None
+        } else {
+            Some(Err(shorthand_error(
+                value,
+                "expected an interval like 30s, 15m, 1h or 1d",
+            )))
+        };
+    }
+
+    let amount: u32 = match amount.parse() {
+        Ok(amount) if amount > 0 => amount,
+        _ => return Some(Err(shorthand_error(value, "the amount must be at least 1"))),
+    };
+    let (limit, unit_name) = match unit {
+        "s" => (60, "minute"),
+        "m" => (60, "hour"),
+        "h" => (24, "day"),
+        _ => (1, "day"),
+    };
+    if unit == "d" && amount != 1 {
+        return Some(Err(shorthand_error(
+            value,
+            "only 1d is supported, use a cron expression for longer intervals",
+        )));
+    }
+    if limit % amount != 0 {
+        return Some(Err(shorthand_error(
+            value,
+            &format!("{amount}{unit} doesn't divide a {unit_name} evenly, use a cron expression"),
+        )));
+    }
+
+    let step = |amount: u32| {
+        if amount == 1 {
+            "*".to_string()
+        } else {
+            format!("*/{amount}")
+        }
+    };
+    Some(Ok(match unit {
+        "s" => format!("{} * * * * *", step(amount)),
+        "m" => format!("0 {} * * * *", step(amount)),
+        "h" => format!("0 0 {} * * *", step(amount)),
+        _ => "0 0 0 * * *".to_string(),
+    }))
+}
+
+fn shorthand_error(value: &str, reason: &str) -> ScheduleError {
+    ScheduleError::Shorthand {
+        value: value.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn cron_of(value: &str) -> String {
+        Schedule::parse(value, Duration::ZERO)
+            .unwrap_or_else(|e| panic!("{value}: {e}"))
+            .cron()
+            .to_string()
+    }
+
+    #[test]
+    fn shorthands_translate_to_cron() {
+        for (value, expected) in [
+            ("30s", "*/30 * * * * *"),
+            ("1m", "0 * * * * *"),
+            ("15m", "0 */15 * * * *"),
+            ("1h", "0 0 * * * *"),
+            ("6h", "0 0 */6 * * *"),
+            ("1d", "0 0 0 * * *"),
+            ("every 15m", "0 */15 * * * *"),
+            ("every: 15m", "0 */15 * * * *"),
+            ("every:15m", "0 */15 * * * *"),
+            ("  every 1h  ", "0 0 * * * *"),
+        ] {
+            assert_eq!(cron_of(value), expected, "{value}");
+        }
+    }
+
+    #[test]
+    fn cron_expressions_are_kept() {
+        assert_eq!(cron_of("0 */10 * * * *"), "0 */10 * * * *");
+        assert_eq!(cron_of(" 0 0 3 * * * "), "0 0 3 * * *");
+    }
+
+    #[test]
+    fn invalid_shorthands_are_rejected() {
+        for value in ["7m", "45s", "5h", "2d", "0m", "every 15x", "every: m"] {
+            assert!(
+                matches!(
+                    Schedule::parse(value, Duration::ZERO),
+                    Err(ScheduleError::Shorthand { .. })
+                ),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_cron_expressions_are_rejected() {
+        for value in ["", "15x", "* * *", "0 0 25 * * *"] {
+            let error = Schedule::parse(value, Duration::ZERO).unwrap_err();
+            assert!(matches!(error, ScheduleError::Cron { .. }), "{value}");
+            assert!(error
+                .to_string()
+                .contains("'sec min hour day month weekday'"));
+        }
+    }
+
+    #[test]
+    fn schedules_in_the_past_never_fire() {
+        assert!(matches!(
+            Schedule::parse("0 0 0 1 1 * 2000", Duration::ZERO),
+            Err(ScheduleError::NeverFires { .. })
+        ));
+    }
+
+    #[test]
+    fn too_frequent_schedules_are_rejected() {
+        match Schedule::parse("30s", MINUTE) {
+            Err(ScheduleError::TooFrequent { interval, .. }) => {
+                assert_eq!(interval, Duration::from_secs(30))
+            }
+            other => panic!("expected TooFrequent, got {other:?}"),
+        }
+        assert!(Schedule::parse("* * * * * *", MINUTE).is_err());
+        assert!(Schedule::parse("1m", MINUTE).is_ok());
+        assert!(Schedule::parse("30s", Duration::from_secs(10)).is_ok());
+    }
+
+    #[test]
+    fn interval_and_display() {
+        let schedule = Schedule::parse("every 15m", MINUTE).unwrap();
+        assert_eq!(schedule.interval_millis(), Some(15 * 60 * 1000));
+
+        let display = schedule.to_string();
+        assert!(
+            display.starts_with("every 15m (0 */15 * * * *), next runs: "),
+            "{display}"
+        );
+        assert_eq!(display.matches(", ").count(), 3, "{display}");
+
+        let cron = Schedule::parse("0 0 3 * * *", MINUTE).unwrap().to_string();
+        assert!(cron.starts_with("0 0 3 * * *, next runs: "), "{cron}");
+    }
+}
@@ -1,20 +1,48 @@
-use crate::config::ADMIN_TOKEN;
+use crate::config::{ADMIN_TOKEN, INDEX_NAMES, MAX_CAPTION_CHARS};
 use crate::models::{
-    AdminEnqueueResponse, AdminLoginResponse, AdminQueueResponse, AdminStats,
-    AdminVideoListResponse, VideoMetadata,
+    AdminEnqueueResponse, AdminLoginResponse, AdminLookupResponse, AdminQueueResponse, AdminStats,
+    AdminTaskStatus, AdminVideoDetail, AdminVideoListResponse, CheckSource, CrawlHistoryEntry,
+    FieldChange, IndexPreview, LookupHit, LookupKind, MetadataSnapshot, MonitorPurgeResponse,
+    OversizedCaptionReport, PlaylistMembership, PreviewExclusion, TranscriptLine, VideoMetadata,
+    VideoMetadataDiff,
 };
-use crate::services::crawler::VideoQueue;
+use crate::services::archive_generation::ArchiveGeneration;
+use crate::services::crawler::{self, run_crawl_pass, CaptionFetch, PreparedMetadata, VideoQueue};
+use crate::services::es_breaker::ES_BREAKER;
+use crate::services::maintenance_throttle::MaintenanceRun;
 use crate::services::monitoring_service::{
-    get_monitored_channels_list, get_monitored_playlist_list,
+    get_monitored_channels_list, get_monitored_playlist_list, MONITORED_PlAYLISTS,
+    MONITORED_CHANNELS,
+};
+use crate::services::transcript_throttle::TRANSCRIPT_THROTTLE;
+use crate::services::{
+    indexing_log_service, maintenance_service, optout_service, sentence_service, video_service,
 };
 use crate::utils;
-use anyhow::Result;
-use elasticsearch::{DeleteByQueryParts, DeleteParts, Elasticsearch, SearchParts};
+use anyhow::{Context, Result};
+use elasticsearch::http::headers::HeaderMap;
+use elasticsearch::http::Method;
+use elasticsearch::params::{Conflicts, Refresh};
+use elasticsearch::{
+    DeleteByQueryParts, DeleteParts, Elasticsearch, GetParts, SearchParts, UpdateByQueryParts,
+    UpdateParts,
+};
 use serde_json::{json, Value};
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
+
+/// Videos listed in the oversized caption report
+const OVERSIZED_REPORT_VIDEOS: usize = 50;
+
+/// Maximum hits per group of an admin lookup
+const LOOKUP_LIMIT: usize = 10;
+
+/// How often a background caption deletion is checked for completion
+const TASK_POLL_INTERVAL: Duration = Duration::from_secs(2);
 
 pub async fn authenticate_admin(token: &str) -> Result<AdminLoginResponse> {
-    if token == &*ADMIN_TOKEN {
+    if token == *ADMIN_TOKEN {
         Ok(AdminLoginResponse {
             success: true,
             message: "Authentication successful".to_string(),
@@ -31,15 +59,36 @@ pub async fn get_admin_stats(
     es_client: &Elasticsearch,
     video_queue: &VideoQueue,
 ) -> Result<AdminStats> {
-    let total_videos = get_index_count(es_client, "youtube_videos").await;
-    let total_captions = get_index_count(es_client, "youtube_captions").await;
+    let total_videos = get_index_count(es_client, INDEX_NAMES.videos).await;
+    let total_captions = get_index_count(es_client, INDEX_NAMES.captions).await;
     let last_crawl_time = get_last_crawl_time(es_client).await;
+    let (total_duration_seconds, videos_missing_duration) = get_duration_totals(es_client).await;
+    let videos_skipped_too_long = indexing_log_service::count_videos_with_note(
+        es_client,
+        indexing_log_service::NOTE_SKIPPED_TOO_LONG,
+    )
+    .await
+    .unwrap_or_else(|e| {
+        log::error!("Failed to count videos skipped for their duration: {e:?}");
+        0
+    });
+
+    let transcripts_upgraded = indexing_log_service::count_videos_with_note(
+        es_client,
+        indexing_log_service::NOTE_TRANSCRIPT_UPGRADED,
+    )
+    .await
+    .unwrap_or_else(|e| {
+        log::error!("Failed to count videos with upgraded transcripts: {e:?}");
+        0
+    });
 
     let channels = get_monitored_channels_list(es_client).await;
     let playlists = get_monitored_playlist_list(es_client).await;
     let active_monitors = channels.iter().filter(|c| c.active).count() as i32
         + playlists.iter().filter(|c| c.active).count() as i32;
     let queue_size = video_queue.get_size();
+    let crawl_running = video_queue.is_crawl_running();
 
     Ok(AdminStats {
         total_videos,
@@ -47,25 +96,74 @@ pub async fn get_admin_stats(
         last_crawl_time,
         active_monitors,
         queue_size,
+        crawl_running,
+        captions_paused_until: TRANSCRIPT_THROTTLE.cooldown_until(),
+        total_duration_hours: total_duration_seconds / 3600.0,
+        videos_missing_duration,
+        videos_skipped_too_long,
+        elasticsearch_circuit: ES_BREAKER.state().name().to_string(),
+        transcripts_upgraded,
     })
 }
 
+/// Starts a crawl pass in the background. Returns the number of queue items the pass
+/// will attempt, or None if a pass is already running.
+pub fn start_crawl_pass(
+    es_client: &Elasticsearch,
+    video_queue: &Arc<VideoQueue>,
+    generation: &Arc<ArchiveGeneration>,
+    max: i32,
+) -> Option<usize> {
+    let crawl = video_queue.try_begin_crawl()?;
+
+    let attempting = video_queue.get_size().min(max.max(0) as usize);
+
+    let es_client = es_client.clone();
+    let video_queue = video_queue.clone();
+    let generation = generation.clone();
+    tokio::spawn(async move {
+        let _crawl = crawl;
+        run_crawl_pass(&es_client, &video_queue, &generation, max).await;
+    });
+
+    Some(attempting)
+}
+
+/// Rebuilds the whole sentence index in the background, holding the maintenance `run`
+/// until it finished
+pub fn start_sentence_rebuild(es_client: &Elasticsearch, run: MaintenanceRun) {
+    let es_client = es_client.clone();
+    tokio::spawn(async move {
+        let progress = |done: usize, total: usize| run.progress(done as i64, total as i64);
+        if let Err(e) =
+            maintenance_service::rebuild_sentences(&es_client, progress, Some(&run)).await
+        {
+            log::error!("Sentence rebuild failed: {e:?}");
+        }
+    });
+}
+
 pub async fn get_admin_queue(video_queue: &Arc<VideoQueue>) -> Result<AdminQueueResponse> {
-    let items = video_queue.get_all_items();
+    let mut items = video_queue.get_all_items();
+    items.sort_by_key(|item| item.added_at_ms);
 
-    Ok(AdminQueueResponse {
-        success: true,
-        message: format!("Retrieved {} queue items", items.len()),
-        items,
-    })
+    Ok(AdminQueueResponse::single_page(items))
 }
 
 pub async fn enqueue_video(
     video_queue: &Arc<VideoQueue>,
     url: &str,
 ) -> Result<AdminEnqueueResponse> {
-    let video_id = utils::extract_youtube_video_id(url)
-        .ok_or_else(|| anyhow::anyhow!("Invalid YouTube URL"))?;
+    let video_id = utils::extract_youtube_video_id(url)?;
+
+    // The crawl worker checks again, this only spares the admin a silent skip
+    if let Some(channel_id) = crawler::fetch_video_channel_id(&video_id).await {
+        if optout_service::is_opted_out(&channel_id).await {
+            return Err(anyhow::anyhow!(
+                "Video {video_id} belongs to channel {channel_id}, which opted out"
+            ));
+        }
+    }
 
     video_queue.add_video(video_id.clone());
 
@@ -75,20 +173,137 @@ pub async fn enqueue_video(
     })
 }
 
+/// Captions included in a preview, the rest is only counted
+const PREVIEW_CAPTIONS: usize = 20;
+
+/// Runs the indexing pipeline for a single video without writing anything: what would be
+/// fetched, which filters would exclude it and which caption track would be indexed.
+/// Captions are only fetched if no filter excludes the video.
+pub async fn preview_video(es_client: &Elasticsearch, video_id: &str) -> IndexPreview {
+    let prepared = crawler::prepare_video_metadata(es_client, video_id, None).await;
+    preview_prepared(es_client, video_id, prepared).await
+}
+
+/// The preview of a video whose metadata is already prepared
+async fn preview_prepared(
+    es_client: &Elasticsearch,
+    video_id: &str,
+    prepared: PreparedMetadata,
+) -> IndexPreview {
+    let mut exclusions = Vec::new();
+    if prepared.opted_out {
+        exclusions.push(PreviewExclusion {
+            filter: "opt_out".to_string(),
+            reason: format!("Channel {} opted out", prepared.metadata.channel_id),
+            monitor: None,
+        });
+    }
+    if let Some((max_duration, monitor)) = &prepared.too_long {
+        exclusions.push(PreviewExclusion {
+            filter: "max_duration".to_string(),
+            reason: format!(
+                "{}s is longer than the maximum of {max_duration}s",
+                prepared.metadata.duration
+            ),
+            monitor: monitor.clone(),
+        });
+    }
+
+    let mut preview = IndexPreview {
+        video_id: video_id.to_string(),
+        metadata: None,
+        exclusions,
+        caption_tracks: Vec::new(),
+        selected_track: None,
+        caption_count: 0,
+        captions: Vec::new(),
+        captions_deferred: false,
+    };
+    if preview.exclusions.is_empty() {
+        match crawler::fetch_video_captions(es_client, video_id).await {
+            CaptionFetch::Fetched { tracks, transcript } => {
+                preview.caption_tracks = tracks;
+                if let Some(transcript) = transcript {
+                    preview.caption_count = transcript.captions.len();
+                    preview.captions = transcript
+                        .captions
+                        .into_iter()
+                        .take(PREVIEW_CAPTIONS)
+                        .map(|caption| TranscriptLine {
+                            text: caption.text,
+                            start_time: caption.start_time,
+                            end_time: caption.end_time,
+                        })
+                        .collect();
+                    preview.selected_track = Some(transcript.track);
+                }
+            }
+            CaptionFetch::Failed { tracks } => preview.caption_tracks = tracks,
+            CaptionFetch::Deferred => preview.captions_deferred = true,
+        }
+    }
+    preview.metadata = prepared.fetched.then_some(prepared.metadata);
+    preview
+}
+
 pub async fn remove_from_queue(video_queue: &Arc<VideoQueue>, id: &str) -> Result<()> {
     video_queue.remove_item(id);
     Ok(())
 }
 
-pub async fn delete_video(es_client: &Elasticsearch, video_id: &str) -> Result<()> {
-    let delete_video_response = es_client
-        .delete(DeleteParts::IndexId("youtube_videos", video_id))
+/// Resets failed queue items to pending, only those that failed with `failure_kind` if
+/// given. Returns their count.
+pub fn retry_failed_queue_items(
+    video_queue: &Arc<VideoQueue>,
+    failure_kind: Option<&str>,
+) -> usize {
+    let retried = video_queue.retry_failed(failure_kind);
+    log::info!(
+        "Reset {retried} failed queue items{} to pending",
+        failure_kind
+            .map(|kind| format!(" ({kind})"))
+            .unwrap_or_default()
+    );
+    retried
+}
+
+/// Removes the queue items with `status`, only "failed" ones may be cleared: completed
+/// items leave the queue on their own. Returns their count.
+pub fn clear_queue(video_queue: &Arc<VideoQueue>, status: &str) -> Result<usize> {
+    if status != "failed" {
+        return Err(anyhow::anyhow!(
+            "Queue items that are {status} can't be cleared"
+        ));
+    }
+    let cleared = video_queue.clear_status(status);
+    log::info!("Cleared {cleared} {status} queue items");
+    Ok(cleared)
+}
+
+/// Hides the video from all listings right away and starts deleting its captions in
+/// the background, at the pace of the maintenance `run` which is held until then. The
+/// metadata document is removed once that task has finished. Returns the Elasticsearch
+/// task id.
+pub async fn delete_video(
+    es_client: &Elasticsearch,
+    generation: &ArchiveGeneration,
+    video_id: &str,
+    run: MaintenanceRun,
+) -> Result<String> {
+    let flag_response = es_client
+        .update(UpdateParts::IndexId(INDEX_NAMES.videos, video_id))
+        .refresh(Refresh::True)
+        .body(json!({ "doc": { "deleting": true } }))
         .send()
         .await?;
 
-    if !delete_video_response.status_code().is_success() {
-        return Err(anyhow::anyhow!("Failed to delete video metadata"));
+    if !flag_response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Failed to flag video {video_id} for deletion: {}",
+            flag_response.status_code()
+        ));
     }
+    generation.bump();
 
     let delete_captions_body = json!({
         "query": {
@@ -99,16 +314,469 @@ pub async fn delete_video(es_client: &Elasticsearch, video_id: &str) -> Result<(
     });
 
     let delete_captions_response = es_client
-        .delete_by_query(DeleteByQueryParts::Index(&["youtube_captions"]))
+        .delete_by_query(DeleteByQueryParts::Index(&[INDEX_NAMES.captions]))
+        .wait_for_completion(false)
+        .conflicts(Conflicts::Proceed)
+        .scroll_size(run.throttle().scroll_size())
+        .requests_per_second(run.throttle().requests_per_second())
         .body(delete_captions_body)
         .send()
         .await?;
 
     if !delete_captions_response.status_code().is_success() {
+        set_video_deleting(es_client, video_id, false).await;
         return Err(anyhow::anyhow!("Failed to delete video captions"));
     }
 
-    Ok(())
+    let json_response: Value = delete_captions_response.json().await?;
+    let task_id = json_response["task"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Caption deletion returned no task id"))?
+        .to_string();
+
+    let es_client = es_client.clone();
+    let video_id = video_id.to_string();
+    let watched_task = task_id.clone();
+    tokio::spawn(async move {
+        finish_video_deletion(&es_client, &video_id, &watched_task, &run).await;
+    });
+
+    Ok(task_id)
+}
+
+/// Polls an Elasticsearch task until it has completed, reporting its progress to the
+/// maintenance `run`. Fails if the task failed or Elasticsearch forgot it.
+async fn wait_for_task(
+    es_client: &Elasticsearch,
+    task_id: &str,
+    run: Option<&MaintenanceRun>,
+) -> Result<AdminTaskStatus> {
+    loop {
+        tokio::time::sleep(TASK_POLL_INTERVAL).await;
+
+        let status = get_task_status(es_client, task_id).await;
+        if let (Some(run), Ok(Some(status))) = (run, &status) {
+            run.progress(status.deleted, status.total);
+        }
+        match status {
+            Ok(Some(status)) if status.completed => {
+                return match status.error {
+                    Some(e) => Err(anyhow::anyhow!(e)),
+                    None => Ok(status),
+                };
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => return Err(anyhow::anyhow!("Task {task_id} disappeared")),
+            Err(e) => log::warn!("Failed to check task {task_id}: {e:?}"),
+        }
+    }
+}
+
+/// Waits for the caption deletion of a video, then deletes its metadata. A failed task
+/// makes the video visible again so the deletion can be retried.
+async fn finish_video_deletion(
+    es_client: &Elasticsearch,
+    video_id: &str,
+    task_id: &str,
+    run: &MaintenanceRun,
+) {
+    let status = match wait_for_task(es_client, task_id, Some(run)).await {
+        Ok(status) => status,
+        Err(e) => {
+            log::error!("Deleting the captions of video {video_id} failed: {e}");
+            set_video_deleting(es_client, video_id, false).await;
+            return;
+        }
+    };
+    if let Err(e) = sentence_service::delete_sentences(es_client, &[video_id.to_string()]).await {
+        log::warn!("Failed to delete the sentences of video {video_id}: {e:?}");
+    }
+
+    match es_client
+        .delete(DeleteParts::IndexId(INDEX_NAMES.videos, video_id))
+        .send()
+        .await
+    {
+        Ok(response) if response.status_code().is_success() => {
+            log::info!("Video {video_id} deleted with {} captions", status.deleted);
+        }
+        Ok(response) => log::error!(
+            "Failed to delete metadata of video {video_id}: {}",
+            response.status_code()
+        ),
+        Err(e) => log::error!("Failed to delete metadata of video {video_id}: {e:?}"),
+    }
+}
+
+/// Deletes all indexed videos of a channel like `delete_video` does for one: they are
+/// hidden right away, their captions are deleted in the background and the metadata
+/// once that finished, paced by the maintenance `run`. Returns the Elasticsearch task id
+/// of the caption deletion.
+pub async fn delete_channel_content(
+    es_client: &Elasticsearch,
+    generation: &ArchiveGeneration,
+    channel_id: &str,
+    video_ids: &[String],
+    run: MaintenanceRun,
+) -> Result<String> {
+    let channel_query = json!({ "term": { "channel_id.keyword": channel_id } });
+    delete_content(
+        es_client,
+        generation,
+        &format!("channel {channel_id}"),
+        channel_query,
+        video_ids,
+        run,
+    )
+    .await
+}
+
+/// Hides the videos matching `videos_query` right away, deletes the captions of
+/// `video_ids` in the background and the videos once that finished. `what` names the
+/// content in messages. Elasticsearch works through them in batches at the pace of the
+/// maintenance `run`, which is held until the videos are gone. Returns the Elasticsearch
+/// task id of the caption deletion.
+async fn delete_content(
+    es_client: &Elasticsearch,
+    generation: &ArchiveGeneration,
+    what: &str,
+    videos_query: Value,
+    video_ids: &[String],
+    run: MaintenanceRun,
+) -> Result<String> {
+    let flag_response = es_client
+        .update_by_query(UpdateByQueryParts::Index(&[INDEX_NAMES.videos]))
+        .conflicts(Conflicts::Proceed)
+        .scroll_size(run.throttle().scroll_size())
+        .requests_per_second(run.throttle().requests_per_second())
+        .refresh(true)
+        .body(json!({
+            "query": videos_query.clone(),
+            "script": { "source": "ctx._source.deleting = true", "lang": "painless" }
+        }))
+        .send()
+        .await?;
+    if !flag_response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Failed to flag the videos of {what} for deletion: {}",
+            flag_response.status_code()
+        ));
+    }
+    generation.bump();
+
+    let delete_captions_response = es_client
+        .delete_by_query(DeleteByQueryParts::Index(&[INDEX_NAMES.captions]))
+        .wait_for_completion(false)
+        .conflicts(Conflicts::Proceed)
+        .scroll_size(run.throttle().scroll_size())
+        .requests_per_second(run.throttle().requests_per_second())
+        .body(json!({ "query": { "terms": { "video_id": video_ids } } }))
+        .send()
+        .await?;
+    if !delete_captions_response.status_code().is_success() {
+        return Err(anyhow::anyhow!("Failed to delete the captions of {what}"));
+    }
+
+    let json_response: Value = delete_captions_response.json().await?;
+    let task_id = json_response["task"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Caption deletion returned no task id"))?
+        .to_string();
+
+    let es_client = es_client.clone();
+    let what = what.to_string();
+    let video_ids = video_ids.to_vec();
+    let watched_task = task_id.clone();
+    tokio::spawn(async move {
+        if let Err(e) = wait_for_task(&es_client, &watched_task, Some(&run)).await {
+            // The videos stay flagged so they don't reappear, e.g. of an opted-out channel
+            log::error!("Deleting the captions of {what} failed: {e}");
+            return;
+        }
+        if let Err(e) = sentence_service::delete_sentences(&es_client, &video_ids).await {
+            log::warn!("Failed to delete the sentences of {what}: {e:?}");
+        }
+        match es_client
+            .delete_by_query(DeleteByQueryParts::Index(&[INDEX_NAMES.videos]))
+            .conflicts(Conflicts::Proceed)
+            .scroll_size(run.throttle().scroll_size())
+            .requests_per_second(run.throttle().requests_per_second())
+            .body(json!({ "query": videos_query }))
+            .send()
+            .await
+        {
+            Ok(response) if response.status_code().is_success() => {
+                log::info!("Deleted the content of {what}");
+            }
+            Ok(response) => log::error!(
+                "Failed to delete the videos of {what}: {}",
+                response.status_code()
+            ),
+            Err(e) => log::error!("Failed to delete the videos of {what}: {e:?}"),
+        }
+    });
+
+    Ok(task_id)
+}
+
+/// Deletes the videos of a removed channel monitor under the maintenance `run`, without
+/// one only counts them
+pub async fn purge_channel_videos(
+    es_client: &Elasticsearch,
+    generation: &ArchiveGeneration,
+    channel_id: &str,
+    run: Option<MaintenanceRun>,
+) -> Result<MonitorPurgeResponse> {
+    let dry_run = run.is_none();
+    let video_ids =
+        video_service::video_ids_of_channels(es_client, &[channel_id.to_string()]).await?;
+    let mut response = MonitorPurgeResponse {
+        monitor_id: channel_id.to_string(),
+        dry_run,
+        videos: video_ids.len() as i64,
+        captions: optout_service::count_captions(es_client, &video_ids).await?,
+        untagged_videos: 0,
+        task_id: None,
+    };
+    if let Some(run) = run.filter(|_| !video_ids.is_empty()) {
+        response.task_id =
+            Some(delete_channel_content(es_client, generation, channel_id, &video_ids, run).await?);
+        log::info!(
+            "Purging {} videos of removed channel {channel_id}",
+            video_ids.len()
+        );
+    }
+    Ok(response)
+}
+
+/// Deletes the videos only a removed playlist monitor brought in, those also in another
+/// playlist or of a monitored channel just leave the playlist. Runs under the maintenance
+/// `run`, without one only counts.
+pub async fn purge_playlist_videos(
+    es_client: &Elasticsearch,
+    generation: &ArchiveGeneration,
+    playlist_id: &str,
+    run: Option<MaintenanceRun>,
+) -> Result<MonitorPurgeResponse> {
+    let dry_run = run.is_none();
+    let monitored_channels: HashSet<String> = MONITORED_CHANNELS
+        .read()
+        .await
+        .iter()
+        .map(|channel| channel.channel_id.clone())
+        .collect();
+    let mut sole_videos: Vec<String> = Vec::new();
+    let mut shared_videos: Vec<String> = Vec::new();
+    maintenance_service::scroll_index(
+        es_client,
+        INDEX_NAMES.videos,
+        json!({ "term": { "playlists.keyword": playlist_id } }),
+        true,
+        |hit| {
+            let Some(video_id) = hit["_id"].as_str() else {
+                return Ok(());
+            };
+            let source = &hit["_source"];
+            let in_other_playlist = source["playlists"].as_array().is_some_and(|playlists| {
+                playlists
+                    .iter()
+                    .any(|playlist| playlist.as_str() != Some(playlist_id))
+            });
+            let channel_monitored = source["channel_id"]
+                .as_str()
+                .is_some_and(|channel_id| monitored_channels.contains(channel_id));
+            if in_other_playlist || channel_monitored {
+                shared_videos.push(video_id.to_string());
+            } else {
+                sole_videos.push(video_id.to_string());
+            }
+            Ok(())
+        },
+    )
+    .await
+    .with_context(|| format!("Looking up the videos of playlist {playlist_id} failed"))?;
+
+    let mut response = MonitorPurgeResponse {
+        monitor_id: playlist_id.to_string(),
+        dry_run,
+        videos: sole_videos.len() as i64,
+        captions: optout_service::count_captions(es_client, &sole_videos).await?,
+        untagged_videos: shared_videos.len() as i64,
+        task_id: None,
+    };
+    let Some(run) = run else {
+        return Ok(response);
+    };
+
+    if !shared_videos.is_empty() {
+        let untag_response = es_client
+            .update_by_query(UpdateByQueryParts::Index(&[INDEX_NAMES.videos]))
+            .conflicts(Conflicts::Proceed)
+            .scroll_size(run.throttle().scroll_size())
+            .requests_per_second(run.throttle().requests_per_second())
+            .refresh(true)
+            .body(json!({
+                "query": { "ids": { "values": shared_videos } },
+                "script": {
+                    "source": "ctx._source.playlists.removeIf(p -> p == params.playlist_id)",
+                    "lang": "painless",
+                    "params": { "playlist_id": playlist_id }
+                }
+            }))
+            .send()
+            .await?;
+        if !untag_response.status_code().is_success() {
+            return Err(anyhow::anyhow!(
+                "Failed to remove playlist {playlist_id} from its videos: {}",
+                untag_response.status_code()
+            ));
+        }
+        generation.bump();
+    }
+    if !sole_videos.is_empty() {
+        response.task_id = Some(
+            delete_content(
+                es_client,
+                generation,
+                &format!("playlist {playlist_id}"),
+                json!({ "ids": { "values": sole_videos } }),
+                &sole_videos,
+                run,
+            )
+            .await?,
+        );
+    }
+    log::info!(
+        "Purging {} videos of removed playlist {playlist_id}, {} stay in other sources",
+        sole_videos.len(),
+        shared_videos.len()
+    );
+    Ok(response)
+}
+
+async fn set_video_deleting(es_client: &Elasticsearch, video_id: &str, deleting: bool) {
+    if let Err(e) = es_client
+        .update(UpdateParts::IndexId(INDEX_NAMES.videos, video_id))
+        .body(json!({ "doc": { "deleting": deleting } }))
+        .send()
+        .await
+    {
+        log::error!("Failed to set the deleting flag of video {video_id}: {e:?}");
+    }
+}
+
+/// Counts the captions longer than MAX_CAPTION_CHARS, which were indexed before long
+/// captions were split. The length is computed from the source at search time, it counts
+/// UTF-16 units rather than chars.
+pub async fn oversized_caption_report(es_client: &Elasticsearch) -> Result<OversizedCaptionReport> {
+    let max_chars = *MAX_CAPTION_CHARS;
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.captions]))
+        .body(json!({
+            "size": 0,
+            "track_total_hits": true,
+            "runtime_mappings": {
+                "text_length": {
+                    "type": "long",
+                    "script": {
+                        "source": "def text = params._source.text; emit(text == null ? 0 : text.length())"
+                    }
+                }
+            },
+            "query": { "range": { "text_length": { "gt": max_chars } } },
+            "aggs": {
+                "videos": { "cardinality": { "field": "video_id" } },
+                "longest": { "max": { "field": "text_length" } },
+                "by_video": { "terms": { "field": "video_id", "size": OVERSIZED_REPORT_VIDEOS } }
+            }
+        }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Counting oversized captions failed: {}",
+            response.text().await.unwrap_or_default()
+        ));
+    }
+
+    let body: Value = response.json().await?;
+    Ok(OversizedCaptionReport {
+        max_chars,
+        captions: body["hits"]["total"]["value"].as_i64().unwrap_or(0),
+        videos: body["aggregations"]["videos"]["value"]
+            .as_i64()
+            .unwrap_or(0),
+        longest_chars: body["aggregations"]["longest"]["value"]
+            .as_f64()
+            .unwrap_or(0.0) as i64,
+        video_ids: body["aggregations"]["by_video"]["buckets"]
+            .as_array()
+            .map(|buckets| {
+                buckets
+                    .iter()
+                    .filter_map(|bucket| bucket["key"].as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    })
+}
+
+/// Status of an Elasticsearch task, None if Elasticsearch doesn't know it
+pub async fn get_task_status(
+    es_client: &Elasticsearch,
+    task_id: &str,
+) -> Result<Option<AdminTaskStatus>> {
+    // The tasks API is only exposed with the experimental-apis feature
+    let response = es_client
+        .send(
+            Method::Get,
+            &format!("/_tasks/{task_id}"),
+            HeaderMap::new(),
+            Option::<&()>::None,
+            Option::<Vec<u8>>::None,
+            None,
+        )
+        .await?;
+
+    if response.status_code().as_u16() == 404 {
+        return Ok(None);
+    }
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Task lookup failed with status: {}",
+            response.status_code()
+        ));
+    }
+
+    let json_response: Value = response.json().await?;
+    let completed = json_response["completed"].as_bool().unwrap_or(false);
+    // A finished task reports its final counts in the response, a running one in its status
+    let counts = if completed {
+        &json_response["response"]
+    } else {
+        &json_response["task"]["status"]
+    };
+    let error = if !json_response["error"].is_null() {
+        Some(
+            json_response["error"]["reason"]
+                .as_str()
+                .unwrap_or("Unknown error")
+                .to_string(),
+        )
+    } else {
+        counts["failures"]
+            .as_array()
+            .filter(|failures| !failures.is_empty())
+            .map(|failures| format!("{} documents could not be deleted", failures.len()))
+    };
+
+    Ok(Some(AdminTaskStatus {
+        task_id: task_id.to_string(),
+        completed,
+        total: counts["total"].as_i64().unwrap_or(0),
+        deleted: counts["deleted"].as_i64().unwrap_or(0),
+        error,
+    }))
 }
 
 pub async fn get_videos_paginated(
@@ -122,7 +790,9 @@ pub async fn get_videos_paginated(
         "size": per_page,
         "from": from,
         "query": {
-            "match_all": {}
+            "bool": {
+                "must_not": [{ "term": { "deleting": true } }]
+            }
         },
         "sort": [
             {
@@ -134,7 +804,7 @@ pub async fn get_videos_paginated(
     });
 
     let response = es_client
-        .search(SearchParts::Index(&["youtube_videos"]))
+        .search(SearchParts::Index(&[INDEX_NAMES.videos]))
         .body(search_body)
         .send()
         .await?;
@@ -161,15 +831,398 @@ pub async fn get_videos_paginated(
         }
     }
 
-    Ok(AdminVideoListResponse {
-        videos,
-        total,
-        page,
-        per_page,
+    Ok(AdminVideoListResponse::new(videos, total, page, per_page))
+}
+
+/// Looks up a video URL, video id, monitor name or id, or title fragment in the queue,
+/// the indexed videos and the monitors at once. `per_page` is the page size of the
+/// admin video list the video links point into.
+pub async fn lookup(
+    es_client: &Elasticsearch,
+    video_queue: &VideoQueue,
+    query: &str,
+    per_page: i64,
+) -> Result<AdminLookupResponse> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(AdminLookupResponse::default());
+    }
+    let video_id = utils::extract_youtube_video_id(query).unwrap_or_else(|_| query.to_string());
+
+    let (videos, channels, playlists) = tokio::join!(
+        lookup_videos(es_client, query, &video_id, per_page),
+        get_monitored_channels_list(es_client),
+        get_monitored_playlist_list(es_client),
+    );
+
+    let queue = video_queue
+        .get_all_items()
+        .into_iter()
+        .filter(|item| {
+            item.video_id == video_id
+                || matches!(
+                    &item.source,
+                    Some(CheckSource::Channel(id) | CheckSource::Playlist(id)) if id == query
+                )
+        })
+        .take(LOOKUP_LIMIT)
+        .map(|item| LookupHit {
+            kind: LookupKind::Queue,
+            link: format!("/admin/queue?highlight={}", item.id),
+            id: item.id,
+            label: item.video_id,
+            detail: Some(item.status),
+            page: None,
+        })
+        .collect();
+
+    let needle = query.to_lowercase();
+    let channel_hits = channels
+        .into_iter()
+        .filter(|c| c.channel_id == query || c.channel_name.to_lowercase().contains(&needle))
+        .map(|c| LookupHit {
+            kind: LookupKind::Channel,
+            link: format!("/admin/monitors?highlight={}", c.channel_id),
+            detail: Some(format!("Channel, {} videos indexed", c.videos_indexed)),
+            id: c.channel_id,
+            label: c.channel_name,
+            page: None,
+        });
+    let playlist_hits = playlists
+        .into_iter()
+        .filter(|p| p.playlist_id == query || p.playlist_name.to_lowercase().contains(&needle))
+        .map(|p| LookupHit {
+            kind: LookupKind::Playlist,
+            link: format!("/admin/monitors?highlight={}", p.playlist_id),
+            detail: Some(format!("Playlist, {} videos indexed", p.videos_indexed)),
+            id: p.playlist_id,
+            label: p.playlist_name,
+            page: None,
+        });
+    let monitors = channel_hits
+        .chain(playlist_hits)
+        .take(LOOKUP_LIMIT)
+        .collect();
+
+    Ok(AdminLookupResponse {
+        queue,
+        videos: videos?,
+        monitors,
     })
 }
 
-async fn get_index_count(es_client: &Elasticsearch, index: &str) -> i64 {
+/// Indexed videos matching the id or channel id exactly, or the title by prefix
+async fn lookup_videos(
+    es_client: &Elasticsearch,
+    query: &str,
+    video_id: &str,
+    per_page: i64,
+) -> Result<Vec<LookupHit>> {
+    let search_body = json!({
+        "size": LOOKUP_LIMIT,
+        "query": {
+            "bool": {
+                "should": [
+                    { "ids": { "values": [video_id] } },
+                    { "term": { "channel_id.keyword": query } },
+                    { "match_phrase_prefix": { "title": query } }
+                ],
+                "minimum_should_match": 1,
+                "must_not": [{ "term": { "deleting": true } }]
+            }
+        },
+        "_source": ["video_id", "title", "channel_name", "upload_date"]
+    });
+
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.videos]))
+        .body(search_body)
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!("Video lookup failed"));
+    }
+
+    let json_response: Value = response.json().await?;
+    let mut hits = Vec::new();
+    for hit in json_response["hits"]["hits"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        let source = &hit["_source"];
+        let video_id = hit["_id"].as_str().unwrap_or_default().to_string();
+        let page = get_video_list_page(
+            es_client,
+            source["upload_date"].as_i64().unwrap_or(0),
+            per_page,
+        )
+        .await;
+        hits.push(LookupHit {
+            kind: LookupKind::Video,
+            link: format!("/admin/videos?page={page}&highlight={video_id}"),
+            label: source["title"].as_str().unwrap_or(&video_id).to_string(),
+            detail: source["channel_name"].as_str().map(str::to_string),
+            id: video_id,
+            page: Some(page),
+        });
+    }
+    Ok(hits)
+}
+
+/// Page of the admin video list (newest upload first) a video uploaded at `upload_date` is on
+async fn get_video_list_page(es_client: &Elasticsearch, upload_date: i64, per_page: i64) -> i64 {
+    let count_body = json!({
+        "query": {
+            "bool": {
+                "filter": [{ "range": { "upload_date": { "gt": upload_date } } }],
+                "must_not": [{ "term": { "deleting": true } }]
+            }
+        }
+    });
+
+    let newer = match es_client
+        .count(elasticsearch::CountParts::Index(&[INDEX_NAMES.videos]))
+        .body(count_body)
+        .send()
+        .await
+    {
+        Ok(response) => response
+            .json::<Value>()
+            .await
+            .ok()
+            .and_then(|json_response| json_response["count"].as_i64())
+            .unwrap_or(0),
+        Err(e) => {
+            log::error!("Failed to count newer videos: {e:?}");
+            0
+        }
+    };
+    newer / per_page.max(1) + 1
+}
+
+/// Returns the crawl history of a video, or None if the video is not indexed
+pub async fn get_crawl_history(
+    es_client: &Elasticsearch,
+    video_id: &str,
+) -> Result<Option<Vec<CrawlHistoryEntry>>> {
+    let response = es_client
+        .get(GetParts::IndexId(INDEX_NAMES.videos, video_id))
+        ._source_includes(&["crawl_history"])
+        .send()
+        .await?;
+
+    if response.status_code().as_u16() == 404 {
+        return Ok(None);
+    }
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!("Failed to fetch crawl history"));
+    }
+
+    let json_response: Value = response.json().await?;
+    let history = serde_json::from_value(json_response["_source"]["crawl_history"].clone())
+        .unwrap_or_default();
+
+    Ok(Some(history))
+}
+
+/// Entries of the indexing log shown with a video, the latest crawls suffice
+const VIDEO_INDEXING_LOG_ENTRIES: i64 = 20;
+
+/// The video document with where it came from, which monitors reference it and what was
+/// indexed of it. None if the video is unknown.
+pub async fn get_video_detail(
+    es_client: &Elasticsearch,
+    video_id: &str,
+) -> Result<Option<AdminVideoDetail>> {
+    let response = es_client
+        .get(GetParts::IndexId(INDEX_NAMES.videos, video_id))
+        .send()
+        .await?;
+
+    if response.status_code().as_u16() == 404 {
+        return Ok(None);
+    }
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!("Failed to fetch video metadata"));
+    }
+
+    let json_response: Value = response.json().await?;
+    let video: VideoMetadata = serde_json::from_value(json_response["_source"].clone())?;
+    let (caption_count, caption_languages) = get_caption_languages(es_client, video_id).await?;
+    let indexing_log = indexing_log_service::get_entries(
+        es_client,
+        Some(video_id),
+        None,
+        None,
+        1,
+        VIDEO_INDEXING_LOG_ENTRIES,
+    )
+    .await?
+    .items;
+
+    // Videos crawled before `discovered_by` existed still have their source in the log
+    let source = video
+        .discovered_by
+        .clone()
+        .or_else(|| indexing_log.last().and_then(|entry| entry.source.clone()));
+
+    let channels = MONITORED_CHANNELS.read().await;
+    let playlists = MONITORED_PlAYLISTS.read().await;
+    let playlist_name = |playlist_id: &str| {
+        playlists
+            .iter()
+            .find(|p| p.playlist_id == playlist_id)
+            .map(|p| p.playlist_name.clone())
+    };
+    let source_name = match &source {
+        Some(CheckSource::Channel(channel_id)) => channels
+            .iter()
+            .find(|c| &c.channel_id == channel_id)
+            .map(|c| c.channel_name.clone()),
+        Some(CheckSource::Playlist(playlist_id)) => playlist_name(playlist_id),
+        None => None,
+    };
+    let memberships = video
+        .playlists
+        .iter()
+        .map(|playlist_id| PlaylistMembership {
+            playlist_id: playlist_id.clone(),
+            playlist_name: playlist_name(playlist_id),
+        })
+        .collect();
+    let channel_monitor_active = channels
+        .iter()
+        .find(|c| c.channel_id == video.channel_id)
+        .map(|c| c.active);
+
+    Ok(Some(AdminVideoDetail {
+        source,
+        source_name,
+        playlists: memberships,
+        caption_count,
+        caption_languages,
+        indexing_log,
+        channel_monitor_active,
+        video,
+    }))
+}
+
+/// Number of captions of a video and their languages, most captions first
+async fn get_caption_languages(
+    es_client: &Elasticsearch,
+    video_id: &str,
+) -> Result<(i64, Vec<String>)> {
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.captions]))
+        .body(json!({
+            "size": 0,
+            "track_total_hits": true,
+            "query": { "term": { "video_id": video_id } },
+            "aggs": {
+                "languages": { "terms": { "field": "language", "size": 50 } }
+            }
+        }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Caption language aggregation failed: {}",
+            response.status_code()
+        ));
+    }
+
+    let json_response: Value = response.json().await?;
+    let languages = json_response["aggregations"]["languages"]["buckets"]
+        .as_array()
+        .map(|buckets| {
+            buckets
+                .iter()
+                .filter_map(|bucket| bucket["key"].as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok((
+        json_response["hits"]["total"]["value"]
+            .as_i64()
+            .unwrap_or(0),
+        languages,
+    ))
+}
+
+/// Compares the latest crawl of a video with the one before. None if the video is unknown.
+pub async fn get_metadata_diff(
+    es_client: &Elasticsearch,
+    video_id: &str,
+) -> Result<Option<VideoMetadataDiff>> {
+    let response = es_client
+        .get(GetParts::IndexId(INDEX_NAMES.videos, video_id))
+        .send()
+        .await?;
+
+    if response.status_code().as_u16() == 404 {
+        return Ok(None);
+    }
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!("Failed to fetch video metadata"));
+    }
+
+    let json_response: Value = response.json().await?;
+    let metadata: VideoMetadata = serde_json::from_value(json_response["_source"].clone())?;
+    let latest = MetadataSnapshot::from(&metadata);
+
+    Ok(Some(VideoMetadataDiff {
+        video_id: metadata.video_id.clone(),
+        previous_crawl_date: metadata.previous.as_ref().map(|p| p.crawl_date),
+        latest_crawl_date: latest.crawl_date,
+        changes: metadata
+            .previous
+            .as_ref()
+            .map(|previous| diff_metadata(previous, &latest))
+            .unwrap_or_default(),
+    }))
+}
+
+/// Field-by-field comparison of two crawls, unchanged fields are left out
+pub fn diff_metadata(previous: &MetadataSnapshot, current: &MetadataSnapshot) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    let mut compare = |field: &str, previous: String, current: String| {
+        if previous != current {
+            changes.push(FieldChange {
+                field: field.to_string(),
+                previous,
+                current,
+            });
+        }
+    };
+
+    compare("title", previous.title.clone(), current.title.clone());
+    compare(
+        "duration",
+        previous.duration.to_string(),
+        current.duration.to_string(),
+    );
+    compare(
+        "likes",
+        previous.likes.to_string(),
+        current.likes.to_string(),
+    );
+    compare(
+        "views",
+        previous.views.to_string(),
+        current.views.to_string(),
+    );
+    compare(
+        "comment_count",
+        previous.comment_count.to_string(),
+        current.comment_count.to_string(),
+    );
+    compare("tags", previous.tags.join(", "), current.tags.join(", "));
+
+    changes
+}
+
+pub(crate) async fn get_index_count(es_client: &Elasticsearch, index: &str) -> i64 {
     let count_body = json!({
         "query": {
             "match_all": {}
@@ -196,6 +1249,43 @@ async fn get_index_count(es_client: &Elasticsearch, index: &str) -> i64 {
     0
 }
 
+/// Summed duration of all indexed videos in seconds, and how many videos have no
+/// duration at all (indexed before it was stored)
+async fn get_duration_totals(es_client: &Elasticsearch) -> (f64, i64) {
+    let search_body = json!({
+        "size": 0,
+        "aggs": {
+            "total_duration": { "sum": { "field": "duration" } },
+            "missing_duration": { "missing": { "field": "duration" } }
+        }
+    });
+
+    match es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.videos]))
+        .body(search_body)
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if let Ok(json_response) = response.json::<Value>().await {
+                let aggregations = &json_response["aggregations"];
+                return (
+                    aggregations["total_duration"]["value"]
+                        .as_f64()
+                        .unwrap_or(0.0),
+                    aggregations["missing_duration"]["doc_count"]
+                        .as_i64()
+                        .unwrap_or(0),
+                );
+            }
+        }
+        Err(e) => {
+            log::error!("Failed to sum video durations: {e:?}");
+        }
+    }
+    (0.0, 0)
+}
+
 async fn get_last_crawl_time(es_client: &Elasticsearch) -> Option<i64> {
     let search_body = json!({
         "size": 1,
@@ -213,7 +1303,7 @@ async fn get_last_crawl_time(es_client: &Elasticsearch) -> Option<i64> {
     });
 
     match es_client
-        .search(SearchParts::Index(&["youtube_videos"]))
+        .search(SearchParts::Index(&[INDEX_NAMES.videos]))
         .body(search_body)
         .send()
         .await
@@ -236,3 +1326,395 @@ async fn get_last_crawl_time(es_client: &Elasticsearch) -> Option<i64> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MonitoredChannel;
+    use crate::services::simulation;
+    use crate::testsupport::{maintenance_run, opt_out, search_hits, MockElasticsearch};
+
+    fn crawl(title: &str, likes: i64, tags: &[&str]) -> VideoMetadata {
+        let mut metadata = simulation::video_metadata("dQw4w9WgXcQ");
+        metadata.title = title.to_string();
+        metadata.likes = likes;
+        metadata.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        metadata
+    }
+
+    fn diff(previous: &VideoMetadata, current: &VideoMetadata) -> Vec<FieldChange> {
+        diff_metadata(
+            &MetadataSnapshot::from(previous),
+            &MetadataSnapshot::from(current),
+        )
+    }
+
+    fn change(field: &str, previous: &str, current: &str) -> FieldChange {
+        FieldChange {
+            field: field.to_string(),
+            previous: previous.to_string(),
+            current: current.to_string(),
+        }
+    }
+
+    #[test]
+    fn unchanged_crawls_have_no_changes() {
+        let video = crawl("Title", 10, &["a"]);
+        assert!(diff(&video, &video.clone()).is_empty());
+    }
+
+    #[test]
+    fn crawl_date_is_not_a_change() {
+        let previous = crawl("Title", 10, &["a"]);
+        let mut current = previous.clone();
+        current.crawl_date += 24 * 60 * 60;
+        assert!(diff(&previous, &current).is_empty());
+    }
+
+    #[test]
+    fn title_edits_are_reported() {
+        let changes = diff(&crawl("Old", 10, &[]), &crawl("New", 10, &[]));
+        assert_eq!(changes, vec![change("title", "Old", "New")]);
+    }
+
+    #[test]
+    fn counter_jumps_are_reported() {
+        let previous = crawl("Title", 10, &[]);
+        let mut current = crawl("Title", 5_000, &[]);
+        current.views = previous.views + 1;
+        current.comment_count = previous.comment_count;
+        assert_eq!(
+            diff(&previous, &current),
+            vec![
+                change("likes", "10", "5000"),
+                change(
+                    "views",
+                    &previous.views.to_string(),
+                    &current.views.to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn tag_changes_are_reported() {
+        let changes = diff(
+            &crawl("Title", 10, &["a", "b"]),
+            &crawl("Title", 10, &["b", "c"]),
+        );
+        assert_eq!(changes, vec![change("tags", "a, b", "b, c")]);
+
+        let removed = diff(&crawl("Title", 10, &["a"]), &crawl("Title", 10, &[]));
+        assert_eq!(removed, vec![change("tags", "a", "")]);
+    }
+
+    #[test]
+    fn changes_are_listed_in_field_order() {
+        let mut previous = crawl("Old", 1, &["a"]);
+        previous.duration = 60;
+        let mut current = crawl("New", 2, &["b"]);
+        current.duration = 61;
+        current.views = previous.views;
+        current.comment_count = previous.comment_count;
+        let fields: Vec<String> = diff(&previous, &current)
+            .into_iter()
+            .map(|change| change.field)
+            .collect();
+        assert_eq!(fields, ["title", "duration", "likes", "tags"]);
+    }
+
+    #[test]
+    fn renamed_videos_are_flagged() {
+        let mut video = crawl("New", 10, &[]);
+        assert!(!video.is_renamed());
+        video.previous = Some(MetadataSnapshot::from(&crawl("Old", 10, &[])));
+        assert!(video.is_renamed());
+        video.previous = Some(MetadataSnapshot::from(&crawl("New", 20, &[])));
+        assert!(!video.is_renamed());
+    }
+
+    #[rocket::async_test]
+    async fn admin_stats_are_gathered_from_the_cluster() {
+        let es = MockElasticsearch::start().await;
+        es.stub(
+            "POST",
+            &format!("/{}/_count", INDEX_NAMES.videos),
+            json!({ "count": 12 }),
+        )
+        .await;
+        es.stub(
+            "POST",
+            &format!("/{}/_count", INDEX_NAMES.captions),
+            json!({ "count": 3400 }),
+        )
+        .await;
+        let videos_search = format!("/{}/_search", INDEX_NAMES.videos);
+        es.stub_body(
+            "POST",
+            &videos_search,
+            |body| body["sort"][0]["crawl_date"].is_object(),
+            search_hits(vec![(
+                "aaaaaaaaaaa",
+                json!({ "crawl_date": 1_700_000_000 }),
+            )]),
+        )
+        .await;
+        es.stub_body(
+            "POST",
+            &videos_search,
+            |body| body["aggs"]["total_duration"].is_object(),
+            json!({
+                "aggregations": {
+                    "total_duration": { "value": 7200.0 },
+                    "missing_duration": { "doc_count": 2 }
+                }
+            }),
+        )
+        .await;
+        es.stub_body(
+            "POST",
+            &format!("/{}/_search", INDEX_NAMES.indexing_log),
+            |body| body["query"]["term"]["note"] == indexing_log_service::NOTE_SKIPPED_TOO_LONG,
+            json!({ "aggregations": { "videos": { "value": 4 } } }),
+        )
+        .await;
+
+        let queue = VideoQueue::new();
+        queue.add_video("bbbbbbbbbbb".to_string());
+        let stats = get_admin_stats(&es.client, &queue).await.unwrap();
+
+        assert_eq!(stats.total_videos, 12);
+        assert_eq!(stats.total_captions, 3400);
+        assert_eq!(stats.last_crawl_time, Some(1_700_000_000));
+        assert_eq!(stats.total_duration_hours, 2.0);
+        assert_eq!(stats.videos_missing_duration, 2);
+        assert_eq!(stats.videos_skipped_too_long, 4);
+        assert_eq!(stats.queue_size, 1);
+        assert!(!stats.crawl_running);
+        // The upgrade count wasn't stubbed, a failing lookup counts as none
+        assert_eq!(stats.transcripts_upgraded, 0);
+    }
+
+    #[rocket::async_test]
+    async fn admin_stats_survive_an_unreachable_cluster() {
+        let es = MockElasticsearch::start().await;
+        let stats = get_admin_stats(&es.client, &VideoQueue::new())
+            .await
+            .unwrap();
+        assert_eq!((stats.total_videos, stats.total_captions), (0, 0));
+        assert_eq!(stats.last_crawl_time, None);
+        assert_eq!(stats.total_duration_hours, 0.0);
+    }
+
+    #[rocket::async_test]
+    async fn deleting_a_video_flags_it_and_deletes_its_captions() {
+        let es = MockElasticsearch::start().await;
+        let update = format!("/{}/_update/dQw4w9WgXcQ", INDEX_NAMES.videos);
+        let delete_captions = format!("/{}/_delete_by_query", INDEX_NAMES.captions);
+        es.stub("POST", &update, json!({ "result": "updated" }))
+            .await;
+        es.stub("POST", &delete_captions, json!({ "task": "node:123" }))
+            .await;
+
+        let generation = ArchiveGeneration::new();
+        let before = generation.current();
+        let task_id = delete_video(&es.client, &generation, "dQw4w9WgXcQ", maintenance_run())
+            .await
+            .unwrap();
+
+        assert_eq!(task_id, "node:123");
+        assert_eq!(
+            es.bodies("POST", &update).await[0],
+            json!({ "doc": { "deleting": true } })
+        );
+        assert_eq!(
+            es.bodies("POST", &delete_captions).await,
+            [json!({ "query": { "term": { "video_id": "dQw4w9WgXcQ" } } })]
+        );
+        assert!(
+            generation.current() > before,
+            "cached searches must not serve the video"
+        );
+    }
+
+    #[rocket::async_test]
+    async fn failed_caption_deletion_unflags_the_video() {
+        let es = MockElasticsearch::start().await;
+        let update = format!("/{}/_update/dQw4w9WgXcQ", INDEX_NAMES.videos);
+        es.stub("POST", &update, json!({ "result": "updated" }))
+            .await;
+        es.stub_status(
+            "POST",
+            &format!("/{}/_delete_by_query", INDEX_NAMES.captions),
+            500,
+            json!({ "error": "boom" }),
+        )
+        .await;
+
+        assert!(delete_video(
+            &es.client,
+            &ArchiveGeneration::new(),
+            "dQw4w9WgXcQ",
+            maintenance_run()
+        )
+        .await
+        .is_err());
+        assert_eq!(
+            es.bodies("POST", &update).await,
+            [
+                json!({ "doc": { "deleting": true } }),
+                json!({ "doc": { "deleting": false } })
+            ]
+        );
+    }
+
+    #[rocket::async_test]
+    async fn unknown_videos_are_not_deleted() {
+        let es = MockElasticsearch::start().await;
+        es.stub_status(
+            "POST",
+            &format!("/{}/_update/dQw4w9WgXcQ", INDEX_NAMES.videos),
+            404,
+            json!({ "error": "document_missing_exception" }),
+        )
+        .await;
+
+        assert!(delete_video(
+            &es.client,
+            &ArchiveGeneration::new(),
+            "dQw4w9WgXcQ",
+            maintenance_run()
+        )
+        .await
+        .is_err());
+        assert!(es
+            .bodies(
+                "POST",
+                &format!("/{}/_delete_by_query", INDEX_NAMES.captions)
+            )
+            .await
+            .is_empty());
+    }
+
+    #[rocket::async_test]
+    async fn playlist_purge_previews_videos_past_the_first_page() {
+        let es = MockElasticsearch::start().await;
+        let playlist_id = "PLpurgePreviewTest0000000000000000";
+        let search = format!("/{}/_search", INDEX_NAMES.videos);
+        let mut first_page = search_hits(vec![(
+            "aaaaaaaaaaa",
+            json!({ "channel_id": "UCnotMonitoredPurge00001", "playlists": [playlist_id] }),
+        )]);
+        first_page["_scroll_id"] = json!("page-2");
+        es.stub("POST", &search, first_page).await;
+        let mut second_page = search_hits(vec![
+            (
+                "bbbbbbbbbbb",
+                json!({
+                    "channel_id": "UCnotMonitoredPurge00001",
+                    "playlists": [playlist_id, "PLotherPlaylist"]
+                }),
+            ),
+            (
+                "ccccccccccc",
+                json!({ "channel_id": "UCnotMonitoredPurge00001", "playlists": [playlist_id] }),
+            ),
+        ]);
+        second_page["_scroll_id"] = json!("page-3");
+        es.stub_body(
+            "POST",
+            "/_search/scroll",
+            |body| body["scroll_id"] == "page-2",
+            second_page,
+        )
+        .await;
+        let mut last_page = search_hits(vec![]);
+        last_page["_scroll_id"] = json!("page-3");
+        es.stub_body(
+            "POST",
+            "/_search/scroll",
+            |body| body["scroll_id"] == "page-3",
+            last_page,
+        )
+        .await;
+        let count = format!("/{}/_count", INDEX_NAMES.captions);
+        es.stub("POST", &count, json!({ "count": 12 })).await;
+
+        let response =
+            purge_playlist_videos(&es.client, &ArchiveGeneration::new(), playlist_id, None)
+                .await
+                .unwrap();
+
+        assert!(response.dry_run);
+        assert_eq!(response.videos, 2);
+        assert_eq!(response.untagged_videos, 1);
+        assert_eq!(response.captions, 12);
+        assert_eq!(
+            es.bodies("POST", &count).await[0]["query"],
+            json!({ "terms": { "video_id": ["aaaaaaaaaaa", "ccccccccccc"] } })
+        );
+    }
+
+    /// Metadata of a video as if fetched from YouTube, of a channel no other test uses
+    fn fetched_video(video_id: &str, channel_id: &str, duration: i64) -> VideoMetadata {
+        VideoMetadata {
+            channel_id: channel_id.to_string(),
+            duration,
+            ..simulation::video_metadata(video_id)
+        }
+    }
+
+    async fn preview(es: &MockElasticsearch, metadata: VideoMetadata) -> IndexPreview {
+        let video_id = metadata.video_id.clone();
+        let prepared =
+            crawler::prepare_fetched_metadata(&es.client, &video_id, None, Ok(metadata)).await;
+        preview_prepared(&es.client, &video_id, prepared).await
+    }
+
+    #[rocket::async_test]
+    async fn previews_name_the_filters_excluding_a_video() {
+        let es = MockElasticsearch::start().await;
+
+        let included = preview(
+            &es,
+            fetched_video("aaaaaaaaaaa", "UCpreviewIncluded0000001", 60),
+        )
+        .await;
+        assert!(included.exclusions.is_empty());
+        assert!(included.selected_track.is_some());
+        assert!(included.caption_count > 0);
+
+        // Opted out, captions aren't even fetched
+        let opted_out = "UCpreviewOptedOut0000001";
+        opt_out(&es, opted_out).await;
+        let excluded = preview(&es, fetched_video("bbbbbbbbbbb", opted_out, 60)).await;
+        let filters: Vec<&str> = excluded
+            .exclusions
+            .iter()
+            .map(|exclusion| exclusion.filter.as_str())
+            .collect();
+        assert_eq!(filters, ["opt_out"]);
+        assert!(excluded.caption_tracks.is_empty());
+        assert_eq!(excluded.caption_count, 0);
+        assert!(excluded.metadata.is_some());
+
+        // Longer than the maximum its monitor allows
+        let monitored = "UCpreviewMaxDuration0001";
+        MONITORED_CHANNELS.write().await.push(MonitoredChannel {
+            max_duration_seconds: Some(600),
+            ..simulation::monitored_channel(monitored)
+        });
+        let short = preview(&es, fetched_video("ccccccccccc", monitored, 600)).await;
+        assert!(short.exclusions.is_empty());
+        let long = preview(&es, fetched_video("ddddddddddd", monitored, 601)).await;
+        assert_eq!(
+            long.exclusions,
+            [PreviewExclusion {
+                filter: "max_duration".to_string(),
+                reason: "601s is longer than the maximum of 600s".to_string(),
+                monitor: Some(CheckSource::Channel(monitored.to_string())),
+            }]
+        );
+        assert!(long.caption_tracks.is_empty());
+    }
+}
@@ -0,0 +1,247 @@
+use crate::config::INDEX_NAMES;
+use crate::models::{ApiKey, ApiKeyCreateResponse};
+use anyhow::Result;
+use elasticsearch::{Elasticsearch, GetParts, IndexParts, SearchParts, UpdateParts};
+use log::{error, info};
+use rand::Rng;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+pub enum ApiKeyCheck {
+    Allowed(String), // key hash
+    QuotaExceeded,
+    Unknown,
+}
+
+struct KeyUsage {
+    daily_quota: i64,
+    day: String,
+    count: i64,
+    dirty: bool,
+}
+
+/// In-memory per-key, per-day request counters. Counts are flushed to ES periodically
+/// so usage survives restarts without writing on every request.
+pub struct ApiKeyUsageTracker {
+    usage: Mutex<HashMap<String, KeyUsage>>,
+}
+
+impl Default for ApiKeyUsageTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ApiKeyUsageTracker {
+    pub fn new() -> Self {
+        ApiKeyUsageTracker {
+            usage: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts one request against the key. Returns None if the key is not cached yet.
+    fn try_consume(&self, key_hash: &str, today: &str) -> Option<bool> {
+        let mut usage = self.usage.lock().ok()?;
+        let entry = usage.get_mut(key_hash)?;
+
+        if entry.day != today {
+            entry.day = today.to_string();
+            entry.count = 0;
+        }
+        if entry.count >= entry.daily_quota {
+            return Some(false);
+        }
+        entry.count += 1;
+        entry.dirty = true;
+        Some(true)
+    }
+
+    fn insert(&self, key: &ApiKey, today: &str) {
+        if let Ok(mut usage) = self.usage.lock() {
+            usage
+                .entry(key.key_hash.clone())
+                .or_insert_with(|| KeyUsage {
+                    daily_quota: key.daily_quota,
+                    day: today.to_string(),
+                    count: if key.usage_day == today {
+                        key.usage_today
+                    } else {
+                        0
+                    },
+                    dirty: false,
+                });
+        }
+    }
+
+    fn current_usage(&self, key_hash: &str, today: &str) -> Option<i64> {
+        let usage = self.usage.lock().ok()?;
+        usage
+            .get(key_hash)
+            .map(|entry| if entry.day == today { entry.count } else { 0 })
+    }
+
+    /// Returns (key_hash, day, count) for every counter changed since the last call
+    fn take_dirty(&self) -> Vec<(String, String, i64)> {
+        if let Ok(mut usage) = self.usage.lock() {
+            usage
+                .iter_mut()
+                .filter(|(_, entry)| entry.dirty)
+                .map(|(hash, entry)| {
+                    entry.dirty = false;
+                    (hash.clone(), entry.day.clone(), entry.count)
+                })
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+pub fn hash_api_key(raw_key: &str) -> String {
+    format!("{:x}", Sha256::digest(raw_key.as_bytes()))
+}
+
+fn today() -> String {
+    chrono::Utc::now().format("%Y-%m-%d").to_string()
+}
+
+fn generate_api_key() -> String {
+    let bytes: [u8; 24] = rand::thread_rng().gen();
+    let encoded: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sk_{encoded}")
+}
+
+/// Creates a new key. The plain key is only returned here, ES only stores its hash.
+pub async fn create_api_key(
+    es_client: &Elasticsearch,
+    label: &str,
+    daily_quota: i64,
+) -> Result<ApiKeyCreateResponse> {
+    let raw_key = generate_api_key();
+    let key = ApiKey {
+        key_hash: hash_api_key(&raw_key),
+        label: label.to_string(),
+        daily_quota,
+        created_at: chrono::Utc::now().timestamp(),
+        usage_day: today(),
+        usage_today: 0,
+    };
+
+    let response = es_client
+        .index(IndexParts::IndexId(INDEX_NAMES.api_keys, &key.key_hash))
+        .body(json!(key))
+        .send()
+        .await?;
+
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Failed to store API key: {}",
+            response.text().await.unwrap_or_default()
+        ));
+    }
+
+    info!("API key created: {} ({} requests/day)", label, daily_quota);
+    Ok(ApiKeyCreateResponse {
+        key: raw_key,
+        label: key.label,
+        daily_quota,
+    })
+}
+
+/// Lists all keys with the in-memory usage of today applied
+pub async fn list_api_keys(
+    es_client: &Elasticsearch,
+    tracker: &ApiKeyUsageTracker,
+) -> Result<Vec<ApiKey>> {
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.api_keys]))
+        .body(json!({
+            "query": { "match_all": {} },
+            "sort": [{ "created_at": { "order": "desc" } }],
+            "size": 1000
+        }))
+        .send()
+        .await?;
+
+    let json_response: Value = response.json().await?;
+    let today = today();
+
+    let keys = json_response["hits"]["hits"]
+        .as_array()
+        .map(|hits| {
+            hits.iter()
+                .filter_map(|hit| serde_json::from_value::<ApiKey>(hit["_source"].clone()).ok())
+                .map(|mut key| {
+                    if let Some(count) = tracker.current_usage(&key.key_hash, &today) {
+                        key.usage_today = count;
+                    } else if key.usage_day != today {
+                        key.usage_today = 0;
+                    }
+                    key.usage_day = today.clone();
+                    key
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(keys)
+}
+
+/// Validates a raw key and counts the request against its daily quota
+pub async fn check_api_key(
+    es_client: &Elasticsearch,
+    tracker: &ApiKeyUsageTracker,
+    raw_key: &str,
+) -> Result<ApiKeyCheck> {
+    let key_hash = hash_api_key(raw_key);
+    let today = today();
+
+    if tracker.try_consume(&key_hash, &today).is_none() {
+        match fetch_api_key(es_client, &key_hash).await? {
+            Some(key) => tracker.insert(&key, &today),
+            None => return Ok(ApiKeyCheck::Unknown),
+        }
+    }
+
+    match tracker.try_consume(&key_hash, &today) {
+        Some(true) => Ok(ApiKeyCheck::Allowed(key_hash)),
+        Some(false) => Ok(ApiKeyCheck::QuotaExceeded),
+        None => Ok(ApiKeyCheck::Unknown),
+    }
+}
+
+async fn fetch_api_key(es_client: &Elasticsearch, key_hash: &str) -> Result<Option<ApiKey>> {
+    let response = es_client
+        .get(GetParts::IndexId(INDEX_NAMES.api_keys, key_hash))
+        .send()
+        .await?;
+
+    if response.status_code().as_u16() == 404 {
+        return Ok(None);
+    }
+
+    let json_response: Value = response.json().await?;
+    Ok(serde_json::from_value(json_response["_source"].clone()).ok())
+}
+
+/// Writes the changed usage counters back to ES
+pub async fn flush_usage(es_client: &Elasticsearch, tracker: &ApiKeyUsageTracker) {
+    for (key_hash, day, count) in tracker.take_dirty() {
+        let result = es_client
+            .update(UpdateParts::IndexId(INDEX_NAMES.api_keys, &key_hash))
+            .body(json!({
+                "doc": {
+                    "usage_day": day,
+                    "usage_today": count
+                }
+            }))
+            .send()
+            .await;
+
+        if let Err(e) = result {
+            error!("Failed to flush API key usage for {key_hash}: {e:?}");
+        }
+    }
+}
@@ -0,0 +1,34 @@
+//! Counter bumped whenever indexed videos change: crawled, recrawled or deleted. Caches of
+//! search results and video metadata key their entries by it, and clients get it as the
+//! `X-Archive-Generation` header to notice that what they cached may be stale. The server's
+//! counter lives in `AppState`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub struct ArchiveGeneration {
+    generation: AtomicU64,
+}
+
+impl Default for ArchiveGeneration {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ArchiveGeneration {
+    /// Starts at the current time, so a restarted server never repeats a generation a
+    /// client saw before
+    pub fn new() -> Self {
+        ArchiveGeneration {
+            generation: AtomicU64::new(chrono::Utc::now().timestamp_millis() as u64),
+        }
+    }
+
+    pub fn current(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    pub fn bump(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+}
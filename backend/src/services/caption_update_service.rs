@@ -0,0 +1,270 @@
+//! Detects transcripts that changed since they were indexed. A recrawl compares the
+//! fetched captions with the indexed ones, a scheduled sweep looks for videos indexed
+//! with auto-generated captions whose creator uploaded a manual track since.
+
+use crate::config::{
+    CAPTION_UPDATE_MIN_AGE_MONTHS, CAPTION_UPDATE_SAMPLE_SIZE, INDEX_NAMES, LANGUAGE_PRIORITY,
+};
+use crate::models::{Caption, CaptionTrack};
+use crate::services::crawler::{self, VideoQueue};
+use crate::services::indexing_log_service;
+use crate::services::maintenance_throttle::MaintenanceRun;
+use crate::services::transcript_throttle::TRANSCRIPT_THROTTLE;
+use anyhow::Result;
+use elasticsearch::params::Conflicts;
+use elasticsearch::{DeleteByQueryParts, Elasticsearch, SearchParts, UpdateParts};
+use log::{error, info, warn};
+use serde_json::{json, Value};
+
+/// How a fetched transcript compares to the indexed captions of the video
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptChange {
+    /// No captions were indexed before
+    New,
+    Unchanged,
+    Changed,
+    /// Auto-generated captions replaced by a manual track
+    Upgraded,
+}
+
+impl TranscriptChange {
+    /// Indexing log note of the crawl, None if there is nothing to report
+    pub fn note(&self) -> Option<&'static str> {
+        match self {
+            TranscriptChange::Changed => Some(indexing_log_service::NOTE_TRANSCRIPT_CHANGED),
+            TranscriptChange::Upgraded => Some(indexing_log_service::NOTE_TRANSCRIPT_UPGRADED),
+            TranscriptChange::New | TranscriptChange::Unchanged => None,
+        }
+    }
+
+    /// Whether captions of the old transcript may be left over after indexing the new one
+    pub fn replaces_captions(&self) -> bool {
+        matches!(self, TranscriptChange::Changed | TranscriptChange::Upgraded)
+    }
+}
+
+/// Cheap summary of a transcript: line count, total characters and a rolling hash
+/// over the text of all lines in order
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Fingerprint {
+    lines: usize,
+    chars: usize,
+    hash: u64,
+}
+
+impl Fingerprint {
+    fn add(&mut self, text: &str) {
+        self.lines += 1;
+        self.chars += text.chars().count();
+        for byte in text.bytes().chain([b'\n']) {
+            self.hash = self.hash.wrapping_mul(31).wrapping_add(byte as u64);
+        }
+    }
+}
+
+fn fingerprint<'a>(texts: impl Iterator<Item = &'a str>) -> Fingerprint {
+    let mut fingerprint = Fingerprint::default();
+    for text in texts {
+        fingerprint.add(text);
+    }
+    fingerprint
+}
+
+/// Compares the fetched `captions`, sorted by start time, with the indexed ones
+pub async fn compare(
+    es_client: &Elasticsearch,
+    video_id: &str,
+    captions: &[Caption],
+    is_generated: bool,
+) -> Result<TranscriptChange> {
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.captions]))
+        .body(json!({
+            "size": 10000,
+            "_source": ["text", "is_generated"],
+            "query": { "term": { "video_id": video_id } },
+            "sort": [{ "start_time": { "order": "asc" } }]
+        }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Indexed caption search failed: {}",
+            response.status_code()
+        ));
+    }
+
+    let json_response: Value = response.json().await?;
+    let indexed: Vec<&Value> = json_response["hits"]["hits"]
+        .as_array()
+        .map(|hits| hits.iter().map(|hit| &hit["_source"]).collect())
+        .unwrap_or_default();
+    if indexed.is_empty() {
+        return Ok(TranscriptChange::New);
+    }
+
+    let before = fingerprint(
+        indexed
+            .iter()
+            .map(|c| c["text"].as_str().unwrap_or_default()),
+    );
+    let after = fingerprint(captions.iter().map(|c| c.text.as_str()));
+    if before == after {
+        return Ok(TranscriptChange::Unchanged);
+    }
+    let was_generated = indexed
+        .iter()
+        .any(|c| c["is_generated"].as_bool().unwrap_or(false));
+    Ok(if was_generated && !is_generated {
+        TranscriptChange::Upgraded
+    } else {
+        TranscriptChange::Changed
+    })
+}
+
+/// Deletes the captions of a video that are not among `keep_ids`, the lines of a
+/// replaced transcript that the new one didn't overwrite
+pub async fn delete_stale_captions(
+    es_client: &Elasticsearch,
+    video_id: &str,
+    keep_ids: &[String],
+) -> Result<()> {
+    let response = es_client
+        .delete_by_query(DeleteByQueryParts::Index(&[INDEX_NAMES.captions]))
+        .conflicts(Conflicts::Proceed)
+        .refresh(true)
+        .body(json!({
+            "query": {
+                "bool": {
+                    "filter": [{ "term": { "video_id": video_id } }],
+                    "must_not": [{ "ids": { "values": keep_ids } }]
+                }
+            }
+        }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Deleting stale captions failed: {}",
+            response.status_code()
+        ));
+    }
+    let json_response: Value = response.json().await?;
+    let deleted = json_response["deleted"].as_i64().unwrap_or(0);
+    if deleted > 0 {
+        info!("Deleted {deleted} captions of the replaced transcript of video {video_id}");
+    }
+    Ok(())
+}
+
+/// Videos with auto-generated captions crawled before `cutoff` (unix seconds) and not
+/// checked for a manual track since, least recently checked first
+async fn sweep_candidates(es_client: &Elasticsearch, cutoff: i64) -> Result<Vec<String>> {
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.videos]))
+        .body(json!({
+            "size": *CAPTION_UPDATE_SAMPLE_SIZE,
+            "_source": false,
+            "query": {
+                "bool": {
+                    "filter": [
+                        { "term": { "caption_source.keyword": "generated" } },
+                        { "range": { "crawl_date": { "lte": cutoff } } }
+                    ],
+                    "must_not": [
+                        { "term": { "deleting": true } },
+                        { "range": { "caption_checked_at": { "gt": cutoff } } }
+                    ]
+                }
+            },
+            "sort": [
+                { "caption_checked_at": { "order": "asc", "missing": "_first", "unmapped_type": "long" } },
+                { "crawl_date": { "order": "asc" } }
+            ]
+        }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Caption update candidate search failed: {}",
+            response.status_code()
+        ));
+    }
+
+    let json_response: Value = response.json().await?;
+    Ok(json_response["hits"]["hits"]
+        .as_array()
+        .map(|hits| {
+            hits.iter()
+                .filter_map(|hit| hit["_id"].as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Stores the listed tracks, the video isn't sampled again for `CAPTION_UPDATE_MIN_AGE_MONTHS`
+async fn mark_checked(es_client: &Elasticsearch, video_id: &str, tracks: &[CaptionTrack]) {
+    if let Err(e) = es_client
+        .update(UpdateParts::IndexId(INDEX_NAMES.videos, video_id))
+        .body(json!({
+            "doc": {
+                "caption_tracks": tracks,
+                "caption_checked_at": chrono::Utc::now().timestamp()
+            }
+        }))
+        .send()
+        .await
+    {
+        warn!("Failed to mark the captions of video {video_id} as checked: {e:?}");
+    }
+}
+
+/// Scheduled sweep: lists the caption tracks of a sample of videos indexed with
+/// auto-generated captions and queues a recrawl of those that have a manual track in a
+/// prioritized language now. The recrawl logs the upgrade in the indexing log. Runs under
+/// the maintenance `run`, pausing after every batch of checked videos.
+pub async fn run_caption_update_sweep(
+    es_client: &Elasticsearch,
+    video_queue: &VideoQueue,
+    run: &MaintenanceRun,
+) {
+    let cutoff =
+        chrono::Utc::now().timestamp() - *CAPTION_UPDATE_MIN_AGE_MONTHS * 30 * 24 * 60 * 60;
+    let video_ids = match sweep_candidates(es_client, cutoff).await {
+        Ok(video_ids) => video_ids,
+        Err(e) => {
+            error!("Failed to load caption update candidates: {e:?}");
+            return;
+        }
+    };
+
+    let mut checked = 0;
+    let mut queued = 0;
+    for (i, video_id) in video_ids.iter().enumerate() {
+        if i > 0 && i % run.throttle().batch_size == 0 {
+            run.pause().await;
+        }
+        run.progress(i as i64, video_ids.len() as i64);
+        if TRANSCRIPT_THROTTLE.cooldown_until().is_some() {
+            info!("Caption fetching is paused, the caption update sweep stops early");
+            break;
+        }
+        // Failed listings are not marked, the video comes up again in the next sweep
+        let Some(tracks) = crawler::list_caption_tracks(video_id).await else {
+            continue;
+        };
+        mark_checked(es_client, video_id, &tracks).await;
+        checked += 1;
+        if crawler::select_caption_track(&tracks, &LANGUAGE_PRIORITY)
+            .is_some_and(|t| !t.is_generated)
+        {
+            info!("Video {video_id} has a manual caption track now, queueing a recrawl");
+            video_queue.add_video(video_id.clone());
+            queued += 1;
+        }
+    }
+    info!(
+        "Caption update sweep checked {checked} of {} videos, {queued} queued for a recrawl",
+        video_ids.len()
+    );
+}
@@ -1,19 +1,38 @@
-use crate::config::{LANGUAGE_PRIORITY, YOUTUBE_API_KEY};
-use crate::models::{Caption, QueueItem, VideoMetadata};
+use crate::config::{
+    CRAWL_ITEM_TIMEOUT_MINUTES, INDEX_METADATA_FOR_SKIPPED, INDEX_NAMES, LANGUAGE_PRIORITY,
+    MAX_CAPTION_CHARS, MAX_VIDEO_DURATION_SECONDS, SIMULATION_MODE, YOUTUBE_API_KEY,
+};
+use crate::models::{
+    caption_segment, Caption, CaptionTrack, CheckSource, CrawlHistoryEntry, IndexingLogEntry,
+    MetadataSnapshot, QueueItem, VideoMetadata,
+};
+use crate::services::archive_generation::ArchiveGeneration;
+use crate::services::caption_update_service::{self, TranscriptChange};
+use crate::services::health_service::HEALTH;
+use crate::services::indexing_log_service;
+use crate::services::monitoring_service;
+use crate::services::notification_service::ACTIVITY;
+use crate::services::optout_service;
+use crate::services::simulation;
+use crate::services::transcript_throttle::is_blocked_error;
+use crate::services::transcript_throttle::TRANSCRIPT_THROTTLE;
+use crate::services::youtube_api::{self, NetworkTimeout, Route, YOUTUBE_PROXIES};
 use crate::utils;
-use elasticsearch::{Elasticsearch, IndexParts, UpdateParts};
-use lazy_static::lazy_static;
-use log::{error, info};
-use reqwest::Client;
-use serde_json::json;
+use elasticsearch::{CountParts, Elasticsearch, GetParts, IndexParts, UpdateParts};
+use log::{error, info, warn};
+use serde_json::{json, Value};
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use yt_transcript_rs::api::YouTubeTranscriptApi;
+use std::time::{Duration, Instant};
 
-lazy_static! {
-    static ref YOUTUBE_TRANSCRIPT_API: YouTubeTranscriptApi =
-        YouTubeTranscriptApi::new(None, None, None).expect("Failed to create YouTubeTranscriptApi");
-}
+/// Number of crawl-history entries kept per video
+const MAX_CRAWL_HISTORY: usize = 20;
+/// `failure_kind` of items processing for longer than CRAWL_ITEM_TIMEOUT_MINUTES, whether
+/// the crawl noticed itself or the watchdog of a later pass did
+const FAILURE_TIMEOUT: &str = "timeout";
+/// `skip_reason` of videos longer than the maximum duration
+const SKIP_REASON_TOO_LONG: &str = "too_long";
 
 pub fn split_language_codes(language_codes: &str) -> Vec<&str> {
     // vec!["en", "en-GB", "en-US", "de", "de-DE"]
@@ -25,6 +44,19 @@ pub fn split_language_codes(language_codes: &str) -> Vec<&str> {
 
 pub struct VideoQueue {
     queue: Arc<Mutex<VecDeque<QueueItem>>>,
+    crawl_running: Arc<AtomicBool>,
+}
+
+/// Holds the crawl flag of a `VideoQueue` while a pass runs, releases it on drop so a
+/// panicking pass doesn't leave the queue marked as crawling
+pub struct CrawlGuard {
+    crawl_running: Arc<AtomicBool>,
+}
+
+impl Drop for CrawlGuard {
+    fn drop(&mut self) {
+        self.crawl_running.store(false, Ordering::SeqCst);
+    }
 }
 
 impl Default for VideoQueue {
@@ -38,20 +70,43 @@ impl VideoQueue {
         let queue = VecDeque::new();
         VideoQueue {
             queue: Arc::new(Mutex::new(queue)),
+            crawl_running: Arc::new(AtomicBool::new(false)),
         }
     }
 
-    pub fn add_playlist_video(&self, video_id: String, playlist_id: Option<String>) -> String {
+    /// Marks a crawl pass as running until the returned guard is dropped. Returns None if
+    /// another pass already is.
+    pub fn try_begin_crawl(&self) -> Option<CrawlGuard> {
+        self.crawl_running
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .ok()?;
+        Some(CrawlGuard {
+            crawl_running: self.crawl_running.clone(),
+        })
+    }
+
+    pub fn is_crawl_running(&self) -> bool {
+        self.crawl_running.load(Ordering::SeqCst)
+    }
+
+    /// Enqueues a video found by a monitor check, `source` is carried into its metadata
+    pub fn add_discovered_video(&self, video_id: String, source: Option<CheckSource>) -> String {
         if let Ok(mut queue) = self.queue.lock() {
-            let item_id = format!("{}_{}", chrono::Utc::now().timestamp(), video_id);
+            let now = chrono::Utc::now();
+            let item_id = format!("{}_{}", now.timestamp(), video_id);
             let item = QueueItem {
                 id: item_id.clone(),
                 video_id,
                 status: "pending".to_string(),
-                added_at: chrono::Utc::now().to_rfc3339(),
+                added_at: now.to_rfc3339(),
                 processed_at: None,
+                added_at_ms: now.timestamp_millis(),
+                processed_at_ms: None,
+                started_at_ms: None,
                 error_message: None,
-                playlist_id,
+                failure_kind: None,
+                retries: 0,
+                source,
             };
             queue.push_back(item);
             item_id
@@ -61,47 +116,96 @@ impl VideoQueue {
     }
 
     pub fn add_video(&self, video_id: String) -> String {
-        self.add_playlist_video(video_id, None)
+        self.add_discovered_video(video_id, None)
     }
 
+    /// Marks the next pending item as processing. It stays in the queue until it is
+    /// completed, so failed and stalled items remain visible in the admin queue.
     pub fn pop_next_video(&self) -> Option<QueueItem> {
         if let Ok(mut queue) = self.queue.lock() {
-            if let Some(mut item) = queue.pop_front() {
-                item.status = "processing".to_string();
-                Some(item)
-            } else {
-                None
-            }
+            let item = queue.iter_mut().find(|item| item.status == "pending")?;
+            item.status = "processing".to_string();
+            item.started_at_ms = Some(chrono::Utc::now().timestamp_millis());
+            Some(item.clone())
         } else {
             None
         }
     }
 
     pub fn mark_completed(&self, item_id: &str) {
-        if let Ok(mut queue) = self.queue.lock() {
-            for item in queue.iter_mut() {
-                if item.id == item_id {
-                    item.status = "completed".to_string();
-                    item.processed_at = Some(chrono::Utc::now().to_rfc3339());
-                    break;
-                }
-            }
-        }
+        self.remove_item(item_id);
     }
 
-    pub fn mark_failed(&self, item_id: &str, error_message: String) {
+    pub fn mark_failed(&self, item_id: &str, failure_kind: &str, error_message: String) {
         if let Ok(mut queue) = self.queue.lock() {
             for item in queue.iter_mut() {
                 if item.id == item_id {
                     item.status = "failed".to_string();
-                    item.processed_at = Some(chrono::Utc::now().to_rfc3339());
+                    let now = chrono::Utc::now();
+                    item.processed_at = Some(now.to_rfc3339());
+                    item.processed_at_ms = Some(now.timestamp_millis());
                     item.error_message = Some(error_message);
+                    item.failure_kind = Some(failure_kind.to_string());
                     break;
                 }
             }
         }
     }
 
+    /// Marks items processing for longer than `timeout` as failed. Returns their count.
+    pub fn fail_stalled_items(&self, timeout: Duration) -> usize {
+        let cutoff = chrono::Utc::now().timestamp_millis() - timeout.as_millis() as i64;
+        let stalled: Vec<String> = match self.queue.lock() {
+            Ok(queue) => queue
+                .iter()
+                .filter(|item| {
+                    item.status == "processing"
+                        && item.started_at_ms.is_some_and(|started| started < cutoff)
+                })
+                .map(|item| item.id.clone())
+                .collect(),
+            Err(_) => return 0,
+        };
+
+        for item_id in &stalled {
+            self.mark_failed(item_id, FAILURE_TIMEOUT, timeout_message(timeout));
+        }
+        stalled.len()
+    }
+
+    /// Resets failed items to pending, only those that failed with `failure_kind` if
+    /// given. Returns their count.
+    pub fn retry_failed(&self, failure_kind: Option<&str>) -> usize {
+        let Ok(mut queue) = self.queue.lock() else {
+            return 0;
+        };
+        let mut retried = 0;
+        for item in queue.iter_mut().filter(|item| {
+            item.status == "failed"
+                && failure_kind.is_none_or(|kind| item.failure_kind.as_deref() == Some(kind))
+        }) {
+            item.status = "pending".to_string();
+            item.retries += 1;
+            item.started_at_ms = None;
+            item.processed_at = None;
+            item.processed_at_ms = None;
+            item.error_message = None;
+            item.failure_kind = None;
+            retried += 1;
+        }
+        retried
+    }
+
+    /// Removes every item with `status`. Returns their count.
+    pub fn clear_status(&self, status: &str) -> usize {
+        let Ok(mut queue) = self.queue.lock() else {
+            return 0;
+        };
+        let before = queue.len();
+        queue.retain(|item| item.status != status);
+        before - queue.len()
+    }
+
     pub fn get_all_items(&self) -> Vec<QueueItem> {
         if let Ok(queue) = self.queue.lock() {
             queue.iter().cloned().collect()
@@ -120,17 +224,20 @@ impl VideoQueue {
         false
     }
 
+    /// Number of items still waiting to be processed
     pub fn get_size(&self) -> usize {
         if let Ok(queue) = self.queue.lock() {
-            queue.len()
+            queue.iter().filter(|item| item.status == "pending").count()
         } else {
             0
         }
     }
 }
 
-async fn fetch_video_metadata(video_id: &str) -> Result<VideoMetadata, Box<dyn std::error::Error>> {
-    let client = Client::new();
+async fn fetch_video_metadata(video_id: &str) -> anyhow::Result<VideoMetadata> {
+    if *SIMULATION_MODE {
+        return Ok(simulation::video_metadata(video_id));
+    }
     let api_key = &*YOUTUBE_API_KEY;
 
     // Documentation: https://developers.google.com/youtube/v3/docs/videos
@@ -138,15 +245,20 @@ async fn fetch_video_metadata(video_id: &str) -> Result<VideoMetadata, Box<dyn s
         "https://www.googleapis.com/youtube/v3/videos?id={video_id}&key={api_key}&part=snippet,statistics,contentDetails"
     );
 
-    let response = client
-        .get(&url)
-        .send()
-        .await?
-        .json::<serde_json::Value>()
-        .await?;
+    let response = youtube_api::get_video_json(&url, video_id, "videos").await?;
+    Ok(parse_video_metadata(
+        video_id,
+        &response,
+        chrono::Utc::now().timestamp(),
+    ))
+}
+
+/// Metadata of a video from a Data API `videos` response. Pure, so captured responses
+/// (see `raw_capture`) can be parsed again after the fact.
+pub fn parse_video_metadata(video_id: &str, response: &Value, crawl_date: i64) -> VideoMetadata {
     let item = &response["items"][0];
 
-    Ok(VideoMetadata {
+    VideoMetadata {
         title: item["snippet"]["title"].as_str().unwrap_or("").to_string(),
         channel_id: item["snippet"]["channelId"]
             .as_str()
@@ -157,10 +269,7 @@ async fn fetch_video_metadata(video_id: &str) -> Result<VideoMetadata, Box<dyn s
             .unwrap_or("")
             .to_string(),
         upload_date: utils::parse_iso8601_to_timestamp(
-            &*item["snippet"]["publishedAt"]
-                .as_str()
-                .unwrap_or("")
-                .to_string(),
+            item["snippet"]["publishedAt"].as_str().unwrap_or(""),
         ),
         likes: item["statistics"]["likeCount"]
             .as_str()
@@ -173,10 +282,7 @@ async fn fetch_video_metadata(video_id: &str) -> Result<VideoMetadata, Box<dyn s
             .parse()
             .unwrap_or(0),
         duration: utils::parse_iso8601_duration_to_seconds(
-            &*item["contentDetails"]["duration"]
-                .as_str()
-                .unwrap_or("")
-                .to_string(),
+            item["contentDetails"]["duration"].as_str().unwrap_or(""),
         ),
         comment_count: item["statistics"]["commentCount"]
             .as_str()
@@ -193,42 +299,202 @@ async fn fetch_video_metadata(video_id: &str) -> Result<VideoMetadata, Box<dyn s
             })
             .unwrap_or_default(),
         has_captions: false,
-        crawl_date: chrono::Utc::now().timestamp(),
+        crawl_date,
         video_id: video_id.to_string(),
         playlists: vec![],
-    })
+        crawl_history: vec![],
+        hidden: false,
+        deleting: false,
+        caption_tracks: vec![],
+        caption_source: None,
+        discovered_by: None,
+        previous: None,
+        skip_reason: None,
+        caption_coverage: None,
+    }
+}
+
+/// Channel of a video as YouTube reports it, None if the lookup failed
+pub async fn fetch_video_channel_id(video_id: &str) -> Option<String> {
+    fetch_video_metadata(video_id)
+        .await
+        .ok()
+        .map(|metadata| metadata.channel_id)
+        .filter(|channel_id| !channel_id.is_empty())
+}
+
+/// What the crawl did with the metadata of a video
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataOutcome {
+    Indexed,
+    /// Fetching or indexing failed, after a failed fetch nothing was indexed
+    Failed,
+    /// The video's channel opted out, nothing was indexed
+    OptedOut,
+    /// Longer than the maximum duration, captions must not be fetched. Its metadata is
+    /// indexed unless INDEX_METADATA_FOR_SKIPPED is off.
+    TooLong,
+}
+
+/// Metadata of a video as a crawl would index it, merged with what was indexed before,
+/// and the filters that apply to it. Nothing is written.
+pub struct PreparedMetadata {
+    pub metadata: VideoMetadata,
+    /// False if fetching failed and `metadata` only holds what was indexed before, empty
+    /// for videos that weren't
+    pub fetched: bool,
+    pub opted_out: bool,
+    /// Maximum duration the video exceeds, with the monitor that set it (None if global)
+    pub too_long: Option<(i64, Option<CheckSource>)>,
+}
+
+/// Records where a crawled video came from: the monitor that discovered it and the
+/// playlists it is known to be in, merged with what the indexed document already has
+fn attribute_source(
+    metadata: &mut VideoMetadata,
+    indexed: Option<&VideoMetadata>,
+    source: Option<CheckSource>,
+) {
+    if let Some(CheckSource::Playlist(playlist_id)) = &source {
+        metadata.playlists.push(playlist_id.clone());
+    }
+    if let Some(indexed) = indexed {
+        for playlist in &indexed.playlists {
+            if !metadata.playlists.contains(playlist) {
+                metadata.playlists.push(playlist.clone());
+            }
+        }
+    }
+    // Provenance is kept from the first crawl, recrawls don't carry a source
+    metadata.discovered_by = indexed
+        .and_then(|indexed| indexed.discovered_by.clone())
+        .or(source);
 }
 
-pub async fn process_video_metadata(
+/// Fetches the metadata of a video and applies the opt-out and duration filters
+pub async fn prepare_video_metadata(
     es_client: &Elasticsearch,
     video_id: &str,
-    playlist_id: Option<String>,
-) {
-    let mut metadata = fetch_video_metadata(&video_id).await.unwrap_or_else(|e| {
-        error!("Failed to fetch metadata for video {}: {:?}", video_id, e);
-        VideoMetadata {
-            title: String::new(),
-            channel_name: String::new(),
-            channel_id: String::new(),
-            upload_date: 0,
-            likes: 0,
-            views: 0,
-            duration: 0,
-            comment_count: 0,
-            tags: Vec::new(),
-            has_captions: false,
-            crawl_date: 0,
-            video_id: String::new(),
-            playlists: vec![],
+    source: Option<CheckSource>,
+) -> PreparedMetadata {
+    let fetch = fetch_video_metadata(video_id).await;
+    prepare_fetched_metadata(es_client, video_id, source, fetch).await
+}
+
+/// Like `prepare_video_metadata`, with the result of fetching the metadata from YouTube
+pub(crate) async fn prepare_fetched_metadata(
+    es_client: &Elasticsearch,
+    video_id: &str,
+    source: Option<CheckSource>,
+    fetch: anyhow::Result<VideoMetadata>,
+) -> PreparedMetadata {
+    let (mut metadata, fetched) = match fetch {
+        Ok(metadata) => (metadata, true),
+        Err(e) => {
+            error!("Failed to fetch metadata for video {}: {:?}", video_id, e);
+            if youtube_api::is_timeout(&e) {
+                ACTIVITY.record_failure("network_timeout");
+            } else {
+                ACTIVITY.record_failure("metadata_fetch");
+            }
+            // The filters still apply to videos indexed before
+            let indexed = fetch_indexed_metadata(es_client, video_id).await;
+            (indexed.unwrap_or_default(), false)
         }
-    });
+    };
+
+    let opted_out = optout_service::is_opted_out(&metadata.channel_id).await;
+
+    let max_duration = match monitoring_service::max_duration_override(
+        source.as_ref(),
+        &metadata.channel_id,
+    )
+    .await
+    {
+        Some((seconds, monitor)) => Some((seconds, Some(monitor))),
+        None => MAX_VIDEO_DURATION_SECONDS.map(|seconds| (seconds, None)),
+    };
+    let too_long = max_duration.filter(|(max_duration, _)| metadata.duration > *max_duration);
+    if too_long.is_some() {
+        metadata.skip_reason = Some(SKIP_REASON_TOO_LONG.to_string());
+    }
+
+    // Keep new videos of hidden channels out of public search
+    metadata.hidden =
+        metadata.hidden || monitoring_service::is_channel_hidden(&metadata.channel_id).await;
+
+    if fetched {
+        let indexed = fetch_indexed_metadata(es_client, video_id).await;
+        let mut crawl_history = indexed
+            .as_ref()
+            .map(|indexed| indexed.crawl_history.clone())
+            .unwrap_or_default();
+        metadata.previous = indexed.as_ref().map(MetadataSnapshot::from);
+        attribute_source(&mut metadata, indexed.as_ref(), source);
+        // Recomputed once the captions are indexed, kept if they are deferred
+        metadata.caption_coverage = indexed
+            .as_ref()
+            .and_then(|indexed| indexed.caption_coverage);
+        crawl_history.push(CrawlHistoryEntry {
+            crawl_date: metadata.crawl_date,
+            views: metadata.views,
+            likes: metadata.likes,
+            caption_count: count_video_captions(es_client, video_id).await,
+        });
+        if crawl_history.len() > MAX_CRAWL_HISTORY {
+            crawl_history.drain(..crawl_history.len() - MAX_CRAWL_HISTORY);
+        }
+        metadata.crawl_history = crawl_history;
+    }
+
+    PreparedMetadata {
+        metadata,
+        fetched,
+        opted_out,
+        too_long,
+    }
+}
+
+/// Indexes the fetched metadata of a video unless a filter excludes it or fetching failed
+async fn index_prepared_metadata(
+    es_client: &Elasticsearch,
+    video_id: &str,
+    prepared: PreparedMetadata,
+) -> MetadataOutcome {
+    let PreparedMetadata {
+        metadata,
+        fetched,
+        opted_out,
+        too_long,
+    } = prepared;
+
+    // Last guard of the opt-out, whichever way the video got into the queue
+    if opted_out {
+        info!(
+            "Skipping video {} of opted-out channel {}",
+            video_id, metadata.channel_id
+        );
+        return MetadataOutcome::OptedOut;
+    }
 
-    if playlist_id.is_some() {
-        metadata.playlists.push(playlist_id.unwrap().to_string());
+    // Nothing to index, writing what could be fetched would reset the indexed document,
+    // e.g. unhide a video of a hidden channel
+    if !fetched {
+        return MetadataOutcome::Failed;
+    }
+
+    if let Some((max_duration, _)) = too_long {
+        info!(
+            "Skipping captions of video {}, {}s is longer than the maximum of {}s",
+            video_id, metadata.duration, max_duration
+        );
+        if !*INDEX_METADATA_FOR_SKIPPED {
+            return MetadataOutcome::TooLong;
+        }
     }
 
     match es_client
-        .index(IndexParts::IndexId("youtube_videos", &video_id))
+        .index(IndexParts::IndexId(INDEX_NAMES.videos, video_id))
         .body(json!(metadata))
         .send()
         .await
@@ -240,6 +506,8 @@ pub async fn process_video_metadata(
                     video_id,
                     response.text().await
                 );
+                ACTIVITY.record_failure("metadata_index");
+                MetadataOutcome::Failed
             } else {
                 info!(
                     "Processed YT-Video: {}\nChannel: {} -> {}, Upload Date: {}, Crawl Date: {}\nDuration: {}, Views: {}, Likes: {}, Comments: {} Captions: {},\nTags: {}",
@@ -255,6 +523,11 @@ pub async fn process_video_metadata(
                     metadata.has_captions,
                     metadata.tags.join(", "),
                 );
+                if too_long.is_some() {
+                    MetadataOutcome::TooLong
+                } else {
+                    MetadataOutcome::Indexed
+                }
             }
         }
         Err(e) => {
@@ -262,16 +535,182 @@ pub async fn process_video_metadata(
                 "Failed to send metadata to Elasticsearch for video ID {}: {e:?}",
                 video_id
             );
+            ACTIVITY.record_failure("metadata_index");
+            MetadataOutcome::Failed
         }
     }
 }
 
-async fn update_has_captions(es_client: &Elasticsearch, video_id: &str) {
+/// Returns the metadata of an already indexed video (None for new videos)
+async fn fetch_indexed_metadata(
+    es_client: &Elasticsearch,
+    video_id: &str,
+) -> Option<VideoMetadata> {
+    let response = match es_client
+        .get(GetParts::IndexId(INDEX_NAMES.videos, video_id))
+        .send()
+        .await
+    {
+        Ok(response) if response.status_code().is_success() => response,
+        _ => return None,
+    };
+
+    response
+        .json::<serde_json::Value>()
+        .await
+        .ok()
+        .and_then(|json| serde_json::from_value(json["_source"].clone()).ok())
+}
+
+/// Whether the already indexed video is hidden, captions inherit the flag
+async fn is_video_hidden(es_client: &Elasticsearch, video_id: &str) -> bool {
     match es_client
-        .update(UpdateParts::IndexId("youtube_videos", video_id))
+        .get(GetParts::IndexId(INDEX_NAMES.videos, video_id))
+        ._source_includes(&["hidden"])
+        .send()
+        .await
+    {
+        Ok(response) if response.status_code().is_success() => response
+            .json::<serde_json::Value>()
+            .await
+            .ok()
+            .and_then(|json| json["_source"]["hidden"].as_bool())
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Fields of an indexed video its captions need
+#[derive(Default)]
+struct CaptionedVideo {
+    upload_date: Option<i64>,
+    channel_id: Option<String>,
+    duration: i64,
+}
+
+/// Upload date, channel and duration of an indexed video. The upload date and channel
+/// are copied onto its captions, the duration gives their coverage.
+async fn captioned_video(es_client: &Elasticsearch, video_id: &str) -> CaptionedVideo {
+    match es_client
+        .get(GetParts::IndexId(INDEX_NAMES.videos, video_id))
+        ._source_includes(&["upload_date", "channel_id", "duration"])
+        .send()
+        .await
+    {
+        Ok(response) if response.status_code().is_success() => {
+            let json = response
+                .json::<serde_json::Value>()
+                .await
+                .unwrap_or_default();
+            CaptionedVideo {
+                upload_date: json["_source"]["upload_date"].as_i64(),
+                channel_id: json["_source"]["channel_id"].as_str().map(str::to_string),
+                duration: json["_source"]["duration"].as_i64().unwrap_or(0),
+            }
+        }
+        _ => CaptionedVideo::default(),
+    }
+}
+
+/// Seconds of a video covered by at least one caption. Lines of auto-generated tracks
+/// overlap, overlapping seconds count once.
+pub fn captioned_seconds(mut intervals: Vec<(f64, f64)>) -> f64 {
+    intervals.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut total = 0.0;
+    let mut covered_until = f64::NEG_INFINITY;
+    for (start, end) in intervals {
+        let start = start.max(covered_until);
+        if end > start {
+            total += end - start;
+            covered_until = end;
+        }
+    }
+    total
+}
+
+/// Share of a video covered by captions, None if its duration is unknown
+pub fn caption_coverage(captioned_seconds: f64, duration: i64) -> Option<f64> {
+    (duration > 0).then(|| (captioned_seconds / duration as f64).min(1.0))
+}
+
+async fn count_video_captions(es_client: &Elasticsearch, video_id: &str) -> i64 {
+    match es_client
+        .count(CountParts::Index(&[INDEX_NAMES.captions]))
+        .body(json!({ "query": { "term": { "video_id": video_id } } }))
+        .send()
+        .await
+    {
+        Ok(response) => response
+            .json::<serde_json::Value>()
+            .await
+            .ok()
+            .and_then(|json| json["count"].as_i64())
+            .unwrap_or(0),
+        Err(e) => {
+            error!("Failed to count captions for video ID {}: {e:?}", video_id);
+            0
+        }
+    }
+}
+
+async fn update_caption_coverage(es_client: &Elasticsearch, video_id: &str, coverage: Option<f64>) {
+    if let Err(e) = es_client
+        .update(UpdateParts::IndexId(INDEX_NAMES.videos, video_id))
+        .body(json!({ "doc": { "caption_coverage": coverage } }))
+        .send()
+        .await
+    {
+        error!("Failed to update the caption coverage of video ID {video_id}: {e:?}");
+    }
+}
+
+/// Stores the caption count of the current crawl on the latest crawl-history entry
+async fn update_latest_caption_count(es_client: &Elasticsearch, video_id: &str, count: usize) {
+    let script = json!({
+        "script": {
+            "source": "if (ctx._source.crawl_history != null && ctx._source.crawl_history.size() > 0) { ctx._source.crawl_history[ctx._source.crawl_history.size() - 1].caption_count = params.count }",
+            "params": { "count": count }
+        }
+    });
+
+    if let Err(e) = es_client
+        .update(UpdateParts::IndexId(INDEX_NAMES.videos, video_id))
+        .body(script)
+        .send()
+        .await
+    {
+        error!(
+            "Failed to update crawl history for video ID {}: {e:?}",
+            video_id
+        );
+    }
+}
+
+async fn update_caption_tracks(es_client: &Elasticsearch, video_id: &str, tracks: &[CaptionTrack]) {
+    if let Err(e) = es_client
+        .update(UpdateParts::IndexId(INDEX_NAMES.videos, video_id))
         .body(json!({
             "doc": {
-                "has_captions": true
+                "caption_tracks": tracks
+            }
+        }))
+        .send()
+        .await
+    {
+        error!(
+            "Failed to update caption tracks for video ID {}: {e:?}",
+            video_id
+        );
+    }
+}
+
+async fn update_has_captions(es_client: &Elasticsearch, video_id: &str, is_generated: bool) {
+    match es_client
+        .update(UpdateParts::IndexId(INDEX_NAMES.videos, video_id))
+        .body(json!({
+            "doc": {
+                "has_captions": true,
+                "caption_source": caption_source_name(is_generated)
             }
         }))
         .send()
@@ -295,124 +734,588 @@ async fn update_has_captions(es_client: &Elasticsearch, video_id: &str) {
     }
 }
 
-pub async fn process_video_captions(es_client: &Elasticsearch, video_id: &str) {
-    match YOUTUBE_TRANSCRIPT_API.list_transcripts(&video_id).await {
-        Ok(transcript_list) => {
-            // Get available language codes, not display names
-            let available_language_codes: Vec<_> = transcript_list
-                .generated_transcripts
-                .keys()
-                .chain(transcript_list.manually_created_transcripts.keys())
-                .collect();
+/// Picks the track to index: the first language of `priority` that has any track,
+/// preferring the creator-uploaded track over the auto-generated one of that language.
+pub fn select_caption_track<'a>(
+    tracks: &'a [CaptionTrack],
+    priority: &[String],
+) -> Option<&'a CaptionTrack> {
+    priority.iter().find_map(|lang| {
+        let mut in_language = tracks.iter().filter(|t| t.language_code == *lang);
+        in_language
+            .clone()
+            .find(|t| !t.is_generated)
+            .or_else(|| in_language.next())
+    })
+}
 
-            let selected_language_code = LANGUAGE_PRIORITY
-                .iter()
-                .find(|&lang| available_language_codes.iter().any(|&avail| avail == lang))
-                .unwrap_or(&"en".to_string())
-                .to_string();
+/// Blocks through a proxy only take that proxy out of rotation, blocks of the direct
+/// connection pause caption fetching. Returns whether the error was a block.
+fn record_transcript_error(route: &Route, error: &str) -> bool {
+    if !route.is_proxied() {
+        return TRANSCRIPT_THROTTLE.record_error(error);
+    }
+    let blocked = is_blocked_error(error);
+    YOUTUBE_PROXIES.report(route, !blocked);
+    blocked
+}
 
-            // Get the display name for logging
-            let selected_transcript = transcript_list
+fn caption_source_name(is_generated: bool) -> &'static str {
+    if is_generated {
+        "generated"
+    } else {
+        "manual"
+    }
+}
+
+/// A transcript request that timed out says nothing about throttling, only the proxy
+/// is penalized. The video is retried later.
+fn record_transcript_timeout(route: &Route, video_id: &str, timeout: &NetworkTimeout) {
+    error!("{timeout} for video ID {video_id}, retrying later");
+    ACTIVITY.record_failure("network_timeout");
+    YOUTUBE_PROXIES.report(route, false);
+}
+
+/// Result of listing the caption tracks of a video
+enum TrackListing {
+    Listed(Vec<CaptionTrack>),
+    /// Listing failed, `blocked` if YouTube is throttling us
+    Failed {
+        blocked: bool,
+    },
+    TimedOut,
+}
+
+async fn list_tracks_via(route: &Route, video_id: &str) -> TrackListing {
+    TRANSCRIPT_THROTTLE.wait_turn().await;
+    match youtube_api::with_timeout(
+        "transcript list",
+        route.transcript_api.list_transcripts(video_id),
+    )
+    .await
+    {
+        Ok(Ok(transcript_list)) => TrackListing::Listed(
+            transcript_list
                 .manually_created_transcripts
-                .get(&selected_language_code)
-                .or_else(|| {
-                    transcript_list
-                        .generated_transcripts
-                        .get(&selected_language_code)
-                });
+                .values()
+                .chain(transcript_list.generated_transcripts.values())
+                .map(|t| CaptionTrack {
+                    language_code: t.language_code.clone(),
+                    language: t.language.clone(),
+                    is_generated: t.is_generated,
+                })
+                .collect(),
+        ),
+        Ok(Err(e)) => {
+            error!("Failed to list transcripts for video ID {video_id}: {e:?}");
+            ACTIVITY.record_failure("transcript_list");
+            TrackListing::Failed {
+                blocked: record_transcript_error(route, &format!("{e:?}")),
+            }
+        }
+        Err(timeout) => {
+            record_transcript_timeout(route, video_id, &timeout);
+            TrackListing::TimedOut
+        }
+    }
+}
+
+/// Lists the caption tracks of a video without fetching any transcript. None if listing
+/// failed or caption fetching is paused.
+pub async fn list_caption_tracks(video_id: &str) -> Option<Vec<CaptionTrack>> {
+    if *SIMULATION_MODE {
+        return Some(vec![simulation::caption_track()]);
+    }
+    if TRANSCRIPT_THROTTLE.cooldown_until().is_some() {
+        return None;
+    }
+    match list_tracks_via(&YOUTUBE_PROXIES.next_route(), video_id).await {
+        TrackListing::Listed(caption_tracks) => Some(caption_tracks),
+        TrackListing::Failed { .. } | TrackListing::TimedOut => None,
+    }
+}
+
+/// Outcome of fetching the captions of a video
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CaptionsIndexed {
+    pub count: usize,
+    /// None if no transcript was fetched
+    pub change: Option<TranscriptChange>,
+}
+
+/// The transcript of the track selected by `LANGUAGE_PRIORITY`
+pub struct FetchedTranscript {
+    pub track: CaptionTrack,
+    pub captions: Vec<Caption>,
+}
+
+/// Result of fetching the captions of a video, nothing is written yet
+pub enum CaptionFetch {
+    /// `transcript` is None if no track is in a prioritized language
+    Fetched {
+        tracks: Vec<CaptionTrack>,
+        transcript: Option<FetchedTranscript>,
+    },
+    /// Failed in a way retrying won't fix, `tracks` holds what was listed before
+    Failed { tracks: Vec<CaptionTrack> },
+    /// YouTube is throttling transcript fetches or timed out, retry later
+    Deferred,
+}
+
+/// Lists the caption tracks of a video and fetches the transcript of the selected one
+pub async fn fetch_video_captions(es_client: &Elasticsearch, video_id: &str) -> CaptionFetch {
+    if *SIMULATION_MODE {
+        let track = simulation::caption_track();
+        let hidden = is_video_hidden(es_client, video_id).await;
+        return CaptionFetch::Fetched {
+            tracks: vec![track.clone()],
+            transcript: Some(FetchedTranscript {
+                track,
+                captions: simulation::captions(video_id, hidden),
+            }),
+        };
+    }
+
+    if TRANSCRIPT_THROTTLE.cooldown_until().is_some() {
+        info!("Caption fetching is paused, skipping captions of video {video_id}");
+        return CaptionFetch::Deferred;
+    }
+
+    let route = YOUTUBE_PROXIES.next_route();
+    let caption_tracks = match list_tracks_via(&route, video_id).await {
+        TrackListing::Listed(caption_tracks) => caption_tracks,
+        TrackListing::Failed { blocked: true } | TrackListing::TimedOut => {
+            return CaptionFetch::Deferred
+        }
+        TrackListing::Failed { blocked: false } => {
+            return CaptionFetch::Failed { tracks: Vec::new() }
+        }
+    };
+
+    let available_language_codes: Vec<&str> = caption_tracks
+        .iter()
+        .map(|t| t.language_code.as_str())
+        .collect();
+
+    let Some(selected_track) = select_caption_track(&caption_tracks, &LANGUAGE_PRIORITY).cloned()
+    else {
+        info!(
+            "No caption track in a prioritized language for video {}: available codes: {:?}",
+            video_id, available_language_codes
+        );
+        return CaptionFetch::Fetched {
+            tracks: caption_tracks,
+            transcript: None,
+        };
+    };
+    let selected_language_code = selected_track.language_code.clone();
+    let is_generated = selected_track.is_generated;
 
-            let display_name = selected_transcript
-                .map(|t| t.language.as_str())
-                .unwrap_or(&selected_language_code);
+    info!(
+        "Selected {} track \"{}\" ({}) for video {}: available codes: {:?}",
+        caption_source_name(is_generated),
+        selected_track.language,
+        selected_language_code,
+        video_id,
+        available_language_codes
+    );
+
+    let languages = &[selected_language_code.as_str()];
+    TRANSCRIPT_THROTTLE.wait_turn().await;
+    match youtube_api::with_timeout(
+        "transcript fetch",
+        route
+            .transcript_api
+            .fetch_transcript(video_id, languages, false),
+    )
+    .await
+    {
+        Ok(Ok(transcript)) => {
+            TRANSCRIPT_THROTTLE.record_success();
+            YOUTUBE_PROXIES.report(&route, true);
+            let hidden = is_video_hidden(es_client, video_id).await;
+            let mut captions: Vec<Caption> = Vec::new();
 
+            for entry in transcript {
+                let caption = Caption {
+                    video_id: video_id.to_string(),
+                    text: utils::normalize_caption_text(&entry.text),
+                    raw_text: Some(entry.text),
+                    start_time: entry.start,
+                    end_time: entry.start + entry.duration,
+                    hidden,
+                    language: Some(selected_language_code.clone()),
+                    is_generated,
+                    segment: caption_segment(entry.start),
+                    upload_date: None,
+                    channel_id: None,
+                };
+                captions.extend(split_long_caption(caption, *MAX_CAPTION_CHARS));
+            }
             info!(
-                "Selected language \"{}\" ({}) for video {}: available codes: {:?}",
-                display_name, selected_language_code, video_id, available_language_codes
+                "Fetched {} captions for video ID: {video_id}",
+                captions.len()
             );
+            CaptionFetch::Fetched {
+                tracks: caption_tracks,
+                transcript: Some(FetchedTranscript {
+                    track: selected_track,
+                    captions,
+                }),
+            }
+        }
+        Ok(Err(e)) => {
+            error!("Failed to fetch transcript for video ID {video_id}: {e:?}");
+            ACTIVITY.record_failure("transcript_fetch");
+            if record_transcript_error(&route, &format!("{e:?}")) {
+                CaptionFetch::Deferred
+            } else {
+                CaptionFetch::Failed {
+                    tracks: caption_tracks,
+                }
+            }
+        }
+        Err(timeout) => {
+            record_transcript_timeout(&route, video_id, &timeout);
+            CaptionFetch::Deferred
+        }
+    }
+}
 
-            let languages = &[selected_language_code.as_str()];
-            match YOUTUBE_TRANSCRIPT_API
-                .fetch_transcript(&video_id, languages, false)
-                .await
-            {
-                Ok(transcript) => {
-                    let mut captions_to_index: Vec<Caption> = Vec::new();
-
-                    for entry in transcript {
-                        captions_to_index.push(Caption {
-                            video_id: video_id.to_string(),
-                            text: entry.text,
-                            start_time: entry.start,
-                            end_time: entry.start + entry.duration,
-                        });
-                    }
-                    info!(
-                        "Fetched {} captions for video ID: {video_id}",
-                        captions_to_index.len()
-                    );
+/// A caption longer than `max_chars` as several captions, each spanning the share of the
+/// time its text takes up. Whole paragraphs in one caption would swamp snippets and the
+/// neighbor context of search results.
+fn split_long_caption(caption: Caption, max_chars: usize) -> Vec<Caption> {
+    let chunks = utils::split_caption_text(&caption.text, max_chars);
+    if chunks.len() <= 1 {
+        return vec![caption];
+    }
 
-                    let mut captions_success = true;
-
-                    for caption in captions_to_index {
-                        let doc_id = format!("{}_{}", caption.video_id, caption.start_time);
-                        match es_client
-                            .index(IndexParts::IndexId("youtube_captions", &doc_id))
-                            .body(json!(caption))
-                            .send()
-                            .await
-                        {
-                            Ok(response) => {
-                                if response.status_code().is_success() {
-                                    //info!("Indexed caption for video ID: {}", caption.video_id);
-                                } else {
-                                    error!(
-                                        "Failed to index caption for video ID {}: {:?}",
-                                        caption.video_id,
-                                        response.text().await
-                                    );
-                                    captions_success = false;
-                                }
-                            }
-                            Err(e) => {
-                                captions_success = false;
-                                error!(
-                            "Failed to send caption to Elasticsearch for video ID {}: {e:?}",
-                            caption.video_id
-                        );
-                            }
-                        }
-                    }
+    let total_chars = caption.text.chars().count();
+    // Start times are part of the document IDs, a caption without a duration gets one
+    let end_time = caption
+        .end_time
+        .max(caption.start_time + 0.01 * chunks.len() as f64);
+    let end_offsets: Vec<usize> = chunks
+        .iter()
+        .skip(1)
+        .map(|(offset, _)| *offset)
+        .chain([total_chars])
+        .collect();
+    chunks
+        .into_iter()
+        .zip(end_offsets)
+        .map(|((offset, text), end_offset)| {
+            let start_time =
+                utils::interpolate_caption_time(caption.start_time, end_time, offset, total_chars);
+            Caption {
+                // The raw text can't be cut at the same places, chunks keep their own text
+                raw_text: Some(text.clone()),
+                text,
+                start_time,
+                end_time: utils::interpolate_caption_time(
+                    caption.start_time,
+                    end_time,
+                    end_offset,
+                    total_chars,
+                ),
+                segment: caption_segment(start_time),
+                ..caption.clone()
+            }
+        })
+        .collect()
+}
 
-                    if captions_success {
-                        update_has_captions(es_client, video_id).await;
-                    }
-                }
-                Err(e) => {
-                    error!("Failed to fetch transcript for video ID {video_id}: {e:?}");
+/// Fetches and indexes the captions of a video and compares them with the captions
+/// indexed before. Returns None if they were skipped because YouTube is throttling
+/// transcript fetches or timed out, the video should be retried later.
+pub async fn process_video_captions(
+    es_client: &Elasticsearch,
+    video_id: &str,
+) -> Option<CaptionsIndexed> {
+    let (tracks, transcript) = match fetch_video_captions(es_client, video_id).await {
+        CaptionFetch::Fetched { tracks, transcript } => (tracks, transcript),
+        CaptionFetch::Failed { tracks } => (tracks, None),
+        CaptionFetch::Deferred => return None,
+    };
+    if !tracks.is_empty() {
+        update_caption_tracks(es_client, video_id, &tracks).await;
+    }
+    match transcript {
+        Some(transcript) => Some(
+            index_captions(
+                es_client,
+                video_id,
+                transcript.captions,
+                transcript.track.is_generated,
+            )
+            .await,
+        ),
+        None => Some(CaptionsIndexed::default()),
+    }
+}
+
+/// Indexes the fetched captions of a video and marks it as captioned if all succeeded.
+/// Captions of a replaced transcript that the new one didn't overwrite are deleted.
+async fn index_captions(
+    es_client: &Elasticsearch,
+    video_id: &str,
+    captions_to_index: Vec<Caption>,
+    is_generated: bool,
+) -> CaptionsIndexed {
+    let change = match caption_update_service::compare(
+        es_client,
+        video_id,
+        &captions_to_index,
+        is_generated,
+    )
+    .await
+    {
+        Ok(change) => Some(change),
+        Err(e) => {
+            warn!("Failed to compare the transcript of video {video_id}: {e:?}");
+            None
+        }
+    };
+
+    let mut captions_success = true;
+    let caption_count = captions_to_index.len();
+    let mut indexed = 0;
+    let mut doc_ids = Vec::with_capacity(caption_count);
+    let video = captioned_video(es_client, video_id).await;
+    let coverage = caption_coverage(
+        captioned_seconds(
+            captions_to_index
+                .iter()
+                .map(|caption| (caption.start_time, caption.end_time))
+                .collect(),
+        ),
+        video.duration,
+    );
+
+    for mut caption in captions_to_index {
+        caption.upload_date = video.upload_date;
+        caption.channel_id = video.channel_id.clone();
+        let doc_id = format!("{}_{}", caption.video_id, caption.start_time);
+        match es_client
+            .index(IndexParts::IndexId(INDEX_NAMES.captions, &doc_id))
+            .body(json!(caption))
+            .send()
+            .await
+        {
+            Ok(response) => {
+                if !response.status_code().is_success() {
+                    error!(
+                        "Failed to index caption for video ID {}: {:?}",
+                        caption.video_id,
+                        response.text().await
+                    );
+                    captions_success = false;
+                } else {
+                    indexed += 1;
                 }
             }
+            Err(e) => {
+                captions_success = false;
+                error!(
+                    "Failed to send caption to Elasticsearch for video ID {}: {e:?}",
+                    caption.video_id
+                );
+            }
         }
-        Err(e) => {
-            error!("Failed to list transcripts for video ID {video_id}: {e:?}");
+        doc_ids.push(doc_id);
+    }
+
+    if captions_success {
+        update_has_captions(es_client, video_id, is_generated).await;
+        update_latest_caption_count(es_client, video_id, caption_count).await;
+        update_caption_coverage(es_client, video_id, coverage).await;
+        if change.is_some_and(|change| change.replaces_captions()) {
+            if let Err(e) =
+                caption_update_service::delete_stale_captions(es_client, video_id, &doc_ids).await
+            {
+                error!("Failed to delete stale captions of video {video_id}: {e:?}");
+            }
         }
+    } else {
+        ACTIVITY.record_failure("caption_index");
+    }
+    if let Some(change) = change {
+        info!("Transcript of video {video_id}: {change:?}");
+    }
+    CaptionsIndexed {
+        count: indexed,
+        change,
     }
 }
 
 pub async fn crawl_youtube_video(
     es_client: &Elasticsearch,
     video_queue: &VideoQueue,
+    generation: &ArchiveGeneration,
+    maxcount: i32,
+) {
+    let Some(_crawl) = video_queue.try_begin_crawl() else {
+        info!("YouTube caption crawl already running, skipping.");
+        return;
+    };
+
+    run_crawl_pass(es_client, video_queue, generation, maxcount).await;
+}
+
+fn timeout_message(timeout: Duration) -> String {
+    format!("Timeout: processing took longer than {:?}", timeout)
+}
+
+/// Writes the outcome of a processed queue item to the indexing log. Title and channel
+/// are read back from the index, after a failed metadata fetch they are from the last
+/// successful crawl if there was one.
+async fn record_indexing_log(
+    es_client: &Elasticsearch,
+    item: &QueueItem,
+    started: Instant,
+    failure_kind: Option<&str>,
+    note: Option<&str>,
+    captions_indexed: usize,
+) {
+    let indexed = fetch_indexed_metadata(es_client, &item.video_id).await;
+    let entry = IndexingLogEntry {
+        video_id: item.video_id.clone(),
+        title: indexed
+            .as_ref()
+            .map(|m| m.title.clone())
+            .unwrap_or_default(),
+        channel_id: indexed
+            .as_ref()
+            .map(|m| m.channel_id.clone())
+            .unwrap_or_default(),
+        channel_name: indexed
+            .as_ref()
+            .map(|m| m.channel_name.clone())
+            .unwrap_or_default(),
+        source: item.source.clone(),
+        status: match failure_kind {
+            Some(_) => indexing_log_service::STATUS_FAILED,
+            None => indexing_log_service::STATUS_COMPLETED,
+        }
+        .to_string(),
+        failure_kind: failure_kind.map(str::to_string),
+        note: note.map(str::to_string),
+        captions_indexed: captions_indexed as i64,
+        processing_ms: started.elapsed().as_millis() as i64,
+        finished_at: chrono::Utc::now().timestamp_millis(),
+    };
+    indexing_log_service::record(es_client, &entry).await;
+}
+
+/// Indexes the metadata of a queued video and then its captions, unless a filter excludes
+/// the video or its metadata couldn't be indexed: the channel of a video whose fetch
+/// failed is unknown, it may have opted out. `fetch` is the result of fetching the
+/// metadata. The captions are None if they were deferred.
+async fn crawl_video(
+    es_client: &Elasticsearch,
+    video_id: &str,
+    source: Option<CheckSource>,
+    fetch: anyhow::Result<VideoMetadata>,
+) -> (MetadataOutcome, Option<CaptionsIndexed>) {
+    let prepared = prepare_fetched_metadata(es_client, video_id, source, fetch).await;
+    let metadata = index_prepared_metadata(es_client, video_id, prepared).await;
+    let captions_indexed = match metadata {
+        MetadataOutcome::Indexed => process_video_captions(es_client, video_id).await,
+        MetadataOutcome::Failed | MetadataOutcome::OptedOut | MetadataOutcome::TooLong => {
+            Some(CaptionsIndexed::default())
+        }
+    };
+    (metadata, captions_indexed)
+}
+
+/// Processes up to `maxcount` queued videos, bumping `generation` for each one indexed.
+/// The caller must hold the crawl flag (see `VideoQueue::try_begin_crawl`).
+pub async fn run_crawl_pass(
+    es_client: &Elasticsearch,
+    video_queue: &VideoQueue,
+    generation: &ArchiveGeneration,
     maxcount: i32,
 ) {
     info!("Starting YouTube caption crawl...");
 
+    let timeout = Duration::from_secs(*CRAWL_ITEM_TIMEOUT_MINUTES * 60);
+    let stalled = video_queue.fail_stalled_items(timeout);
+    if stalled > 0 {
+        error!("Marked {} stalled queue items as failed", stalled);
+    }
+
     let mut count = 0;
     while let Some(item) = video_queue.pop_next_video() {
         info!("Processing video ID: {}", item.video_id);
 
-        process_video_metadata(es_client, &item.video_id, item.playlist_id).await;
-        process_video_captions(es_client, &item.video_id).await;
+        let started = Instant::now();
+        let processing = async {
+            let fetch = fetch_video_metadata(&item.video_id).await;
+            crawl_video(es_client, &item.video_id, item.source.clone(), fetch).await
+        };
 
-        video_queue.mark_completed(&item.id);
+        let (failure_kind, note, captions_indexed) = match tokio::time::timeout(timeout, processing)
+            .await
+        {
+            Ok((metadata, captions_indexed)) => {
+                HEALTH.record_crawl_attempt(metadata != MetadataOutcome::Failed);
+                if metadata == MetadataOutcome::Failed {
+                    // Stays in the queue to be retried, e.g. once the quota is reset
+                    video_queue.mark_failed(
+                        &item.id,
+                        "metadata_fetch",
+                        format!("Failed to fetch the metadata of video {}", item.video_id),
+                    );
+                } else {
+                    video_queue.mark_completed(&item.id);
+                }
+                if matches!(
+                    metadata,
+                    MetadataOutcome::Indexed | MetadataOutcome::TooLong
+                ) {
+                    generation.bump();
+                }
+                if captions_indexed.is_none() {
+                    // Metadata is up to date, captions are fetched once the pause is over
+                    video_queue.add_discovered_video(item.video_id.clone(), item.source.clone());
+                }
+                let failure_kind = match metadata {
+                    MetadataOutcome::Failed => Some("metadata_fetch"),
+                    MetadataOutcome::OptedOut => Some("opted_out"),
+                    MetadataOutcome::Indexed if captions_indexed.is_none() => {
+                        Some("captions_deferred")
+                    }
+                    MetadataOutcome::Indexed | MetadataOutcome::TooLong => None,
+                };
+                let captions = captions_indexed.unwrap_or_default();
+                let note = if metadata == MetadataOutcome::TooLong {
+                    Some(indexing_log_service::NOTE_SKIPPED_TOO_LONG)
+                } else {
+                    captions.change.and_then(|change| change.note())
+                };
+                (failure_kind, note, captions.count)
+            }
+            Err(_) => {
+                error!(
+                    "Processing video ID {} timed out after {:?}",
+                    item.video_id, timeout
+                );
+                HEALTH.record_crawl_attempt(false);
+                ACTIVITY.record_failure(FAILURE_TIMEOUT);
+                video_queue.mark_failed(&item.id, FAILURE_TIMEOUT, timeout_message(timeout));
+                (Some(FAILURE_TIMEOUT), None, 0)
+            }
+        };
+        record_indexing_log(
+            es_client,
+            &item,
+            started,
+            failure_kind,
+            note,
+            captions_indexed,
+        )
+        .await;
 
         count += 1;
         if count >= maxcount {
@@ -422,3 +1325,525 @@ pub async fn crawl_youtube_video(
     }
     info!("YouTube caption crawl completed.");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::admin_service;
+    use crate::testsupport::{opt_out, MockElasticsearch};
+
+    /// A Data API `videos` response as captured by `raw_capture`
+    fn sample_videos_response() -> Value {
+        serde_json::from_str(include_str!("testdata/youtube_videos_response.json")).unwrap()
+    }
+
+    #[test]
+    fn captured_videos_responses_replay() {
+        let metadata =
+            parse_video_metadata("dQw4w9WgXcQ", &sample_videos_response(), 1_700_000_000);
+
+        assert_eq!(metadata.video_id, "dQw4w9WgXcQ");
+        assert_eq!(
+            metadata.title,
+            "Rick Astley - Never Gonna Give You Up (Official Music Video)"
+        );
+        assert_eq!(metadata.channel_id, "UCuAXFkgsw1L7xaCfnd5JJOw");
+        assert_eq!(metadata.channel_name, "Rick Astley");
+        assert_eq!(metadata.upload_date, 1_256_453_853);
+        assert_eq!(metadata.duration, 213);
+        assert_eq!(metadata.views, 1_689_632_412);
+        assert_eq!(metadata.likes, 18_420_351);
+        assert_eq!(metadata.comment_count, 2_401_873);
+        assert_eq!(
+            metadata.tags,
+            ["rick astley", "Never Gonna Give You Up", "nggyu"]
+        );
+        assert_eq!(metadata.crawl_date, 1_700_000_000);
+        assert!(!metadata.has_captions && !metadata.hidden);
+    }
+
+    #[test]
+    fn incomplete_videos_responses_parse_to_empty_values() {
+        let mut response = sample_videos_response();
+        let item = &mut response["items"][0];
+        item["statistics"] = serde_json::json!({ "viewCount": "many" });
+        item["contentDetails"]["duration"] = serde_json::json!("P0D");
+        item["snippet"]
+            .as_object_mut()
+            .unwrap()
+            .retain(|key, _| key == "channelId");
+
+        let metadata = parse_video_metadata("dQw4w9WgXcQ", &response, 0);
+        assert_eq!(metadata.title, "");
+        assert_eq!(metadata.channel_id, "UCuAXFkgsw1L7xaCfnd5JJOw");
+        assert_eq!((metadata.upload_date, metadata.duration), (0, 0));
+        assert_eq!((metadata.views, metadata.likes), (0, 0));
+        assert!(metadata.tags.is_empty());
+
+        // A video YouTube no longer knows
+        let metadata = parse_video_metadata("dQw4w9WgXcQ", &serde_json::json!({ "items": [] }), 0);
+        assert_eq!(metadata.title, "");
+        assert_eq!(metadata.channel_id, "");
+    }
+
+    #[test]
+    fn crawl_flag_is_exclusive_and_released_on_drop() {
+        let queue = VideoQueue::new();
+        let crawl = queue.try_begin_crawl().expect("flag is free");
+        assert!(queue.is_crawl_running());
+        assert!(queue.try_begin_crawl().is_none());
+        drop(crawl);
+        assert!(!queue.is_crawl_running());
+    }
+
+    #[test]
+    fn crawl_flag_is_released_when_the_pass_panics() {
+        let queue = VideoQueue::new();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _crawl = queue.try_begin_crawl().expect("flag is free");
+            panic!("crawl pass failed");
+        }));
+        assert!(result.is_err());
+        assert!(!queue.is_crawl_running());
+        assert!(queue.try_begin_crawl().is_some());
+    }
+
+    fn track(language_code: &str, is_generated: bool) -> CaptionTrack {
+        CaptionTrack {
+            language: language_code.to_string(),
+            language_code: language_code.to_string(),
+            is_generated,
+        }
+    }
+
+    fn priority(codes: &[&str]) -> Vec<String> {
+        codes.iter().map(|code| code.to_string()).collect()
+    }
+
+    #[test]
+    fn manual_track_wins_over_generated_in_the_same_language() {
+        let tracks = [track("en", true), track("de", false), track("en", false)];
+        let selected = select_caption_track(&tracks, &priority(&["en", "de"])).unwrap();
+        assert_eq!(selected, &track("en", false));
+    }
+
+    #[test]
+    fn generated_track_is_the_fallback() {
+        let tracks = [track("de", false), track("en", true)];
+        let selected = select_caption_track(&tracks, &priority(&["en", "de"])).unwrap();
+        assert_eq!(selected, &track("en", true));
+    }
+
+    #[test]
+    fn language_priority_beats_manual_tracks() {
+        // A generated track of a preferred language over a manual one of a later language
+        let tracks = [track("de", false), track("en-GB", true)];
+        let selected = select_caption_track(&tracks, &priority(&["en", "en-GB", "de"])).unwrap();
+        assert_eq!(selected, &track("en-GB", true));
+    }
+
+    #[test]
+    fn no_track_in_a_configured_language() {
+        let tracks = [track("fr", false), track("es", true)];
+        assert_eq!(
+            select_caption_track(&tracks, &priority(&["en", "de"])),
+            None
+        );
+        assert_eq!(select_caption_track(&[], &priority(&["en"])), None);
+        assert_eq!(select_caption_track(&tracks, &[]), None);
+    }
+
+    #[test]
+    fn caption_source_names() {
+        assert_eq!(caption_source_name(false), "manual");
+        assert_eq!(caption_source_name(true), "generated");
+    }
+
+    fn queued_item(video_id: &str, status: &str) -> QueueItem {
+        QueueItem {
+            id: format!("1700000000_{video_id}"),
+            video_id: video_id.to_string(),
+            status: status.to_string(),
+            added_at: "2023-11-14T22:13:20+00:00".to_string(),
+            processed_at: None,
+            added_at_ms: 1_700_000_000_000,
+            processed_at_ms: None,
+            started_at_ms: (status == "processing").then_some(1_700_000_001_000),
+            error_message: None,
+            failure_kind: None,
+            retries: 0,
+            source: None,
+        }
+    }
+
+    /// A queue holding `items` as they are
+    fn queue_of(items: Vec<QueueItem>) -> VideoQueue {
+        let queue = VideoQueue::new();
+        *queue.queue.lock().unwrap() = items.into();
+        queue
+    }
+
+    #[test]
+    fn watchdog_fails_items_processing_too_long() {
+        let queue = queue_of(vec![
+            queued_item("video000001", "pending"),
+            queued_item("video000002", "pending"),
+            queued_item("video000003", "pending"),
+        ]);
+        let stalled = queue.pop_next_video().unwrap();
+        let running = queue.pop_next_video().unwrap();
+        let timeout = Duration::from_secs(15 * 60);
+        {
+            let mut items = queue.queue.lock().unwrap();
+            let now = chrono::Utc::now().timestamp_millis();
+            for item in items.iter_mut() {
+                if item.id == stalled.id {
+                    item.started_at_ms = Some(now - timeout.as_millis() as i64 - 1_000);
+                } else if item.id == running.id {
+                    item.started_at_ms = Some(now - timeout.as_millis() as i64 + 60_000);
+                }
+            }
+        }
+
+        assert_eq!(queue.fail_stalled_items(timeout), 1);
+        let items = queue.get_all_items();
+        assert_eq!(items[0].status, "failed");
+        assert_eq!(items[0].failure_kind.as_deref(), Some(FAILURE_TIMEOUT));
+        assert_eq!(items[0].error_message, Some(timeout_message(timeout)));
+        assert!(items[0].processed_at_ms.is_some());
+        assert_eq!(items[1].status, "processing");
+        assert_eq!(items[2].status, "pending");
+
+        // Failed once, not again on the next pass
+        assert_eq!(queue.fail_stalled_items(timeout), 0);
+    }
+
+    #[test]
+    fn stalled_items_can_be_retried() {
+        let queue = queue_of(vec![queued_item("video000001", "pending")]);
+        let item = queue.pop_next_video().unwrap();
+        queue.mark_failed(&item.id, FAILURE_TIMEOUT, "Timeout".to_string());
+        assert_eq!(queue.retry_failed(Some("metadata_fetch")), 0);
+        assert_eq!(queue.retry_failed(Some(FAILURE_TIMEOUT)), 1);
+
+        let items = queue.get_all_items();
+        assert_eq!(items[0].status, "pending");
+        assert_eq!(items[0].retries, 1);
+        assert_eq!(items[0].failure_kind, None);
+    }
+
+    fn long_caption(start_time: f64, end_time: f64) -> Caption {
+        Caption {
+            video_id: "aaaaaaaaaaa".to_string(),
+            text: "aaaa bbbb cccc dddd".to_string(),
+            raw_text: None,
+            start_time,
+            end_time,
+            hidden: false,
+            language: Some("en".to_string()),
+            is_generated: false,
+            segment: caption_segment(start_time),
+            upload_date: None,
+            channel_id: None,
+        }
+    }
+
+    #[test]
+    fn long_captions_split_by_their_share_of_the_text() {
+        let chunks = split_long_caption(long_caption(100.0, 119.0), 9);
+        let texts: Vec<&str> = chunks.iter().map(|caption| caption.text.as_str()).collect();
+        assert_eq!(texts, ["aaaa bbbb", "cccc dddd"]);
+        assert_eq!(chunks[0].raw_text.as_deref(), Some("aaaa bbbb"));
+        assert_eq!((chunks[0].start_time, chunks[0].end_time), (100.0, 110.0));
+        assert_eq!((chunks[1].start_time, chunks[1].end_time), (110.0, 119.0));
+        assert!(chunks
+            .iter()
+            .all(|caption| caption.language.as_deref() == Some("en")));
+
+        let short = split_long_caption(long_caption(100.0, 119.0), 19);
+        assert_eq!(short.len(), 1);
+        assert_eq!(short[0].raw_text, None);
+    }
+
+    #[test]
+    fn zero_duration_captions_split_into_distinct_start_times() {
+        for (start_time, end_time) in [(0.0, 0.0), (3600.25, 3600.25), (50.0, 49.0)] {
+            let chunks = split_long_caption(long_caption(start_time, end_time), 4);
+            assert_eq!(chunks.len(), 4);
+            assert_eq!(chunks[0].start_time, start_time);
+            for pair in chunks.windows(2) {
+                assert!(
+                    pair[0].start_time < pair[1].start_time,
+                    "{start_time}: {} >= {}",
+                    pair[0].start_time,
+                    pair[1].start_time
+                );
+                assert!(pair[0].end_time <= pair[1].start_time + 1e-9);
+            }
+            // The start times make up the document IDs
+            let ids: std::collections::HashSet<String> = chunks
+                .iter()
+                .map(|caption| format!("{}_{}", caption.video_id, caption.start_time))
+                .collect();
+            assert_eq!(ids.len(), chunks.len());
+        }
+    }
+
+    fn failed_item(video_id: &str, failure_kind: &str, retries: u32) -> QueueItem {
+        let mut item = queued_item(video_id, "failed");
+        item.failure_kind = Some(failure_kind.to_string());
+        item.error_message = Some(format!("{failure_kind} error"));
+        item.processed_at_ms = Some(1_700_000_002_000);
+        item.retries = retries;
+        item
+    }
+
+    /// A queue after a quota outage, one item of every state
+    fn populated_queue() -> VideoQueue {
+        queue_of(vec![
+            queued_item("video000001", "pending"),
+            failed_item("video000002", FAILURE_TIMEOUT, 1),
+            queued_item("video000003", "processing"),
+            failed_item("video000004", "metadata_fetch", 0),
+            failed_item("video000005", FAILURE_TIMEOUT, 0),
+            queued_item("video000006", "pending"),
+        ])
+    }
+
+    fn statuses(queue: &VideoQueue) -> Vec<String> {
+        queue
+            .get_all_items()
+            .into_iter()
+            .map(|item| item.status)
+            .collect()
+    }
+
+    #[test]
+    fn retrying_resets_all_failed_items() {
+        let queue = populated_queue();
+        assert_eq!(queue.retry_failed(None), 3);
+
+        assert_eq!(
+            statuses(&queue),
+            [
+                "pending",
+                "pending",
+                "processing",
+                "pending",
+                "pending",
+                "pending"
+            ]
+        );
+        let items = queue.get_all_items();
+        let retries: Vec<u32> = items.iter().map(|item| item.retries).collect();
+        assert_eq!(retries, [0, 2, 0, 1, 1, 0]);
+        for item in [&items[1], &items[3], &items[4]] {
+            assert_eq!(item.failure_kind, None);
+            assert_eq!(item.error_message, None);
+            assert_eq!(item.processed_at_ms, None);
+        }
+        assert_eq!(queue.get_size(), 5);
+        // Nothing is left to retry
+        assert_eq!(queue.retry_failed(None), 0);
+    }
+
+    #[test]
+    fn retrying_one_failure_kind() {
+        let queue = populated_queue();
+        assert_eq!(queue.retry_failed(Some(FAILURE_TIMEOUT)), 2);
+        assert_eq!(
+            statuses(&queue),
+            [
+                "pending",
+                "pending",
+                "processing",
+                "failed",
+                "pending",
+                "pending"
+            ]
+        );
+        assert_eq!(queue.retry_failed(Some("quota")), 0);
+    }
+
+    #[test]
+    fn clearing_removes_only_the_given_status() {
+        let queue = Arc::new(populated_queue());
+        assert_eq!(admin_service::clear_queue(&queue, "failed").unwrap(), 3);
+        assert_eq!(statuses(&queue), ["pending", "processing", "pending"]);
+        assert_eq!(admin_service::clear_queue(&queue, "failed").unwrap(), 0);
+
+        for status in ["pending", "processing", "completed", "everything"] {
+            assert!(
+                admin_service::clear_queue(&queue, status).is_err(),
+                "{status}"
+            );
+        }
+        assert_eq!(statuses(&queue), ["pending", "processing", "pending"]);
+    }
+
+    #[test]
+    fn retried_items_are_crawled_again() {
+        let queue = populated_queue();
+        queue.retry_failed(Some("metadata_fetch"));
+        let picked: Vec<String> = std::iter::from_fn(|| queue.pop_next_video())
+            .map(|item| item.video_id)
+            .collect();
+        assert_eq!(picked, ["video000001", "video000004", "video000006"]);
+    }
+
+    const CHANNEL: &str = "UCaaaaaaaaaaaaaaaaaaaaaa";
+    const PLAYLIST: &str = "PLaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+
+    #[test]
+    fn discovered_videos_carry_their_source_in_the_queue() {
+        let queue = VideoQueue::new();
+        queue.add_discovered_video(
+            "aaaaaaaaaaa".to_string(),
+            Some(CheckSource::Channel(CHANNEL.to_string())),
+        );
+        queue.add_discovered_video(
+            "bbbbbbbbbbb".to_string(),
+            Some(CheckSource::Playlist(PLAYLIST.to_string())),
+        );
+        let sources: Vec<(String, Option<CheckSource>)> = queue
+            .get_all_items()
+            .into_iter()
+            .map(|item| (item.video_id, item.source))
+            .collect();
+        assert!(sources.contains(&(
+            "aaaaaaaaaaa".to_string(),
+            Some(CheckSource::Channel(CHANNEL.to_string()))
+        )));
+        assert!(sources.contains(&(
+            "bbbbbbbbbbb".to_string(),
+            Some(CheckSource::Playlist(PLAYLIST.to_string()))
+        )));
+    }
+
+    #[test]
+    fn channel_discoveries_are_attributed_to_the_channel() {
+        let mut metadata = simulation::video_metadata("aaaaaaaaaaa");
+        let source = CheckSource::Channel(CHANNEL.to_string());
+        attribute_source(&mut metadata, None, Some(source.clone()));
+        assert_eq!(metadata.discovered_by, Some(source));
+        assert!(
+            metadata.playlists.is_empty(),
+            "the uploads playlist is not a playlist membership"
+        );
+    }
+
+    #[test]
+    fn playlist_discoveries_are_attributed_to_the_playlist() {
+        let mut metadata = simulation::video_metadata("aaaaaaaaaaa");
+        let source = CheckSource::Playlist(PLAYLIST.to_string());
+        attribute_source(&mut metadata, None, Some(source.clone()));
+        assert_eq!(metadata.discovered_by, Some(source));
+        assert_eq!(metadata.playlists, [PLAYLIST]);
+    }
+
+    #[test]
+    fn recrawls_keep_the_first_source() {
+        let mut indexed = simulation::video_metadata("aaaaaaaaaaa");
+        indexed.discovered_by = Some(CheckSource::Channel(CHANNEL.to_string()));
+        indexed.playlists = vec!["PLbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb".to_string()];
+
+        let mut recrawl = simulation::video_metadata("aaaaaaaaaaa");
+        attribute_source(&mut recrawl, Some(&indexed), None);
+        assert_eq!(recrawl.discovered_by, indexed.discovered_by);
+        assert_eq!(recrawl.playlists, indexed.playlists);
+
+        // Found again through a playlist, it is added to the memberships only
+        let mut rediscovered = simulation::video_metadata("aaaaaaaaaaa");
+        attribute_source(
+            &mut rediscovered,
+            Some(&indexed),
+            Some(CheckSource::Playlist(PLAYLIST.to_string())),
+        );
+        assert_eq!(rediscovered.discovered_by, indexed.discovered_by);
+        assert_eq!(
+            rediscovered.playlists,
+            [PLAYLIST, "PLbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb"]
+        );
+    }
+
+    #[test]
+    fn recrawls_of_unattributed_videos_stay_unattributed() {
+        let indexed = simulation::video_metadata("aaaaaaaaaaa");
+        let mut recrawl = simulation::video_metadata("aaaaaaaaaaa");
+        attribute_source(&mut recrawl, Some(&indexed), None);
+        assert_eq!(recrawl.discovered_by, None);
+    }
+
+    /// Fetching the metadata from YouTube failed, e.g. because the quota is exhausted
+    fn quota_exceeded() -> anyhow::Result<VideoMetadata> {
+        Err(anyhow::anyhow!("quotaExceeded"))
+    }
+
+    /// Answers reads of the video's metadata document with `indexed`. Returns the path
+    /// of the document.
+    async fn stub_indexed_video(es: &MockElasticsearch, indexed: &VideoMetadata) -> String {
+        let doc = format!("/{}/_doc/{}", INDEX_NAMES.videos, indexed.video_id);
+        es.stub(
+            "GET",
+            &doc,
+            json!({ "_id": indexed.video_id, "found": true, "_source": indexed }),
+        )
+        .await;
+        doc
+    }
+
+    #[rocket::async_test]
+    async fn failed_refetches_keep_hidden_videos_hidden() {
+        let es = MockElasticsearch::start().await;
+        let mut indexed = simulation::video_metadata("hiddenVideo");
+        indexed.hidden = true;
+        let doc = stub_indexed_video(&es, &indexed).await;
+
+        let prepared =
+            prepare_fetched_metadata(&es.client, "hiddenVideo", None, quota_exceeded()).await;
+        assert!(!prepared.fetched);
+        assert_eq!(prepared.metadata.channel_id, indexed.channel_id);
+        assert!(prepared.metadata.hidden);
+        assert_eq!(
+            index_prepared_metadata(&es.client, "hiddenVideo", prepared).await,
+            MetadataOutcome::Failed
+        );
+        for method in ["PUT", "POST"] {
+            assert!(es.bodies(method, &doc).await.is_empty(), "{method}");
+        }
+    }
+
+    #[rocket::async_test]
+    async fn failed_fetches_index_no_captions_of_opted_out_channels() {
+        let es = MockElasticsearch::start().await;
+        let opted_out = "UCoptedOutFailedFetch001";
+        opt_out(&es, opted_out).await;
+        let written_before = es.paths("PUT").await.len();
+
+        // Indexed before the channel opted out, its channel is known
+        let indexed = VideoMetadata {
+            channel_id: opted_out.to_string(),
+            ..simulation::video_metadata("optedOutVid")
+        };
+        stub_indexed_video(&es, &indexed).await;
+        let (metadata, captions) =
+            crawl_video(&es.client, "optedOutVid", None, quota_exceeded()).await;
+        assert_eq!(metadata, MetadataOutcome::OptedOut);
+        assert_eq!(captions.map(|captions| captions.count), Some(0));
+
+        // Never indexed, its channel is unknown and may be the opted-out one
+        let (metadata, captions) =
+            crawl_video(&es.client, "unknownVide", None, quota_exceeded()).await;
+        assert_eq!(metadata, MetadataOutcome::Failed);
+        assert_eq!(captions.map(|captions| captions.count), Some(0));
+
+        let written: Vec<String> = es.paths("PUT").await.split_off(written_before);
+        assert!(written.is_empty(), "{written:?}");
+        let captions_index = format!("/{}/", INDEX_NAMES.captions);
+        let posted = es.paths("POST").await;
+        assert!(
+            !posted.iter().any(|path| path.starts_with(&captions_index)),
+            "{posted:?}"
+        );
+    }
+}
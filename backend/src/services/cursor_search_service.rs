@@ -0,0 +1,437 @@
+//! Flat, cursor-paged caption search for bots walking all matches of a query. Pages are
+//! read with `search_after` from a point in time opened with the first page, so the
+//! walk sees the index as it was when it started: captions indexed or deleted meanwhile
+//! neither show up nor shift the order, and every match is returned exactly once. A
+//! point in time is kept for `PIT_KEEP_ALIVE` after each page, an expired cursor has to
+//! start over.
+
+use crate::config::INDEX_NAMES;
+use crate::models::{CaptionHit, CursorSearchResponse};
+use crate::services::search_service::{build_main_query_by_type, SearchOptions, SearchType};
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use elasticsearch::{Elasticsearch, OpenPointInTimeParts, SearchParts};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+
+pub const CURSOR_PAGE_SIZE: usize = 20;
+pub const MAX_CURSOR_PAGE_SIZE: usize = 100;
+const PIT_KEEP_ALIVE: &str = "5m";
+
+/// What the opaque cursor holds
+#[derive(Debug, Serialize, Deserialize)]
+struct Cursor {
+    /// The query the walk started with, a cursor can't continue another one
+    q: String,
+    /// The options it started with, changing them midway would skip or repeat hits
+    options: CursorOptions,
+    pit: String,
+    /// Sort values of the last returned hit
+    after: Vec<Value>,
+}
+
+/// The normalized options that decide which captions match
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct CursorOptions {
+    search_type: String,
+    language: Option<String>,
+    exclude_auto: bool,
+}
+
+impl CursorOptions {
+    fn of(options: &SearchOptions) -> Self {
+        CursorOptions {
+            search_type: match options.search_type {
+                SearchType::Natural => "natural",
+                SearchType::Wide => "wide",
+            }
+            .to_string(),
+            language: options.language.clone(),
+            exclude_auto: options.exclude_auto,
+        }
+    }
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    fn decode(cursor: &str) -> Option<Cursor> {
+        let bytes = URL_SAFE_NO_PAD.decode(cursor.trim()).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+#[derive(Debug)]
+pub enum CursorError {
+    /// Not a cursor of this API, or one of a different query or options
+    Invalid,
+    /// The point in time of the walk expired
+    Expired,
+}
+
+impl fmt::Display for CursorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CursorError::Invalid => write!(f, "Invalid search cursor"),
+            CursorError::Expired => write!(f, "Search cursor expired"),
+        }
+    }
+}
+
+impl std::error::Error for CursorError {}
+
+/// The cursor error behind `error`, if any
+pub fn cursor_error(error: &anyhow::Error) -> Option<&CursorError> {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<CursorError>())
+}
+
+async fn open_point_in_time(es_client: &Elasticsearch) -> Result<String> {
+    let response = es_client
+        .open_point_in_time(OpenPointInTimeParts::Index(&[INDEX_NAMES.captions]))
+        .keep_alive(PIT_KEEP_ALIVE)
+        .send()
+        .await
+        .context("Opening a point in time failed")?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Opening a point in time failed: {}",
+            response.status_code()
+        ));
+    }
+    let json_response: Value = response.json().await?;
+    json_response["id"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow::anyhow!("Point in time response without an id"))
+}
+
+/// Frees the point in time of a finished walk instead of waiting for it to expire
+async fn close_point_in_time(es_client: &Elasticsearch, pit: &str) {
+    if let Err(e) = es_client
+        .close_point_in_time()
+        .body(json!({ "id": pit }))
+        .send()
+        .await
+    {
+        warn!("Failed to close a point in time: {e:?}");
+    }
+}
+
+/// One page of the matching captions of `query_string`, by score, then video and start
+/// time. Starts a new walk without `cursor`.
+pub async fn search_cursor(
+    es_client: &Elasticsearch,
+    query_string: &str,
+    cursor: Option<&str>,
+    size: usize,
+    options: &SearchOptions,
+) -> Result<CursorSearchResponse> {
+    let (pit, after) = match cursor {
+        Some(cursor) => {
+            let cursor = Cursor::decode(cursor).ok_or(CursorError::Invalid)?;
+            if cursor.q != query_string || cursor.options != CursorOptions::of(options) {
+                return Err(CursorError::Invalid.into());
+            }
+            (cursor.pit, Some(cursor.after))
+        }
+        None => (open_point_in_time(es_client).await?, None),
+    };
+
+    let mut body = json!({
+        "size": size,
+        "query": build_main_query_by_type(query_string, options),
+        "pit": { "id": pit, "keep_alive": PIT_KEEP_ALIVE },
+        "_source": ["video_id", "text", "start_time", "end_time"],
+        "track_total_hits": false,
+        // The point in time adds its own tiebreaker, so the order is total
+        "sort": [
+            { "_score": { "order": "desc" } },
+            { "video_id": { "order": "asc" } },
+            { "start_time": { "order": "asc" } }
+        ]
+    });
+    if let Some(after) = after {
+        body["search_after"] = json!(after);
+    }
+
+    let response = es_client
+        .search(SearchParts::None)
+        .body(body)
+        .send()
+        .await
+        .context("Elasticsearch cursor search request failed")?;
+    if response.status_code().as_u16() == 404 {
+        return Err(CursorError::Expired.into());
+    }
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Cursor search failed: {}",
+            response.status_code()
+        ));
+    }
+    let json_response: Value = response.json().await?;
+    // Elasticsearch may hand out a new id for the same point in time
+    let pit = json_response["pit_id"]
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or(pit);
+
+    let raw_hits = json_response["hits"]["hits"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let hits: Vec<CaptionHit> = raw_hits
+        .iter()
+        .map(|hit| {
+            let source = &hit["_source"];
+            CaptionHit {
+                video_id: source["video_id"].as_str().unwrap_or_default().to_string(),
+                start_time: source["start_time"].as_f64().unwrap_or_default(),
+                end_time: source["end_time"].as_f64().unwrap_or_default(),
+                text: source["text"].as_str().unwrap_or_default().to_string(),
+                score: hit["_score"].as_f64().unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    let last_sort = raw_hits
+        .last()
+        .and_then(|hit| hit["sort"].as_array())
+        .cloned();
+    let next_cursor = match last_sort {
+        Some(after) if hits.len() == size => Some(
+            Cursor {
+                q: query_string.to_string(),
+                options: CursorOptions::of(options),
+                pit,
+                after,
+            }
+            .encode(),
+        ),
+        _ => {
+            close_point_in_time(es_client, &pit).await;
+            None
+        }
+    };
+
+    Ok(CursorSearchResponse { hits, next_cursor })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::search_service::{SortBy, SortOrder};
+    use crate::testsupport::MockElasticsearch;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    fn natural() -> SearchOptions {
+        SearchOptions::natural(SortBy::Relevance, SortOrder::Desc)
+    }
+
+    fn page_hit(video_id: &str, start_time: f64) -> Value {
+        json!({
+            "_score": 1.0,
+            "_source": {
+                "video_id": video_id,
+                "text": "lorem ipsum",
+                "start_time": start_time,
+                "end_time": start_time + 4.0
+            },
+            "sort": [1.0, video_id, start_time]
+        })
+    }
+
+    /// Opens the walk with a full first page, so it hands out a cursor
+    async fn first_page(es: &MockElasticsearch, options: &SearchOptions) -> String {
+        es.stub(
+            "POST",
+            &format!("/{}/_pit", INDEX_NAMES.captions),
+            json!({ "id": "pit-1" }),
+        )
+        .await;
+        es.stub(
+            "POST",
+            "/_search",
+            json!({
+                "pit_id": "pit-1",
+                "hits": { "hits": [page_hit("aaaaaaaaaaa", 0.0), page_hit("aaaaaaaaaaa", 4.0)] }
+            }),
+        )
+        .await;
+        search_cursor(&es.client, "lorem", None, 2, options)
+            .await
+            .unwrap()
+            .next_cursor
+            .expect("cursor of a full page")
+    }
+
+    #[rocket::async_test]
+    async fn cursors_continue_after_the_last_hit() {
+        let es = MockElasticsearch::start().await;
+        let cursor = first_page(&es, &natural()).await;
+
+        search_cursor(&es.client, "lorem", Some(&cursor), 2, &natural())
+            .await
+            .unwrap();
+
+        let searches = es.bodies("POST", "/_search").await;
+        assert_eq!(searches.len(), 2);
+        assert_eq!(searches[0].get("search_after"), None);
+        assert_eq!(
+            searches[1]["search_after"],
+            json!([1.0, "aaaaaaaaaaa", 4.0])
+        );
+        assert_eq!(searches[1]["pit"]["id"], "pit-1");
+    }
+
+    /// Hits of a stubbed cluster as `(video_id, start_time)`, all with the same score
+    type Hits = Vec<(String, f64)>;
+
+    /// Answers searches the way a cluster with points in time does: opening one
+    /// snapshots `live`, searches in a point in time page through its snapshot after
+    /// `search_after`, searches without one read `live`
+    async fn stub_point_in_time_cluster(es: &MockElasticsearch, live: Arc<Mutex<Hits>>) {
+        let snapshots: Arc<Mutex<HashMap<String, Hits>>> = Arc::default();
+
+        let (opened, indexed) = (snapshots.clone(), live.clone());
+        es.stub_fn(
+            "POST",
+            &format!("/{}/_pit", INDEX_NAMES.captions),
+            move |_| {
+                let mut opened = opened.lock().unwrap();
+                let id = format!("pit-{}", opened.len() + 1);
+                opened.insert(id.clone(), indexed.lock().unwrap().clone());
+                json!({ "id": id })
+            },
+        )
+        .await;
+
+        es.stub_fn("POST", "/_search", move |body| {
+            let pit = body["pit"]["id"].as_str();
+            let mut hits = match pit {
+                Some(pit) => snapshots.lock().unwrap()[pit].clone(),
+                None => live.lock().unwrap().clone(),
+            };
+            hits.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+            if let Some(after) = body["search_after"].as_array() {
+                let after = (
+                    after[1].as_str().unwrap().to_string(),
+                    after[2].as_f64().unwrap(),
+                );
+                hits.retain(|hit| hit.0.cmp(&after.0).then(hit.1.total_cmp(&after.1)).is_gt());
+            }
+            let size = body["size"].as_u64().unwrap() as usize;
+            let page: Vec<Value> = hits
+                .iter()
+                .take(size)
+                .map(|(video_id, start_time)| page_hit(video_id, *start_time))
+                .collect();
+            json!({ "pit_id": pit, "hits": { "hits": page } })
+        })
+        .await;
+    }
+
+    #[rocket::async_test]
+    async fn walks_return_every_hit_once_while_captions_are_indexed() {
+        let es = MockElasticsearch::start().await;
+        let indexed: Hits = vec![
+            ("bbbbbbbbbbb".to_string(), 0.0),
+            ("bbbbbbbbbbb".to_string(), 4.0),
+            ("ccccccccccc".to_string(), 0.0),
+            ("ddddddddddd".to_string(), 0.0),
+            ("ddddddddddd".to_string(), 8.0),
+        ];
+        let live = Arc::new(Mutex::new(indexed.clone()));
+        stub_point_in_time_cluster(&es, live.clone()).await;
+
+        let mut walked: Hits = Vec::new();
+        let mut cursor: Option<String> = None;
+        // Two hits per page, a walk that doesn't end by then repeats pages
+        for page in 0..=indexed.len() {
+            let response = search_cursor(&es.client, "lorem", cursor.as_deref(), 2, &natural())
+                .await
+                .unwrap();
+            walked.extend(
+                response
+                    .hits
+                    .into_iter()
+                    .map(|hit| (hit.video_id, hit.start_time)),
+            );
+            // Captions indexed in the middle of the walk: the first would shift every
+            // later page, the second would show up in one of them
+            if page == 0 {
+                let mut live = live.lock().unwrap();
+                live.push(("aaaaaaaaaaa".to_string(), 0.0));
+                live.push(("ccccccccccc".to_string(), 2.0));
+            }
+            cursor = response.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(cursor, None, "the walk didn't end");
+
+        assert_eq!(walked, indexed);
+        let searches = es.bodies("POST", "/_search").await;
+        assert!(searches.iter().all(|search| search["pit"]["id"] == "pit-1"));
+        assert_eq!(
+            es.bodies("POST", &format!("/{}/_pit", INDEX_NAMES.captions))
+                .await
+                .len(),
+            1
+        );
+    }
+
+    #[rocket::async_test]
+    async fn cursors_are_bound_to_query_and_options() {
+        let es = MockElasticsearch::start().await;
+        let cursor = first_page(&es, &natural()).await;
+
+        let changed = [
+            ("ipsum", natural()),
+            (
+                "lorem",
+                SearchOptions::wide(SortBy::Relevance, SortOrder::Desc),
+            ),
+            ("lorem", natural().with_language(Some("de".to_string()))),
+            ("lorem", natural().with_exclude_auto(true)),
+        ];
+        for (query, options) in &changed {
+            let error = search_cursor(&es.client, query, Some(&cursor), 2, options)
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(cursor_error(&error), Some(CursorError::Invalid)),
+                "{query} {options:?}"
+            );
+        }
+        // Rejected before searching
+        assert_eq!(es.bodies("POST", "/_search").await.len(), 1);
+    }
+
+    #[rocket::async_test]
+    async fn malformed_cursors_are_invalid() {
+        let es = MockElasticsearch::start().await;
+        for cursor in [
+            "",
+            "not a cursor",
+            &URL_SAFE_NO_PAD.encode(b"{\"q\":\"lorem\"}"),
+        ] {
+            let error = search_cursor(&es.client, "lorem", Some(cursor), 2, &natural())
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(cursor_error(&error), Some(CursorError::Invalid)),
+                "{cursor}"
+            );
+        }
+    }
+}
@@ -1,40 +1,225 @@
-use elasticsearch::{indices::IndicesCreateParts, Elasticsearch};
+use crate::config::INDEX_NAMES;
+use crate::services::stemming;
+use elasticsearch::indices::{IndicesCreateParts, IndicesExistsParts};
+use elasticsearch::Elasticsearch;
 use log::{error, info};
-use serde_json::json;
+use serde_json::{json, Map, Value};
 
+/// Suffix of newly created indices, the names in `IndexNames` are aliases of them
+const INDEX_VERSION: &str = "v1";
+
+/// Creates every index that is missing. Videos and monitors are mapped dynamically.
 pub async fn create_es_index(es_client: &Elasticsearch) {
-    let create_index_body = json!({
+    for index in [
+        INDEX_NAMES.videos,
+        INDEX_NAMES.monitored_channels,
+        INDEX_NAMES.monitored_playlists,
+    ] {
+        create_index_if_missing(es_client, index, json!({})).await;
+    }
+
+    let captions_index_body = json!({
+        "mappings": {
+            "properties": {
+                "video_id": { "type": "keyword" },
+                "text": stemming::text_mapping(),
+                "raw_text": { "type": "text", "index": false },
+                "start_time": { "type": "float" },
+                "end_time": { "type": "float" },
+                "hidden": { "type": "boolean" },
+                "language": { "type": "keyword" },
+                "is_generated": { "type": "boolean" },
+                "segment": { "type": "integer" },
+                "upload_date": { "type": "long" },
+                "channel_id": { "type": "keyword" }
+            }
+        }
+    });
+    create_index_if_missing(es_client, INDEX_NAMES.captions, captions_index_body.clone()).await;
+    // Captions stitched into sentences, same document shape
+    create_index_if_missing(es_client, INDEX_NAMES.sentences, captions_index_body).await;
+    // Indices created before a language was configured
+    stemming::ensure_stemmed_fields(es_client).await;
+
+    let reports_index_body = json!({
         "mappings": {
             "properties": {
                 "video_id": { "type": "keyword" },
-                "text": { "type": "text" },
                 "start_time": { "type": "float" },
-                "end_time": { "type": "float" }
+                "query": { "type": "text" },
+                "day": { "type": "keyword" },
+                "reported_at": { "type": "long" }
+            }
+        }
+    });
+    create_index_if_missing(es_client, INDEX_NAMES.caption_reports, reports_index_body).await;
+
+    let api_keys_index_body = json!({
+        "mappings": {
+            "properties": {
+                "key_hash": { "type": "keyword" },
+                "label": { "type": "keyword" },
+                "daily_quota": { "type": "long" },
+                "created_at": { "type": "long" },
+                "usage_day": { "type": "keyword" },
+                "usage_today": { "type": "long" }
+            }
+        }
+    });
+    create_index_if_missing(es_client, INDEX_NAMES.api_keys, api_keys_index_body).await;
+
+    let alerts_index_body = json!({
+        "mappings": {
+            "properties": {
+                "id": { "type": "keyword" },
+                "kind": { "type": "keyword" },
+                "message": { "type": "text" },
+                "created_at": { "type": "long" },
+                "acknowledged": { "type": "boolean" }
+            }
+        }
+    });
+    create_index_if_missing(es_client, INDEX_NAMES.alerts, alerts_index_body).await;
+
+    let feedback_index_body = json!({
+        "mappings": {
+            "properties": {
+                "query_hash": { "type": "keyword" },
+                "search_type": { "type": "keyword" },
+                "video_id": { "type": "keyword" },
+                "rank": { "type": "integer" },
+                "action": { "type": "keyword" },
+                "ip_hash": { "type": "keyword" },
+                "created_at": { "type": "long" }
+            }
+        }
+    });
+    create_index_if_missing(es_client, INDEX_NAMES.search_feedback, feedback_index_body).await;
+
+    let search_log_index_body = json!({
+        "mappings": {
+            "properties": {
+                "query": { "type": "keyword" },
+                "created_at": { "type": "long" }
+            }
+        }
+    });
+    create_index_if_missing(es_client, INDEX_NAMES.search_log, search_log_index_body).await;
+
+    let indexing_log_index_body = json!({
+        "mappings": {
+            "properties": {
+                "video_id": { "type": "keyword" },
+                "title": { "type": "text" },
+                "channel_id": { "type": "keyword" },
+                "channel_name": { "type": "keyword" },
+                "source": {
+                    "properties": {
+                        "type": { "type": "keyword" },
+                        "id": { "type": "keyword" }
+                    }
+                },
+                "status": { "type": "keyword" },
+                "failure_kind": { "type": "keyword" },
+                "note": { "type": "keyword" },
+                "captions_indexed": { "type": "long" },
+                "processing_ms": { "type": "long" },
+                "finished_at": { "type": "long" }
+            }
+        }
+    });
+    create_index_if_missing(es_client, INDEX_NAMES.indexing_log, indexing_log_index_body).await;
+
+    let optout_index_body = json!({
+        "mappings": {
+            "properties": {
+                "channel_id": { "type": "keyword" },
+                "reason": { "type": "text" },
+                "created_at_ms": { "type": "long" }
             }
         }
     });
+    create_index_if_missing(es_client, INDEX_NAMES.optout_channels, optout_index_body).await;
+
+    // Visibility of monitor tags, tags themselves are stored on the monitors
+    let monitor_tags_index_body = json!({
+        "mappings": {
+            "properties": {
+                "tag": { "type": "keyword" },
+                "public": { "type": "boolean" }
+            }
+        }
+    });
+    create_index_if_missing(es_client, INDEX_NAMES.monitor_tags, monitor_tags_index_body).await;
+
+    // The synonym set of query expansion, a single document
+    let synonyms_index_body = json!({
+        "mappings": {
+            "properties": {
+                "groups": { "type": "keyword", "index": false },
+                "updated_at": { "type": "long" }
+            }
+        }
+    });
+    create_index_if_missing(es_client, INDEX_NAMES.synonyms, synonyms_index_body).await;
+
+    // Applied migration version, see `migrations`
+    let schema_meta_index_body = json!({
+        "mappings": {
+            "properties": {
+                "version": { "type": "integer" },
+                "updated_at": { "type": "long" }
+            }
+        }
+    });
+    create_index_if_missing(es_client, INDEX_NAMES.schema_meta, schema_meta_index_body).await;
+}
+
+/// Creates `<index>_v1` with `index` as its alias, unless an index or alias of that name
+/// exists. Indices created before aliases were used keep working as they are.
+async fn create_index_if_missing(es_client: &Elasticsearch, index: &str, mut body: Value) {
+    match es_client
+        .indices()
+        .exists(IndicesExistsParts::Index(&[index]))
+        .send()
+        .await
+    {
+        Ok(response) if response.status_code().is_success() => {
+            info!("Elasticsearch index '{index}' already exists.");
+            return;
+        }
+        Ok(_) => {}
+        Err(e) => {
+            error!("Failed to connect to Elasticsearch to check index '{index}': {e:?}");
+            return;
+        }
+    }
 
+    let mut aliases = Map::new();
+    aliases.insert(index.to_string(), json!({}));
+    body["aliases"] = Value::Object(aliases);
+    let versioned = format!("{index}_{INDEX_VERSION}");
     match es_client
         .indices()
-        .create(IndicesCreateParts::Index("youtube_captions"))
-        .body(create_index_body)
+        .create(IndicesCreateParts::Index(&versioned))
+        .body(body)
         .send()
         .await
     {
         Ok(response) => {
             if response.status_code().is_success() {
-                info!("Elasticsearch index 'youtube_captions' created or already exists.");
+                info!("Elasticsearch index '{versioned}' created with alias '{index}'.");
             } else {
                 let response_text = response.text().await.unwrap_or_default();
                 if response_text.contains("resource_already_exists_exception") {
-                    info!("Elasticsearch index 'youtube_captions' already exists.");
+                    info!("Elasticsearch index '{index}' already exists.");
                 } else {
-                    error!("Failed to create Elasticsearch index: {response_text}");
+                    error!("Failed to create Elasticsearch index '{index}': {response_text}");
                 }
             }
         }
         Err(e) => {
-            error!("Failed to connect to Elasticsearch to create index: {e:?}");
+            error!("Failed to connect to Elasticsearch to create index '{index}': {e:?}");
         }
     }
 }
@@ -0,0 +1,377 @@
+//! Circuit breaker for Elasticsearch outages. After a few consecutive transport failures
+//! searches are rejected right away for a cooldown instead of each waiting for its own
+//! connect timeout. Once the cooldown passed, a single ping decides whether to close again,
+//! one that doesn't answer within `PROBE_TIMEOUT` counts as failed.
+//! Public endpoints run their Elasticsearch calls through `guarded`.
+
+use crate::config::{ES_BREAKER_COOLDOWN_SECONDS, ES_BREAKER_FAILURES};
+use elasticsearch::Elasticsearch;
+use lazy_static::lazy_static;
+use log::{info, warn};
+use std::fmt;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+lazy_static! {
+    pub static ref ES_BREAKER: CircuitBreaker = CircuitBreaker::new(
+        *ES_BREAKER_FAILURES,
+        Duration::from_secs(*ES_BREAKER_COOLDOWN_SECONDS)
+    );
+}
+
+/// How long the probe of a half-open circuit waits for the ping
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    Closed,
+    Open,     // rejecting until the cooldown passed
+    HalfOpen, // cooldown passed, the next request probes
+}
+
+impl BreakerState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BreakerState::Closed => "closed",
+            BreakerState::Open => "open",
+            BreakerState::HalfOpen => "half_open",
+        }
+    }
+}
+
+/// Error of a call the open circuit rejected without sending it
+#[derive(Debug)]
+pub struct CircuitOpen;
+
+impl fmt::Display for CircuitOpen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Elasticsearch is unreachable, the circuit is open")
+    }
+}
+
+impl std::error::Error for CircuitOpen {}
+
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    probe_timeout: Duration,
+    state: Mutex<BreakerInner>,
+}
+
+#[derive(Default)]
+struct BreakerInner {
+    consecutive_failures: u32,
+    open_until: Option<Instant>, // None while closed
+    probing: bool,
+}
+
+/// What `allow` does with a request
+enum Admission {
+    Pass,
+    Reject,
+    Probe,
+}
+
+/// Ends the probe of a half-open circuit however `allow` returns, also when the request
+/// waiting for the ping is dropped
+struct ProbeGuard<'a> {
+    breaker: &'a CircuitBreaker,
+}
+
+impl Drop for ProbeGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.breaker.state.lock() {
+            state.probing = false;
+        }
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        CircuitBreaker {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            probe_timeout: PROBE_TIMEOUT,
+            state: Mutex::new(BreakerInner::default()),
+        }
+    }
+
+    pub fn state(&self) -> BreakerState {
+        let Ok(state) = self.state.lock() else {
+            return BreakerState::Closed;
+        };
+        match state.open_until {
+            None => BreakerState::Closed,
+            Some(until) if Instant::now() < until => BreakerState::Open,
+            Some(_) => BreakerState::HalfOpen,
+        }
+    }
+
+    /// Whether a request may go to Elasticsearch. The first request after the cooldown
+    /// pings the cluster and closes the circuit if it answers, requests arriving during
+    /// the ping are rejected.
+    pub async fn allow(&self, es_client: &Elasticsearch) -> bool {
+        let admission = match self.state.lock() {
+            Ok(mut state) => {
+                let open_until = state.open_until;
+                match open_until {
+                    None => Admission::Pass,
+                    Some(until) if Instant::now() < until || state.probing => Admission::Reject,
+                    Some(_) => {
+                        state.probing = true;
+                        Admission::Probe
+                    }
+                }
+            }
+            Err(_) => Admission::Pass,
+        };
+
+        match admission {
+            Admission::Pass => true,
+            Admission::Reject => false,
+            Admission::Probe => {
+                let _probe = ProbeGuard { breaker: self };
+                let healthy = matches!(
+                    tokio::time::timeout(self.probe_timeout, es_client.ping().send()).await,
+                    Ok(Ok(response)) if response.status_code().is_success()
+                );
+                if healthy {
+                    self.record_success();
+                } else {
+                    self.open();
+                }
+                healthy
+            }
+        }
+    }
+
+    pub fn record_success(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            if state.open_until.is_some() {
+                info!("Elasticsearch is reachable again, closing the circuit");
+            }
+            state.consecutive_failures = 0;
+            state.open_until = None;
+        }
+    }
+
+    pub fn record_failure(&self) {
+        let threshold_reached = match self.state.lock() {
+            Ok(mut state) => {
+                state.consecutive_failures += 1;
+                state.consecutive_failures >= self.failure_threshold
+            }
+            Err(_) => false,
+        };
+        if threshold_reached {
+            self.open();
+        }
+    }
+
+    /// Counts the outcome of a request, only transport failures open the circuit
+    pub fn record<T>(&self, result: &anyhow::Result<T>) {
+        match result {
+            Ok(_) => self.record_success(),
+            Err(e) if is_transport_error(e) => self.record_failure(),
+            Err(_) => {}
+        }
+    }
+
+    /// Runs `call` if the circuit lets it through and counts its outcome. A rejected call
+    /// fails with `CircuitOpen` without being polled.
+    pub async fn call<T>(
+        &self,
+        es_client: &Elasticsearch,
+        call: impl Future<Output = anyhow::Result<T>>,
+    ) -> anyhow::Result<T> {
+        if !self.allow(es_client).await {
+            return Err(CircuitOpen.into());
+        }
+        let result = call.await;
+        self.record(&result);
+        result
+    }
+
+    fn open(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.open_until = Some(Instant::now() + self.cooldown);
+            warn!(
+                "Elasticsearch unreachable after {} failures, rejecting searches for {}s",
+                state.consecutive_failures,
+                self.cooldown.as_secs()
+            );
+        }
+    }
+}
+
+/// Runs a service call through `ES_BREAKER`, see `CircuitBreaker::call`
+pub async fn guarded<T>(
+    es_client: &Elasticsearch,
+    call: impl Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<T> {
+    ES_BREAKER.call(es_client, call).await
+}
+
+/// Whether the call failed because Elasticsearch is unreachable: the circuit rejected it
+/// or it never got a response. Endpoints answer these with 503.
+pub fn is_unavailable(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<CircuitOpen>().is_some() || is_transport_error(error)
+}
+
+/// Whether the request never got a response: connection refused, DNS, timeouts.
+/// Responses that failed to parse don't count, the cluster did answer.
+pub fn is_transport_error(error: &anyhow::Error) -> bool {
+    let unanswered = error.chain().any(|cause| {
+        cause
+            .downcast_ref::<elasticsearch::Error>()
+            .is_some_and(|e| e.status_code().is_none())
+    });
+    unanswered
+        && !error
+            .chain()
+            .any(|cause| cause.downcast_ref::<serde_json::Error>().is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::create_elasticsearch_client_for;
+    use crate::testsupport::MockElasticsearch;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const COOLDOWN: Duration = Duration::from_millis(100);
+
+    /// A client whose requests never get a response, nothing listens on the discard port
+    fn unreachable_client() -> Elasticsearch {
+        create_elasticsearch_client_for("http://127.0.0.1:9").unwrap()
+    }
+
+    /// A ping through `breaker`, counting the calls that were actually sent
+    async fn ping(
+        breaker: &CircuitBreaker,
+        es_client: &Elasticsearch,
+        sent: &AtomicUsize,
+    ) -> anyhow::Result<()> {
+        breaker
+            .call(es_client, async {
+                sent.fetch_add(1, Ordering::SeqCst);
+                es_client.ping().send().await?;
+                Ok(())
+            })
+            .await
+    }
+
+    #[rocket::async_test]
+    async fn breaker_opens_probes_and_closes() {
+        let es = MockElasticsearch::start().await;
+        es.stub("HEAD", "/", json!({})).await;
+        let unreachable = unreachable_client();
+        let breaker = CircuitBreaker::new(2, COOLDOWN);
+        let sent = AtomicUsize::new(0);
+
+        // Closed: failures below the threshold only count
+        assert!(ping(&breaker, &unreachable, &sent).await.is_err());
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        let error = ping(&breaker, &unreachable, &sent).await.unwrap_err();
+        assert!(is_transport_error(&error));
+        assert_eq!(sent.load(Ordering::SeqCst), 2);
+
+        // Open: rejected without being sent
+        assert_eq!(breaker.state(), BreakerState::Open);
+        let error = ping(&breaker, &es.client, &sent).await.unwrap_err();
+        assert!(error.downcast_ref::<CircuitOpen>().is_some());
+        assert!(is_unavailable(&error));
+        assert_eq!(sent.load(Ordering::SeqCst), 2);
+
+        // Half-open: a failed probe opens the circuit for another cooldown
+        tokio::time::sleep(COOLDOWN).await;
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        assert!(!breaker.allow(&unreachable).await);
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        // Half-open: a successful probe closes it and lets the call through
+        tokio::time::sleep(COOLDOWN).await;
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        ping(&breaker, &es.client, &sent).await.unwrap();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert_eq!(sent.load(Ordering::SeqCst), 3);
+    }
+
+    #[rocket::async_test]
+    async fn only_consecutive_transport_failures_open_the_circuit() {
+        let es = MockElasticsearch::start().await;
+        es.stub("HEAD", "/", json!({})).await;
+        let unreachable = unreachable_client();
+        let breaker = CircuitBreaker::new(2, COOLDOWN);
+        let sent = AtomicUsize::new(0);
+
+        assert!(ping(&breaker, &unreachable, &sent).await.is_err());
+        ping(&breaker, &es.client, &sent).await.unwrap();
+        assert!(ping(&breaker, &unreachable, &sent).await.is_err());
+        assert_eq!(
+            breaker.state(),
+            BreakerState::Closed,
+            "a success resets the count"
+        );
+
+        // The cluster answered, however badly
+        for _ in 0..3 {
+            let answered: anyhow::Result<()> = breaker
+                .call(&es.client, async {
+                    Err(anyhow::anyhow!("Elasticsearch returned 500"))
+                })
+                .await;
+            assert!(answered.is_err());
+        }
+        assert_eq!(breaker.state(), BreakerState::Closed);
+
+        assert!(ping(&breaker, &unreachable, &sent).await.is_err());
+        assert_eq!(breaker.state(), BreakerState::Open);
+    }
+
+    #[rocket::async_test]
+    async fn a_hanging_probe_times_out_and_opens_the_circuit() {
+        let es = MockElasticsearch::start().await;
+        es.stub_delayed("HEAD", "/", Duration::from_secs(10), json!({}))
+            .await;
+        let breaker = CircuitBreaker {
+            probe_timeout: Duration::from_millis(100),
+            ..CircuitBreaker::new(1, COOLDOWN)
+        };
+        breaker.record_failure();
+
+        tokio::time::sleep(COOLDOWN).await;
+        let probe = tokio::time::timeout(Duration::from_secs(5), breaker.allow(&es.client));
+        assert_eq!(probe.await.ok(), Some(false), "the probe gave up in time");
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        let healthy = MockElasticsearch::start().await;
+        healthy.stub("HEAD", "/", json!({})).await;
+        tokio::time::sleep(COOLDOWN).await;
+        assert!(breaker.allow(&healthy.client).await, "the next probe runs");
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[rocket::async_test]
+    async fn a_dropped_probe_lets_the_next_request_probe() {
+        let es = MockElasticsearch::start().await;
+        es.stub_delayed("HEAD", "/", Duration::from_secs(10), json!({}))
+            .await;
+        let breaker = CircuitBreaker::new(1, COOLDOWN);
+        breaker.record_failure();
+
+        tokio::time::sleep(COOLDOWN).await;
+        // The request is given up on while its probe still waits for the ping
+        let abandoned =
+            tokio::time::timeout(Duration::from_millis(50), breaker.allow(&es.client)).await;
+        assert!(abandoned.is_err());
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+
+        let healthy = MockElasticsearch::start().await;
+        healthy.stub("HEAD", "/", json!({})).await;
+        assert!(breaker.allow(&healthy.client).await);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+}
@@ -0,0 +1,187 @@
+use crate::config::INDEX_NAMES;
+use crate::models::{AdminCtrStats, CtrRow, FeedbackAction, SearchFeedbackRequest};
+use anyhow::Result;
+use elasticsearch::{Elasticsearch, IndexParts, SearchParts};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+/// Ranks beyond this are not tracked, nobody pages that far
+pub const MAX_FEEDBACK_RANK: usize = 200;
+
+/// Hex digits kept of the IP hash, enough to spot a single client skewing the numbers
+const IP_HASH_LENGTH: usize = 8;
+
+fn hash(value: &str) -> String {
+    format!("{:x}", Sha256::digest(value.as_bytes()))
+}
+
+/// Stores one feedback event. Only hashes of the query and the client IP are kept.
+pub async fn submit_feedback(
+    es_client: &Elasticsearch,
+    request: &SearchFeedbackRequest,
+    client_ip: Option<IpAddr>,
+) -> Result<()> {
+    let ip_hash = client_ip
+        .map(|ip| hash(&ip.to_string())[..IP_HASH_LENGTH].to_string())
+        .unwrap_or_default();
+    let document = json!({
+        "query_hash": hash(request.query.trim().to_lowercase().as_str()),
+        "search_type": request.search_type,
+        "video_id": request.video_id,
+        "rank": request.rank,
+        "action": request.action.key(),
+        "ip_hash": ip_hash,
+        "created_at": chrono::Utc::now().timestamp_millis()
+    });
+
+    let response = es_client
+        .index(IndexParts::Index(INDEX_NAMES.search_feedback))
+        .body(document)
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Failed to store search feedback: {}",
+            response.status_code()
+        ));
+    }
+    Ok(())
+}
+
+/// Counts of one search type: shown pages by their last rank, clicks and expands by rank
+#[derive(Default)]
+struct TypeCounts {
+    views_by_last_rank: BTreeMap<usize, i64>,
+    clicks_by_rank: BTreeMap<usize, i64>,
+    expands_by_rank: BTreeMap<usize, i64>,
+}
+
+impl TypeCounts {
+    /// Pages that showed the given rank
+    fn impressions_at(&self, rank: usize) -> i64 {
+        self.views_by_last_rank
+            .range(rank..)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// Result pages shown, the page size is up to the client so results are not counted
+    fn pages_shown(&self) -> i64 {
+        self.views_by_last_rank.values().sum()
+    }
+}
+
+/// Click-through rates of the last `days` days by rank position and by search type
+pub async fn get_ctr_stats(es_client: &Elasticsearch, days: i64) -> Result<AdminCtrStats> {
+    let since = chrono::Utc::now().timestamp_millis() - days * 24 * 60 * 60 * 1000;
+    let search_body = json!({
+        "size": 0,
+        "query": { "range": { "created_at": { "gte": since } } },
+        "aggs": {
+            "search_types": {
+                "terms": { "field": "search_type", "size": 10 },
+                "aggs": {
+                    "actions": {
+                        "terms": { "field": "action", "size": 10 },
+                        "aggs": {
+                            "ranks": { "terms": { "field": "rank", "size": MAX_FEEDBACK_RANK } }
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.search_feedback]))
+        .body(search_body)
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!("Elasticsearch feedback aggregation failed"));
+    }
+    let json_response: Value = response.json().await?;
+
+    let mut by_type: BTreeMap<String, TypeCounts> = BTreeMap::new();
+    for type_bucket in buckets(&json_response["aggregations"]["search_types"]) {
+        let counts = by_type
+            .entry(type_bucket["key"].as_str().unwrap_or_default().to_string())
+            .or_default();
+        for action_bucket in buckets(&type_bucket["actions"]) {
+            let target = match action_bucket["key"].as_str() {
+                Some("view") => &mut counts.views_by_last_rank,
+                Some("click") => &mut counts.clicks_by_rank,
+                Some("expand") => &mut counts.expands_by_rank,
+                _ => continue,
+            };
+            for rank_bucket in buckets(&action_bucket["ranks"]) {
+                if let (Some(rank), Some(count)) = (
+                    rank_bucket["key"].as_u64(),
+                    rank_bucket["doc_count"].as_i64(),
+                ) {
+                    target.insert(rank as usize, count);
+                }
+            }
+        }
+    }
+
+    let max_rank = by_type
+        .values()
+        .flat_map(|counts| {
+            counts
+                .views_by_last_rank
+                .keys()
+                .chain(counts.clicks_by_rank.keys())
+        })
+        .max()
+        .copied()
+        .unwrap_or(0);
+    let by_rank = (1..=max_rank)
+        .map(|rank| CtrRow {
+            key: rank.to_string(),
+            impressions: by_type.values().map(|c| c.impressions_at(rank)).sum(),
+            clicks: by_type
+                .values()
+                .map(|c| count_at(&c.clicks_by_rank, rank))
+                .sum(),
+            expands: by_type
+                .values()
+                .map(|c| count_at(&c.expands_by_rank, rank))
+                .sum(),
+        })
+        .filter(|row| row.impressions > 0 || row.clicks > 0 || row.expands > 0)
+        .collect();
+    let by_search_type = by_type
+        .iter()
+        .map(|(search_type, counts)| CtrRow {
+            key: search_type.clone(),
+            impressions: counts.pages_shown(),
+            clicks: counts.clicks_by_rank.values().sum(),
+            expands: counts.expands_by_rank.values().sum(),
+        })
+        .collect();
+
+    Ok(AdminCtrStats {
+        days,
+        by_rank,
+        by_search_type,
+    })
+}
+
+fn buckets(aggregation: &Value) -> impl Iterator<Item = &Value> {
+    aggregation["buckets"].as_array().into_iter().flatten()
+}
+
+fn count_at(counts: &BTreeMap<usize, i64>, rank: usize) -> i64 {
+    counts.get(&rank).copied().unwrap_or(0)
+}
+
+/// Whether the event can be stored, invalid ones are dropped silently
+pub fn is_valid_feedback(request: &SearchFeedbackRequest) -> bool {
+    (1..=MAX_FEEDBACK_RANK).contains(&request.rank)
+        && !request.query.trim().is_empty()
+        && matches!(request.search_type.as_str(), "natural" | "wide")
+        && (request.action == FeedbackAction::View || !request.video_id.is_empty())
+}
@@ -0,0 +1,227 @@
+//! How often a phrase was said over time, by the upload date of the videos. Popular
+//! phrases get asked for again and again, so results are cached for a while.
+
+use crate::config::INDEX_NAMES;
+use crate::models::{FrequencyBucket, PhraseFrequencyResponse};
+use crate::services::archive_generation::ArchiveGeneration;
+use crate::services::search_service::{self, SearchOptions, SortBy, SortOrder};
+use anyhow::{Context, Result};
+use elasticsearch::{Elasticsearch, SearchParts};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+/// Entries beyond this are evicted, oldest first
+const CACHE_CAPACITY: usize = 1000;
+
+/// Normalized query, interval and the archive generation the result was computed at
+type CacheKey = (String, FrequencyInterval, u64);
+
+lazy_static::lazy_static! {
+    static ref FREQUENCY_CACHE: Mutex<HashMap<CacheKey, (Instant, PhraseFrequencyResponse)>> =
+        Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrequencyInterval {
+    Week,
+    Month,
+    Year,
+}
+
+impl FrequencyInterval {
+    fn name(&self) -> &'static str {
+        match self {
+            FrequencyInterval::Week => "week",
+            FrequencyInterval::Month => "month",
+            FrequencyInterval::Year => "year",
+        }
+    }
+
+    /// How far back the buckets reach, keeps the number of buckets bounded
+    fn max_range_days(&self) -> i64 {
+        match self {
+            FrequencyInterval::Week => 2 * 365,
+            FrequencyInterval::Month => 10 * 365,
+            FrequencyInterval::Year => 30 * 365,
+        }
+    }
+}
+
+/// Caption matches and distinct videos of `query` per interval of upload dates.
+/// Captions indexed before they stored their video's upload date are not counted.
+pub async fn phrase_frequency(
+    es_client: &Elasticsearch,
+    generation: &ArchiveGeneration,
+    query: &str,
+    interval: FrequencyInterval,
+) -> Result<PhraseFrequencyResponse> {
+    let cache_key = (query.trim().to_lowercase(), interval, generation.current());
+    if let Some(cached) = cached(&cache_key) {
+        return Ok(cached);
+    }
+
+    let since = chrono::Utc::now().timestamp() - interval.max_range_days() * 24 * 60 * 60;
+    let options = SearchOptions::natural(SortBy::Relevance, SortOrder::Desc);
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.captions]))
+        .body(json!({
+            "size": 0,
+            "query": {
+                "bool": {
+                    "must": [search_service::build_main_query_by_type(query, &options)],
+                    "filter": [{ "range": { "upload_date": { "gte": since } } }]
+                }
+            },
+            "aggs": {
+                "over_time": {
+                    "date_histogram": {
+                        // Upload dates are unix seconds, dates are read as millis
+                        "script": {
+                            "source": "doc['upload_date'].value * 1000",
+                            "lang": "painless"
+                        },
+                        "calendar_interval": interval.name(),
+                        "min_doc_count": 0
+                    },
+                    "aggs": {
+                        "videos": { "cardinality": { "field": "video_id" } }
+                    }
+                }
+            }
+        }))
+        .send()
+        .await
+        .context("Elasticsearch frequency request failed")?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Frequency aggregation failed: {}",
+            response.status_code()
+        ));
+    }
+
+    let json_response: Value = response.json().await?;
+    let buckets = json_response["aggregations"]["over_time"]["buckets"]
+        .as_array()
+        .map(|buckets| {
+            buckets
+                .iter()
+                .map(|bucket| FrequencyBucket {
+                    start: bucket["key"].as_i64().unwrap_or(0) / 1000,
+                    captions: bucket["doc_count"].as_i64().unwrap_or(0),
+                    videos: bucket["videos"]["value"].as_i64().unwrap_or(0),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let frequency = PhraseFrequencyResponse {
+        query: query.trim().to_string(),
+        interval: interval.name().to_string(),
+        buckets,
+    };
+    store(cache_key, frequency.clone());
+    Ok(frequency)
+}
+
+fn cached(key: &CacheKey) -> Option<PhraseFrequencyResponse> {
+    let cache = FREQUENCY_CACHE.lock().ok()?;
+    cache
+        .get(key)
+        .filter(|(stored_at, _)| stored_at.elapsed() < CACHE_TTL)
+        .map(|(_, frequency)| frequency.clone())
+}
+
+fn store(key: CacheKey, frequency: PhraseFrequencyResponse) {
+    let Ok(mut cache) = FREQUENCY_CACHE.lock() else {
+        return;
+    };
+    // Results of older generations can't be hit anymore
+    cache.retain(|(_, _, generation), (stored_at, _)| {
+        *generation == key.2 && stored_at.elapsed() < CACHE_TTL
+    });
+    if cache.len() >= CACHE_CAPACITY {
+        if let Some(oldest) = cache
+            .iter()
+            .min_by_key(|(_, (stored_at, _))| *stored_at)
+            .map(|(key, _)| key.clone())
+        {
+            cache.remove(&oldest);
+        }
+    }
+    cache.insert(key, (Instant::now(), frequency));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::admin_service;
+    use crate::testsupport::{maintenance_run, MockElasticsearch};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    /// Stubs a cluster holding one matching caption of dQw4w9WgXcQ until it is deleted
+    async fn cluster_with_deletable_video() -> (MockElasticsearch, Arc<AtomicBool>) {
+        let es = MockElasticsearch::start().await;
+        let deleted = Arc::new(AtomicBool::new(false));
+        let deleted_flag = deleted.clone();
+        es.stub_fn(
+            "POST",
+            &format!("/{}/_search", INDEX_NAMES.captions),
+            move |_| {
+                let count = if deleted_flag.load(Ordering::SeqCst) {
+                    0
+                } else {
+                    1
+                };
+                json!({
+                    "aggregations": { "over_time": { "buckets": [{
+                        "key": 1_704_067_200_000_i64,
+                        "doc_count": count,
+                        "videos": { "value": count }
+                    }] } }
+                })
+            },
+        )
+        .await;
+        es.stub(
+            "POST",
+            &format!("/{}/_update/dQw4w9WgXcQ", INDEX_NAMES.videos),
+            json!({ "result": "updated" }),
+        )
+        .await;
+        es.stub(
+            "POST",
+            &format!("/{}/_delete_by_query", INDEX_NAMES.captions),
+            json!({ "task": "node:1" }),
+        )
+        .await;
+        (es, deleted)
+    }
+
+    #[rocket::async_test]
+    async fn deleted_videos_are_never_served_from_the_cache() {
+        let (es, deleted) = cluster_with_deletable_video().await;
+        // Unique, other tests share the cache
+        let query = "deleted video frequency cache";
+
+        let generation = ArchiveGeneration::new();
+        let before = phrase_frequency(&es.client, &generation, query, FrequencyInterval::Month)
+            .await
+            .unwrap();
+        assert_eq!(before.buckets[0].videos, 1);
+
+        admin_service::delete_video(&es.client, &generation, "dQw4w9WgXcQ", maintenance_run())
+            .await
+            .unwrap();
+        deleted.store(true, Ordering::SeqCst);
+
+        let after = phrase_frequency(&es.client, &generation, query, FrequencyInterval::Month)
+            .await
+            .unwrap();
+        assert_eq!(after.buckets[0].videos, 0, "served the deleted video");
+        assert_eq!(after.buckets[0].captions, 0);
+    }
+}
@@ -0,0 +1,261 @@
+use crate::config::{
+    APP_NAME, HEALTH_MAX_CRAWL_FAILURES, INDEX_NAMES, MONITOR_CHECK_SCHEDULE, SMTP_CONFIG,
+};
+use crate::models::HealthAlert;
+use crate::services::crawler::VideoQueue;
+use crate::services::notification_service::{self, Email};
+use anyhow::Result;
+use elasticsearch::{Elasticsearch, IndexParts, SearchParts, UpdateParts};
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use serde_json::{json, Value};
+use std::sync::Mutex;
+
+/// Queue items pending without any completed crawl for this long raise an alert
+const MAX_QUEUE_STALL_MILLIS: i64 = 60 * 60 * 1000;
+/// Monitor checks may be late by this many schedule intervals before an alert is raised
+const MONITOR_INTERVALS_TOLERATED: i64 = 3;
+
+lazy_static! {
+    pub static ref HEALTH: HealthTracker = HealthTracker::new();
+}
+
+/// Progress markers of the background jobs, checked by `run_health_check`
+pub struct HealthTracker {
+    state: Mutex<HealthState>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HealthState {
+    pub last_monitor_check: i64, // unix millis, startup until the first check
+    pub last_crawl_success: i64, // unix millis, startup until the first crawl
+    pub consecutive_crawl_failures: u32,
+}
+
+impl HealthTracker {
+    pub fn new() -> Self {
+        let now = chrono::Utc::now().timestamp_millis();
+        HealthTracker {
+            state: Mutex::new(HealthState {
+                last_monitor_check: now,
+                last_crawl_success: now,
+                consecutive_crawl_failures: 0,
+            }),
+        }
+    }
+
+    pub fn record_monitor_check(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.last_monitor_check = chrono::Utc::now().timestamp_millis();
+        }
+    }
+
+    pub fn record_crawl_attempt(&self, success: bool) {
+        if let Ok(mut state) = self.state.lock() {
+            if success {
+                state.last_crawl_success = chrono::Utc::now().timestamp_millis();
+                state.consecutive_crawl_failures = 0;
+            } else {
+                state.consecutive_crawl_failures += 1;
+            }
+        }
+    }
+
+    fn snapshot(&self) -> Option<HealthState> {
+        self.state.lock().ok().map(|state| state.clone())
+    }
+}
+
+impl Default for HealthTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns (kind, message) of every failed check
+pub fn evaluate_health(
+    state: &HealthState,
+    now: i64,
+    monitor_interval_millis: Option<i64>,
+    queue_size: usize,
+    max_crawl_failures: u32,
+) -> Vec<(&'static str, String)> {
+    let mut problems = Vec::new();
+
+    if let Some(interval) = monitor_interval_millis {
+        let late_after = interval * MONITOR_INTERVALS_TOLERATED;
+        if now - state.last_monitor_check > late_after {
+            problems.push((
+                "monitor_stalled",
+                format!(
+                    "No monitor check completed for {} minutes (schedule interval: {} minutes)",
+                    (now - state.last_monitor_check) / 60_000,
+                    interval / 60_000
+                ),
+            ));
+        }
+    }
+
+    if queue_size > 0 && now - state.last_crawl_success > MAX_QUEUE_STALL_MILLIS {
+        problems.push((
+            "queue_stalled",
+            format!(
+                "{} videos are queued but no crawl succeeded for {} minutes",
+                queue_size,
+                (now - state.last_crawl_success) / 60_000
+            ),
+        ));
+    }
+
+    if max_crawl_failures > 0 && state.consecutive_crawl_failures >= max_crawl_failures {
+        problems.push((
+            "crawl_failing",
+            format!(
+                "The last {} crawl attempts failed",
+                state.consecutive_crawl_failures
+            ),
+        ));
+    }
+
+    problems
+}
+
+/// Raises an alert for every failed check that has no unacknowledged alert yet
+pub async fn run_health_check(es_client: &Elasticsearch, video_queue: &VideoQueue) {
+    let Some(state) = HEALTH.snapshot() else {
+        return;
+    };
+    let now = chrono::Utc::now().timestamp_millis();
+    let problems = evaluate_health(
+        &state,
+        now,
+        MONITOR_CHECK_SCHEDULE.interval_millis(),
+        video_queue.get_size(),
+        *HEALTH_MAX_CRAWL_FAILURES,
+    );
+
+    for (kind, message) in problems {
+        match has_open_alert(es_client, kind).await {
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(e) => {
+                error!("Failed to look up open health alerts: {e:?}");
+                continue;
+            }
+        }
+
+        warn!("Health alert ({kind}): {message}");
+        let alert = HealthAlert {
+            id: format!("{}_{}", kind, now),
+            kind: kind.to_string(),
+            message,
+            created_at: now,
+            acknowledged: false,
+        };
+        if let Err(e) = store_alert(es_client, &alert).await {
+            error!("Failed to store health alert: {e:?}");
+        }
+        publish_alert(&alert).await;
+    }
+}
+
+async fn has_open_alert(es_client: &Elasticsearch, kind: &str) -> Result<bool> {
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.alerts]))
+        .body(json!({
+            "size": 0,
+            "query": {
+                "bool": {
+                    "filter": [
+                        { "term": { "kind": kind } },
+                        { "term": { "acknowledged": false } }
+                    ]
+                }
+            }
+        }))
+        .send()
+        .await?;
+    let body: Value = response.json().await?;
+    Ok(body["hits"]["total"]["value"].as_i64().unwrap_or(0) > 0)
+}
+
+async fn store_alert(es_client: &Elasticsearch, alert: &HealthAlert) -> Result<()> {
+    let response = es_client
+        .index(IndexParts::IndexId(INDEX_NAMES.alerts, &alert.id))
+        .body(json!(alert))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Indexing alert failed with status: {}",
+            response.status_code()
+        ));
+    }
+    Ok(())
+}
+
+/// Mails the alert to the digest recipients if SMTP is configured
+async fn publish_alert(alert: &HealthAlert) {
+    let Some(config) = &*SMTP_CONFIG else {
+        return;
+    };
+    let email = Email {
+        subject: format!("{}: health alert ({})", &*APP_NAME, alert.kind),
+        text: alert.message.clone(),
+        html: format!("<p>{}</p>", alert.message),
+    };
+    if let Err(e) = notification_service::send_email(config, &email).await {
+        error!("Failed to send health alert email: {e:?}");
+    }
+}
+
+pub async fn list_alerts(
+    es_client: &Elasticsearch,
+    include_acknowledged: bool,
+) -> Result<Vec<HealthAlert>> {
+    let query = if include_acknowledged {
+        json!({ "match_all": {} })
+    } else {
+        json!({ "term": { "acknowledged": false } })
+    };
+
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.alerts]))
+        .body(json!({
+            "size": 100,
+            "query": query,
+            "sort": [{ "created_at": { "order": "desc" } }]
+        }))
+        .send()
+        .await?;
+
+    let body: Value = response.json().await?;
+    Ok(body["hits"]["hits"]
+        .as_array()
+        .map(|hits| {
+            hits.iter()
+                .filter_map(|hit| serde_json::from_value(hit["_source"].clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Returns false if there is no alert with that id
+pub async fn acknowledge_alert(es_client: &Elasticsearch, id: &str) -> Result<bool> {
+    let response = es_client
+        .update(UpdateParts::IndexId(INDEX_NAMES.alerts, id))
+        .body(json!({ "doc": { "acknowledged": true } }))
+        .send()
+        .await?;
+
+    match response.status_code().as_u16() {
+        404 => Ok(false),
+        status if (200..300).contains(&status) => {
+            info!("Health alert {id} acknowledged");
+            Ok(true)
+        }
+        status => Err(anyhow::anyhow!(
+            "Acknowledging alert failed with status: {status}"
+        )),
+    }
+}
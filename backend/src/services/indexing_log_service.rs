@@ -0,0 +1,115 @@
+//! Permanent record of what the crawl worker did: one entry per processed queue item.
+//! The queue forgets items once they complete, this index is never pruned.
+
+use crate::config::INDEX_NAMES;
+use crate::models::{IndexingLogEntry, IndexingLogResponse};
+use anyhow::Result;
+use elasticsearch::{Elasticsearch, IndexParts, SearchParts};
+use log::warn;
+use serde_json::{json, Value};
+
+pub const STATUS_COMPLETED: &str = "completed";
+pub const STATUS_FAILED: &str = "failed";
+
+/// Note of completed crawls that skipped captions for the video's duration
+pub const NOTE_SKIPPED_TOO_LONG: &str = "skipped: too long";
+/// Note of recrawls whose transcript differed from the indexed captions
+pub const NOTE_TRANSCRIPT_CHANGED: &str = "captions: changed";
+/// Note of recrawls that replaced auto-generated captions with a manual track
+pub const NOTE_TRANSCRIPT_UPGRADED: &str = "captions: upgraded to manual";
+
+/// Stores the outcome of one crawl. A failed write is only logged, it must not fail
+/// the crawl itself.
+pub async fn record(es_client: &Elasticsearch, entry: &IndexingLogEntry) {
+    match es_client
+        .index(IndexParts::Index(INDEX_NAMES.indexing_log))
+        .body(json!(entry))
+        .send()
+        .await
+    {
+        Ok(response) if !response.status_code().is_success() => warn!(
+            "Failed to write indexing log of video {}: {}",
+            entry.video_id,
+            response.status_code()
+        ),
+        Ok(_) => {}
+        Err(e) => warn!(
+            "Failed to write indexing log of video {}: {e:?}",
+            entry.video_id
+        ),
+    }
+}
+
+/// Distinct videos with at least one entry carrying `note`
+pub async fn count_videos_with_note(es_client: &Elasticsearch, note: &str) -> Result<i64> {
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.indexing_log]))
+        .body(json!({
+            "size": 0,
+            "query": { "term": { "note": note } },
+            "aggs": { "videos": { "cardinality": { "field": "video_id" } } }
+        }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Indexing log count failed: {}",
+            response.status_code()
+        ));
+    }
+
+    let json_response: Value = response.json().await?;
+    Ok(json_response["aggregations"]["videos"]["value"]
+        .as_i64()
+        .unwrap_or(0))
+}
+
+/// Entries matching all given filters, newest first
+pub async fn get_entries(
+    es_client: &Elasticsearch,
+    video_id: Option<&str>,
+    channel_id: Option<&str>,
+    status: Option<&str>,
+    page: i64,
+    per_page: i64,
+) -> Result<IndexingLogResponse> {
+    let filters: Vec<Value> = [
+        ("video_id", video_id),
+        ("channel_id", channel_id),
+        ("status", status),
+    ]
+    .into_iter()
+    .filter_map(|(field, value)| value.map(|value| json!({ "term": { field: value } })))
+    .collect();
+
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.indexing_log]))
+        .body(json!({
+            "size": per_page,
+            "from": (page - 1) * per_page,
+            "query": { "bool": { "filter": filters } },
+            "sort": [{ "finished_at": { "order": "desc" } }]
+        }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Indexing log search failed: {}",
+            response.status_code()
+        ));
+    }
+
+    let json_response: Value = response.json().await?;
+    let entries = json_response["hits"]["hits"]
+        .as_array()
+        .map(|hits| {
+            hits.iter()
+                .filter_map(|hit| serde_json::from_value(hit["_source"].clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    let total = json_response["hits"]["total"]["value"]
+        .as_i64()
+        .unwrap_or(0);
+    Ok(IndexingLogResponse::new(entries, total, page, per_page))
+}
@@ -0,0 +1,906 @@
+use crate::config::INDEX_NAMES;
+use crate::models::CAPTION_SEGMENT_SECONDS;
+use crate::services::admin_service::get_index_count;
+use crate::services::crawler::{self, process_video_captions};
+use crate::services::maintenance_throttle::MaintenanceRun;
+use crate::services::sentence_service;
+use crate::services::transcript_throttle::TRANSCRIPT_THROTTLE;
+use crate::utils;
+use anyhow::Result;
+use elasticsearch::http::request::JsonBody;
+use elasticsearch::indices::IndicesPutMappingParts;
+use elasticsearch::params::{Conflicts, Refresh};
+use elasticsearch::{
+    BulkParts, ClearScrollParts, DeleteByQueryParts, Elasticsearch, ScrollParts, SearchParts,
+    UpdateByQueryParts,
+};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{BufRead, Write};
+
+const SCROLL_KEEP_ALIVE: &str = "2m";
+const SCROLL_PAGE_SIZE: i64 = 1000;
+const IMPORT_BATCH_SIZE: usize = 500;
+
+/// One line of an NDJSON export
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedDocument {
+    pub index: String, // unprefixed, so an export can be imported into any environment
+    pub id: String,
+    pub source: Value,
+}
+
+#[derive(Debug, Default)]
+pub struct BackfillReport {
+    pub attempted: usize,
+    pub completed: usize,
+    /// Left for later because transcript fetching got throttled or timed out
+    pub deferred: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    pub videos: i64,
+    pub captions: i64,
+    pub videos_without_captions: usize,
+    /// Videos that have captions indexed but no metadata document
+    pub orphaned_caption_videos: Vec<String>,
+    /// Videos whose stored caption coverage was outdated and got recomputed
+    pub recomputed_coverage: usize,
+}
+
+impl VerifyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.orphaned_caption_videos.is_empty()
+    }
+}
+
+/// Ids of all videos whose metadata says no captions were indexed
+pub async fn videos_without_captions(es_client: &Elasticsearch) -> Result<Vec<String>> {
+    let mut video_ids = Vec::new();
+    scroll_index(
+        es_client,
+        INDEX_NAMES.videos,
+        json!({ "term": { "has_captions": false } }),
+        false,
+        |hit| {
+            if let Some(id) = hit["_id"].as_str() {
+                video_ids.push(id.to_string());
+            }
+            Ok(())
+        },
+    )
+    .await?;
+    Ok(video_ids)
+}
+
+/// Fetches captions for every video that has none, the same way a crawl does, pausing
+/// after every batch of the maintenance `run`. Stops early once YouTube throttles
+/// transcript fetches, timed out videos are skipped.
+pub async fn backfill_captions(
+    es_client: &Elasticsearch,
+    mut progress: impl FnMut(usize, usize),
+    run: &MaintenanceRun,
+) -> Result<BackfillReport> {
+    let video_ids = videos_without_captions(es_client).await?;
+    let mut report = BackfillReport::default();
+
+    for (i, video_id) in video_ids.iter().enumerate() {
+        if i > 0 && i % run.throttle().batch_size == 0 {
+            run.pause().await;
+        }
+        report.attempted += 1;
+        if process_video_captions(es_client, video_id).await.is_some() {
+            report.completed += 1;
+        } else if TRANSCRIPT_THROTTLE.cooldown_until().is_some() {
+            warn!("Caption fetching is throttled, stopping the backfill");
+            report.deferred += video_ids.len() - i;
+            break;
+        } else {
+            report.deferred += 1;
+        }
+        progress(i + 1, video_ids.len());
+    }
+
+    Ok(report)
+}
+
+/// Sets `segment` on captions indexed before transcripts were segmented, at the pace of
+/// the maintenance `run`. Returns the number of updated captions.
+pub async fn backfill_caption_segments(
+    es_client: &Elasticsearch,
+    run: &MaintenanceRun,
+) -> Result<i64> {
+    let response = es_client
+        .update_by_query(UpdateByQueryParts::Index(&[INDEX_NAMES.captions]))
+        .conflicts(Conflicts::Proceed)
+        .scroll_size(run.throttle().scroll_size())
+        .requests_per_second(run.throttle().requests_per_second())
+        .refresh(true)
+        .body(json!({
+            "query": { "bool": { "must_not": { "exists": { "field": "segment" } } } },
+            "script": {
+                "source": "ctx._source.segment = (int) Math.floor(ctx._source.start_time / params.segment_seconds)",
+                "lang": "painless",
+                "params": { "segment_seconds": CAPTION_SEGMENT_SECONDS }
+            }
+        }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Segment backfill failed: {}",
+            response.status_code()
+        ));
+    }
+
+    let json_response: Value = response.json().await?;
+    let updated = json_response["updated"].as_i64().unwrap_or(0);
+    info!("Backfilled the segment of {updated} captions");
+    Ok(updated)
+}
+
+/// Copies the upload date of every video onto its captions and sentences that don't have
+/// it yet. Returns the number of updated documents.
+pub async fn backfill_caption_upload_dates(
+    es_client: &Elasticsearch,
+    run: &MaintenanceRun,
+) -> Result<i64> {
+    copy_video_field_to_captions(es_client, "upload_date", run).await
+}
+
+/// Copies the channel of every video onto its captions and sentences that don't have it
+/// yet, searches filter channels by it. Returns the number of updated documents.
+pub async fn backfill_caption_channel_ids(
+    es_client: &Elasticsearch,
+    run: &MaintenanceRun,
+) -> Result<i64> {
+    copy_video_field_to_captions(es_client, "channel_id", run).await
+}
+
+/// Sets `field` of the captions and sentences without it to the value of their video, a
+/// chunk of videos at a time at the pace of the maintenance `run`
+async fn copy_video_field_to_captions(
+    es_client: &Elasticsearch,
+    field: &str,
+    run: &MaintenanceRun,
+) -> Result<i64> {
+    let mut values: Vec<(String, Value)> = Vec::new();
+    scroll_index(
+        es_client,
+        INDEX_NAMES.videos,
+        json!({ "exists": { "field": field } }),
+        true,
+        |hit| {
+            if let (Some(id), Some(value)) = (hit["_id"].as_str(), hit["_source"].get(field)) {
+                values.push((id.to_string(), value.clone()));
+            }
+            Ok(())
+        },
+    )
+    .await?;
+
+    let mut updated = 0;
+    for (i, chunk) in values.chunks(1000).enumerate() {
+        if i > 0 {
+            run.pause().await;
+        }
+        run.progress((i * 1000) as i64, values.len() as i64);
+        let video_ids: Vec<&str> = chunk.iter().map(|(id, _)| id.as_str()).collect();
+        let by_video: serde_json::Map<String, Value> = chunk.iter().cloned().collect();
+        let response = es_client
+            .update_by_query(UpdateByQueryParts::Index(&[
+                INDEX_NAMES.captions,
+                INDEX_NAMES.sentences,
+            ]))
+            .conflicts(Conflicts::Proceed)
+            .scroll_size(run.throttle().scroll_size())
+            .requests_per_second(run.throttle().requests_per_second())
+            .refresh(true)
+            .body(json!({
+                "query": {
+                    "bool": {
+                        "filter": { "terms": { "video_id": video_ids } },
+                        "must_not": { "exists": { "field": field } }
+                    }
+                },
+                "script": {
+                    "source": "ctx._source[params.field] = params.values[ctx._source.video_id]",
+                    "lang": "painless",
+                    "params": { "field": field, "values": by_video }
+                }
+            }))
+            .send()
+            .await?;
+        if !response.status_code().is_success() {
+            return Err(anyhow::anyhow!(
+                "Backfilling {field} failed: {}",
+                response.status_code()
+            ));
+        }
+        let json_response: Value = response.json().await?;
+        updated += json_response["updated"].as_i64().unwrap_or(0);
+    }
+
+    info!("Backfilled {field} of {updated} captions and sentences");
+    Ok(updated)
+}
+
+/// Keeps the text of captions indexed before it was normalized as `raw_text` and
+/// replaces it with `utils::normalize_caption_text`, then restitches the sentences of
+/// the changed videos. Works page by page at the pace of the maintenance `run`. Returns
+/// the number of updated captions.
+pub async fn normalize_caption_texts(
+    es_client: &Elasticsearch,
+    run: &MaintenanceRun,
+) -> Result<usize> {
+    let response = es_client
+        .indices()
+        .put_mapping(IndicesPutMappingParts::Index(&[INDEX_NAMES.captions]))
+        .body(json!({ "properties": { "raw_text": { "type": "text", "index": false } } }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Mapping raw_text failed: {}",
+            response.text().await.unwrap_or_default()
+        ));
+    }
+
+    let mut scroll = IndexScroll::start(
+        es_client,
+        INDEX_NAMES.captions,
+        json!({ "match_all": {} }),
+        true,
+        run.throttle().scroll_size(),
+    )
+    .await?;
+    let mut scanned = 0;
+    let mut changed = 0;
+    let mut video_ids: BTreeSet<String> = BTreeSet::new();
+    while let Some(hits) = scroll.next_page().await? {
+        if scanned > 0 {
+            run.pause().await;
+        }
+        let mut updates: Vec<(String, Value)> = Vec::new();
+        for hit in &hits {
+            let source = &hit["_source"];
+            let (Some(id), Some(video_id), Some(text)) = (
+                hit["_id"].as_str(),
+                source["video_id"].as_str(),
+                source["text"].as_str(),
+            ) else {
+                continue;
+            };
+            let raw_text = source["raw_text"].as_str();
+            let normalized = utils::normalize_caption_text(raw_text.unwrap_or(text));
+            if raw_text.is_none() || normalized != text {
+                updates.push((
+                    id.to_string(),
+                    json!({ "raw_text": raw_text.unwrap_or(text), "text": normalized }),
+                ));
+                video_ids.insert(video_id.to_string());
+            }
+        }
+        bulk_update(es_client, INDEX_NAMES.captions, &updates).await?;
+        scanned += hits.len();
+        changed += updates.len();
+        run.progress(scanned as i64, scroll.total);
+    }
+
+    let mut since_pause = 0;
+    for video_id in &video_ids {
+        match sentence_service::rebuild_video(es_client, video_id).await {
+            Ok(count) => since_pause += count,
+            Err(e) => warn!("Failed to rebuild the sentences of {video_id}: {e:?}"),
+        }
+        if since_pause >= run.throttle().batch_size {
+            run.pause().await;
+            since_pause = 0;
+        }
+    }
+
+    info!(
+        "Normalized the text of {} captions of {} videos",
+        changed,
+        video_ids.len()
+    );
+    Ok(changed)
+}
+
+/// Recomputes the caption coverage of every video from its indexed captions. Returns the
+/// number of videos whose coverage changed.
+pub async fn recompute_caption_coverage(es_client: &Elasticsearch) -> Result<usize> {
+    let mut intervals: HashMap<String, Vec<(f64, f64)>> = HashMap::new();
+    scroll_index(
+        es_client,
+        INDEX_NAMES.captions,
+        json!({ "match_all": {} }),
+        true,
+        |hit| {
+            let source = &hit["_source"];
+            if let (Some(video_id), Some(start), Some(end)) = (
+                source["video_id"].as_str(),
+                source["start_time"].as_f64(),
+                source["end_time"].as_f64(),
+            ) {
+                intervals
+                    .entry(video_id.to_string())
+                    .or_default()
+                    .push((start, end));
+            }
+            Ok(())
+        },
+    )
+    .await?;
+
+    let mut updates: Vec<(String, Value)> = Vec::new();
+    scroll_index(
+        es_client,
+        INDEX_NAMES.videos,
+        json!({ "match_all": {} }),
+        true,
+        |hit| {
+            let Some(video_id) = hit["_id"].as_str() else {
+                return Ok(());
+            };
+            let duration = hit["_source"]["duration"].as_i64().unwrap_or(0);
+            let coverage = intervals.remove(video_id).and_then(|intervals| {
+                crawler::caption_coverage(crawler::captioned_seconds(intervals), duration)
+            });
+            let stored = hit["_source"]["caption_coverage"].as_f64();
+            let unchanged = match (coverage, stored) {
+                (Some(coverage), Some(stored)) => (coverage - stored).abs() < 0.001,
+                (coverage, stored) => coverage.is_none() && stored.is_none(),
+            };
+            if !unchanged {
+                updates.push((
+                    video_id.to_string(),
+                    json!({ "caption_coverage": coverage }),
+                ));
+            }
+            Ok(())
+        },
+    )
+    .await?;
+
+    bulk_update(es_client, INDEX_NAMES.videos, &updates).await?;
+    info!(
+        "Recomputed the caption coverage of {} videos",
+        updates.len()
+    );
+    Ok(updates.len())
+}
+
+/// Deletes all sentences, then stitches them anew from the captions of every video.
+/// With a maintenance `run` it pauses after every batch of sentences. Returns the number
+/// of sentences indexed.
+pub async fn rebuild_sentences(
+    es_client: &Elasticsearch,
+    mut progress: impl FnMut(usize, usize),
+    run: Option<&MaintenanceRun>,
+) -> Result<usize> {
+    let response = es_client
+        .delete_by_query(DeleteByQueryParts::Index(&[INDEX_NAMES.sentences]))
+        .conflicts(Conflicts::Proceed)
+        .refresh(true)
+        .body(json!({ "query": { "match_all": {} } }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Deleting sentences failed: {}",
+            response.status_code()
+        ));
+    }
+
+    let mut video_ids: Vec<String> = caption_video_ids(es_client).await?.into_iter().collect();
+    video_ids.sort();
+    let mut total = 0;
+    let mut since_pause = 0;
+    for (i, video_id) in video_ids.iter().enumerate() {
+        match sentence_service::rebuild_video(es_client, video_id).await {
+            Ok(count) => {
+                total += count;
+                since_pause += count;
+            }
+            Err(e) => warn!("Failed to rebuild the sentences of {video_id}: {e:?}"),
+        }
+        progress(i + 1, video_ids.len());
+        if let Some(run) = run.filter(|run| since_pause >= run.throttle().batch_size) {
+            run.pause().await;
+            since_pause = 0;
+        }
+    }
+
+    info!("Rebuilt {total} sentences of {} videos", video_ids.len());
+    Ok(total)
+}
+
+/// Ids of every video with a metadata document, hidden ones included
+async fn all_video_ids(es_client: &Elasticsearch) -> Result<HashSet<String>> {
+    let mut video_ids = HashSet::new();
+    scroll_index(
+        es_client,
+        INDEX_NAMES.videos,
+        json!({ "match_all": {} }),
+        false,
+        |hit| {
+            if let Some(id) = hit["_id"].as_str() {
+                video_ids.insert(id.to_string());
+            }
+            Ok(())
+        },
+    )
+    .await?;
+    Ok(video_ids)
+}
+
+/// Video ids referenced by captions, collected with a paginated composite aggregation
+async fn caption_video_ids(es_client: &Elasticsearch) -> Result<HashSet<String>> {
+    let mut video_ids = HashSet::new();
+    let mut after: Option<Value> = None;
+
+    loop {
+        let mut composite = json!({
+            "size": 1000,
+            "sources": [{ "video_id": { "terms": { "field": "video_id" } } }]
+        });
+        if let Some(after_key) = &after {
+            composite["after"] = after_key.clone();
+        }
+
+        let response = es_client
+            .search(SearchParts::Index(&[INDEX_NAMES.captions]))
+            .body(json!({
+                "size": 0,
+                "aggs": { "videos": { "composite": composite } }
+            }))
+            .send()
+            .await?;
+        if !response.status_code().is_success() {
+            return Err(anyhow::anyhow!(
+                "Caption aggregation failed with status: {}",
+                response.status_code()
+            ));
+        }
+
+        let body: Value = response.json().await?;
+        let aggregation = &body["aggregations"]["videos"];
+        let buckets = aggregation["buckets"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        for bucket in &buckets {
+            if let Some(id) = bucket["key"]["video_id"].as_str() {
+                video_ids.insert(id.to_string());
+            }
+        }
+
+        match aggregation.get("after_key") {
+            Some(after_key) if !buckets.is_empty() => after = Some(after_key.clone()),
+            _ => break,
+        }
+    }
+
+    Ok(video_ids)
+}
+
+async fn find_orphaned_caption_videos(es_client: &Elasticsearch) -> Result<Vec<String>> {
+    let videos = all_video_ids(es_client).await?;
+    let mut orphans: Vec<String> = caption_video_ids(es_client)
+        .await?
+        .into_iter()
+        .filter(|id| !videos.contains(id))
+        .collect();
+    orphans.sort();
+    Ok(orphans)
+}
+
+/// Deletes captions whose video has no metadata document, at the pace of the
+/// maintenance `run`. Returns the affected video ids.
+pub async fn prune_orphaned_captions(
+    es_client: &Elasticsearch,
+    run: &MaintenanceRun,
+) -> Result<Vec<String>> {
+    let orphans = find_orphaned_caption_videos(es_client).await?;
+
+    for (i, chunk) in orphans.chunks(500).enumerate() {
+        if i > 0 {
+            run.pause().await;
+        }
+        run.progress((i * 500) as i64, orphans.len() as i64);
+        let response = es_client
+            .delete_by_query(DeleteByQueryParts::Index(&[
+                INDEX_NAMES.captions,
+                INDEX_NAMES.sentences,
+            ]))
+            .conflicts(Conflicts::Proceed)
+            .scroll_size(run.throttle().scroll_size())
+            .requests_per_second(run.throttle().requests_per_second())
+            .body(json!({ "query": { "terms": { "video_id": chunk } } }))
+            .send()
+            .await?;
+        if !response.status_code().is_success() {
+            return Err(anyhow::anyhow!(
+                "Deleting orphaned captions failed with status: {}",
+                response.status_code()
+            ));
+        }
+    }
+
+    if !orphans.is_empty() {
+        info!("Pruned captions of {} orphaned videos", orphans.len());
+    }
+    Ok(orphans)
+}
+
+pub async fn verify(es_client: &Elasticsearch) -> Result<VerifyReport> {
+    Ok(VerifyReport {
+        videos: get_index_count(es_client, INDEX_NAMES.videos).await,
+        captions: get_index_count(es_client, INDEX_NAMES.captions).await,
+        videos_without_captions: videos_without_captions(es_client).await?.len(),
+        orphaned_caption_videos: find_orphaned_caption_videos(es_client).await?,
+        recomputed_coverage: recompute_caption_coverage(es_client).await?,
+    })
+}
+
+/// Writes every document of `IndexNames::exported` as one `ExportedDocument` per line.
+/// Returns the number of exported documents.
+pub async fn export_documents(
+    es_client: &Elasticsearch,
+    out: &mut impl Write,
+    mut progress: impl FnMut(&str, usize),
+) -> Result<usize> {
+    let mut total = 0;
+    for (name, index) in INDEX_NAMES.exported() {
+        let mut exported = 0;
+        scroll_index(es_client, index, json!({ "match_all": {} }), true, |hit| {
+            let document = ExportedDocument {
+                index: name.to_string(),
+                id: hit["_id"].as_str().unwrap_or_default().to_string(),
+                source: hit["_source"].clone(),
+            };
+            serde_json::to_writer(&mut *out, &document)?;
+            out.write_all(b"\n")?;
+            exported += 1;
+            Ok(())
+        })
+        .await?;
+        progress(index, exported);
+        total += exported;
+    }
+    out.flush()?;
+    Ok(total)
+}
+
+/// Indexes the documents of an NDJSON export, overwriting documents with the same id.
+/// Returns the number of imported documents.
+pub async fn import_documents(
+    es_client: &Elasticsearch,
+    input: impl BufRead,
+    mut progress: impl FnMut(usize),
+) -> Result<usize> {
+    let mut batch: Vec<ExportedDocument> = Vec::new();
+    let mut imported = 0;
+
+    for (line_number, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut document: ExportedDocument = serde_json::from_str(&line)
+            .map_err(|e| anyhow::anyhow!("Line {}: {e}", line_number + 1))?;
+        let Some((_, index)) = INDEX_NAMES
+            .exported()
+            .into_iter()
+            .find(|(name, _)| *name == document.index)
+        else {
+            return Err(anyhow::anyhow!(
+                "Line {}: unknown index '{}'",
+                line_number + 1,
+                document.index
+            ));
+        };
+        document.index = index.to_string();
+        batch.push(document);
+
+        if batch.len() >= IMPORT_BATCH_SIZE {
+            imported += bulk_index(es_client, &batch).await?;
+            batch.clear();
+            progress(imported);
+        }
+    }
+    if !batch.is_empty() {
+        imported += bulk_index(es_client, &batch).await?;
+        progress(imported);
+    }
+
+    Ok(imported)
+}
+
+async fn bulk_index(es_client: &Elasticsearch, documents: &[ExportedDocument]) -> Result<usize> {
+    let mut body: Vec<JsonBody<Value>> = Vec::with_capacity(documents.len() * 2);
+    for document in documents {
+        body.push(json!({ "index": { "_index": document.index, "_id": document.id } }).into());
+        body.push(document.source.clone().into());
+    }
+
+    let response = es_client.bulk(BulkParts::None).body(body).send().await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Bulk import failed with status: {}",
+            response.status_code()
+        ));
+    }
+
+    let result: Value = response.json().await?;
+    if result["errors"].as_bool().unwrap_or(false) {
+        let first_error = result["items"]
+            .as_array()
+            .and_then(|items| items.iter().find(|item| !item["index"]["error"].is_null()))
+            .map(|item| item["index"]["error"].to_string())
+            .unwrap_or_default();
+        return Err(anyhow::anyhow!("Bulk import had errors: {first_error}"));
+    }
+    Ok(documents.len())
+}
+
+/// Applies partial `(id, doc)` updates to documents of `index` in batches
+async fn bulk_update(
+    es_client: &Elasticsearch,
+    index: &str,
+    updates: &[(String, Value)],
+) -> Result<()> {
+    for chunk in updates.chunks(IMPORT_BATCH_SIZE) {
+        let mut body: Vec<JsonBody<Value>> = Vec::with_capacity(chunk.len() * 2);
+        for (id, doc) in chunk {
+            body.push(json!({ "update": { "_index": index, "_id": id } }).into());
+            body.push(json!({ "doc": doc }).into());
+        }
+        let response = es_client
+            .bulk(BulkParts::None)
+            .refresh(Refresh::True)
+            .body(body)
+            .send()
+            .await?;
+        if !response.status_code().is_success() {
+            return Err(anyhow::anyhow!(
+                "Bulk update of {index} failed: {}",
+                response.status_code()
+            ));
+        }
+        let result: Value = response.json().await?;
+        if result["errors"].as_bool().unwrap_or(false) {
+            warn!("Some documents of {index} could not be updated, run the job again");
+        }
+    }
+    Ok(())
+}
+
+/// Calls `handle` for every hit of the query, paging with the scroll API
+pub(crate) async fn scroll_index(
+    es_client: &Elasticsearch,
+    index: &str,
+    query: Value,
+    with_source: bool,
+    mut handle: impl FnMut(&Value) -> Result<()>,
+) -> Result<()> {
+    let mut scroll =
+        IndexScroll::start(es_client, index, query, with_source, SCROLL_PAGE_SIZE).await?;
+    while let Some(hits) = scroll.next_page().await? {
+        for hit in &hits {
+            handle(hit)?;
+        }
+    }
+    Ok(())
+}
+
+/// The hits of a query one page at a time, for callers that write back each page before
+/// reading the next
+struct IndexScroll<'a> {
+    es_client: &'a Elasticsearch,
+    /// Number of hits of the query
+    total: i64,
+    /// Response whose hits weren't returned yet
+    pending: Option<Value>,
+    scroll_id: Option<String>,
+}
+
+impl<'a> IndexScroll<'a> {
+    async fn start(
+        es_client: &'a Elasticsearch,
+        index: &str,
+        query: Value,
+        with_source: bool,
+        page_size: i64,
+    ) -> Result<IndexScroll<'a>> {
+        let response = es_client
+            .search(SearchParts::Index(&[index]))
+            .scroll(SCROLL_KEEP_ALIVE)
+            .body(json!({
+                "size": page_size,
+                "query": query,
+                "sort": ["_doc"],
+                "_source": with_source,
+                "track_total_hits": true
+            }))
+            .send()
+            .await?;
+        if !response.status_code().is_success() {
+            return Err(anyhow::anyhow!(
+                "Searching index '{index}' failed with status: {}",
+                response.status_code()
+            ));
+        }
+        let body: Value = response.json().await?;
+        Ok(IndexScroll {
+            es_client,
+            total: body["hits"]["total"]["value"].as_i64().unwrap_or(0),
+            pending: Some(body),
+            scroll_id: None,
+        })
+    }
+
+    /// The next page of hits, None once all were returned. The scroll is cleared then.
+    async fn next_page(&mut self) -> Result<Option<Vec<Value>>> {
+        let body = match (self.pending.take(), &self.scroll_id) {
+            (Some(body), _) => body,
+            (None, Some(scroll_id)) => {
+                self.es_client
+                    .scroll(ScrollParts::None)
+                    .body(json!({ "scroll": SCROLL_KEEP_ALIVE, "scroll_id": scroll_id }))
+                    .send()
+                    .await?
+                    .json()
+                    .await?
+            }
+            (None, None) => return Ok(None),
+        };
+        self.scroll_id = body["_scroll_id"].as_str().map(str::to_string);
+
+        let hits = body["hits"]["hits"].as_array().cloned().unwrap_or_default();
+        if hits.is_empty() {
+            if let Some(scroll_id) = self.scroll_id.take() {
+                let _ = self
+                    .es_client
+                    .clear_scroll(ClearScrollParts::None)
+                    .body(json!({ "scroll_id": scroll_id }))
+                    .send()
+                    .await;
+            }
+            return Ok(None);
+        }
+        Ok(Some(hits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsupport::{maintenance_run, search_hits, MockElasticsearch};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[rocket::async_test]
+    async fn caption_texts_are_normalized_once() {
+        let es = MockElasticsearch::start().await;
+        es.stub(
+            "PUT",
+            &format!("/{}/_mapping", INDEX_NAMES.captions),
+            json!({ "acknowledged": true }),
+        )
+        .await;
+        let mut first_page = search_hits(vec![
+            // Indexed before normalization
+            (
+                "legacy",
+                json!({ "video_id": "aaaaaaaaaaa", "text": "wait ,what  ?" }),
+            ),
+            // Indexed by the current crawler
+            (
+                "current",
+                json!({
+                    "video_id": "bbbbbbbbbbb",
+                    "raw_text": "no  way !",
+                    "text": "no way!"
+                }),
+            ),
+        ]);
+        first_page["hits"]["total"]["value"] = json!(3);
+        first_page["_scroll_id"] = json!("scroll-1");
+        es.stub(
+            "POST",
+            &format!("/{}/_search", INDEX_NAMES.captions),
+            first_page,
+        )
+        .await;
+        let pages = Arc::new(AtomicUsize::new(0));
+        es.stub_fn("POST", "/_search/scroll", {
+            let pages = pages.clone();
+            move |_| {
+                let mut page = match pages.fetch_add(1, Ordering::SeqCst) {
+                    0 => search_hits(vec![(
+                        "legacy2",
+                        json!({ "video_id": "ccccccccccc", "text": "so ,  what" }),
+                    )]),
+                    _ => search_hits(vec![]),
+                };
+                page["_scroll_id"] = json!("scroll-1");
+                page
+            }
+        })
+        .await;
+        es.stub("POST", "/_bulk", json!({ "errors": false, "items": [] }))
+            .await;
+
+        let run = maintenance_run();
+        assert_eq!(normalize_caption_texts(&es.client, &run).await.unwrap(), 2);
+
+        // Each page is written back before the next one is read
+        let bulk = es.ndjson_bodies("POST", "/_bulk").await;
+        assert_eq!(bulk.len(), 2);
+        assert_eq!(bulk[0][0]["update"]["_id"], "legacy");
+        assert_eq!(
+            bulk[0][1]["doc"],
+            json!({ "raw_text": "wait ,what  ?", "text": "wait,what?" })
+        );
+        assert_eq!(bulk[0].len(), 2, "normalized captions are left alone");
+        assert_eq!(bulk[1][0]["update"]["_id"], "legacy2");
+        let search = &es
+            .bodies("POST", &format!("/{}/_search", INDEX_NAMES.captions))
+            .await[0];
+        assert_eq!(search["size"], json!(run.throttle().scroll_size()));
+        let current = run.throttle().current().unwrap();
+        assert_eq!((current.done, current.total), (3, 3));
+    }
+
+    #[rocket::async_test]
+    async fn orphans_are_pruned_at_the_pace_of_the_run() {
+        let es = MockElasticsearch::start().await;
+        es.stub(
+            "POST",
+            &format!("/{}/_search", INDEX_NAMES.videos),
+            search_hits(vec![("aaaaaaaaaaa", json!({}))]),
+        )
+        .await;
+        es.stub(
+            "POST",
+            &format!("/{}/_search", INDEX_NAMES.captions),
+            json!({
+                "aggregations": {
+                    "videos": {
+                        "buckets": [
+                            { "key": { "video_id": "aaaaaaaaaaa" }, "doc_count": 3 },
+                            { "key": { "video_id": "bbbbbbbbbbb" }, "doc_count": 2 }
+                        ]
+                    }
+                }
+            }),
+        )
+        .await;
+        let delete = format!(
+            "/{},{}/_delete_by_query",
+            INDEX_NAMES.captions, INDEX_NAMES.sentences
+        );
+        es.stub("POST", &delete, json!({ "deleted": 2 })).await;
+
+        let run = maintenance_run();
+        let orphans = prune_orphaned_captions(&es.client, &run).await.unwrap();
+        assert_eq!(orphans, ["bbbbbbbbbbb"]);
+
+        let deletes = es.bodies("POST", &delete).await;
+        assert_eq!(
+            deletes,
+            [json!({ "query": { "terms": { "video_id": ["bbbbbbbbbbb"] } } })]
+        );
+        let query = es.queries("POST", &delete).await.remove(0);
+        assert!(query.contains(&format!("scroll_size={}", run.throttle().scroll_size())));
+        assert!(query.contains(&format!(
+            "requests_per_second={}",
+            run.throttle().requests_per_second()
+        )));
+    }
+}
@@ -0,0 +1,218 @@
+//! Back-pressure for heavy maintenance on Elasticsearch: deleting videos, the videos of
+//! a channel or playlist, hiding a channel, rebuilding the sentence index, backfills,
+//! reanalyzing captions, the scheduled monitor backfill, caption update sweep and
+//! sentence compaction and pruning the search log. Only one of them runs at a time. They work in batches of
+//! MAINTENANCE_BATCH_SIZE documents with MAINTENANCE_BATCH_DELAY_MS between batches, so
+//! live searches keep their share of a small node.
+
+use crate::config::{MAINTENANCE_BATCH_DELAY_MS, MAINTENANCE_BATCH_SIZE};
+use crate::models::RunningMaintenance;
+use log::info;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+pub struct MaintenanceThrottle {
+    permit: Arc<Semaphore>,
+    current: Mutex<Option<RunningMaintenance>>,
+    pub batch_size: usize,
+    pub batch_delay: Duration,
+}
+
+impl MaintenanceThrottle {
+    pub fn new(batch_size: usize, batch_delay: Duration) -> Self {
+        MaintenanceThrottle {
+            permit: Arc::new(Semaphore::new(1)),
+            current: Mutex::new(None),
+            batch_size: batch_size.max(1),
+            batch_delay,
+        }
+    }
+
+    /// Batches of MAINTENANCE_BATCH_SIZE with MAINTENANCE_BATCH_DELAY_MS between them
+    pub fn from_config() -> Self {
+        MaintenanceThrottle::new(
+            *MAINTENANCE_BATCH_SIZE,
+            Duration::from_millis(*MAINTENANCE_BATCH_DELAY_MS),
+        )
+    }
+
+    /// The running maintenance task, None while the throttle is free
+    pub fn current(&self) -> Option<RunningMaintenance> {
+        self.current.lock().ok()?.clone()
+    }
+
+    /// Takes the throttle for the task `name` until the returned run is dropped. Fails
+    /// with the running task if another one holds it.
+    pub fn try_start(self: &Arc<Self>, name: &str) -> Result<MaintenanceRun, RunningMaintenance> {
+        // The permit is only taken and released while holding `current`, so a busy
+        // throttle always has a task to report
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        let Ok(permit) = self.permit.clone().try_acquire_owned() else {
+            return Err(current.clone().unwrap_or_else(|| RunningMaintenance {
+                name: "unknown".to_string(),
+                started_at: 0,
+                done: 0,
+                total: 0,
+            }));
+        };
+        info!("Maintenance started: {name}");
+        *current = Some(RunningMaintenance {
+            name: name.to_string(),
+            started_at: chrono::Utc::now().timestamp(),
+            done: 0,
+            total: 0,
+        });
+        Ok(MaintenanceRun {
+            throttle: self.clone(),
+            permit: Some(permit),
+        })
+    }
+
+    /// `requests_per_second` of a delete or update by query, so Elasticsearch pauses
+    /// about `batch_delay` between its batches of `batch_size` documents. -1 disables
+    /// Elasticsearch's throttling.
+    pub fn requests_per_second(&self) -> i64 {
+        let delay_ms = self.batch_delay.as_millis() as i64;
+        if delay_ms == 0 {
+            return -1;
+        }
+        (self.batch_size as i64 * 1000 / delay_ms).max(1)
+    }
+
+    /// `scroll_size` of a delete or update by query
+    pub fn scroll_size(&self) -> i64 {
+        self.batch_size as i64
+    }
+}
+
+/// Holds the maintenance throttle while a heavy operation runs, releases it on drop.
+/// Moved into background tasks that outlive the request that started them.
+pub struct MaintenanceRun {
+    throttle: Arc<MaintenanceThrottle>,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl MaintenanceRun {
+    pub fn throttle(&self) -> &MaintenanceThrottle {
+        &self.throttle
+    }
+
+    /// Documents or videos processed so far out of `total`
+    pub fn progress(&self, done: i64, total: i64) {
+        if let Ok(mut current) = self.throttle.current.lock() {
+            if let Some(task) = current.as_mut() {
+                task.done = done;
+                task.total = total;
+            }
+        }
+    }
+
+    /// Waits between two batches
+    pub async fn pause(&self) {
+        tokio::time::sleep(self.throttle.batch_delay).await;
+    }
+}
+
+impl Drop for MaintenanceRun {
+    fn drop(&mut self) {
+        let mut current = self
+            .throttle
+            .current
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(task) = current.take() {
+            info!("Maintenance finished: {}", task.name);
+        }
+        self.permit.take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttle() -> Arc<MaintenanceThrottle> {
+        Arc::new(MaintenanceThrottle::new(100, Duration::from_millis(100)))
+    }
+
+    #[test]
+    fn a_second_operation_is_rejected_while_one_runs() {
+        let throttle = throttle();
+        let run = throttle.try_start("delete video aaaaaaaaaaa").unwrap();
+
+        let running = throttle.try_start("rebuild sentences").err().unwrap();
+        assert_eq!(running.name, "delete video aaaaaaaaaaa");
+        assert_eq!(throttle.current().unwrap().name, "delete video aaaaaaaaaaa");
+
+        drop(run);
+        assert!(throttle.current().is_none());
+        assert!(throttle.try_start("rebuild sentences").is_ok());
+    }
+
+    #[rocket::async_test]
+    async fn concurrent_operations_get_one_permit() {
+        let throttle = throttle();
+        let tasks: Vec<_> = (0..8)
+            .map(|i| {
+                let throttle = throttle.clone();
+                tokio::spawn(async move {
+                    let run = throttle.try_start(&format!("operation {i}")).ok()?;
+                    // Holds the permit while the others try to take it
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    drop(run);
+                    Some(i)
+                })
+            })
+            .collect();
+
+        let mut started = 0;
+        for task in tasks {
+            if task.await.unwrap().is_some() {
+                started += 1;
+            }
+        }
+        assert_eq!(started, 1);
+        assert!(throttle.current().is_none());
+    }
+
+    #[rocket::async_test]
+    async fn runs_moved_into_background_tasks_hold_the_permit() {
+        let throttle = throttle();
+        let run = throttle.try_start("purge channel").unwrap();
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            let _ = released.await;
+            run.progress(10, 20);
+            drop(run);
+        });
+
+        assert!(throttle.try_start("hide channel").is_err());
+        release.send(()).unwrap();
+        task.await.unwrap();
+        assert!(throttle.try_start("hide channel").is_ok());
+    }
+
+    #[test]
+    fn progress_is_reported_while_running() {
+        let throttle = throttle();
+        let run = throttle.try_start("rebuild sentences").unwrap();
+        run.progress(3, 10);
+
+        let current = throttle.current().unwrap();
+        assert_eq!((current.done, current.total), (3, 10));
+    }
+
+    #[test]
+    fn batches_translate_to_requests_per_second() {
+        assert_eq!(throttle().requests_per_second(), 1000);
+        assert_eq!(
+            MaintenanceThrottle::new(100, Duration::ZERO).requests_per_second(),
+            -1
+        );
+        assert_eq!(
+            MaintenanceThrottle::new(0, Duration::from_secs(10)).scroll_size(),
+            1
+        );
+    }
+}
@@ -1,6 +1,33 @@
 pub mod admin_service;
+pub mod api_key_service;
+pub mod archive_generation;
+pub mod caption_update_service;
 pub mod crawler;
+pub mod cursor_search_service;
 pub mod elasticsearch_service;
+pub mod es_breaker;
+pub mod feedback_service;
+pub mod frequency_service;
+pub mod health_service;
+pub mod indexing_log_service;
+pub mod maintenance_service;
+pub mod maintenance_throttle;
+pub mod monitor_tag_service;
 pub(crate) mod monitoring_service;
+pub mod notification_service;
+pub mod optout_service;
+pub mod rate_limiter;
+pub mod raw_capture;
+pub mod relevance_service;
+pub mod report_service;
+pub mod rss_discovery;
 pub mod search_service;
+pub mod sentence_service;
+pub mod simulation;
+pub mod stemming;
+pub mod synonym_service;
+pub mod thumbnail_service;
+pub mod transcript_throttle;
+pub mod trending_service;
 pub mod video_service;
+pub mod youtube_api;
@@ -0,0 +1,146 @@
+//! Free-form tags grouping monitored channels and playlists. The tags are stored on the
+//! monitors, this index only remembers which tags the admin made public: those are shown
+//! on the channels of the public directory so visitors can browse by topic.
+
+use crate::config::INDEX_NAMES;
+use crate::models::MonitorTag;
+use crate::services::monitoring_service::{MONITORED_PlAYLISTS, MONITORED_CHANNELS};
+use anyhow::Result;
+use elasticsearch::params::Refresh;
+use elasticsearch::{Elasticsearch, IndexParts, SearchParts};
+use log::{error, info};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet};
+use tokio::sync::RwLock;
+
+lazy_static::lazy_static! {
+    static ref PUBLIC_TAGS: RwLock<HashSet<String>> = RwLock::new(HashSet::new());
+}
+
+pub async fn load_public_tags(es_client: &Elasticsearch) {
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.monitor_tags]))
+        .body(json!({ "query": { "term": { "public": true } }, "size": 10000 }))
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status_code().is_success() => {
+            let response_body: Value = response.json().await.unwrap_or_default();
+            let tags: HashSet<String> = response_body["hits"]["hits"]
+                .as_array()
+                .map(|hits| {
+                    hits.iter()
+                        .filter_map(|hit| hit["_source"]["tag"].as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default();
+            info!("Loaded {} public monitor tags", tags.len());
+            *PUBLIC_TAGS.write().await = tags;
+        }
+        Ok(response) => error!(
+            "Failed to load public monitor tags: {}",
+            response.status_code()
+        ),
+        Err(e) => error!("Failed to load public monitor tags: {e:?}"),
+    }
+}
+
+/// Trimmed, lowercased tags without empty ones and duplicates, in their original order
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+fn tag_entry<'a>(
+    tags: &'a mut BTreeMap<String, MonitorTag>,
+    tag: &str,
+    public_tags: &HashSet<String>,
+) -> &'a mut MonitorTag {
+    tags.entry(tag.to_string()).or_insert_with(|| MonitorTag {
+        tag: tag.to_string(),
+        public: public_tags.contains(tag),
+        channels: 0,
+        playlists: 0,
+    })
+}
+
+/// Every tag used on a monitor or marked public, by name
+pub async fn list_tags() -> Vec<MonitorTag> {
+    let public_tags = PUBLIC_TAGS.read().await.clone();
+    let mut tags: BTreeMap<String, MonitorTag> = BTreeMap::new();
+    for channel in MONITORED_CHANNELS.read().await.iter() {
+        for tag in &channel.tags {
+            tag_entry(&mut tags, tag, &public_tags).channels += 1;
+        }
+    }
+    for playlist in MONITORED_PlAYLISTS.read().await.iter() {
+        for tag in &playlist.tags {
+            tag_entry(&mut tags, tag, &public_tags).playlists += 1;
+        }
+    }
+    // Public tags no monitor uses anymore stay listed so they can be made private
+    for tag in &public_tags {
+        tag_entry(&mut tags, tag, &public_tags);
+    }
+    tags.into_values().collect()
+}
+
+/// Shows or hides a tag on the public channel directory
+pub async fn set_tag_public(es_client: &Elasticsearch, tag: &str, public: bool) -> Result<()> {
+    let tag = tag.trim().to_lowercase();
+    let response = es_client
+        .index(IndexParts::IndexId(INDEX_NAMES.monitor_tags, &tag))
+        .refresh(Refresh::True)
+        .body(json!({ "tag": tag, "public": public }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Failed to store the visibility of tag {tag}: {}",
+            response.status_code()
+        ));
+    }
+
+    let mut public_tags = PUBLIC_TAGS.write().await;
+    if public {
+        public_tags.insert(tag.clone());
+    } else {
+        public_tags.remove(&tag);
+    }
+    info!(
+        "Monitor tag \"{tag}\" is {} now",
+        if public { "public" } else { "private" }
+    );
+    Ok(())
+}
+
+/// Public tags of the monitored channels, channel id -> tags
+pub async fn public_channel_tags() -> BTreeMap<String, Vec<String>> {
+    let public_tags = PUBLIC_TAGS.read().await;
+    if public_tags.is_empty() {
+        return BTreeMap::new();
+    }
+    MONITORED_CHANNELS
+        .read()
+        .await
+        .iter()
+        .filter(|channel| !channel.hidden)
+        .map(|channel| {
+            let tags: Vec<String> = channel
+                .tags
+                .iter()
+                .filter(|tag| public_tags.contains(*tag))
+                .cloned()
+                .collect();
+            (channel.channel_id.clone(), tags)
+        })
+        .filter(|(_, tags)| !tags.is_empty())
+        .collect()
+}
@@ -1,23 +1,56 @@
-use crate::api::{MonitoredChannelStats, MonitoredPlaylistStats};
-use crate::config::{MONITOR_CHECK_SCHEDULE, YOUTUBE_API_KEY};
-use crate::models::{MonitoredChannel, MonitoredPlaylist};
+use crate::config::{
+    BACKFILL_MAX_QUEUE, BACKFILL_SCHEDULE, BACKFILL_THRESHOLD, BACKFILL_VIDEOS_PER_CYCLE,
+    INDEX_NAMES, MONITOR_CHECK_SCHEDULE, SIMULATION_MODE, YOUTUBE_API_KEY,
+};
+use crate::models::{
+    BulkMonitorResult, CheckSource, DiscoveryMode, EnqueueOrder, FieldError, MonitorAddResponse,
+    MonitorUpdate, MonitoredChannel, MonitoredPlaylist, TagCheckResponse,
+};
+use crate::models::{MonitoredChannelStats, MonitoredPlaylistStats};
+use crate::services::archive_generation::ArchiveGeneration;
 use crate::services::crawler::VideoQueue;
-use elasticsearch::{DeleteParts, Elasticsearch, SearchParts};
+use crate::services::health_service::HEALTH;
+use crate::services::maintenance_throttle::{MaintenanceRun, MaintenanceThrottle};
+use crate::services::monitor_tag_service;
+use crate::services::notification_service::ACTIVITY;
+use crate::services::optout_service;
+use crate::services::rss_discovery;
+use crate::services::simulation;
+use crate::services::video_service;
+use crate::services::youtube_api;
+use crate::utils::{self, ChannelRef};
+use elasticsearch::params::Conflicts;
+use elasticsearch::{DeleteParts, Elasticsearch, MgetParts, SearchParts, UpdateByQueryParts};
 use log::{error, info};
-use reqwest::Client;
 use serde_json::{json, Value};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::RwLock;
 use tokio_cron_scheduler::{Job, JobScheduler};
 
 lazy_static::lazy_static! {
     pub static ref MONITORED_CHANNELS: Arc<RwLock<Vec<MonitoredChannel>>> = Arc::new(RwLock::new(Vec::new()));
     pub static ref MONITORED_PlAYLISTS: Arc<RwLock<Vec<MonitoredPlaylist>>> = Arc::new(RwLock::new(Vec::new()));
+    /// channel id -> uploads playlist id
+    static ref UPLOADS_PLAYLISTS: RwLock<HashMap<String, String>> = RwLock::new(HashMap::new());
 }
 
+/// Rotates the backfilling monitor a backfill cycle starts with
+static BACKFILL_CURSOR: AtomicUsize = AtomicUsize::new(0);
+
+/// While backfilling, checks still enqueue uploads this recent right away
+const BACKFILL_RECENT_UPLOAD_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+/// Indexing rate of the last days, backfill completion is estimated from it
+const BACKFILL_RATE_DAYS: i64 = 7;
+
 pub async fn setup_monitoring(
     es_client: Arc<Elasticsearch>,
     video_queue: Arc<VideoQueue>,
+    maintenance: Arc<MaintenanceThrottle>,
 ) -> Result<(), anyhow::Error> {
     info!("Setting up monitoring scheduler...");
 
@@ -29,17 +62,40 @@ pub async fn setup_monitoring(
     let es_client_clone = es_client.clone();
     let queue_clone = video_queue.clone();
 
-    let monitor_job = Job::new_async(MONITOR_CHECK_SCHEDULE.as_str(), move |_uuid, _l| {
+    let monitor_job = Job::new_async(MONITOR_CHECK_SCHEDULE.cron(), move |_uuid, _l| {
         let es_client = es_client_clone.clone();
         let queue = queue_clone.clone();
         Box::pin(async move {
-            check_monitored_channels(&es_client, &queue).await;
-            check_monitored_playlists(&es_client, &queue).await;
+            check_monitored_channels(&es_client, &queue, None).await;
+            check_monitored_playlists(&es_client, &queue, None).await;
+            HEALTH.record_monitor_check();
         })
     })?;
 
     sched.add(monitor_job).await?;
 
+    let es_client_clone = es_client.clone();
+    let queue_clone = video_queue.clone();
+    let backfill_job = Job::new_async(BACKFILL_SCHEDULE.cron(), move |_uuid, _l| {
+        let es_client = es_client_clone.clone();
+        let queue = queue_clone.clone();
+        let maintenance = maintenance.clone();
+        Box::pin(async move {
+            // A cycle is skipped while other maintenance holds the throttle
+            let run = match maintenance.try_start("backfill monitors") {
+                Ok(run) => run,
+                Err(running) => {
+                    info!("Skipping this backfill cycle during {}", running.name);
+                    return;
+                }
+            };
+            run_backfill(&es_client, &queue, &run).await;
+        })
+    })?;
+
+    sched.add(backfill_job).await?;
+    info!("Backfill scheduled: {}", BACKFILL_SCHEDULE.cron());
+
     sched.start().await?;
     info!("Monitoring scheduler started.");
     Ok(())
@@ -48,77 +104,223 @@ pub async fn setup_monitoring(
 pub async fn get_monitored_channels_list(es_client: &Elasticsearch) -> Vec<MonitoredChannelStats> {
     let channels = MONITORED_CHANNELS.read().await.clone();
 
-    let mut result = Vec::new();
-    for channel in channels {
-        let response = es_client
-            .count(elasticsearch::CountParts::Index(&["youtube_videos"]))
-            .body(json!({
-                "query": {
-                    "match": {
-                        "channel_id": channel.channel_id
-                    }
-                }
-            }))
-            .send()
-            .await;
+    let started = Instant::now();
+    let ids: Vec<String> = channels.iter().map(|c| c.channel_id.clone()).collect();
+    let counts = aggregate_videos_by_term(es_client, "channel_id.keyword", &ids).await;
+    info!(
+        "Counted indexed videos of {} channels in {:?}",
+        ids.len(),
+        started.elapsed()
+    );
 
-        let video_count = match response {
-            Ok(r) => {
-                let count: Value = r.json().await.unwrap_or(json!({"count": 0}));
-                count["count"].as_i64().unwrap_or(0) as i32
-            }
-            Err(_) => 0,
-        };
+    channels
+        .into_iter()
+        .map(|channel| channel_stats(channel, &counts))
+        .collect()
+}
 
-        result.push(MonitoredChannelStats {
-            channel_id: channel.channel_id,
-            channel_name: channel.channel_name,
-            active: channel.active,
-            created_at: channel.created_at,
-            videos_indexed: video_count,
-            videos_uploaded: channel.videos_uploaded,
-        });
+/// Stats of a single monitored channel, None if it isn't monitored
+pub async fn get_monitored_channel_stats(
+    es_client: &Elasticsearch,
+    channel_id: &str,
+) -> Option<MonitoredChannelStats> {
+    let channel = MONITORED_CHANNELS
+        .read()
+        .await
+        .iter()
+        .find(|c| c.channel_id == channel_id)
+        .cloned()?;
+    let counts =
+        aggregate_videos_by_term(es_client, "channel_id.keyword", &[channel_id.to_string()]).await;
+    Some(channel_stats(channel, &counts))
+}
+
+fn channel_stats(
+    channel: MonitoredChannel,
+    counts: &HashMap<String, VideoTotals>,
+) -> MonitoredChannelStats {
+    let totals = counts.get(&channel.channel_id).copied().unwrap_or_default();
+    // RSS channels only know their latest uploads, they are never backfilled
+    let backfilling = channel.discovery_mode == DiscoveryMode::Api
+        && is_backfilling(channel.videos_uploaded, totals.videos);
+    MonitoredChannelStats {
+        videos_indexed: totals.videos as i32,
+        indexed_hours: totals.duration_seconds / 3600.0,
+        backfilling,
+        backfill_eta_ms: backfilling
+            .then(|| backfill_eta_ms(channel.videos_uploaded - totals.videos, &totals))
+            .flatten(),
+        channel_id: channel.channel_id,
+        channel_name: channel.channel_name,
+        active: channel.active,
+        created_at: channel.created_at,
+        created_at_ms: channel.created_at_ms,
+        videos_uploaded: channel.videos_uploaded,
+        hidden: channel.hidden,
+        enqueue_order: channel.enqueue_order,
+        discovery_mode: channel.discovery_mode,
+        max_duration_seconds: channel.max_duration_seconds,
+        tags: channel.tags,
     }
-    result
 }
 
 pub async fn get_monitored_playlist_list(es_client: &Elasticsearch) -> Vec<MonitoredPlaylistStats> {
     let playlists = MONITORED_PlAYLISTS.read().await.clone();
 
-    let mut result = Vec::with_capacity(playlists.len());
-    for playlist in playlists {
-        let pid = playlist.playlist_id.clone();
+    let started = Instant::now();
+    let ids: Vec<String> = playlists.iter().map(|p| p.playlist_id.clone()).collect();
+    let counts = aggregate_videos_by_term(es_client, "playlists.keyword", &ids).await;
+    info!(
+        "Counted indexed videos of {} playlists in {:?}",
+        ids.len(),
+        started.elapsed()
+    );
 
-        let response = es_client
-            .count(elasticsearch::CountParts::Index(&["youtube_videos"]))
-            .body(json!({
-                "query": {
-                    "term": {
-                        "playlists.keyword": { "value": pid }
+    playlists
+        .into_iter()
+        .map(|playlist| playlist_stats(playlist, &counts))
+        .collect()
+}
+
+/// Stats of a single monitored playlist, None if it isn't monitored
+pub async fn get_monitored_playlist_stats(
+    es_client: &Elasticsearch,
+    playlist_id: &str,
+) -> Option<MonitoredPlaylistStats> {
+    let playlist = MONITORED_PlAYLISTS
+        .read()
+        .await
+        .iter()
+        .find(|p| p.playlist_id == playlist_id)
+        .cloned()?;
+    let counts =
+        aggregate_videos_by_term(es_client, "playlists.keyword", &[playlist_id.to_string()]).await;
+    Some(playlist_stats(playlist, &counts))
+}
+
+fn playlist_stats(
+    playlist: MonitoredPlaylist,
+    counts: &HashMap<String, VideoTotals>,
+) -> MonitoredPlaylistStats {
+    let totals = counts
+        .get(&playlist.playlist_id)
+        .copied()
+        .unwrap_or_default();
+    let backfilling = is_backfilling(playlist.videos_added, totals.videos);
+    MonitoredPlaylistStats {
+        videos_indexed: totals.videos as i32,
+        backfilling,
+        backfill_eta_ms: backfilling
+            .then(|| backfill_eta_ms(playlist.videos_added - totals.videos, &totals))
+            .flatten(),
+        playlist_id: playlist.playlist_id,
+        playlist_name: playlist.playlist_name,
+        active: playlist.active,
+        created_at: playlist.created_at,
+        created_at_ms: playlist.created_at_ms,
+        videos_added: playlist.videos_added,
+        enqueue_order: playlist.enqueue_order,
+        max_duration_seconds: playlist.max_duration_seconds,
+        tags: playlist.tags,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct VideoTotals {
+    videos: i64,
+    duration_seconds: f64, // videos without a duration count as zero
+    recent_videos: i64,    // crawled within the last `BACKFILL_RATE_DAYS`
+}
+
+/// Whether a monitor listing `listed` videos misses too many to enqueue them at once
+fn is_backfilling(listed: i64, indexed: i64) -> bool {
+    listed - indexed > *BACKFILL_THRESHOLD
+}
+
+/// When the `missing` videos are indexed at the rate of the last `BACKFILL_RATE_DAYS`,
+/// unix millis. None if nothing was indexed recently.
+fn backfill_eta_ms(missing: i64, totals: &VideoTotals) -> Option<i64> {
+    if totals.recent_videos == 0 {
+        return None;
+    }
+    let rate_window_ms = BACKFILL_RATE_DAYS * 24 * 60 * 60 * 1000;
+    let ms_per_video = rate_window_ms as f64 / totals.recent_videos as f64;
+    Some(chrono::Utc::now().timestamp_millis() + (missing as f64 * ms_per_video) as i64)
+}
+
+/// Counts the indexed videos and recently crawled ones and sums their durations per value
+/// of `field` with a single terms aggregation. Values without any indexed video are
+/// missing from the map.
+async fn aggregate_videos_by_term(
+    es_client: &Elasticsearch,
+    field: &str,
+    values: &[String],
+) -> HashMap<String, VideoTotals> {
+    if values.is_empty() {
+        return HashMap::new();
+    }
+
+    let recent_cutoff = chrono::Utc::now().timestamp() - BACKFILL_RATE_DAYS * 24 * 60 * 60;
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.videos]))
+        .body(json!({
+            "size": 0,
+            "query": {
+                "terms": { field: values }
+            },
+            "aggs": {
+                "per_value": {
+                    "terms": {
+                        "field": field,
+                        "size": values.len(),
+                        "include": values
+                    },
+                    "aggs": {
+                        "duration": { "sum": { "field": "duration" } },
+                        "recent": {
+                            "filter": { "range": { "crawl_date": { "gte": recent_cutoff } } }
+                        }
                     }
                 }
-            }))
-            .send()
-            .await;
-
-        let video_count = match response {
-            Ok(r) => {
-                let count: Value = r.json().await.unwrap_or(json!({"count": 0}));
-                count["count"].as_i64().unwrap_or(0) as i32
             }
-            Err(_) => 0,
-        };
+        }))
+        .send()
+        .await;
 
-        result.push(MonitoredPlaylistStats {
-            playlist_id: playlist.playlist_id,
-            playlist_name: playlist.playlist_name,
-            active: playlist.active,
-            created_at: playlist.created_at,
-            videos_indexed: video_count,
-            videos_added: playlist.videos_added,
-        });
+    match response {
+        Ok(r) => match r.json::<Value>().await {
+            Ok(body) => parse_bucket_totals(&body["aggregations"]["per_value"]),
+            Err(e) => {
+                error!("Failed to parse video count aggregation on {field}: {e:?}");
+                HashMap::new()
+            }
+        },
+        Err(e) => {
+            error!("Failed to aggregate video counts on {field}: {e:?}");
+            HashMap::new()
+        }
     }
-    result
+}
+
+fn parse_bucket_totals(aggregation: &Value) -> HashMap<String, VideoTotals> {
+    aggregation["buckets"]
+        .as_array()
+        .map(|buckets| {
+            buckets
+                .iter()
+                .filter_map(|bucket| {
+                    Some((
+                        bucket["key"].as_str()?.to_string(),
+                        VideoTotals {
+                            videos: bucket["doc_count"].as_i64().unwrap_or(0),
+                            duration_seconds: bucket["duration"]["value"].as_f64().unwrap_or(0.0),
+                            recent_videos: bucket["recent"]["doc_count"].as_i64().unwrap_or(0),
+                        },
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
 pub async fn remove_monitored_channel(
@@ -128,7 +330,10 @@ pub async fn remove_monitored_channel(
     info!("Removing monitored channel: {}", channel_id);
 
     es_client
-        .delete(DeleteParts::IndexId("monitored_channels", channel_id))
+        .delete(DeleteParts::IndexId(
+            INDEX_NAMES.monitored_channels,
+            channel_id,
+        ))
         .send()
         .await?;
 
@@ -146,7 +351,10 @@ pub async fn remove_monitored_playlist(
     info!("Removing monitored playlist: {}", playlist_id);
 
     es_client
-        .delete(DeleteParts::IndexId("monitored_playlists", playlist_id))
+        .delete(DeleteParts::IndexId(
+            INDEX_NAMES.monitored_playlists,
+            playlist_id,
+        ))
         .send()
         .await?;
 
@@ -157,52 +365,61 @@ pub async fn remove_monitored_playlist(
     Ok(())
 }
 
+async fn lookup_channel_id(url: &str) -> Result<String, anyhow::Error> {
+    let response = youtube_api::get_json(url).await?;
+    youtube_api::check_api_error(&response)?;
+    Ok(response["items"][0]["id"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Channel not found"))?
+        .to_string())
+}
+
 async fn fetch_monitored_channel(input: &str) -> Result<MonitoredChannel, anyhow::Error> {
-    let client = Client::new();
     let api_key = &*YOUTUBE_API_KEY;
 
-    // Extract channel ID from different URL formats
-    let channel_id = if input.contains("/channel/") {
+    let channel_ref = ChannelRef::parse(input)
+        .ok_or_else(|| anyhow::anyhow!("Invalid channel URL format: {}", input.trim()))?;
+    if *SIMULATION_MODE {
+        let channel_id = match channel_ref {
+            ChannelRef::Id(id) => id,
+            ChannelRef::Handle(name) | ChannelRef::Username(name) => format!("UC{name}"),
+            ChannelRef::Video(video_id) => simulation::video_metadata(&video_id).channel_id,
+        };
+        return Ok(simulation::monitored_channel(&channel_id));
+    }
+
+    let channel_id = match channel_ref {
         // Format: https://www.youtube.com/channel/UCTeLqJq1mXUX5WWoNXLmOIA
-        input
-            .split("/channel/")
-            .nth(1)
-            .ok_or_else(|| anyhow::anyhow!("Invalid channel URL"))?
-            .to_string()
-    } else if input.contains("/@") {
+        ChannelRef::Id(id) => id,
         // Format: https://youtube.com/@RobertsSpaceInd
-        let handle = input
-            .split("/@")
-            .nth(1)
-            .ok_or_else(|| anyhow::anyhow!("Invalid handle URL"))?;
-        // Get channel ID from handle via API
-        let url = format!(
-            "https://www.googleapis.com/youtube/v3/channels?part=id&forHandle={}&key={}",
-            handle, api_key
-        );
-        let response = client.get(&url).send().await?.json::<Value>().await?;
-        response["items"][0]["id"]
-            .as_str()
-            .ok_or_else(|| anyhow::anyhow!("Invalid API response"))?
-            .to_string()
-    } else if input.contains("/c/") {
+        ChannelRef::Handle(handle) => {
+            lookup_channel_id(&format!(
+                "https://www.googleapis.com/youtube/v3/channels?part=id&forHandle={}&key={}",
+                handle, api_key
+            ))
+            .await?
+        }
         // Format: https://www.youtube.com/c/RobertsSpaceInd
-        let custom = input
-            .split("/c/")
-            .nth(1)
-            .ok_or_else(|| anyhow::anyhow!("Invalid custom URL"))?;
-        // Get channel ID from custom URL via API
-        let url = format!(
-            "https://www.googleapis.com/youtube/v3/channels?part=id&forUsername={}&key={}",
-            custom, api_key
-        );
-        let response = client.get(&url).send().await?.json::<Value>().await?;
-        response["items"][0]["id"]
-            .as_str()
-            .ok_or_else(|| anyhow::anyhow!("Invalid API response"))?
-            .to_string()
-    } else {
-        return Err(anyhow::anyhow!("Invalid channel URL format"));
+        ChannelRef::Username(name) => {
+            lookup_channel_id(&format!(
+                "https://www.googleapis.com/youtube/v3/channels?part=id&forUsername={}&key={}",
+                name, api_key
+            ))
+            .await?
+        }
+        // Format: https://youtu.be/<video id>, resolved to the uploading channel
+        ChannelRef::Video(video_id) => {
+            let url = format!(
+                "https://www.googleapis.com/youtube/v3/videos?part=snippet&id={}&key={}",
+                video_id, api_key
+            );
+            let response = youtube_api::get_json(&url).await?;
+            youtube_api::check_api_error(&response)?;
+            response["items"][0]["snippet"]["channelId"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Video not found"))?
+                .to_string()
+        }
     };
 
     let url = format!(
@@ -210,9 +427,11 @@ async fn fetch_monitored_channel(input: &str) -> Result<MonitoredChannel, anyhow
         channel_id, api_key
     );
 
-    let response = client.get(&url).send().await?.json::<Value>().await?;
+    let response = youtube_api::get_json(&url).await?;
+    youtube_api::check_api_error(&response)?;
     let channel = &response["items"][0];
 
+    let now = chrono::Utc::now();
     Ok(MonitoredChannel {
         channel_id,
         channel_name: channel["snippet"]["title"]
@@ -220,39 +439,39 @@ async fn fetch_monitored_channel(input: &str) -> Result<MonitoredChannel, anyhow
             .ok_or_else(|| anyhow::anyhow!("Invalid channel title"))?
             .to_string(),
         active: true,
-        created_at: chrono::Utc::now().to_rfc3339(),
+        enqueue_order: EnqueueOrder::default(),
+        discovery_mode: DiscoveryMode::default(),
+        created_at: now.to_rfc3339(),
+        created_at_ms: now.timestamp_millis(),
         videos_uploaded: channel["statistics"]["videoCount"]
             .as_str()
             .unwrap_or("0")
             .parse::<i64>()
             .unwrap_or(0),
+        hidden: false,
+        max_duration_seconds: None,
+        tags: Vec::new(),
     })
 }
 
 async fn fetch_monitored_playlist(input: &str) -> Result<MonitoredPlaylist, anyhow::Error> {
-    let client = Client::new();
     let api_key = &*YOUTUBE_API_KEY;
 
-    let playlist_id = if input.contains("/playlist?list=") {
-        // Format: https://www.youtube.com/playlist?list=PLVct2QDhDrB2HMkwQar8kZDPZP7ZdyIAC
-        input
-            .split("list=")
-            .nth(1)
-            .ok_or_else(|| anyhow::anyhow!("Invalid playlist URL"))?
-            .split('&')
-            .next()
-            .ok_or_else(|| anyhow::anyhow!("Invalid playlist URL"))?
-            .to_string()
-    } else {
-        return Err(anyhow::anyhow!("Invalid playlist URL format"));
-    };
+    // Formats: https://www.youtube.com/playlist?list=PLVct2QDhDrB2HMkwQar8kZDPZP7ZdyIAC,
+    // watch/share links with a `list` parameter or the bare ID
+    let playlist_id = utils::extract_playlist_id(input)
+        .ok_or_else(|| anyhow::anyhow!("Invalid playlist URL format: {}", input.trim()))?;
+    if *SIMULATION_MODE {
+        return Ok(simulation::monitored_playlist(&playlist_id));
+    }
 
     let url = format!(
         "https://www.googleapis.com/youtube/v3/playlists?part=snippet,contentDetails&id={}&key={}",
         playlist_id, api_key
     );
 
-    let response = client.get(&url).send().await?.json::<Value>().await?;
+    let response = youtube_api::get_json(&url).await?;
+    youtube_api::check_api_error(&response)?;
     let playlist = &response["items"][0];
     let playlist_name = playlist["snippet"]["title"]
         .as_str()
@@ -263,36 +482,55 @@ async fn fetch_monitored_playlist(input: &str) -> Result<MonitoredPlaylist, anyh
         .as_i64()
         .ok_or_else(|| anyhow::anyhow!("Invalid video count"))?;
 
+    let now = chrono::Utc::now();
     Ok(MonitoredPlaylist {
         playlist_id,
         playlist_name,
         active: true,
-        created_at: chrono::Utc::now().to_rfc3339(),
+        created_at: now.to_rfc3339(),
+        created_at_ms: now.timestamp_millis(),
         videos_added: video_count,
+        enqueue_order: EnqueueOrder::default(),
+        max_duration_seconds: None,
+        tags: Vec::new(),
     })
 }
 
 pub async fn add_monitored_channel(
     channel_input: &str,
     es_client: &Elasticsearch,
-) -> Result<(), anyhow::Error> {
+) -> Result<MonitorAddResponse, anyhow::Error> {
     info!("Adding new monitored channel: {}", channel_input);
 
-    let new_channel;
-
-    match fetch_monitored_channel(channel_input).await {
-        Ok(channel) => {
-            new_channel = channel;
-        }
+    let new_channel = match fetch_monitored_channel(channel_input).await {
+        Ok(channel) => channel,
         Err(e) => {
             error!("Failed to fetch monitored channel from youtube: {}", e);
             return Err(e);
         }
+    };
+
+    if MONITORED_CHANNELS
+        .read()
+        .await
+        .iter()
+        .any(|c| c.channel_id == new_channel.channel_id)
+    {
+        return Err(anyhow::anyhow!(
+            "Channel {} is already monitored",
+            new_channel.channel_name
+        ));
+    }
+    if optout_service::is_opted_out(&new_channel.channel_id).await {
+        return Err(anyhow::anyhow!(
+            "Channel {} opted out of the archive",
+            new_channel.channel_name
+        ));
     }
 
     es_client
         .index(elasticsearch::IndexParts::IndexId(
-            "monitored_channels",
+            INDEX_NAMES.monitored_channels,
             &new_channel.channel_id,
         ))
         .body(json!(new_channel))
@@ -304,32 +542,54 @@ pub async fn add_monitored_channel(
         new_channel.channel_name, new_channel.channel_id
     );
 
+    // Videos of the channel may already be indexed from a playlist or manual enqueue
+    let counts = aggregate_videos_by_term(
+        es_client,
+        "channel_id.keyword",
+        std::slice::from_ref(&new_channel.channel_id),
+    )
+    .await;
+    let response = MonitorAddResponse {
+        id: new_channel.channel_id.clone(),
+        name: new_channel.channel_name.clone(),
+        canonical_url: utils::canonical_channel_url(&new_channel.channel_id),
+        channel: Some(channel_stats(new_channel.clone(), &counts)),
+        playlist: None,
+    };
     let mut channels = MONITORED_CHANNELS.write().await;
     channels.push(new_channel);
-    Ok(())
+    Ok(response)
 }
 
 pub async fn add_monitored_playlist(
     playlist_input: &str,
     es_client: &Elasticsearch,
-) -> Result<(), anyhow::Error> {
+) -> Result<MonitorAddResponse, anyhow::Error> {
     info!("Adding new monitored channel: {}", playlist_input);
 
-    let new_playlist;
-
-    match fetch_monitored_playlist(playlist_input).await {
-        Ok(playlist) => {
-            new_playlist = playlist;
-        }
+    let new_playlist = match fetch_monitored_playlist(playlist_input).await {
+        Ok(playlist) => playlist,
         Err(e) => {
             error!("Failed to fetch monitored playlist from youtube: {}", e);
             return Err(e);
         }
+    };
+
+    if MONITORED_PlAYLISTS
+        .read()
+        .await
+        .iter()
+        .any(|p| p.playlist_id == new_playlist.playlist_id)
+    {
+        return Err(anyhow::anyhow!(
+            "Playlist {} is already monitored",
+            new_playlist.playlist_name
+        ));
     }
 
     es_client
         .index(elasticsearch::IndexParts::IndexId(
-            "monitored_playlists",
+            INDEX_NAMES.monitored_playlists,
             &new_playlist.playlist_id,
         ))
         .body(json!(new_playlist))
@@ -341,16 +601,82 @@ pub async fn add_monitored_playlist(
         new_playlist.playlist_name, new_playlist.playlist_id
     );
 
+    let counts = aggregate_videos_by_term(
+        es_client,
+        "playlists.keyword",
+        std::slice::from_ref(&new_playlist.playlist_id),
+    )
+    .await;
+    let response = MonitorAddResponse {
+        id: new_playlist.playlist_id.clone(),
+        name: new_playlist.playlist_name.clone(),
+        canonical_url: utils::canonical_playlist_url(&new_playlist.playlist_id),
+        channel: None,
+        playlist: Some(playlist_stats(new_playlist.clone(), &counts)),
+    };
     let mut playlists = MONITORED_PlAYLISTS.write().await;
     playlists.push(new_playlist);
-    Ok(())
+    Ok(response)
+}
+
+/// Adds the monitors one after another. Successes stay added if later inputs fail;
+/// once the YouTube API quota is exhausted the remaining inputs are not attempted.
+pub async fn add_monitored_bulk(
+    inputs: &[String],
+    is_playlist: bool,
+    es_client: &Elasticsearch,
+) -> Vec<BulkMonitorResult> {
+    let mut results = Vec::with_capacity(inputs.len());
+    let mut quota_exhausted = false;
+
+    for input in inputs.iter().map(|i| i.trim()).filter(|i| !i.is_empty()) {
+        if quota_exhausted {
+            results.push(BulkMonitorResult {
+                input: input.to_string(),
+                added: None,
+                error: Some("Skipped, YouTube API quota exhausted".to_string()),
+            });
+            continue;
+        }
+
+        let outcome = if is_playlist {
+            add_monitored_playlist(input, es_client).await
+        } else {
+            add_monitored_channel(input, es_client).await
+        };
+        results.push(match outcome {
+            Ok(added) => BulkMonitorResult {
+                input: input.to_string(),
+                added: Some(added),
+                error: None,
+            },
+            Err(e) => {
+                quota_exhausted = e
+                    .downcast_ref::<youtube_api::ApiError>()
+                    .is_some_and(|e| e.is_quota_exceeded());
+                BulkMonitorResult {
+                    input: input.to_string(),
+                    added: None,
+                    error: Some(e.to_string()),
+                }
+            }
+        });
+    }
+
+    info!(
+        "Bulk add of {} {}: {} added",
+        results.len(),
+        if is_playlist { "playlists" } else { "channels" },
+        results.iter().filter(|r| r.added.is_some()).count()
+    );
+    results
 }
 
 async fn load_monitored_channels(es_client: &Elasticsearch) {
     info!("Loading monitored channels from Elasticsearch...");
 
     let search_response = es_client
-        .search(SearchParts::Index(&["monitored_channels"]))
+        .search(SearchParts::Index(&[INDEX_NAMES.monitored_channels]))
         .body(json!({
             "query": {
                 "match_all": {}
@@ -370,9 +696,13 @@ async fn load_monitored_channels(es_client: &Elasticsearch) {
 
                 for hit in hits {
                     if let Some(source) = hit["_source"].as_object() {
-                        if let Ok(channel) =
+                        if let Ok(mut channel) =
                             serde_json::from_value::<MonitoredChannel>(source.clone().into())
                         {
+                            if channel.created_at_ms == 0 {
+                                channel.created_at_ms =
+                                    utils::parse_iso8601_to_millis(&channel.created_at);
+                            }
                             channels.push(channel);
                         }
                     }
@@ -391,7 +721,7 @@ async fn load_monitored_playlists(es_client: &Elasticsearch) {
     info!("Loading monitored channels from Elasticsearch...");
 
     let search_response = es_client
-        .search(SearchParts::Index(&["monitored_playlists"]))
+        .search(SearchParts::Index(&[INDEX_NAMES.monitored_playlists]))
         .body(json!({
             "query": {
                 "match_all": {}
@@ -411,10 +741,14 @@ async fn load_monitored_playlists(es_client: &Elasticsearch) {
 
                 for hit in hits {
                     if let Some(source) = hit["_source"].as_object() {
-                        if let Ok(channel) =
+                        if let Ok(mut playlist) =
                             serde_json::from_value::<MonitoredPlaylist>(source.clone().into())
                         {
-                            playlists.push(channel);
+                            if playlist.created_at_ms == 0 {
+                                playlist.created_at_ms =
+                                    utils::parse_iso8601_to_millis(&playlist.created_at);
+                            }
+                            playlists.push(playlist);
                         }
                     }
                 }
@@ -428,32 +762,70 @@ async fn load_monitored_playlists(es_client: &Elasticsearch) {
     }
 }
 
-async fn check_monitored_channels(es_client: &Elasticsearch, video_queue: &VideoQueue) {
+/// Whether a monitor with `tags` is included in a check limited to `tag`
+fn has_tag(tags: &[String], tag: Option<&str>) -> bool {
+    tag.is_none_or(|tag| tags.iter().any(|t| t == tag))
+}
+
+/// Checks every active monitored channel, or only those tagged with `tag`
+async fn check_monitored_channels(
+    es_client: &Elasticsearch,
+    video_queue: &VideoQueue,
+    tag: Option<&str>,
+) {
     info!("Checking monitored channels for new videos...");
 
     // Snapshot MONITORED_CHANNELS and drop the lock immediately
-    let channels: Vec<(String, bool, String)> = {
+    let channels: Vec<(String, bool, String, DiscoveryMode)> = {
         let guard = MONITORED_CHANNELS.read().await;
         guard
             .iter()
-            .map(|c| (c.channel_id.clone(), c.active, c.channel_name.clone()))
+            .filter(|c| has_tag(&c.tags, tag))
+            .map(|c| {
+                (
+                    c.channel_id.clone(),
+                    c.active,
+                    c.channel_name.clone(),
+                    c.discovery_mode,
+                )
+            })
             .collect()
     };
 
-    for (channel_id, active, channel_name) in channels {
+    for (channel_id, active, channel_name, discovery_mode) in channels {
         info!(
-            "Checking channel: {} ({}) - active: {}",
-            channel_name, channel_id, active
+            "Checking channel: {} ({}) - active: {}, discovery: {:?}",
+            channel_name, channel_id, active, discovery_mode
         );
 
-        if active {
-            check_channel_for_new_videos(&channel_id, &es_client, &video_queue).await;
+        if !active {
+            continue;
+        }
+        match discovery_mode {
+            DiscoveryMode::Api => {
+                check_channel_for_new_videos(&channel_id, es_client, video_queue).await
+            }
+            DiscoveryMode::Rss => {
+                if let Err(e) =
+                    check_channel_feed_for_new_videos(&channel_id, es_client, video_queue).await
+                {
+                    error!("Failed to check feed of channel {}: {}", channel_id, e);
+                    ACTIVITY.record_monitor_error(format!(
+                        "Channel {} ({}): RSS feed: {}",
+                        channel_name, channel_id, e
+                    ));
+                }
+            }
         }
     }
     info!("Finished checking monitored channels!");
 }
 
-async fn check_monitored_playlists(es_client: &Elasticsearch, video_queue: &VideoQueue) {
+async fn check_monitored_playlists(
+    es_client: &Elasticsearch,
+    video_queue: &VideoQueue,
+    tag: Option<&str>,
+) {
     info!("Checking monitored playlists for new videos...");
 
     // Snapshot MONITORED_PlAYLISTS and drop the lock immediately
@@ -461,6 +833,7 @@ async fn check_monitored_playlists(es_client: &Elasticsearch, video_queue: &Vide
         let guard = MONITORED_PlAYLISTS.read().await;
         guard
             .iter()
+            .filter(|p| has_tag(&p.tags, tag))
             .map(|p| (p.playlist_id.clone(), p.active, p.playlist_name.clone()))
             .collect()
     };
@@ -475,59 +848,412 @@ async fn check_monitored_playlists(es_client: &Elasticsearch, video_queue: &Vide
             continue;
         }
 
-        match check_playlist_for_new_videos(&playlist_id, es_client, video_queue, None).await {
-            Ok(video_count) => {
-                if let Err(e) = es_client
-                    .update(elasticsearch::UpdateParts::IndexId(
-                        "monitored_playlists",
-                        &playlist_id,
-                    ))
-                    .body(json!({ "doc": { "videos_added": video_count } }))
-                    .send()
-                    .await
-                {
-                    error!("Failed to update playlist video count: {}", e);
-                }
-            }
-            Err(e) => {
-                error!(
-                    "Error checking playlist {} for new videos: {}",
-                    playlist_id, e
-                );
-            }
+        let source = CheckSource::Playlist(playlist_id.clone());
+        if let Err(e) = check_source_for_new_videos(&source, es_client, video_queue).await {
+            error!(
+                "Error checking playlist {} for new videos: {}",
+                playlist_id, e
+            );
+            ACTIVITY.record_monitor_error(format!(
+                "Playlist {} ({}): {}",
+                playlist_name, playlist_id, e
+            ));
         }
     }
 
     info!("Finished checking monitored playlists!");
 }
 
+/// Checks the active monitors tagged with `tag` in the background. Returns how many
+/// channels and playlists are checked.
+pub async fn start_tag_check(
+    tag: &str,
+    es_client: &Elasticsearch,
+    video_queue: &Arc<VideoQueue>,
+) -> TagCheckResponse {
+    let channels = MONITORED_CHANNELS
+        .read()
+        .await
+        .iter()
+        .filter(|c| c.active && has_tag(&c.tags, Some(tag)))
+        .count();
+    let playlists = MONITORED_PlAYLISTS
+        .read()
+        .await
+        .iter()
+        .filter(|p| p.active && has_tag(&p.tags, Some(tag)))
+        .count();
+
+    let es_client = es_client.clone();
+    let video_queue = video_queue.clone();
+    let check_tag = tag.to_string();
+    tokio::spawn(async move {
+        info!("Checking the monitors tagged \"{}\"", check_tag);
+        check_monitored_channels(&es_client, &video_queue, Some(&check_tag)).await;
+        check_monitored_playlists(&es_client, &video_queue, Some(&check_tag)).await;
+    });
+
+    TagCheckResponse {
+        tag: tag.to_string(),
+        channels,
+        playlists,
+    }
+}
+
 pub async fn check_channel_for_new_videos(
     channel_id: &str,
     es_client: &Elasticsearch,
     video_queue: &VideoQueue,
 ) {
-    match get_channel_playlist_id(&channel_id).await {
-        Ok(playlist_id) => {
-            match check_playlist_for_new_videos(&playlist_id, &es_client, &video_queue, None).await
-            {
-                Ok(count) => {
-                    if let Err(e) = update_channel_video_count(channel_id, count, &es_client).await
-                    {
-                        error!("Failed to update channel video count: {}", e);
-                    }
-                }
-                Err(e) => {
-                    error!("Failed to check playlist for new videos: {}", e);
-                }
-            }
+    let source = CheckSource::Channel(channel_id.to_string());
+    if let Err(e) = check_source_for_new_videos(&source, es_client, video_queue).await {
+        error!(
+            "Failed to check channel {} for new videos: {}",
+            channel_id, e
+        );
+        ACTIVITY.record_monitor_error(format!("Channel {}: {}", channel_id, e));
+    }
+}
+
+/// The uploads playlist of a channel or the monitored playlist itself
+async fn source_playlist_id(source: &CheckSource) -> Result<String, anyhow::Error> {
+    match source {
+        CheckSource::Channel(channel_id) => get_channel_playlist_id(channel_id)
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to get upload playlist: {}", e)),
+        CheckSource::Playlist(playlist_id) => Ok(playlist_id.clone()),
+    }
+}
+
+/// Enqueues the videos of a channel's uploads playlist or of a monitored playlist that
+/// aren't indexed yet, attributed to `source`, and updates the monitor's video count.
+/// Of a backfilling monitor only the recent uploads are enqueued, see `run_backfill`.
+/// Returns the number of videos in the playlist.
+pub async fn check_source_for_new_videos(
+    source: &CheckSource,
+    es_client: &Elasticsearch,
+    video_queue: &VideoQueue,
+) -> Result<i64, anyhow::Error> {
+    let playlist_id = source_playlist_id(source).await?;
+
+    let all_playlist_videos = match fetch_all_playlist_videos(&playlist_id).await {
+        Ok(videos) => videos,
+        Err(e) => {
+            error!("Failed to fetch playlist videos: {}", e);
+            return Ok(0);
+        }
+    };
+
+    info!("Found {} videos in playlist", all_playlist_videos.len());
+    enqueue_missing_videos(
+        source,
+        &all_playlist_videos,
+        EnqueueScope::DeferBacklog,
+        es_client,
+        video_queue,
+    )
+    .await;
+
+    let video_count = all_playlist_videos.len() as i64;
+    if let Err(e) = update_source_video_count(source, video_count, es_client).await {
+        error!("Failed to update video count of {:?}: {}", source, e);
+    }
+    Ok(video_count)
+}
+
+/// Enqueues the latest uploads of a channel's RSS feed that aren't indexed yet. The feed
+/// only lists the newest videos, so the channel's video count is left alone.
+/// Returns the number of enqueued videos.
+pub async fn check_channel_feed_for_new_videos(
+    channel_id: &str,
+    es_client: &Elasticsearch,
+    video_queue: &VideoQueue,
+) -> Result<usize, anyhow::Error> {
+    let videos = rss_discovery::fetch_channel_feed(channel_id).await?;
+    info!("Found {} videos in feed of {}", videos.len(), channel_id);
+
+    let source = CheckSource::Channel(channel_id.to_string());
+    Ok(enqueue_missing_videos(&source, &videos, EnqueueScope::All, es_client, video_queue).await)
+}
+
+/// Which of the missing videos of a listing are enqueued
+#[derive(Debug, Clone, Copy)]
+enum EnqueueScope {
+    /// All of them, feeds only list the latest uploads anyway
+    All,
+    /// Only the recent uploads if more than `BACKFILL_THRESHOLD` are missing, the
+    /// backfill scheduler enqueues the older ones
+    DeferBacklog,
+    /// At most this many, the share of a backfill cycle
+    Backfill(usize),
+}
+
+/// Adds the videos that are neither indexed nor queued yet to the queue, attributed to
+/// `source` and in its enqueue order. Returns how many were added.
+async fn enqueue_missing_videos(
+    source: &CheckSource,
+    videos: &[PlaylistVideo],
+    scope: EnqueueScope,
+    es_client: &Elasticsearch,
+    video_queue: &VideoQueue,
+) -> usize {
+    if let CheckSource::Channel(channel_id) = source {
+        if optout_service::is_opted_out(channel_id).await {
+            info!(
+                "Channel {} opted out, enqueueing none of its videos",
+                channel_id
+            );
+            return 0;
         }
+    }
+
+    let video_ids: Vec<&str> = videos.iter().map(|v| v.video_id.as_str()).collect();
+    let indexed = match indexed_video_ids(es_client, &video_ids).await {
+        Ok(indexed) => indexed,
         Err(e) => {
-            error!(
-                "Failed to get upload playlist for channel {}: {}",
-                channel_id, e
+            error!("Failed to check video existence: {}", e);
+            return 0;
+        }
+    };
+    let queued: HashSet<String> = video_queue
+        .get_all_items()
+        .into_iter()
+        .map(|item| item.video_id)
+        .collect();
+
+    let mut missing_videos = Vec::new();
+    for video in videos.iter() {
+        if indexed.contains(&video.video_id) || queued.contains(&video.video_id) {
+            continue;
+        }
+        if let Some(channel_id) = &video.channel_id {
+            if optout_service::is_opted_out(channel_id).await {
+                info!(
+                    "Skipping video {} of opted-out channel {}",
+                    video.video_id, channel_id
+                );
+                continue;
+            }
+        }
+        missing_videos.push(video);
+    }
+
+    if matches!(scope, EnqueueScope::DeferBacklog)
+        && missing_videos.len() as i64 > *BACKFILL_THRESHOLD
+    {
+        let recent_cutoff = chrono::Utc::now().timestamp() - BACKFILL_RECENT_UPLOAD_SECONDS;
+        let missing = missing_videos.len();
+        missing_videos.retain(|video| video.published_at >= recent_cutoff);
+        info!(
+            "{:?} is backfilling, {} older videos are left to the backfill scheduler",
+            source,
+            missing - missing_videos.len()
+        );
+    }
+
+    let enqueue_order = get_enqueue_order(source).await;
+    sort_for_enqueue(&mut missing_videos, enqueue_order);
+    if let EnqueueScope::Backfill(limit) = scope {
+        missing_videos.truncate(limit);
+    }
+    for video in &missing_videos {
+        video_queue.add_discovered_video(video.video_id.clone(), Some(source.clone()));
+        info!("Added video to queue: {}", video.video_id);
+    }
+    info!(
+        "Enqueued {} videos from {:?} ({:?})",
+        missing_videos.len(),
+        source,
+        enqueue_order
+    );
+    missing_videos.len()
+}
+
+/// IDs among `video_ids` that are indexed, looked up in batches
+async fn indexed_video_ids(
+    es_client: &Elasticsearch,
+    video_ids: &[&str],
+) -> Result<HashSet<String>, anyhow::Error> {
+    let mut indexed = HashSet::new();
+    for batch in video_ids.chunks(1000) {
+        let response = es_client
+            .mget(MgetParts::Index(INDEX_NAMES.videos))
+            ._source(&["false"])
+            .body(json!({ "ids": batch }))
+            .send()
+            .await?;
+        if !response.status_code().is_success() {
+            return Err(anyhow::anyhow!(
+                "Video lookup failed: {}",
+                response.status_code()
+            ));
+        }
+        let response_body: Value = response.json().await?;
+        if let Some(docs) = response_body["docs"].as_array() {
+            indexed.extend(
+                docs.iter()
+                    .filter(|doc| doc["found"].as_bool().unwrap_or(false))
+                    .filter_map(|doc| doc["_id"].as_str().map(str::to_string)),
             );
         }
     }
+    Ok(indexed)
+}
+
+/// Active monitors listing more than `BACKFILL_THRESHOLD` videos that aren't indexed.
+/// RSS channels are skipped, listing their uploads costs the quota they are meant to save.
+async fn backfilling_sources(es_client: &Elasticsearch) -> Vec<CheckSource> {
+    let channels: Vec<(String, i64)> = MONITORED_CHANNELS
+        .read()
+        .await
+        .iter()
+        .filter(|c| c.active && c.discovery_mode == DiscoveryMode::Api)
+        .map(|c| (c.channel_id.clone(), c.videos_uploaded))
+        .collect();
+    let playlists: Vec<(String, i64)> = MONITORED_PlAYLISTS
+        .read()
+        .await
+        .iter()
+        .filter(|p| p.active)
+        .map(|p| (p.playlist_id.clone(), p.videos_added))
+        .collect();
+
+    let channel_ids: Vec<String> = channels.iter().map(|(id, _)| id.clone()).collect();
+    let playlist_ids: Vec<String> = playlists.iter().map(|(id, _)| id.clone()).collect();
+    let (channel_counts, playlist_counts) = tokio::join!(
+        aggregate_videos_by_term(es_client, "channel_id.keyword", &channel_ids),
+        aggregate_videos_by_term(es_client, "playlists.keyword", &playlist_ids),
+    );
+    let indexed = |counts: &HashMap<String, VideoTotals>, id: &str| {
+        counts.get(id).map_or(0, |totals| totals.videos)
+    };
+
+    let mut sources: Vec<CheckSource> = channels
+        .into_iter()
+        .filter(|(id, listed)| is_backfilling(*listed, indexed(&channel_counts, id)))
+        .map(|(id, _)| CheckSource::Channel(id))
+        .collect();
+    sources.extend(
+        playlists
+            .into_iter()
+            .filter(|(id, listed)| is_backfilling(*listed, indexed(&playlist_counts, id)))
+            .map(|(id, _)| CheckSource::Playlist(id)),
+    );
+    sources
+}
+
+/// One backfill cycle: enqueues up to `BACKFILL_VIDEOS_PER_CYCLE` videos, and none
+/// while `BACKFILL_MAX_QUEUE` are pending, split evenly between the backfilling
+/// monitors. The monitor going first rotates, shares a monitor doesn't use go to the
+/// ones after it. Runs under the maintenance `run`, pausing between the monitors.
+pub async fn run_backfill(
+    es_client: &Elasticsearch,
+    video_queue: &VideoQueue,
+    run: &MaintenanceRun,
+) {
+    let budget =
+        (*BACKFILL_VIDEOS_PER_CYCLE).min(BACKFILL_MAX_QUEUE.saturating_sub(video_queue.get_size()));
+    if budget == 0 {
+        info!("Queue is full, skipping this backfill cycle");
+        return;
+    }
+
+    let mut sources = backfilling_sources(es_client).await;
+    if sources.is_empty() {
+        return;
+    }
+    let start = BACKFILL_CURSOR.fetch_add(1, Ordering::Relaxed) % sources.len();
+    sources.rotate_left(start);
+
+    let mut remaining = budget;
+    for (i, source) in sources.iter().enumerate() {
+        if remaining == 0 {
+            break;
+        }
+        if i > 0 {
+            run.pause().await;
+        }
+        run.progress((budget - remaining) as i64, budget as i64);
+        let share = remaining.div_ceil(sources.len() - i);
+        let videos = match source_playlist_id(source).await {
+            Ok(playlist_id) => fetch_all_playlist_videos(&playlist_id).await,
+            Err(e) => Err(e),
+        };
+        match videos {
+            Ok(videos) => {
+                remaining -= enqueue_missing_videos(
+                    source,
+                    &videos,
+                    EnqueueScope::Backfill(share),
+                    es_client,
+                    video_queue,
+                )
+                .await;
+            }
+            Err(e) => error!("Failed to backfill {:?}: {}", source, e),
+        }
+    }
+    info!(
+        "Backfill cycle enqueued {} videos of {} monitors",
+        budget - remaining,
+        sources.len()
+    );
+}
+
+/// Enqueue order configured on the monitor behind `source`, the default for unknown ones
+async fn get_enqueue_order(source: &CheckSource) -> EnqueueOrder {
+    match source {
+        CheckSource::Channel(channel_id) => MONITORED_CHANNELS
+            .read()
+            .await
+            .iter()
+            .find(|c| &c.channel_id == channel_id)
+            .map(|c| c.enqueue_order),
+        CheckSource::Playlist(playlist_id) => MONITORED_PlAYLISTS
+            .read()
+            .await
+            .iter()
+            .find(|p| &p.playlist_id == playlist_id)
+            .map(|p| p.enqueue_order),
+    }
+    .unwrap_or_default()
+}
+
+/// Sorts by `published_at`, ties and `PlaylistOrder` keep the order of the playlist
+fn sort_for_enqueue(videos: &mut [&PlaylistVideo], order: EnqueueOrder) {
+    match order {
+        EnqueueOrder::NewestFirst => videos.sort_by_key(|video| Reverse(video.published_at)),
+        EnqueueOrder::OldestFirst => videos.sort_by_key(|video| video.published_at),
+        EnqueueOrder::PlaylistOrder => {}
+    }
+}
+
+async fn update_source_video_count(
+    source: &CheckSource,
+    video_count: i64,
+    es_client: &Elasticsearch,
+) -> Result<(), anyhow::Error> {
+    match source {
+        CheckSource::Channel(channel_id) => {
+            update_channel_video_count(channel_id, video_count, es_client).await
+        }
+        CheckSource::Playlist(playlist_id) => {
+            es_client
+                .update(elasticsearch::UpdateParts::IndexId(
+                    INDEX_NAMES.monitored_playlists,
+                    playlist_id,
+                ))
+                .body(json!({ "doc": { "videos_added": video_count } }))
+                .send()
+                .await?;
+
+            let mut playlists = MONITORED_PlAYLISTS.write().await;
+            if let Some(playlist) = playlists.iter_mut().find(|p| p.playlist_id == *playlist_id) {
+                playlist.videos_added = video_count;
+            }
+            Ok(())
+        }
+    }
 }
 
 async fn update_channel_video_count(
@@ -537,7 +1263,7 @@ async fn update_channel_video_count(
 ) -> Result<(), anyhow::Error> {
     es_client
         .update(elasticsearch::UpdateParts::IndexId(
-            "monitored_channels",
+            INDEX_NAMES.monitored_channels,
             channel_id,
         ))
         .body(json!({
@@ -557,55 +1283,17 @@ async fn update_channel_video_count(
     }
 }
 
-pub async fn check_playlist_for_new_videos(
-    playlist_id: &str,
-    es_client: &Elasticsearch,
-    video_queue: &VideoQueue,
-    source_playlist_id: Option<String>,
-) -> Result<i64, anyhow::Error> {
-    let all_playlist_videos = match fetch_all_playlist_videos(playlist_id).await {
-        Ok(videos) => videos,
-        Err(e) => {
-            error!("Failed to fetch playlist videos: {}", e);
-            return Ok(0);
-        }
-    };
-
-    info!("Found {} videos in playlist", all_playlist_videos.len());
-
-    let mut added_videos = 0;
-    for video_id in all_playlist_videos.clone() {
-        let search_response = es_client
-            .get(elasticsearch::GetParts::IndexId(
-                "youtube_videos",
-                &video_id,
-            ))
-            .send()
-            .await;
-
-        match search_response {
-            Ok(response) => {
-                // Video doesn't exist, add to queue
-                if !response.status_code().is_success() {
-                    video_queue.add_playlist_video(video_id.clone(), source_playlist_id.clone());
-                    added_videos += 1;
-                    info!("Added video to queue: {}", video_id);
-                } else {
-                    info!("Video already exists: {}", video_id);
-                }
-            }
-            Err(e) => {
-                error!("Failed to check video existence: {}", e);
-            }
-        }
+/// returns the complete video-library-playlist (as list-id) of a channel with the given channel-id.
+/// It never changes, so it is only looked up once per channel.
+pub async fn get_channel_playlist_id(channel_id: &str) -> Result<String, anyhow::Error> {
+    if *SIMULATION_MODE {
+        // Simulated uploads are generated from the channel ID itself
+        return Ok(channel_id.to_string());
+    }
+    if let Some(playlist_id) = UPLOADS_PLAYLISTS.read().await.get(channel_id) {
+        return Ok(playlist_id.clone());
     }
-    info!("Enqueued {} videos from Playlist", added_videos);
-    Ok(all_playlist_videos.len() as i64)
-}
 
-/// returns the complete video-library-playlist (as list-id) of a channel with the given channel-id
-pub async fn get_channel_playlist_id(channel_id: &str) -> Result<String, anyhow::Error> {
-    let client = Client::new();
     let api_key = &*YOUTUBE_API_KEY;
 
     let url = format!(
@@ -613,25 +1301,39 @@ pub async fn get_channel_playlist_id(channel_id: &str) -> Result<String, anyhow:
         channel_id, api_key
     );
 
-    let response = client
-        .get(&url)
-        .send()
-        .await?
-        .json::<serde_json::Value>()
-        .await?;
+    let response = youtube_api::get_json(&url).await?;
+    youtube_api::check_api_error(&response)?;
 
     let uploads_playlist_id = response["items"][0]["contentDetails"]["relatedPlaylists"]["uploads"]
         .as_str()
-        .ok_or_else(|| anyhow::anyhow!("No uploads playlist found"))?;
+        .ok_or_else(|| anyhow::anyhow!("No uploads playlist found"))?
+        .to_string();
+
+    UPLOADS_PLAYLISTS
+        .write()
+        .await
+        .insert(channel_id.to_string(), uploads_playlist_id.clone());
+    Ok(uploads_playlist_id)
+}
 
-    Ok(uploads_playlist_id.to_string())
+/// Video of a playlist with the time it was published to the playlist
+#[derive(Debug)]
+pub struct PlaylistVideo {
+    pub video_id: String,
+    pub published_at: i64, // unix seconds, 0 if unknown
+    /// Channel that uploaded the video, None if the playlist doesn't tell
+    pub channel_id: Option<String>,
 }
 
-/// Returns a list of YT-Videos of a given playlist.
-pub async fn fetch_all_playlist_videos(playlist_id: &str) -> Result<Vec<String>, anyhow::Error> {
-    let client = Client::new();
+/// Returns a list of YT-Videos of a given playlist, in playlist order.
+pub async fn fetch_all_playlist_videos(
+    playlist_id: &str,
+) -> Result<Vec<PlaylistVideo>, anyhow::Error> {
+    if *SIMULATION_MODE {
+        return Ok(simulation::playlist_videos(playlist_id));
+    }
     let api_key = &*YOUTUBE_API_KEY;
-    let mut all_video_ids = Vec::new();
+    let mut all_videos = Vec::new();
     let mut next_page_token: Option<String> = None;
 
     loop {
@@ -645,17 +1347,21 @@ pub async fn fetch_all_playlist_videos(playlist_id: &str) -> Result<Vec<String>,
             url.push_str(&format!("&pageToken={}", token));
         }
 
-        let response = client
-            .get(&url)
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?;
+        let response = youtube_api::get_json(&url).await?;
 
         if let Some(items) = response["items"].as_array() {
             for item in items {
                 if let Some(video_id) = item["snippet"]["resourceId"]["videoId"].as_str() {
-                    all_video_ids.push(video_id.to_string());
+                    all_videos.push(PlaylistVideo {
+                        video_id: video_id.to_string(),
+                        published_at: utils::parse_iso8601_to_timestamp(
+                            item["snippet"]["publishedAt"].as_str().unwrap_or(""),
+                        ),
+                        // Missing for private and deleted videos
+                        channel_id: item["snippet"]["videoOwnerChannelId"]
+                            .as_str()
+                            .map(str::to_string),
+                    });
                 }
             }
         }
@@ -667,7 +1373,7 @@ pub async fn fetch_all_playlist_videos(playlist_id: &str) -> Result<Vec<String>,
         }
     }
 
-    Ok(all_video_ids)
+    Ok(all_videos)
 }
 
 pub async fn set_channel_active(
@@ -677,7 +1383,7 @@ pub async fn set_channel_active(
 ) -> Result<(), anyhow::Error> {
     es_client
         .update(elasticsearch::UpdateParts::IndexId(
-            "monitored_channels",
+            INDEX_NAMES.monitored_channels,
             channel_id,
         ))
         .body(json!({
@@ -704,7 +1410,7 @@ pub async fn set_playlist_active(
 ) -> Result<(), anyhow::Error> {
     es_client
         .update(elasticsearch::UpdateParts::IndexId(
-            "monitored_playlists",
+            INDEX_NAMES.monitored_playlists,
             playlist_id,
         ))
         .body(json!({
@@ -723,3 +1429,665 @@ pub async fn set_playlist_active(
         Err(anyhow::anyhow!("Playlist not found"))
     }
 }
+
+const MAX_MONITOR_NAME_LENGTH: usize = 200;
+const MAX_MONITOR_TAGS: usize = 20;
+const MAX_TAG_LENGTH: usize = 40;
+
+/// Checks a monitor update before anything is written, one error per invalid field
+pub fn validate_monitor_update(update: &MonitorUpdate, is_playlist: bool) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    if update.is_empty() {
+        errors.push(FieldError {
+            field: String::new(),
+            message: "Nothing to update".to_string(),
+        });
+    }
+    if let Some(name) = &update.name {
+        let name = name.trim();
+        if name.is_empty() {
+            errors.push(FieldError {
+                field: "name".to_string(),
+                message: "Name must not be empty".to_string(),
+            });
+        } else if name.chars().count() > MAX_MONITOR_NAME_LENGTH {
+            errors.push(FieldError {
+                field: "name".to_string(),
+                message: format!(
+                    "Name must be at most {} characters",
+                    MAX_MONITOR_NAME_LENGTH
+                ),
+            });
+        }
+    }
+    if is_playlist && update.hidden.is_some() {
+        errors.push(FieldError {
+            field: "hidden".to_string(),
+            message: "Playlists can't be hidden".to_string(),
+        });
+    }
+    if is_playlist && update.discovery_mode.is_some() {
+        errors.push(FieldError {
+            field: "discovery_mode".to_string(),
+            message: "Playlists are always checked through the API".to_string(),
+        });
+    }
+    if update
+        .max_duration_seconds
+        .is_some_and(|seconds| seconds < 0)
+    {
+        errors.push(FieldError {
+            field: "max_duration_seconds".to_string(),
+            message: "Maximum duration must not be negative".to_string(),
+        });
+    }
+    if let Some(tags) = &update.tags {
+        let tags = monitor_tag_service::normalize_tags(tags);
+        if tags.len() > MAX_MONITOR_TAGS {
+            errors.push(FieldError {
+                field: "tags".to_string(),
+                message: format!("At most {} tags per monitor", MAX_MONITOR_TAGS),
+            });
+        }
+        if let Some(tag) = tags.iter().find(|tag| tag.chars().count() > MAX_TAG_LENGTH) {
+            errors.push(FieldError {
+                field: "tags".to_string(),
+                message: format!(
+                    "Tag \"{}\" is longer than {} characters",
+                    tag, MAX_TAG_LENGTH
+                ),
+            });
+        }
+    }
+    errors
+}
+
+/// Applies a validated update. Returns false if the channel isn't monitored. Changing
+/// `hidden` needs the maintenance `run`.
+pub async fn update_channel(
+    channel_id: &str,
+    update: &MonitorUpdate,
+    es_client: &Elasticsearch,
+    generation: &ArchiveGeneration,
+    run: Option<&MaintenanceRun>,
+) -> Result<bool, anyhow::Error> {
+    if update.hidden.is_some() && run.is_none() {
+        return Err(anyhow::anyhow!(
+            "Hiding channel {channel_id} needs the maintenance throttle"
+        ));
+    }
+    if !MONITORED_CHANNELS
+        .read()
+        .await
+        .iter()
+        .any(|c| c.channel_id == channel_id)
+    {
+        return Ok(false);
+    }
+
+    let name = update.name.as_ref().map(|name| name.trim().to_string());
+    let mut doc = serde_json::Map::new();
+    if let Some(name) = &name {
+        doc.insert("channel_name".to_string(), json!(name));
+    }
+    if let Some(active) = update.active {
+        doc.insert("active".to_string(), json!(active));
+    }
+    if let Some(enqueue_order) = update.enqueue_order {
+        doc.insert("enqueue_order".to_string(), json!(enqueue_order));
+    }
+    if let Some(discovery_mode) = update.discovery_mode {
+        doc.insert("discovery_mode".to_string(), json!(discovery_mode));
+    }
+    let max_duration_seconds = update.max_duration_seconds.map(duration_override);
+    if let Some(max_duration_seconds) = max_duration_seconds {
+        doc.insert(
+            "max_duration_seconds".to_string(),
+            json!(max_duration_seconds),
+        );
+    }
+    let tags = update
+        .tags
+        .as_deref()
+        .map(monitor_tag_service::normalize_tags);
+    if let Some(tags) = &tags {
+        doc.insert("tags".to_string(), json!(tags));
+    }
+
+    if !doc.is_empty() {
+        es_client
+            .update(elasticsearch::UpdateParts::IndexId(
+                INDEX_NAMES.monitored_channels,
+                channel_id,
+            ))
+            .body(json!({ "doc": doc }))
+            .send()
+            .await?;
+
+        let mut channels = MONITORED_CHANNELS.write().await;
+        if let Some(channel) = channels.iter_mut().find(|c| c.channel_id == channel_id) {
+            if let Some(name) = name {
+                channel.channel_name = name;
+            }
+            if let Some(active) = update.active {
+                channel.active = active;
+            }
+            if let Some(enqueue_order) = update.enqueue_order {
+                channel.enqueue_order = enqueue_order;
+            }
+            if let Some(discovery_mode) = update.discovery_mode {
+                channel.discovery_mode = discovery_mode;
+            }
+            if let Some(max_duration_seconds) = max_duration_seconds {
+                channel.max_duration_seconds = max_duration_seconds;
+            }
+            if let Some(tags) = tags {
+                channel.tags = tags;
+            }
+        }
+    }
+    // Hiding also has to be applied to the channel's indexed videos
+    if let (Some(hidden), Some(run)) = (update.hidden, run) {
+        set_channel_hidden(channel_id, hidden, es_client, generation, run).await?;
+    }
+    info!("Updated monitored channel {}: {:?}", channel_id, update);
+    Ok(true)
+}
+
+/// Applies a validated update. Returns false if the playlist isn't monitored.
+pub async fn update_playlist(
+    playlist_id: &str,
+    update: &MonitorUpdate,
+    es_client: &Elasticsearch,
+) -> Result<bool, anyhow::Error> {
+    if !MONITORED_PlAYLISTS
+        .read()
+        .await
+        .iter()
+        .any(|p| p.playlist_id == playlist_id)
+    {
+        return Ok(false);
+    }
+
+    let name = update.name.as_ref().map(|name| name.trim().to_string());
+    let mut doc = serde_json::Map::new();
+    if let Some(name) = &name {
+        doc.insert("playlist_name".to_string(), json!(name));
+    }
+    if let Some(active) = update.active {
+        doc.insert("active".to_string(), json!(active));
+    }
+    if let Some(enqueue_order) = update.enqueue_order {
+        doc.insert("enqueue_order".to_string(), json!(enqueue_order));
+    }
+    let max_duration_seconds = update.max_duration_seconds.map(duration_override);
+    if let Some(max_duration_seconds) = max_duration_seconds {
+        doc.insert(
+            "max_duration_seconds".to_string(),
+            json!(max_duration_seconds),
+        );
+    }
+    let tags = update
+        .tags
+        .as_deref()
+        .map(monitor_tag_service::normalize_tags);
+    if let Some(tags) = &tags {
+        doc.insert("tags".to_string(), json!(tags));
+    }
+
+    es_client
+        .update(elasticsearch::UpdateParts::IndexId(
+            INDEX_NAMES.monitored_playlists,
+            playlist_id,
+        ))
+        .body(json!({ "doc": doc }))
+        .send()
+        .await?;
+
+    let mut playlists = MONITORED_PlAYLISTS.write().await;
+    if let Some(playlist) = playlists.iter_mut().find(|p| p.playlist_id == playlist_id) {
+        if let Some(name) = name {
+            playlist.playlist_name = name;
+        }
+        if let Some(active) = update.active {
+            playlist.active = active;
+        }
+        if let Some(enqueue_order) = update.enqueue_order {
+            playlist.enqueue_order = enqueue_order;
+        }
+        if let Some(max_duration_seconds) = max_duration_seconds {
+            playlist.max_duration_seconds = max_duration_seconds;
+        }
+        if let Some(tags) = tags {
+            playlist.tags = tags;
+        }
+    }
+    info!("Updated monitored playlist {}: {:?}", playlist_id, update);
+    Ok(true)
+}
+
+/// Stored override of an update's `max_duration_seconds`, where 0 removes it
+fn duration_override(seconds: i64) -> Option<i64> {
+    (seconds > 0).then_some(seconds)
+}
+
+/// Maximum duration set on the monitor that enqueued a video, with that monitor.
+/// Manually enqueued videos fall back to the monitor of their channel, if there is one.
+pub async fn max_duration_override(
+    source: Option<&CheckSource>,
+    channel_id: &str,
+) -> Option<(i64, CheckSource)> {
+    match source {
+        Some(CheckSource::Playlist(playlist_id)) => MONITORED_PlAYLISTS
+            .read()
+            .await
+            .iter()
+            .find(|p| &p.playlist_id == playlist_id)
+            .and_then(|p| p.max_duration_seconds)
+            .map(|seconds| (seconds, CheckSource::Playlist(playlist_id.clone()))),
+        Some(CheckSource::Channel(channel_id)) => channel_duration_override(channel_id).await,
+        None => channel_duration_override(channel_id).await,
+    }
+}
+
+async fn channel_duration_override(channel_id: &str) -> Option<(i64, CheckSource)> {
+    MONITORED_CHANNELS
+        .read()
+        .await
+        .iter()
+        .find(|c| c.channel_id == channel_id)
+        .and_then(|c| c.max_duration_seconds)
+        .map(|seconds| (seconds, CheckSource::Channel(channel_id.to_string())))
+}
+
+pub async fn is_channel_hidden(channel_id: &str) -> bool {
+    MONITORED_CHANNELS
+        .read()
+        .await
+        .iter()
+        .any(|c| c.channel_id == channel_id && c.hidden)
+}
+
+/// Hides or unhides a channel without deleting anything. The flag is copied onto the
+/// channel's video and caption documents so public queries can filter on it directly,
+/// in batches at the pace of the maintenance `run`. Returns false if the channel isn't
+/// monitored, nothing is written then.
+pub async fn set_channel_hidden(
+    channel_id: &str,
+    hidden: bool,
+    es_client: &Elasticsearch,
+    generation: &ArchiveGeneration,
+    run: &MaintenanceRun,
+) -> Result<bool, anyhow::Error> {
+    if !MONITORED_CHANNELS
+        .read()
+        .await
+        .iter()
+        .any(|c| c.channel_id == channel_id)
+    {
+        return Ok(false);
+    }
+
+    let throttle = run.throttle();
+    es_client
+        .update(elasticsearch::UpdateParts::IndexId(
+            INDEX_NAMES.monitored_channels,
+            channel_id,
+        ))
+        .body(json!({
+            "doc": {
+                "hidden": hidden
+            }
+        }))
+        .send()
+        .await?;
+
+    if let Some(channel) = MONITORED_CHANNELS
+        .write()
+        .await
+        .iter_mut()
+        .find(|c| c.channel_id == channel_id)
+    {
+        channel.hidden = hidden;
+    }
+
+    let script = json!({
+        "source": "ctx._source.hidden = params.hidden",
+        "params": { "hidden": hidden }
+    });
+
+    let response = es_client
+        .update_by_query(UpdateByQueryParts::Index(&[INDEX_NAMES.videos]))
+        .conflicts(Conflicts::Proceed)
+        .scroll_size(throttle.scroll_size())
+        .requests_per_second(throttle.requests_per_second())
+        .refresh(true)
+        .body(json!({
+            "script": script,
+            "query": { "term": { "channel_id.keyword": channel_id } }
+        }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Failed to update videos of channel {}: {}",
+            channel_id,
+            response.text().await.unwrap_or_default()
+        ));
+    }
+    generation.bump();
+
+    let video_ids =
+        video_service::video_ids_of_channels(es_client, &[channel_id.to_string()]).await?;
+    for (i, chunk) in video_ids.chunks(throttle.batch_size).enumerate() {
+        if i > 0 {
+            run.pause().await;
+        }
+        let response = es_client
+            .update_by_query(UpdateByQueryParts::Index(&[
+                INDEX_NAMES.captions,
+                INDEX_NAMES.sentences,
+            ]))
+            .conflicts(Conflicts::Proceed)
+            .scroll_size(throttle.scroll_size())
+            .requests_per_second(throttle.requests_per_second())
+            .refresh(true)
+            .body(json!({
+                "script": script,
+                "query": { "terms": { "video_id": chunk } }
+            }))
+            .send()
+            .await?;
+        if !response.status_code().is_success() {
+            return Err(anyhow::anyhow!(
+                "Failed to update captions of channel {}: {}",
+                channel_id,
+                response.text().await.unwrap_or_default()
+            ));
+        }
+        run.progress(
+            (i * throttle.batch_size + chunk.len()) as i64,
+            video_ids.len() as i64,
+        );
+    }
+
+    info!(
+        "Channel {} {} ({} videos)",
+        channel_id,
+        if hidden { "hidden" } else { "unhidden" },
+        video_ids.len()
+    );
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsupport::{maintenance_run, opt_out, MockElasticsearch};
+
+    fn aggregation_response() -> Value {
+        json!({
+            "took": 3,
+            "hits": { "total": { "value": 7, "relation": "eq" }, "hits": [] },
+            "aggregations": {
+                "per_value": {
+                    "doc_count_error_upper_bound": 0,
+                    "sum_other_doc_count": 0,
+                    "buckets": [
+                        {
+                            "key": "UCaaaaaaaaaaaaaaaaaaaaaa",
+                            "doc_count": 5,
+                            "duration": { "value": 9000.0 },
+                            "recent": { "doc_count": 2 }
+                        },
+                        {
+                            "key": "UCbbbbbbbbbbbbbbbbbbbbbb",
+                            "doc_count": 2,
+                            "duration": { "value": 0.0 },
+                            "recent": { "doc_count": 0 }
+                        }
+                    ]
+                }
+            }
+        })
+    }
+
+    fn channel(channel_id: &str, videos_uploaded: i64) -> MonitoredChannel {
+        MonitoredChannel {
+            videos_uploaded,
+            ..simulation::monitored_channel(channel_id)
+        }
+    }
+
+    #[test]
+    fn bucket_totals_are_parsed() {
+        let totals = parse_bucket_totals(&aggregation_response()["aggregations"]["per_value"]);
+        assert_eq!(totals.len(), 2);
+        assert_eq!(
+            totals["UCaaaaaaaaaaaaaaaaaaaaaa"],
+            VideoTotals {
+                videos: 5,
+                duration_seconds: 9000.0,
+                recent_videos: 2
+            }
+        );
+        assert_eq!(totals["UCbbbbbbbbbbbbbbbbbbbbbb"].videos, 2);
+    }
+
+    #[test]
+    fn missing_aggregation_is_empty() {
+        assert!(parse_bucket_totals(&json!({})["aggregations"]["per_value"]).is_empty());
+        assert!(parse_bucket_totals(&json!({ "buckets": [{ "doc_count": 1 }] })).is_empty());
+    }
+
+    #[test]
+    fn counts_map_back_onto_the_monitors() {
+        let counts = parse_bucket_totals(&aggregation_response()["aggregations"]["per_value"]);
+        let stats: Vec<MonitoredChannelStats> = [
+            channel("UCaaaaaaaaaaaaaaaaaaaaaa", 5),
+            channel("UCcccccccccccccccccccccc", 3),
+            channel("UCbbbbbbbbbbbbbbbbbbbbbb", 2),
+        ]
+        .into_iter()
+        .map(|channel| channel_stats(channel, &counts))
+        .collect();
+
+        let indexed: Vec<(&str, i32)> = stats
+            .iter()
+            .map(|stats| (stats.channel_id.as_str(), stats.videos_indexed))
+            .collect();
+        assert_eq!(
+            indexed,
+            [
+                ("UCaaaaaaaaaaaaaaaaaaaaaa", 5),
+                // Monitors without indexed videos are still listed
+                ("UCcccccccccccccccccccccc", 0),
+                ("UCbbbbbbbbbbbbbbbbbbbbbb", 2)
+            ]
+        );
+        assert_eq!(stats[0].indexed_hours, 2.5);
+        assert!(stats.iter().all(|stats| !stats.backfilling));
+    }
+
+    #[test]
+    fn backfill_eta_needs_recent_crawls() {
+        let counts = parse_bucket_totals(&aggregation_response()["aggregations"]["per_value"]);
+        let missing = *BACKFILL_THRESHOLD + 1;
+
+        let stats = channel_stats(channel("UCaaaaaaaaaaaaaaaaaaaaaa", 5 + missing), &counts);
+        assert!(stats.backfilling);
+        let eta = stats
+            .backfill_eta_ms
+            .expect("recently crawled videos give an ETA");
+        assert!(eta > chrono::Utc::now().timestamp_millis());
+
+        let stats = channel_stats(channel("UCbbbbbbbbbbbbbbbbbbbbbb", 2 + missing), &counts);
+        assert!(stats.backfilling);
+        assert_eq!(stats.backfill_eta_ms, None);
+    }
+
+    /// Stubs the video lookup, the first video of the simulated listing is indexed
+    async fn stub_first_video_indexed(es: &MockElasticsearch, videos: &[PlaylistVideo]) {
+        let docs: Vec<Value> = videos
+            .iter()
+            .enumerate()
+            .map(|(i, video)| json!({ "_id": video.video_id, "found": i == 0 }))
+            .collect();
+        es.stub(
+            "POST",
+            &format!("/{}/_mget", INDEX_NAMES.videos),
+            json!({ "docs": docs }),
+        )
+        .await;
+    }
+
+    #[rocket::async_test]
+    async fn playlist_checks_enqueue_the_missing_videos() {
+        let es = MockElasticsearch::start().await;
+        let playlist_id = "PLcheckcheckcheckcheckcheckcheck00";
+        let videos = simulation::playlist_videos(playlist_id);
+        stub_first_video_indexed(&es, &videos).await;
+        let update = format!("/{}/_update/{playlist_id}", INDEX_NAMES.monitored_playlists);
+        es.stub("POST", &update, json!({ "result": "updated" }))
+            .await;
+
+        let queue = VideoQueue::new();
+        let source = CheckSource::Playlist(playlist_id.to_string());
+        let video_count = check_source_for_new_videos(&source, &es.client, &queue)
+            .await
+            .unwrap();
+
+        assert_eq!(video_count, videos.len() as i64);
+        let items = queue.get_all_items();
+        assert_eq!(items.len(), videos.len() - 1);
+        assert!(items
+            .iter()
+            .all(|item| item.source.as_ref() == Some(&source)));
+        assert!(!items.iter().any(|item| item.video_id == videos[0].video_id));
+
+        let lookups = es
+            .bodies("POST", &format!("/{}/_mget", INDEX_NAMES.videos))
+            .await;
+        assert_eq!(lookups.len(), 1);
+        assert_eq!(lookups[0]["ids"].as_array().unwrap().len(), videos.len());
+        assert_eq!(
+            es.bodies("POST", &update).await,
+            [json!({ "doc": { "videos_added": videos.len() } })]
+        );
+    }
+
+    #[rocket::async_test]
+    async fn queued_videos_are_not_enqueued_twice() {
+        let es = MockElasticsearch::start().await;
+        let playlist_id = "PLtwicetwicetwicetwicetwicetwice00";
+        let videos = simulation::playlist_videos(playlist_id);
+        stub_first_video_indexed(&es, &videos).await;
+
+        let queue = VideoQueue::new();
+        let source = CheckSource::Playlist(playlist_id.to_string());
+        check_source_for_new_videos(&source, &es.client, &queue)
+            .await
+            .unwrap();
+        check_source_for_new_videos(&source, &es.client, &queue)
+            .await
+            .unwrap();
+
+        assert_eq!(queue.get_size(), videos.len() - 1);
+    }
+
+    #[rocket::async_test]
+    async fn channel_checks_attribute_the_videos_to_the_channel() {
+        let es = MockElasticsearch::start().await;
+        let channel_id = "UCcheckcheckcheckcheck00";
+        // Simulated uploads are listed under the channel ID
+        let videos = simulation::playlist_videos(channel_id);
+        stub_first_video_indexed(&es, &videos).await;
+
+        let queue = VideoQueue::new();
+        let source = CheckSource::Channel(channel_id.to_string());
+        check_source_for_new_videos(&source, &es.client, &queue)
+            .await
+            .unwrap();
+
+        let items = queue.get_all_items();
+        assert_eq!(items.len(), videos.len() - 1);
+        assert!(items
+            .iter()
+            .all(|item| item.source.as_ref() == Some(&source)));
+        assert_eq!(
+            es.bodies(
+                "POST",
+                &format!("/{}/_update/{channel_id}", INDEX_NAMES.monitored_channels)
+            )
+            .await,
+            [json!({ "doc": { "videos_uploaded": videos.len() } })]
+        );
+    }
+
+    #[rocket::async_test]
+    async fn checks_enqueue_no_videos_of_opted_out_channels() {
+        let es = MockElasticsearch::start().await;
+        let opted_out = "UCoptedOutMonitorCheck01";
+        let other = "UCnotOptedOutMonitor0001";
+        opt_out(&es, opted_out).await;
+        es.stub(
+            "POST",
+            &format!("/{}/_mget", INDEX_NAMES.videos),
+            json!({ "docs": [] }),
+        )
+        .await;
+        let video = |video_id: &str, channel_id: &str| PlaylistVideo {
+            video_id: video_id.to_string(),
+            published_at: 0,
+            channel_id: Some(channel_id.to_string()),
+        };
+
+        // A playlist mixing the channel's videos with others
+        let queue = VideoQueue::new();
+        let playlist = CheckSource::Playlist("PLoptedOutoptedOutoptedOutoptedO".to_string());
+        let videos = [
+            video("aaaaaaaaaaa", opted_out),
+            video("bbbbbbbbbbb", other),
+            video("ccccccccccc", opted_out),
+        ];
+        let enqueued =
+            enqueue_missing_videos(&playlist, &videos, EnqueueScope::All, &es.client, &queue).await;
+        assert_eq!(enqueued, 1);
+        let queued: Vec<String> = queue
+            .get_all_items()
+            .into_iter()
+            .map(|item| item.video_id)
+            .collect();
+        assert_eq!(queued, ["bbbbbbbbbbb"]);
+
+        // The channel's own uploads, even those not attributed to a channel
+        let queue = VideoQueue::new();
+        let channel = CheckSource::Channel(opted_out.to_string());
+        let uploads = [PlaylistVideo {
+            channel_id: None,
+            ..video("ddddddddddd", opted_out)
+        }];
+        let enqueued =
+            enqueue_missing_videos(&channel, &uploads, EnqueueScope::All, &es.client, &queue).await;
+        assert_eq!(enqueued, 0);
+        assert_eq!(queue.get_size(), 0);
+    }
+
+    #[rocket::async_test]
+    async fn hiding_an_unknown_channel_writes_nothing() {
+        let es = MockElasticsearch::start().await;
+        let channel_id = "UCnotMonitoredHidden0001";
+        let hidden = set_channel_hidden(
+            channel_id,
+            true,
+            &es.client,
+            &ArchiveGeneration::new(),
+            &maintenance_run(),
+        )
+        .await
+        .unwrap();
+        assert!(!hidden);
+        let update = format!("/{}/_update/{channel_id}", INDEX_NAMES.monitored_channels);
+        assert!(es.bodies("POST", &update).await.is_empty());
+    }
+}
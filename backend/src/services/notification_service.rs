@@ -0,0 +1,631 @@
+use crate::config::INDEX_NAMES;
+use crate::models::ApiKey;
+use crate::services::api_key_service::{self, ApiKeyUsageTracker};
+use anyhow::Result;
+use elasticsearch::{Elasticsearch, SearchParts};
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use log::{error, info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::env;
+use std::sync::Mutex;
+
+const MAX_DIGEST_VIDEOS: usize = 200;
+const MAX_TRACKED_QUERIES: usize = 10_000;
+const MAX_MONITOR_ERRORS: usize = 100;
+const TOP_QUERY_COUNT: usize = 10;
+
+/// Occurrences per key, most frequent first
+type Counts = Vec<(String, usize)>;
+
+lazy_static::lazy_static! {
+    /// Activity since the last digest, filled by the crawler, the monitors and search
+    pub static ref ACTIVITY: ActivityLog = ActivityLog::new();
+}
+
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+impl SmtpConfig {
+    /// Returns None unless SMTP_ENABLED is set and host, sender and recipients are configured
+    pub fn from_env() -> Option<Self> {
+        let enabled = env::var("SMTP_ENABLED")
+            .map(|v| v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        if !enabled {
+            return None;
+        }
+
+        let non_empty = |name: &str| env::var(name).ok().filter(|v| !v.trim().is_empty());
+
+        let config = SmtpConfig {
+            host: non_empty("SMTP_HOST")?,
+            port: non_empty("SMTP_PORT")
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(587),
+            username: non_empty("SMTP_USERNAME"),
+            password: non_empty("SMTP_PASSWORD"),
+            from: non_empty("SMTP_FROM")?,
+            to: non_empty("SMTP_TO")?
+                .split(',')
+                .map(|to| to.trim().to_string())
+                .filter(|to| !to.is_empty())
+                .collect(),
+        };
+
+        if config.to.is_empty() {
+            return None;
+        }
+        Some(config)
+    }
+}
+
+#[derive(Default)]
+struct Activity {
+    failures: HashMap<String, usize>,
+    monitor_errors: Vec<String>,
+    search_queries: HashMap<String, usize>,
+}
+
+pub struct ActivityLog {
+    activity: Mutex<Activity>,
+}
+
+impl ActivityLog {
+    pub fn new() -> Self {
+        ActivityLog {
+            activity: Mutex::new(Activity::default()),
+        }
+    }
+
+    /// Counts a crawl failure, `kind` being a short stable identifier like "transcript_fetch"
+    pub fn record_failure(&self, kind: &str) {
+        if let Ok(mut activity) = self.activity.lock() {
+            *activity.failures.entry(kind.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    pub fn record_monitor_error(&self, message: String) {
+        if let Ok(mut activity) = self.activity.lock() {
+            if activity.monitor_errors.len() < MAX_MONITOR_ERRORS {
+                activity.monitor_errors.push(message);
+            }
+        }
+    }
+
+    pub fn record_search(&self, query: &str) {
+        let query = query.trim().to_lowercase();
+        if let Ok(mut activity) = self.activity.lock() {
+            if let Some(count) = activity.search_queries.get_mut(&query) {
+                *count += 1;
+            } else if activity.search_queries.len() < MAX_TRACKED_QUERIES {
+                activity.search_queries.insert(query, 1);
+            }
+        }
+    }
+
+    /// Returns (failures by kind, monitor errors, top queries) and resets the log
+    fn take(&self) -> (Counts, Vec<String>, Counts) {
+        let activity = match self.activity.lock() {
+            Ok(mut activity) => std::mem::take(&mut *activity),
+            Err(_) => return (Vec::new(), Vec::new(), Vec::new()),
+        };
+
+        let mut failures: Vec<_> = activity.failures.into_iter().collect();
+        failures.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let mut queries: Vec<_> = activity.search_queries.into_iter().collect();
+        queries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        queries.truncate(TOP_QUERY_COUNT);
+
+        (failures, activity.monitor_errors, queries)
+    }
+}
+
+impl Default for ActivityLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DigestVideo {
+    pub video_id: String,
+    pub title: String,
+    pub channel_name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct DigestData {
+    pub since: i64, // unix
+    pub until: i64, // unix
+    pub indexed_total: usize,
+    pub indexed_videos: Vec<DigestVideo>,
+    pub failures: Vec<(String, usize)>,
+    pub monitor_errors: Vec<String>,
+    pub api_keys: Vec<ApiKey>,
+    pub top_queries: Vec<(String, usize)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Email {
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+}
+
+/// Collects the digest of the last 24h. Activity counters are reset by this call.
+pub async fn collect_digest_data(
+    es_client: &Elasticsearch,
+    api_key_usage: &ApiKeyUsageTracker,
+) -> Result<DigestData> {
+    let until = chrono::Utc::now().timestamp();
+    let since = until - 24 * 60 * 60;
+
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.videos]))
+        .body(json!({
+            "query": { "range": { "crawl_date": { "gte": since } } },
+            "_source": ["video_id", "title", "channel_name"],
+            "sort": [{ "crawl_date": { "order": "desc" } }],
+            "size": MAX_DIGEST_VIDEOS,
+            "track_total_hits": true
+        }))
+        .send()
+        .await?;
+
+    let json_response: Value = response.json().await?;
+    let indexed_total = json_response["hits"]["total"]["value"]
+        .as_u64()
+        .unwrap_or(0) as usize;
+    let indexed_videos = json_response["hits"]["hits"]
+        .as_array()
+        .map(|hits| {
+            hits.iter()
+                .filter_map(|hit| serde_json::from_value(hit["_source"].clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let api_keys = api_key_service::list_api_keys(es_client, api_key_usage)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Failed to load API keys for digest: {e:?}");
+            Vec::new()
+        });
+
+    let (failures, monitor_errors, top_queries) = ACTIVITY.take();
+
+    Ok(DigestData {
+        since,
+        until,
+        indexed_total,
+        indexed_videos,
+        failures,
+        monitor_errors,
+        api_keys,
+        top_queries,
+    })
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders the digest as plain text and HTML. Kept free of any I/O.
+pub fn compose_digest(app_name: &str, data: &DigestData) -> Email {
+    let subject = format!(
+        "{}: {} videos indexed, {} failures",
+        app_name,
+        data.indexed_total,
+        data.failures.iter().map(|(_, count)| count).sum::<usize>()
+    );
+    let period = format!(
+        "{} - {}",
+        format_timestamp(data.since),
+        format_timestamp(data.until)
+    );
+
+    let mut text = format!("{app_name} daily digest\n{period}\n");
+    let mut html = format!(
+        "<h2>{} daily digest</h2><p>{}</p>",
+        escape_html(app_name),
+        escape_html(&period)
+    );
+
+    text.push_str(&format!("\nVideos indexed: {}\n", data.indexed_total));
+    html.push_str(&format!("<h3>Videos indexed: {}</h3>", data.indexed_total));
+    if !data.indexed_videos.is_empty() {
+        html.push_str("<ul>");
+        for video in &data.indexed_videos {
+            text.push_str(&format!(
+                "  - {} ({}) [{}]\n",
+                video.title, video.channel_name, video.video_id
+            ));
+            html.push_str(&format!(
+                "<li><a href=\"https://www.youtube.com/watch?v={}\">{}</a> ({})</li>",
+                escape_html(&video.video_id),
+                escape_html(&video.title),
+                escape_html(&video.channel_name)
+            ));
+        }
+        html.push_str("</ul>");
+        if data.indexed_total > data.indexed_videos.len() {
+            let more = data.indexed_total - data.indexed_videos.len();
+            text.push_str(&format!("  ... and {more} more\n"));
+            html.push_str(&format!("<p>... and {more} more</p>"));
+        }
+    }
+
+    push_counts(
+        &mut text,
+        &mut html,
+        "Failures by kind",
+        &data.failures,
+        "No failures",
+    );
+
+    text.push_str(&format!(
+        "\nMonitor errors: {}\n",
+        data.monitor_errors.len()
+    ));
+    html.push_str(&format!(
+        "<h3>Monitor errors: {}</h3>",
+        data.monitor_errors.len()
+    ));
+    if !data.monitor_errors.is_empty() {
+        html.push_str("<ul>");
+        for monitor_error in &data.monitor_errors {
+            text.push_str(&format!("  - {monitor_error}\n"));
+            html.push_str(&format!("<li>{}</li>", escape_html(monitor_error)));
+        }
+        html.push_str("</ul>");
+    }
+
+    text.push_str("\nAPI key usage today\n");
+    html.push_str("<h3>API key usage today</h3>");
+    if data.api_keys.is_empty() {
+        text.push_str("  No API keys\n");
+        html.push_str("<p>No API keys</p>");
+    } else {
+        html.push_str("<ul>");
+        for key in &data.api_keys {
+            text.push_str(&format!(
+                "  - {}: {} / {}\n",
+                key.label, key.usage_today, key.daily_quota
+            ));
+            html.push_str(&format!(
+                "<li>{}: {} / {}</li>",
+                escape_html(&key.label),
+                key.usage_today,
+                key.daily_quota
+            ));
+        }
+        html.push_str("</ul>");
+    }
+
+    push_counts(
+        &mut text,
+        &mut html,
+        "Top search queries",
+        &data.top_queries,
+        "No searches",
+    );
+
+    Email {
+        subject,
+        text,
+        html,
+    }
+}
+
+fn push_counts(
+    text: &mut String,
+    html: &mut String,
+    title: &str,
+    counts: &[(String, usize)],
+    empty: &str,
+) {
+    text.push_str(&format!("\n{title}\n"));
+    html.push_str(&format!("<h3>{}</h3>", escape_html(title)));
+
+    if counts.is_empty() {
+        text.push_str(&format!("  {empty}\n"));
+        html.push_str(&format!("<p>{}</p>", escape_html(empty)));
+        return;
+    }
+
+    html.push_str("<ul>");
+    for (name, count) in counts {
+        text.push_str(&format!("  - {name}: {count}\n"));
+        html.push_str(&format!("<li>{}: {}</li>", escape_html(name), count));
+    }
+    html.push_str("</ul>");
+}
+
+pub fn compose_test_email(app_name: &str) -> Email {
+    let sent_at = format_timestamp(chrono::Utc::now().timestamp());
+    Email {
+        subject: format!("{app_name}: test notification"),
+        text: format!("Email notifications are working.\nSent at {sent_at}\n"),
+        html: format!(
+            "<p>Email notifications are working.</p><p>Sent at {}</p>",
+            escape_html(&sent_at)
+        ),
+    }
+}
+
+pub async fn send_email(config: &SmtpConfig, email: &Email) -> Result<()> {
+    let mut builder = Message::builder()
+        .from(config.from.parse::<Mailbox>()?)
+        .subject(email.subject.clone());
+    for to in &config.to {
+        builder = builder.to(to.parse::<Mailbox>()?);
+    }
+    let message = builder.multipart(MultiPart::alternative_plain_html(
+        email.text.clone(),
+        email.html.clone(),
+    ))?;
+
+    let mut transport =
+        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?.port(config.port);
+    if let (Some(username), Some(password)) = (&config.username, &config.password) {
+        transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+
+    transport.build().send(message).await?;
+    info!("Sent email '{}' to {:?}", email.subject, config.to);
+    Ok(())
+}
+
+pub async fn send_daily_digest(
+    es_client: &Elasticsearch,
+    api_key_usage: &ApiKeyUsageTracker,
+    config: &SmtpConfig,
+    app_name: &str,
+) {
+    let data = match collect_digest_data(es_client, api_key_usage).await {
+        Ok(data) => data,
+        Err(e) => {
+            error!("Failed to collect digest data: {e:?}");
+            return;
+        }
+    };
+
+    if let Err(e) = send_email(config, &compose_digest(app_name, &data)).await {
+        error!("Failed to send daily digest: {e:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn video(video_id: &str, title: &str, channel_name: &str) -> DigestVideo {
+        DigestVideo {
+            video_id: video_id.to_string(),
+            title: title.to_string(),
+            channel_name: channel_name.to_string(),
+        }
+    }
+
+    fn digest() -> DigestData {
+        DigestData {
+            since: 1_700_000_000,
+            until: 1_700_086_400,
+            indexed_total: 0,
+            indexed_videos: Vec::new(),
+            failures: Vec::new(),
+            monitor_errors: Vec::new(),
+            api_keys: Vec::new(),
+            top_queries: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn empty_digest() {
+        let email = compose_digest("Starchive", &digest());
+        assert_eq!(email.subject, "Starchive: 0 videos indexed, 0 failures");
+        assert!(email
+            .text
+            .starts_with("Starchive daily digest\n2023-11-14 22:13 UTC - 2023-11-15 22:13 UTC\n"));
+        for line in [
+            "No failures",
+            "No API keys",
+            "No searches",
+            "Monitor errors: 0",
+        ] {
+            assert!(email.text.contains(line), "{line} missing");
+            assert!(email.html.contains(line), "{line} missing");
+        }
+        assert!(!email.text.contains("more"));
+    }
+
+    #[test]
+    fn subject_sums_the_failures() {
+        let mut data = digest();
+        data.indexed_total = 3;
+        data.failures = vec![
+            ("timeout".to_string(), 2),
+            ("transcript_fetch".to_string(), 5),
+        ];
+        let email = compose_digest("Starchive", &data);
+        assert_eq!(email.subject, "Starchive: 3 videos indexed, 7 failures");
+        assert!(email
+            .text
+            .contains("  - timeout: 2\n  - transcript_fetch: 5\n"));
+    }
+
+    #[test]
+    fn html_is_escaped() {
+        let mut data = digest();
+        data.indexed_total = 1;
+        data.indexed_videos = vec![video("abc\"def", "<b>Tom & Jerry</b>", "A&B")];
+        data.monitor_errors = vec!["<script>alert(1)</script>".to_string()];
+        data.top_queries = vec![("\"rock & roll\"".to_string(), 4)];
+        let email = compose_digest("<Archive>", &data);
+
+        assert!(email
+            .html
+            .starts_with("<h2>&lt;Archive&gt; daily digest</h2>"));
+        assert!(email.html.contains(
+            "<li><a href=\"https://www.youtube.com/watch?v=abc&quot;def\">\
+             &lt;b&gt;Tom &amp; Jerry&lt;/b&gt;</a> (A&amp;B)</li>"
+        ));
+        assert!(email
+            .html
+            .contains("<li>&lt;script&gt;alert(1)&lt;/script&gt;</li>"));
+        assert!(email
+            .html
+            .contains("<li>&quot;rock &amp; roll&quot;: 4</li>"));
+        assert!(!email.html.contains("<script>"));
+
+        // The plain text part is not HTML
+        assert!(email
+            .text
+            .contains("  - <b>Tom & Jerry</b> (A&B) [abc\"def]\n"));
+        assert!(email.text.contains("  - \"rock & roll\": 4\n"));
+    }
+
+    #[test]
+    fn videos_beyond_the_listed_ones_are_summarized() {
+        let mut data = digest();
+        data.indexed_total = 250;
+        data.indexed_videos = (0..MAX_DIGEST_VIDEOS)
+            .map(|i| video(&format!("video{i:06}"), "Title", "Channel"))
+            .collect();
+        let email = compose_digest("Starchive", &data);
+        assert!(email.text.contains("  ... and 50 more\n"));
+        assert!(email.html.contains("<p>... and 50 more</p>"));
+
+        data.indexed_total = MAX_DIGEST_VIDEOS;
+        let email = compose_digest("Starchive", &data);
+        assert!(!email.text.contains("more"));
+    }
+
+    #[test]
+    fn api_key_usage() {
+        let mut data = digest();
+        data.api_keys = vec![ApiKey {
+            key_hash: "hash".to_string(),
+            label: "Research <team>".to_string(),
+            daily_quota: 1000,
+            created_at: 0,
+            usage_day: "2023-11-15".to_string(),
+            usage_today: 42,
+        }];
+        let email = compose_digest("Starchive", &data);
+        assert!(email.text.contains("  - Research <team>: 42 / 1000\n"));
+        assert!(email
+            .html
+            .contains("<li>Research &lt;team&gt;: 42 / 1000</li>"));
+    }
+
+    #[test]
+    fn take_orders_and_resets() {
+        let log = ActivityLog::new();
+        for query in ["Rust", "rust ", "zig", "go", "go", "RUST"] {
+            log.record_search(query);
+        }
+        for kind in ["timeout", "private", "timeout"] {
+            log.record_failure(kind);
+        }
+        log.record_monitor_error("channel gone".to_string());
+
+        let (failures, monitor_errors, queries) = log.take();
+        assert_eq!(
+            failures,
+            vec![("timeout".to_string(), 2), ("private".to_string(), 1)]
+        );
+        assert_eq!(monitor_errors, vec!["channel gone".to_string()]);
+        // By count, ties alphabetically
+        assert_eq!(
+            queries,
+            vec![
+                ("rust".to_string(), 3),
+                ("go".to_string(), 2),
+                ("zig".to_string(), 1)
+            ]
+        );
+
+        let (failures, monitor_errors, queries) = log.take();
+        assert!(failures.is_empty() && monitor_errors.is_empty() && queries.is_empty());
+    }
+
+    #[test]
+    fn take_keeps_the_top_queries() {
+        let log = ActivityLog::new();
+        for i in 0..TOP_QUERY_COUNT + 5 {
+            for _ in 0..=i {
+                log.record_search(&format!("query {i:02}"));
+            }
+        }
+        let (_, _, queries) = log.take();
+        assert_eq!(queries.len(), TOP_QUERY_COUNT);
+        assert_eq!(
+            queries[0],
+            (format!("query {:02}", TOP_QUERY_COUNT + 4), 15)
+        );
+        assert!(queries.windows(2).all(|pair| pair[0].1 > pair[1].1));
+    }
+
+    #[test]
+    fn monitor_errors_are_capped() {
+        let log = ActivityLog::new();
+        for i in 0..MAX_MONITOR_ERRORS + 10 {
+            log.record_monitor_error(format!("error {i}"));
+        }
+        assert_eq!(log.take().1.len(), MAX_MONITOR_ERRORS);
+    }
+
+    #[test]
+    fn smtp_is_disabled_without_complete_config() {
+        // Only this test touches the SMTP variables
+        for name in [
+            "SMTP_ENABLED",
+            "SMTP_HOST",
+            "SMTP_PORT",
+            "SMTP_USERNAME",
+            "SMTP_PASSWORD",
+            "SMTP_FROM",
+            "SMTP_TO",
+        ] {
+            env::remove_var(name);
+        }
+        env::set_var("SMTP_HOST", "smtp.example.com");
+        env::set_var("SMTP_FROM", "archive@example.com");
+        env::set_var("SMTP_TO", "ops@example.com, ,admin@example.com");
+        assert!(SmtpConfig::from_env().is_none(), "disabled by default");
+
+        env::set_var("SMTP_ENABLED", "TRUE");
+        let config = SmtpConfig::from_env().expect("complete config");
+        assert_eq!(config.port, 587);
+        assert_eq!(config.to, ["ops@example.com", "admin@example.com"]);
+        assert_eq!(config.username, None);
+
+        env::set_var("SMTP_TO", " , ");
+        assert!(SmtpConfig::from_env().is_none(), "no recipients");
+        env::set_var("SMTP_TO", "ops@example.com");
+        env::set_var("SMTP_HOST", " ");
+        assert!(SmtpConfig::from_env().is_none(), "no host");
+    }
+}
@@ -0,0 +1,175 @@
+//! Channels whose creators asked to be removed from the archive. The list is kept in
+//! memory, every ingestion path checks it: manual enqueues, monitor checks and finally
+//! the crawl worker before anything is indexed.
+
+use crate::config::INDEX_NAMES;
+use crate::models::{OptOutChannel, OptOutRequest, OptOutResponse};
+use crate::services::archive_generation::ArchiveGeneration;
+use crate::services::maintenance_throttle::MaintenanceRun;
+use crate::services::{admin_service, video_service};
+use anyhow::Result;
+use elasticsearch::params::Refresh;
+use elasticsearch::{CountParts, DeleteParts, Elasticsearch, IndexParts, SearchParts};
+use log::{error, info};
+use serde_json::{json, Value};
+use tokio::sync::RwLock;
+
+lazy_static::lazy_static! {
+    static ref OPTED_OUT_CHANNELS: RwLock<Vec<OptOutChannel>> = RwLock::new(Vec::new());
+}
+
+pub async fn load_opt_outs(es_client: &Elasticsearch) {
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.optout_channels]))
+        .body(json!({ "query": { "match_all": {} }, "size": 10000 }))
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status_code().is_success() => {
+            let response_body: Value = response.json().await.unwrap_or_default();
+            let opt_outs: Vec<OptOutChannel> = response_body["hits"]["hits"]
+                .as_array()
+                .map(|hits| {
+                    hits.iter()
+                        .filter_map(|hit| serde_json::from_value(hit["_source"].clone()).ok())
+                        .collect()
+                })
+                .unwrap_or_default();
+            info!("Loaded {} opted-out channels", opt_outs.len());
+            *OPTED_OUT_CHANNELS.write().await = opt_outs;
+        }
+        Ok(response) => error!(
+            "Failed to load opted-out channels: {}",
+            response.status_code()
+        ),
+        Err(e) => error!("Failed to load opted-out channels: {e:?}"),
+    }
+}
+
+pub async fn is_opted_out(channel_id: &str) -> bool {
+    !channel_id.is_empty()
+        && OPTED_OUT_CHANNELS
+            .read()
+            .await
+            .iter()
+            .any(|opt_out| opt_out.channel_id == channel_id)
+}
+
+/// Opted-out channels, latest first
+pub async fn list_opt_outs() -> Vec<OptOutChannel> {
+    let mut opt_outs = OPTED_OUT_CHANNELS.read().await.clone();
+    opt_outs.sort_by_key(|opt_out| std::cmp::Reverse(opt_out.created_at_ms));
+    opt_outs
+}
+
+/// Counts the channel's indexed content and, unless `dry_run`, stores the opt-out and
+/// starts deleting that content if requested. Deleting needs the maintenance `run`.
+pub async fn add_opt_out(
+    es_client: &Elasticsearch,
+    generation: &ArchiveGeneration,
+    request: &OptOutRequest,
+    dry_run: bool,
+    run: Option<MaintenanceRun>,
+) -> Result<OptOutResponse> {
+    let channel_id = request.channel_id.trim();
+    let video_ids =
+        video_service::video_ids_of_channels(es_client, &[channel_id.to_string()]).await?;
+    let captions = count_captions(es_client, &video_ids).await?;
+    let mut response = OptOutResponse {
+        channel_id: channel_id.to_string(),
+        dry_run,
+        videos: video_ids.len() as i64,
+        captions,
+        task_id: None,
+    };
+    if dry_run {
+        return Ok(response);
+    }
+    if request.delete_content && run.is_none() {
+        return Err(anyhow::anyhow!(
+            "Deleting the content of channel {channel_id} needs the maintenance throttle"
+        ));
+    }
+
+    let opt_out = OptOutChannel {
+        channel_id: channel_id.to_string(),
+        reason: request.reason.trim().to_string(),
+        created_at_ms: chrono::Utc::now().timestamp_millis(),
+    };
+    let index_response = es_client
+        .index(IndexParts::IndexId(INDEX_NAMES.optout_channels, channel_id))
+        .refresh(Refresh::True)
+        .body(json!(opt_out))
+        .send()
+        .await?;
+    if !index_response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Failed to store the opt-out of channel {channel_id}: {}",
+            index_response.status_code()
+        ));
+    }
+    {
+        let mut opt_outs = OPTED_OUT_CHANNELS.write().await;
+        opt_outs.retain(|existing| existing.channel_id != channel_id);
+        opt_outs.push(opt_out);
+    }
+    info!("Channel {channel_id} opted out");
+
+    if let Some(run) = run.filter(|_| request.delete_content && !video_ids.is_empty()) {
+        response.task_id = Some(
+            admin_service::delete_channel_content(
+                es_client, generation, channel_id, &video_ids, run,
+            )
+            .await?,
+        );
+    }
+    Ok(response)
+}
+
+/// Lifts an opt-out. Returns false if the channel wasn't opted out.
+pub async fn remove_opt_out(es_client: &Elasticsearch, channel_id: &str) -> Result<bool> {
+    let response = es_client
+        .delete(DeleteParts::IndexId(
+            INDEX_NAMES.optout_channels,
+            channel_id,
+        ))
+        .refresh(Refresh::True)
+        .send()
+        .await?;
+    if response.status_code().as_u16() == 404 {
+        return Ok(false);
+    }
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Failed to remove the opt-out of channel {channel_id}: {}",
+            response.status_code()
+        ));
+    }
+
+    OPTED_OUT_CHANNELS
+        .write()
+        .await
+        .retain(|opt_out| opt_out.channel_id != channel_id);
+    info!("Opt-out of channel {channel_id} removed");
+    Ok(true)
+}
+
+pub async fn count_captions(es_client: &Elasticsearch, video_ids: &[String]) -> Result<i64> {
+    if video_ids.is_empty() {
+        return Ok(0);
+    }
+    let response = es_client
+        .count(CountParts::Index(&[INDEX_NAMES.captions]))
+        .body(json!({ "query": { "terms": { "video_id": video_ids } } }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Caption count failed: {}",
+            response.status_code()
+        ));
+    }
+    let json_response: Value = response.json().await?;
+    Ok(json_response["count"].as_i64().unwrap_or(0))
+}
@@ -0,0 +1,51 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Keys are pruned once the map grows beyond this many entries
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Sliding-window rate limiter keyed by an arbitrary string (usually the client IP).
+pub struct RateLimiter {
+    max_hits: usize,
+    window_seconds: i64,
+    hits: Mutex<HashMap<String, VecDeque<i64>>>,
+}
+
+impl RateLimiter {
+    pub fn new(max_hits: usize, window_seconds: i64) -> Self {
+        RateLimiter {
+            max_hits,
+            window_seconds,
+            hits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records a hit for `key`. Returns false if the key already used up its window.
+    pub fn check(&self, key: &str) -> bool {
+        let now = chrono::Utc::now().timestamp();
+
+        if let Ok(mut hits) = self.hits.lock() {
+            let entry = hits.entry(key.to_string()).or_default();
+            while let Some(&oldest) = entry.front() {
+                if now - oldest >= self.window_seconds {
+                    entry.pop_front();
+                } else {
+                    break;
+                }
+            }
+
+            if entry.len() >= self.max_hits {
+                return false;
+            }
+            entry.push_back(now);
+
+            if hits.len() > PRUNE_THRESHOLD {
+                let window = self.window_seconds;
+                hits.retain(|_, v| v.back().is_some_and(|&t| now - t < window));
+            }
+            true
+        } else {
+            true
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Debug mode keeping the raw YouTube API response of each video on disk, so odd
+//! metadata (empty titles, zero durations) can be traced back to what YouTube sent.
+//! Enabled with `CAPTURE_RAW_RESPONSES`, the oldest captures are pruned once the
+//! directory grows beyond `RAW_CAPTURE_MAX_MB`.
+
+use crate::config::{CAPTURE_RAW_RESPONSES, RAW_CAPTURE_DIR, RAW_CAPTURE_MAX_MB};
+use crate::services::thumbnail_service::is_cacheable_id;
+use anyhow::Result;
+use lazy_static::lazy_static;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+const CAPTURE_SUFFIX: &str = ".json";
+
+lazy_static! {
+    pub static ref RAW_CAPTURES: RawCaptureStore = RawCaptureStore::new(
+        PathBuf::from(&*RAW_CAPTURE_DIR),
+        *RAW_CAPTURE_MAX_MB * 1024 * 1024
+    );
+}
+
+/// One stored response, the file holds it as JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawCapture {
+    pub video_id: String,
+    /// Data API endpoint, e.g. "videos"
+    pub endpoint: String,
+    pub captured_at: i64,
+    pub response: Value,
+}
+
+/// Latest response per video and endpoint, files are named `<video_id>.<endpoint>.json`
+pub struct RawCaptureStore {
+    dir: PathBuf,
+    max_bytes: u64,
+    // Serializes pruning so two concurrent captures don't both walk the directory
+    pruning: Mutex<()>,
+}
+
+impl RawCaptureStore {
+    pub fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        RawCaptureStore {
+            dir,
+            max_bytes,
+            pruning: Mutex::new(()),
+        }
+    }
+
+    /// Stores `response` if capturing is enabled, replacing an earlier capture of the
+    /// same video and endpoint. Failures are only logged, crawling goes on regardless.
+    pub fn capture(&self, video_id: &str, endpoint: &str, response: &Value) {
+        if !*CAPTURE_RAW_RESPONSES {
+            return;
+        }
+        if let Err(e) = self.write(video_id, endpoint, response) {
+            warn!("Failed to capture the {endpoint} response of {video_id}: {e:?}");
+        }
+    }
+
+    fn path(&self, video_id: &str, endpoint: &str) -> PathBuf {
+        self.dir
+            .join(format!("{video_id}.{endpoint}{CAPTURE_SUFFIX}"))
+    }
+
+    fn write(&self, video_id: &str, endpoint: &str, response: &Value) -> Result<()> {
+        // Both become part of the file name
+        if !is_cacheable_id(video_id) || !is_cacheable_id(endpoint) {
+            return Err(anyhow::anyhow!("Invalid capture key {video_id}/{endpoint}"));
+        }
+        let capture = RawCapture {
+            video_id: video_id.to_string(),
+            endpoint: endpoint.to_string(),
+            captured_at: chrono::Utc::now().timestamp(),
+            response: response.clone(),
+        };
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.path(video_id, endpoint), serde_json::to_vec(&capture)?)?;
+        self.prune();
+        Ok(())
+    }
+
+    /// Captures of a video, one per endpoint, oldest first
+    pub fn get(&self, video_id: &str) -> Vec<RawCapture> {
+        if !is_cacheable_id(video_id) {
+            return Vec::new();
+        }
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let prefix = format!("{video_id}.");
+        let mut captures: Vec<RawCapture> = entries
+            .flatten()
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                name.starts_with(&prefix) && name.ends_with(CAPTURE_SUFFIX)
+            })
+            .filter_map(|entry| {
+                let bytes = fs::read(entry.path()).ok()?;
+                serde_json::from_slice(&bytes).ok()
+            })
+            .collect();
+        captures.sort_by_key(|capture| capture.captured_at);
+        captures
+    }
+
+    /// Deletes the oldest captures until the directory fits `max_bytes`
+    fn prune(&self) {
+        let _guard = self.pruning.lock().unwrap();
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+
+        let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                Some((metadata.modified().ok()?, metadata.len(), entry.path()))
+            })
+            .collect();
+        let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+        if total <= self.max_bytes {
+            return;
+        }
+
+        files.sort_by_key(|(modified, _, _)| *modified);
+        let mut pruned = 0;
+        for (_, size, path) in files {
+            if total <= self.max_bytes {
+                break;
+            }
+            if fs::remove_file(&path).is_ok() {
+                total -= size;
+                pruned += 1;
+            }
+        }
+        info!("Pruned {pruned} raw captures, {total} bytes are left");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::crawler::parse_video_metadata;
+    use std::time::Duration;
+
+    /// Capture directory of one test, removed again when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir()
+                .join(format!("starchive-captures-{}-{name}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn sample_videos_response() -> Value {
+        serde_json::from_str(include_str!("testdata/youtube_videos_response.json")).unwrap()
+    }
+
+    fn file_size(store: &RawCaptureStore, video_id: &str) -> u64 {
+        fs::metadata(store.path(video_id, "videos")).unwrap().len()
+    }
+
+    #[test]
+    fn captured_responses_replay_through_the_parser() {
+        let dir = TempDir::new("replay");
+        let store = RawCaptureStore::new(dir.0.clone(), u64::MAX);
+        let response = sample_videos_response();
+        store.write("dQw4w9WgXcQ", "videos", &response).unwrap();
+
+        let captures = store.get("dQw4w9WgXcQ");
+        assert_eq!(captures.len(), 1);
+        assert_eq!(captures[0].endpoint, "videos");
+        assert_eq!(captures[0].response, response);
+
+        let metadata = parse_video_metadata("dQw4w9WgXcQ", &captures[0].response, 0);
+        assert_eq!(metadata.channel_id, "UCuAXFkgsw1L7xaCfnd5JJOw");
+        assert_eq!(metadata.duration, 213);
+    }
+
+    #[test]
+    fn later_captures_replace_earlier_ones() {
+        let dir = TempDir::new("replace");
+        let store = RawCaptureStore::new(dir.0.clone(), u64::MAX);
+        store
+            .write("dQw4w9WgXcQ", "videos", &serde_json::json!({ "items": [] }))
+            .unwrap();
+        store
+            .write("dQw4w9WgXcQ", "videos", &sample_videos_response())
+            .unwrap();
+
+        let captures = store.get("dQw4w9WgXcQ");
+        assert_eq!(captures.len(), 1);
+        assert_eq!(captures[0].response, sample_videos_response());
+        assert!(store.get("aaaaaaaaaaa").is_empty());
+    }
+
+    #[test]
+    fn keys_that_are_no_file_names_are_rejected() {
+        let dir = TempDir::new("invalid-key");
+        let store = RawCaptureStore::new(dir.0.clone(), u64::MAX);
+        let response = sample_videos_response();
+
+        assert!(store.write("../secret", "videos", &response).is_err());
+        assert!(store.write("dQw4w9WgXcQ", "videos/..", &response).is_err());
+        assert!(store.get("../secret").is_empty());
+        assert!(!dir.0.exists());
+    }
+
+    #[test]
+    fn oldest_captures_are_pruned() {
+        let dir = TempDir::new("prune");
+        let unlimited = RawCaptureStore::new(dir.0.clone(), u64::MAX);
+        let response = sample_videos_response();
+        unlimited.write("aaaaaaaaaaa", "videos", &response).unwrap();
+        unlimited.write("bbbbbbbbbbb", "videos", &response).unwrap();
+        fs::File::options()
+            .append(true)
+            .open(unlimited.path("aaaaaaaaaaa", "videos"))
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(60))
+            .unwrap();
+
+        let both = file_size(&unlimited, "aaaaaaaaaaa") + file_size(&unlimited, "bbbbbbbbbbb");
+        let store = RawCaptureStore::new(dir.0.clone(), both - 1);
+        store.prune();
+
+        assert!(store.get("aaaaaaaaaaa").is_empty());
+        assert_eq!(store.get("bbbbbbbbbbb").len(), 1);
+    }
+}
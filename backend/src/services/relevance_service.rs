@@ -0,0 +1,126 @@
+//! Previews how a candidate relevance config would rank the videos of a query, next to
+//! the ranking searches use now, before the boosts are changed for good.
+
+use crate::config::INDEX_NAMES;
+use crate::models::{RankMove, RankedVideo, RelevanceConfig, RelevancePreview};
+use crate::services::search_service::{self, SearchOptions};
+use anyhow::Result;
+use elasticsearch::{Elasticsearch, MgetParts};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Videos ranked per config
+const PREVIEW_SIZE: usize = 25;
+
+/// Ranks the top videos of `query` with the current and the candidate config
+pub async fn preview(
+    es_client: &Elasticsearch,
+    query: &str,
+    options: &SearchOptions,
+    candidate: &RelevanceConfig,
+) -> Result<RelevancePreview> {
+    let current_config = RelevanceConfig::default();
+    let (current, candidate) = futures::try_join!(
+        search_service::rank_videos(es_client, query, PREVIEW_SIZE, options, &current_config),
+        search_service::rank_videos(es_client, query, PREVIEW_SIZE, options, candidate),
+    )?;
+
+    let mut video_ids: Vec<&str> = current.iter().map(|v| v.video_id.as_str()).collect();
+    for video in &candidate {
+        if !video_ids.contains(&video.video_id.as_str()) {
+            video_ids.push(&video.video_id);
+        }
+    }
+    let titles = video_titles(es_client, &video_ids).await?;
+    let with_titles = |ranking: Vec<RankedVideo>| -> Vec<RankedVideo> {
+        ranking
+            .into_iter()
+            .map(|video| RankedVideo {
+                title: titles.get(&video.video_id).cloned(),
+                ..video
+            })
+            .collect()
+    };
+    let (current, candidate) = (with_titles(current), with_titles(candidate));
+
+    let (moves, unchanged) = rank_moves(&current, &candidate);
+    Ok(RelevancePreview {
+        current_config,
+        current,
+        candidate,
+        moves,
+        unchanged,
+    })
+}
+
+/// Videos whose rank differs between the rankings, ordered by candidate rank with the
+/// videos that dropped out last. Also returns the number of videos that kept their rank.
+fn rank_moves(current: &[RankedVideo], candidate: &[RankedVideo]) -> (Vec<RankMove>, usize) {
+    let rank_in = |ranking: &[RankedVideo], video_id: &str| {
+        ranking
+            .iter()
+            .position(|video| video.video_id == video_id)
+            .map(|position| position + 1)
+    };
+
+    let mut moves = Vec::new();
+    let mut unchanged = 0;
+    for (position, video) in candidate.iter().enumerate() {
+        let current_rank = rank_in(current, &video.video_id);
+        if current_rank == Some(position + 1) {
+            unchanged += 1;
+        } else {
+            moves.push(RankMove {
+                video_id: video.video_id.clone(),
+                current_rank,
+                candidate_rank: Some(position + 1),
+            });
+        }
+    }
+    for (position, video) in current.iter().enumerate() {
+        if rank_in(candidate, &video.video_id).is_none() {
+            moves.push(RankMove {
+                video_id: video.video_id.clone(),
+                current_rank: Some(position + 1),
+                candidate_rank: None,
+            });
+        }
+    }
+    (moves, unchanged)
+}
+
+async fn video_titles(
+    es_client: &Elasticsearch,
+    video_ids: &[&str],
+) -> Result<HashMap<String, String>> {
+    if video_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let response = es_client
+        .mget(MgetParts::Index(INDEX_NAMES.videos))
+        ._source_includes(&["title"])
+        .body(json!({ "ids": video_ids }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Loading video titles failed: {}",
+            response.status_code()
+        ));
+    }
+
+    let json_response: Value = response.json().await?;
+    Ok(json_response["docs"]
+        .as_array()
+        .map(|docs| {
+            docs.iter()
+                .filter_map(|doc| {
+                    Some((
+                        doc["_id"].as_str()?.to_string(),
+                        doc["_source"]["title"].as_str()?.to_string(),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
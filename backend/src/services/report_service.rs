@@ -0,0 +1,144 @@
+use crate::config::INDEX_NAMES;
+use crate::models::{CaptionReport, CaptionReportRequest, VideoReportCount};
+use anyhow::Result;
+use elasticsearch::{CreateParts, Elasticsearch, MgetParts, SearchParts};
+use log::info;
+use serde_json::{json, Value};
+
+/// Stores a report for a single caption. Reports are deduplicated per (video, caption, day),
+/// so the return value is false if the caption was already reported today.
+pub async fn submit_caption_report(
+    es_client: &Elasticsearch,
+    request: &CaptionReportRequest,
+) -> Result<bool> {
+    let now = chrono::Utc::now();
+    let day = now.format("%Y-%m-%d").to_string();
+    let doc_id = format!("{}_{}_{}", request.video_id, request.start_time, day);
+
+    let report = CaptionReport {
+        video_id: request.video_id.clone(),
+        start_time: request.start_time,
+        query: request.query.chars().take(200).collect(),
+        day,
+        reported_at: now.timestamp(),
+    };
+
+    let response = es_client
+        .create(CreateParts::IndexId(INDEX_NAMES.caption_reports, &doc_id))
+        .body(json!(report))
+        .send()
+        .await?;
+
+    let status = response.status_code();
+    if status.as_u16() == 409 {
+        return Ok(false);
+    }
+    if !status.is_success() {
+        return Err(anyhow::anyhow!(
+            "Failed to store caption report: {}",
+            response.text().await.unwrap_or_default()
+        ));
+    }
+
+    info!(
+        "Caption reported: video {} at {}s",
+        report.video_id, report.start_time
+    );
+    Ok(true)
+}
+
+/// Returns the number of reports per video, most reported first
+pub async fn get_report_counts(es_client: &Elasticsearch) -> Result<Vec<VideoReportCount>> {
+    let search_body = json!({
+        "size": 0,
+        "aggs": {
+            "videos": {
+                "terms": {
+                    "field": "video_id",
+                    "size": 500,
+                    "order": { "_count": "desc" }
+                },
+                "aggs": {
+                    "reported_captions": { "cardinality": { "field": "start_time" } },
+                    "last_reported_at": { "max": { "field": "reported_at" } }
+                }
+            }
+        }
+    });
+
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.caption_reports]))
+        .body(search_body)
+        .send()
+        .await?;
+
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!("Elasticsearch report aggregation failed"));
+    }
+
+    let json_response: Value = response.json().await?;
+    let mut reports: Vec<VideoReportCount> = json_response["aggregations"]["videos"]["buckets"]
+        .as_array()
+        .map(|buckets| {
+            buckets
+                .iter()
+                .filter_map(|bucket| {
+                    Some(VideoReportCount {
+                        video_id: bucket["key"].as_str()?.to_string(),
+                        title: None,
+                        report_count: bucket["doc_count"].as_i64().unwrap_or(0),
+                        reported_captions: bucket["reported_captions"]["value"]
+                            .as_i64()
+                            .unwrap_or(0),
+                        last_reported_at: bucket["last_reported_at"]["value"]
+                            .as_f64()
+                            .map(|v| v as i64),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    attach_video_titles(es_client, &mut reports).await;
+
+    Ok(reports)
+}
+
+async fn attach_video_titles(es_client: &Elasticsearch, reports: &mut [VideoReportCount]) {
+    if reports.is_empty() {
+        return;
+    }
+
+    let docs: Vec<Value> = reports
+        .iter()
+        .map(|r| json!({ "_index": INDEX_NAMES.videos, "_id": r.video_id, "_source": ["title"] }))
+        .collect();
+
+    let response = match es_client
+        .mget(MgetParts::None)
+        .body(json!({ "docs": docs }))
+        .send()
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => {
+            log::error!("Failed to fetch titles for reported videos: {e:?}");
+            return;
+        }
+    };
+
+    if let Ok(json_response) = response.json::<Value>().await {
+        if let Some(docs) = json_response["docs"].as_array() {
+            for doc in docs {
+                let (Some(id), Some(title)) =
+                    (doc["_id"].as_str(), doc["_source"]["title"].as_str())
+                else {
+                    continue;
+                };
+                if let Some(report) = reports.iter_mut().find(|r| r.video_id == id) {
+                    report.title = Some(title.to_string());
+                }
+            }
+        }
+    }
+}
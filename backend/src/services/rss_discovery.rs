@@ -0,0 +1,195 @@
+//! New uploads of a channel from its public Atom feed, which costs no API quota but
+//! only lists the ~15 latest videos.
+
+use crate::config::SIMULATION_MODE;
+use crate::services::monitoring_service::PlaylistVideo;
+use crate::services::simulation;
+use crate::services::youtube_api;
+use anyhow::Result;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+pub fn feed_url(channel_id: &str) -> String {
+    format!("https://www.youtube.com/feeds/videos.xml?channel_id={channel_id}")
+}
+
+/// Latest uploads of a channel, newest first like the feed
+pub async fn fetch_channel_feed(channel_id: &str) -> Result<Vec<PlaylistVideo>> {
+    if *SIMULATION_MODE {
+        return Ok(simulation::playlist_videos(channel_id));
+    }
+    fetch_feed(&feed_url(channel_id)).await
+}
+
+/// Fails on error statuses and on responses that are no feed, like a consent page
+async fn fetch_feed(url: &str) -> Result<Vec<PlaylistVideo>> {
+    let xml = youtube_api::get_text(url).await?;
+    parse_feed(&xml)
+}
+
+/// Video ids, publish times and channels of the `<entry>` elements of a channel feed
+pub fn parse_feed(xml: &str) -> Result<Vec<PlaylistVideo>> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut videos = Vec::new();
+    let mut in_entry = false;
+    let mut current_element: Vec<u8> = Vec::new();
+    let mut video_id: Option<String> = None;
+    let mut channel_id: Option<String> = None;
+    let mut published_at = 0;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) => {
+                let name = element.name().as_ref().to_vec();
+                if name == b"entry" {
+                    in_entry = true;
+                    video_id = None;
+                    channel_id = None;
+                    published_at = 0;
+                }
+                current_element = name;
+            }
+            Event::Text(text) if in_entry => {
+                let text = text.unescape()?;
+                match current_element.as_slice() {
+                    b"yt:videoId" => video_id = Some(text.trim().to_string()),
+                    b"yt:channelId" => channel_id = Some(text.trim().to_string()),
+                    b"published" => {
+                        published_at = chrono::DateTime::parse_from_rfc3339(text.trim())
+                            .map(|time| time.timestamp())
+                            .unwrap_or(0);
+                    }
+                    _ => {}
+                }
+            }
+            Event::End(element) => {
+                if element.name().as_ref() == b"entry" {
+                    in_entry = false;
+                    if let Some(video_id) = video_id.take().filter(|id| !id.is_empty()) {
+                        videos.push(PlaylistVideo {
+                            video_id,
+                            published_at,
+                            channel_id: channel_id.take(),
+                        });
+                    }
+                }
+                current_element.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if videos.is_empty() && !xml.contains("<feed") {
+        return Err(anyhow::anyhow!("Response is not a channel feed"));
+    }
+    Ok(videos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const CHANNEL_ID: &str = "UCTeLqJq1mXUX5WWoNXLmOIA";
+    /// A channel feed as YouTube serves it, shortened to three entries
+    const SAMPLE_FEED: &str = include_str!("testdata/channel_feed.xml");
+
+    fn summary(videos: &[PlaylistVideo]) -> Vec<(&str, i64, Option<&str>)> {
+        videos
+            .iter()
+            .map(|video| {
+                (
+                    video.video_id.as_str(),
+                    video.published_at,
+                    video.channel_id.as_deref(),
+                )
+            })
+            .collect()
+    }
+
+    async fn serve_feed(response: ResponseTemplate) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/feeds/videos.xml"))
+            .respond_with(response)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[test]
+    fn parses_the_entries_of_a_channel_feed() {
+        let videos = parse_feed(SAMPLE_FEED).unwrap();
+        assert_eq!(
+            summary(&videos),
+            [
+                ("aaaaaaaaaaa", 1_714_669_206, Some(CHANNEL_ID)),
+                ("bbbbbbbbbbb", 1_714_163_400, Some(CHANNEL_ID)),
+                ("c-_ccccccc1", 1_713_607_200, Some(CHANNEL_ID)),
+            ]
+        );
+    }
+
+    #[test]
+    fn channel_without_uploads_has_an_empty_feed() {
+        let feed = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns:yt="http://www.youtube.com/xml/schemas/2015" xmlns="http://www.w3.org/2005/Atom">
+ <yt:channelId>UCTeLqJq1mXUX5WWoNXLmOIA</yt:channelId>
+ <title>Empty</title>
+</feed>"#;
+        assert!(parse_feed(feed).unwrap().is_empty());
+    }
+
+    #[test]
+    fn entries_without_a_video_id_are_skipped() {
+        let feed = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+ <entry><title>No id</title><published>not a date</published></entry>
+ <entry><yt:videoId>aaaaaaaaaaa</yt:videoId><published>not a date</published></entry>
+</feed>"#;
+        assert_eq!(
+            summary(&parse_feed(feed).unwrap()),
+            [("aaaaaaaaaaa", 0, None)]
+        );
+    }
+
+    #[test]
+    fn responses_that_are_no_feed_are_rejected() {
+        assert!(
+            parse_feed("<!DOCTYPE html><html><body>Before you continue</body></html>").is_err()
+        );
+        assert!(parse_feed("").is_err());
+        assert!(parse_feed("<feed><entry><yt:videoId>a</entry></feed>").is_err());
+    }
+
+    #[rocket::async_test]
+    async fn fetches_and_parses_the_feed() {
+        let server = serve_feed(
+            ResponseTemplate::new(200).set_body_raw(SAMPLE_FEED, "application/atom+xml"),
+        )
+        .await;
+        let url = format!("{}/feeds/videos.xml?channel_id={CHANNEL_ID}", server.uri());
+
+        assert_eq!(fetch_feed(&url).await.unwrap().len(), 3);
+    }
+
+    #[rocket::async_test]
+    async fn failed_fetches_are_errors() {
+        let server = serve_feed(ResponseTemplate::new(404)).await;
+        let url = format!("{}/feeds/videos.xml?channel_id={CHANNEL_ID}", server.uri());
+        let error = fetch_feed(&url).await.unwrap_err();
+        assert!(error.to_string().contains("404"), "{error}");
+
+        let server = serve_feed(
+            ResponseTemplate::new(200)
+                .set_body_raw("<html><body>Before you continue</body></html>", "text/html"),
+        )
+        .await;
+        let url = format!("{}/feeds/videos.xml?channel_id={CHANNEL_ID}", server.uri());
+        let error = fetch_feed(&url).await.unwrap_err();
+        assert_eq!(error.to_string(), "Response is not a channel feed");
+    }
+}
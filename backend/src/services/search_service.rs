@@ -1,10 +1,19 @@
-use crate::models::{Caption, SearchResponse, SearchResult};
+use crate::config::{INDEX_NAMES, MAX_CAPTION_CHARS, SEARCH_DEADLINE_MS};
+use crate::models::{
+    caption_segment, Caption, RankedVideo, RelevanceConfig, SearchResponse, SearchResult,
+};
+use crate::services::{stemming, synonym_service};
 use crate::utils;
 use anyhow::{Context, Result};
 use elasticsearch::{Elasticsearch, SearchParts};
-use log::{debug, info};
+use futures::stream::{self, StreamExt};
+use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value};
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
 /// Fragmenting
 const DEFAULT_FRAGMENT_SIZE: usize = 400;
@@ -16,19 +25,166 @@ const DEFAULT_NO_MATCH_SIZE: usize = 250;
 const DEFAULT_NEIGHBORS_BEFORE: usize = 2;
 const DEFAULT_NEIGHBORS_AFTER: usize = 2;
 const MAX_COMBINED_CHARS: usize = 800;
+/// Per-video window queries in flight while enriching a page
+const NEIGHBOR_FETCH_CONCURRENCY: usize = 4;
+/// Largest window a single query returns, Elasticsearch's default result window
+const MAX_NEIGHBOR_WINDOW_SIZE: usize = 10000;
+
+/// Matching videos the pagination aggregation collects, deeper pages are unreachable
+const MAX_AGGREGATED_VIDEOS: usize = 10000;
+
+/// Warning of a search whose metadata-based sort fell back to relevance
+pub const METADATA_UNAVAILABLE_WARNING: &str = "metadata unavailable";
+/// Warning of a search that ran out of time before stitching the neighbor context
+pub const CONTEXT_OMITTED_WARNING: &str = "context omitted due to timeout";
+
+/// Bounds of the search deadline an admin can set
+pub const MIN_SEARCH_DEADLINE: Duration = Duration::from_millis(500);
+pub const MAX_SEARCH_DEADLINE: Duration = Duration::from_secs(60);
+
+lazy_static::lazy_static! {
+    /// Overall budget of a search in millis, starts at `SEARCH_DEADLINE_MS`
+    static ref DEADLINE_MS: AtomicU64 = AtomicU64::new(*SEARCH_DEADLINE_MS);
+}
+
+pub fn search_deadline() -> Duration {
+    Duration::from_millis(DEADLINE_MS.load(Ordering::Relaxed))
+}
+
+/// The deadline an admin asked for, within the allowed bounds
+fn clamp_search_deadline(deadline: Duration) -> Duration {
+    deadline.clamp(MIN_SEARCH_DEADLINE, MAX_SEARCH_DEADLINE)
+}
+
+/// Changes the budget of searches started from now on, clamped to the allowed bounds
+pub fn set_search_deadline(deadline: Duration) -> Duration {
+    let deadline = clamp_search_deadline(deadline);
+    DEADLINE_MS.store(deadline.as_millis() as u64, Ordering::Relaxed);
+    info!("Search deadline set to {deadline:?}");
+    deadline
+}
+
+/// A search whose primary query didn't finish within the deadline
+#[derive(Debug)]
+pub struct SearchTimeout {
+    pub after: Duration,
+}
+
+impl fmt::Display for SearchTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Search exceeded its deadline of {:?}", self.after)
+    }
+}
+
+impl std::error::Error for SearchTimeout {}
+
+pub fn is_search_timeout(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| cause.is::<SearchTimeout>())
+}
+
+/// Time budget of one search, shared by all of its stages
+struct Deadline {
+    budget: Duration,
+    at: Instant,
+}
+
+impl Deadline {
+    fn start(budget: Duration) -> Self {
+        Deadline {
+            budget,
+            at: Instant::now() + budget,
+        }
+    }
+
+    fn remaining(&self) -> Duration {
+        self.at.saturating_duration_since(Instant::now())
+    }
+
+    /// Runs a stage in the remaining time, fails with `SearchTimeout` beyond it
+    async fn run<T>(&self, stage: impl Future<Output = Result<T>>) -> Result<T> {
+        match tokio::time::timeout(self.remaining(), stage).await {
+            Ok(result) => result,
+            Err(_) => Err(SearchTimeout { after: self.budget }.into()),
+        }
+    }
+}
 
 /// HTML tags for highlighting
 const PRE_TAG: &str = "<strong>";
 const POST_TAG: &str = "</strong>";
 
+/// Stand-in markers while highlighting is disabled, removed before responding
+const DISABLED_PRE_TAG: &str = "\u{E000}";
+const DISABLED_POST_TAG: &str = "\u{E001}";
+
 #[derive(Debug, Clone)]
 pub struct SearchOptions {
     pub search_type: SearchType,
     pub fuzzy_distance: Option<String>, // "AUTO", "1", "2", etc.
+    pub exact_terms: bool,              // skip the stemmed sub-field entirely
+    pub highlight_tags: HighlightTags,
+    pub plain_text: bool, // also return the snippet without markup as `snippet_text`
+    pub collapse: bool,   // only the best caption per video
+    pub exclude_auto: bool, // only manually uploaded captions
+    pub language: Option<String>, // only captions in this language, stemmed by its rules
+    pub exclude_channels: Vec<String>, // no captions of these channels
+    pub channels: Vec<String>, // only videos of these channels, all if empty
+    relevance: RelevanceConfig, // passed to each search explicitly
+    pub granularity: Granularity,
     pub sort_by: SortBy,
     pub sort_order: SortOrder,
 }
 
+/// Markers placed around matches in snippets. Empty markers disable highlighting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HighlightTags {
+    pub pre: String,
+    pub post: String,
+}
+
+impl Default for HighlightTags {
+    fn default() -> Self {
+        HighlightTags {
+            pre: PRE_TAG.to_string(),
+            post: POST_TAG.to_string(),
+        }
+    }
+}
+
+impl HighlightTags {
+    pub fn none() -> Self {
+        HighlightTags {
+            pre: String::new(),
+            post: String::new(),
+        }
+    }
+
+    fn is_disabled(&self) -> bool {
+        self.pre.is_empty() && self.post.is_empty()
+    }
+
+    /// Tags used while stitching and truncating. Truncation needs to find the hit even
+    /// when highlighting is disabled, so stand-in markers are used in that case.
+    fn working(&self) -> (&str, &str) {
+        if self.is_disabled() {
+            (DISABLED_PRE_TAG, DISABLED_POST_TAG)
+        } else {
+            (&self.pre, &self.post)
+        }
+    }
+
+    /// Removes the stand-in markers again, a no-op for real tags
+    fn finish(&self, snippet: String) -> String {
+        if self.is_disabled() {
+            snippet
+                .replace(DISABLED_PRE_TAG, "")
+                .replace(DISABLED_POST_TAG, "")
+        } else {
+            snippet
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SortBy {
     Relevance, // default sort after search-score
@@ -37,6 +193,7 @@ pub enum SortBy {
     Views,
     Likes,
     CaptionMatches, // amount of matches per video
+    ViewVelocity,   // views gained between the two most recent crawls
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +202,23 @@ pub enum SortOrder {
     Desc,
 }
 
+/// The documents a search matches: single captions or captions stitched into sentences
+#[derive(Debug, Clone, Default)]
+pub enum Granularity {
+    #[default]
+    Caption,
+    Sentence, // finds phrases spanning caption boundaries
+}
+
+impl Granularity {
+    fn index(&self) -> &'static str {
+        match self {
+            Granularity::Caption => INDEX_NAMES.captions,
+            Granularity::Sentence => INDEX_NAMES.sentences,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum SearchType {
     Natural, // Exact phrase + basic stemming
@@ -58,6 +232,16 @@ impl SearchOptions {
             SearchOptions {
                 search_type: SearchType::Natural,
                 fuzzy_distance: None,
+                exact_terms: false,
+                highlight_tags: HighlightTags::default(),
+                plain_text: false,
+                collapse: false,
+                exclude_auto: false,
+                language: None,
+                exclude_channels: Vec::new(),
+                channels: Vec::new(),
+                relevance: RelevanceConfig::default(),
+                granularity: Granularity::default(),
                 sort_by: sort_by.clone(),
                 sort_order: sort_order.clone(),
             }
@@ -65,6 +249,16 @@ impl SearchOptions {
         Self {
             search_type: SearchType::Natural,
             fuzzy_distance: None,
+            exact_terms: false,
+            highlight_tags: HighlightTags::default(),
+            plain_text: false,
+            collapse: false,
+            exclude_auto: false,
+            language: None,
+            exclude_channels: Vec::new(),
+            channels: Vec::new(),
+            relevance: RelevanceConfig::default(),
+            granularity: Granularity::default(),
             sort_by,
             sort_order,
         }
@@ -76,6 +270,16 @@ impl SearchOptions {
             SearchOptions {
                 search_type: SearchType::Wide,
                 fuzzy_distance: Some("AUTO".to_string()),
+                exact_terms: false,
+                highlight_tags: HighlightTags::default(),
+                plain_text: false,
+                collapse: false,
+                exclude_auto: false,
+                language: None,
+                exclude_channels: Vec::new(),
+                channels: Vec::new(),
+                relevance: RelevanceConfig::default(),
+                granularity: Granularity::default(),
                 sort_by: sort_by.clone(),
                 sort_order: sort_order.clone(),
             }
@@ -83,62 +287,198 @@ impl SearchOptions {
         Self {
             search_type: SearchType::Wide,
             fuzzy_distance: Some("AUTO".to_string()),
+            exact_terms: false,
+            highlight_tags: HighlightTags::default(),
+            plain_text: false,
+            collapse: false,
+            exclude_auto: false,
+            language: None,
+            exclude_channels: Vec::new(),
+            channels: Vec::new(),
+            relevance: RelevanceConfig::default(),
+            granularity: Granularity::default(),
             sort_by,
             sort_order,
         }
     }
+
+    pub fn with_exact_terms(mut self, exact_terms: bool) -> Self {
+        self.exact_terms = exact_terms;
+        self
+    }
+
+    pub fn with_highlight_tags(mut self, highlight_tags: HighlightTags) -> Self {
+        self.highlight_tags = highlight_tags;
+        self
+    }
+
+    /// Strips all markup from snippets and additionally returns them as `snippet_text`
+    pub fn with_plain_text(mut self, plain_text: bool) -> Self {
+        if plain_text {
+            self.highlight_tags = HighlightTags::none();
+        }
+        self.plain_text = plain_text;
+        self
+    }
+
+    pub fn with_collapse(mut self, collapse: bool) -> Self {
+        self.collapse = collapse;
+        self
+    }
+
+    pub fn with_exclude_auto(mut self, exclude_auto: bool) -> Self {
+        self.exclude_auto = exclude_auto;
+        self
+    }
+
+    pub fn with_language(mut self, language: Option<String>) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Leaves out all videos of these channels
+    pub fn with_exclude_channels(mut self, exclude_channels: Vec<String>) -> Self {
+        self.exclude_channels = exclude_channels;
+        self
+    }
+
+    /// Only finds videos of these channels
+    pub fn with_channels(mut self, channels: Vec<String>) -> Self {
+        self.channels = channels;
+        self
+    }
+
+    pub fn with_granularity(mut self, granularity: Granularity) -> Self {
+        self.granularity = granularity;
+        self
+    }
+
+    /// The options with the relevance config of the search
+    fn with_relevance(&self, relevance: &RelevanceConfig) -> SearchOptions {
+        let mut options = self.clone();
+        options.relevance = relevance.clone();
+        options
+    }
 }
 
+/// One page of the videos matching a query, scored with the boosts of `relevance`
 pub async fn search_captions_with_pagination(
     es_client: &Elasticsearch,
     query_string: &str,
     page: usize,
     page_size: usize,
     options: &SearchOptions,
+    relevance: &RelevanceConfig,
 ) -> Result<SearchResponse> {
-    let from = page * page_size;
+    let deadline = Deadline::start(search_deadline());
+    search_within_deadline(
+        es_client,
+        query_string,
+        page,
+        page_size,
+        options,
+        relevance,
+        &deadline,
+    )
+    .await
+}
+
+async fn search_within_deadline(
+    es_client: &Elasticsearch,
+    query_string: &str,
+    page: usize,
+    page_size: usize,
+    options: &SearchOptions,
+    relevance: &RelevanceConfig,
+    deadline: &Deadline,
+) -> Result<SearchResponse> {
+    let options = &options.with_relevance(relevance);
 
     // Total counts without pagination
-    let total_counts = get_total_counts(es_client, query_string, options).await?;
+    let total_counts = deadline
+        .run(get_total_counts(es_client, query_string, options))
+        .await?;
+
+    let total_pages = (total_counts.0 as f32 / page_size as f32).ceil() as usize;
+    let (page, clamped) = clamp_page(page, page_size, total_pages);
+    let from = page * page_size;
 
-    let video_ids =
-        get_paginated_video_ids(es_client, query_string, from, page_size, options).await?;
+    let mut warnings = Vec::new();
+    let videos: Vec<(String, i64)> = deadline
+        .run(get_paginated_video_ids(
+            es_client,
+            query_string,
+            from,
+            page_size,
+            options,
+            &mut warnings,
+        ))
+        .await?
+        .into_iter()
+        .map(|data| (data.video_id, data.match_count))
+        .collect();
 
     // Get detailed results for these videos
     let mut results = Vec::new();
-    for video_id in video_ids.iter() {
-        let video_results =
-            get_all_captions_for_video(es_client, query_string, video_id, options).await?;
-        results.extend(video_results);
+    if options.collapse {
+        // One query for the whole page instead of one per video
+        results = deadline
+            .run(get_best_caption_per_video(
+                es_client,
+                query_string,
+                &videos,
+                options,
+            ))
+            .await?;
+    } else {
+        for (video_id, _) in videos.iter() {
+            let video_results = deadline
+                .run(get_all_captions_for_video(
+                    es_client,
+                    query_string,
+                    video_id,
+                    options,
+                ))
+                .await?;
+            results.extend(video_results);
+        }
     }
 
     // Step 3: Process each result with neighbors
-    for res in results.iter_mut() {
-        let (prev, next) = fetch_neighbors_for_hit(
+    let query_terms = tokenize_query_terms(query_string);
+    let (pre_tag, post_tag) = options.highlight_tags.working();
+    let anchors: Vec<NeighborAnchor> = results
+        .iter()
+        .map(|res| NeighborAnchor {
+            start_time: res.start_time,
+            end_time: res.end_time,
+            text: normalize_for_comparison(&res.snippet_html, pre_tag, post_tag),
+        })
+        .collect();
+    // The results are complete without their context, a late enrichment only drops it
+    let neighbors = match tokio::time::timeout(
+        deadline.remaining(),
+        fetch_neighbors_for_page(
             es_client,
-            &res.video_id,
-            res.start_time,
-            res.end_time,
+            &results,
+            &anchors,
             DEFAULT_NEIGHBORS_BEFORE,
             DEFAULT_NEIGHBORS_AFTER,
-        )
-        .await
-        .unwrap_or_default();
-
-        // Build neighbor text blocks
-        let prev_text = join_neighbor_text(&prev);
-        let next_text = join_neighbor_text(&next);
-
-        // Combine with improved sentence awareness
-        let combined = stitch_with_neighbors_enhanced(&prev_text, &res.snippet_html, &next_text);
-
-        // Trim to a max length while keeping the highlight in view
-        res.snippet_html =
-            truncate_around_highlight(&combined, MAX_COMBINED_CHARS, PRE_TAG, POST_TAG);
+        ),
+    )
+    .await
+    {
+        Ok(neighbors) => neighbors,
+        Err(_) => {
+            warn!("Search deadline passed, omitting the context of \"{query_string}\"");
+            warnings.push(CONTEXT_OMITTED_WARNING.to_string());
+            vec![(Vec::new(), Vec::new()); results.len()]
+        }
+    };
+    for (res, (prev, next)) in results.iter_mut().zip(neighbors) {
+        stitch_context(res, &prev, &next, &query_terms, options);
     }
 
-    let total_pages = (total_counts.0 as f32 / page_size as f32).ceil() as usize;
-
     Ok(SearchResponse {
         results,
         total_videos: total_counts.0,
@@ -146,36 +486,204 @@ pub async fn search_captions_with_pagination(
         page,
         page_size,
         total_pages,
+        clamped,
+        warnings,
     })
 }
 
+/// Replaces the snippet of a hit with its caption stitched between the neighbors
+fn stitch_context(
+    res: &mut SearchResult,
+    prev: &[Caption],
+    next: &[Caption],
+    query_terms: &[String],
+    options: &SearchOptions,
+) {
+    let (pre_tag, post_tag) = options.highlight_tags.working();
+
+    // Build neighbor text blocks
+    let prev_text = join_neighbor_text(prev, true);
+    let next_text = join_neighbor_text(next, false);
+
+    // Combine with improved sentence awareness
+    let combined = stitch_with_neighbors_enhanced(&prev_text, &res.snippet_html, &next_text);
+
+    // Trim to a max length while keeping the highlight in view
+    let truncated = truncate_around_highlight(&combined, MAX_COMBINED_CHARS, pre_tag, post_tag);
+
+    // Mark query terms in the neighbor text as well. Done after truncating so the
+    // window stays centered on the ES highlight of the anchor caption.
+    let highlighted = highlight_query_terms(&truncated, query_terms, pre_tag, post_tag);
+    res.snippet_html = options.highlight_tags.finish(highlighted);
+
+    if options.plain_text {
+        res.snippet_text = Some(res.snippet_html.clone());
+    }
+}
+
+/// Up to `size` random captions in a stable order for `seed`, matching `query` if
+/// given and filtered by `options` like a search. Each hit has its context stitched in
+/// like a search result. The same seed picks the same captions while the index
+/// doesn't change.
+pub async fn random_captions(
+    es_client: &Elasticsearch,
+    query: Option<&str>,
+    size: usize,
+    seed: u64,
+    options: &SearchOptions,
+) -> Result<Vec<SearchResult>> {
+    let base_query = match query {
+        Some(query) => build_main_query_by_type(query, options),
+        None => {
+            let mut filter = vec![];
+            if options.exclude_auto {
+                filter.push(json!({ "term": { "is_generated": false } }));
+            }
+            if let Some(language) = &options.language {
+                filter.push(json!({ "term": { "language": language } }));
+            }
+            if !options.channels.is_empty() {
+                filter.push(json!({ "terms": { "channel_id": options.channels } }));
+            }
+            json!({
+                "bool": {
+                    "filter": filter,
+                    "must_not": [{ "term": { "hidden": true } }]
+                }
+            })
+        }
+    };
+
+    let mut body = json!({
+        "size": size,
+        "query": {
+            "function_score": {
+                "query": base_query,
+                "functions": [{ "random_score": { "seed": seed, "field": "_seq_no" } }],
+                "boost_mode": "replace"
+            }
+        },
+        "_source": ["video_id", "text", "start_time", "end_time"]
+    });
+    if let Some(query) = query {
+        body["highlight"] = build_highlight(query, options);
+    }
+
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.captions]))
+        .body(body)
+        .send()
+        .await
+        .context("Elasticsearch random caption request failed")?
+        .json::<Value>()
+        .await
+        .context("Failed to parse Elasticsearch random caption response as JSON")?;
+    let mut results = process_search_response(response).await;
+
+    let (pre_tag, post_tag) = options.highlight_tags.working();
+    let anchors: Vec<NeighborAnchor> = results
+        .iter()
+        .map(|res| NeighborAnchor {
+            start_time: res.start_time,
+            end_time: res.end_time,
+            text: normalize_for_comparison(&res.snippet_html, pre_tag, post_tag),
+        })
+        .collect();
+    let neighbors = fetch_neighbors_for_page(
+        es_client,
+        &results,
+        &anchors,
+        DEFAULT_NEIGHBORS_BEFORE,
+        DEFAULT_NEIGHBORS_AFTER,
+    )
+    .await;
+    let query_terms = query.map(tokenize_query_terms).unwrap_or_default();
+    for (res, (prev, next)) in results.iter_mut().zip(neighbors) {
+        stitch_context(res, &prev, &next, &query_terms, options);
+    }
+    Ok(results)
+}
+
+/// The top `size` videos of a query in the order of `options`, with the scores they were
+/// ranked by. Titles are left for the caller.
+pub async fn rank_videos(
+    es_client: &Elasticsearch,
+    query_string: &str,
+    size: usize,
+    options: &SearchOptions,
+    relevance: &RelevanceConfig,
+) -> Result<Vec<RankedVideo>> {
+    let options = options.with_relevance(relevance);
+    let mut warnings = Vec::new();
+    let videos =
+        get_paginated_video_ids(es_client, query_string, 0, size, &options, &mut warnings).await?;
+    Ok(videos
+        .into_iter()
+        .map(|data| RankedVideo {
+            video_id: data.video_id,
+            title: None,
+            avg_score: data.avg_score,
+            max_score: data.max_score,
+            match_count: data.match_count,
+        })
+        .collect())
+}
+
+/// Bounds `page` to the last existing page and to the videos the terms aggregation of
+/// `get_paginated_video_ids` can reach. Returns the page and whether it was changed.
+fn clamp_page(page: usize, page_size: usize, total_pages: usize) -> (usize, bool) {
+    let reachable_pages = MAX_AGGREGATED_VIDEOS / page_size.max(1);
+    let last_page = total_pages.min(reachable_pages).saturating_sub(1);
+    if page > last_page {
+        (last_page, true)
+    } else {
+        (page, false)
+    }
+}
+
+/// Ids of up to `limit` videos with captions matching the query, most matches first
+pub async fn matching_video_ids(
+    es_client: &Elasticsearch,
+    query_string: &str,
+    options: &SearchOptions,
+    limit: usize,
+) -> Result<Vec<String>> {
+    let response = es_client
+        .search(SearchParts::Index(&[options.granularity.index()]))
+        .body(json!({
+            "size": 0,
+            "query": build_main_query_by_type(query_string, options),
+            "aggs": {
+                "unique_videos": { "terms": { "field": "video_id", "size": limit } }
+            }
+        }))
+        .send()
+        .await
+        .context("Elasticsearch aggregation request failed")?
+        .json::<Value>()
+        .await?;
+
+    Ok(response["aggregations"]["unique_videos"]["buckets"]
+        .as_array()
+        .map(|buckets| {
+            buckets
+                .iter()
+                .filter_map(|bucket| bucket["key"].as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
 /// Get total counts of matching videos and captions
 async fn get_total_counts(
     es_client: &Elasticsearch,
     query_string: &str,
     options: &SearchOptions,
 ) -> Result<(usize, usize)> {
-    let main_query = build_main_query_by_type(query_string, options);
-
-    let query_body = json!({
-        "size": 0,
-        "query": main_query,
-        "aggs": {
-            "unique_videos": {
-                "cardinality": {
-                    "field": "video_id"
-                }
-            },
-            "total_captions": {
-                "value_count": {
-                    "field": "video_id"
-                }
-            }
-        }
-    });
+    let query_body = build_total_counts_body(query_string, options);
 
     let response = es_client
-        .search(SearchParts::Index(&["youtube_captions"]))
+        .search(SearchParts::Index(&[options.granularity.index()]))
         .body(query_body)
         .send()
         .await
@@ -194,37 +702,42 @@ async fn get_total_counts(
     Ok((total_videos, total_captions))
 }
 
-/// Get unique video IDs with video-level pagination and deterministic sorting
-async fn get_paginated_video_ids(
-    es_client: &Elasticsearch,
-    query_string: &str,
-    from: usize,
-    size: usize,
-    options: &SearchOptions,
-) -> Result<Vec<String>> {
+fn build_total_counts_body(query_string: &str, options: &SearchOptions) -> Value {
     let main_query = build_main_query_by_type(query_string, options);
 
-    let query_body = json!({
+    json!({
         "size": 0,
         "query": main_query,
         "aggs": {
             "unique_videos": {
-                "terms": {
-                    "field": "video_id",
-                    "size": 10000,  // Get all matching videos first
-                    "order": { "_key": "asc" }  // Temporary ordering for consistency
-                },
-                "aggs": {
-                    "max_score": { "max": { "script": "_score" } },
-                    "avg_score": { "avg": { "script": "_score" } },
-                    "match_count": { "value_count": { "field": "video_id" } }
+                "cardinality": {
+                    "field": "video_id"
+                }
+            },
+            "total_captions": {
+                "value_count": {
+                    "field": "video_id"
                 }
             }
         }
-    });
+    })
+}
+
+/// Get unique video IDs with their caption match count, using video-level pagination
+/// and deterministic sorting. Sorts by relevance and adds a warning if a metadata-based
+/// sort can't load the video metadata.
+async fn get_paginated_video_ids(
+    es_client: &Elasticsearch,
+    query_string: &str,
+    from: usize,
+    size: usize,
+    options: &SearchOptions,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<VideoSortData>> {
+    let query_body = build_video_aggregation_body(query_string, options);
 
     let response = es_client
-        .search(SearchParts::Index(&["youtube_captions"]))
+        .search(SearchParts::Index(&[options.granularity.index()]))
         .body(query_body)
         .send()
         .await
@@ -255,16 +768,28 @@ async fn get_paginated_video_ids(
                 duration: 0,
                 views: 0.0,
                 likes: 0.0,
+                view_velocity: None,
             })
         })
         .collect();
 
     // If we need video metadata for sorting, fetch it from youtube_videos index
+    let mut sort_by = &options.sort_by;
+    let mut sort_order = &options.sort_order;
     if matches!(
         options.sort_by,
-        SortBy::UploadDate | SortBy::Duration | SortBy::Views | SortBy::Likes
+        SortBy::UploadDate
+            | SortBy::Duration
+            | SortBy::Views
+            | SortBy::Likes
+            | SortBy::ViewVelocity
     ) {
-        fetch_video_metadata_for_sorting(es_client, &mut video_data).await?;
+        if let Err(e) = fetch_video_metadata_for_sorting(es_client, &mut video_data).await {
+            warn!("Video metadata unavailable, sorting by relevance instead: {e:?}");
+            warnings.push(METADATA_UNAVAILABLE_WARNING.to_string());
+            sort_by = &SortBy::Relevance;
+            sort_order = &SortOrder::Desc;
+        }
     }
 
     for data in &mut video_data {
@@ -274,10 +799,10 @@ async fn get_paginated_video_ids(
 
     // Sort based on the specified criteria and order
     video_data.sort_by(|a, b| {
-        let ordering = match options.sort_by {
+        match sort_by {
             SortBy::Relevance => {
                 // Primary: avg_score, Secondary: video_id (for deterministic results)
-                utils::compare_with_order_float(a.avg_score, b.avg_score, &options.sort_order)
+                utils::compare_with_order_float(a.avg_score, b.avg_score, sort_order)
                     .then_with(|| a.video_id.cmp(&b.video_id))
             }
             SortBy::CaptionMatches => {
@@ -285,7 +810,7 @@ async fn get_paginated_video_ids(
                 utils::compare_with_order_float(
                     a.match_count as f64,
                     b.match_count as f64,
-                    &options.sort_order,
+                    sort_order,
                 )
                 .then_with(|| {
                     utils::compare_with_order_float(a.avg_score, b.avg_score, &SortOrder::Desc)
@@ -294,7 +819,7 @@ async fn get_paginated_video_ids(
             }
             SortBy::UploadDate => {
                 // Primary: upload_date, Secondary: avg_score, Tertiary: video_id
-                utils::compare_with_order_int(a.upload_date, b.upload_date, &options.sort_order)
+                utils::compare_with_order_int(a.upload_date, b.upload_date, sort_order)
                     .then_with(|| {
                         utils::compare_with_order_float(a.avg_score, b.avg_score, &SortOrder::Desc)
                     })
@@ -302,7 +827,7 @@ async fn get_paginated_video_ids(
             }
             SortBy::Duration => {
                 // Primary: duration, Secondary: avg_score, Tertiary: video_id
-                utils::compare_with_order_int(a.duration, b.duration, &options.sort_order)
+                utils::compare_with_order_int(a.duration, b.duration, sort_order)
                     .then_with(|| {
                         utils::compare_with_order_float(a.avg_score, b.avg_score, &SortOrder::Desc)
                     })
@@ -310,7 +835,7 @@ async fn get_paginated_video_ids(
             }
             SortBy::Views => {
                 // Primary: views, Secondary: avg_score, Tertiary: video_id
-                utils::compare_with_order_float(a.views, b.views, &options.sort_order)
+                utils::compare_with_order_float(a.views, b.views, sort_order)
                     .then_with(|| {
                         utils::compare_with_order_float(a.avg_score, b.avg_score, &SortOrder::Desc)
                     })
@@ -318,32 +843,39 @@ async fn get_paginated_video_ids(
             }
             SortBy::Likes => {
                 // Primary: likes, Secondary: avg_score, Tertiary: video_id
-                utils::compare_with_order_float(a.likes, b.likes, &options.sort_order)
+                utils::compare_with_order_float(a.likes, b.likes, sort_order)
                     .then_with(|| {
                         utils::compare_with_order_float(a.avg_score, b.avg_score, &SortOrder::Desc)
                     })
                     .then_with(|| a.video_id.cmp(&b.video_id))
             }
-        };
-
-        ordering
+            SortBy::ViewVelocity => {
+                // Videos without crawl history always go last, regardless of order
+                // Primary: view_velocity, Secondary: avg_score, Tertiary: video_id
+                match (a.view_velocity, b.view_velocity) {
+                    (Some(a_velocity), Some(b_velocity)) => {
+                        utils::compare_with_order_int(a_velocity, b_velocity, sort_order)
+                    }
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                }
+                .then_with(|| {
+                    utils::compare_with_order_float(a.avg_score, b.avg_score, &SortOrder::Desc)
+                })
+                .then_with(|| a.video_id.cmp(&b.video_id))
+            }
+        }
     });
 
     // Apply pagination
-    let video_ids: Vec<String> = video_data
-        .into_iter()
-        .skip(from)
-        .take(size)
-        .map(|data| data.video_id)
-        .collect();
-
-    Ok(video_ids)
+    Ok(video_data.into_iter().skip(from).take(size).collect())
 }
 
 /// Fetch video metadata from youtube_videos index for sorting purposes
 async fn fetch_video_metadata_for_sorting(
     es_client: &Elasticsearch,
-    video_data: &mut Vec<VideoSortData>,
+    video_data: &mut [VideoSortData],
 ) -> Result<()> {
     // Extract video IDs
     let video_ids: Vec<&String> = video_data.iter().map(|v| &v.video_id).collect();
@@ -352,7 +884,7 @@ async fn fetch_video_metadata_for_sorting(
     let mut docs = Vec::new();
     for video_id in video_ids {
         docs.push(json!({
-            "_index": "youtube_videos",
+            "_index": INDEX_NAMES.videos,
             "_id": video_id
         }));
     }
@@ -366,9 +898,22 @@ async fn fetch_video_metadata_for_sorting(
         .body(mget_body)
         .send()
         .await
-        .context("Failed to fetch video metadata")?
-        .json::<Value>()
-        .await?;
+        .context("Failed to fetch video metadata")?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Video metadata request failed: {}",
+            response.status_code()
+        ));
+    }
+    let response = response.json::<Value>().await?;
+
+    // A missing or red index fails per document, not the whole request
+    if let Some(error) = response["docs"]
+        .as_array()
+        .and_then(|docs| docs.iter().find_map(|doc| doc.get("error")))
+    {
+        return Err(anyhow::anyhow!("Video metadata unavailable: {error}"));
+    }
 
     // Parse the metadata and update video_data
     if let Some(docs_array) = response.get("docs").and_then(|d| d.as_array()) {
@@ -390,6 +935,10 @@ async fn fetch_video_metadata_for_sorting(
                         source.get("views").and_then(|v| v.as_i64()).unwrap_or(0) as f64;
                     video_entry.likes =
                         source.get("likes").and_then(|l| l.as_i64()).unwrap_or(0) as f64;
+                    video_entry.view_velocity = source
+                        .get("crawl_history")
+                        .and_then(|h| h.as_array())
+                        .and_then(|history| view_velocity_from_history(history));
                 }
             }
         }
@@ -398,6 +947,18 @@ async fn fetch_video_metadata_for_sorting(
     Ok(())
 }
 
+/// Views gained between the two most recent crawls. A single crawl counts as 0,
+/// no crawl history at all yields None.
+fn view_velocity_from_history(history: &[Value]) -> Option<i64> {
+    let views_at = |entry: &Value| entry.get("views").and_then(|v| v.as_i64()).unwrap_or(0);
+
+    match history {
+        [] => None,
+        [_] => Some(0),
+        [.., previous, latest] => Some(views_at(latest) - views_at(previous)),
+    }
+}
+
 #[derive(Debug)]
 struct VideoSortData {
     video_id: String,
@@ -408,6 +969,7 @@ struct VideoSortData {
     duration: i64,
     views: f64,
     likes: f64,
+    view_velocity: Option<i64>,
 }
 
 /// Get all matching captions for a specific video
@@ -437,26 +999,7 @@ async fn get_all_captions_for_video(
         "size": 10000,  // Large size to get all captions for this video
         "query": combined_query,
         "_source": ["video_id", "text", "start_time", "end_time"],
-        "highlight": {
-            "pre_tags": [PRE_TAG],
-            "post_tags": [POST_TAG],
-            "fields": {
-                "text": {
-                    "type": "unified",
-                    "number_of_fragments": DEFAULT_NUM_FRAGMENTS,
-                    "fragment_size": DEFAULT_FRAGMENT_SIZE,
-                    "order": "score",
-                    "boundary_scanner": "sentence",
-                    "boundary_chars": ".,!?;",
-                    "boundary_max_scan": DEFAULT_BOUNDARY_MAX_SCAN,
-                    "no_match_size": DEFAULT_NO_MATCH_SIZE,
-                    "highlight_query": main_query,
-                    "fragmenter": "simple",
-                    "max_analyzed_offset": 1000000
-                }
-            },
-            "require_field_match": true
-        },
+        "highlight": build_highlight(query_string, options),
         "sort": [
             { "_score": { "order": "desc" } },
             { "start_time": { "order": "asc" } }
@@ -464,7 +1007,7 @@ async fn get_all_captions_for_video(
     });
 
     let response = es_client
-        .search(SearchParts::Index(&["youtube_captions"]))
+        .search(SearchParts::Index(&[options.granularity.index()]))
         .body(query_body)
         .send()
         .await
@@ -477,28 +1020,54 @@ async fn get_all_captions_for_video(
     Ok(results)
 }
 
-fn build_main_query_by_type(query_string: &str, options: &SearchOptions) -> Value {
+/// Highlight settings shared by the per-video and the collapsed caption queries
+fn build_highlight(query_string: &str, options: &SearchOptions) -> Value {
+    let (pre_tag, post_tag) = options.highlight_tags.working();
+    let highlight_query = build_highlight_query(query_string, options);
+
+    json!({
+        "pre_tags": [pre_tag],
+        "post_tags": [post_tag],
+        "fields": {
+            "text": {
+                "type": "unified",
+                "number_of_fragments": DEFAULT_NUM_FRAGMENTS,
+                "fragment_size": DEFAULT_FRAGMENT_SIZE,
+                "order": "score",
+                "boundary_scanner": "sentence",
+                "boundary_chars": ".,!?;",
+                "boundary_max_scan": DEFAULT_BOUNDARY_MAX_SCAN,
+                "no_match_size": DEFAULT_NO_MATCH_SIZE,
+                "highlight_query": highlight_query,
+                "fragmenter": "simple",
+                "max_analyzed_offset": 1000000
+            }
+        },
+        "require_field_match": true
+    })
+}
+
+/// The terms the highlighter marks, only those of the positive part of the query. A
+/// caption the wide search found only through its fuzzy clauses may have matched on the
+/// stemmed field, so a fuzzy match on `text` with the same fuzziness makes sure the
+/// misspelled token itself gets wrapped instead of the caption falling back to
+/// unhighlighted text.
+fn build_highlight_query(query_string: &str, options: &SearchOptions) -> Value {
+    let operators = parse_query_operators(query_string);
+    let positive_query = json!({ "bool": { "must": build_positive_clauses(&operators, options) } });
     match options.search_type {
-        SearchType::Natural => {
+        SearchType::Natural => positive_query,
+        SearchType::Wide => {
+            let fuzzy_setting = options.fuzzy_distance.as_deref().unwrap_or("AUTO");
             json!({
                 "bool": {
                     "should": [
-                        // Exact phrase match (highest priority)
+                        positive_query,
                         {
-                            "match_phrase": {
+                            "match": {
                                 "text": {
-                                    "query": query_string,
-                                    "boost": 3.0
-                                }
-                            }
-                        },
-                        // Exact phrase match on stemmed field (for basic stemming)
-                        {
-                            "match_phrase": {
-                                "text.stemmed": {
-                                    "query": query_string,
-                                    "boost": 1.0,
-                                    "slop": 0  // No word reordering allowed
+                                    "query": operators.unquoted,
+                                    "fuzziness": fuzzy_setting
                                 }
                             }
                         }
@@ -507,9 +1076,310 @@ fn build_main_query_by_type(query_string: &str, options: &SearchOptions) -> Valu
                 }
             })
         }
+    }
+}
+
+/// The single best matching caption of every video, in the order of `videos`
+async fn get_best_caption_per_video(
+    es_client: &Elasticsearch,
+    query_string: &str,
+    videos: &[(String, i64)],
+    options: &SearchOptions,
+) -> Result<Vec<SearchResult>> {
+    if videos.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let main_query = build_main_query_by_type(query_string, options);
+    let video_ids: Vec<&str> = videos.iter().map(|(id, _)| id.as_str()).collect();
+
+    let query_body = json!({
+        "size": videos.len(),
+        "query": {
+            "bool": {
+                "must": [
+                    main_query,
+                    { "terms": { "video_id": video_ids } }
+                ]
+            }
+        },
+        "collapse": { "field": "video_id" },
+        "_source": ["video_id", "text", "start_time", "end_time"],
+        "highlight": build_highlight(query_string, options),
+        "sort": [
+            { "_score": { "order": "desc" } },
+            { "start_time": { "order": "asc" } }
+        ]
+    });
+
+    let response = es_client
+        .search(SearchParts::Index(&[options.granularity.index()]))
+        .body(query_body)
+        .send()
+        .await
+        .context("Elasticsearch collapsed captions request failed")?
+        .json::<Value>()
+        .await
+        .context("Failed to parse Elasticsearch collapsed captions response as JSON")?;
+
+    let mut best_hits = process_search_response(response).await;
+
+    // ES orders by score, the page order comes from the video sorting
+    let results = videos
+        .iter()
+        .filter_map(|(video_id, match_count)| {
+            let idx = best_hits.iter().position(|r| &r.video_id == video_id)?;
+            let mut result = best_hits.swap_remove(idx);
+            result.match_count = Some(*match_count);
+            Some(result)
+        })
+        .collect();
+
+    Ok(results)
+}
+
+/// A query split into its operators
+struct QueryOperators {
+    /// The positive part of the query, `+term`s without their '+' and phrases without
+    /// their quotes
+    text: String,
+    /// Positive words outside of quoted phrases
+    unquoted: String,
+    /// `"quoted phrases"`, which the wide search matches exactly
+    phrases: Vec<String>,
+    /// `+term`s, which additionally have to match the unstemmed field
+    exact_terms: Vec<String>,
+    /// `-term`s and `-"quoted phrases"`, no returned caption contains them
+    excluded: Vec<String>,
+}
+
+/// Splits the `"quoted phrase"`, `+term`, `-term` and `-"quoted phrase"` operators off
+/// the query. An unclosed quote of an excluded phrase runs to the end of the query, one
+/// of a positive phrase is kept as part of the word it starts. Operators only count at
+/// the start of a word: hyphenated words like `re-entry` and a `-` right after a closing
+/// quote are text, a lone `-` is dropped.
+fn parse_query_operators(query_string: &str) -> QueryOperators {
+    let mut words = Vec::new();
+    let mut unquoted = Vec::new();
+    let mut phrases = Vec::new();
+    let mut exact_terms = Vec::new();
+    let mut excluded = Vec::new();
+
+    let mut rest = query_string.trim_start();
+    let mut word_start = true;
+    while !rest.is_empty() {
+        if let Some(quoted) = rest.strip_prefix("-\"").filter(|_| word_start) {
+            let (phrase, after) = quoted.split_once('"').unwrap_or((quoted, ""));
+            if !phrase.trim().is_empty() {
+                excluded.push(phrase.trim().to_string());
+            }
+            word_start = after.is_empty() || after.starts_with(char::is_whitespace);
+            rest = after.trim_start();
+            continue;
+        }
+        if let Some((phrase, after)) = rest
+            .strip_prefix('"')
+            .and_then(|quoted| quoted.split_once('"'))
+        {
+            if !phrase.trim().is_empty() {
+                phrases.push(phrase.trim().to_string());
+                words.push(phrase.trim());
+            }
+            word_start = after.is_empty() || after.starts_with(char::is_whitespace);
+            rest = after.trim_start();
+            continue;
+        }
+
+        let (word, after) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        rest = after.trim_start();
+        let operator_allowed = std::mem::replace(&mut word_start, true);
+        if word == "-" {
+            continue;
+        }
+        if let Some(term) = word
+            .strip_prefix('-')
+            .filter(|term| operator_allowed && !term.is_empty())
+        {
+            excluded.push(term.to_string());
+        } else if let Some(term) = word
+            .strip_prefix('+')
+            .filter(|term| operator_allowed && !term.is_empty())
+        {
+            exact_terms.push(term.to_string());
+            words.push(term);
+            unquoted.push(term);
+        } else {
+            words.push(word);
+            unquoted.push(word);
+        }
+    }
+
+    QueryOperators {
+        text: words.join(" "),
+        unquoted: unquoted.join(" "),
+        phrases,
+        exact_terms,
+        excluded,
+    }
+}
+
+/// The clauses matching the positive part of the query, which score and get highlighted.
+/// The wide search matches quoted phrases exactly and only fuzzes the words around them,
+/// the natural search matches the whole query as a phrase anyway.
+fn build_positive_clauses(operators: &QueryOperators, options: &SearchOptions) -> Vec<Value> {
+    let mut must = Vec::new();
+    if matches!(options.search_type, SearchType::Wide) && !operators.phrases.is_empty() {
+        if !operators.unquoted.is_empty() {
+            must.push(build_expanded_text_query(&operators.unquoted, options));
+        }
+        must.extend(
+            operators
+                .phrases
+                .iter()
+                .map(|phrase| json!({ "match_phrase": { "text": phrase } })),
+        );
+    } else {
+        must.push(build_expanded_text_query(&operators.text, options));
+    }
+    must.extend(operators.exact_terms.iter().map(|term| {
+        json!({
+            "match": {
+                "text": {
+                    "query": term,
+                    "operator": "and"
+                }
+            }
+        })
+    }));
+    must
+}
+
+/// The caption query of a search, also used to aggregate matches elsewhere
+pub fn build_main_query_by_type(query_string: &str, options: &SearchOptions) -> Value {
+    let operators = parse_query_operators(query_string);
+    let must = build_positive_clauses(&operators, options);
+
+    let mut filter = vec![];
+    if options.exclude_auto {
+        // Captions indexed before the flag existed have no value and are skipped too
+        filter.push(json!({ "term": { "is_generated": false } }));
+    }
+    if let Some(language) = &options.language {
+        filter.push(json!({ "term": { "language": language } }));
+    }
+    if !options.channels.is_empty() {
+        filter.push(json!({ "terms": { "channel_id": options.channels } }));
+    }
+
+    // Captions of hidden channels are never returned by public search
+    let mut must_not = vec![json!({ "term": { "hidden": true } })];
+    if !options.exclude_channels.is_empty() {
+        must_not.push(json!({ "terms": { "channel_id": options.exclude_channels } }));
+    }
+    must_not.extend(
+        operators
+            .excluded
+            .iter()
+            .map(|excluded| json!({ "match_phrase": { "text": excluded } })),
+    );
+
+    json!({
+        "bool": {
+            "must": must,
+            "filter": filter,
+            "must_not": must_not
+        }
+    })
+}
+
+/// The text query, additionally matching the synonym variants of the query with a
+/// lower score
+fn build_expanded_text_query(query_string: &str, options: &SearchOptions) -> Value {
+    let variants = synonym_service::expand_query(query_string);
+    build_text_query_with_variants(query_string, &variants, options)
+}
+
+fn build_text_query_with_variants(
+    query_string: &str,
+    variants: &[String],
+    options: &SearchOptions,
+) -> Value {
+    if variants.is_empty() {
+        return build_text_query(query_string, options);
+    }
+
+    let mut should = vec![build_text_query(query_string, options)];
+    should.extend(variants.iter().map(|variant| {
+        json!({
+            "bool": {
+                "must": [build_text_query(variant, options)],
+                "boost": options.relevance.synonym_boost
+            }
+        })
+    }));
+    json!({
+        "bool": {
+            "should": should,
+            "minimum_should_match": 1
+        }
+    })
+}
+
+fn build_text_query(query_string: &str, options: &SearchOptions) -> Value {
+    let relevance = &options.relevance;
+    match options.search_type {
+        SearchType::Natural => {
+            let mut should = vec![
+                // Exact phrase match (highest priority)
+                json!({
+                    "match_phrase": {
+                        "text": {
+                            "query": query_string,
+                            "boost": if options.exact_terms {
+                                relevance.exact_phrase_boost
+                            } else {
+                                relevance.phrase_boost
+                            }
+                        }
+                    }
+                }),
+            ];
+
+            if !options.exact_terms {
+                // Exact phrase match on the stemmed field(s) of the language (for basic stemming)
+                should.push(json!({
+                    "multi_match": {
+                        "query": query_string,
+                        "fields": [stemming::stemmed_field(options.language.as_deref())],
+                        "type": "phrase",
+                        "boost": relevance.stemmed_phrase_boost,
+                        "slop": 0  // No word reordering allowed
+                    }
+                }));
+            }
+
+            json!({
+                "bool": {
+                    "should": should,
+                    "minimum_should_match": 1
+                }
+            })
+        }
         SearchType::Wide => {
             let fuzzy_setting = options.fuzzy_distance.as_deref().unwrap_or("AUTO");
 
+            // Without stemming the unstemmed field carries the whole score, so it gets boosted
+            let (all_fields, fuzzy_all_fields, partial_fields) = if options.exact_terms {
+                (json!(["text^3"]), json!(["text^2"]), json!(["text^1.5"]))
+            } else {
+                let stemmed = stemming::stemmed_field(options.language.as_deref());
+                (
+                    json!(["text^2", stemmed]),
+                    json!(["text^1.5", stemmed]),
+                    json!(["text", stemmed]),
+                )
+            };
+
             json!({
                 "bool": {
                     "should": [
@@ -518,7 +1388,7 @@ fn build_main_query_by_type(query_string: &str, options: &SearchOptions) -> Valu
                             "match_phrase": {
                                 "text": {
                                     "query": query_string,
-                                    "boost": 4.0
+                                    "boost": relevance.exact_phrase_boost
                                 }
                             }
                         },
@@ -527,8 +1397,8 @@ fn build_main_query_by_type(query_string: &str, options: &SearchOptions) -> Valu
                             "match_phrase": {
                                 "text": {
                                     "query": query_string,
-                                    "slop": 3,  // Allow up to 3 words between terms
-                                    "boost": 3.0
+                                    "slop": relevance.phrase_slop,  // Words allowed between terms
+                                    "boost": relevance.phrase_boost
                                 }
                             }
                         },
@@ -536,44 +1406,44 @@ fn build_main_query_by_type(query_string: &str, options: &SearchOptions) -> Valu
                         {
                             "multi_match": {
                                 "query": query_string,
-                                "fields": ["text^2", "text.stemmed"],
+                                "fields": all_fields,
                                 "type": "best_fields",
                                 "operator": "and",  // All words must be present
-                                "boost": 2.5
+                                "boost": relevance.all_terms_boost
                             }
                         },
                         // All words must be present with fuzzy matching
                         {
                             "multi_match": {
                                 "query": query_string,
-                                "fields": ["text^1.5", "text.stemmed"],
+                                "fields": fuzzy_all_fields,
                                 "type": "best_fields",
                                 "operator": "and",
                                 "fuzziness": fuzzy_setting,
-                                "boost": 2.0
+                                "boost": relevance.fuzzy_all_terms_boost
                             }
                         },
                         // At least most words present (for partial matches)
                         {
                             "multi_match": {
                                 "query": query_string,
-                                "fields": ["text", "text.stemmed"],
+                                "fields": partial_fields,
                                 "type": "best_fields",
                                 "operator": "or",
                                 "minimum_should_match": "75%",  // At least 75% of words
-                                "boost": 1.5
+                                "boost": relevance.partial_terms_boost
                             }
                         },
                         // Fuzzy matching for typos (lowest priority)
                         {
                             "multi_match": {
                                 "query": query_string,
-                                "fields": ["text", "text.stemmed"],
+                                "fields": partial_fields,
                                 "type": "best_fields",
                                 "operator": "or",
                                 "fuzziness": fuzzy_setting,
                                 "minimum_should_match": "50%",
-                                "boost": 1.0
+                                "boost": relevance.fuzzy_partial_terms_boost
                             }
                         }
                     ],
@@ -601,7 +1471,8 @@ fn parse_search_result(source: &Map<String, Value>, hit: &Value) -> SearchResult
         .and_then(|v| v.as_f64())
         .unwrap_or_default();
 
-    // Prefer highlight if present; fallback to the raw text
+    // Prefer highlight if present; fallback to the text, clipped in case of an oversized
+    // caption indexed before long captions were split
     let snippet_html = hit
         .get("highlight")
         .and_then(|hl| hl.get("text"))
@@ -613,7 +1484,7 @@ fn parse_search_result(source: &Map<String, Value>, hit: &Value) -> SearchResult
             source
                 .get("text")
                 .and_then(|v| v.as_str())
-                .map(|s| s.to_string())
+                .map(|s| utils::clip_caption_text(s, *MAX_CAPTION_CHARS, false))
         })
         .unwrap_or_default();
 
@@ -622,6 +1493,8 @@ fn parse_search_result(source: &Map<String, Value>, hit: &Value) -> SearchResult
         start_time,
         end_time,
         snippet_html,
+        snippet_text: None,
+        match_count: None,
     }
 }
 
@@ -640,7 +1513,7 @@ async fn process_search_response(response: Value) -> Vec<SearchResult> {
             .get("_source")
             .and_then(|s| s.as_object())
             .cloned()
-            .unwrap_or_else(|| Map::new());
+            .unwrap_or_else(Map::new);
 
         let result = parse_search_result(&source, &hit);
         out.push(result);
@@ -649,159 +1522,377 @@ async fn process_search_response(response: Value) -> Vec<SearchResult> {
     out
 }
 
-async fn fetch_neighbors_for_hit(
+fn build_video_aggregation_body(query_string: &str, options: &SearchOptions) -> Value {
+    let main_query = build_main_query_by_type(query_string, options);
+
+    json!({
+        "size": 0,
+        "query": main_query,
+        "aggs": {
+            "unique_videos": {
+                "terms": {
+                    "field": "video_id",
+                    "size": MAX_AGGREGATED_VIDEOS,  // Get all matching videos first
+                    "order": { "_key": "asc" }  // Temporary ordering for consistency
+                },
+                "aggs": {
+                    "max_score": { "max": { "script": "_score" } },
+                    "avg_score": { "avg": { "script": "_score" } },
+                    "match_count": { "value_count": { "field": "video_id" } }
+                }
+            }
+        }
+    })
+}
+
+/// Previous and next captions of a hit
+type Neighbors = (Vec<Caption>, Vec<Caption>);
+
+/// Previous and next captions of every hit of a page, in the order of `results`. Hits
+/// are grouped by video, each video's window is fetched once and concurrently with the
+/// other videos'. Hits whose window failed to load get no neighbors.
+async fn fetch_neighbors_for_page(
     es_client: &Elasticsearch,
-    video_id: &str,
-    anchor_start_time: f64,
-    anchor_end_time: f64,
+    results: &[SearchResult],
+    anchors: &[NeighborAnchor],
     before: usize,
     after: usize,
-) -> Result<(Vec<Caption>, Vec<Caption>)> {
-    let window_seconds = ((before + after) as f64 * 6.0).max(30.0);
-    let start_window = anchor_start_time - window_seconds;
-    let end_window = anchor_end_time + window_seconds;
+) -> Vec<Neighbors> {
+    // Positions of the hits of each video, videos in order of their first hit
+    let mut hits_by_video: Vec<(String, Vec<usize>)> = Vec::new();
+    for (i, res) in results.iter().enumerate() {
+        match hits_by_video
+            .iter_mut()
+            .find(|(video_id, _)| *video_id == res.video_id)
+        {
+            Some((_, hits)) => hits.push(i),
+            None => hits_by_video.push((res.video_id.clone(), vec![i])),
+        }
+    }
 
-    let window_query = json!({
-        "_source": ["text", "start_time", "end_time"],
-        "size": ((before + after + 1) * 3).max(50),
-        "sort": [{ "start_time": { "order": "asc" } }],
-        "query": {
-            "bool": {
-                "filter": [
-                    { "term": { "video_id": video_id }},
-                    { "range": { "start_time": { "gte": start_window, "lte": end_window } } }
-                ]
-            }
+    let windows: Vec<(Vec<usize>, Vec<Neighbors>)> = stream::iter(hits_by_video)
+        .map(|(video_id, hits)| async move {
+            let video_anchors: Vec<&NeighborAnchor> = hits.iter().map(|&i| &anchors[i]).collect();
+            let neighbors =
+                fetch_neighbors_for_video(es_client, &video_id, &video_anchors, before, after)
+                    .await
+                    .unwrap_or_else(|e| {
+                        warn!("Failed to fetch neighbors for {video_id}: {e:?}");
+                        vec![Default::default(); hits.len()]
+                    });
+            (hits, neighbors)
+        })
+        .buffer_unordered(NEIGHBOR_FETCH_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut neighbors = vec![(Vec::new(), Vec::new()); results.len()];
+    for (hits, video_neighbors) in windows {
+        for (i, hit_neighbors) in hits.into_iter().zip(video_neighbors) {
+            neighbors[i] = hit_neighbors;
         }
-    });
+    }
+    neighbors
+}
 
+/// Captions of the video starting within the window, the earliest `size` of them
+async fn fetch_neighbor_window(
+    es_client: &Elasticsearch,
+    video_id: &str,
+    start_window: f64,
+    end_window: f64,
+    size: usize,
+) -> Result<Vec<Caption>> {
     let resp = es_client
-        .search(SearchParts::Index(&["youtube_captions"]))
-        .body(window_query)
+        .search(SearchParts::Index(&[INDEX_NAMES.captions]))
+        .body(build_neighbor_window_body(
+            video_id,
+            start_window,
+            end_window,
+            size,
+        ))
         .send()
         .await
         .context("Elasticsearch window search failed")?
         .json::<Value>()
         .await
         .context("Failed to parse window response JSON")?;
+    Ok(parse_neighbor_hits(resp)?)
+}
 
-    let all_captions = parse_neighbor_hits(resp);
+/// Neighbors of several hits of one video from a single window query spanning all of
+/// them. Each hit gets the captions its own window would have returned: a hit whose
+/// window was cut off by the size limit of the shared query is fetched on its own.
+async fn fetch_neighbors_for_video(
+    es_client: &Elasticsearch,
+    video_id: &str,
+    anchors: &[&NeighborAnchor],
+    before: usize,
+    after: usize,
+) -> Result<Vec<(Vec<Caption>, Vec<Caption>)>> {
+    let hit_size = neighbor_window_size(before, after);
+    let windows: Vec<(f64, f64)> = anchors
+        .iter()
+        .map(|anchor| neighbor_window(anchor.start_time, anchor.end_time, before, after))
+        .collect();
+    let start_window = windows
+        .iter()
+        .map(|(start, _)| *start)
+        .fold(f64::INFINITY, f64::min);
+    let end_window = windows
+        .iter()
+        .map(|(_, end)| *end)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let size = (hit_size * anchors.len()).min(MAX_NEIGHBOR_WINDOW_SIZE);
+
+    let all_captions =
+        fetch_neighbor_window(es_client, video_id, start_window, end_window, size).await?;
+    // Start time up to which the shared query returned every caption
+    let complete_until = if all_captions.len() < size {
+        f64::INFINITY
+    } else {
+        all_captions
+            .last()
+            .map_or(f64::NEG_INFINITY, |c| c.start_time)
+    };
 
-    let mut anchor_index = None;
-    for (i, caption) in all_captions.iter().enumerate() {
-        if (caption.start_time - anchor_start_time).abs() < 0.1 {
-            anchor_index = Some(i);
-            break;
+    let mut neighbors = Vec::with_capacity(anchors.len());
+    for (anchor, (start, end)) in anchors.iter().zip(windows) {
+        let mut window: Vec<Caption> = all_captions
+            .iter()
+            .filter(|caption| caption.start_time >= start && caption.start_time <= end)
+            .take(hit_size)
+            .cloned()
+            .collect();
+        if window.len() < hit_size && end >= complete_until {
+            window = fetch_neighbor_window(es_client, video_id, start, end, hit_size).await?;
         }
+        let (prev_captions, next_captions) = split_neighbors(window, anchor, before, after);
+
+        debug!(
+            "Found {} prev neighbors and {} next neighbors for video {} at {}s",
+            prev_captions.len(),
+            next_captions.len(),
+            video_id,
+            anchor.start_time
+        );
+        neighbors.push((prev_captions, next_captions));
     }
+    Ok(neighbors)
+}
 
-    let (prev_captions, next_captions) = match anchor_index {
-        Some(anchor_idx) => {
-            let prev_start = if anchor_idx >= before {
-                anchor_idx - before
-            } else {
-                0
-            };
-            let prev_captions = all_captions[prev_start..anchor_idx].to_vec();
+/// Max difference between start times that still counts as the same caption, stored
+/// times may be off by float noise (12.0 vs 12.001)
+const ANCHOR_TIME_TOLERANCE: f64 = 0.1;
 
-            let next_start = anchor_idx + 1;
-            let next_end = (next_start + after).min(all_captions.len());
-            let next_captions = all_captions[next_start..next_end].to_vec();
+/// The caption a search hit matched, its text normalized with `normalize_for_comparison`
+struct NeighborAnchor {
+    start_time: f64,
+    end_time: f64,
+    text: String,
+}
 
-            (prev_captions, next_captions)
+impl NeighborAnchor {
+    /// Whether `caption` is the anchor itself or already shown by it: it starts at the
+    /// anchor's time, lies mostly within the anchor's time range or its whole text is
+    /// part of the anchor's. Slightly overlapping neighbors, as auto-generated captions
+    /// have, are kept.
+    fn covers(&self, caption: &Caption) -> bool {
+        if (caption.start_time - self.start_time).abs() < ANCHOR_TIME_TOLERANCE {
+            return true;
+        }
+        let overlap = caption.end_time.min(self.end_time) - caption.start_time.max(self.start_time);
+        let duration = caption.end_time - caption.start_time;
+        if overlap > 0.0 && overlap >= duration / 2.0 {
+            return true;
         }
-        None => {
-            let mut prev_captions = Vec::new();
-            let mut next_captions = Vec::new();
-
-            for caption in all_captions {
-                if caption.start_time < anchor_start_time {
-                    prev_captions.push(caption);
-                } else if caption.start_time > anchor_end_time {
-                    next_captions.push(caption);
-                }
-            }
 
-            if prev_captions.len() > before {
-                prev_captions = prev_captions[prev_captions.len() - before..].to_vec();
-            }
-            if next_captions.len() > after {
-                next_captions.truncate(after);
-            }
+        let text = normalize_for_comparison(&caption.text, "", "");
+        !text.is_empty() && format!(" {} ", self.text).contains(&format!(" {text} "))
+    }
+}
+
+/// Splits the captions around the anchor into up to `before` previous and `after` next
+/// ones, leaving out any caption the anchor already covers
+fn split_neighbors(
+    all_captions: Vec<Caption>,
+    anchor: &NeighborAnchor,
+    before: usize,
+    after: usize,
+) -> (Vec<Caption>, Vec<Caption>) {
+    let mut prev_captions = Vec::new();
+    let mut next_captions = Vec::new();
 
-            (prev_captions, next_captions)
+    for caption in all_captions {
+        if anchor.covers(&caption) {
+            continue;
         }
-    };
+        if caption.start_time < anchor.start_time {
+            prev_captions.push(caption);
+        } else {
+            next_captions.push(caption);
+        }
+    }
 
-    debug!(
-        "Found {} prev neighbors and {} next neighbors for video {} at {}s",
-        prev_captions.len(),
-        next_captions.len(),
-        video_id,
-        anchor_start_time
-    );
+    if prev_captions.len() > before {
+        prev_captions.drain(..prev_captions.len() - before);
+    }
+    next_captions.truncate(after);
+
+    (prev_captions, next_captions)
+}
+
+/// Lowercased words of a snippet without highlight tags and punctuation, to compare
+/// caption texts regardless of formatting
+fn normalize_for_comparison(text: &str, pre_tag: &str, post_tag: &str) -> String {
+    let mut text = text.to_string();
+    for tag in [pre_tag, post_tag] {
+        if !tag.is_empty() {
+            text = text.replace(tag, " ");
+        }
+    }
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Start time range around the anchor, wide enough to contain `before` and `after`
+/// neighbors even with short captions
+fn neighbor_window(
+    anchor_start_time: f64,
+    anchor_end_time: f64,
+    before: usize,
+    after: usize,
+) -> (f64, f64) {
+    let window_seconds = ((before + after) as f64 * 6.0).max(30.0);
+    (
+        anchor_start_time - window_seconds,
+        anchor_end_time + window_seconds,
+    )
+}
+
+/// Captions of one anchor's window considered, the earliest ones
+fn neighbor_window_size(before: usize, after: usize) -> usize {
+    ((before + after + 1) * 3).max(50)
+}
+
+/// Captions of the video starting within the window, oldest first
+fn build_neighbor_window_body(
+    video_id: &str,
+    start_window: f64,
+    end_window: f64,
+    size: usize,
+) -> Value {
+    json!({
+        "_source": ["video_id", "text", "start_time", "end_time"],
+        "size": size,
+        "sort": [{ "start_time": { "order": "asc" } }],
+        "query": {
+            "bool": {
+                "filter": [
+                    { "term": { "video_id": video_id }},
+                    { "range": { "start_time": { "gte": start_window, "lte": end_window } } }
+                ]
+            }
+        }
+    })
+}
+
+/// Error for caption hits that don't contain the fields we requested via `_source`
+#[derive(Debug)]
+pub enum CaptionParseError {
+    MissingHits,
+    MissingField { hit: usize, field: &'static str },
+}
 
-    Ok((prev_captions, next_captions))
+impl fmt::Display for CaptionParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptionParseError::MissingHits => write!(f, "response contains no hits array"),
+            CaptionParseError::MissingField { hit, field } => {
+                write!(f, "caption hit {hit} is missing field '{field}'")
+            }
+        }
+    }
 }
 
-fn parse_neighbor_hits(resp: Value) -> Vec<Caption> {
-    resp.get("hits")
+impl std::error::Error for CaptionParseError {}
+
+fn parse_neighbor_hits(resp: Value) -> Result<Vec<Caption>, CaptionParseError> {
+    let hits = resp
+        .get("hits")
         .and_then(|h| h.get("hits"))
         .and_then(|arr| arr.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|hit| {
-                    let src = hit.get("_source")?.as_object()?;
-                    let text = src.get("text")?.as_str()?.to_string();
-                    let start_time = src
-                        .get("start_time")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0);
-                    let end_time = src.get("end_time").and_then(|v| v.as_f64()).unwrap_or(0.0);
-
-                    let video_id = src
-                        .get("video_id")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string();
-
-                    Some(Caption {
-                        video_id,
-                        text,
-                        start_time,
-                        end_time,
-                    })
-                })
-                .collect::<Vec<_>>()
+        .ok_or(CaptionParseError::MissingHits)?;
+
+    hits.iter()
+        .enumerate()
+        .map(|(i, hit)| {
+            let missing = |field| CaptionParseError::MissingField { hit: i, field };
+            let src = hit.get("_source").ok_or(missing("_source"))?;
+            let start_time = src
+                .get("start_time")
+                .and_then(|v| v.as_f64())
+                .ok_or(missing("start_time"))?;
+
+            Ok(Caption {
+                video_id: src
+                    .get("video_id")
+                    .and_then(|v| v.as_str())
+                    .ok_or(missing("video_id"))?
+                    .to_string(),
+                text: src
+                    .get("text")
+                    .and_then(|v| v.as_str())
+                    .ok_or(missing("text"))?
+                    .to_string(),
+                raw_text: None,
+                start_time,
+                end_time: src
+                    .get("end_time")
+                    .and_then(|v| v.as_f64())
+                    .ok_or(missing("end_time"))?,
+                hidden: false,
+                language: src
+                    .get("language")
+                    .and_then(|v| v.as_str())
+                    .map(String::from),
+                is_generated: src
+                    .get("is_generated")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false),
+                segment: caption_segment(start_time),
+                upload_date: src.get("upload_date").and_then(|v| v.as_i64()),
+                channel_id: src
+                    .get("channel_id")
+                    .and_then(|v| v.as_str())
+                    .map(String::from),
+            })
         })
-        .unwrap_or_default()
+        .collect()
 }
 
-fn join_neighbor_text(prev: &Vec<Caption>) -> String {
+/// Caption text is normalized at index time, only the ends are trimmed for display.
+/// Oversized legacy captions are clipped, previous neighbors keep their end so the text
+/// next to the anchor stays.
+fn join_neighbor_text(prev: &[Caption], keep_end: bool) -> String {
     let texts: Vec<String> = prev
         .iter()
-        .map(|d| clean_caption_text(&d.text))
-        .filter(|s| !s.trim().is_empty())
+        .map(|d| d.text.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| utils::clip_caption_text(s, *MAX_CAPTION_CHARS, keep_end))
         .collect();
     texts.join(" ")
 }
 
-fn clean_caption_text(text: &str) -> String {
-    text.trim()
-        .replace("  ", " ") // Collapse multiple spaces
-        .replace(" ,", ",") // Fix spacing around punctuation
-        .replace(" .", ".")
-        .replace(" ?", "?")
-        .replace(" !", "!")
-        .to_string()
-}
-
 /// Enhanced stitching with better sentence awareness
 fn stitch_with_neighbors_enhanced(prev: &str, anchor_html: &str, next: &str) -> String {
     let mut parts = Vec::new();
 
     if !prev.is_empty() {
-        let prev_clean = clean_caption_text(prev);
+        let prev_clean = prev.trim().to_string();
         // Only add ellipsis if previous doesn't end with punctuation
         if prev_clean.ends_with(&['.', '!', '?', ':'][..]) {
             parts.push(prev_clean);
@@ -810,10 +1901,10 @@ fn stitch_with_neighbors_enhanced(prev: &str, anchor_html: &str, next: &str) ->
         }
     }
 
-    parts.push(clean_caption_text(anchor_html));
+    parts.push(anchor_html.trim().to_string());
 
     if !next.is_empty() {
-        let next_clean = clean_caption_text(next);
+        let next_clean = next.trim().to_string();
         // Only add ellipsis if next doesn't start with punctuation
         if next_clean.starts_with(&['.', ',', '!', '?', ':'][..]) {
             parts.push(next_clean);
@@ -825,102 +1916,1710 @@ fn stitch_with_neighbors_enhanced(prev: &str, anchor_html: &str, next: &str) ->
     parts.join(" ")
 }
 
-fn truncate_around_highlight(s: &str, max_chars: usize, pre_tag: &str, post_tag: &str) -> String {
-    if s.chars().count() <= max_chars {
-        return s.to_string();
+/// Lowercased, deduplicated words of the positive part of the query. Single characters
+/// are skipped.
+fn tokenize_query_terms(query_string: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for term in parse_query_operators(query_string)
+        .text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| t.chars().count() > 1)
+        .map(|t| t.to_lowercase())
+    {
+        if !terms.contains(&term) {
+            terms.push(term);
+        }
     }
+    terms
+}
 
-    if let Some(pre_idx) = s.find(pre_tag) {
-        let after_pre = &s[pre_idx + pre_tag.len()..];
-        if let Some(rel_post_idx) = after_pre.find(post_tag) {
-            let hl_start = pre_idx;
-            let hl_end = pre_idx + pre_tag.len() + rel_post_idx + post_tag.len();
-
-            let total_chars = s.chars().count();
-            let s_chars: Vec<char> = s.chars().collect();
+/// Wraps whole-word, case-insensitive occurrences of `terms` in `pre_tag`/`post_tag`.
+/// Text already between the tags is left untouched, so nothing gets wrapped twice.
+fn highlight_query_terms(html: &str, terms: &[String], pre_tag: &str, post_tag: &str) -> String {
+    if terms.is_empty() {
+        return html.to_string();
+    }
 
-            let hl_start_chars = s[..hl_start].chars().count();
-            let hl_chars = s[hl_start..hl_end].chars().count();
-            let hl_end_chars = hl_start_chars + hl_chars;
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(pre_idx) = rest.find(pre_tag) {
+        out.push_str(&wrap_terms(&rest[..pre_idx], terms, pre_tag, post_tag));
+
+        let highlighted = &rest[pre_idx..];
+        match highlighted[pre_tag.len()..].find(post_tag) {
+            Some(rel_post_idx) => {
+                let end = pre_tag.len() + rel_post_idx + post_tag.len();
+                out.push_str(&highlighted[..end]);
+                rest = &highlighted[end..];
+            }
+            None => {
+                out.push_str(highlighted);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(&wrap_terms(rest, terms, pre_tag, post_tag));
 
-            let remaining = max_chars.saturating_sub(hl_chars);
-            let side = remaining / 2;
-            let extra_buffer = 20;
+    out
+}
 
-            let mut prefix_take = (side + extra_buffer).min(hl_start_chars);
-            let mut suffix_take = (side + extra_buffer).min(total_chars - hl_end_chars);
+fn wrap_terms(text: &str, terms: &[String], pre_tag: &str, post_tag: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let push_word = |out: &mut String, word: &str| {
+        // Words directly after '&' or '&#' are HTML entity names, not caption text
+        let is_entity = out.ends_with('&') || out.ends_with("&#");
+        if !is_entity && terms.contains(&word.to_lowercase()) {
+            out.push_str(pre_tag);
+            out.push_str(word);
+            out.push_str(post_tag);
+        } else {
+            out.push_str(word);
+        }
+    };
 
-            let total_take = prefix_take + hl_chars + suffix_take;
-            if total_take < max_chars {
-                let extra = max_chars - total_take;
-                if prefix_take < hl_start_chars {
-                    let can_expand_prefix = (hl_start_chars - prefix_take).min(extra / 2);
-                    prefix_take += can_expand_prefix;
-                }
-                if suffix_take < (total_chars - hl_end_chars) {
-                    let can_expand_suffix =
-                        (total_chars - hl_end_chars - suffix_take).min(extra / 2);
-                    suffix_take += can_expand_suffix;
-                }
+    let mut word_start = None;
+    for (i, c) in text.char_indices() {
+        if c.is_alphanumeric() {
+            word_start.get_or_insert(i);
+        } else {
+            if let Some(start) = word_start.take() {
+                push_word(&mut out, &text[start..i]);
             }
+            out.push(c);
+        }
+    }
+    if let Some(start) = word_start {
+        push_word(&mut out, &text[start..]);
+    }
 
-            let start_char = hl_start_chars - prefix_take;
-            let end_char = (hl_end_chars + suffix_take).min(total_chars);
+    out
+}
 
-            let mut actual_start = start_char;
-            let mut actual_end = end_char;
+/// A highlight tag in a snippet, `start..end` in chars
+struct TagSpan {
+    start: usize,
+    end: usize,
+    opens: bool,
+}
 
-            // Find sentence boundaries for more natural breaks
-            if start_char > 0 {
-                for i in (0..=start_char.min(start_char + 30)).rev() {
-                    if i < s_chars.len() && matches!(s_chars[i], '.' | '!' | '?') {
-                        actual_start = (i + 1).min(s_chars.len() - 1);
-                        break;
-                    }
-                }
-                // Fallback to word boundary
-                if actual_start == start_char {
-                    for i in (0..=start_char.min(start_char + 20)).rev() {
-                        if i < s_chars.len() && s_chars[i] == ' ' {
-                            actual_start = i + 1;
-                            break;
+/// Pre and post tags of `chars` in the order they pair up, which also works for tags
+/// that are the same on both sides
+fn tag_spans(chars: &[char], pre: &[char], post: &[char]) -> Vec<TagSpan> {
+    let mut spans: Vec<TagSpan> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let opens = spans.last().is_none_or(|tag| !tag.opens);
+        let tag = if opens { pre } else { post };
+        if !tag.is_empty() && chars[i..].starts_with(tag) {
+            spans.push(TagSpan {
+                start: i,
+                end: i + tag.len(),
+                opens,
+            });
+            i += tag.len();
+        } else {
+            i += 1;
+        }
+    }
+    spans
+}
+
+/// Moves a cut position out of a tag it would split, to the tag's start or end
+fn cut_outside_tags(tags: &[TagSpan], pos: usize, to_end: bool) -> usize {
+    match tags.iter().find(|tag| tag.start < pos && pos < tag.end) {
+        Some(tag) if to_end => tag.end,
+        Some(tag) => tag.start,
+        None => pos,
+    }
+}
+
+/// Whether a highlight opened before `pos` is still open there
+fn is_open_at(tags: &[TagSpan], pos: usize) -> bool {
+    tags.iter()
+        .take_while(|tag| tag.end <= pos)
+        .last()
+        .is_some_and(|tag| tag.opens)
+}
+
+/// Shortens `s` to about `max_chars` chars around its first highlight, preferring sentence
+/// and word boundaries. Positions are counted in chars throughout so multi-byte text is
+/// never split, tags are never cut and highlights cut by the window are closed again.
+fn truncate_around_highlight(s: &str, max_chars: usize, pre_tag: &str, post_tag: &str) -> String {
+    let s_chars: Vec<char> = s.chars().collect();
+    let total_chars = s_chars.len();
+    if total_chars <= max_chars {
+        return s.to_string();
+    }
+
+    let pre: Vec<char> = pre_tag.chars().collect();
+    let post: Vec<char> = post_tag.chars().collect();
+    let tags = tag_spans(&s_chars, &pre, &post);
+
+    let first_highlight = tags
+        .first()
+        .zip(tags.get(1))
+        .map(|(open, close)| (open.start, close.end));
+    let Some((hl_start_chars, hl_end_chars)) = first_highlight else {
+        let end = cut_outside_tags(&tags, max_chars.saturating_sub(2), false);
+        let prefix: String = s_chars[..end].iter().collect();
+        return format!("{}…", prefix.trim_end());
+    };
+    let hl_chars = hl_end_chars - hl_start_chars;
+
+    let remaining = max_chars.saturating_sub(hl_chars);
+    let side = remaining / 2;
+    let extra_buffer = 20;
+
+    let mut prefix_take = (side + extra_buffer).min(hl_start_chars);
+    let mut suffix_take = (side + extra_buffer).min(total_chars - hl_end_chars);
+
+    let total_take = prefix_take + hl_chars + suffix_take;
+    if total_take < max_chars {
+        let extra = max_chars - total_take;
+        prefix_take += (hl_start_chars - prefix_take).min(extra / 2);
+        suffix_take += (total_chars - hl_end_chars - suffix_take).min(extra / 2);
+    }
+
+    let start_char = hl_start_chars - prefix_take;
+    let end_char = hl_end_chars + suffix_take;
+
+    let mut actual_start = start_char;
+    let mut actual_end = end_char;
+
+    // Find sentence boundaries for more natural breaks
+    if start_char > 0 {
+        if let Some(i) = (start_char.saturating_sub(30)..start_char)
+            .rev()
+            .find(|&i| matches!(s_chars[i], '.' | '!' | '?'))
+        {
+            actual_start = i + 1;
+        } else if let Some(i) = (start_char.saturating_sub(20)..start_char)
+            .rev()
+            .find(|&i| s_chars[i] == ' ')
+        {
+            // Fallback to word boundary
+            actual_start = i + 1;
+        }
+    }
+
+    if end_char < total_chars {
+        let search_end = (end_char + 30).min(total_chars);
+        if let Some(i) = (end_char..search_end).find(|&i| matches!(s_chars[i], '.' | '!' | '?')) {
+            actual_end = i + 1;
+        } else if let Some(i) =
+            (end_char..(end_char + 20).min(total_chars)).find(|&i| s_chars[i] == ' ')
+        {
+            // Fallback to word boundary
+            actual_end = i;
+        }
+    }
+
+    let actual_start = cut_outside_tags(&tags, actual_start, false);
+    let actual_end = cut_outside_tags(&tags, actual_end, true);
+
+    let trimmed: String = s_chars[actual_start..actual_end].iter().collect();
+    let reopen = if is_open_at(&tags, actual_start) {
+        pre_tag
+    } else {
+        ""
+    };
+    let close = if is_open_at(&tags, actual_end) {
+        post_tag
+    } else {
+        ""
+    };
+
+    let mut with_ellipses = trimmed;
+    if actual_start > 0 {
+        with_ellipses = format!("…{reopen}{}", with_ellipses.trim_start());
+    }
+    if actual_end < total_chars {
+        with_ellipses = format!("{}{close}…", with_ellipses.trim_end());
+    }
+
+    with_ellipses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsupport::{search_hits, MockElasticsearch};
+
+    fn highlight(html: &str, query: &str) -> String {
+        highlight_query_terms(html, &tokenize_query_terms(query), PRE_TAG, POST_TAG)
+    }
+
+    #[test]
+    fn stitching_adds_ellipses_unless_punctuated() {
+        assert_eq!(
+            stitch_with_neighbors_enhanced("before", "<strong>x</strong>", "after"),
+            "…before <strong>x</strong> after…"
+        );
+        assert_eq!(
+            stitch_with_neighbors_enhanced("Done.", " anchor ", ", and more"),
+            "Done. anchor , and more"
+        );
+        assert_eq!(stitch_with_neighbors_enhanced("", "anchor", ""), "anchor");
+    }
+
+    #[test]
+    fn query_terms_are_marked_in_the_neighbors() {
+        let stitched = stitch_with_neighbors_enhanced(
+            "a Black hole",
+            "the <strong>black hole</strong> grows",
+            "black holes and BLACK",
+        );
+        assert_eq!(
+            highlight(&stitched, "black hole"),
+            "…a <strong>Black</strong> <strong>hole</strong> the <strong>black hole</strong> \
+             grows <strong>black</strong> holes and <strong>BLACK</strong>…"
+        );
+    }
+
+    #[test]
+    fn terms_straddling_the_anchor_boundary() {
+        // "black" ends the previous caption, "hole" is the ES highlight of the anchor
+        let stitched =
+            stitch_with_neighbors_enhanced("into the black", "<strong>hole</strong> we go", "");
+        assert_eq!(
+            highlight(&stitched, "black hole"),
+            "…into the <strong>black</strong> <strong>hole</strong> we go"
+        );
+
+        // The anchor ends in "black", the next caption starts with "hole"
+        let stitched =
+            stitch_with_neighbors_enhanced("", "a <strong>black</strong>", "hole appears");
+        assert_eq!(
+            highlight(&stitched, "black hole"),
+            "a <strong>black</strong> <strong>hole</strong> appears…"
+        );
+    }
+
+    #[test]
+    fn highlighting_respects_word_boundaries() {
+        assert_eq!(
+            highlight("blackhole and blacker but black.", "black"),
+            "blackhole and blacker but <strong>black</strong>."
+        );
+        assert_eq!(
+            highlight("Ünïcode ÜNÏCODE", "ünïcode"),
+            "<strong>Ünïcode</strong> <strong>ÜNÏCODE</strong>"
+        );
+    }
+
+    #[test]
+    fn highlighting_never_wraps_twice() {
+        let html = "<strong>black hole</strong> black";
+        let once = highlight(html, "black");
+        assert_eq!(once, "<strong>black hole</strong> <strong>black</strong>");
+        assert_eq!(highlight(&once, "black"), once);
+    }
+
+    #[test]
+    fn highlighting_keeps_html_escapes_intact() {
+        assert_eq!(
+            highlight("Tom &amp; Jerry &#39;amp&#39;", "amp"),
+            "Tom &amp; Jerry &#39;<strong>amp</strong>&#39;"
+        );
+        assert_eq!(
+            highlight("&lt;b&gt; lt", "lt"),
+            "&lt;b&gt; <strong>lt</strong>"
+        );
+    }
+
+    #[test]
+    fn single_characters_and_duplicates_are_not_terms() {
+        assert_eq!(
+            tokenize_query_terms("a Black black b hole"),
+            ["black", "hole"]
+        );
+        assert_eq!(highlight("a b c", "a b"), "a b c");
+    }
+
+    #[test]
+    fn disabled_highlighting_uses_stand_in_markers() {
+        let tags = HighlightTags::none();
+        let (pre, post) = tags.working();
+        let html = highlight_query_terms("black hole", &tokenize_query_terms("hole"), pre, post);
+        assert_ne!(html, "black hole");
+        assert_eq!(tags.finish(html), "black hole");
+    }
+
+    fn neighbor_response() -> Value {
+        json!({
+            "hits": {
+                "hits": [
+                    {
+                        "_source": {
+                            "video_id": "dQw4w9WgXcQ",
+                            "text": "never gonna give you up",
+                            "start_time": 601.5,
+                            "end_time": 604.0,
+                            "language": "en",
+                            "is_generated": true,
+                            "upload_date": 1256083200
+                        }
+                    },
+                    {
+                        "_source": {
+                            "video_id": "dQw4w9WgXcQ",
+                            "text": "never gonna let you down",
+                            "start_time": 604.0,
+                            "end_time": 606.5
                         }
                     }
+                ]
+            }
+        })
+    }
+
+    #[test]
+    fn neighbor_hits_are_fully_populated() {
+        let captions = parse_neighbor_hits(neighbor_response()).unwrap();
+        assert_eq!(captions.len(), 2);
+
+        let first = &captions[0];
+        assert_eq!(first.video_id, "dQw4w9WgXcQ");
+        assert_eq!(first.text, "never gonna give you up");
+        assert_eq!((first.start_time, first.end_time), (601.5, 604.0));
+        assert_eq!(first.language.as_deref(), Some("en"));
+        assert!(first.is_generated);
+        assert_eq!(first.segment, caption_segment(601.5));
+        assert_eq!(first.upload_date, Some(1256083200));
+        assert_eq!(first.raw_text, None);
+        assert!(!first.hidden);
+
+        // Optional fields of captions indexed before they existed
+        let second = &captions[1];
+        assert_eq!(second.video_id, "dQw4w9WgXcQ");
+        assert_eq!(second.language, None);
+        assert!(!second.is_generated);
+        assert_eq!(second.upload_date, None);
+    }
+
+    #[test]
+    fn neighbor_window_requests_every_required_field() {
+        let body = build_neighbor_window_body("dQw4w9WgXcQ", 0.0, 60.0, 50);
+        let source: Vec<&str> = body["_source"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|field| field.as_str().unwrap())
+            .collect();
+        for field in ["video_id", "text", "start_time", "end_time"] {
+            assert!(source.contains(&field), "{field} is not requested");
+        }
+    }
+
+    #[test]
+    fn neighbor_hits_missing_a_field_are_an_error() {
+        for field in ["video_id", "text", "start_time", "end_time"] {
+            let mut response = neighbor_response();
+            response["hits"]["hits"][1]["_source"]
+                .as_object_mut()
+                .unwrap()
+                .remove(field);
+            match parse_neighbor_hits(response) {
+                Err(CaptionParseError::MissingField {
+                    hit: 1,
+                    field: missing,
+                }) => {
+                    assert_eq!(missing, field)
                 }
+                other => panic!("expected missing {field}, got {other:?}"),
             }
+        }
 
-            if end_char < total_chars {
-                for i in end_char..=(end_char + 30).min(total_chars - 1) {
-                    if i < s_chars.len() && matches!(s_chars[i], '.' | '!' | '?') {
-                        actual_end = (i + 1).min(s_chars.len());
-                        break;
-                    }
+        assert!(matches!(
+            parse_neighbor_hits(json!({ "hits": {} })),
+            Err(CaptionParseError::MissingHits)
+        ));
+        assert!(matches!(
+            parse_neighbor_hits(json!({ "hits": { "hits": [{}] } })),
+            Err(CaptionParseError::MissingField {
+                hit: 0,
+                field: "_source"
+            })
+        ));
+    }
+
+    fn natural() -> SearchOptions {
+        SearchOptions::natural(SortBy::Relevance, SortOrder::Desc)
+    }
+
+    fn wide() -> SearchOptions {
+        SearchOptions::wide(SortBy::Relevance, SortOrder::Desc)
+    }
+
+    #[test]
+    fn plus_terms_are_exact_and_stay_in_the_text() {
+        let operators = parse_query_operators("+Meshing networks +");
+        assert_eq!(operators.exact_terms, ["Meshing"]);
+        assert_eq!(operators.text, "Meshing networks +");
+        assert_eq!(operators.unquoted, "Meshing networks +");
+        assert!(operators.phrases.is_empty() && operators.excluded.is_empty());
+    }
+
+    #[test]
+    fn quoted_phrases() {
+        let operators = parse_query_operators(r#"  "black hole"  sun "" "open"#);
+        assert_eq!(operators.phrases, ["black hole"]);
+        assert_eq!(operators.text, r#"black hole sun "open"#);
+        assert_eq!(operators.unquoted, r#"sun "open"#);
+    }
+
+    #[test]
+    fn mixed_operators() {
+        let operators =
+            parse_query_operators(r#"+rust "borrow checker" -"garbage collector" -java fast"#);
+        assert_eq!(operators.exact_terms, ["rust"]);
+        assert_eq!(operators.phrases, ["borrow checker"]);
+        assert_eq!(operators.excluded, ["garbage collector", "java"]);
+        assert_eq!(operators.text, "rust borrow checker fast");
+        assert_eq!(operators.unquoted, "rust fast");
+    }
+
+    #[test]
+    fn minus_terms_are_excluded_and_left_out_of_the_text() {
+        let operators = parse_query_operators("jump drive -quantum -Meshing");
+        assert_eq!(operators.excluded, ["quantum", "Meshing"]);
+        assert_eq!(operators.text, "jump drive");
+        assert_eq!(operators.unquoted, "jump drive");
+    }
+
+    #[test]
+    fn excluded_phrases() {
+        let operators = parse_query_operators(r#"ships -"jump drive" -"  " -"quantum travel"#);
+        assert_eq!(operators.excluded, ["jump drive", "quantum travel"]);
+        assert_eq!(operators.text, "ships");
+        assert!(operators.phrases.is_empty());
+    }
+
+    #[test]
+    fn lone_minus_signs_are_dropped() {
+        let operators = parse_query_operators("space - ships -");
+        assert!(operators.excluded.is_empty());
+        assert_eq!(operators.text, "space ships");
+        assert_eq!(operators.unquoted, "space ships");
+    }
+
+    #[test]
+    fn hyphenated_words_are_no_exclusions() {
+        let operators = parse_query_operators(r#"re-entry well-known "heat shield"-test"#);
+        assert!(operators.excluded.is_empty(), "{:?}", operators.excluded);
+        assert_eq!(operators.phrases, ["heat shield"]);
+        assert_eq!(operators.text, "re-entry well-known heat shield -test");
+        assert_eq!(operators.unquoted, "re-entry well-known -test");
+
+        let operators = parse_query_operators("re-entry -re-entry");
+        assert_eq!(operators.excluded, ["re-entry"]);
+        assert_eq!(operators.text, "re-entry");
+    }
+
+    #[test]
+    fn wide_phrases_become_match_phrase_clauses() {
+        let query =
+            build_main_query_by_type(r#"fast "borrow checker" "lifetime elision""#, &wide());
+        let must = query["bool"]["must"].as_array().unwrap();
+        assert_eq!(must.len(), 3);
+        // Only the words around the phrases are fuzzed
+        assert_eq!(must[0], build_expanded_text_query("fast", &wide()));
+        assert_eq!(
+            must[1],
+            json!({ "match_phrase": { "text": "borrow checker" } })
+        );
+        assert_eq!(
+            must[2],
+            json!({ "match_phrase": { "text": "lifetime elision" } })
+        );
+
+        let phrase_only = build_main_query_by_type(r#""borrow checker""#, &wide());
+        assert_eq!(
+            phrase_only["bool"]["must"],
+            json!([{ "match_phrase": { "text": "borrow checker" } }])
+        );
+    }
+
+    #[test]
+    fn natural_phrases_stay_in_the_text_query() {
+        let query = build_main_query_by_type(r#"fast "borrow checker""#, &natural());
+        assert_eq!(
+            query["bool"]["must"],
+            json!([build_expanded_text_query("fast borrow checker", &natural())])
+        );
+    }
+
+    #[test]
+    fn exact_terms_flag_drops_the_stemmed_fields() {
+        for options in [natural(), wide()] {
+            let stemmed = build_main_query_by_type("meshing", &options);
+            assert!(stemmed.to_string().contains("text.stemmed"));
+
+            let exact = build_main_query_by_type("meshing", &options.with_exact_terms(true));
+            assert!(!exact.to_string().contains("text.stemmed"), "{exact}");
+        }
+    }
+
+    #[test]
+    fn exact_terms_flag_raises_the_exact_boosts() {
+        let relevance = RelevanceConfig::default();
+        let natural = build_text_query("meshing", &natural().with_exact_terms(true));
+        assert_eq!(
+            natural["bool"]["should"][0]["match_phrase"]["text"]["boost"],
+            json!(relevance.exact_phrase_boost)
+        );
+
+        let wide = build_text_query("meshing", &wide().with_exact_terms(true));
+        assert_eq!(
+            wide["bool"]["should"][2]["multi_match"]["fields"],
+            json!(["text^3"])
+        );
+    }
+
+    #[test]
+    fn plus_term_matches_unstemmed_while_the_rest_stems() {
+        for options in [natural(), wide()] {
+            let query = build_main_query_by_type("+meshing networks", &options);
+            let must = query["bool"]["must"].as_array().unwrap();
+            assert_eq!(must.len(), 2);
+            assert!(must[0].to_string().contains("text.stemmed"));
+            assert_eq!(
+                must[1],
+                json!({ "match": { "text": { "query": "meshing", "operator": "and" } } })
+            );
+        }
+    }
+
+    #[test]
+    fn channel_filters_match_the_captions_channel() {
+        let channels = vec![FILTERED_CHANNEL.to_string()];
+        let query = build_main_query_by_type("hello", &natural().with_channels(channels.clone()));
+        assert_eq!(
+            query["bool"]["filter"],
+            json!([{ "terms": { "channel_id": channels } }])
+        );
+        let unfiltered = build_main_query_by_type("hello", &natural());
+        assert_eq!(unfiltered["bool"]["filter"], json!([]));
+    }
+
+    #[rocket::async_test]
+    async fn random_captions_without_a_query_filter_by_channel() {
+        let es = MockElasticsearch::start().await;
+        let search = format!("/{}/_search", INDEX_NAMES.captions);
+        es.stub("POST", &search, search_hits(vec![])).await;
+        let channels = vec![FILTERED_CHANNEL.to_string()];
+
+        random_captions(
+            &es.client,
+            None,
+            5,
+            42,
+            &natural().with_channels(channels.clone()),
+        )
+        .await
+        .unwrap();
+
+        let body = &es.bodies("POST", &search).await[0];
+        let query = &body["query"]["function_score"]["query"]["bool"];
+        assert_eq!(
+            query["filter"],
+            json!([{ "terms": { "channel_id": channels } }])
+        );
+        assert_eq!(query["must_not"], json!([{ "term": { "hidden": true } }]));
+    }
+
+    #[test]
+    fn multi_word_synonyms_expand_into_phrase_clauses() {
+        let relevance = RelevanceConfig::default();
+        let variants = vec!["roberts space industries ships".to_string()];
+        for options in [natural(), wide()] {
+            let query = build_text_query_with_variants("rsi ships", &variants, &options);
+            let should = query["bool"]["should"].as_array().unwrap();
+            assert_eq!(should.len(), 2);
+            assert_eq!(should[0], build_text_query("rsi ships", &options));
+
+            let variant = &should[1]["bool"];
+            assert_eq!(variant["boost"], json!(relevance.synonym_boost));
+            assert_eq!(
+                variant["must"][0],
+                build_text_query("roberts space industries ships", &options)
+            );
+            // The multi-word term has to appear as a phrase
+            assert_eq!(
+                variant["must"][0]["bool"]["should"][0]["match_phrase"]["text"]["query"],
+                "roberts space industries ships"
+            );
+        }
+    }
+
+    #[test]
+    fn queries_without_synonyms_are_not_wrapped() {
+        assert_eq!(
+            build_text_query_with_variants("rsi ships", &[], &natural()),
+            build_text_query("rsi ships", &natural())
+        );
+    }
+
+    #[test]
+    fn pages_within_the_results_are_kept() {
+        assert_eq!(clamp_page(0, 10, 5), (0, false));
+        assert_eq!(clamp_page(4, 10, 5), (4, false));
+    }
+
+    #[test]
+    fn pages_past_the_last_are_clamped() {
+        assert_eq!(clamp_page(5, 10, 5), (4, true));
+        assert_eq!(clamp_page(100_000, 50, 5), (4, true));
+        assert_eq!(clamp_page(usize::MAX, 50, 5), (4, true));
+    }
+
+    #[test]
+    fn pages_of_empty_results_clamp_to_the_first() {
+        assert_eq!(clamp_page(0, 10, 0), (0, false));
+        assert_eq!(clamp_page(3, 10, 0), (0, true));
+    }
+
+    #[test]
+    fn pages_are_bounded_by_the_aggregated_videos() {
+        // 10000 videos at 50 per page, page 199 is the last the aggregation reaches
+        let total_pages = 1_000_000;
+        assert_eq!(clamp_page(199, 50, total_pages), (199, false));
+        assert_eq!(clamp_page(200, 50, total_pages), (199, true));
+        assert_eq!(
+            clamp_page(0, 1, total_pages),
+            (0, false),
+            "a page size of 1 reaches the first page"
+        );
+        assert_eq!(
+            clamp_page(MAX_AGGREGATED_VIDEOS, 1, total_pages),
+            (MAX_AGGREGATED_VIDEOS - 1, true)
+        );
+    }
+
+    #[test]
+    fn zero_page_size_does_not_divide_by_zero() {
+        assert_eq!(clamp_page(3, 0, 5), (3, false));
+    }
+
+    // Snapshots of the query bodies, in `src/services/snapshots`. After an intended change
+    // to a query, regenerate them with `UPDATE_SNAPSHOTS=1 cargo test snapshot` and review
+    // the diff of the JSON files.
+
+    fn assert_snapshot(name: &str, body: &Value) {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/services/snapshots")
+            .join(format!("{name}.json"));
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            let json = serde_json::to_string_pretty(body).unwrap();
+            std::fs::write(&path, format!("{json}\n")).unwrap();
+            return;
+        }
+        let snapshot = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+            panic!(
+                "{}: {e}, run with UPDATE_SNAPSHOTS=1 to create it",
+                path.display()
+            )
+        });
+        let snapshot: Value = serde_json::from_str(&snapshot).unwrap();
+        assert_eq!(
+            body, &snapshot,
+            "{name} changed, run with UPDATE_SNAPSHOTS=1 to accept the change"
+        );
+    }
+
+    const ALL_SORTS: [SortBy; 7] = [
+        SortBy::Relevance,
+        SortBy::UploadDate,
+        SortBy::Duration,
+        SortBy::Views,
+        SortBy::Likes,
+        SortBy::CaptionMatches,
+        SortBy::ViewVelocity,
+    ];
+
+    /// Options of every sort, which is applied to the aggregated videos and leaves the
+    /// query bodies alone
+    fn sorted_options(wide: bool, filtered: bool) -> Vec<SearchOptions> {
+        ALL_SORTS
+            .into_iter()
+            .flat_map(|sort_by| {
+                [
+                    (sort_by.clone(), SortOrder::Asc),
+                    (sort_by, SortOrder::Desc),
+                ]
+            })
+            .map(|(sort_by, sort_order)| {
+                let options = if wide {
+                    SearchOptions::wide(sort_by, sort_order)
+                } else {
+                    SearchOptions::natural(sort_by, sort_order)
+                };
+                if filtered {
+                    filtered_options(options)
+                } else {
+                    options
                 }
-                // Fallback to word boundary
-                if actual_end == end_char {
-                    for i in end_char..=(end_char + 20).min(total_chars - 1) {
-                        if i < s_chars.len() && s_chars[i] == ' ' {
-                            actual_end = i;
-                            break;
-                        }
-                    }
+            })
+            .collect()
+    }
+
+    const FILTERED_CHANNEL: &str = "UCaaaaaaaaaaaaaaaaaaaaaa";
+    const EXCLUDED_CHANNEL: &str = "UCbbbbbbbbbbbbbbbbbbbbbb";
+
+    /// Every filter on
+    fn filtered_options(options: SearchOptions) -> SearchOptions {
+        options
+            .with_exclude_auto(true)
+            .with_language(Some("de".to_string()))
+            .with_channels(vec![FILTERED_CHANNEL.to_string()])
+            .with_exclude_channels(vec![EXCLUDED_CHANNEL.to_string()])
+    }
+
+    fn snapshot_cases() -> Vec<(String, &'static str, Vec<SearchOptions>)> {
+        let mut cases = Vec::new();
+        for (kind, wide) in [("natural", false), ("wide", true)] {
+            cases.push((kind.to_string(), "hello world", sorted_options(wide, false)));
+            cases.push((
+                format!("{kind}_filtered"),
+                "hello world -goodbye",
+                sorted_options(wide, true),
+            ));
+        }
+        cases
+    }
+
+    #[test]
+    fn main_query_snapshots() {
+        for (case, query, all_options) in snapshot_cases() {
+            for options in all_options {
+                assert_snapshot(
+                    &format!("main_query_{case}"),
+                    &build_main_query_by_type(query, &options),
+                );
+            }
+        }
+    }
+
+    /// Each language searches its own stemmed subfield, one without an analyzer all of them
+    #[test]
+    fn main_query_language_snapshots() {
+        for (kind, wide) in [("natural", false), ("wide", true)] {
+            for language in ["en", "en-GB", "de", "ja"] {
+                for options in sorted_options(wide, false) {
+                    let options = options.with_language(Some(language.to_string()));
+                    assert_snapshot(
+                        &format!("main_query_{kind}_lang_{}", language.replace('-', "_")),
+                        &build_main_query_by_type("hello world", &options),
+                    );
                 }
             }
+        }
+    }
 
-            let trimmed: String = s_chars[actual_start..actual_end].iter().collect();
+    #[test]
+    fn total_counts_snapshots() {
+        for (case, query, all_options) in snapshot_cases() {
+            for options in all_options {
+                assert_snapshot(
+                    &format!("total_counts_{case}"),
+                    &build_total_counts_body(query, &options),
+                );
+            }
+        }
+    }
 
-            let mut with_ellipses = trimmed;
-            if actual_start > 0 {
-                with_ellipses = format!("…{}", with_ellipses.trim_start());
+    #[test]
+    fn video_aggregation_snapshots() {
+        for (case, query, all_options) in snapshot_cases() {
+            for options in all_options {
+                assert_snapshot(
+                    &format!("video_aggregation_{case}"),
+                    &build_video_aggregation_body(query, &options),
+                );
             }
-            if actual_end < total_chars {
-                with_ellipses = format!("{}…", with_ellipses.trim_end());
+        }
+    }
+
+    #[test]
+    fn neighbor_window_snapshot() {
+        let (start_window, end_window) = (590.5, 615.0);
+        assert_snapshot(
+            "neighbor_window",
+            &build_neighbor_window_body(
+                "dQw4w9WgXcQ",
+                start_window,
+                end_window,
+                neighbor_window_size(2, 2),
+            ),
+        );
+    }
+
+    fn caption(text: &str, start_time: f64, end_time: f64) -> Caption {
+        Caption {
+            video_id: "dQw4w9WgXcQ".to_string(),
+            text: text.to_string(),
+            raw_text: None,
+            start_time,
+            end_time,
+            hidden: false,
+            language: None,
+            is_generated: false,
+            segment: 0,
+            upload_date: None,
+            channel_id: None,
+        }
+    }
+
+    /// Anchor of a hit on "hello world" from 12s to 14s, as the search builds it
+    fn hello_anchor() -> NeighborAnchor {
+        NeighborAnchor {
+            start_time: 12.0,
+            end_time: 14.0,
+            text: normalize_for_comparison(
+                &format!("{PRE_TAG}Hello{POST_TAG}, world!"),
+                PRE_TAG,
+                POST_TAG,
+            ),
+        }
+    }
+
+    fn texts(captions: &[Caption]) -> Vec<&str> {
+        captions
+            .iter()
+            .map(|caption| caption.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn anchor_with_float_noise_is_no_neighbor() {
+        for anchor_start in [12.001, 11.999, 12.05] {
+            let window = vec![
+                caption("one", 8.0, 10.0),
+                caption("two", 10.0, anchor_start),
+                caption("hello world", anchor_start, 14.0),
+                caption("three", 14.0, 16.0),
+                caption("four", 16.0, 18.0),
+            ];
+            let (prev, next) = split_neighbors(window, &hello_anchor(), 2, 2);
+            assert_eq!(texts(&prev), ["one", "two"], "{anchor_start}");
+            assert_eq!(texts(&next), ["three", "four"], "{anchor_start}");
+        }
+    }
+
+    #[test]
+    fn captions_within_the_anchor_are_no_neighbors() {
+        let window = vec![
+            caption("two", 10.0, 12.0),
+            // Mostly within the anchor's time range
+            caption("different words", 12.5, 13.5),
+            caption("three", 14.0, 16.0),
+        ];
+        let (prev, next) = split_neighbors(window, &hello_anchor(), 2, 2);
+        assert_eq!(texts(&prev), ["two"]);
+        assert_eq!(texts(&next), ["three"]);
+    }
+
+    #[test]
+    fn captions_repeating_the_anchor_text_are_no_neighbors() {
+        let window = vec![
+            // Starts early enough to pass the time checks, the text is the anchor's
+            caption("Hello, WORLD.", 11.0, 12.5),
+            caption("world", 14.0, 15.0),
+            caption("wor", 15.0, 16.0),
+            caption("world peace", 16.0, 17.0),
+        ];
+        let (prev, next) = split_neighbors(window, &hello_anchor(), 2, 3);
+        assert!(prev.is_empty());
+        assert_eq!(texts(&next), ["wor", "world peace"]);
+    }
+
+    #[test]
+    fn slightly_overlapping_neighbors_are_kept() {
+        let window = vec![caption("before", 10.0, 12.3), caption("after", 13.8, 16.0)];
+        let (prev, next) = split_neighbors(window, &hello_anchor(), 2, 2);
+        assert_eq!(texts(&prev), ["before"]);
+        assert_eq!(texts(&next), ["after"]);
+    }
+
+    #[test]
+    fn neighbors_are_limited_to_the_closest() {
+        let window: Vec<Caption> = (0..10)
+            .map(|i| {
+                caption(
+                    &format!("caption {i}"),
+                    i as f64 * 3.0,
+                    i as f64 * 3.0 + 2.0,
+                )
+            })
+            .collect();
+        let anchor = NeighborAnchor {
+            start_time: 15.0,
+            end_time: 17.0,
+            text: "caption 5".to_string(),
+        };
+        let (prev, next) = split_neighbors(window, &anchor, 2, 1);
+        assert_eq!(texts(&prev), ["caption 3", "caption 4"]);
+        assert_eq!(texts(&next), ["caption 6"]);
+    }
+
+    /// Captions of the video starting within the window, the earliest `size` of them,
+    /// as the cluster answers a window query
+    fn captions_in_window(
+        captions: &[Caption],
+        video_id: &str,
+        start: f64,
+        end: f64,
+        size: usize,
+    ) -> Vec<Caption> {
+        captions
+            .iter()
+            .filter(|c| c.video_id == video_id && c.start_time >= start && c.start_time <= end)
+            .take(size)
+            .cloned()
+            .collect()
+    }
+
+    /// A dense video, a sparse one and a short one, captions sorted by start time
+    fn neighbor_fixture() -> Vec<Caption> {
+        let mut captions = Vec::new();
+        for (video_id, count, step) in [
+            ("aaaaaaaaaaa", 1200, 0.5),
+            ("bbbbbbbbbbb", 100, 4.0),
+            ("ccccccccccc", 3, 2.0),
+        ] {
+            captions.extend((0..count).map(|i| Caption {
+                video_id: video_id.to_string(),
+                ..caption(
+                    &format!("{video_id} line {i}"),
+                    i as f64 * step,
+                    (i + 1) as f64 * step,
+                )
+            }));
+        }
+        captions
+    }
+
+    #[rocket::async_test]
+    async fn page_neighbors_match_per_hit_windows() {
+        let captions = neighbor_fixture();
+        let es = MockElasticsearch::start().await;
+        let search = format!("/{}/_search", INDEX_NAMES.captions);
+        let indexed = captions.clone();
+        es.stub_fn("POST", &search, move |body| {
+            let filter = &body["query"]["bool"]["filter"];
+            let range = &filter[1]["range"]["start_time"];
+            let hits: Vec<Value> = captions_in_window(
+                &indexed,
+                filter[0]["term"]["video_id"].as_str().unwrap_or_default(),
+                range["gte"].as_f64().unwrap_or_default(),
+                range["lte"].as_f64().unwrap_or_default(),
+                body["size"].as_u64().unwrap_or_default() as usize,
+            )
+            .iter()
+            .map(|caption| json!({ "_source": caption }))
+            .collect();
+            json!({ "hits": { "hits": hits } })
+        })
+        .await;
+
+        // Interleaved videos, overlapping windows, and a dense video whose last hit lies
+        // beyond what the shared window query returns
+        let hits = [
+            ("aaaaaaaaaaa", 20),
+            ("bbbbbbbbbbb", 5),
+            ("aaaaaaaaaaa", 600),
+            ("bbbbbbbbbbb", 6),
+            ("ccccccccccc", 1),
+            ("aaaaaaaaaaa", 80),
+            ("bbbbbbbbbbb", 99),
+        ];
+        let results: Vec<SearchResult> = hits
+            .iter()
+            .map(|&(video_id, line)| {
+                let hit = captions
+                    .iter()
+                    .find(|c| c.text == format!("{video_id} line {line}"))
+                    .unwrap();
+                SearchResult {
+                    video_id: video_id.to_string(),
+                    start_time: hit.start_time,
+                    end_time: hit.end_time,
+                    snippet_html: format!("{PRE_TAG}{}{POST_TAG}", hit.text),
+                    snippet_text: None,
+                    match_count: None,
+                }
+            })
+            .collect();
+        let anchors: Vec<NeighborAnchor> = results
+            .iter()
+            .map(|res| NeighborAnchor {
+                start_time: res.start_time,
+                end_time: res.end_time,
+                text: normalize_for_comparison(&res.snippet_html, PRE_TAG, POST_TAG),
+            })
+            .collect();
+
+        let page = fetch_neighbors_for_page(&es.client, &results, &anchors, 2, 2).await;
+
+        // One query per video and one for the hit at 600s
+        assert_eq!(es.bodies("POST", &search).await.len(), 4);
+        assert_eq!(page.len(), results.len());
+        for ((res, anchor), (prev, next)) in results.iter().zip(&anchors).zip(&page) {
+            let (start, end) = neighbor_window(res.start_time, res.end_time, 2, 2);
+            let window = captions_in_window(
+                &captions,
+                &res.video_id,
+                start,
+                end,
+                neighbor_window_size(2, 2),
+            );
+            let (expected_prev, expected_next) = split_neighbors(window, anchor, 2, 2);
+            assert_eq!(texts(prev), texts(&expected_prev), "{}", res.snippet_html);
+            assert_eq!(texts(next), texts(&expected_next), "{}", res.snippet_html);
+        }
+        assert!(!page[2].0.is_empty(), "neighbors of the hit at 600s");
+    }
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    fn spans(s: &str, pre: &str, post: &str) -> Vec<(usize, usize, bool)> {
+        tag_spans(&chars(s), &chars(pre), &chars(post))
+            .iter()
+            .map(|tag| (tag.start, tag.end, tag.opens))
+            .collect()
+    }
+
+    /// The highlight tags of a snippet pair up and none is cut
+    fn assert_tags_balanced(snippet: &str) {
+        let tags = spans(snippet, "<b>", "</b>");
+        assert!(
+            tags.len().is_multiple_of(2),
+            "unbalanced tags in {snippet:?}"
+        );
+        let stray = snippet.matches("<b>").count() + snippet.matches("</b>").count();
+        assert_eq!(stray, tags.len(), "cut tag in {snippet:?}");
+    }
+
+    #[test]
+    fn tag_spans_are_counted_in_chars() {
+        assert_eq!(
+            spans("ä<b>ü</b>€", "<b>", "</b>"),
+            [(1, 4, true), (5, 9, false)]
+        );
+        assert_eq!(
+            spans("星**際**公**", "**", "**"),
+            [(1, 3, true), (4, 6, false), (7, 9, true)]
+        );
+        // A close tag before any open one is text
+        assert_eq!(spans("🚀</b><b>x", "<b>", "</b>"), [(5, 8, true)]);
+        assert!(spans("no tags", "", "").is_empty());
+    }
+
+    #[test]
+    fn cuts_move_out_of_tags() {
+        let tags = tag_spans(&chars("ä<b>ü</b>€"), &chars("<b>"), &chars("</b>"));
+        assert_eq!(cut_outside_tags(&tags, 2, false), 1);
+        assert_eq!(cut_outside_tags(&tags, 2, true), 4);
+        assert_eq!(cut_outside_tags(&tags, 7, false), 5);
+        assert_eq!(cut_outside_tags(&tags, 7, true), 9);
+        // Positions at a tag's edge don't split it
+        for pos in [0, 1, 4, 5, 9, 10] {
+            assert_eq!(cut_outside_tags(&tags, pos, false), pos);
+            assert_eq!(cut_outside_tags(&tags, pos, true), pos);
+        }
+    }
+
+    #[test]
+    fn short_snippets_are_kept() {
+        let snippet = "Größe <b>星際</b> 🚀";
+        assert_eq!(
+            truncate_around_highlight(snippet, 100, "<b>", "</b>"),
+            snippet
+        );
+    }
+
+    #[test]
+    fn truncation_counts_multi_byte_chars() {
+        let snippet = format!("{}<b>ü</b>{}", "ä".repeat(50), "ö".repeat(50));
+        assert_eq!(
+            truncate_around_highlight(&snippet, 20, "<b>", "</b>"),
+            format!("…{}<b>ü</b>{}…", "ä".repeat(26), "ö".repeat(26))
+        );
+    }
+
+    #[test]
+    fn highlights_survive_truncation_wherever_they_are() {
+        let filler = "Die Größe des Schiffs 🚀 ist 星際公民. ".repeat(10);
+        for highlight in [
+            "<b>Größe</b>",
+            "<b>🚀🚀</b>",
+            "<b>星際公民</b>",
+            "<b>👩‍🚀 é</b>",
+        ] {
+            let at_start = format!("{highlight} {filler}");
+            let truncated = truncate_around_highlight(&at_start, 80, "<b>", "</b>");
+            assert!(truncated.starts_with(highlight), "{truncated}");
+            assert!(truncated.ends_with('…'), "{truncated}");
+            assert_tags_balanced(&truncated);
+
+            let in_middle = format!("{filler}{highlight} {filler}");
+            let truncated = truncate_around_highlight(&in_middle, 80, "<b>", "</b>");
+            assert!(truncated.starts_with('…'), "{truncated}");
+            assert!(truncated.contains(highlight), "{truncated}");
+            assert!(truncated.ends_with('…'), "{truncated}");
+            assert_tags_balanced(&truncated);
+
+            let at_end = format!("{filler}{highlight}");
+            let truncated = truncate_around_highlight(&at_end, 80, "<b>", "</b>");
+            assert!(truncated.starts_with('…'), "{truncated}");
+            assert!(truncated.ends_with(highlight), "{truncated}");
+            assert_tags_balanced(&truncated);
+        }
+    }
+
+    #[test]
+    fn highlights_cut_by_the_window_are_closed() {
+        let snippet = format!("<b>hi</b> {}<b>{}</b>", "ä".repeat(10), "星".repeat(60));
+        let truncated = truncate_around_highlight(&snippet, 30, "<b>", "</b>");
+        assert_eq!(
+            truncated,
+            format!("<b>hi</b> {}<b>{}</b>…", "ä".repeat(10), "星".repeat(16))
+        );
+    }
+
+    #[test]
+    fn snippets_without_highlight_are_cut_at_the_limit() {
+        let truncated = truncate_around_highlight(&"星".repeat(50), 10, "<b>", "</b>");
+        assert_eq!(truncated, format!("{}…", "星".repeat(8)));
+        // A lone open tag is never split
+        let truncated =
+            truncate_around_highlight(&format!("ää<b>{}", "ö".repeat(20)), 5, "<b>", "</b>");
+        assert_eq!(truncated, "ää…");
+    }
+
+    /// Random multi-byte snippets with well-formed highlights: truncation never panics and
+    /// keeps the first highlight whole
+    #[test]
+    fn truncation_never_panics_and_keeps_the_highlight() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let words = [
+            "a",
+            "Größe",
+            "🚀",
+            "星際",
+            "公民",
+            "é",
+            "👩‍🚀",
+            "ß.",
+            "end!",
+            "?",
+            "  ",
+            "x",
+        ];
+        let mut rng = StdRng::seed_from_u64(713);
+        for _ in 0..2_000 {
+            let mut snippet = String::new();
+            let mut first_highlight: Option<String> = None;
+            for _ in 0..rng.gen_range(0..60) {
+                let word = words[rng.gen_range(0..words.len())];
+                if rng.gen_ratio(1, 8) {
+                    let highlight = format!("<b>{word}</b>");
+                    first_highlight.get_or_insert_with(|| highlight.clone());
+                    snippet.push_str(&highlight);
+                } else {
+                    snippet.push_str(word);
+                }
+                if rng.gen_bool(0.5) {
+                    snippet.push(' ');
+                }
+            }
+            let max_chars = rng.gen_range(0..120);
+
+            let truncated = truncate_around_highlight(&snippet, max_chars, "<b>", "</b>");
+            if let Some(highlight) = &first_highlight {
+                assert!(
+                    truncated.contains(highlight.as_str()),
+                    "{snippet:?} -> {truncated:?}"
+                );
+            }
+            assert_tags_balanced(&truncated);
+        }
+    }
+
+    /// Arbitrary text with stray tag characters, only panics matter here
+    #[test]
+    fn truncation_never_panics_on_arbitrary_text() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let alphabet = [
+            '<', 'b', '>', '/', ' ', '.', 'ä', '🚀', '星', '\u{200d}', '*',
+        ];
+        let mut rng = StdRng::seed_from_u64(7130);
+        for _ in 0..2_000 {
+            let snippet: String = (0..rng.gen_range(0..80))
+                .map(|_| alphabet[rng.gen_range(0..alphabet.len())])
+                .collect();
+            let max_chars = rng.gen_range(0..100);
+            truncate_around_highlight(&snippet, max_chars, "<b>", "</b>");
+            truncate_around_highlight(&snippet, max_chars, "**", "**");
+            truncate_around_highlight(&snippet, max_chars, "", "");
+        }
+    }
+
+    fn captions_search() -> String {
+        format!("/{}/_search", INDEX_NAMES.captions)
+    }
+
+    /// Two matching videos, "bbbbbbbbbbb" scoring higher, each with one hit for "hello"
+    /// at 10s between two neighbors
+    async fn stub_two_matching_videos(es: &MockElasticsearch) {
+        es.stub_body(
+            "POST",
+            &captions_search(),
+            |body| body["aggs"]["total_captions"].is_object(),
+            json!({
+                "aggregations": {
+                    "unique_videos": { "value": 2 },
+                    "total_captions": { "value": 5 }
+                }
+            }),
+        )
+        .await;
+        es.stub_body(
+            "POST",
+            &captions_search(),
+            |body| body["aggs"]["unique_videos"]["terms"].is_object(),
+            json!({
+                "aggregations": {
+                    "unique_videos": {
+                        "buckets": [
+                            {
+                                "key": "aaaaaaaaaaa",
+                                "doc_count": 3,
+                                "avg_score": { "value": 1.0 },
+                                "max_score": { "value": 1.5 }
+                            },
+                            {
+                                "key": "bbbbbbbbbbb",
+                                "doc_count": 2,
+                                "avg_score": { "value": 2.0 },
+                                "max_score": { "value": 2.5 }
+                            }
+                        ]
+                    }
+                }
+            }),
+        )
+        .await;
+        for video_id in ["aaaaaaaaaaa", "bbbbbbbbbbb"] {
+            es.stub_body(
+                "POST",
+                &captions_search(),
+                move |body| {
+                    body["highlight"].is_object()
+                        && body["query"]["bool"]["must"][1]["term"]["video_id"] == video_id
+                },
+                json!({
+                    "hits": {
+                        "hits": [{
+                            "_id": format!("{video_id}_10"),
+                            "_source": {
+                                "video_id": video_id,
+                                "text": "hello world",
+                                "start_time": 10.0,
+                                "end_time": 12.0
+                            },
+                            "highlight": { "text": ["<strong>hello</strong> world"] }
+                        }]
+                    }
+                }),
+            )
+            .await;
+            let caption = |text: &str, start_time: f64| {
+                json!({
+                    "video_id": video_id,
+                    "text": text,
+                    "start_time": start_time,
+                    "end_time": start_time + 2.0
+                })
+            };
+            es.stub_body(
+                "POST",
+                &captions_search(),
+                move |body| body["query"]["bool"]["filter"][0]["term"]["video_id"] == video_id,
+                search_hits(vec![
+                    ("before", caption("the caption before", 8.0)),
+                    ("anchor", caption("hello world", 10.0)),
+                    ("after", caption("the caption after", 12.0)),
+                ]),
+            )
+            .await;
+        }
+    }
+
+    /// Video IDs of the per-video caption queries sent
+    async fn queried_videos(es: &MockElasticsearch) -> Vec<String> {
+        es.bodies("POST", &captions_search())
+            .await
+            .iter()
+            .filter(|body| body["highlight"].is_object())
+            .filter_map(|body| body["query"]["bool"]["must"][1]["term"]["video_id"].as_str())
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[rocket::async_test]
+    async fn search_pages_through_the_videos_by_relevance() {
+        let es = MockElasticsearch::start().await;
+        stub_two_matching_videos(&es).await;
+        let options = SearchOptions::natural(SortBy::Relevance, SortOrder::Desc);
+
+        let response = search_captions_with_pagination(
+            &es.client,
+            "hello",
+            0,
+            1,
+            &options,
+            &RelevanceConfig::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!((response.total_videos, response.total_captions), (2, 5));
+        assert_eq!((response.page, response.total_pages), (0, 2));
+        assert!(!response.clamped);
+        assert!(response.warnings.is_empty());
+        assert_eq!(response.results.len(), 1);
+        let result = &response.results[0];
+        assert_eq!(result.video_id, "bbbbbbbbbbb");
+        assert!(result.snippet_html.contains("<strong>hello</strong>"));
+        assert!(result.snippet_html.contains("the caption before"));
+        assert!(result.snippet_html.contains("the caption after"));
+        assert_eq!(queried_videos(&es).await, ["bbbbbbbbbbb"]);
+
+        // The query sent is the one of the snapshots
+        let counts = es
+            .bodies("POST", &captions_search())
+            .await
+            .into_iter()
+            .find(|body| body["aggs"]["total_captions"].is_object())
+            .unwrap();
+        assert_eq!(counts, build_total_counts_body("hello", &options));
+    }
+
+    #[rocket::async_test]
+    async fn pages_past_the_end_return_the_last() {
+        let es = MockElasticsearch::start().await;
+        stub_two_matching_videos(&es).await;
+
+        let response = search_captions_with_pagination(
+            &es.client,
+            "hello",
+            100_000,
+            1,
+            &SearchOptions::natural(SortBy::Relevance, SortOrder::Desc),
+            &RelevanceConfig::default(),
+        )
+        .await
+        .unwrap();
+
+        assert!(response.clamped);
+        assert_eq!(response.page, 1);
+        assert_eq!(queried_videos(&es).await, ["aaaaaaaaaaa"]);
+    }
+
+    /// Whether `query` contains a fuzzy `match` on `text` for `term`
+    fn has_fuzzy_match(query: &Value, term: &str) -> bool {
+        match query {
+            Value::Object(fields) => {
+                let text = &fields.get("match").unwrap_or(&Value::Null)["text"];
+                (text["query"] == term && text["fuzziness"].is_string())
+                    || fields.values().any(|value| has_fuzzy_match(value, term))
             }
+            Value::Array(values) => values.iter().any(|value| has_fuzzy_match(value, term)),
+            _ => false,
+        }
+    }
 
-            return with_ellipses;
+    #[rocket::async_test]
+    async fn captions_matched_only_through_fuzziness_are_highlighted() {
+        let es = MockElasticsearch::start().await;
+        es.stub_body(
+            "POST",
+            &captions_search(),
+            |body| body["aggs"]["total_captions"].is_object(),
+            json!({
+                "aggregations": {
+                    "unique_videos": { "value": 1 },
+                    "total_captions": { "value": 1 }
+                }
+            }),
+        )
+        .await;
+        es.stub_body(
+            "POST",
+            &captions_search(),
+            |body| body["aggs"]["unique_videos"]["terms"].is_object(),
+            json!({
+                "aggregations": {
+                    "unique_videos": {
+                        "buckets": [{
+                            "key": "aaaaaaaaaaa",
+                            "doc_count": 1,
+                            "avg_score": { "value": 1.0 },
+                            "max_score": { "value": 1.0 }
+                        }]
+                    }
+                }
+            }),
+        )
+        .await;
+        es.stub_body(
+            "POST",
+            &captions_search(),
+            |body| body["query"]["bool"]["filter"][0]["term"]["video_id"].is_string(),
+            search_hits(vec![]),
+        )
+        .await;
+        // Like the cluster, the misspelled "helo" only highlights "hello" through a fuzzy
+        // clause on `text`, otherwise the caption comes back as the unhighlighted
+        // no-match fragment
+        es.stub_fn("POST", &captions_search(), |body| {
+            let highlight = &body["highlight"];
+            let fragment =
+                if has_fuzzy_match(&highlight["fields"]["text"]["highlight_query"], "helo") {
+                    format!(
+                        "{}hello{} world",
+                        highlight["pre_tags"][0].as_str().unwrap_or_default(),
+                        highlight["post_tags"][0].as_str().unwrap_or_default()
+                    )
+                } else {
+                    "hello world".to_string()
+                };
+            json!({
+                "hits": {
+                    "hits": [{
+                        "_id": "aaaaaaaaaaa_10",
+                        "_source": {
+                            "video_id": "aaaaaaaaaaa",
+                            "text": "hello world",
+                            "start_time": 10.0,
+                            "end_time": 12.0
+                        },
+                        "highlight": { "text": [fragment] }
+                    }]
+                }
+            })
+        })
+        .await;
+
+        let response = search_captions_with_pagination(
+            &es.client,
+            "helo",
+            0,
+            10,
+            &SearchOptions::wide(SortBy::Relevance, SortOrder::Desc),
+            &RelevanceConfig::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.results.len(), 1);
+        assert!(
+            response.results[0]
+                .snippet_html
+                .contains("<strong>hello</strong>"),
+            "{}",
+            response.results[0].snippet_html
+        );
+        assert_eq!(queried_videos(&es).await, ["aaaaaaaaaaa"]);
+    }
+
+    #[rocket::async_test]
+    async fn upload_date_sort_reads_the_video_metadata() {
+        let es = MockElasticsearch::start().await;
+        stub_two_matching_videos(&es).await;
+        es.stub(
+            "POST",
+            "/_mget",
+            json!({
+                "docs": [
+                    { "_id": "aaaaaaaaaaa", "found": true, "_source": { "upload_date": 1_600_000_000 } },
+                    { "_id": "bbbbbbbbbbb", "found": true, "_source": { "upload_date": 1_700_000_000 } }
+                ]
+            }),
+        )
+        .await;
+
+        let response = search_captions_with_pagination(
+            &es.client,
+            "hello",
+            0,
+            2,
+            &SearchOptions::natural(SortBy::UploadDate, SortOrder::Asc),
+            &RelevanceConfig::default(),
+        )
+        .await
+        .unwrap();
+
+        assert!(response.warnings.is_empty());
+        assert_eq!(queried_videos(&es).await, ["aaaaaaaaaaa", "bbbbbbbbbbb"]);
+    }
+
+    #[rocket::async_test]
+    async fn unavailable_metadata_falls_back_to_relevance() {
+        let es = MockElasticsearch::start().await;
+        stub_two_matching_videos(&es).await;
+
+        let response = search_captions_with_pagination(
+            &es.client,
+            "hello",
+            0,
+            2,
+            &SearchOptions::natural(SortBy::UploadDate, SortOrder::Asc),
+            &RelevanceConfig::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.warnings, [METADATA_UNAVAILABLE_WARNING]);
+        assert_eq!(queried_videos(&es).await, ["bbbbbbbbbbb", "aaaaaaaaaaa"]);
+    }
+
+    const SLOW_RESPONSE: Duration = Duration::from_secs(2);
+
+    async fn search_within(es: &MockElasticsearch, budget: Duration) -> Result<SearchResponse> {
+        search_within_deadline(
+            &es.client,
+            "hello",
+            0,
+            2,
+            &SearchOptions::natural(SortBy::Relevance, SortOrder::Desc),
+            &RelevanceConfig::default(),
+            &Deadline::start(budget),
+        )
+        .await
+    }
+
+    #[rocket::async_test]
+    async fn slow_context_is_omitted() {
+        let es = MockElasticsearch::start().await;
+        es.stub_body_delayed(
+            "POST",
+            &captions_search(),
+            |body| body["query"]["bool"]["filter"][0]["term"]["video_id"].is_string(),
+            SLOW_RESPONSE,
+            search_hits(vec![]),
+        )
+        .await;
+        stub_two_matching_videos(&es).await;
+
+        let started = Instant::now();
+        let response = search_within(&es, Duration::from_millis(300))
+            .await
+            .unwrap();
+
+        assert!(started.elapsed() < SLOW_RESPONSE, "waited for the context");
+        assert_eq!(response.warnings, [CONTEXT_OMITTED_WARNING]);
+        assert_eq!(response.results.len(), 2);
+        for result in &response.results {
+            assert!(result.snippet_html.contains("<strong>hello</strong>"));
+            assert!(!result.snippet_html.contains("the caption"));
         }
     }
 
-    let prefix: String = s.chars().take(max_chars.saturating_sub(2)).collect();
-    format!("{}…", prefix.trim_end())
+    #[rocket::async_test]
+    async fn context_within_the_deadline_is_kept() {
+        let es = MockElasticsearch::start().await;
+        stub_two_matching_videos(&es).await;
+
+        let response = search_within(&es, Duration::from_secs(5)).await.unwrap();
+        assert!(response.warnings.is_empty());
+        assert!(response.results[0]
+            .snippet_html
+            .contains("the caption before"));
+    }
+
+    #[rocket::async_test]
+    async fn slow_primary_queries_time_out() {
+        let es = MockElasticsearch::start().await;
+        es.stub_body_delayed(
+            "POST",
+            &captions_search(),
+            |body| body["aggs"]["total_captions"].is_object(),
+            SLOW_RESPONSE,
+            json!({}),
+        )
+        .await;
+        stub_two_matching_videos(&es).await;
+
+        let started = Instant::now();
+        let error = search_within(&es, Duration::from_millis(300))
+            .await
+            .unwrap_err();
+
+        assert!(started.elapsed() < SLOW_RESPONSE);
+        assert!(is_search_timeout(&error), "{error:?}");
+        assert!(queried_videos(&es).await.is_empty());
+    }
+
+    #[test]
+    fn admin_deadlines_are_clamped() {
+        assert_eq!(
+            clamp_search_deadline(Duration::from_millis(1)),
+            MIN_SEARCH_DEADLINE
+        );
+        assert_eq!(
+            clamp_search_deadline(Duration::from_secs(3600)),
+            MAX_SEARCH_DEADLINE
+        );
+        assert_eq!(
+            clamp_search_deadline(Duration::from_secs(3)),
+            Duration::from_secs(3)
+        );
+    }
+
+    /// A missing videos index fails the multi-get per document, with status 200
+    #[rocket::async_test]
+    async fn missing_videos_index_falls_back_to_relevance() {
+        let es = MockElasticsearch::start().await;
+        stub_two_matching_videos(&es).await;
+        let missing = |video_id: &str| {
+            json!({
+                "_index": INDEX_NAMES.videos,
+                "_id": video_id,
+                "error": {
+                    "root_cause": [{ "type": "index_not_found_exception", "reason": "no such index" }],
+                    "type": "index_not_found_exception",
+                    "reason": "no such index"
+                }
+            })
+        };
+        es.stub(
+            "POST",
+            "/_mget",
+            json!({ "docs": [missing("aaaaaaaaaaa"), missing("bbbbbbbbbbb")] }),
+        )
+        .await;
+
+        let response = search_captions_with_pagination(
+            &es.client,
+            "hello",
+            0,
+            2,
+            &SearchOptions::natural(SortBy::Views, SortOrder::Desc),
+            &RelevanceConfig::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.warnings, [METADATA_UNAVAILABLE_WARNING]);
+        assert_eq!(response.results.len(), 2);
+        assert_eq!(queried_videos(&es).await, ["bbbbbbbbbbb", "aaaaaaaaaaa"]);
+    }
 }
@@ -0,0 +1,487 @@
+//! Captions stitched into sentences for a secondary index, so phrases spanning a caption
+//! boundary can be searched. Sentences are stored in the caption document shape, search
+//! reads either index the same way. A nightly job rebuilds the sentences of recently
+//! crawled videos, the `rebuild-sentences` maintenance task all of them.
+
+use crate::config::INDEX_NAMES;
+use crate::models::{caption_segment, Caption};
+use crate::services::maintenance_service;
+use crate::services::maintenance_throttle::MaintenanceRun;
+use anyhow::{Context, Result};
+use elasticsearch::http::request::JsonBody;
+use elasticsearch::params::Conflicts;
+use elasticsearch::{BulkParts, DeleteByQueryParts, Elasticsearch};
+use log::{error, info, warn};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// A sentence is cut at the next caption boundary beyond this many words. Auto-generated
+/// captions have no punctuation at all.
+const MAX_SENTENCE_WORDS: usize = 60;
+/// A pause between captions this long ends the sentence
+const MAX_SENTENCE_GAP_SECONDS: f64 = 5.0;
+/// Videos crawled within this many days are rebuilt by the nightly job
+const COMPACTION_LOOKBACK_DAYS: i64 = 2;
+const BULK_BATCH_SIZE: usize = 500;
+
+/// Abbreviations whose period doesn't end a sentence
+const ABBREVIATIONS: &[&str] = &["mr.", "mrs.", "ms.", "dr.", "st.", "vs.", "e.g.", "i.e."];
+
+/// A sentence being stitched together
+struct PendingSentence {
+    words: Vec<String>,
+    start_time: f64,
+    first: Caption,
+}
+
+impl PendingSentence {
+    fn finish(self, end_time: f64) -> Caption {
+        Caption {
+            text: self.words.join(" "),
+            raw_text: None,
+            start_time: self.start_time,
+            end_time: end_time.max(self.start_time),
+            segment: caption_segment(self.start_time),
+            ..self.first
+        }
+    }
+}
+
+fn ends_sentence(word: &str) -> bool {
+    let word = word.trim_end_matches(['"', '\'', ')', ']', '»', '”', '’']);
+    word.ends_with(['.', '!', '?', '…']) && !ABBREVIATIONS.contains(&word.to_lowercase().as_str())
+}
+
+/// Stitches the captions of one video track, sorted by start time, into sentences.
+/// Sentences end at `.`, `!` or `?`, at a pause between captions or at the first caption
+/// boundary after `MAX_SENTENCE_WORDS`. A sentence ending inside a caption gets its time
+/// interpolated by the character offset within that caption.
+pub fn stitch_sentences(captions: &[Caption]) -> Vec<Caption> {
+    let mut sentences = Vec::new();
+    let mut current: Option<PendingSentence> = None;
+    let mut previous_end = 0.0;
+
+    for caption in captions {
+        let words: Vec<&str> = caption.text.split_whitespace().collect();
+        if words.is_empty() {
+            continue;
+        }
+        if caption.start_time - previous_end > MAX_SENTENCE_GAP_SECONDS {
+            if let Some(sentence) = current.take() {
+                sentences.push(sentence.finish(previous_end));
+            }
+        }
+
+        let duration = (caption.end_time - caption.start_time).max(0.0);
+        let total_chars: usize = words.iter().map(|word| word.chars().count() + 1).sum();
+        let time_at =
+            |offset: usize| caption.start_time + duration * offset as f64 / total_chars as f64;
+        let mut offset = 0;
+        for word in words {
+            let sentence = current.get_or_insert_with(|| PendingSentence {
+                words: Vec::new(),
+                start_time: time_at(offset),
+                first: caption.clone(),
+            });
+            sentence.words.push(word.to_string());
+            offset += word.chars().count() + 1;
+            if ends_sentence(word) {
+                if let Some(sentence) = current.take() {
+                    sentences.push(sentence.finish(time_at(offset - 1)));
+                }
+            }
+        }
+
+        if current
+            .as_ref()
+            .is_some_and(|sentence| sentence.words.len() >= MAX_SENTENCE_WORDS)
+        {
+            if let Some(sentence) = current.take() {
+                sentences.push(sentence.finish(caption.end_time));
+            }
+        }
+        previous_end = caption.end_time;
+    }
+
+    if let Some(sentence) = current {
+        sentences.push(sentence.finish(previous_end));
+    }
+    sentences
+}
+
+/// All captions of a video in time order. Scrolls, long videos may have more captions
+/// than one search returns.
+async fn fetch_captions(es_client: &Elasticsearch, video_id: &str) -> Result<Vec<Caption>> {
+    let mut captions: Vec<Caption> = Vec::new();
+    maintenance_service::scroll_index(
+        es_client,
+        INDEX_NAMES.captions,
+        json!({ "term": { "video_id": video_id } }),
+        true,
+        |hit| {
+            if let Ok(caption) = serde_json::from_value(hit["_source"].clone()) {
+                captions.push(caption);
+            }
+            Ok(())
+        },
+    )
+    .await
+    .context("Caption search failed")?;
+    captions.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+    Ok(captions)
+}
+
+/// Deletes the sentences of these videos
+pub async fn delete_sentences(es_client: &Elasticsearch, video_ids: &[String]) -> Result<()> {
+    for chunk in video_ids.chunks(1000) {
+        let response = es_client
+            .delete_by_query(DeleteByQueryParts::Index(&[INDEX_NAMES.sentences]))
+            .conflicts(Conflicts::Proceed)
+            .refresh(true)
+            .body(json!({ "query": { "terms": { "video_id": chunk } } }))
+            .send()
+            .await?;
+        if !response.status_code().is_success() {
+            return Err(anyhow::anyhow!(
+                "Deleting sentences failed: {}",
+                response.status_code()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the sentences of a video with freshly stitched ones, one run per caption
+/// language. Returns the number of sentences indexed.
+pub async fn rebuild_video(es_client: &Elasticsearch, video_id: &str) -> Result<usize> {
+    let mut tracks: BTreeMap<Option<String>, Vec<Caption>> = BTreeMap::new();
+    for caption in fetch_captions(es_client, video_id).await? {
+        tracks
+            .entry(caption.language.clone())
+            .or_default()
+            .push(caption);
+    }
+    let sentences: Vec<Caption> = tracks
+        .values()
+        .flat_map(|captions| stitch_sentences(captions))
+        .collect();
+
+    delete_sentences(es_client, &[video_id.to_string()]).await?;
+    for batch in sentences.chunks(BULK_BATCH_SIZE) {
+        let mut body: Vec<JsonBody<Value>> = Vec::with_capacity(batch.len() * 2);
+        for sentence in batch {
+            body.push(json!({ "index": {} }).into());
+            body.push(json!(sentence).into());
+        }
+        let response = es_client
+            .bulk(BulkParts::Index(INDEX_NAMES.sentences))
+            .body(body)
+            .send()
+            .await?;
+        if !response.status_code().is_success() {
+            return Err(anyhow::anyhow!(
+                "Bulk sentence indexing failed: {}",
+                response.status_code()
+            ));
+        }
+        let json_response: Value = response.json().await?;
+        if json_response["errors"].as_bool().unwrap_or(false) {
+            return Err(anyhow::anyhow!(
+                "Some sentences of video {video_id} failed to index"
+            ));
+        }
+    }
+    Ok(sentences.len())
+}
+
+/// Videos crawled since `since` (unix seconds)
+async fn recently_crawled_video_ids(es_client: &Elasticsearch, since: i64) -> Result<Vec<String>> {
+    let mut video_ids = Vec::new();
+    maintenance_service::scroll_index(
+        es_client,
+        INDEX_NAMES.videos,
+        json!({ "range": { "crawl_date": { "gte": since } } }),
+        false,
+        |hit| {
+            if let Some(id) = hit["_id"].as_str() {
+                video_ids.push(id.to_string());
+            }
+            Ok(())
+        },
+    )
+    .await
+    .context("Recent video search failed")?;
+    Ok(video_ids)
+}
+
+/// Nightly job: rebuilds the sentences of every video crawled in the last days, pausing
+/// after every batch of sentences at the pace of the maintenance `run`
+pub async fn compact_recent(es_client: &Elasticsearch, run: &MaintenanceRun) {
+    let since = chrono::Utc::now().timestamp() - COMPACTION_LOOKBACK_DAYS * 24 * 60 * 60;
+    let video_ids = match recently_crawled_video_ids(es_client, since).await {
+        Ok(video_ids) => video_ids,
+        Err(e) => {
+            error!("Failed to load recently crawled videos: {e:?}");
+            return;
+        }
+    };
+
+    let mut total = 0;
+    let mut since_pause = 0;
+    for (i, video_id) in video_ids.iter().enumerate() {
+        match rebuild_video(es_client, video_id).await {
+            Ok(count) => {
+                total += count;
+                since_pause += count;
+            }
+            Err(e) => warn!("Failed to rebuild the sentences of {video_id}: {e:?}"),
+        }
+        run.progress((i + 1) as i64, video_ids.len() as i64);
+        if since_pause >= run.throttle().batch_size {
+            run.pause().await;
+            since_pause = 0;
+        }
+    }
+    info!(
+        "Compacted the captions of {} videos into {total} sentences",
+        video_ids.len()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsupport::{search_hits, MockElasticsearch};
+
+    fn caption(text: &str, start_time: f64, end_time: f64) -> Caption {
+        Caption {
+            video_id: "dQw4w9WgXcQ".to_string(),
+            text: text.to_string(),
+            raw_text: Some(text.to_string()),
+            start_time,
+            end_time,
+            hidden: false,
+            language: Some("en".to_string()),
+            is_generated: false,
+            segment: 0,
+            upload_date: Some(1_700_000_000),
+            channel_id: Some("UCuAXFkgsw1L7xaCfnd5JJOw".to_string()),
+        }
+    }
+
+    fn texts(sentences: &[Caption]) -> Vec<&str> {
+        sentences
+            .iter()
+            .map(|sentence| sentence.text.as_str())
+            .collect()
+    }
+
+    fn assert_time(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn sentences_span_caption_boundaries() {
+        let sentences = stitch_sentences(&[
+            caption("we tested the server", 0.0, 2.0),
+            caption("meshing works. next", 2.0, 4.0),
+        ]);
+        assert_eq!(
+            texts(&sentences),
+            ["we tested the server meshing works.", "next"]
+        );
+
+        // "meshing works. next" has 20 characters with one trailing space per word, the
+        // period is the 14th
+        assert_time(sentences[0].start_time, 0.0);
+        assert_time(sentences[0].end_time, 2.0 + 2.0 * 14.0 / 20.0);
+        assert_time(sentences[1].start_time, 2.0 + 2.0 * 15.0 / 20.0);
+        assert_time(sentences[1].end_time, 4.0);
+    }
+
+    #[test]
+    fn sentences_take_the_fields_of_their_first_caption() {
+        let mut second = caption("there.", 62.0, 63.0);
+        second.language = Some("de".to_string());
+        let sentences = stitch_sentences(&[caption("Hello", 61.0, 62.0), second]);
+
+        assert_eq!(sentences.len(), 1);
+        let sentence = &sentences[0];
+        assert_eq!(sentence.text, "Hello there.");
+        assert_eq!(sentence.raw_text, None);
+        assert_eq!(sentence.language.as_deref(), Some("en"));
+        assert_eq!(sentence.upload_date, Some(1_700_000_000));
+        assert_eq!(
+            sentence.channel_id.as_deref(),
+            Some("UCuAXFkgsw1L7xaCfnd5JJOw")
+        );
+        assert_eq!(sentence.segment, caption_segment(61.0));
+    }
+
+    #[test]
+    fn several_sentences_in_one_caption() {
+        let sentences = stitch_sentences(&[caption("Yes! Really? Sure… ok.", 0.0, 4.0)]);
+        assert_eq!(texts(&sentences), ["Yes!", "Really?", "Sure…", "ok."]);
+        for pair in sentences.windows(2) {
+            assert!(pair[0].end_time <= pair[1].start_time);
+        }
+    }
+
+    #[test]
+    fn abbreviations_and_closing_quotes() {
+        let sentences = stitch_sentences(&[caption(
+            "Dr. Smith and Mrs. Jones, e.g. here. He said \"stop.\" Then (really.) done",
+            0.0,
+            10.0,
+        )]);
+        assert_eq!(
+            texts(&sentences),
+            [
+                "Dr. Smith and Mrs. Jones, e.g. here.",
+                "He said \"stop.\"",
+                "Then (really.)",
+                "done"
+            ]
+        );
+    }
+
+    #[test]
+    fn a_pause_ends_the_sentence() {
+        let sentences = stitch_sentences(&[
+            caption("no punctuation", 0.0, 2.0),
+            caption("right after", 2.0, 4.0),
+            caption("exactly at the limit", 9.0, 10.0),
+            caption("after a pause", 15.5, 17.0),
+        ]);
+        assert_eq!(
+            texts(&sentences),
+            [
+                "no punctuation right after exactly at the limit",
+                "after a pause"
+            ]
+        );
+        assert_time(sentences[0].end_time, 10.0);
+        assert_time(sentences[1].start_time, 15.5);
+    }
+
+    #[test]
+    fn long_sentences_are_cut_at_the_next_caption_boundary() {
+        let words = |count: usize| vec!["word"; count].join(" ");
+        let sentences = stitch_sentences(&[
+            caption(&words(25), 0.0, 5.0),
+            caption(&words(25), 5.0, 10.0),
+            caption(&words(25), 10.0, 15.0),
+            caption(&words(5), 15.0, 16.0),
+        ]);
+        assert_eq!(sentences.len(), 2);
+        assert_eq!(sentences[0].text, words(75));
+        assert_time(sentences[0].end_time, 15.0);
+        assert_eq!(sentences[1].text, words(5));
+        assert_time(sentences[1].start_time, 15.0);
+
+        let exactly = stitch_sentences(&[caption(&words(MAX_SENTENCE_WORDS), 0.0, 5.0)]);
+        assert_eq!(exactly.len(), 1);
+    }
+
+    #[test]
+    fn empty_captions_are_skipped() {
+        assert!(stitch_sentences(&[]).is_empty());
+        assert!(stitch_sentences(&[caption("  ", 0.0, 1.0)]).is_empty());
+
+        let sentences = stitch_sentences(&[
+            caption("  spaced   out  ", 0.0, 1.0),
+            caption("", 1.0, 2.0),
+            caption(" words.", 2.0, 3.0),
+        ]);
+        assert_eq!(texts(&sentences), ["spaced out words."]);
+    }
+
+    #[test]
+    fn zero_duration_captions_keep_ordered_times() {
+        let sentences =
+            stitch_sentences(&[caption("One. Two.", 3.0, 3.0), caption("Three", 3.0, 2.0)]);
+        assert_eq!(texts(&sentences), ["One.", "Two.", "Three"]);
+        for sentence in &sentences {
+            assert_time(sentence.start_time, 3.0);
+            assert!(sentence.end_time >= sentence.start_time);
+        }
+    }
+
+    fn scroll_page(hits: Vec<(&str, Value)>, next: &str) -> Value {
+        let mut page = search_hits(hits);
+        page["_scroll_id"] = json!(next);
+        page
+    }
+
+    #[rocket::async_test]
+    async fn recent_videos_are_read_past_the_first_page() {
+        let es = MockElasticsearch::start().await;
+        let search = format!("/{}/_search", INDEX_NAMES.videos);
+        es.stub(
+            "POST",
+            &search,
+            scroll_page(vec![("aaaaaaaaaaa", json!({}))], "page-2"),
+        )
+        .await;
+        es.stub_body(
+            "POST",
+            "/_search/scroll",
+            |body| body["scroll_id"] == "page-2",
+            scroll_page(vec![("bbbbbbbbbbb", json!({}))], "page-3"),
+        )
+        .await;
+        es.stub_body(
+            "POST",
+            "/_search/scroll",
+            |body| body["scroll_id"] == "page-3",
+            scroll_page(vec![], "page-3"),
+        )
+        .await;
+
+        let video_ids = recently_crawled_video_ids(&es.client, 1_700_000_000)
+            .await
+            .unwrap();
+
+        assert_eq!(video_ids, ["aaaaaaaaaaa", "bbbbbbbbbbb"]);
+        assert_eq!(
+            es.bodies("POST", &search).await[0]["query"],
+            json!({ "range": { "crawl_date": { "gte": 1_700_000_000 } } })
+        );
+    }
+
+    #[rocket::async_test]
+    async fn scrolled_captions_are_put_in_time_order() {
+        let es = MockElasticsearch::start().await;
+        es.stub(
+            "POST",
+            &format!("/{}/_search", INDEX_NAMES.captions),
+            scroll_page(
+                vec![("later", json!(caption("second", 4.0, 6.0)))],
+                "page-2",
+            ),
+        )
+        .await;
+        es.stub_body(
+            "POST",
+            "/_search/scroll",
+            |body| body["scroll_id"] == "page-2",
+            scroll_page(
+                vec![("earlier", json!(caption("first", 0.0, 2.0)))],
+                "page-3",
+            ),
+        )
+        .await;
+        es.stub_body(
+            "POST",
+            "/_search/scroll",
+            |body| body["scroll_id"] == "page-3",
+            scroll_page(vec![], "page-3"),
+        )
+        .await;
+
+        let captions = fetch_captions(&es.client, "dQw4w9WgXcQ").await.unwrap();
+
+        let texts: Vec<&str> = captions.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, ["first", "second"]);
+    }
+}
@@ -0,0 +1,170 @@
+//! Deterministic stand-ins for the YouTube API and transcript fetcher, used instead of
+//! them when SIMULATION_MODE is set. Everything is derived from the requested ID, so the
+//! same video always gets the same metadata and captions.
+
+use crate::config::{LANGUAGE_PRIORITY, SIMULATION_PLAYLIST};
+use crate::models::{
+    caption_segment, Caption, CaptionTrack, DiscoveryMode, EnqueueOrder, MonitoredChannel,
+    MonitoredPlaylist, VideoMetadata,
+};
+use crate::services::monitoring_service::PlaylistVideo;
+use sha2::{Digest, Sha256};
+
+/// Videos of a generated playlist when SIMULATION_PLAYLIST is unset
+const GENERATED_PLAYLIST_LENGTH: usize = 10;
+/// Channels the simulated videos are spread across
+const SIMULATED_CHANNELS: u64 = 4;
+const CAPTION_SECONDS: f64 = 4.0;
+/// 2024-01-01, simulated uploads are spread over the year before
+const BASE_UPLOAD_DATE: i64 = 1_704_067_200;
+
+const WORDS: &str = "lorem ipsum dolor sit amet consectetur adipiscing elit sed do eiusmod tempor \
+    incididunt labore dolore magna aliqua enim minim veniam quis nostrud \
+    exercitation ullamco laboris nisi aliquip commodo consequat duis aute irure \
+    reprehenderit voluptate velit esse cillum fugiat nulla pariatur";
+
+/// Stable pseudo-random numbers for an ID, `index` picks the number
+fn seed(id: &str, index: u64) -> u64 {
+    let digest = Sha256::digest(format!("{id}:{index}").as_bytes());
+    u64::from_be_bytes(digest[..8].try_into().unwrap())
+}
+
+fn hex_prefix(id: &str, length: usize) -> String {
+    format!("{:x}", Sha256::digest(id.as_bytes()))[..length].to_string()
+}
+
+pub fn video_metadata(video_id: &str) -> VideoMetadata {
+    let channel = seed(video_id, 0) % SIMULATED_CHANNELS;
+    VideoMetadata {
+        title: format!("Simulated video {video_id}"),
+        // 24 characters like a real channel ID, so the admin endpoints accept it
+        channel_id: format!("UCsimulatedChannel{channel:06}"),
+        channel_name: format!("Simulated Channel {channel}"),
+        upload_date: BASE_UPLOAD_DATE - (seed(video_id, 1) % 365) as i64 * 24 * 60 * 60,
+        likes: (seed(video_id, 2) % 10_000) as i64,
+        views: (seed(video_id, 3) % 1_000_000) as i64,
+        duration: caption_count(video_id) as i64 * CAPTION_SECONDS as i64,
+        comment_count: (seed(video_id, 4) % 1_000) as i64,
+        tags: vec!["simulated".to_string()],
+        has_captions: false,
+        crawl_date: chrono::Utc::now().timestamp(),
+        video_id: video_id.to_string(),
+        playlists: vec![],
+        crawl_history: vec![],
+        hidden: false,
+        deleting: false,
+        caption_tracks: vec![],
+        caption_source: None,
+        discovered_by: None,
+        previous: None,
+        skip_reason: None,
+        caption_coverage: None,
+    }
+}
+
+fn caption_count(video_id: &str) -> usize {
+    20 + (seed(video_id, 5) % 40) as usize
+}
+
+/// The single track every simulated video has, in the most preferred language
+pub fn caption_track() -> CaptionTrack {
+    let language_code = LANGUAGE_PRIORITY
+        .first()
+        .cloned()
+        .unwrap_or_else(|| "en".to_string());
+    CaptionTrack {
+        language: format!("Simulated ({language_code})"),
+        language_code,
+        is_generated: false,
+    }
+}
+
+/// Lorem-style sentences, every fifth one names the video ID so searching for it finds them
+pub fn captions(video_id: &str, hidden: bool) -> Vec<Caption> {
+    let language = caption_track().language_code;
+    let vocabulary: Vec<&str> = WORDS.split_whitespace().collect();
+    (0..caption_count(video_id))
+        .map(|index| {
+            let word_count = 6 + (seed(video_id, 100 + index as u64) % 6) as usize;
+            let mut words: Vec<&str> = (0..word_count)
+                .map(|word| {
+                    let pick = seed(video_id, 1000 + (index * 16 + word) as u64);
+                    vocabulary[(pick % vocabulary.len() as u64) as usize]
+                })
+                .collect();
+            if index % 5 == 0 {
+                words.insert(word_count / 2, video_id);
+            }
+            let start_time = index as f64 * CAPTION_SECONDS;
+            Caption {
+                video_id: video_id.to_string(),
+                text: words.join(" "),
+                raw_text: None,
+                start_time,
+                end_time: start_time + CAPTION_SECONDS,
+                hidden,
+                language: Some(language.clone()),
+                is_generated: false,
+                segment: caption_segment(start_time),
+                upload_date: None,
+                channel_id: None,
+            }
+        })
+        .collect()
+}
+
+/// Videos of a channel's uploads or a playlist: SIMULATION_PLAYLIST if set, otherwise
+/// IDs generated from the source ID
+pub fn playlist_videos(source_id: &str) -> Vec<PlaylistVideo> {
+    let video_ids: Vec<String> = if SIMULATION_PLAYLIST.is_empty() {
+        let prefix = hex_prefix(source_id, 9);
+        (0..GENERATED_PLAYLIST_LENGTH)
+            .map(|index| format!("{prefix}{index:02}"))
+            .collect()
+    } else {
+        SIMULATION_PLAYLIST.clone()
+    };
+    video_ids
+        .into_iter()
+        .map(|video_id| {
+            let metadata = video_metadata(&video_id);
+            PlaylistVideo {
+                video_id,
+                published_at: metadata.upload_date,
+                channel_id: Some(metadata.channel_id),
+            }
+        })
+        .collect()
+}
+
+pub fn monitored_channel(channel_id: &str) -> MonitoredChannel {
+    let now = chrono::Utc::now();
+    MonitoredChannel {
+        channel_id: channel_id.to_string(),
+        channel_name: format!("Simulated Channel {channel_id}"),
+        active: true,
+        enqueue_order: EnqueueOrder::default(),
+        discovery_mode: DiscoveryMode::default(),
+        created_at: now.to_rfc3339(),
+        created_at_ms: now.timestamp_millis(),
+        videos_uploaded: playlist_videos(channel_id).len() as i64,
+        hidden: false,
+        max_duration_seconds: None,
+        tags: Vec::new(),
+    }
+}
+
+pub fn monitored_playlist(playlist_id: &str) -> MonitoredPlaylist {
+    let now = chrono::Utc::now();
+    MonitoredPlaylist {
+        playlist_id: playlist_id.to_string(),
+        playlist_name: format!("Simulated Playlist {playlist_id}"),
+        active: true,
+        created_at: now.to_rfc3339(),
+        created_at_ms: now.timestamp_millis(),
+        videos_added: playlist_videos(playlist_id).len() as i64,
+        enqueue_order: EnqueueOrder::default(),
+        max_duration_seconds: None,
+        tags: Vec::new(),
+    }
+}
@@ -0,0 +1,167 @@
+//! Language specific stemming of caption text. Every configured caption language with a
+//! built-in Elasticsearch analyzer gets its own `text.stemmed_<code>` subfield, so German
+//! captions aren't stemmed with English rules.
+
+use crate::config::{INDEX_NAMES, LANGUAGE_PRIORITY};
+use crate::services::maintenance_throttle::MaintenanceRun;
+use anyhow::Result;
+use elasticsearch::indices::IndicesPutMappingParts;
+use elasticsearch::params::Conflicts;
+use elasticsearch::{Elasticsearch, UpdateByQueryParts};
+use log::{info, warn};
+use serde_json::{json, Map, Value};
+
+/// Base language code -> built-in language analyzer
+const ANALYZERS: &[(&str, &str)] = &[
+    ("en", "english"),
+    ("de", "german"),
+    ("es", "spanish"),
+    ("fr", "french"),
+    ("it", "italian"),
+    ("pt", "portuguese"),
+    ("nl", "dutch"),
+    ("sv", "swedish"),
+    ("da", "danish"),
+    ("no", "norwegian"),
+    ("fi", "finnish"),
+    ("ru", "russian"),
+    ("tr", "turkish"),
+    ("cs", "czech"),
+    ("hu", "hungarian"),
+    ("ro", "romanian"),
+];
+
+/// Matches the stemmed subfields of all languages
+const ALL_STEMMED_FIELDS: &str = "text.stemmed_*";
+
+/// "de" for "de-DE"
+fn base_code(language: &str) -> String {
+    language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+fn analyzer(language: &str) -> Option<&'static str> {
+    let code = base_code(language);
+    ANALYZERS
+        .iter()
+        .find(|(analyzer_code, _)| *analyzer_code == code)
+        .map(|(_, analyzer)| *analyzer)
+}
+
+/// The stemmed field to search captions of `language` in, all stemmed fields if no
+/// language is selected or it has no analyzer
+pub fn stemmed_field(language: Option<&str>) -> String {
+    match language.filter(|language| analyzer(language).is_some()) {
+        Some(language) => format!("text.stemmed_{}", base_code(language)),
+        None => ALL_STEMMED_FIELDS.to_string(),
+    }
+}
+
+/// Mapping of the caption `text` field with a stemmed subfield per configured language
+pub fn text_mapping() -> Value {
+    let mut fields = Map::new();
+    for language in LANGUAGE_PRIORITY.iter() {
+        if let Some(analyzer) = analyzer(language) {
+            fields.insert(
+                format!("stemmed_{}", base_code(language)),
+                json!({ "type": "text", "analyzer": analyzer }),
+            );
+        }
+    }
+    json!({ "type": "text", "fields": fields })
+}
+
+/// Adds the stemmed subfields of newly configured languages to existing caption and
+/// sentence indices. Captions indexed before only get them analyzed by `reanalyze`.
+pub async fn ensure_stemmed_fields(es_client: &Elasticsearch) {
+    for index in [INDEX_NAMES.captions, INDEX_NAMES.sentences] {
+        match es_client
+            .indices()
+            .put_mapping(IndicesPutMappingParts::Index(&[index]))
+            .body(json!({ "properties": { "text": text_mapping() } }))
+            .send()
+            .await
+        {
+            Ok(response) if response.status_code().is_success() => {}
+            Ok(response) => warn!(
+                "Failed to add stemmed fields to {index}: {}",
+                response.text().await.unwrap_or_default()
+            ),
+            Err(e) => warn!("Failed to add stemmed fields to {index}: {e:?}"),
+        }
+    }
+}
+
+/// Rewrites every caption and sentence in place so the stemmed subfields of all
+/// configured languages get analyzed, at the pace of the maintenance `run`. Returns the
+/// number of rewritten documents.
+pub async fn reanalyze(es_client: &Elasticsearch, run: &MaintenanceRun) -> Result<i64> {
+    ensure_stemmed_fields(es_client).await;
+
+    let mut total = 0;
+    for index in [INDEX_NAMES.captions, INDEX_NAMES.sentences] {
+        let response = es_client
+            .update_by_query(UpdateByQueryParts::Index(&[index]))
+            .conflicts(Conflicts::Proceed)
+            .scroll_size(run.throttle().scroll_size())
+            .requests_per_second(run.throttle().requests_per_second())
+            .refresh(true)
+            .send()
+            .await?;
+        if !response.status_code().is_success() {
+            return Err(anyhow::anyhow!(
+                "Reanalyzing {index} failed: {}",
+                response.status_code()
+            ));
+        }
+        let json_response: Value = response.json().await?;
+        total += json_response["updated"].as_i64().unwrap_or(0);
+    }
+    info!("Reanalyzed {total} captions and sentences");
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testsupport::init_env;
+
+    #[test]
+    fn regional_codes_use_the_base_language() {
+        assert_eq!(base_code("de-DE"), "de");
+        assert_eq!(base_code("pt_BR"), "pt");
+        assert_eq!(base_code("EN"), "en");
+        assert_eq!(analyzer("en-GB"), Some("english"));
+        assert_eq!(analyzer("ja"), None);
+        assert_eq!(analyzer(""), None);
+    }
+
+    #[test]
+    fn languages_search_their_own_stemmed_field() {
+        assert_eq!(stemmed_field(Some("en")), "text.stemmed_en");
+        assert_eq!(stemmed_field(Some("en-GB")), "text.stemmed_en");
+        assert_eq!(stemmed_field(Some("de")), "text.stemmed_de");
+        assert_eq!(stemmed_field(Some("es-419")), "text.stemmed_es");
+        assert_eq!(stemmed_field(Some("ja")), ALL_STEMMED_FIELDS);
+        assert_eq!(stemmed_field(None), ALL_STEMMED_FIELDS);
+    }
+
+    #[test]
+    fn configured_languages_get_one_subfield_each() {
+        // LANGUAGE_PRIORITY is en,en-GB,de
+        init_env();
+        assert_eq!(
+            text_mapping(),
+            json!({
+                "type": "text",
+                "fields": {
+                    "stemmed_en": { "type": "text", "analyzer": "english" },
+                    "stemmed_de": { "type": "text", "analyzer": "german" }
+                }
+            })
+        );
+    }
+}
@@ -0,0 +1,228 @@
+//! Admin-managed synonyms, applied by expanding queries: a query containing a term of a
+//! group is also searched with the term replaced by each other term of the group. The
+//! set is kept in memory so changes apply to the next search without reindexing.
+
+use crate::config::INDEX_NAMES;
+use crate::models::SynonymSet;
+use anyhow::Result;
+use elasticsearch::params::Refresh;
+use elasticsearch::{Elasticsearch, GetParts, IndexParts};
+use log::{error, info};
+use serde_json::{json, Value};
+use std::sync::RwLock;
+
+/// Document of the synonyms index holding the whole set
+const SYNONYMS_DOC_ID: &str = "synonyms";
+
+/// Variants searched per query, further matches are ignored
+const MAX_QUERY_VARIANTS: usize = 8;
+
+lazy_static::lazy_static! {
+    static ref SYNONYM_GROUPS: RwLock<Vec<Vec<String>>> = RwLock::new(Vec::new());
+}
+
+pub async fn load_synonyms(es_client: &Elasticsearch) {
+    let response = es_client
+        .get(GetParts::IndexId(INDEX_NAMES.synonyms, SYNONYMS_DOC_ID))
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status_code().as_u16() == 404 => {}
+        Ok(response) if response.status_code().is_success() => {
+            let response_body: Value = response.json().await.unwrap_or_default();
+            let groups: Vec<Vec<String>> =
+                serde_json::from_value(response_body["_source"]["groups"].clone())
+                    .unwrap_or_default();
+            info!("Loaded {} synonym groups", groups.len());
+            if let Ok(mut synonym_groups) = SYNONYM_GROUPS.write() {
+                *synonym_groups = groups;
+            }
+        }
+        Ok(response) => error!("Failed to load synonyms: {}", response.status_code()),
+        Err(e) => error!("Failed to load synonyms: {e:?}"),
+    }
+}
+
+pub fn synonyms() -> SynonymSet {
+    SynonymSet {
+        groups: SYNONYM_GROUPS
+            .read()
+            .map(|groups| groups.clone())
+            .unwrap_or_default(),
+    }
+}
+
+/// Lowercased terms with collapsed whitespace, without empty terms and duplicates.
+/// Groups left with less than two terms are dropped.
+pub fn normalize_groups(groups: &[Vec<String>]) -> Vec<Vec<String>> {
+    groups
+        .iter()
+        .map(|group| {
+            let mut terms: Vec<String> = Vec::new();
+            for term in group {
+                let term = term
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .to_lowercase();
+                if !term.is_empty() && !terms.contains(&term) {
+                    terms.push(term);
+                }
+            }
+            terms
+        })
+        .filter(|terms| terms.len() >= 2)
+        .collect()
+}
+
+/// Replaces the synonym set, the next search uses it. Returns the stored set.
+pub async fn set_synonyms(es_client: &Elasticsearch, synonyms: &SynonymSet) -> Result<SynonymSet> {
+    let groups = normalize_groups(&synonyms.groups);
+    let response = es_client
+        .index(IndexParts::IndexId(INDEX_NAMES.synonyms, SYNONYMS_DOC_ID))
+        .refresh(Refresh::True)
+        .body(json!({
+            "groups": groups,
+            "updated_at": chrono::Utc::now().timestamp(),
+        }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Failed to store the synonyms: {}",
+            response.status_code()
+        ));
+    }
+
+    info!("Stored {} synonym groups", groups.len());
+    if let Ok(mut synonym_groups) = SYNONYM_GROUPS.write() {
+        *synonym_groups = groups.clone();
+    }
+    Ok(SynonymSet { groups })
+}
+
+/// Lowercased word without surrounding punctuation, for comparing with synonym terms
+fn comparable(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+}
+
+/// Variants of the query with a term of the current synonym set replaced, see
+/// `expand_query_in`
+pub fn expand_query(query: &str) -> Vec<String> {
+    match SYNONYM_GROUPS.read() {
+        Ok(groups) => expand_query_in(&groups, query),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Variants of the query with a synonym term replaced by each other term of its group.
+/// Only the first occurrence of a term is replaced, one term per variant. Multi-word
+/// terms match consecutive words and are inserted as words, so phrase clauses built
+/// from a variant require them as a phrase.
+fn expand_query_in(groups: &[Vec<String>], query: &str) -> Vec<String> {
+    if groups.is_empty() {
+        return Vec::new();
+    }
+
+    let words: Vec<&str> = query.split_whitespace().collect();
+    let comparable_words: Vec<String> = words.iter().map(|word| comparable(word)).collect();
+    let original = comparable_words.join(" ");
+
+    let mut variants: Vec<String> = Vec::new();
+    for group in groups {
+        for term in group {
+            let term_words: Vec<&str> = term.split(' ').collect();
+            let Some(start) = comparable_words
+                .windows(term_words.len())
+                .position(|window| window.iter().zip(&term_words).all(|(a, b)| a == b))
+            else {
+                continue;
+            };
+            let end = start + term_words.len();
+
+            for replacement in group.iter().filter(|other| *other != term) {
+                let variant = words[..start]
+                    .iter()
+                    .copied()
+                    .chain([replacement.as_str()])
+                    .chain(words[end..].iter().copied())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if variant != original && !variants.contains(&variant) {
+                    variants.push(variant);
+                }
+                if variants.len() >= MAX_QUERY_VARIANTS {
+                    return variants;
+                }
+            }
+        }
+    }
+    variants
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn groups(groups: &[&[&str]]) -> Vec<Vec<String>> {
+        normalize_groups(
+            &groups
+                .iter()
+                .map(|group| group.iter().map(|term| term.to_string()).collect())
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn groups_are_normalized() {
+        assert_eq!(
+            groups(&[
+                &["RSI", "Roberts  Space Industries", "rsi", " "],
+                &["alone"],
+                &["Alone", "ALONE"],
+            ]),
+            [["rsi", "roberts space industries"]]
+        );
+    }
+
+    #[test]
+    fn single_words_expand_into_multi_word_terms() {
+        let groups = groups(&[&["rsi", "roberts space industries"]]);
+        assert_eq!(
+            expand_query_in(&groups, "RSI ships"),
+            ["roberts space industries ships"]
+        );
+    }
+
+    #[test]
+    fn consecutive_words_expand_into_single_words() {
+        let groups = groups(&[&["rsi", "roberts space industries"]]);
+        assert_eq!(
+            expand_query_in(&groups, "new Roberts Space Industries, ships"),
+            ["new rsi ships"]
+        );
+        // Only consecutive words are a multi-word term
+        assert!(expand_query_in(&groups, "roberts industries space").is_empty());
+        assert!(expand_query_in(&groups, "roberts space").is_empty());
+    }
+
+    #[test]
+    fn each_other_term_becomes_a_variant() {
+        let groups = groups(&[&["car", "automobile", "motor vehicle"], &["fast", "quick"]]);
+        assert_eq!(
+            expand_query_in(&groups, "fast car"),
+            ["fast automobile", "fast motor vehicle", "quick car"]
+        );
+        assert!(expand_query_in(&groups, "slow bike").is_empty());
+        assert!(expand_query_in(&[], "fast car").is_empty());
+    }
+
+    #[test]
+    fn variants_are_capped() {
+        let many: Vec<String> = (0..20).map(|i| format!("term{i}")).collect();
+        let groups = vec![many];
+        assert_eq!(expand_query_in(&groups, "term0").len(), MAX_QUERY_VARIANTS);
+    }
+}
@@ -0,0 +1,363 @@
+use crate::config::{THUMBNAIL_CACHE_DIR, THUMBNAIL_CACHE_MAX_MB};
+use anyhow::Result;
+use lazy_static::lazy_static;
+use log::{info, warn};
+use reqwest::Client;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::sync::Mutex;
+
+/// Content type stored next to the image, thumbnails are not always JPEGs
+const CONTENT_TYPE_SUFFIX: &str = ".type";
+const FALLBACK_CONTENT_TYPE: &str = "image/jpeg";
+const THUMBNAIL_ORIGIN: &str = "https://i.ytimg.com";
+
+lazy_static! {
+    pub static ref THUMBNAIL_CACHE: ThumbnailCache = ThumbnailCache::new(
+        PathBuf::from(&*THUMBNAIL_CACHE_DIR),
+        *THUMBNAIL_CACHE_MAX_MB * 1024 * 1024
+    );
+}
+
+/// Original YouTube thumbnail of a video, the same one the search results show
+pub fn thumbnail_url(video_id: &str) -> String {
+    thumbnail_url_at(THUMBNAIL_ORIGIN, video_id)
+}
+
+fn thumbnail_url_at(origin: &str, video_id: &str) -> String {
+    format!("{}/vi/{}/mqdefault.jpg", origin, video_id)
+}
+
+pub struct Thumbnail {
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Thumbnails on disk, evicting the least recently served ones once the directory
+/// grows beyond `max_bytes`
+pub struct ThumbnailCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    origin: String,
+    client: Client,
+    // Serializes eviction so two concurrent downloads don't both walk the directory. An
+    // async lock, a panicking eviction can't poison it for all later downloads.
+    eviction: Mutex<()>,
+}
+
+impl ThumbnailCache {
+    pub fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        ThumbnailCache {
+            dir,
+            max_bytes,
+            origin: THUMBNAIL_ORIGIN.to_string(),
+            client: Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            eviction: Mutex::new(()),
+        }
+    }
+
+    /// Downloads the thumbnails from `origin` instead of YouTube, e.g. a stub server
+    pub fn with_origin(mut self, origin: &str) -> Self {
+        self.origin = origin.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Cached thumbnail of a video, downloaded on the first request
+    pub async fn get(&self, video_id: &str) -> Result<Thumbnail> {
+        if !is_cacheable_id(video_id) {
+            return Err(anyhow::anyhow!("Invalid video id: {video_id}"));
+        }
+        if let Some(thumbnail) = self.read(video_id).await {
+            return Ok(thumbnail);
+        }
+
+        let response = self
+            .client
+            .get(thumbnail_url_at(&self.origin, video_id))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Thumbnail of {video_id} returned status {}",
+                response.status()
+            ));
+        }
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| value.starts_with("image/"))
+            .unwrap_or(FALLBACK_CONTENT_TYPE)
+            .to_string();
+        let bytes = response.bytes().await?.to_vec();
+
+        let thumbnail = Thumbnail {
+            content_type,
+            bytes,
+        };
+        if let Err(e) = self.write(video_id, &thumbnail).await {
+            warn!("Failed to cache thumbnail of {video_id}: {e:?}");
+        }
+        Ok(thumbnail)
+    }
+
+    fn image_path(&self, video_id: &str) -> PathBuf {
+        self.dir.join(video_id)
+    }
+
+    fn content_type_path(&self, video_id: &str) -> PathBuf {
+        self.dir.join(format!("{video_id}{CONTENT_TYPE_SUFFIX}"))
+    }
+
+    async fn read(&self, video_id: &str) -> Option<Thumbnail> {
+        let path = self.image_path(video_id);
+        let bytes = tokio::fs::read(&path).await.ok()?;
+        // The modification time is the last access for eviction
+        let touched = path.clone();
+        let _ = tokio::task::spawn_blocking(move || {
+            fs::File::options()
+                .append(true)
+                .open(&touched)?
+                .set_modified(SystemTime::now())
+        })
+        .await;
+        let content_type = tokio::fs::read_to_string(self.content_type_path(video_id))
+            .await
+            .unwrap_or_else(|_| FALLBACK_CONTENT_TYPE.to_string());
+        Some(Thumbnail {
+            content_type,
+            bytes,
+        })
+    }
+
+    async fn write(&self, video_id: &str, thumbnail: &Thumbnail) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        tokio::fs::write(self.content_type_path(video_id), &thumbnail.content_type).await?;
+        tokio::fs::write(self.image_path(video_id), &thumbnail.bytes).await?;
+        self.evict().await;
+        Ok(())
+    }
+
+    /// Deletes the least recently served thumbnails until the cache fits `max_bytes`
+    async fn evict(&self) {
+        let _guard = self.eviction.lock().await;
+        let dir = self.dir.clone();
+        let max_bytes = self.max_bytes;
+        if let Err(e) = tokio::task::spawn_blocking(move || evict_dir(&dir, max_bytes)).await {
+            warn!("Thumbnail eviction failed: {e:?}");
+        }
+    }
+}
+
+/// Walks the cache directory, so it runs on the blocking pool
+fn evict_dir(dir: &Path, max_bytes: u64) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut images: Vec<(SystemTime, u64, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| {
+            !entry
+                .file_name()
+                .to_string_lossy()
+                .ends_with(CONTENT_TYPE_SUFFIX)
+        })
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((metadata.modified().ok()?, metadata.len(), entry.path()))
+        })
+        .collect();
+    let mut total: u64 = images.iter().map(|(_, size, _)| size).sum();
+    if total <= max_bytes {
+        return;
+    }
+
+    images.sort_by_key(|(modified, _, _)| *modified);
+    let mut evicted = 0;
+    for (_, size, path) in images {
+        if total <= max_bytes {
+            break;
+        }
+        let mut content_type_path = path.clone().into_os_string();
+        content_type_path.push(CONTENT_TYPE_SUFFIX);
+        if fs::remove_file(&path).is_ok() {
+            let _ = fs::remove_file(content_type_path);
+            total -= size;
+            evicted += 1;
+        }
+    }
+    info!("Evicted {evicted} thumbnails, cache is at {total} bytes");
+}
+
+/// Video ids become file names, so only YouTube's id alphabet is accepted
+pub fn is_cacheable_id(video_id: &str) -> bool {
+    !video_id.is_empty()
+        && video_id.len() <= 32
+        && video_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Cache directory of one test, removed again when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "starchive-thumbnails-{}-{name}",
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn thumbnail(size: usize) -> Thumbnail {
+        Thumbnail {
+            content_type: "image/jpeg".to_string(),
+            bytes: vec![0; size],
+        }
+    }
+
+    fn set_last_served(cache: &ThumbnailCache, video_id: &str, seconds_ago: u64) {
+        fs::File::options()
+            .append(true)
+            .open(cache.image_path(video_id))
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(seconds_ago))
+            .unwrap();
+    }
+
+    async fn stub_thumbnail(server: &MockServer, video_id: &str, response: ResponseTemplate) {
+        Mock::given(method("GET"))
+            .and(path(format!("/vi/{video_id}/mqdefault.jpg")))
+            .respond_with(response)
+            .mount(server)
+            .await;
+    }
+
+    #[rocket::async_test]
+    async fn caches_within_the_limit_are_kept() {
+        let dir = TempDir::new("within-limit");
+        let cache = ThumbnailCache::new(dir.0.clone(), 250);
+        cache.write("aaaaaaaaaaa", &thumbnail(100)).await.unwrap();
+        cache.write("bbbbbbbbbbb", &thumbnail(100)).await.unwrap();
+
+        assert!(cache.read("aaaaaaaaaaa").await.is_some());
+        assert!(cache.read("bbbbbbbbbbb").await.is_some());
+    }
+
+    #[rocket::async_test]
+    async fn least_recently_served_thumbnails_are_evicted() {
+        let dir = TempDir::new("eviction");
+        let cache = ThumbnailCache::new(dir.0.clone(), 250);
+        cache.write("aaaaaaaaaaa", &thumbnail(100)).await.unwrap();
+        cache.write("bbbbbbbbbbb", &thumbnail(100)).await.unwrap();
+        set_last_served(&cache, "aaaaaaaaaaa", 30);
+        set_last_served(&cache, "bbbbbbbbbbb", 20);
+        // Serving the older one makes the other the least recently served
+        assert!(cache.read("aaaaaaaaaaa").await.is_some());
+
+        cache.write("ccccccccccc", &thumbnail(100)).await.unwrap();
+
+        assert!(cache.read("bbbbbbbbbbb").await.is_none());
+        assert!(!cache.content_type_path("bbbbbbbbbbb").exists());
+        assert!(cache.read("aaaaaaaaaaa").await.is_some());
+        assert!(cache.read("ccccccccccc").await.is_some());
+    }
+
+    #[rocket::async_test]
+    async fn eviction_frees_as_many_thumbnails_as_needed() {
+        let dir = TempDir::new("eviction-many");
+        let cache = ThumbnailCache::new(dir.0.clone(), 250);
+        for (i, video_id) in ["aaaaaaaaaaa", "bbbbbbbbbbb"].iter().enumerate() {
+            cache.write(video_id, &thumbnail(100)).await.unwrap();
+            set_last_served(&cache, video_id, 60 - i as u64);
+        }
+
+        cache.write("ccccccccccc", &thumbnail(200)).await.unwrap();
+
+        assert!(cache.read("aaaaaaaaaaa").await.is_none());
+        assert!(cache.read("bbbbbbbbbbb").await.is_none());
+        assert!(cache.read("ccccccccccc").await.is_some());
+    }
+
+    #[rocket::async_test]
+    async fn content_types_are_passed_through_and_cached() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/vi/aaaaaaaaaaa/mqdefault.jpg"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(b"webp".to_vec(), "image/webp"))
+            // The second request is served from disk
+            .expect(1)
+            .mount(&server)
+            .await;
+        let dir = TempDir::new("content-type");
+        let cache = ThumbnailCache::new(dir.0.clone(), 1024).with_origin(&server.uri());
+
+        for _ in 0..2 {
+            let thumbnail = cache.get("aaaaaaaaaaa").await.unwrap();
+            assert_eq!(thumbnail.content_type, "image/webp");
+            assert_eq!(thumbnail.bytes, b"webp");
+        }
+    }
+
+    #[rocket::async_test]
+    async fn non_image_content_types_fall_back_to_jpeg() {
+        let server = MockServer::start().await;
+        stub_thumbnail(
+            &server,
+            "aaaaaaaaaaa",
+            ResponseTemplate::new(200).set_body_raw(b"jpeg".to_vec(), "text/html"),
+        )
+        .await;
+        let dir = TempDir::new("fallback-type");
+        let cache = ThumbnailCache::new(dir.0.clone(), 1024).with_origin(&server.uri());
+
+        let thumbnail = cache.get("aaaaaaaaaaa").await.unwrap();
+        assert_eq!(thumbnail.content_type, FALLBACK_CONTENT_TYPE);
+    }
+
+    #[rocket::async_test]
+    async fn failed_downloads_are_not_cached() {
+        let server = MockServer::start().await;
+        stub_thumbnail(&server, "aaaaaaaaaaa", ResponseTemplate::new(404)).await;
+        let dir = TempDir::new("failed-download");
+        let cache = ThumbnailCache::new(dir.0.clone(), 1024).with_origin(&server.uri());
+
+        assert!(cache.get("aaaaaaaaaaa").await.is_err());
+        assert!(cache.read("aaaaaaaaaaa").await.is_none());
+    }
+
+    #[rocket::async_test]
+    async fn ids_that_are_no_file_names_are_rejected() {
+        let server = MockServer::start().await;
+        let dir = TempDir::new("invalid-id");
+        let cache = ThumbnailCache::new(dir.0.clone(), 1024).with_origin(&server.uri());
+
+        let too_long = "a".repeat(33);
+        for video_id in ["", "../secret", "a/b", "a.b", too_long.as_str()] {
+            assert!(!is_cacheable_id(video_id), "{video_id}");
+            assert!(cache.get(video_id).await.is_err(), "{video_id}");
+        }
+        assert!(server.received_requests().await.unwrap().is_empty());
+        assert!(is_cacheable_id("dQw4w9WgXcQ"));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::config::TRANSCRIPT_MIN_DELAY_MS;
+use lazy_static::lazy_static;
+use log::warn;
+use rand::Rng;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// First cooldown after YouTube starts blocking, doubled on every further block
+const BASE_COOLDOWN_SECONDS: i64 = 60;
+const MAX_COOLDOWN_SECONDS: i64 = 60 * 60;
+
+/// Error fragments `yt_transcript_rs` reports when YouTube throttles or blocks the IP
+const BLOCKED_MARKERS: &[&str] = &[
+    "429",
+    "Too Many Requests",
+    "TooManyRequests",
+    "RequestBlocked",
+    "IpBlocked",
+];
+
+lazy_static! {
+    pub static ref TRANSCRIPT_THROTTLE: TranscriptThrottle =
+        TranscriptThrottle::new(Duration::from_millis(*TRANSCRIPT_MIN_DELAY_MS));
+}
+
+/// Spaces out transcript fetches and pauses them for a while once YouTube blocks us.
+/// Metadata crawling is not affected.
+pub struct TranscriptThrottle {
+    min_delay: Duration,
+    state: Mutex<ThrottleState>,
+}
+
+#[derive(Default)]
+struct ThrottleState {
+    last_fetch: Option<Instant>,
+    cooldown_until: Option<i64>, // unix millis
+    consecutive_blocks: u32,
+}
+
+impl TranscriptThrottle {
+    pub fn new(min_delay: Duration) -> Self {
+        TranscriptThrottle {
+            min_delay,
+            state: Mutex::new(ThrottleState::default()),
+        }
+    }
+
+    /// Waits until the minimum delay (plus up to 50% jitter) since the last fetch passed
+    pub async fn wait_turn(&self) {
+        let wait = match self.state.lock() {
+            Ok(mut state) => {
+                let jitter_ms = rand::thread_rng().gen_range(0..=self.min_delay.as_millis() / 2);
+                let delay = self.min_delay + Duration::from_millis(jitter_ms as u64);
+                let wait = state
+                    .last_fetch
+                    .map(|last| delay.saturating_sub(last.elapsed()))
+                    .unwrap_or_default();
+                state.last_fetch = Some(Instant::now() + wait);
+                wait
+            }
+            Err(_) => Duration::ZERO,
+        };
+
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// End of the current cooldown in unix millis, None if fetching is allowed
+    pub fn cooldown_until(&self) -> Option<i64> {
+        let now = chrono::Utc::now().timestamp_millis();
+        self.state
+            .lock()
+            .ok()
+            .and_then(|state| state.cooldown_until)
+            .filter(|&until| until > now)
+    }
+
+    pub fn record_success(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.consecutive_blocks = 0;
+        }
+    }
+
+    /// Starts or extends the cooldown if `error` looks like YouTube blocking us.
+    /// Returns whether it did.
+    pub fn record_error(&self, error: &str) -> bool {
+        if !is_blocked_error(error) {
+            return false;
+        }
+
+        if let Ok(mut state) = self.state.lock() {
+            let cooldown = cooldown_seconds(state.consecutive_blocks);
+            state.consecutive_blocks += 1;
+            state.cooldown_until = Some(chrono::Utc::now().timestamp_millis() + cooldown * 1000);
+            warn!(
+                "YouTube is throttling transcript fetches, pausing captions for {}s",
+                cooldown
+            );
+        }
+        true
+    }
+}
+
+pub fn is_blocked_error(error: &str) -> bool {
+    BLOCKED_MARKERS.iter().any(|marker| error.contains(marker))
+}
+
+fn cooldown_seconds(consecutive_blocks: u32) -> i64 {
+    BASE_COOLDOWN_SECONDS
+        .saturating_mul(1i64 << consecutive_blocks.min(16))
+        .min(MAX_COOLDOWN_SECONDS)
+}
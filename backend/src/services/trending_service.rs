@@ -0,0 +1,239 @@
+use crate::config::{INDEX_NAMES, SEARCH_LOG_ENABLED};
+use crate::models::TrendingVideo;
+use crate::services::maintenance_throttle::MaintenanceThrottle;
+use crate::services::search_service::{self, SearchOptions, SortBy, SortOrder};
+use anyhow::Result;
+use elasticsearch::http::request::JsonBody;
+use elasticsearch::params::{Conflicts, Refresh};
+use elasticsearch::{
+    BulkParts, DeleteByQueryParts, Elasticsearch, IndexParts, SearchParts, UpdateByQueryParts,
+};
+use log::{error, info, warn};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Logged searches are only kept as long as the job looks back
+const SEARCH_LOG_RETENTION_DAYS: i64 = 7;
+/// Popular queries of the week a video is matched against
+const POPULAR_QUERY_COUNT: usize = 50;
+/// Videos counted per popular query, the best matching ones
+const VIDEOS_PER_QUERY: usize = 200;
+const MAX_TRENDING_VIDEOS: usize = 50;
+
+/// Stores a search for the weekly popular queries. Does nothing unless
+/// SEARCH_LOG_ENABLED is set, and never holds up the search itself.
+pub fn log_search(es_client: &Elasticsearch, query: &str) {
+    if !*SEARCH_LOG_ENABLED {
+        return;
+    }
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return;
+    }
+
+    let es_client = es_client.clone();
+    tokio::spawn(async move {
+        let result = es_client
+            .index(IndexParts::Index(INDEX_NAMES.search_log))
+            .body(json!({
+                "query": query,
+                "created_at": chrono::Utc::now().timestamp_millis()
+            }))
+            .send()
+            .await;
+        if let Err(e) = result {
+            warn!("Failed to log search: {e:?}");
+        }
+    });
+}
+
+/// Most searched queries since `since` (unix millis), most frequent first
+async fn popular_queries(es_client: &Elasticsearch, since: i64) -> Result<Vec<String>> {
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.search_log]))
+        .body(json!({
+            "size": 0,
+            "query": { "range": { "created_at": { "gte": since } } },
+            "aggs": {
+                "queries": { "terms": { "field": "query", "size": POPULAR_QUERY_COUNT } }
+            }
+        }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Search log aggregation failed: {}",
+            response.status_code()
+        ));
+    }
+
+    let json_response: Value = response.json().await?;
+    Ok(json_response["aggregations"]["queries"]["buckets"]
+        .as_array()
+        .map(|buckets| {
+            buckets
+                .iter()
+                .filter_map(|bucket| bucket["key"].as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Weekly job: counts for every video how many of the week's popular queries it matched,
+/// stores that as `recent_match_score` and prunes the search log under the `maintenance`
+/// throttle
+pub async fn update_recent_match_scores(
+    es_client: &Elasticsearch,
+    maintenance: &Arc<MaintenanceThrottle>,
+) {
+    if !*SEARCH_LOG_ENABLED {
+        return;
+    }
+    let since =
+        chrono::Utc::now().timestamp_millis() - SEARCH_LOG_RETENTION_DAYS * 24 * 60 * 60 * 1000;
+
+    let queries = match popular_queries(es_client, since).await {
+        Ok(queries) => queries,
+        Err(e) => {
+            error!("Failed to load popular queries: {e:?}");
+            return;
+        }
+    };
+
+    let mut scores: HashMap<String, i64> = HashMap::new();
+    let options = SearchOptions::natural(SortBy::Relevance, SortOrder::Desc);
+    for query in &queries {
+        match search_service::matching_video_ids(es_client, query, &options, VIDEOS_PER_QUERY).await
+        {
+            Ok(video_ids) => {
+                for video_id in video_ids {
+                    *scores.entry(video_id).or_insert(0) += 1;
+                }
+            }
+            Err(e) => warn!("Failed to match popular query '{query}': {e:?}"),
+        }
+    }
+
+    if let Err(e) = store_scores(es_client, &scores).await {
+        error!("Failed to store recent match scores: {e:?}");
+        return;
+    }
+    info!(
+        "Updated recent match scores of {} videos from {} popular queries",
+        scores.len(),
+        queries.len()
+    );
+
+    // Pruning waits for the next run while other maintenance holds the throttle
+    let run = match maintenance.try_start("prune search log") {
+        Ok(run) => run,
+        Err(running) => {
+            info!("Not pruning the search log during {}", running.name);
+            return;
+        }
+    };
+    if let Err(e) = es_client
+        .delete_by_query(DeleteByQueryParts::Index(&[INDEX_NAMES.search_log]))
+        .conflicts(Conflicts::Proceed)
+        .scroll_size(run.throttle().scroll_size())
+        .requests_per_second(run.throttle().requests_per_second())
+        .body(json!({ "query": { "range": { "created_at": { "lt": since } } } }))
+        .send()
+        .await
+    {
+        error!("Failed to prune the search log: {e:?}");
+    }
+}
+
+/// Replaces last week's scores: resets all of them, then writes the new ones
+async fn store_scores(es_client: &Elasticsearch, scores: &HashMap<String, i64>) -> Result<()> {
+    let response = es_client
+        .update_by_query(UpdateByQueryParts::Index(&[INDEX_NAMES.videos]))
+        .conflicts(Conflicts::Proceed)
+        .refresh(true)
+        .body(json!({
+            "query": { "range": { "recent_match_score": { "gt": 0 } } },
+            "script": { "source": "ctx._source.recent_match_score = 0", "lang": "painless" }
+        }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Resetting scores failed: {}",
+            response.status_code()
+        ));
+    }
+    if scores.is_empty() {
+        return Ok(());
+    }
+
+    let mut body: Vec<JsonBody<Value>> = Vec::with_capacity(scores.len() * 2);
+    for (video_id, score) in scores {
+        body.push(json!({ "update": { "_index": INDEX_NAMES.videos, "_id": video_id } }).into());
+        body.push(json!({ "doc": { "recent_match_score": score } }).into());
+    }
+    let response = es_client
+        .bulk(BulkParts::None)
+        .refresh(Refresh::True)
+        .body(body)
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Bulk score update failed: {}",
+            response.status_code()
+        ));
+    }
+    // Videos deleted since the search are expected to fail, nothing to report
+    Ok(())
+}
+
+/// Public videos with the highest `recent_match_score`, empty if search logging is off
+pub async fn get_trending_matches(
+    es_client: &Elasticsearch,
+    limit: usize,
+) -> Result<Vec<TrendingVideo>> {
+    if !*SEARCH_LOG_ENABLED {
+        return Ok(Vec::new());
+    }
+
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.videos]))
+        .body(json!({
+            "size": limit.min(MAX_TRENDING_VIDEOS),
+            "_source": ["video_id", "title", "channel_name", "recent_match_score"],
+            "query": {
+                "bool": {
+                    "filter": [{ "range": { "recent_match_score": { "gt": 0 } } }],
+                    "must_not": [
+                        { "term": { "hidden": true } },
+                        { "term": { "deleting": true } }
+                    ]
+                }
+            },
+            "sort": [
+                // The field only exists once the job ran
+                { "recent_match_score": { "order": "desc", "unmapped_type": "long" } },
+                { "upload_date": { "order": "desc", "unmapped_type": "long" } }
+            ]
+        }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Trending matches search failed: {}",
+            response.status_code()
+        ));
+    }
+
+    let json_response: Value = response.json().await?;
+    Ok(json_response["hits"]["hits"]
+        .as_array()
+        .map(|hits| {
+            hits.iter()
+                .filter_map(|hit| serde_json::from_value(hit["_source"].clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default())
+}
@@ -1,18 +1,48 @@
-use anyhow::Result;
-use elasticsearch::{Elasticsearch, SearchParts};
+use crate::config::INDEX_NAMES;
+use crate::models::{
+    caption_segment, CaptionLanguage, PublicChannel, TranscriptLine, UploadMonth, VideoMetadata,
+    VideoTranscript,
+};
+use crate::services::archive_generation::ArchiveGeneration;
+use crate::services::{maintenance_service, monitor_tag_service};
+use anyhow::{Context, Result};
+use elasticsearch::{Elasticsearch, ExistsParts, GetParts, SearchParts};
 use serde_json::{json, Value};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Aggregation bucket of captions without a language field
+const UNKNOWN_LANGUAGE: &str = "unknown";
+/// Channels offered as search filters
+const MAX_CHANNELS: usize = 500;
+/// Videos listed on the public page of a channel
+const MAX_CHANNEL_VIDEOS: usize = 500;
+/// The channel directory is aggregated over every video, so it is reused for a while
+/// unless the archive changes
+const DIRECTORY_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+lazy_static::lazy_static! {
+    /// Channel directory without tags, with the archive generation it was computed at
+    static ref CHANNEL_DIRECTORY: Mutex<Option<(u64, Instant, Vec<PublicChannel>)>> =
+        Mutex::new(None);
+}
 
 pub async fn list_all_videos(es_client: &Elasticsearch) -> Result<Vec<String>> {
     let search_body = json!({
         "size": 10000,
         "query": {
-            "match_all": {}
+            "bool": {
+                "must_not": [
+                    { "term": { "hidden": true } },
+                    { "term": { "deleting": true } }
+                ]
+            }
         },
         "_source": false
     });
 
     let response = es_client
-        .search(SearchParts::Index(&["youtube_videos"]))
+        .search(SearchParts::Index(&[INDEX_NAMES.videos]))
         .body(search_body)
         .send()
         .await?;
@@ -37,3 +67,502 @@ pub async fn list_all_videos(es_client: &Elasticsearch) -> Result<Vec<String>> {
 
     Ok(video_ids)
 }
+
+/// Channels with public videos, most videos first, with the public tags of their monitor
+/// and their uploads per month
+pub async fn list_public_channels(
+    es_client: &Elasticsearch,
+    generation: &ArchiveGeneration,
+) -> Result<Vec<PublicChannel>> {
+    let generation = generation.current();
+    let mut channels = match cached_directory(generation) {
+        Some(channels) => channels,
+        None => {
+            let channels = aggregate_public_channels(es_client).await?;
+            if let Ok(mut directory) = CHANNEL_DIRECTORY.lock() {
+                *directory = Some((generation, Instant::now(), channels.clone()));
+            }
+            channels
+        }
+    };
+
+    // Tags change without touching the archive, so they are never cached
+    let mut channel_tags = monitor_tag_service::public_channel_tags().await;
+    for channel in &mut channels {
+        channel.tags = channel_tags.remove(&channel.channel_id).unwrap_or_default();
+    }
+    Ok(channels)
+}
+
+fn cached_directory(generation: u64) -> Option<Vec<PublicChannel>> {
+    let directory = CHANNEL_DIRECTORY.lock().ok()?;
+    directory
+        .as_ref()
+        .filter(|(cached_generation, stored_at, _)| {
+            *cached_generation == generation && stored_at.elapsed() < DIRECTORY_CACHE_TTL
+        })
+        .map(|(_, _, channels)| channels.clone())
+}
+
+async fn aggregate_public_channels(es_client: &Elasticsearch) -> Result<Vec<PublicChannel>> {
+    let search_body = json!({
+        "size": 0,
+        "query": {
+            "bool": {
+                "must_not": [
+                    { "term": { "hidden": true } },
+                    { "term": { "deleting": true } }
+                ]
+            }
+        },
+        "aggs": {
+            "channels": {
+                "terms": { "field": "channel_id.keyword", "size": MAX_CHANNELS },
+                "aggs": {
+                    "name": { "terms": { "field": "channel_name.keyword", "size": 1 } },
+                    // Videos without a known upload date would stretch it back to 1970
+                    "uploads": {
+                        "filter": { "range": { "upload_date": { "gt": 0 } } },
+                        "aggs": {
+                            "months": {
+                                "date_histogram": {
+                                    // Upload dates are unix seconds, dates are read as millis
+                                    "script": {
+                                        "source": "doc['upload_date'].value * 1000",
+                                        "lang": "painless"
+                                    },
+                                    "calendar_interval": "month",
+                                    "min_doc_count": 0
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.videos]))
+        .body(search_body)
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Elasticsearch channel aggregation failed with status: {}",
+            response.status_code()
+        ));
+    }
+
+    let json_response: Value = response.json().await?;
+    Ok(json_response["aggregations"]["channels"]["buckets"]
+        .as_array()
+        .map(|buckets| {
+            buckets
+                .iter()
+                .filter_map(|bucket| {
+                    let channel_id = bucket["key"].as_str()?.to_string();
+                    Some(PublicChannel {
+                        channel_name: bucket["name"]["buckets"][0]["key"]
+                            .as_str()
+                            .unwrap_or(&channel_id)
+                            .to_string(),
+                        channel_id,
+                        video_count: bucket["doc_count"].as_i64().unwrap_or(0),
+                        tags: Vec::new(),
+                        upload_histogram: upload_months(&bucket["uploads"]["months"]),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Month buckets of a date histogram, the gaps between uploads filled with zero counts
+fn upload_months(histogram: &Value) -> Vec<UploadMonth> {
+    histogram["buckets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|bucket| {
+            let start = chrono::DateTime::from_timestamp_millis(bucket["key"].as_i64()?)?;
+            Some(UploadMonth {
+                month: start.format("%Y-%m").to_string(),
+                count: bucket["doc_count"].as_i64().unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+/// Public videos of a channel for its public page, newest first
+pub async fn list_public_channel_videos(
+    es_client: &Elasticsearch,
+    channel_id: &str,
+) -> Result<Vec<VideoMetadata>> {
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.videos]))
+        .body(json!({
+            "size": MAX_CHANNEL_VIDEOS,
+            "query": {
+                "bool": {
+                    "filter": [{ "term": { "channel_id.keyword": channel_id } }],
+                    "must_not": [
+                        { "term": { "hidden": true } },
+                        { "term": { "deleting": true } }
+                    ]
+                }
+            },
+            "sort": [{ "upload_date": "desc" }],
+            "_source": { "excludes": ["crawl_history", "previous"] }
+        }))
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Elasticsearch channel video listing failed with status: {}",
+            response.status_code()
+        ));
+    }
+
+    let json_response: Value = response.json().await?;
+    Ok(json_response["hits"]["hits"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|hit| serde_json::from_value(hit["_source"].clone()).ok())
+        .collect())
+}
+
+/// IDs of all videos of the given channels, hidden ones included. Scrolls, channels
+/// may have more videos than one search returns.
+pub async fn video_ids_of_channels(
+    es_client: &Elasticsearch,
+    channel_ids: &[String],
+) -> Result<Vec<String>> {
+    let mut video_ids = Vec::new();
+    maintenance_service::scroll_index(
+        es_client,
+        INDEX_NAMES.videos,
+        json!({ "terms": { "channel_id.keyword": channel_ids } }),
+        false,
+        |hit| {
+            if let Some(id) = hit["_id"].as_str() {
+                video_ids.push(id.to_string());
+            }
+            Ok(())
+        },
+    )
+    .await
+    .context("Elasticsearch channel video lookup failed")?;
+    Ok(video_ids)
+}
+
+/// Whether the video is indexed, hidden or not
+pub async fn is_indexed_video(es_client: &Elasticsearch, video_id: &str) -> Result<bool> {
+    let response = es_client
+        .exists(ExistsParts::IndexId(INDEX_NAMES.videos, video_id))
+        .send()
+        .await?;
+    match response.status_code().as_u16() {
+        404 => Ok(false),
+        _ if response.status_code().is_success() => Ok(true),
+        status => Err(anyhow::anyhow!("Elasticsearch returned {status}")),
+    }
+}
+
+/// Whether the video is indexed and may be shown publicly
+pub async fn is_public_video(es_client: &Elasticsearch, video_id: &str) -> Result<bool> {
+    let response = es_client
+        .get(GetParts::IndexId(INDEX_NAMES.videos, video_id))
+        ._source_includes(&["hidden", "deleting"])
+        .send()
+        .await?;
+    if !response.status_code().is_success() {
+        return Ok(false);
+    }
+    let video: Value = response.json().await?;
+    let source = &video["_source"];
+    Ok(!source["hidden"].as_bool().unwrap_or(false)
+        && !source["deleting"].as_bool().unwrap_or(false))
+}
+
+/// Caption languages of a video: indexed caption counts per language from a terms
+/// aggregation, joined with the caption tracks recorded on the video at crawl time.
+/// Tracks without indexed captions are listed with a count of 0.
+pub async fn get_video_languages(
+    es_client: &Elasticsearch,
+    video_id: &str,
+) -> Result<Vec<CaptionLanguage>> {
+    let video = es_client
+        .get(GetParts::IndexId(INDEX_NAMES.videos, video_id))
+        .send()
+        .await?;
+    if !video.status_code().is_success() {
+        return Ok(vec![]);
+    }
+    let video: Value = video.json().await?;
+    let metadata: VideoMetadata = serde_json::from_value(video["_source"].clone())?;
+    if metadata.hidden || metadata.deleting {
+        return Ok(vec![]);
+    }
+
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.captions]))
+        .body(json!({
+            "size": 0,
+            "query": {
+                "term": { "video_id": video_id }
+            },
+            "aggs": {
+                "languages": {
+                    "terms": {
+                        "field": "language",
+                        "missing": UNKNOWN_LANGUAGE,
+                        "size": 100
+                    }
+                }
+            }
+        }))
+        .send()
+        .await?;
+
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Elasticsearch search failed with status: {}",
+            response.status_code()
+        ));
+    }
+
+    let json_response: Value = response.json().await?;
+    let mut counts: Vec<(String, i64)> = json_response["aggregations"]["languages"]["buckets"]
+        .as_array()
+        .map(|buckets| {
+            buckets
+                .iter()
+                .filter_map(|bucket| {
+                    Some((
+                        bucket["key"].as_str()?.to_string(),
+                        bucket["doc_count"].as_i64().unwrap_or(0),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut languages: Vec<CaptionLanguage> = metadata
+        .caption_tracks
+        .into_iter()
+        .map(|track| {
+            let caption_count = counts
+                .iter()
+                .position(|(code, _)| *code == track.language_code)
+                .map(|i| counts.remove(i).1)
+                .unwrap_or(0);
+            CaptionLanguage {
+                language_code: track.language_code,
+                language: track.language,
+                is_generated: Some(track.is_generated),
+                caption_count,
+            }
+        })
+        .collect();
+
+    // Captions indexed before tracks were recorded
+    languages.extend(
+        counts
+            .into_iter()
+            .map(|(code, caption_count)| CaptionLanguage {
+                language: code.clone(),
+                language_code: code,
+                is_generated: None,
+                caption_count,
+            }),
+    );
+
+    languages.sort_by_key(|language| std::cmp::Reverse(language.caption_count));
+    Ok(languages)
+}
+
+/// Indexed captions of a video in `language`, or in the language with the most captions.
+/// Only the captions of `segment` if given, see `caption_segment`. None for unknown and
+/// hidden videos and videos without captions.
+pub async fn get_transcript(
+    es_client: &Elasticsearch,
+    video_id: &str,
+    language: Option<&str>,
+    segment: Option<i64>,
+) -> Result<Option<VideoTranscript>> {
+    let languages = get_video_languages(es_client, video_id).await?;
+    let language_code = match language {
+        Some(code) => code.to_string(),
+        None => match languages.iter().find(|l| l.caption_count > 0) {
+            Some(language) => language.language_code.clone(),
+            None => return Ok(None),
+        },
+    };
+    if !languages.iter().any(|l| l.language_code == language_code) {
+        return Ok(None);
+    }
+
+    let language_filter = if language_code == UNKNOWN_LANGUAGE {
+        json!({ "bool": { "must_not": { "exists": { "field": "language" } } } })
+    } else {
+        json!({ "term": { "language": language_code } })
+    };
+
+    let mut body = json!({
+        "size": 10000,
+        "_source": ["text", "start_time", "end_time"],
+        "sort": [{ "start_time": { "order": "asc" } }],
+        "query": {
+            "bool": {
+                "filter": [
+                    { "term": { "video_id": video_id } },
+                    language_filter
+                ]
+            }
+        },
+        // Taken from the start times so captions that weren't backfilled yet count too
+        "aggs": { "last_start": { "max": { "field": "start_time" } } }
+    });
+    if let Some(segment) = segment {
+        // A post filter leaves the aggregation over all segments
+        body["post_filter"] = json!({ "term": { "segment": segment } });
+    }
+
+    let response = es_client
+        .search(SearchParts::Index(&[INDEX_NAMES.captions]))
+        .body(body)
+        .send()
+        .await?;
+
+    if !response.status_code().is_success() {
+        return Err(anyhow::anyhow!(
+            "Elasticsearch search failed with status: {}",
+            response.status_code()
+        ));
+    }
+
+    let json_response: Value = response.json().await?;
+    let lines = json_response["hits"]["hits"]
+        .as_array()
+        .map(|hits| {
+            hits.iter()
+                .filter_map(|hit| serde_json::from_value(hit["_source"].clone()).ok())
+                .collect::<Vec<TranscriptLine>>()
+        })
+        .unwrap_or_default();
+
+    let segment_count = json_response["aggregations"]["last_start"]["value"]
+        .as_f64()
+        .map_or(0, |last_start| caption_segment(last_start) + 1);
+
+    Ok(Some(VideoTranscript {
+        video_id: video_id.to_string(),
+        language_code,
+        lines,
+        segment,
+        segment_count,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::admin_service;
+    use crate::testsupport::{maintenance_run, search_hits, MockElasticsearch};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    /// A page of video hits continued by the scroll `next`
+    fn scroll_page(video_ids: &[&str], next: &str) -> Value {
+        let mut page = search_hits(video_ids.iter().map(|id| (*id, json!({}))).collect());
+        page["_scroll_id"] = json!(next);
+        page
+    }
+
+    #[rocket::async_test]
+    async fn channel_videos_are_read_past_the_first_page() {
+        let es = MockElasticsearch::start().await;
+        let search = format!("/{}/_search", INDEX_NAMES.videos);
+        es.stub("POST", &search, scroll_page(&["aaaaaaaaaaa"], "page-2"))
+            .await;
+        es.stub_body(
+            "POST",
+            "/_search/scroll",
+            |body| body["scroll_id"] == "page-2",
+            scroll_page(&["bbbbbbbbbbb", "ccccccccccc"], "page-3"),
+        )
+        .await;
+        es.stub_body(
+            "POST",
+            "/_search/scroll",
+            |body| body["scroll_id"] == "page-3",
+            scroll_page(&[], "page-3"),
+        )
+        .await;
+
+        let channels = vec!["UCaaaaaaaaaaaaaaaaaaaaaa".to_string()];
+        let video_ids = video_ids_of_channels(&es.client, &channels).await.unwrap();
+
+        assert_eq!(video_ids, ["aaaaaaaaaaa", "bbbbbbbbbbb", "ccccccccccc"]);
+        let query = &es.bodies("POST", &search).await[0]["query"];
+        assert_eq!(
+            *query,
+            json!({ "terms": { "channel_id.keyword": channels } })
+        );
+    }
+
+    #[rocket::async_test]
+    async fn deleted_videos_leave_the_cached_channel_directory() {
+        let es = MockElasticsearch::start().await;
+        // Unique, the directory is cached process-wide
+        let channel_id = "UCdirectoryCacheTest0001";
+        let deleted = Arc::new(AtomicBool::new(false));
+        let deleted_flag = deleted.clone();
+        es.stub_fn(
+            "POST",
+            &format!("/{}/_search", INDEX_NAMES.videos),
+            move |_| {
+                let buckets = if deleted_flag.load(Ordering::SeqCst) {
+                    json!([])
+                } else {
+                    json!([{ "key": channel_id, "doc_count": 1 }])
+                };
+                json!({ "aggregations": { "channels": { "buckets": buckets } } })
+            },
+        )
+        .await;
+        es.stub(
+            "POST",
+            &format!("/{}/_update/dQw4w9WgXcQ", INDEX_NAMES.videos),
+            json!({ "result": "updated" }),
+        )
+        .await;
+        es.stub(
+            "POST",
+            &format!("/{}/_delete_by_query", INDEX_NAMES.captions),
+            json!({ "task": "node:1" }),
+        )
+        .await;
+        let listed = |channels: &[PublicChannel]| {
+            channels
+                .iter()
+                .any(|channel| channel.channel_id == channel_id)
+        };
+
+        let generation = ArchiveGeneration::new();
+        assert!(listed(
+            &list_public_channels(&es.client, &generation).await.unwrap()
+        ));
+
+        admin_service::delete_video(&es.client, &generation, "dQw4w9WgXcQ", maintenance_run())
+            .await
+            .unwrap();
+        deleted.store(true, Ordering::SeqCst);
+
+        assert!(!listed(
+            &list_public_channels(&es.client, &generation).await.unwrap()
+        ));
+    }
+}
@@ -0,0 +1,306 @@
+//! HTTP access to YouTube (Data API and transcripts), optionally routed through the
+//! proxies configured in `YOUTUBE_PROXY_URL`.
+
+use crate::config::{YOUTUBE_PROXY_URLS, YOUTUBE_REQUEST_TIMEOUT_SECONDS};
+use crate::models::ProxyStatus;
+use crate::services::raw_capture::RAW_CAPTURES;
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use reqwest::{Client, Proxy};
+use serde_json::Value;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use yt_transcript_rs::api::YouTubeTranscriptApi;
+
+/// Consecutive failures after which a proxy is taken out of rotation
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+const PROXY_DISABLE_SECONDS: i64 = 5 * 60;
+
+lazy_static! {
+    pub static ref YOUTUBE_PROXIES: ProxyPool = ProxyPool::new(&YOUTUBE_PROXY_URLS);
+}
+
+/// Client and transcript API of one route, either a proxy or the direct connection
+#[derive(Clone)]
+pub struct Route {
+    proxy: Option<usize>,
+    pub client: Client,
+    pub transcript_api: Arc<YouTubeTranscriptApi>,
+}
+
+impl Route {
+    pub fn is_proxied(&self) -> bool {
+        self.proxy.is_some()
+    }
+}
+
+struct ProxyEntry {
+    url: String,
+    route: Route,
+    stats: Mutex<ProxyStats>,
+}
+
+#[derive(Default)]
+struct ProxyStats {
+    requests: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    disabled_until: Option<i64>, // unix millis
+}
+
+/// Round-robin rotation over the configured proxies. Proxies failing repeatedly are
+/// skipped for a while; without any usable proxy requests go out directly.
+pub struct ProxyPool {
+    direct: Route,
+    proxies: Vec<ProxyEntry>,
+    next: AtomicUsize,
+}
+
+impl ProxyPool {
+    pub fn new(urls: &[String]) -> Self {
+        let proxies: Vec<ProxyEntry> = urls
+            .iter()
+            .enumerate()
+            .filter_map(|(i, url)| match build_route(Some((i, url))) {
+                Ok(route) => Some(ProxyEntry {
+                    url: url.clone(),
+                    route,
+                    stats: Mutex::new(ProxyStats::default()),
+                }),
+                Err(e) => {
+                    error!("Ignoring invalid YouTube proxy {}: {e:?}", redact(url));
+                    None
+                }
+            })
+            .collect();
+        info!("Using {} proxies for YouTube requests", proxies.len());
+
+        ProxyPool {
+            direct: build_route(None).expect("Failed to create YouTube client"),
+            proxies,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// The next usable proxy in rotation, or the direct connection
+    pub fn next_route(&self) -> Route {
+        let now = chrono::Utc::now().timestamp_millis();
+        for _ in 0..self.proxies.len() {
+            let index = self.next.fetch_add(1, Ordering::Relaxed) % self.proxies.len();
+            let entry = &self.proxies[index];
+            if let Ok(mut stats) = entry.stats.lock() {
+                if stats.disabled_until.is_some_and(|until| until > now) {
+                    continue;
+                }
+                stats.disabled_until = None;
+                stats.requests += 1;
+            }
+            return entry.route.clone();
+        }
+
+        if !self.proxies.is_empty() {
+            warn!("All YouTube proxies are disabled, connecting directly");
+        }
+        self.direct.clone()
+    }
+
+    pub fn report(&self, route: &Route, success: bool) {
+        let Some(entry) = route.proxy.and_then(|i| self.proxies.get(i)) else {
+            return;
+        };
+        let Ok(mut stats) = entry.stats.lock() else {
+            return;
+        };
+
+        if success {
+            stats.consecutive_failures = 0;
+            return;
+        }
+        stats.failures += 1;
+        stats.consecutive_failures += 1;
+        if stats.consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+            stats.consecutive_failures = 0;
+            stats.disabled_until =
+                Some(chrono::Utc::now().timestamp_millis() + PROXY_DISABLE_SECONDS * 1000);
+            warn!(
+                "YouTube proxy {} failed {} times in a row, disabled for {}s",
+                redact(&entry.url),
+                MAX_CONSECUTIVE_FAILURES,
+                PROXY_DISABLE_SECONDS
+            );
+        }
+    }
+
+    pub fn status(&self) -> Vec<ProxyStatus> {
+        let now = chrono::Utc::now().timestamp_millis();
+        self.proxies
+            .iter()
+            .filter_map(|entry| {
+                let stats = entry.stats.lock().ok()?;
+                let disabled_until = stats.disabled_until.filter(|&until| until > now);
+                Some(ProxyStatus {
+                    url: redact(&entry.url),
+                    healthy: disabled_until.is_none(),
+                    requests: stats.requests,
+                    failures: stats.failures,
+                    disabled_until,
+                })
+            })
+            .collect()
+    }
+}
+
+/// GET request against the YouTube Data API, through the next proxy in rotation
+pub async fn get_json(url: &str) -> anyhow::Result<Value> {
+    let route = YOUTUBE_PROXIES.next_route();
+    let result = with_timeout("Data API request", async {
+        route.client.get(url).send().await?.json::<Value>().await
+    })
+    .await;
+    YOUTUBE_PROXIES.report(&route, matches!(result, Ok(Ok(_))));
+    Ok(result??)
+}
+
+/// `get_json` for a response about a single video, captured for debugging if
+/// `CAPTURE_RAW_RESPONSES` is on
+pub async fn get_video_json(url: &str, video_id: &str, endpoint: &str) -> anyhow::Result<Value> {
+    let response = get_json(url).await?;
+    RAW_CAPTURES.capture(video_id, endpoint, &response);
+    Ok(response)
+}
+
+/// Body of a plain YouTube page or feed, an error for non-success statuses
+pub async fn get_text(url: &str) -> anyhow::Result<String> {
+    let route = YOUTUBE_PROXIES.next_route();
+    let result = with_timeout("feed request", async {
+        let response = route.client.get(url).send().await?;
+        let status = response.status();
+        Ok::<_, reqwest::Error>((status, response.text().await?))
+    })
+    .await;
+    // An error status is YouTube's answer, not a failure of the proxy
+    YOUTUBE_PROXIES.report(&route, matches!(result, Ok(Ok(_))));
+    let (status, body) = result??;
+    if !status.is_success() {
+        return Err(anyhow::anyhow!("YouTube returned {status} for {url}"));
+    }
+    Ok(body)
+}
+
+/// A YouTube operation that did not finish within `YOUTUBE_REQUEST_TIMEOUT_SECONDS`.
+/// Usually a flaky network path, so the operation is worth retrying later.
+#[derive(Debug)]
+pub struct NetworkTimeout {
+    pub operation: &'static str,
+    pub after: Duration,
+}
+
+impl std::fmt::Display for NetworkTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "YouTube {} timed out after {:?}",
+            self.operation, self.after
+        )
+    }
+}
+
+impl std::error::Error for NetworkTimeout {}
+
+pub fn request_timeout() -> Duration {
+    Duration::from_secs(*YOUTUBE_REQUEST_TIMEOUT_SECONDS)
+}
+
+/// Runs a YouTube operation, giving up after the request timeout. Logs how long finished
+/// operations took so the limit can be tuned.
+pub async fn with_timeout<T>(
+    operation: &'static str,
+    future: impl Future<Output = T>,
+) -> Result<T, NetworkTimeout> {
+    let limit = request_timeout();
+    let started = Instant::now();
+    let output = tokio::time::timeout(limit, future)
+        .await
+        .map_err(|_| NetworkTimeout {
+            operation,
+            after: limit,
+        })?;
+    info!("YouTube {operation} took {:?}", started.elapsed());
+    Ok(output)
+}
+
+/// Whether an error is a timed out YouTube operation or request
+pub fn is_timeout(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause.is::<NetworkTimeout>()
+            || cause
+                .downcast_ref::<reqwest::Error>()
+                .is_some_and(|e| e.is_timeout())
+    })
+}
+
+/// Error object the Data API returns in place of a result
+#[derive(Debug)]
+pub struct ApiError {
+    pub reason: String,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn is_quota_exceeded(&self) -> bool {
+        matches!(
+            self.reason.as_str(),
+            "quotaExceeded" | "dailyLimitExceeded" | "rateLimitExceeded"
+        )
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "YouTube API error ({}): {}", self.reason, self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+pub fn check_api_error(response: &Value) -> Result<(), ApiError> {
+    let error = &response["error"];
+    if error.is_null() {
+        return Ok(());
+    }
+    Err(ApiError {
+        reason: error["errors"][0]["reason"]
+            .as_str()
+            .unwrap_or("unknown")
+            .to_string(),
+        message: error["message"].as_str().unwrap_or_default().to_string(),
+    })
+}
+
+fn build_route(proxy: Option<(usize, &String)>) -> anyhow::Result<Route> {
+    let mut builder = Client::builder().timeout(request_timeout());
+    if let Some((_, url)) = proxy {
+        builder = builder.proxy(Proxy::all(url.as_str())?);
+    }
+    let client = builder.build()?;
+    let transcript_api = YouTubeTranscriptApi::new(None, None, Some(client.clone()))
+        .map_err(|e| anyhow::anyhow!("Failed to create YouTubeTranscriptApi: {e:?}"))?;
+
+    Ok(Route {
+        proxy: proxy.map(|(i, _)| i),
+        client,
+        transcript_api: Arc::new(transcript_api),
+    })
+}
+
+/// Hides proxy credentials in logs and the admin endpoint
+fn redact(url: &str) -> String {
+    match (url.find("://"), url.rfind('@')) {
+        (Some(scheme_end), Some(at)) if at > scheme_end => {
+            format!("{}***{}", &url[..scheme_end + 3], &url[at..])
+        }
+        _ => url.to_string(),
+    }
+}
@@ -0,0 +1,285 @@
+//! Stub Elasticsearch for the tests of services that talk to the cluster. A local HTTP
+//! server answers the requests of a real client with canned responses and records them,
+//! so tests can assert on the request bodies the services send.
+
+use crate::config::{create_elasticsearch_client_for, INDEX_NAMES};
+use crate::models::OptOutRequest;
+use crate::services::api_key_service::ApiKeyUsageTracker;
+use crate::services::archive_generation::ArchiveGeneration;
+use crate::services::crawler::VideoQueue;
+use crate::services::maintenance_throttle::{MaintenanceRun, MaintenanceThrottle};
+use crate::services::optout_service;
+use crate::services::rate_limiter::RateLimiter;
+use crate::AppState;
+use elasticsearch::Elasticsearch;
+use serde_json::Value;
+use std::sync::{Arc, Once};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio_cron_scheduler::JobScheduler;
+use wiremock::matchers::{method, path};
+use wiremock::{Match, Mock, MockServer, Request, ResponseTemplate};
+
+/// Sets the environment the config statics read, before any test reads them. YouTube is
+/// simulated, nothing may leave the machine.
+pub fn init_env() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        std::env::set_var("LANGUAGE_PRIORITY", "en,en-GB,de");
+        std::env::set_var("ADMIN_TOKEN", "test-admin-token");
+        std::env::set_var("DEBUG", "true");
+        std::env::set_var("SIMULATION_MODE", "true");
+    });
+}
+
+/// A maintenance throttle of its own that doesn't pause between batches
+pub fn unpaced_maintenance() -> Arc<MaintenanceThrottle> {
+    Arc::new(MaintenanceThrottle::new(100, Duration::ZERO))
+}
+
+/// The run of a heavy operation under a throttle of its own
+pub fn maintenance_run() -> MaintenanceRun {
+    unpaced_maintenance()
+        .try_start("test")
+        .expect("a new throttle is free")
+}
+
+/// State of a server talking to `es_client`, without the startup work of
+/// `create_app_state`: no migrations and no scheduled jobs
+pub async fn app_state(es_client: Elasticsearch) -> AppState {
+    AppState {
+        es_client,
+        scheduler: Mutex::new(JobScheduler::new().await.expect("a job scheduler")),
+        video_queue: Arc::new(VideoQueue::new()),
+        report_limiter: RateLimiter::new(10, 60 * 60),
+        feedback_limiter: RateLimiter::new(120, 60),
+        anonymous_limiter: RateLimiter::new(60, 60),
+        api_key_usage: Arc::new(ApiKeyUsageTracker::new()),
+        generation: Arc::new(ArchiveGeneration::new()),
+        maintenance: unpaced_maintenance(),
+    }
+}
+
+pub struct MockElasticsearch {
+    server: MockServer,
+    pub client: Elasticsearch,
+}
+
+/// Matches requests whose JSON body passes the predicate
+struct JsonBody<F>(F);
+
+impl<F> Match for JsonBody<F>
+where
+    F: Fn(&Value) -> bool + Send + Sync,
+{
+    fn matches(&self, request: &Request) -> bool {
+        serde_json::from_slice::<Value>(&request.body).is_ok_and(|body| (self.0)(&body))
+    }
+}
+
+fn json_response(status: u16, body: Value) -> ResponseTemplate {
+    ResponseTemplate::new(status)
+        .insert_header("x-elastic-product", "Elasticsearch")
+        .set_body_json(body)
+}
+
+impl MockElasticsearch {
+    /// A server without stubs, unstubbed requests are answered with 404
+    pub async fn start() -> Self {
+        init_env();
+        let server = MockServer::start().await;
+        let client =
+            create_elasticsearch_client_for(&server.uri()).expect("client for the stub server");
+        MockElasticsearch { server, client }
+    }
+
+    /// Answers `method` requests to `path`, e.g. `/youtube_videos/_count`, with `response`
+    pub async fn stub(&self, http_method: &str, request_path: &str, response: Value) {
+        self.stub_status(http_method, request_path, 200, response)
+            .await;
+    }
+
+    /// Like `stub`, with a status other than 200
+    pub async fn stub_status(
+        &self,
+        http_method: &str,
+        request_path: &str,
+        status: u16,
+        response: Value,
+    ) {
+        Mock::given(method(http_method))
+            .and(path(request_path))
+            .respond_with(json_response(status, response))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Like `stub`, only for requests whose JSON body passes `matches`. Stubs of the same
+    /// path are tried in the order they were added.
+    pub async fn stub_body(
+        &self,
+        http_method: &str,
+        request_path: &str,
+        matches: impl Fn(&Value) -> bool + Send + Sync + 'static,
+        response: Value,
+    ) {
+        Mock::given(method(http_method))
+            .and(path(request_path))
+            .and(JsonBody(matches))
+            .respond_with(json_response(200, response))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Like `stub`, answering only after `delay`, for a cluster that hangs
+    pub async fn stub_delayed(
+        &self,
+        http_method: &str,
+        request_path: &str,
+        delay: Duration,
+        response: Value,
+    ) {
+        Mock::given(method(http_method))
+            .and(path(request_path))
+            .respond_with(json_response(200, response).set_delay(delay))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Like `stub_body`, answering only after `delay`, for requests that are too slow
+    pub async fn stub_body_delayed(
+        &self,
+        http_method: &str,
+        request_path: &str,
+        matches: impl Fn(&Value) -> bool + Send + Sync + 'static,
+        delay: Duration,
+        response: Value,
+    ) {
+        Mock::given(method(http_method))
+            .and(path(request_path))
+            .and(JsonBody(matches))
+            .respond_with(json_response(200, response).set_delay(delay))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Like `stub`, with the response computed from the JSON request body, for requests
+    /// that have to be answered the way the cluster would
+    pub async fn stub_fn(
+        &self,
+        http_method: &str,
+        request_path: &str,
+        respond: impl Fn(&Value) -> Value + Send + Sync + 'static,
+    ) {
+        Mock::given(method(http_method))
+            .and(path(request_path))
+            .respond_with(move |request: &Request| {
+                let body = serde_json::from_slice(&request.body).unwrap_or(Value::Null);
+                json_response(200, respond(&body))
+            })
+            .mount(&self.server)
+            .await;
+    }
+
+    /// JSON bodies of the `method` requests to `path` received so far, oldest first.
+    /// Requests without a body give `Value::Null`.
+    pub async fn bodies(&self, http_method: &str, request_path: &str) -> Vec<Value> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|request| {
+                request.method.to_string().eq_ignore_ascii_case(http_method)
+                    && request.url.path() == request_path
+            })
+            .map(|request| serde_json::from_slice(&request.body).unwrap_or(Value::Null))
+            .collect()
+    }
+
+    /// Like `bodies`, for NDJSON requests like `/_bulk`, one value per line
+    pub async fn ndjson_bodies(&self, http_method: &str, request_path: &str) -> Vec<Vec<Value>> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|request| {
+                request.method.to_string().eq_ignore_ascii_case(http_method)
+                    && request.url.path() == request_path
+            })
+            .map(|request| {
+                serde_json::Deserializer::from_slice(&request.body)
+                    .into_iter::<Value>()
+                    .filter_map(Result::ok)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Query strings of the `method` requests to `path` received so far, oldest first
+    pub async fn queries(&self, http_method: &str, request_path: &str) -> Vec<String> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|request| {
+                request.method.to_string().eq_ignore_ascii_case(http_method)
+                    && request.url.path() == request_path
+            })
+            .map(|request| request.url.query().unwrap_or_default().to_string())
+            .collect()
+    }
+
+    /// Paths of the `method` requests received so far, in order
+    pub async fn paths(&self, http_method: &str) -> Vec<String> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|request| request.method.to_string().eq_ignore_ascii_case(http_method))
+            .map(|request| request.url.path().to_string())
+            .collect()
+    }
+}
+
+/// Opts out a channel through the opt-out service. The opt-out list is process-wide,
+/// so the channel must be unique and none of the simulated ones.
+pub async fn opt_out(es: &MockElasticsearch, channel_id: &str) {
+    es.stub(
+        "POST",
+        &format!("/{}/_search", INDEX_NAMES.videos),
+        search_hits(vec![]),
+    )
+    .await;
+    let doc = format!("/{}/_doc/{channel_id}", INDEX_NAMES.optout_channels);
+    for method in ["PUT", "POST"] {
+        es.stub(method, &doc, serde_json::json!({ "result": "created" }))
+            .await;
+    }
+    let request = OptOutRequest {
+        channel_id: channel_id.to_string(),
+        reason: "creator request".to_string(),
+        delete_content: false,
+    };
+    optout_service::add_opt_out(&es.client, &ArchiveGeneration::new(), &request, false, None)
+        .await
+        .expect("opting out");
+}
+
+/// Search response with these hits, each given as `(_id, _source)`
+pub fn search_hits(hits: Vec<(&str, Value)>) -> Value {
+    let total = hits.len();
+    let hits: Vec<Value> = hits
+        .into_iter()
+        .map(|(id, source)| serde_json::json!({ "_id": id, "_source": source }))
+        .collect();
+    serde_json::json!({
+        "hits": {
+            "total": { "value": total, "relation": "eq" },
+            "hits": hits
+        }
+    })
+}
@@ -14,6 +14,15 @@ pub fn parse_iso8601_to_timestamp(date_str: &str) -> i64 {
     0
 }
 
+/// Parse ISO8601/RFC3339 date string to Unix milliseconds
+pub fn parse_iso8601_to_millis(date_str: &str) -> i64 {
+    use chrono::{DateTime, Utc};
+    date_str
+        .parse::<DateTime<Utc>>()
+        .map(|dt| dt.timestamp_millis())
+        .unwrap_or(0)
+}
+
 /// Parse ISO8601 duration string (PT1H2M3S) to total seconds for sorting
 pub fn parse_iso8601_duration_to_seconds(duration_str: &str) -> i64 {
     if duration_str.is_empty() {
@@ -58,14 +67,683 @@ pub fn compare_with_order_int(a: i64, b: i64, order: &SortOrder) -> std::cmp::Or
     compare_with_order_float(a as f64, b as f64, order)
 }
 
-pub fn extract_youtube_video_id(url: &str) -> Option<String> {
-    if let Some(captures) = regex::Regex::new(
-        r"(?:youtube\.com/watch\?v=|youtu\.be/|youtube\.com/embed/)([a-zA-Z0-9_-]{11})",
-    )
-    .ok()?
-    .captures(url)
-    {
-        return captures.get(1).map(|m| m.as_str().to_string());
+/// Why `extract_youtube_video_id` rejected an input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VideoUrlError {
+    /// A playlist URL without a video, its videos are indexed by monitoring the playlist
+    PlaylistOnly(String),
+    Invalid,
+}
+
+impl std::fmt::Display for VideoUrlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VideoUrlError::PlaylistOnly(playlist_id) => write!(
+                f,
+                "Playlist {playlist_id} is not a video, monitor the playlist to index its videos"
+            ),
+            VideoUrlError::Invalid => write!(f, "Invalid YouTube URL"),
+        }
+    }
+}
+
+impl std::error::Error for VideoUrlError {}
+
+pub fn is_video_id(value: &str) -> bool {
+    value.len() == 11
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Extracts the video ID from a bare video ID or a watch, `youtu.be`, shorts, live or
+/// embed URL
+pub fn extract_youtube_video_id(input: &str) -> Result<String, VideoUrlError> {
+    let input = input.trim();
+    if is_video_id(input) {
+        return Ok(input.to_string());
+    }
+
+    let url = normalize_youtube_url(input).ok_or(VideoUrlError::Invalid)?;
+    let segments: Vec<&str> = url
+        .path_segments()
+        .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+    let video_id = if url.host_str() == Some("youtu.be") {
+        segments.first().map(|id| id.to_string())
+    } else {
+        match segments.as_slice() {
+            ["watch"] => url
+                .query_pairs()
+                .find(|(key, _)| key == "v")
+                .map(|(_, id)| id.into_owned()),
+            ["shorts" | "live" | "embed", id] => Some(id.to_string()),
+            _ => None,
+        }
+    };
+
+    match video_id {
+        Some(video_id) if is_video_id(&video_id) => Ok(video_id),
+        Some(_) => Err(VideoUrlError::Invalid),
+        None => match extract_playlist_id(url.as_str()) {
+            Some(playlist_id) => Err(VideoUrlError::PlaylistOnly(playlist_id)),
+            None => Err(VideoUrlError::Invalid),
+        },
+    }
+}
+
+/// Query parameters worth keeping on YouTube URLs, everything else (`si`, `feature`,
+/// `pp`, `utm_*`, ...) is tracking junk
+const MEANINGFUL_QUERY_PARAMS: &[&str] = &["v", "list"];
+
+/// Cleans up a pasted YouTube URL: trims it, adds a missing scheme, unifies the host to
+/// `www.youtube.com` and drops tracking parameters. Returns None if it isn't a URL.
+pub fn normalize_youtube_url(input: &str) -> Option<reqwest::Url> {
+    let input = input.trim();
+    let with_scheme = if input.contains("://") {
+        input.to_string()
+    } else {
+        format!("https://{}", input)
+    };
+
+    // Url lowercases the scheme and host
+    let mut url = reqwest::Url::parse(&with_scheme).ok()?;
+    let host = url.host_str()?.to_string();
+    match host.as_str() {
+        "youtube.com" | "www.youtube.com" | "m.youtube.com" | "music.youtube.com" => {
+            url.set_host(Some("www.youtube.com")).ok()?;
+        }
+        "youtu.be" => {}
+        _ => return None,
+    }
+    url.set_scheme("https").ok()?;
+    url.set_fragment(None);
+
+    let kept: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| MEANINGFUL_QUERY_PARAMS.contains(&key.as_ref()))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+    if kept.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(kept);
+    }
+
+    Some(url)
+}
+
+/// How a monitored channel was referenced in the admin input
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChannelRef {
+    Id(String),
+    Handle(String),
+    Username(String),
+    /// A video of the channel, e.g. a `youtu.be` share link
+    Video(String),
+}
+
+impl ChannelRef {
+    /// Parses a channel URL, `@handle` or bare channel ID
+    pub fn parse(input: &str) -> Option<ChannelRef> {
+        let input = input.trim();
+        if let Some(handle) = input.strip_prefix('@') {
+            return (!handle.is_empty()).then(|| ChannelRef::Handle(handle.to_string()));
+        }
+        if is_channel_id(input) {
+            return Some(ChannelRef::Id(input.to_string()));
+        }
+
+        let url = normalize_youtube_url(input)?;
+        let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+        if url.host_str() == Some("youtu.be") {
+            return segments.first().map(|id| ChannelRef::Video(id.to_string()));
+        }
+
+        match segments.as_slice() {
+            ["watch"] => url
+                .query_pairs()
+                .find(|(key, _)| key == "v")
+                .map(|(_, id)| ChannelRef::Video(id.into_owned())),
+            ["shorts" | "live", id] => Some(ChannelRef::Video(id.to_string())),
+            ["channel", id, ..] if is_channel_id(id) => Some(ChannelRef::Id(id.to_string())),
+            [handle, ..] if handle.len() > 1 && handle.starts_with('@') => {
+                Some(ChannelRef::Handle(handle[1..].to_string()))
+            }
+            ["c" | "user", name, ..] => Some(ChannelRef::Username(name.to_string())),
+            _ => None,
+        }
+    }
+}
+
+pub fn is_channel_id(value: &str) -> bool {
+    value.len() == 24
+        && value.starts_with("UC")
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Extracts the playlist ID from a playlist URL, a watch/share URL with a `list`
+/// parameter or a bare playlist ID
+pub fn extract_playlist_id(input: &str) -> Option<String> {
+    let input = input.trim();
+    let is_playlist_id = |value: &str| {
+        value.len() >= 13
+            && value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+    if is_playlist_id(input) {
+        return Some(input.to_string());
+    }
+
+    let url = normalize_youtube_url(input)?;
+    let list = url
+        .query_pairs()
+        .find(|(key, _)| key == "list")
+        .map(|(_, value)| value.into_owned())?;
+    is_playlist_id(&list).then_some(list)
+}
+
+pub fn canonical_channel_url(channel_id: &str) -> String {
+    format!("https://www.youtube.com/channel/{}", channel_id)
+}
+
+pub fn canonical_playlist_url(playlist_id: &str) -> String {
+    format!("https://www.youtube.com/playlist?list={}", playlist_id)
+}
+
+/// Caption text as it is indexed and searched: NFC normalized, whitespace runs collapsed
+/// to single spaces and no space before closing punctuation. Normalizing twice gives the
+/// same text, so recrawls compare equal to what was indexed.
+pub fn normalize_caption_text(text: &str) -> String {
+    use unicode_normalization::UnicodeNormalization;
+    let composed: String = text.nfc().collect();
+    let mut normalized = String::with_capacity(composed.len());
+    for word in composed.split_whitespace() {
+        if !normalized.is_empty() && !word.starts_with([',', '.', '?', '!']) {
+            normalized.push(' ');
+        }
+        normalized.push_str(word);
+    }
+    normalized
+}
+
+/// Splits caption text longer than `max_chars` chars into chunks of at most `max_chars`,
+/// cut at spaces where possible and hard within words longer than that. Chunks don't
+/// start or end with spaces, each comes with the char offset it starts at, for
+/// interpolating its timestamps.
+pub fn split_caption_text(text: &str, max_chars: usize) -> Vec<(usize, String)> {
+    let chars: Vec<char> = text.chars().collect();
+    if max_chars == 0 || chars.len() <= max_chars {
+        return vec![(0, text.to_string())];
+    }
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    loop {
+        while start < chars.len() && chars[start] == ' ' {
+            start += 1;
+        }
+        if start == chars.len() {
+            return chunks;
+        }
+        let mut end = (start + max_chars).min(chars.len());
+        if end < chars.len() {
+            if let Some(space) = (start + 1..=end).rev().find(|&i| chars[i] == ' ') {
+                end = space;
+            }
+        }
+        let mut chunk_end = end;
+        while chars[chunk_end - 1] == ' ' {
+            chunk_end -= 1;
+        }
+        chunks.push((start, chars[start..chunk_end].iter().collect()));
+        start = end;
+    }
+}
+
+/// Time at char `offset` of a caption spanning `start`..`end`, assuming the text is
+/// spoken at an even pace
+pub fn interpolate_caption_time(start: f64, end: f64, offset: usize, total_chars: usize) -> f64 {
+    if total_chars == 0 {
+        return start;
+    }
+    start + (end - start) * offset as f64 / total_chars as f64
+}
+
+/// Caption text shortened to `max_chars` at a space, with an ellipsis where it was cut.
+/// `keep_end` keeps the end of the text instead of its start. Only captions indexed
+/// before long ones were split can be this long.
+pub fn clip_caption_text(text: &str, max_chars: usize, keep_end: bool) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    if max_chars == 0 || chars.len() <= max_chars {
+        return text.to_string();
+    }
+
+    let keep = max_chars - 1; // room for the ellipsis
+    if keep_end {
+        let cut = chars.len() - keep;
+        let start = (cut..chars.len())
+            .find(|&i| chars[i] == ' ')
+            .map_or(cut, |space| space + 1);
+        format!(
+            "…{}",
+            chars[start..].iter().collect::<String>().trim_start()
+        )
+    } else {
+        let end = (1..=keep).rev().find(|&i| chars[i] == ' ').unwrap_or(keep);
+        format!("{}…", chars[..end].iter().collect::<String>().trim_end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANNEL: &str = "UCaaaaaaaaaaaaaaaaaaaaaa";
+    const PLAYLIST: &str = "PLaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+
+    #[test]
+    fn messy_youtube_urls_normalize() {
+        for (input, expected) in [
+            (
+                " M.YouTube.com/watch?v=dQw4w9WgXcQ&si=abc&t=10#t=5 ",
+                Some("https://www.youtube.com/watch?v=dQw4w9WgXcQ"),
+            ),
+            (
+                "http://youtube.com/playlist?list=PLabc&utm_source=share",
+                Some("https://www.youtube.com/playlist?list=PLabc"),
+            ),
+            (
+                "https://music.youtube.com/watch?v=dQw4w9WgXcQ&feature=share",
+                Some("https://www.youtube.com/watch?v=dQw4w9WgXcQ"),
+            ),
+            (
+                "HTTPS://WWW.YOUTUBE.COM/@Handle?si=abc",
+                Some("https://www.youtube.com/@Handle"),
+            ),
+            (
+                "youtu.be/dQw4w9WgXcQ?si=xyz",
+                Some("https://youtu.be/dQw4w9WgXcQ"),
+            ),
+            ("https://example.com/watch?v=dQw4w9WgXcQ", None),
+            ("not a url", None),
+            ("", None),
+        ] {
+            assert_eq!(
+                normalize_youtube_url(input).map(String::from),
+                expected.map(str::to_string),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn messy_channel_inputs_resolve() {
+        let id = || Some(ChannelRef::Id(CHANNEL.to_string()));
+        let handle = || Some(ChannelRef::Handle("Handle".to_string()));
+        let video = || Some(ChannelRef::Video("dQw4w9WgXcQ".to_string()));
+        for (input, expected) in [
+            (CHANNEL.to_string(), id()),
+            (format!("  {CHANNEL}\n"), id()),
+            (format!("https://www.youtube.com/channel/{CHANNEL}"), id()),
+            (
+                format!("  HTTPS://WWW.YOUTUBE.COM/channel/{CHANNEL}  "),
+                id(),
+            ),
+            (format!("youtube.com/channel/{CHANNEL}/videos?si=abc"), id()),
+            (
+                format!("https://m.youtube.com/channel/{CHANNEL}#about"),
+                id(),
+            ),
+            ("@Handle".to_string(), handle()),
+            ("https://www.youtube.com/@Handle".to_string(), handle()),
+            (
+                "m.youtube.com/@Handle/featured?si=abc".to_string(),
+                handle(),
+            ),
+            ("https://youtu.be/dQw4w9WgXcQ?si=xyz".to_string(), video()),
+            (
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&feature=share".to_string(),
+                video(),
+            ),
+            ("youtube.com/shorts/dQw4w9WgXcQ".to_string(), video()),
+            (
+                "https://www.youtube.com/user/SomeUser".to_string(),
+                Some(ChannelRef::Username("SomeUser".to_string())),
+            ),
+            (
+                "https://www.youtube.com/c/SomeName/videos".to_string(),
+                Some(ChannelRef::Username("SomeName".to_string())),
+            ),
+            (format!("https://vimeo.com/channel/{CHANNEL}"), None),
+            ("https://www.youtube.com/channel/UCshort".to_string(), None),
+            ("https://www.youtube.com/".to_string(), None),
+            ("@".to_string(), None),
+            ("".to_string(), None),
+        ] {
+            assert_eq!(ChannelRef::parse(&input), expected, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn messy_playlist_inputs_resolve() {
+        for (input, expected) in [
+            (PLAYLIST.to_string(), Some(PLAYLIST)),
+            (format!(" {PLAYLIST} "), Some(PLAYLIST)),
+            (
+                format!("https://www.youtube.com/playlist?list={PLAYLIST}&si=abc"),
+                Some(PLAYLIST),
+            ),
+            (
+                format!("  WWW.YouTube.com/playlist?list={PLAYLIST}#top "),
+                Some(PLAYLIST),
+            ),
+            (
+                format!("https://www.youtube.com/watch?v=dQw4w9WgXcQ&list={PLAYLIST}&index=3"),
+                Some(PLAYLIST),
+            ),
+            (
+                format!("https://m.youtube.com/watch?list={PLAYLIST}&v=dQw4w9WgXcQ"),
+                Some(PLAYLIST),
+            ),
+            (
+                format!("https://youtu.be/dQw4w9WgXcQ?list={PLAYLIST}&si=xyz"),
+                Some(PLAYLIST),
+            ),
+            (
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string(),
+                None,
+            ),
+            (
+                "https://www.youtube.com/playlist?list=PLshort".to_string(),
+                None,
+            ),
+            (
+                format!("https://example.com/playlist?list={PLAYLIST}"),
+                None,
+            ),
+            ("dQw4w9WgXcQ".to_string(), None),
+            ("".to_string(), None),
+        ] {
+            assert_eq!(
+                extract_playlist_id(&input).as_deref(),
+                expected,
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn video_urls_resolve() {
+        let id = || Ok("dQw4w9WgXcQ".to_string());
+        let playlist = || Err(VideoUrlError::PlaylistOnly(PLAYLIST.to_string()));
+        let invalid = || Err(VideoUrlError::Invalid);
+        for (input, expected) in [
+            ("dQw4w9WgXcQ", id()),
+            ("  dQw4w9WgXcQ\n", id()),
+            ("https://www.youtube.com/watch?v=dQw4w9WgXcQ", id()),
+            ("youtube.com/watch?v=dQw4w9WgXcQ&t=42s", id()),
+            ("HTTP://WWW.YOUTUBE.COM/watch?v=dQw4w9WgXcQ", id()),
+            (
+                "https://m.youtube.com/watch?v=dQw4w9WgXcQ&feature=share",
+                id(),
+            ),
+            ("https://music.youtube.com/watch?v=dQw4w9WgXcQ&si=abc", id()),
+            (
+                format!("https://www.youtube.com/watch?list={PLAYLIST}&v=dQw4w9WgXcQ&index=3")
+                    .as_str(),
+                id(),
+            ),
+            ("https://youtu.be/dQw4w9WgXcQ", id()),
+            ("youtu.be/dQw4w9WgXcQ?si=xyz&t=1", id()),
+            ("https://www.youtube.com/shorts/dQw4w9WgXcQ", id()),
+            ("https://youtube.com/shorts/dQw4w9WgXcQ/", id()),
+            (
+                "https://www.youtube.com/live/dQw4w9WgXcQ?feature=share",
+                id(),
+            ),
+            ("https://www.youtube.com/embed/dQw4w9WgXcQ?autoplay=1", id()),
+            (
+                "https://www.youtube.com/watch?v=a-b_c-d_e-f",
+                Ok("a-b_c-d_e-f".to_string()),
+            ),
+            // Playlists without a video
+            (
+                format!("https://www.youtube.com/playlist?list={PLAYLIST}").as_str(),
+                playlist(),
+            ),
+            (
+                format!("m.youtube.com/playlist?list={PLAYLIST}&si=abc").as_str(),
+                playlist(),
+            ),
+            (
+                format!("https://www.youtube.com/watch?list={PLAYLIST}").as_str(),
+                playlist(),
+            ),
+            // Wrong length or charset
+            ("https://www.youtube.com/watch?v=dQw4w9WgXc", invalid()),
+            ("https://www.youtube.com/watch?v=dQw4w9WgXcQQ", invalid()),
+            ("https://www.youtube.com/watch?v=dQw4w9WgX%21Q", invalid()),
+            ("https://youtu.be/dQw4w9WgX$Q", invalid()),
+            ("dQw4w9WgX!Q", invalid()),
+            // Not a video
+            ("https://example.com/watch?v=dQw4w9WgXcQ", invalid()),
+            ("https://www.youtube.com/@Handle", invalid()),
+            (
+                format!("https://www.youtube.com/channel/{CHANNEL}").as_str(),
+                invalid(),
+            ),
+            ("https://www.youtube.com/shorts/", invalid()),
+            ("https://youtu.be/", invalid()),
+            ("not a url", invalid()),
+            ("", invalid()),
+        ] {
+            assert_eq!(extract_youtube_video_id(input), expected, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn canonical_urls() {
+        assert_eq!(
+            canonical_channel_url(CHANNEL),
+            format!("https://www.youtube.com/channel/{CHANNEL}")
+        );
+        assert_eq!(
+            canonical_playlist_url(PLAYLIST),
+            format!("https://www.youtube.com/playlist?list={PLAYLIST}")
+        );
+    }
+
+    fn owned(chunks: &[(usize, &str)]) -> Vec<(usize, String)> {
+        chunks
+            .iter()
+            .map(|(offset, chunk)| (*offset, chunk.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn short_captions_are_not_split() {
+        assert_eq!(
+            split_caption_text("aaaa bbbb", 9),
+            owned(&[(0, "aaaa bbbb")])
+        );
+        assert_eq!(
+            split_caption_text("aaaa bbbb", 100),
+            owned(&[(0, "aaaa bbbb")])
+        );
+        assert_eq!(
+            split_caption_text("aaaa bbbb", 0),
+            owned(&[(0, "aaaa bbbb")])
+        );
+        assert_eq!(split_caption_text("", 4), owned(&[(0, "")]));
+    }
+
+    #[test]
+    fn captions_split_at_spaces() {
+        // One char over the limit
+        assert_eq!(
+            split_caption_text("aaaa bbbb", 8),
+            owned(&[(0, "aaaa"), (5, "bbbb")])
+        );
+        // The space right at the limit
+        assert_eq!(
+            split_caption_text("aaaa bbbb", 4),
+            owned(&[(0, "aaaa"), (5, "bbbb")])
+        );
+        assert_eq!(
+            split_caption_text("aaaa bbbb", 5),
+            owned(&[(0, "aaaa"), (5, "bbbb")])
+        );
+        assert_eq!(
+            split_caption_text("one two three four", 9),
+            owned(&[(0, "one two"), (8, "three"), (14, "four")])
+        );
+        // Offsets count chars, not bytes
+        assert_eq!(
+            split_caption_text("ääää öö", 4),
+            owned(&[(0, "ääää"), (5, "öö")])
+        );
+    }
+
+    #[test]
+    fn words_longer_than_the_limit_are_cut() {
+        assert_eq!(
+            split_caption_text("abcdefghij kl", 4),
+            owned(&[(0, "abcd"), (4, "efgh"), (8, "ij"), (11, "kl")])
+        );
+        assert_eq!(
+            split_caption_text("abcdefgh", 3),
+            owned(&[(0, "abc"), (3, "def"), (6, "gh")])
+        );
+    }
+
+    #[test]
+    fn chunks_never_start_or_end_with_spaces() {
+        assert_eq!(
+            split_caption_text("  ab cd  ", 3),
+            owned(&[(2, "ab"), (5, "cd")])
+        );
+        assert_eq!(
+            split_caption_text("ab   cd", 4),
+            owned(&[(0, "ab"), (5, "cd")])
+        );
+        assert_eq!(split_caption_text("ab cd    ", 5), owned(&[(0, "ab cd")]));
+    }
+
+    #[test]
+    fn single_char_chunks() {
+        assert_eq!(
+            split_caption_text("ab c", 1),
+            owned(&[(0, "a"), (1, "b"), (3, "c")])
+        );
+        assert_eq!(split_caption_text(" a ", 1), owned(&[(1, "a")]));
+    }
+
+    #[test]
+    fn caption_times_interpolate_by_chars() {
+        assert_eq!(interpolate_caption_time(10.0, 14.0, 0, 8), 10.0);
+        assert_eq!(interpolate_caption_time(10.0, 14.0, 4, 8), 12.0);
+        assert_eq!(interpolate_caption_time(10.0, 14.0, 8, 8), 14.0);
+        assert_eq!(interpolate_caption_time(10.0, 14.0, 3, 0), 10.0);
+        // Without a duration every offset is the start
+        assert_eq!(interpolate_caption_time(10.0, 10.0, 4, 8), 10.0);
+    }
+
+    #[test]
+    fn short_captions_are_not_clipped() {
+        for keep_end in [false, true] {
+            assert_eq!(clip_caption_text("one two", 7, keep_end), "one two");
+            assert_eq!(clip_caption_text("one two", 0, keep_end), "one two");
+        }
+    }
+
+    #[test]
+    fn clipping_cuts_at_spaces() {
+        let text = "one two three four";
+        assert_eq!(clip_caption_text(text, 10, false), "one two…");
+        assert_eq!(clip_caption_text(text, 10, true), "…four");
+        // The space right at the limit
+        assert_eq!(clip_caption_text(text, 9, false), "one two…");
+        assert_eq!(clip_caption_text(text, 17, false), "one two three…");
+        assert_eq!(clip_caption_text(text, 17, true), "…two three four");
+        for keep_end in [false, true] {
+            for max_chars in 1..text.len() {
+                let clipped = clip_caption_text(text, max_chars, keep_end);
+                assert!(clipped.chars().count() <= max_chars, "{clipped:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn clipping_words_longer_than_the_limit() {
+        assert_eq!(clip_caption_text("abcdefghijkl", 5, false), "abcd…");
+        assert_eq!(clip_caption_text("abcdefghijkl", 5, true), "…ijkl");
+        assert_eq!(clip_caption_text("abcdefghijkl", 1, false), "…");
+        assert_eq!(clip_caption_text("abcdefghijkl", 1, true), "…");
+    }
+
+    #[test]
+    fn clipping_drops_spaces_next_to_the_ellipsis() {
+        assert_eq!(clip_caption_text("ab  cdefgh", 5, false), "ab…");
+        assert_eq!(clip_caption_text("abcdef  gh", 5, true), "…gh");
+    }
+
+    /// Caption text the way YouTube sends it
+    const MESSY_CAPTIONS: [&str; 8] = [
+        "  so  this is\tit  ",
+        "wait ,what ?",
+        "no way !\nreally .",
+        "caf\u{65}\u{301} cr\u{e8}me",
+        "\u{a0}non-breaking\u{a0}\u{a0}spaces",
+        "[Music]",
+        "   ",
+        "",
+    ];
+
+    #[test]
+    fn caption_text_normalizes() {
+        for (input, expected) in [
+            ("  so  this is\tit  ", "so this is it"),
+            ("wait ,what ?", "wait,what?"),
+            ("no way !\nreally .", "no way! really."),
+            ("hello , world", "hello, world"),
+            // Composed, so "é" matches however it was typed
+            ("caf\u{65}\u{301}", "caf\u{e9}"),
+            (
+                "\u{a0}non-breaking\u{a0}\u{a0}spaces",
+                "non-breaking spaces",
+            ),
+            ("[Music]", "[Music]"),
+            ("   ", ""),
+        ] {
+            assert_eq!(normalize_caption_text(input), expected, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn caption_text_normalization_is_idempotent() {
+        for input in MESSY_CAPTIONS {
+            let once = normalize_caption_text(input);
+            assert_eq!(normalize_caption_text(&once), once, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn normalized_caption_text_is_stable() {
+        // Recrawls send the same text again, indexed text must not change
+        for text in [
+            "so this is it",
+            "wait, what? no way!",
+            "caf\u{e9} cr\u{e8}me",
+        ] {
+            assert_eq!(normalize_caption_text(text), text);
+        }
+        assert_eq!(
+            normalize_caption_text("caf\u{65}\u{301}"),
+            normalize_caption_text("caf\u{e9}")
+        );
     }
-    None
 }
@@ -0,0 +1,675 @@
+//! End-to-end test of the whole pipeline: enqueue simulated videos, crawl them into a
+//! real Elasticsearch and find their captions through `/search`.
+//!
+//! The config is read from the environment once per process, so all scenarios share
+//! one archive and run in order from a single test.
+//!
+//! Needs Docker for the Elasticsearch container:
+//! `cargo test --features integration-tests`
+
+#![cfg(feature = "integration-tests")]
+
+use backend::models::{
+    AdminEnqueueRequest, AdminEnqueueResponse, AdminQueueResponse, CursorSearchResponse,
+    NewChannel, NewPlaylist, OptOutRequest, OptOutResponse, PublicChannel, RandomQuote,
+    SearchResponse, TrendingVideo, VideoMetadata, VideoTranscript,
+};
+use backend::services::simulation;
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::asynchronous::Client;
+use std::time::Duration;
+use testcontainers::core::{IntoContainerPort, WaitFor};
+use testcontainers::runners::AsyncRunner;
+use testcontainers::{ContainerAsync, GenericImage, ImageExt};
+
+const ES_IMAGE: &str = "docker.elastic.co/elasticsearch/elasticsearch";
+const ES_TAG: &str = "9.0.3"; // Same as docker-compose.yml
+const ADMIN_TOKEN: &str = "e2e-admin-token";
+const VIDEO_IDS: [&str; 2] = ["e2eVideo001", "e2eVideo002"];
+
+fn admin_header() -> Header<'static> {
+    Header::new("Authorization", format!("Bearer {ADMIN_TOKEN}"))
+}
+
+async fn wait_for_cluster(es_url: &str) {
+    let http = reqwest::Client::new();
+    for _ in 0..60 {
+        let healthy = http
+            .get(format!(
+                "{es_url}/_cluster/health?wait_for_status=yellow&timeout=1s"
+            ))
+            .send()
+            .await
+            .is_ok_and(|response| response.status().is_success());
+        if healthy {
+            return;
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    panic!("Elasticsearch at {es_url} did not become healthy");
+}
+
+async fn search(client: &Client, query: &str, page: usize, page_size: usize) -> SearchResponse {
+    let response = client
+        .get(format!(
+            "/search?query={}&type=wide&page={page}&page_size={page_size}",
+            query.replace(' ', "+")
+        ))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok, "search for {query}");
+    response.into_json().await.expect("search response")
+}
+
+/// Elasticsearch in a container and the server on top of it, the container is removed
+/// when this is dropped
+struct Archive {
+    _container: ContainerAsync<GenericImage>,
+    es_url: String,
+    client: Client,
+}
+
+async fn start_archive() -> Archive {
+    let container = GenericImage::new(ES_IMAGE, ES_TAG)
+        .with_exposed_port(9200.tcp())
+        .with_wait_for(WaitFor::message_on_stdout("started"))
+        .with_env_var("discovery.type", "single-node")
+        .with_env_var("xpack.security.enabled", "false")
+        .with_env_var("ES_JAVA_OPTS", "-Xms512m -Xmx512m")
+        .start()
+        .await
+        .expect("Elasticsearch container");
+    let es_url = format!(
+        "http://{}:{}",
+        container.get_host().await.expect("container host"),
+        container
+            .get_host_port_ipv4(9200)
+            .await
+            .expect("container port")
+    );
+    wait_for_cluster(&es_url).await;
+
+    // Read by the config lazies on first use, so before the server is built
+    std::env::set_var("ELASTICSEARCH_URL", &es_url);
+    std::env::set_var("INDEX_PREFIX", "e2e_");
+    std::env::set_var("ADMIN_TOKEN", ADMIN_TOKEN);
+    std::env::set_var("LANGUAGE_PRIORITY", "en");
+    std::env::set_var("DEBUG", "true");
+    std::env::set_var("SIMULATION_MODE", "true");
+    std::env::set_var("TRANSCRIPT_MIN_DELAY_MS", "0");
+    // Crawl passes only run when a scenario starts one, so queued videos stay queued
+    std::env::set_var("CRAWL_QUEUE_SCHEDULE", "0 0 0 1 1 *");
+
+    let client = Client::tracked(backend::rocket().await)
+        .await
+        .expect("valid rocket instance");
+
+    Archive {
+        _container: container,
+        es_url,
+        client,
+    }
+}
+
+impl Archive {
+    async fn refresh(&self) {
+        reqwest::Client::new()
+            .post(format!("{}/_refresh", self.es_url))
+            .send()
+            .await
+            .expect("refreshing the indices");
+    }
+
+    /// Enqueues the videos and waits until a crawl pass indexed them
+    async fn crawl(&self, video_ids: &[&str]) {
+        for video_id in video_ids {
+            let enqueued = self.enqueue(video_id).await;
+            assert!(
+                enqueued.success,
+                "enqueueing {video_id}: {}",
+                enqueued.message
+            );
+        }
+        self.run_crawl(video_ids).await;
+    }
+
+    async fn enqueue(&self, video_id: &str) -> AdminEnqueueResponse {
+        let response = self
+            .client
+            .post("/admin/queue")
+            .header(admin_header())
+            .header(ContentType::JSON)
+            .json(&AdminEnqueueRequest {
+                url: format!("https://www.youtube.com/watch?v={video_id}"),
+            })
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        response.into_json().await.expect("enqueue response")
+    }
+
+    /// IDs of the videos waiting in the queue
+    async fn queued(&self) -> Vec<String> {
+        let queue: AdminQueueResponse = self
+            .client
+            .get("/admin/queue")
+            .header(admin_header())
+            .dispatch()
+            .await
+            .into_json()
+            .await
+            .expect("queue response");
+        queue.items.into_iter().map(|item| item.video_id).collect()
+    }
+
+    /// Runs a crawl pass over the queue and waits until the videos left it
+    async fn run_crawl(&self, video_ids: &[&str]) {
+        let client = &self.client;
+        // Another scenario's pass may still be running
+        let response = client
+            .post(format!(
+                "/admin/crawl/run?max={}",
+                self.queued().await.len()
+            ))
+            .header(admin_header())
+            .dispatch()
+            .await;
+        assert!(
+            [Status::Ok, Status::Conflict].contains(&response.status()),
+            "starting a crawl pass: {}",
+            response.status()
+        );
+
+        let mut crawled = false;
+        for _ in 0..120 {
+            let queue: AdminQueueResponse = client
+                .get("/admin/queue")
+                .header(admin_header())
+                .dispatch()
+                .await
+                .into_json()
+                .await
+                .expect("queue response");
+            let pending: Vec<_> = queue
+                .items
+                .iter()
+                .filter(|item| video_ids.contains(&item.video_id.as_str()))
+                .collect();
+            assert!(
+                pending.iter().all(|item| item.status != "failed"),
+                "crawling failed: {pending:?}"
+            );
+            // Completed items leave the queue
+            if pending.is_empty() {
+                crawled = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        assert!(crawled, "the videos were not crawled in time");
+
+        self.refresh().await;
+    }
+
+    async fn set_channel_hidden(&self, channel_id: &str, hidden: bool) {
+        let action = if hidden { "hide" } else { "unhide" };
+        let response = self
+            .client
+            .post(format!("/monitor/channel/{channel_id}/{action}"))
+            .header(admin_header())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok, "{action} {channel_id}");
+        self.refresh().await;
+    }
+}
+
+#[rocket::async_test]
+async fn archive_end_to_end() {
+    let archive = start_archive().await;
+    archive.crawl(&VIDEO_IDS).await;
+
+    crawled_captions_are_searchable(&archive.client).await;
+    cursor_walks_ignore_videos_indexed_meanwhile(&archive).await;
+    hidden_channels_never_leak(&archive).await;
+    opted_out_channels_are_never_ingested(&archive).await;
+    random_quotes_are_reproducible_and_public(&archive).await;
+}
+
+async fn crawled_captions_are_searchable(client: &Client) {
+    // Search, every fifth simulated caption names its video
+    for video_id in VIDEO_IDS {
+        let expected_captions = simulation::captions(video_id, false)
+            .iter()
+            .filter(|caption| caption.text.contains(video_id))
+            .count();
+        let results = search(client, video_id, 0, 10).await;
+        assert_eq!(results.total_videos, 1, "videos matching {video_id}");
+        assert_eq!(
+            results.total_captions, expected_captions,
+            "captions matching {video_id}"
+        );
+        assert!(!results.results.is_empty());
+        for result in &results.results {
+            assert_eq!(result.video_id, video_id);
+            assert!(
+                result.snippet_html.contains("<strong>"),
+                "highlighted snippet: {}",
+                result.snippet_html
+            );
+        }
+    }
+
+    // Fuzzy matches, the misspelled ID only matches through the wide search's fuzzy
+    // clauses and must still be highlighted instead of falling back to the raw text
+    let misspelled = VIDEO_IDS[0].replace("Video", "Vidoe");
+    let results = search(client, &misspelled, 0, 10).await;
+    assert!(!results.results.is_empty(), "fuzzy matches of {misspelled}");
+    for result in &results.results {
+        assert!(
+            result.snippet_html.contains("<strong>"),
+            "highlighted fuzzy snippet: {}",
+            result.snippet_html
+        );
+    }
+
+    // Pagination, one video per page
+    let query = VIDEO_IDS.join(" ");
+    let first_page = search(client, &query, 0, 1).await;
+    let second_page = search(client, &query, 1, 1).await;
+    assert_eq!(first_page.total_videos, 2);
+    assert_eq!(first_page.total_pages, 2);
+    assert_eq!(second_page.page, 1);
+    let first_video = &first_page.results.first().expect("first page").video_id;
+    let second_video = &second_page.results.first().expect("second page").video_id;
+    assert_ne!(first_video, second_video);
+
+    // Transcript export
+    let captions = simulation::captions(VIDEO_IDS[0], false);
+    let transcript = transcript(client, VIDEO_IDS[0])
+        .await
+        .expect("transcript of a crawled video");
+    assert_eq!(transcript.video_id, VIDEO_IDS[0]);
+    assert_eq!(transcript.lines.len(), captions.len());
+    assert_eq!(transcript.lines[0].text, captions[0].text);
+}
+
+/// Captions of the simulated video containing `word`, as `(video_id, start_time)`
+fn simulated_matches(video_id: &str, word: &str) -> Vec<(String, u64)> {
+    simulation::captions(video_id, false)
+        .into_iter()
+        .filter(|caption| caption.text.split_whitespace().any(|token| token == word))
+        .map(|caption| (caption.video_id, caption.start_time as u64))
+        .collect()
+}
+
+/// A cursor walk returns every match of the index as it was on the first page exactly
+/// once, a video crawled in the middle of the walk neither shows up nor shifts it
+async fn cursor_walks_ignore_videos_indexed_meanwhile(archive: &Archive) {
+    let client = &archive.client;
+    let word = "lorem";
+    let indexed_meanwhile = "e2eCursor01";
+    let mut expected: Vec<(String, u64)> = VIDEO_IDS
+        .iter()
+        .flat_map(|video_id| simulated_matches(video_id, word))
+        .collect();
+    expected.sort();
+    assert!(expected.len() > 3, "matches of {word}");
+    assert!(!simulated_matches(indexed_meanwhile, word).is_empty());
+
+    let walk = format!("/search/cursor?q={word}&type=natural&size=3");
+    let first: CursorSearchResponse = get_json(client, walk.clone()).await;
+    let mut walked: Vec<(String, u64)> = first
+        .hits
+        .iter()
+        .map(|hit| (hit.video_id.clone(), hit.start_time as u64))
+        .collect();
+    let mut cursor = first.next_cursor.expect("cursor of the first page");
+
+    archive.crawl(&[indexed_meanwhile]).await;
+
+    // The cursor only continues the walk it was made for
+    for other in [
+        format!("/search/cursor?q={word}&type=wide&size=3&cursor={cursor}"),
+        format!("/search/cursor?q={word}&type=natural&exclude_auto=true&size=3&cursor={cursor}"),
+        format!("/search/cursor?q={word}&type=natural&lang=en&size=3&cursor={cursor}"),
+    ] {
+        let response = client.get(other.clone()).dispatch().await;
+        assert_eq!(response.status(), Status::UnprocessableEntity, "{other}");
+    }
+
+    loop {
+        let page: CursorSearchResponse = get_json(client, format!("{walk}&cursor={cursor}")).await;
+        walked.extend(
+            page.hits
+                .iter()
+                .map(|hit| (hit.video_id.clone(), hit.start_time as u64)),
+        );
+        match page.next_cursor {
+            Some(next) => cursor = next,
+            None => break,
+        }
+    }
+    walked.sort();
+    assert_eq!(
+        walked, expected,
+        "every match of the first page's index once"
+    );
+
+    // A new walk sees the new video
+    let fresh: CursorSearchResponse = get_json(
+        client,
+        format!("/search/cursor?q={word}&type=natural&size=100"),
+    )
+    .await;
+    assert!(fresh
+        .hits
+        .iter()
+        .any(|hit| hit.video_id == indexed_meanwhile));
+}
+
+async fn transcript(client: &Client, video_id: &str) -> Option<VideoTranscript> {
+    client
+        .get(format!("/video/{video_id}/transcript"))
+        .dispatch()
+        .await
+        .into_json()
+        .await
+        .expect("transcript response")
+}
+
+async fn get_json<T: serde::de::DeserializeOwned + Send + 'static>(
+    client: &Client,
+    uri: String,
+) -> T {
+    let response = client.get(uri.clone()).dispatch().await;
+    assert_eq!(response.status(), Status::Ok, "{uri}");
+    response.into_json().await.expect("JSON response")
+}
+
+/// Nothing of a hidden channel is served by a public endpoint, and all of it is back
+/// once the channel is unhidden
+async fn hidden_channels_never_leak(archive: &Archive) {
+    let client = &archive.client;
+    let video_id = VIDEO_IDS[0];
+    let channel_id = simulation::video_metadata(video_id).channel_id;
+
+    let response = client
+        .post("/monitor/channel")
+        .header(admin_header())
+        .header(ContentType::JSON)
+        .json(&NewChannel {
+            input: format!("https://youtu.be/{video_id}"),
+        })
+        .dispatch()
+        .await;
+    assert_eq!(
+        response.status(),
+        Status::Created,
+        "monitoring {channel_id}"
+    );
+
+    archive.set_channel_hidden(&channel_id, true).await;
+
+    // The other test video may still match fuzzily
+    let results = search(client, video_id, 0, 10).await;
+    assert!(
+        results
+            .results
+            .iter()
+            .all(|result| result.video_id != video_id),
+        "search found a hidden video"
+    );
+
+    let cursor_page: CursorSearchResponse =
+        get_json(client, format!("/search/cursor?q={video_id}&type=wide")).await;
+    assert!(
+        cursor_page.hits.iter().all(|hit| hit.video_id != video_id),
+        "cursor search found a hidden video"
+    );
+
+    for seed in 0..5 {
+        let response = client
+            .get(format!("/search/random?q={video_id}&seed={seed}"))
+            .dispatch()
+            .await;
+        assert_eq!(
+            response.status(),
+            Status::NotFound,
+            "random quote of a hidden video"
+        );
+    }
+
+    let metadata: Option<VideoMetadata> = get_json(client, format!("/video/{video_id}")).await;
+    assert_eq!(metadata, None, "metadata of a hidden video");
+    assert_eq!(
+        transcript(client, video_id).await,
+        None,
+        "transcript of a hidden video"
+    );
+
+    // Feeds
+    let video_ids: Vec<String> = get_json(client, "/video".to_string()).await;
+    assert!(!video_ids.iter().any(|id| id == video_id), "video list");
+    let channels: Vec<PublicChannel> = get_json(client, "/video/channels".to_string()).await;
+    assert!(
+        channels
+            .iter()
+            .all(|channel| channel.channel_id != channel_id),
+        "channel directory"
+    );
+    let channel_videos: Vec<VideoMetadata> =
+        get_json(client, format!("/video/channels/{channel_id}/videos")).await;
+    assert!(channel_videos.is_empty(), "videos of a hidden channel");
+    let response = client.get("/video/trending-matches").dispatch().await;
+    if response.status() == Status::Ok {
+        let trending: Vec<TrendingVideo> = response.into_json().await.expect("trending");
+        assert!(trending.iter().all(|video| video.video_id != video_id));
+    }
+
+    archive.set_channel_hidden(&channel_id, false).await;
+
+    let results = search(client, video_id, 0, 10).await;
+    assert!(
+        results
+            .results
+            .iter()
+            .any(|result| result.video_id == video_id),
+        "unhidden video is searchable again"
+    );
+    let metadata: Option<VideoMetadata> = get_json(client, format!("/video/{video_id}")).await;
+    assert!(metadata.is_some(), "metadata of an unhidden video");
+    assert!(transcript(client, video_id).await.is_some());
+}
+
+/// Simulated videos named `{prefix}NN` that belong to the channel, in order
+fn simulated_videos_of_channel(prefix: &str, channel_id: &str, count: usize) -> Vec<String> {
+    let videos: Vec<String> = (0..100)
+        .map(|index| format!("{prefix}{index:02}"))
+        .filter(|video_id| simulation::video_metadata(video_id).channel_id == channel_id)
+        .take(count)
+        .collect();
+    assert_eq!(videos.len(), count, "simulated videos of {channel_id}");
+    videos
+}
+
+/// A channel that opted out is rejected by a manual enqueue, left out of a playlist
+/// check and skipped by the crawl worker if one of its videos was queued before
+async fn opted_out_channels_are_never_ingested(archive: &Archive) {
+    let client = &archive.client;
+    let playlist_id = "PLe2eOptOutPlaylist";
+    let playlist_videos = simulation::playlist_videos(playlist_id);
+    let channel_id = playlist_videos[0]
+        .channel_id
+        .clone()
+        .expect("channel of a simulated video");
+    let (opted_out, others): (Vec<_>, Vec<_>) = playlist_videos
+        .iter()
+        .map(|video| video.video_id.as_str())
+        .partition(|video_id| simulation::video_metadata(video_id).channel_id == channel_id);
+    assert!(!others.is_empty(), "the playlist mixes channels");
+    let channel_videos = simulated_videos_of_channel("e2eOptOut", &channel_id, 2);
+    let (queued_before, enqueued_after) = (&channel_videos[0], &channel_videos[1]);
+
+    // Queued while the channel was still allowed
+    let enqueued = archive.enqueue(queued_before).await;
+    assert!(enqueued.success, "{}", enqueued.message);
+
+    let response = client
+        .post("/admin/optout")
+        .header(admin_header())
+        .header(ContentType::JSON)
+        .json(&OptOutRequest {
+            channel_id: channel_id.clone(),
+            reason: "creator request".to_string(),
+            delete_content: false,
+        })
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok, "opting out {channel_id}");
+    let opt_out: OptOutResponse = response.into_json().await.expect("opt-out response");
+    assert!(!opt_out.dry_run);
+
+    // Manual enqueue
+    let enqueued = archive.enqueue(enqueued_after).await;
+    assert!(
+        !enqueued.success,
+        "enqueued a video of an opted-out channel"
+    );
+    assert!(
+        enqueued.message.contains("opted out"),
+        "{}",
+        enqueued.message
+    );
+
+    // Playlist containing the channel's videos
+    let response = client
+        .post("/monitor/playlist")
+        .header(admin_header())
+        .header(ContentType::JSON)
+        .json(&NewPlaylist {
+            input: playlist_id.to_string(),
+        })
+        .dispatch()
+        .await;
+    assert_eq!(
+        response.status(),
+        Status::Created,
+        "monitoring {playlist_id}"
+    );
+    let response = client
+        .post(format!("/monitor/playlist/{playlist_id}/check"))
+        .header(admin_header())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok, "checking {playlist_id}");
+    let queued = archive.queued().await;
+    for video_id in &opted_out {
+        assert!(
+            !queued.iter().any(|id| id == video_id),
+            "playlist enqueued {video_id}"
+        );
+    }
+    for video_id in &others {
+        assert!(
+            queued.iter().any(|id| id == video_id),
+            "playlist skipped {video_id}"
+        );
+    }
+    assert!(queued.iter().any(|id| id == queued_before));
+
+    // Crawl worker, the last guard
+    let mut crawled: Vec<&str> = others.clone();
+    crawled.push(queued_before);
+    archive.run_crawl(&crawled).await;
+
+    let mut rejected = opted_out.clone();
+    rejected.extend([queued_before.as_str(), enqueued_after.as_str()]);
+    for video_id in rejected {
+        let metadata: Option<VideoMetadata> = get_json(client, format!("/video/{video_id}")).await;
+        assert_eq!(metadata, None, "indexed {video_id} of an opted-out channel");
+        assert_eq!(transcript(client, video_id).await, None);
+    }
+    for video_id in &others {
+        let metadata: Option<VideoMetadata> = get_json(client, format!("/video/{video_id}")).await;
+        assert!(
+            metadata.is_some(),
+            "{video_id} of another channel is indexed"
+        );
+    }
+}
+
+/// The quote for `uri`, None if nothing matched
+async fn random_quote(client: &Client, uri: String) -> Option<RandomQuote> {
+    let response = client.get(uri.clone()).dispatch().await;
+    match response.status().code {
+        404 => None,
+        200 => Some(response.into_json().await.expect("random quote")),
+        _ => panic!("{uri}: {}", response.status()),
+    }
+}
+
+/// A seed picks the same quote every time, and quotes never come from hidden videos or
+/// videos that are being deleted
+async fn random_quotes_are_reproducible_and_public(archive: &Archive) {
+    let client = &archive.client;
+
+    for uri in [
+        "/search/random?seed=7",
+        "/search/random?seed=42",
+        "/search/random?q=lorem&seed=42",
+        "/search/random?q=ipsum&seed=2024",
+    ] {
+        let first = random_quote(client, uri.to_string())
+            .await
+            .unwrap_or_else(|| panic!("{uri} found no quote"));
+        let again = random_quote(client, uri.to_string()).await;
+        assert_eq!(again.as_ref(), Some(&first), "{uri}");
+    }
+    let unseeded = random_quote(client, "/search/random".to_string())
+        .await
+        .expect("unseeded quote");
+    let replayed = random_quote(client, format!("/search/random?seed={}", unseeded.seed)).await;
+    assert_eq!(
+        replayed,
+        Some(unseeded),
+        "the returned seed replays the quote"
+    );
+
+    // Hidden channels
+    let hidden_channel = simulation::video_metadata(VIDEO_IDS[0]).channel_id;
+    archive.set_channel_hidden(&hidden_channel, true).await;
+    for seed in 0..30 {
+        if let Some(quote) = random_quote(client, format!("/search/random?seed={seed}")).await {
+            assert_ne!(
+                simulation::video_metadata(&quote.result.video_id).channel_id,
+                hidden_channel,
+                "seed {seed} quoted a hidden video"
+            );
+            assert_eq!(quote.video.video_id, quote.result.video_id);
+        }
+    }
+    archive.set_channel_hidden(&hidden_channel, false).await;
+
+    // Videos being deleted, their captions go in the background
+    let deleted = "e2eRandom01";
+    archive.crawl(&[deleted]).await;
+    let quote = random_quote(client, format!("/search/random?q={deleted}&seed=1")).await;
+    assert_eq!(
+        quote.map(|quote| quote.result.video_id).as_deref(),
+        Some(deleted)
+    );
+    let response = client
+        .delete(format!("/admin/video/{deleted}"))
+        .header(admin_header())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok, "deleting {deleted}");
+    for seed in 0..10 {
+        let quote = random_quote(client, format!("/search/random?q={deleted}&seed={seed}")).await;
+        assert!(
+            quote.is_none_or(|quote| quote.result.video_id != deleted),
+            "seed {seed} quoted a deleted video"
+        );
+    }
+}
@@ -1,48 +1,53 @@
-use crate::admin::models::{AdminLoginRequest, AdminLoginResponse, AdminStats};
+use crate::admin::models::{
+    AdminLoginResponse, AdminLookupResponse, AdminSettings, AdminStats, HealthAlert,
+};
+use crate::admin::utils::VIDEOS_PER_PAGE;
 use crate::env_variable_utils::BACKEND_URL;
-use gloo_net::http::Request;
+use starchive_api_types::client::ApiClient;
 
 pub async fn login_admin(token: &str) -> Result<AdminLoginResponse, String> {
-    let backend_url = &*BACKEND_URL;
-    let url = format!("{}/admin/login", backend_url);
+    Ok(ApiClient::new(&BACKEND_URL).login(token).await?)
+}
 
-    let request_body = AdminLoginRequest {
-        token: token.to_string(),
-    };
+pub async fn load_admin_stats(token: &str) -> Result<AdminStats, String> {
+    Ok(ApiClient::new(&BACKEND_URL)
+        .with_token(token)
+        .admin_stats()
+        .await?)
+}
 
-    let response = Request::post(&url)
-        .json(&request_body)
-        .map_err(|e| format!("Request error: {}", e))?
-        .send()
-        .await
-        .map_err(|e| format!("Network error: {}", e))?;
+pub async fn load_settings(token: &str) -> Result<AdminSettings, String> {
+    Ok(ApiClient::new(&BACKEND_URL)
+        .with_token(token)
+        .settings()
+        .await?)
+}
 
-    if response.ok() {
-        response
-            .json::<AdminLoginResponse>()
-            .await
-            .map_err(|e| format!("JSON parse error: {}", e))
-    } else {
-        Err(format!("HTTP error: {}", response.status()))
-    }
+/// Returns the settings as applied, out of bounds values are clamped by the backend
+pub async fn save_settings(token: &str, settings: &AdminSettings) -> Result<AdminSettings, String> {
+    Ok(ApiClient::new(&BACKEND_URL)
+        .with_token(token)
+        .update_settings(settings)
+        .await?)
 }
 
-pub async fn load_admin_stats(token: &str) -> Result<AdminStats, String> {
-    let backend_url = &*BACKEND_URL;
-    let url = format!("{}/admin/stats", backend_url);
+pub async fn load_alerts(token: &str) -> Result<Vec<HealthAlert>, String> {
+    Ok(ApiClient::new(&BACKEND_URL)
+        .with_token(token)
+        .alerts()
+        .await?)
+}
 
-    let response = Request::get(&url)
-        .header("Authorization", &format!("Bearer {}", token))
-        .send()
-        .await
-        .map_err(|e| format!("Network error: {}", e))?;
+pub async fn acknowledge_alert(token: &str, alert_id: &str) -> Result<(), String> {
+    Ok(ApiClient::new(&BACKEND_URL)
+        .with_token(token)
+        .ack_alert(alert_id)
+        .await?)
+}
 
-    if response.ok() {
-        response
-            .json::<AdminStats>()
-            .await
-            .map_err(|e| format!("JSON parse error: {}", e))
-    } else {
-        Err(format!("HTTP error: {}", response.status()))
-    }
+pub async fn lookup(token: &str, query: &str) -> Result<AdminLookupResponse, String> {
+    Ok(ApiClient::new(&BACKEND_URL)
+        .with_token(token)
+        .lookup(query, VIDEOS_PER_PAGE)
+        .await?)
 }
@@ -1,7 +1,15 @@
-use crate::admin::models::AdminStats;
-use crate::admin::utils::format_unix_time_since;
-use crate::router::Route;
-use crate::utils::format_number;
+use crate::admin::api::{load_settings, login_admin, lookup, save_settings};
+use crate::admin::models::{
+    AdminLookupResponse, AdminSettings, AdminStats, DiscoveryMode, EnqueueOrder, FieldError,
+    HealthAlert, LookupHit, LookupKind, MonitorPurgeResponse, MonitorUpdate,
+};
+use crate::admin::router::AdminRoute;
+use crate::admin::utils::{
+    format_unix_time_since, get_stored_admin_token, remove_admin_token, set_login_required_notice,
+    AdminSession,
+};
+use crate::search::search_options::event_value;
+use crate::utils::{format_compact, format_count, format_local_datetime, format_relative_time};
 use yew::prelude::*;
 use yew_router::prelude::*;
 
@@ -66,8 +74,11 @@ pub fn login_form(props: &LoginFormProps) -> Html {
 
 #[derive(Properties, PartialEq)]
 pub struct DashboardProps {
+    pub token: String,
     pub stats: AdminStats,
+    pub alerts: Vec<HealthAlert>,
     pub loading: bool,
+    pub on_ack_alert: Callback<String>,
     pub on_logout: Callback<MouseEvent>,
 }
 
@@ -85,24 +96,74 @@ pub fn dashboard(props: &DashboardProps) -> Html {
                 </button>
             </div>
 
+            { for props.alerts.iter().map(|alert| {
+                let on_ack = {
+                    let on_ack_alert = props.on_ack_alert.clone();
+                    let alert_id = alert.id.clone();
+                    Callback::from(move |_: MouseEvent| on_ack_alert.emit(alert_id.clone()))
+                };
+                html! {
+                    <div class="flex justify-between items-center bg-red-100 border border-red-400 text-red-700 px-4 py-3 rounded mb-4">
+                        <div>
+                            <span class="font-semibold">{"Health alert: "}</span>
+                            {&alert.message}
+                            <span class="text-sm ml-2" title={format_local_datetime(alert.created_at)}>
+                                {format!("({})", format_relative_time(alert.created_at))}
+                            </span>
+                        </div>
+                        <button
+                            onclick={on_ack}
+                            class="bg-red-600 text-white px-3 py-1 rounded hover:bg-red-700 ml-4"
+                        >
+                            {"Acknowledge"}
+                        </button>
+                    </div>
+                }
+            }) }
+
+            if let Some(paused_until) = props.stats.captions_paused_until {
+                <div class="bg-yellow-100 border border-yellow-400 text-yellow-800 px-4 py-3 rounded mb-4">
+                    {"YouTube is throttling transcript fetches. Captions are paused until "}
+                    {format_local_datetime(paused_until)}
+                    {", the queue only refreshes metadata until then."}
+                </div>
+            }
+
+            if !matches!(props.stats.elasticsearch_circuit.as_str(), "" | "closed") {
+                <div class="bg-red-100 border border-red-400 text-red-800 px-4 py-3 rounded mb-4">
+                    {"Elasticsearch is unreachable, searches are rejected until it answers again (circuit "}
+                    {props.stats.elasticsearch_circuit.clone()}
+                    {")."}
+                </div>
+            }
+
             <div class="grid grid-cols-1 md:grid-cols-2 gap-4 mb-6">
-                <Link<Route> to={Route::AdminVideos} classes="bg-blue-600 text-white p-4 rounded text-center hover:bg-blue-700">
+                <Link<AdminRoute> to={AdminRoute::AdminVideos} classes="bg-blue-600 text-white p-4 rounded text-center hover:bg-blue-700">
                     <div class="font-semibold text-lg mb-2">{"Manage Videos"}</div>
-                    <div class="text-3xl font-bold">{format_number(props.stats.total_videos)}</div>
+                    <div class="text-3xl font-bold">{format_compact(props.stats.total_videos)}</div>
                     <div class="text-sm opacity-80">{"Total Videos"}</div>
-                </Link<Route>>
-                <Link<Route> to={Route::AdminCaptions} classes="bg-green-600 text-white p-4 rounded text-center hover:bg-green-700">
+                </Link<AdminRoute>>
+                <Link<AdminRoute> to={AdminRoute::AdminCaptions} classes="bg-green-600 text-white p-4 rounded text-center hover:bg-green-700">
                     <div class="font-semibold text-lg mb-2">{"Manage Captions"}</div>
-                    <div class="text-3xl font-bold">{format_number(props.stats.total_captions)}</div>
+                    <div class="text-3xl font-bold">{format_compact(props.stats.total_captions)}</div>
                     <div class="text-sm opacity-80">{"Total Captions"}</div>
-                </Link<Route>>
-                <Link<Route> to={Route::AdminQueue} classes="bg-purple-600 text-white p-4 rounded text-center hover:bg-purple-700">
+                </Link<AdminRoute>>
+                <Link<AdminRoute> to={AdminRoute::AdminQueue} classes="bg-purple-600 text-white p-4 rounded text-center hover:bg-purple-700">
                     <div class="font-semibold text-lg mb-2">{"Manage Queue"}</div>
                     {
-                        if props.stats.queue_size > 0 {
+                        if props.stats.crawl_running {
+                            html! {
+                                <>
+                                    <div class="text-3xl font-bold animate-pulse">{"Crawling…"}</div>
+                                    <div class="text-sm opacity-80">
+                                        {format!("{} Items in Queue", format_compact(props.stats.queue_size as i64))}
+                                    </div>
+                                </>
+                            }
+                        } else if props.stats.queue_size > 0 {
                             html! {
                                 <>
-                                    <div class="text-3xl font-bold">{format_number(props.stats.queue_size as i64)}</div>
+                                    <div class="text-3xl font-bold">{format_compact(props.stats.queue_size as i64)}</div>
                                     <div class="text-sm opacity-80">{"Items in Queue"}</div>
                                 </>
                             }
@@ -122,17 +183,161 @@ pub fn dashboard(props: &DashboardProps) -> Html {
                             }
                         }
                     }
-                </Link<Route>>
-                <Link<Route> to={Route::AdminMonitors} classes="bg-orange-600 text-white p-4 rounded text-center hover:bg-orange-700">
+                </Link<AdminRoute>>
+                <Link<AdminRoute> to={AdminRoute::AdminMonitors} classes="bg-orange-600 text-white p-4 rounded text-center hover:bg-orange-700">
                     <div class="font-semibold text-lg mb-2">{"Manage Monitors"}</div>
-                    <div class="text-3xl font-bold">{props.stats.active_monitors}</div>
+                    <div class="text-3xl font-bold">{format_compact(props.stats.active_monitors as i64)}</div>
                     <div class="text-sm opacity-80">{"Active Channel & Playlist Monitors"}</div>
-                </Link<Route>>
+                </Link<AdminRoute>>
+            </div>
+
+            <div class="text-center text-gray-700 mb-4">
+                {format!("{} hours of video indexed", format_count(props.stats.total_duration_hours.round() as i64))}
+                if props.stats.videos_missing_duration > 0 {
+                    <div class="text-sm text-gray-500">
+                        {format!(
+                            "{} videos have no duration and are counted as zero, recrawl them to include them",
+                            format_count(props.stats.videos_missing_duration)
+                        )}
+                    </div>
+                }
+                if props.stats.videos_skipped_too_long > 0 {
+                    <div class="text-sm text-gray-500">
+                        {format!(
+                            "{} videos were longer than the maximum duration, their captions were skipped",
+                            format_count(props.stats.videos_skipped_too_long)
+                        )}
+                    </div>
+                }
+                if props.stats.transcripts_upgraded > 0 {
+                    <div class="text-sm text-gray-500">
+                        {format!(
+                            "{} videos had their auto-generated captions replaced by manual ones",
+                            format_count(props.stats.transcripts_upgraded)
+                        )}
+                    </div>
+                }
+            </div>
+
+            <div class="text-center">
+                <Link<AdminRoute> to={AdminRoute::AdminReports} classes="text-blue-600 hover:underline text-sm">
+                    {"⚑ Reported Captions"}
+                </Link<AdminRoute>>
+                <span class="mx-2 text-gray-400">{"·"}</span>
+                <Link<AdminRoute> to={AdminRoute::AdminRelevance} classes="text-blue-600 hover:underline text-sm">
+                    {"Relevance Preview"}
+                </Link<AdminRoute>>
+                <span class="mx-2 text-gray-400">{"·"}</span>
+                <Link<AdminRoute> to={AdminRoute::AdminSynonyms} classes="text-blue-600 hover:underline text-sm">
+                    {"Synonyms"}
+                </Link<AdminRoute>>
             </div>
+
+            <SearchDeadlineSetting token={props.token.clone()} />
         </div>
     }
 }
 
+#[derive(Properties, PartialEq)]
+pub struct SearchDeadlineSettingProps {
+    pub token: String,
+}
+
+/// Runtime budget of a search, back to `SEARCH_DEADLINE_MS` after a restart
+#[function_component(SearchDeadlineSetting)]
+pub fn search_deadline_setting(props: &SearchDeadlineSettingProps) -> Html {
+    let deadline_ms = use_state(|| None::<u64>);
+    let input = use_state(String::new);
+    let error = use_state(|| None::<String>);
+
+    {
+        let token = props.token.clone();
+        let deadline_ms = deadline_ms.clone();
+        let input = input.clone();
+        let error = error.clone();
+        use_effect_with(props.token.clone(), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                match load_settings(&token).await {
+                    Ok(settings) => {
+                        deadline_ms.set(Some(settings.search_deadline_ms));
+                        input.set(settings.search_deadline_ms.to_string());
+                    }
+                    Err(e) => error.set(Some(format!("Failed to load settings: {}", e))),
+                }
+            });
+            || ()
+        });
+    }
+
+    let on_input = {
+        let input = input.clone();
+        Callback::from(move |e: InputEvent| {
+            let target: web_sys::HtmlInputElement = e.target_unchecked_into();
+            input.set(target.value());
+        })
+    };
+
+    let on_submit = {
+        let token = props.token.clone();
+        let deadline_ms = deadline_ms.clone();
+        let input = input.clone();
+        let error = error.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let Ok(requested) = input.trim().parse::<u64>() else {
+                error.set(Some(
+                    "The deadline must be a number of milliseconds".to_string(),
+                ));
+                return;
+            };
+            let token = token.clone();
+            let deadline_ms = deadline_ms.clone();
+            let input = input.clone();
+            let error = error.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let settings = AdminSettings {
+                    search_deadline_ms: requested,
+                };
+                match save_settings(&token, &settings).await {
+                    Ok(applied) => {
+                        error.set(None);
+                        deadline_ms.set(Some(applied.search_deadline_ms));
+                        input.set(applied.search_deadline_ms.to_string());
+                    }
+                    Err(e) => error.set(Some(format!("Failed to save settings: {}", e))),
+                }
+            });
+        })
+    };
+
+    html! {
+        <form onsubmit={on_submit} class="mt-6 flex flex-wrap items-center justify-center gap-2 text-sm text-gray-700">
+            <label for="search-deadline">{"Search deadline (ms)"}</label>
+            <input
+                id="search-deadline"
+                type="number"
+                min="500"
+                max="60000"
+                step="500"
+                class="w-28 px-2 py-1 border border-gray-300 rounded"
+                value={(*input).clone()}
+                oninput={on_input}
+            />
+            <button
+                type="submit"
+                disabled={deadline_ms.is_none()}
+                class="bg-gray-700 text-white px-3 py-1 rounded hover:bg-gray-800 disabled:opacity-50"
+            >
+                {"Save"}
+            </button>
+            <span class="text-gray-500">{"Slower searches drop their context, applies until the next restart"}</span>
+            if let Some(error) = &*error {
+                <span class="w-full text-center text-red-700">{error}</span>
+            }
+        </form>
+    }
+}
+
 #[derive(Properties, PartialEq)]
 pub struct AdminLayoutProps {
     pub children: Children,
@@ -141,17 +346,26 @@ pub struct AdminLayoutProps {
 
 #[function_component(AdminLayout)]
 pub fn admin_layout(props: &AdminLayoutProps) -> Html {
+    // The overview is rendered outside of AdminGuard and only has the stored token
+    let token = use_context::<AdminSession>()
+        .map(|session| session.token)
+        .or_else(get_stored_admin_token);
+
     html! {
         <div class="min-h-screen bg-gray-700 p-4">
             <div class="max-w-4xl mx-auto">
                 <div class="bg-white rounded-lg shadow-lg p-8">
-                    <div class="flex justify-between items-center mb-6">
+                    <div class="flex justify-between items-center gap-4 mb-6">
                         <h1 class="text-3xl font-bold text-gray-800">
                             {&props.title}
                         </h1>
-                        <Link<Route> to={Route::Home} classes="text-blue-600 hover:underline">
+                        if let Some(token) = token {
+                            <AdminLookup {token} />
+                        }
+                        // The search is a separate app, so this is a full page load
+                        <a href="/" class="text-blue-600 hover:underline">
                             {"← Back to Search"}
-                        </Link<Route>>
+                        </a>
                     </div>
                     { for props.children.iter() }
                 </div>
@@ -159,3 +373,528 @@ pub fn admin_layout(props: &AdminLayoutProps) -> Html {
         </div>
     }
 }
+
+#[derive(Properties, PartialEq)]
+pub struct AdminLookupProps {
+    pub token: String,
+}
+
+/// Search box finding queue items, videos and monitors at once. Selecting a hit opens its
+/// admin page with the row highlighted.
+#[function_component(AdminLookup)]
+pub fn admin_lookup(props: &AdminLookupProps) -> Html {
+    let query = use_state(String::new);
+    let results = use_state(|| None::<AdminLookupResponse>);
+    let error = use_state(|| None::<String>);
+    // Responses of outdated queries are dropped
+    let generation = use_mut_ref(|| 0u32);
+    let navigator = use_navigator();
+
+    let on_input = {
+        let token = props.token.clone();
+        let query = query.clone();
+        let results = results.clone();
+        let error = error.clone();
+        let generation = generation.clone();
+
+        Callback::from(move |e: InputEvent| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            let value = input.value();
+            query.set(value.clone());
+
+            *generation.borrow_mut() += 1;
+            let current = *generation.borrow();
+            if value.trim().chars().count() < 2 {
+                results.set(None);
+                error.set(None);
+                return;
+            }
+
+            let token = token.clone();
+            let results = results.clone();
+            let error = error.clone();
+            let generation = generation.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let response = lookup(&token, &value).await;
+                if *generation.borrow() != current {
+                    return;
+                }
+                match response {
+                    Ok(response) => {
+                        results.set(Some(response));
+                        error.set(None);
+                    }
+                    Err(e) => error.set(Some(format!("Lookup failed: {}", e))),
+                }
+            });
+        })
+    };
+
+    let on_select = {
+        let query = query.clone();
+        let results = results.clone();
+
+        Callback::from(move |hit: LookupHit| {
+            query.set(String::new());
+            results.set(None);
+            let Some(navigator) = &navigator else {
+                return;
+            };
+            let route = match hit.kind {
+                LookupKind::Queue => AdminRoute::AdminQueue,
+                LookupKind::Video => AdminRoute::AdminVideos,
+                LookupKind::Channel | LookupKind::Playlist => AdminRoute::AdminMonitors,
+            };
+            let mut params = vec![("highlight", hit.id)];
+            if let Some(page) = hit.page {
+                params.push(("page", page.to_string()));
+            }
+            let _ = navigator.push_with_query(&route, &params);
+        })
+    };
+
+    let group = |title: &str, hits: &[LookupHit]| -> Html {
+        if hits.is_empty() {
+            return html! {};
+        }
+        html! {
+            <div>
+                <div class="px-3 py-1 text-xs font-semibold text-gray-500 uppercase bg-gray-50">{title}</div>
+                { for hits.iter().map(|hit| {
+                    let onclick = {
+                        let on_select = on_select.clone();
+                        let hit = hit.clone();
+                        Callback::from(move |_: MouseEvent| on_select.emit(hit.clone()))
+                    };
+                    html! {
+                        <button type="button" {onclick} class="block w-full text-left px-3 py-2 hover:bg-blue-50">
+                            <div class="text-sm text-gray-900 truncate">{&hit.label}</div>
+                            if let Some(detail) = &hit.detail {
+                                <div class="text-xs text-gray-500 truncate">{detail}</div>
+                            }
+                        </button>
+                    }
+                }) }
+            </div>
+        }
+    };
+
+    html! {
+        <div class="relative flex-grow max-w-sm">
+            <input
+                type="search"
+                class="w-full px-3 py-2 border border-gray-300 rounded focus:outline-none focus:ring-2 focus:ring-blue-500"
+                placeholder="Find video, queue item or monitor..."
+                value={(*query).clone()}
+                oninput={on_input}
+            />
+            if (*results).is_some() || (*error).is_some() {
+                <div class="absolute z-40 mt-1 w-full max-h-96 overflow-y-auto bg-white border border-gray-300 rounded shadow-lg">
+                    if let Some(error) = &*error {
+                        <div class="px-3 py-2 text-sm text-red-700">{error}</div>
+                    } else if let Some(results) = &*results {
+                        if results.is_empty() {
+                            <div class="px-3 py-2 text-sm text-gray-500">{"Nothing found"}</div>
+                        } else {
+                            { group("Queue", &results.queue) }
+                            { group("Videos", &results.videos) }
+                            { group("Monitors", &results.monitors) }
+                        }
+                    }
+                </div>
+            }
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct AdminGuardProps {
+    pub children: Children,
+}
+
+/// Renders admin sub-pages only with a valid stored token and provides it as
+/// `AdminSession` context, otherwise redirects to the login page
+#[function_component(AdminGuard)]
+pub fn admin_guard(props: &AdminGuardProps) -> Html {
+    let token = use_state(get_stored_admin_token);
+    let verified = use_state(|| false);
+
+    {
+        let token = token.clone();
+        let verified = verified.clone();
+
+        use_effect_with((), move |_| {
+            if let Some(stored) = (*token).clone() {
+                wasm_bindgen_futures::spawn_local(async move {
+                    match login_admin(&stored).await {
+                        Ok(response) if !response.success => {
+                            let _ = remove_admin_token();
+                            token.set(None);
+                        }
+                        // An unreachable backend is reported by the page itself
+                        _ => verified.set(true),
+                    }
+                });
+            }
+            || ()
+        });
+    }
+
+    match (*token).clone() {
+        None => {
+            set_login_required_notice();
+            html! { <Redirect<AdminRoute> to={AdminRoute::Admin} /> }
+        }
+        Some(_) if !*verified => html! {
+            <div class="min-h-screen flex items-center justify-center bg-gray-700">
+                <div class="text-white">{"Checking login..."}</div>
+            </div>
+        },
+        Some(token) => html! {
+            <ContextProvider<AdminSession> context={AdminSession { token }}>
+                { for props.children.iter() }
+            </ContextProvider<AdminSession>>
+        },
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct MonitorEditFormProps {
+    pub title: String,
+    pub name: String,
+    pub active: bool,
+    pub hidden: Option<bool>, // None for monitors that can't be hidden
+    pub enqueue_order: EnqueueOrder,
+    pub discovery_mode: Option<DiscoveryMode>, // None for playlists, always checked via the API
+    pub max_duration_seconds: Option<i64>,     // None uses the global maximum
+    pub tags: Vec<String>,
+    pub errors: Vec<FieldError>,
+    pub saving: bool,
+    /// Called with the changed fields only
+    pub on_save: Callback<MonitorUpdate>,
+    pub on_cancel: Callback<()>,
+}
+
+/// Modal form for the settings of a channel or playlist monitor
+#[function_component(MonitorEditForm)]
+pub fn monitor_edit_form(props: &MonitorEditFormProps) -> Html {
+    let name = use_state(|| props.name.clone());
+    let active = use_state(|| props.active);
+    let hidden = use_state(|| props.hidden.unwrap_or(false));
+    let enqueue_order = use_state(|| props.enqueue_order);
+    let discovery_mode = use_state(|| props.discovery_mode.unwrap_or_default());
+    // Empty for no override
+    let max_duration = use_state(|| {
+        props
+            .max_duration_seconds
+            .map(|seconds| seconds.to_string())
+            .unwrap_or_default()
+    });
+    // Comma separated
+    let tags = use_state(|| props.tags.join(", "));
+
+    let on_submit = {
+        let name = name.clone();
+        let active = active.clone();
+        let hidden = hidden.clone();
+        let enqueue_order = enqueue_order.clone();
+        let discovery_mode = discovery_mode.clone();
+        let max_duration = max_duration.clone();
+        let tags = tags.clone();
+        let initial_name = props.name.clone();
+        let initial_active = props.active;
+        let initial_hidden = props.hidden;
+        let initial_enqueue_order = props.enqueue_order;
+        let initial_discovery_mode = props.discovery_mode;
+        let initial_max_duration = props.max_duration_seconds;
+        let initial_tags = props.tags.clone();
+        let on_save = props.on_save.clone();
+        let on_cancel = props.on_cancel.clone();
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            // Unparsable input is sent as is so the backend reports it
+            let max_duration_seconds = match max_duration.trim() {
+                "" => None,
+                value => Some(value.parse::<i64>().unwrap_or(-1)),
+            };
+            let tags: Vec<String> = tags
+                .split(',')
+                .map(|tag| tag.trim().to_lowercase())
+                .filter(|tag| !tag.is_empty())
+                .collect();
+            let update = MonitorUpdate {
+                name: (*name != initial_name).then(|| (*name).clone()),
+                active: (*active != initial_active).then_some(*active),
+                hidden: initial_hidden.filter(|&h| h != *hidden).map(|_| *hidden),
+                enqueue_order: (*enqueue_order != initial_enqueue_order).then_some(*enqueue_order),
+                discovery_mode: initial_discovery_mode
+                    .filter(|&mode| mode != *discovery_mode)
+                    .map(|_| *discovery_mode),
+                max_duration_seconds: (max_duration_seconds != initial_max_duration)
+                    .then(|| max_duration_seconds.unwrap_or(0)),
+                tags: (tags != initial_tags).then_some(tags),
+            };
+            if update.is_empty() {
+                on_cancel.emit(());
+            } else {
+                on_save.emit(update);
+            }
+        })
+    };
+
+    let field_error = |field: &str| -> Html {
+        props
+            .errors
+            .iter()
+            .filter(|error| error.field == field)
+            .map(|error| html! { <p class="text-red-600 text-sm mt-1">{&error.message}</p> })
+            .collect()
+    };
+
+    html! {
+        <div class="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
+            <form onsubmit={on_submit} class="bg-white rounded-lg shadow-lg p-6 w-full max-w-md">
+                <h2 class="text-xl font-semibold text-gray-800 mb-4">{&props.title}</h2>
+                { field_error("") }
+
+                <div class="mb-4">
+                    <label class="block text-gray-700 text-sm font-bold mb-2">{"Name"}</label>
+                    <input
+                        type="text"
+                        class="w-full px-3 py-2 border border-gray-300 rounded"
+                        value={(*name).clone()}
+                        oninput={
+                            let name = name.clone();
+                            Callback::from(move |e: InputEvent| {
+                                let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+                                name.set(input.value());
+                            })
+                        }
+                    />
+                    { field_error("name") }
+                </div>
+
+                <div class="mb-4">
+                    <label class="flex items-center gap-2 text-gray-700">
+                        <input
+                            type="checkbox"
+                            checked={*active}
+                            onchange={
+                                let active = active.clone();
+                                Callback::from(move |_| active.set(!*active))
+                            }
+                        />
+                        {"Active (checked for new videos)"}
+                    </label>
+                    { field_error("active") }
+                </div>
+
+                <div class="mb-4">
+                    <label class="block text-gray-700 text-sm font-bold mb-2">{"Enqueue new videos"}</label>
+                    <select
+                        class="w-full px-3 py-2 border border-gray-300 rounded"
+                        onchange={
+                            let enqueue_order = enqueue_order.clone();
+                            Callback::from(move |e: Event| {
+                                if let Some(order) = event_value(&e).and_then(|value| EnqueueOrder::from_key(&value)) {
+                                    enqueue_order.set(order);
+                                }
+                            })
+                        }
+                    >
+                        { for EnqueueOrder::ALL.iter().map(|order| html! {
+                            <option value={order.key()} selected={*order == *enqueue_order}>{order.label()}</option>
+                        }) }
+                    </select>
+                    { field_error("enqueue_order") }
+                </div>
+
+                if props.discovery_mode.is_some() {
+                    <div class="mb-4">
+                        <label class="block text-gray-700 text-sm font-bold mb-2">{"Scheduled check"}</label>
+                        <select
+                            class="w-full px-3 py-2 border border-gray-300 rounded"
+                            onchange={
+                                let discovery_mode = discovery_mode.clone();
+                                Callback::from(move |e: Event| {
+                                    if let Some(mode) = event_value(&e).and_then(|value| DiscoveryMode::from_key(&value)) {
+                                        discovery_mode.set(mode);
+                                    }
+                                })
+                            }
+                        >
+                            { for DiscoveryMode::ALL.iter().map(|mode| html! {
+                                <option value={mode.key()} selected={*mode == *discovery_mode}>{mode.label()}</option>
+                            }) }
+                        </select>
+                        { field_error("discovery_mode") }
+                    </div>
+                }
+
+                <div class="mb-4">
+                    <label class="block text-gray-700 text-sm font-bold mb-2">{"Maximum video duration (seconds)"}</label>
+                    <input
+                        type="number"
+                        min="1"
+                        placeholder="Global maximum"
+                        class="w-full px-3 py-2 border border-gray-300 rounded"
+                        value={(*max_duration).clone()}
+                        oninput={
+                            let max_duration = max_duration.clone();
+                            Callback::from(move |e: InputEvent| {
+                                let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+                                max_duration.set(input.value());
+                            })
+                        }
+                    />
+                    <p class="text-gray-500 text-xs mt-1">{"Longer videos are indexed without captions"}</p>
+                    { field_error("max_duration_seconds") }
+                </div>
+
+                <div class="mb-4">
+                    <label class="block text-gray-700 text-sm font-bold mb-2">{"Tags"}</label>
+                    <input
+                        type="text"
+                        placeholder="science, history"
+                        class="w-full px-3 py-2 border border-gray-300 rounded"
+                        value={(*tags).clone()}
+                        oninput={
+                            let tags = tags.clone();
+                            Callback::from(move |e: InputEvent| {
+                                let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+                                tags.set(input.value());
+                            })
+                        }
+                    />
+                    <p class="text-gray-500 text-xs mt-1">{"Comma separated, used to filter and check monitors together"}</p>
+                    { field_error("tags") }
+                </div>
+
+                if props.hidden.is_some() {
+                    <div class="mb-4">
+                        <label class="flex items-center gap-2 text-gray-700">
+                            <input
+                                type="checkbox"
+                                checked={*hidden}
+                                onchange={
+                                    let hidden = hidden.clone();
+                                    Callback::from(move |_| hidden.set(!*hidden))
+                                }
+                            />
+                            {"Hidden from public search"}
+                        </label>
+                        { field_error("hidden") }
+                    </div>
+                }
+
+                <div class="flex justify-end gap-2">
+                    <button
+                        type="button"
+                        onclick={
+                            let on_cancel = props.on_cancel.clone();
+                            Callback::from(move |_| on_cancel.emit(()))
+                        }
+                        class="px-4 py-2 bg-gray-200 text-gray-800 rounded hover:bg-gray-300"
+                    >
+                        {"Cancel"}
+                    </button>
+                    <button
+                        type="submit"
+                        disabled={props.saving}
+                        class="px-4 py-2 bg-blue-600 text-white rounded hover:bg-blue-700 disabled:opacity-50"
+                    >
+                        {if props.saving { "Saving..." } else { "Save" }}
+                    </button>
+                </div>
+            </form>
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct MonitorDeleteDialogProps {
+    pub title: String,
+    pub is_playlist: bool,
+    pub preview: Option<MonitorPurgeResponse>, // None while counting
+    pub deleting: bool,
+    /// Called with whether the indexed videos are deleted too
+    pub on_confirm: Callback<bool>,
+    pub on_cancel: Callback<()>,
+}
+
+/// Modal confirming the deletion of a monitor, optionally with its indexed videos
+#[function_component(MonitorDeleteDialog)]
+pub fn monitor_delete_dialog(props: &MonitorDeleteDialogProps) -> Html {
+    let purge_videos = use_state(|| false);
+
+    let purge_label = match &props.preview {
+        None => "Also delete its indexed videos (counting…)".to_string(),
+        Some(preview) if props.is_playlist => format!(
+            "Also delete the {} videos only this playlist brought in, with {} captions. {} videos that other playlists or monitored channels brought in are kept.",
+            format_count(preview.videos),
+            format_count(preview.captions),
+            format_count(preview.untagged_videos)
+        ),
+        Some(preview) => format!(
+            "Also delete its {} indexed videos with {} captions",
+            format_count(preview.videos),
+            format_count(preview.captions)
+        ),
+    };
+    let radio = |purge: bool, label: String| {
+        let purge_videos = purge_videos.clone();
+        html! {
+            <label class="flex items-start gap-2 text-gray-700 mb-2">
+                <input
+                    type="radio"
+                    name="purge"
+                    class="mt-1"
+                    checked={*purge_videos == purge}
+                    onchange={Callback::from(move |_| purge_videos.set(purge))}
+                />
+                <span>{label}</span>
+            </label>
+        }
+    };
+
+    html! {
+        <div class="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
+            <div class="bg-white rounded-lg shadow-lg p-6 w-full max-w-md">
+                <h2 class="text-xl font-semibold text-gray-800 mb-4">{&props.title}</h2>
+                <p class="text-gray-600 text-sm mb-4">{"It won't be checked for new videos anymore."}</p>
+
+                { radio(false, "Keep its indexed videos searchable".to_string()) }
+                { radio(true, purge_label) }
+                if *purge_videos {
+                    <p class="text-red-600 text-sm mb-2">{"Deleted videos and captions can't be restored, only crawled again."}</p>
+                }
+
+                <div class="flex justify-end gap-2 mt-4">
+                    <button
+                        type="button"
+                        onclick={
+                            let on_cancel = props.on_cancel.clone();
+                            Callback::from(move |_| on_cancel.emit(()))
+                        }
+                        class="px-4 py-2 bg-gray-200 text-gray-800 rounded hover:bg-gray-300"
+                    >
+                        {"Cancel"}
+                    </button>
+                    <button
+                        type="button"
+                        disabled={props.deleting || (*purge_videos && props.preview.is_none())}
+                        onclick={
+                            let on_confirm = props.on_confirm.clone();
+                            let purge_videos = *purge_videos;
+                            Callback::from(move |_| on_confirm.emit(purge_videos))
+                        }
+                        class="px-4 py-2 bg-red-600 text-white rounded hover:bg-red-700 disabled:opacity-50"
+                    >
+                        {if props.deleting { "Deleting..." } else if *purge_videos { "Delete with videos" } else { "Delete" }}
+                    </button>
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -2,4 +2,5 @@ pub mod api;
 pub mod components;
 pub mod models;
 pub mod overview;
+pub mod router;
 pub mod utils;
@@ -1,13 +1,19 @@
-use crate::admin::api::{load_admin_stats, login_admin};
+use crate::admin::api::{acknowledge_alert, load_admin_stats, load_alerts, login_admin};
 use crate::admin::components::{AdminLayout, Dashboard, ErrorMessage, LoginForm};
-use crate::admin::models::AdminStats;
-use crate::admin::utils::{get_stored_admin_token, remove_admin_token, store_admin_token};
+use crate::admin::models::{AdminStats, HealthAlert};
+use crate::admin::utils::{
+    get_stored_admin_token, remove_admin_token, store_admin_token, take_login_required_notice,
+};
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
 
 pub mod admin_captions;
 pub mod admin_monitor;
+pub mod admin_optouts;
 pub mod admin_queue;
+pub mod admin_relevance;
+pub mod admin_reports;
+pub mod admin_synonyms;
 pub mod admin_videos;
 
 #[derive(Properties, PartialEq)]
@@ -16,21 +22,27 @@ pub struct AdminPageProps {}
 #[function_component(AdminPage)]
 pub fn admin_page(_props: &AdminPageProps) -> Html {
     let admin_token = use_state(get_stored_admin_token);
-    let login_token_input = use_state(|| String::new());
+    let login_token_input = use_state(String::new);
     let is_authenticated = use_state(|| admin_token.is_some());
     let loading = use_state(|| false);
-    let error_message = use_state(|| None::<String>);
+    // Set when a guarded admin page redirected here
+    let error_message = use_state(|| {
+        take_login_required_notice().then(|| "Please log in to view that page".to_string())
+    });
     let stats = use_state(|| None::<AdminStats>);
+    let alerts = use_state(Vec::<HealthAlert>::new);
 
     // Load stats on component mount if already authenticated
     {
         let admin_token = admin_token.clone();
         let stats = stats.clone();
+        let alerts = alerts.clone();
         let error_message = error_message.clone();
 
         use_effect_with((), move |_| {
             if let Some(token) = (*admin_token).clone() {
                 let stats = stats.clone();
+                let alerts = alerts.clone();
                 let error_message = error_message.clone();
 
                 wasm_bindgen_futures::spawn_local(async move {
@@ -42,6 +54,9 @@ pub fn admin_page(_props: &AdminPageProps) -> Html {
                             error_message.set(Some(format!("Failed to load stats: {}", e)));
                         }
                     }
+                    if let Ok(alert_list) = load_alerts(&token).await {
+                        alerts.set(alert_list);
+                    }
                 });
             }
         });
@@ -62,6 +77,7 @@ pub fn admin_page(_props: &AdminPageProps) -> Html {
         let loading = loading.clone();
         let error_message = error_message.clone();
         let stats = stats.clone();
+        let alerts = alerts.clone();
 
         Callback::from(move |e: web_sys::SubmitEvent| {
             e.prevent_default();
@@ -72,6 +88,7 @@ pub fn admin_page(_props: &AdminPageProps) -> Html {
             let loading = loading.clone();
             let error_message = error_message.clone();
             let stats = stats.clone();
+            let alerts = alerts.clone();
 
             if token.is_empty() {
                 error_message.set(Some("Please enter an admin token".to_string()));
@@ -98,6 +115,9 @@ pub fn admin_page(_props: &AdminPageProps) -> Html {
                                     error_message.set(Some(format!("Failed to load stats: {}", e)));
                                 }
                             }
+                            if let Ok(alert_list) = load_alerts(&token).await {
+                                alerts.set(alert_list);
+                            }
                         } else {
                             error_message.set(Some(response.message));
                         }
@@ -111,10 +131,41 @@ pub fn admin_page(_props: &AdminPageProps) -> Html {
         })
     };
 
+    let on_ack_alert = {
+        let admin_token = admin_token.clone();
+        let alerts = alerts.clone();
+        let error_message = error_message.clone();
+
+        Callback::from(move |alert_id: String| {
+            let Some(token) = (*admin_token).clone() else {
+                return;
+            };
+            let alerts = alerts.clone();
+            let error_message = error_message.clone();
+
+            wasm_bindgen_futures::spawn_local(async move {
+                match acknowledge_alert(&token, &alert_id).await {
+                    Ok(()) => {
+                        let remaining = (*alerts)
+                            .iter()
+                            .filter(|alert| alert.id != alert_id)
+                            .cloned()
+                            .collect();
+                        alerts.set(remaining);
+                    }
+                    Err(e) => {
+                        error_message.set(Some(format!("Failed to acknowledge alert: {}", e)));
+                    }
+                }
+            });
+        })
+    };
+
     let on_logout = {
         let admin_token = admin_token.clone();
         let is_authenticated = is_authenticated.clone();
         let stats = stats.clone();
+        let alerts = alerts.clone();
         let login_token_input = login_token_input.clone();
         let error_message = error_message.clone();
 
@@ -123,6 +174,7 @@ pub fn admin_page(_props: &AdminPageProps) -> Html {
             admin_token.set(None);
             is_authenticated.set(false);
             stats.set(None);
+            alerts.set(Vec::new());
             login_token_input.set(String::new());
             error_message.set(None);
         })
@@ -136,8 +188,11 @@ pub fn admin_page(_props: &AdminPageProps) -> Html {
                 if *is_authenticated {
                     html! {
                         <Dashboard
+                            token={(*admin_token).clone().unwrap_or_default()}
                             stats={(*stats).clone().unwrap_or_default()}
+                            alerts={(*alerts).clone()}
                             loading={*loading}
+                            on_ack_alert={on_ack_alert}
                             on_logout={on_logout}
                         />
                     }
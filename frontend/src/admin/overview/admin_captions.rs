@@ -1,4 +1,4 @@
-use crate::router::Route;
+use crate::admin::router::AdminRoute;
 use yew::prelude::*;
 use yew_router::prelude::*;
 
@@ -16,12 +16,12 @@ pub fn admin_captions_page(_props: &AdminCaptionsPageProps) -> Html {
                             {"Caption Management"}
                         </h1>
                         <div class="flex gap-4">
-                            <Link<Route> to={Route::Admin} classes="text-blue-600 hover:underline">
+                            <Link<AdminRoute> to={AdminRoute::Admin} classes="text-blue-600 hover:underline">
                                 {"← Back to Admin"}
-                            </Link<Route>>
-                            <Link<Route> to={Route::Home} classes="text-blue-600 hover:underline">
+                            </Link<AdminRoute>>
+                            <a href="/" class="text-blue-600 hover:underline">
                                 {"← Back to Search"}
-                            </Link<Route>>
+                            </a>
                         </div>
                     </div>
 
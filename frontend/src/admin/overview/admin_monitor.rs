@@ -1,12 +1,28 @@
+use crate::admin::components::{MonitorDeleteDialog, MonitorEditForm};
+use crate::admin::models::{
+    DiscoveryMode, FieldError, MonitorPurge, MonitorPurgeResponse, MonitorUpdate,
+};
+use crate::admin::overview::admin_optouts::OptOutPanel;
+use crate::admin::router::AdminRoute;
+use crate::admin::utils::{
+    highlight_class, row_id, use_highlighted_row, use_table_state, AdminSession,
+};
 use crate::env_variable_utils::BACKEND_URL;
 use crate::models::{MonitoredChannelStats, MonitoredPlaylistStats};
-use crate::router::Route;
+use crate::utils::{format_count, format_unix_date};
 use gloo_net::http::Request;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use web_sys::window;
+use starchive_api_types::client::ClientError;
+use starchive_api_types::{
+    BulkMonitorRequest, BulkMonitorResult, MonitorAddResponse, MonitorTag, NewChannel, NewPlaylist,
+};
 use yew::prelude::*;
 use yew_router::prelude::*;
 
+/// Monitors are listed in full, their table state only holds the tag filter
+const MONITOR_PER_PAGE: i64 = 0;
+
 #[derive(Properties, PartialEq)]
 pub struct AdminChannelsPageProps {}
 
@@ -17,26 +33,356 @@ pub struct MonitoredChannelModify {
     pub active: bool,
 }
 
+/// Monitor currently open in the edit modal
+#[derive(Clone, PartialEq)]
+enum EditTarget {
+    Channel(MonitoredChannelStats),
+    Playlist(MonitoredPlaylistStats),
+}
+
+impl EditTarget {
+    /// Path segment of the monitor routes
+    fn kind(&self) -> &'static str {
+        match self {
+            EditTarget::Channel(_) => "channel",
+            EditTarget::Playlist(_) => "playlist",
+        }
+    }
+
+    fn id(&self) -> &str {
+        match self {
+            EditTarget::Channel(channel) => &channel.channel_id,
+            EditTarget::Playlist(playlist) => &playlist.playlist_id,
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            EditTarget::Channel(channel) => &channel.channel_name,
+            EditTarget::Playlist(playlist) => &playlist.playlist_name,
+        }
+    }
+}
+
+fn purge_summary(purged: &MonitorPurgeResponse) -> String {
+    let mut summary = format!(
+        "Deleting {} videos with {} captions",
+        format_count(purged.videos),
+        format_count(purged.captions)
+    );
+    if purged.untagged_videos > 0 {
+        summary.push_str(&format!(
+            ", {} videos of other sources are kept",
+            format_count(purged.untagged_videos)
+        ));
+    }
+    summary
+}
+
+enum SaveError {
+    Fields(Vec<FieldError>),
+    Other(String),
+}
+
+#[derive(Properties, PartialEq)]
+struct BulkAddResultsProps {
+    results: Vec<BulkMonitorResult>,
+    on_retry: Callback<String>,
+}
+
+/// Outcome of a bulk add, one line per pasted input
+#[function_component(BulkAddResults)]
+fn bulk_add_results(props: &BulkAddResultsProps) -> Html {
+    if props.results.is_empty() {
+        return html! {};
+    }
+    let failed = props.results.iter().filter(|r| r.error.is_some()).count();
+
+    html! {
+        <div class="mt-4 border rounded p-4">
+            <p class="text-sm text-gray-700 mb-2">
+                {format!("{} added, {} failed", props.results.len() - failed, failed)}
+            </p>
+            <ul class="space-y-1">
+                { for props.results.iter().map(|result| {
+                    match (&result.added, &result.error) {
+                        (Some(added), _) => html! {
+                            <li class="text-sm text-green-700 truncate">
+                                {format!("✓ {} → {} ({})", result.input, added.name, added.canonical_url)}
+                            </li>
+                        },
+                        (None, error) => {
+                            let on_retry = props.on_retry.clone();
+                            let input = result.input.clone();
+                            html! {
+                                <li class="text-sm text-red-700 flex items-center gap-2">
+                                    <span class="truncate">
+                                        {format!("✗ {}: {}", result.input, error.clone().unwrap_or_default())}
+                                    </span>
+                                    <button
+                                        type="button"
+                                        onclick={Callback::from(move |_| on_retry.emit(input.clone()))}
+                                        class="text-blue-600 hover:underline"
+                                    >
+                                        {"Retry"}
+                                    </button>
+                                </li>
+                            }
+                        }
+                    }
+                }) }
+            </ul>
+        </div>
+    }
+}
+
+/// Whether a monitor with `tags` is listed under the selected tag filter
+fn matches_tag(tags: &[String], selected: Option<&str>) -> bool {
+    selected.is_none_or(|selected| tags.iter().any(|tag| tag == selected))
+}
+
+fn tag_chips(tags: &[String]) -> Html {
+    tags.iter()
+        .map(|tag| {
+            html! {
+                <span class="ml-1 px-1.5 py-0.5 text-xs rounded bg-blue-100 text-blue-700">{tag}</span>
+            }
+        })
+        .collect()
+}
+
+/// Progress bar of a backfilling monitor with its estimated completion date
+fn backfill_progress(indexed: i64, listed: i64, eta_ms: Option<i64>) -> Html {
+    let percent = if listed > 0 {
+        (indexed as f64 / listed as f64 * 100.0).clamp(0.0, 100.0)
+    } else {
+        0.0
+    };
+    let eta = match eta_ms {
+        Some(eta_ms) => format!("done ~{}", format_unix_date(eta_ms / 1000)),
+        None => "no recent progress".to_string(),
+    };
+    html! {
+        <div class="mt-1 w-40" title="Older videos are enqueued a share per backfill cycle">
+            <div class="h-1.5 bg-gray-200 rounded">
+                <div class="h-1.5 bg-blue-500 rounded" style={format!("width: {:.0}%", percent)}></div>
+            </div>
+            <span class="text-xs text-gray-500">{format!("Backfilling {:.0}%, {}", percent, eta)}</span>
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+struct TagBarProps {
+    tags: Vec<MonitorTag>,
+    selected: Option<String>,
+    on_select: Callback<Option<String>>,
+    on_toggle_public: Callback<MonitorTag>,
+    on_check: Callback<String>,
+}
+
+/// Tags in use with their monitor counts. Selecting one filters both tables, the
+/// selected tag can be made public or have all its monitors checked at once.
+#[function_component(TagBar)]
+fn tag_bar(props: &TagBarProps) -> Html {
+    if props.tags.is_empty() {
+        return html! {};
+    }
+    let selected = props
+        .selected
+        .as_ref()
+        .and_then(|selected| props.tags.iter().find(|tag| &tag.tag == selected));
+
+    html! {
+        <div class="bg-white rounded-lg shadow-lg p-4 mt-8">
+            <div class="flex flex-wrap items-center gap-2">
+                <span class="text-sm font-bold text-gray-700">{"Tags"}</span>
+                <button
+                    onclick={
+                        let on_select = props.on_select.clone();
+                        Callback::from(move |_| on_select.emit(None))
+                    }
+                    class={classes!(
+                        "px-2", "py-1", "text-sm", "rounded",
+                        if selected.is_none() { "bg-blue-600 text-white" } else { "bg-gray-200 text-gray-800" }
+                    )}
+                >
+                    {"All"}
+                </button>
+                { for props.tags.iter().map(|tag| {
+                    let active = selected.is_some_and(|selected| selected.tag == tag.tag);
+                    let name = tag.tag.clone();
+                    let on_select = props.on_select.clone();
+                    html! {
+                        <button
+                            onclick={Callback::from(move |_| on_select.emit(Some(name.clone())))}
+                            title={if tag.public { "Shown on the public channel directory" } else { "" }}
+                            class={classes!(
+                                "px-2", "py-1", "text-sm", "rounded",
+                                if active { "bg-blue-600 text-white" } else { "bg-gray-200 text-gray-800" }
+                            )}
+                        >
+                            {format!("{} ({})", tag.tag, tag.channels + tag.playlists)}
+                            if tag.public {
+                                {" ●"}
+                            }
+                        </button>
+                    }
+                }) }
+            </div>
+            if let Some(tag) = selected {
+                <div class="flex items-center gap-4 mt-3 text-sm">
+                    <label class="flex items-center gap-2 text-gray-700">
+                        <input
+                            type="checkbox"
+                            checked={tag.public}
+                            onchange={
+                                let on_toggle_public = props.on_toggle_public.clone();
+                                let tag = tag.clone();
+                                Callback::from(move |_| on_toggle_public.emit(tag.clone()))
+                            }
+                        />
+                        {"Public (shown on the channel directory)"}
+                    </label>
+                    <button
+                        onclick={
+                            let on_check = props.on_check.clone();
+                            let name = tag.tag.clone();
+                            Callback::from(move |_| on_check.emit(name.clone()))
+                        }
+                        class="px-3 py-1 bg-green-600 text-white rounded hover:bg-green-700"
+                    >
+                        {format!("Check all with \"{}\"", tag.tag)}
+                    </button>
+                </div>
+            }
+        </div>
+    }
+}
+
+/// Replaces the result of `input` with the outcome of its retry
+fn retried_results(
+    results: &[BulkMonitorResult],
+    input: String,
+    outcome: Result<MonitorAddResponse, String>,
+) -> Vec<BulkMonitorResult> {
+    results
+        .iter()
+        .cloned()
+        .map(|result| {
+            if result.input != input {
+                return result;
+            }
+            match &outcome {
+                Ok(added) => BulkMonitorResult {
+                    input: result.input,
+                    added: Some(added.clone()),
+                    error: None,
+                },
+                Err(e) => BulkMonitorResult {
+                    input: result.input,
+                    added: None,
+                    error: Some(e.clone()),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Replaces the channel with the backend's copy of it, or appends it if it is new
+fn with_channel(
+    channels: &[MonitoredChannelStats],
+    updated: MonitoredChannelStats,
+) -> Vec<MonitoredChannelStats> {
+    let mut channels = channels.to_vec();
+    match channels
+        .iter_mut()
+        .find(|c| c.channel_id == updated.channel_id)
+    {
+        Some(channel) => *channel = updated,
+        None => channels.push(updated),
+    }
+    channels
+}
+
+fn with_playlist(
+    playlists: &[MonitoredPlaylistStats],
+    updated: MonitoredPlaylistStats,
+) -> Vec<MonitoredPlaylistStats> {
+    let mut playlists = playlists.to_vec();
+    match playlists
+        .iter_mut()
+        .find(|p| p.playlist_id == updated.playlist_id)
+    {
+        Some(playlist) => *playlist = updated,
+        None => playlists.push(updated),
+    }
+    playlists
+}
+
+fn with_added_channels(
+    channels: &[MonitoredChannelStats],
+    results: &[BulkMonitorResult],
+) -> Vec<MonitoredChannelStats> {
+    results
+        .iter()
+        .filter_map(|result| result.added.as_ref()?.channel.clone())
+        .fold(channels.to_vec(), |channels, added| {
+            with_channel(&channels, added)
+        })
+}
+
+fn with_added_playlists(
+    playlists: &[MonitoredPlaylistStats],
+    results: &[BulkMonitorResult],
+) -> Vec<MonitoredPlaylistStats> {
+    results
+        .iter()
+        .filter_map(|result| result.added.as_ref()?.playlist.clone())
+        .fold(playlists.to_vec(), |playlists, added| {
+            with_playlist(&playlists, added)
+        })
+}
+
 #[function_component(AdminMonitorsPage)]
 pub fn admin_monitors_page(_props: &AdminChannelsPageProps) -> Html {
     let channels = use_state(Vec::<MonitoredChannelStats>::new);
     let playlists = use_state(Vec::<MonitoredPlaylistStats>::new);
     let loading = use_state(|| false);
     let error_message = use_state(|| None::<String>);
-    let new_channel_id = use_state(|| String::new());
-    let new_playlist_id = use_state(|| String::new());
+    let added_message = use_state(|| None::<String>);
+    let new_channel_id = use_state(String::new);
+    let new_playlist_id = use_state(String::new);
+    let channel_bulk = use_state(|| false);
+    let playlist_bulk = use_state(|| false);
+    let channel_results = use_state(Vec::<BulkMonitorResult>::new);
+    let playlist_results = use_state(Vec::<BulkMonitorResult>::new);
+    let editing = use_state(|| None::<EditTarget>);
+    let removing = use_state(|| None::<EditTarget>); // monitor in the delete dialog
+    let removal_preview = use_state(|| None::<MonitorPurgeResponse>);
+    let removal_busy = use_state(|| false);
+    let edit_errors = use_state(Vec::<FieldError>::new);
+    let saving = use_state(|| false);
+    let tags = use_state(Vec::<MonitorTag>::new);
+    let table = use_table_state(MONITOR_PER_PAGE);
+    let selected_tag = table.filter("tag").map(str::to_string);
+    let session = use_context::<AdminSession>().expect("rendered inside AdminGuard");
+    let highlight =
+        use_highlighted_row(!*loading && (!channels.is_empty() || !playlists.is_empty()));
 
     // Load channels on component mount
     {
+        let session = session.clone();
         let channels = channels.clone();
         let playlists = playlists.clone();
+        let tags = tags.clone();
         let loading = loading.clone();
         let error_message = error_message.clone();
 
         use_effect_with((), move |_| {
             loading.set(true);
             wasm_bindgen_futures::spawn_local(async move {
-                match load_channels().await {
+                match load_channels(&session).await {
                     Ok(channel_list) => {
                         channels.set(channel_list);
                     }
@@ -45,7 +391,7 @@ pub fn admin_monitors_page(_props: &AdminChannelsPageProps) -> Html {
                     }
                 }
 
-                match load_playlists().await {
+                match load_playlists(&session).await {
                     Ok(playlist_list) => {
                         playlists.set(playlist_list);
                     }
@@ -53,51 +399,377 @@ pub fn admin_monitors_page(_props: &AdminChannelsPageProps) -> Html {
                         error_message.set(Some(format!("Failed to load playlists: {}", e)));
                     }
                 }
+
+                match session.client().monitor_tags().await {
+                    Ok(tag_list) => tags.set(tag_list),
+                    Err(e) => error_message.set(Some(format!("Failed to load tags: {}", e))),
+                }
                 loading.set(false);
             });
             || ()
         });
     }
 
-    let on_delete_channel = {
+    let on_confirm_remove = {
+        let session = session.clone();
+        let removing = removing.clone();
+        let removal_busy = removal_busy.clone();
         let channels = channels.clone();
+        let playlists = playlists.clone();
         let error_message = error_message.clone();
-
-        Callback::from(move |channel_id: String| {
+        let added_message = added_message.clone();
+
+        Callback::from(move |purge_videos: bool| {
+            let Some(target) = (*removing).clone() else {
+                return;
+            };
+            let session = session.clone();
+            let removing = removing.clone();
+            let removal_busy = removal_busy.clone();
             let channels = channels.clone();
+            let playlists = playlists.clone();
             let error_message = error_message.clone();
+            let added_message = added_message.clone();
+            let purge = purge_videos.then_some(MonitorPurge::Videos);
+
+            removal_busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                match delete_monitor(&session, target.kind(), target.id(), purge).await {
+                    Ok(purged) => {
+                        match &target {
+                            EditTarget::Channel(channel) => channels.set(
+                                channels
+                                    .iter()
+                                    .filter(|c| c.channel_id != channel.channel_id)
+                                    .cloned()
+                                    .collect(),
+                            ),
+                            EditTarget::Playlist(playlist) => playlists.set(
+                                playlists
+                                    .iter()
+                                    .filter(|p| p.playlist_id != playlist.playlist_id)
+                                    .cloned()
+                                    .collect(),
+                            ),
+                        }
+                        if let Some(purged) = purged {
+                            added_message.set(Some(purge_summary(&purged)));
+                        }
+                    }
+                    Err(e) => {
+                        error_message.set(Some(format!(
+                            "Failed to delete {}: {}",
+                            target.kind(),
+                            e
+                        )));
+                    }
+                }
+                removal_busy.set(false);
+                removing.set(None);
+            });
+        })
+    };
+
+    let on_cancel_remove = {
+        let removing = removing.clone();
+        Callback::from(move |_| removing.set(None))
+    };
+
+    // Applies the update to the row right away and reverts it if the backend rejects it
+    let on_save_edit = {
+        let session = session.clone();
+        let editing = editing.clone();
+        let edit_errors = edit_errors.clone();
+        let saving = saving.clone();
+        let channels = channels.clone();
+        let playlists = playlists.clone();
+        let tags = tags.clone();
+
+        Callback::from(move |update: MonitorUpdate| {
+            let session = session.clone();
+            let Some(target) = (*editing).clone() else {
+                return;
+            };
+            let tags = tags.clone();
+            let editing = editing.clone();
+            let edit_errors = edit_errors.clone();
+            let saving = saving.clone();
+            let channels = channels.clone();
+            let playlists = playlists.clone();
+            let previous_channels = (*channels).clone();
+            let previous_playlists = (*playlists).clone();
+
+            let (kind, id) = match &target {
+                EditTarget::Channel(channel) => {
+                    let updated = previous_channels
+                        .iter()
+                        .cloned()
+                        .map(|mut c| {
+                            if c.channel_id == channel.channel_id {
+                                if let Some(name) = &update.name {
+                                    c.channel_name = name.trim().to_string();
+                                }
+                                c.active = update.active.unwrap_or(c.active);
+                                c.hidden = update.hidden.unwrap_or(c.hidden);
+                                c.enqueue_order = update.enqueue_order.unwrap_or(c.enqueue_order);
+                                c.discovery_mode =
+                                    update.discovery_mode.unwrap_or(c.discovery_mode);
+                                if let Some(seconds) = update.max_duration_seconds {
+                                    c.max_duration_seconds = (seconds > 0).then_some(seconds);
+                                }
+                                if let Some(tags) = &update.tags {
+                                    c.tags = tags.clone();
+                                }
+                            }
+                            c
+                        })
+                        .collect();
+                    channels.set(updated);
+                    ("channel", channel.channel_id.clone())
+                }
+                EditTarget::Playlist(playlist) => {
+                    let updated = previous_playlists
+                        .iter()
+                        .cloned()
+                        .map(|mut p| {
+                            if p.playlist_id == playlist.playlist_id {
+                                if let Some(name) = &update.name {
+                                    p.playlist_name = name.trim().to_string();
+                                }
+                                p.active = update.active.unwrap_or(p.active);
+                                p.enqueue_order = update.enqueue_order.unwrap_or(p.enqueue_order);
+                                if let Some(seconds) = update.max_duration_seconds {
+                                    p.max_duration_seconds = (seconds > 0).then_some(seconds);
+                                }
+                                if let Some(tags) = &update.tags {
+                                    p.tags = tags.clone();
+                                }
+                            }
+                            p
+                        })
+                        .collect();
+                    playlists.set(updated);
+                    ("playlist", playlist.playlist_id.clone())
+                }
+            };
 
+            saving.set(true);
             wasm_bindgen_futures::spawn_local(async move {
-                match delete_channel(&channel_id).await {
-                    Ok(_) => {
-                        // Remove channel from list
-                        let current_channels = (*channels).clone();
-                        let updated_channels: Vec<MonitoredChannelStats> = current_channels
-                            .into_iter()
-                            .filter(|c| c.channel_id != channel_id)
-                            .collect();
-                        channels.set(updated_channels);
+                // The stored monitor replaces the optimistic row, it also has fresh counts
+                let saved = match target {
+                    EditTarget::Channel(_) => patch_monitor(&session, kind, &id, &update)
+                        .await
+                        .map(|channel| channels.set(with_channel(&previous_channels, channel))),
+                    EditTarget::Playlist(_) => patch_monitor(&session, kind, &id, &update)
+                        .await
+                        .map(|playlist| {
+                            playlists.set(with_playlist(&previous_playlists, playlist))
+                        }),
+                };
+                match saved {
+                    Ok(()) => {
+                        edit_errors.set(Vec::new());
+                        editing.set(None);
+                        // Counts of the tags changed
+                        if update.tags.is_some() {
+                            if let Ok(tag_list) = session.client().monitor_tags().await {
+                                tags.set(tag_list);
+                            }
+                        }
                     }
                     Err(e) => {
-                        error_message.set(Some(format!("Failed to delete channel: {}", e)));
+                        channels.set(previous_channels);
+                        playlists.set(previous_playlists);
+                        edit_errors.set(match e {
+                            SaveError::Fields(errors) => errors,
+                            SaveError::Other(message) => vec![FieldError {
+                                field: String::new(),
+                                message,
+                            }],
+                        });
+                    }
+                }
+                saving.set(false);
+            });
+        })
+    };
+
+    let on_cancel_edit = {
+        let editing = editing.clone();
+        let edit_errors = edit_errors.clone();
+        Callback::from(move |_| {
+            editing.set(None);
+            edit_errors.set(Vec::new());
+        })
+    };
+
+    let on_select_tag = {
+        let table = table.clone();
+        Callback::from(move |tag: Option<String>| table.set_filter("tag", tag))
+    };
+
+    let on_toggle_tag_public = {
+        let session = session.clone();
+        let tags = tags.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |tag: MonitorTag| {
+            let session = session.clone();
+            let tags = tags.clone();
+            let error_message = error_message.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match session.client().set_tag_public(&tag.tag, !tag.public).await {
+                    Ok(updated) => tags.set(
+                        tags.iter()
+                            .cloned()
+                            .map(|t| {
+                                if t.tag == updated.tag {
+                                    updated.clone()
+                                } else {
+                                    t
+                                }
+                            })
+                            .collect(),
+                    ),
+                    Err(e) => error_message.set(Some(format!("Failed to update tag: {}", e))),
+                }
+            });
+        })
+    };
+
+    let on_check_tag = {
+        let session = session.clone();
+        let error_message = error_message.clone();
+        let added_message = added_message.clone();
+        Callback::from(move |tag: String| {
+            let session = session.clone();
+            let error_message = error_message.clone();
+            let added_message = added_message.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match session.client().check_tag(&tag).await {
+                    Ok(response) => added_message.set(Some(format!(
+                        "Checking {} channels and {} playlists tagged \"{}\"",
+                        response.channels, response.playlists, response.tag
+                    ))),
+                    Err(ClientError::Http(404)) => {
+                        error_message.set(Some(format!("No active monitor is tagged \"{}\"", tag)))
                     }
+                    Err(e) => error_message.set(Some(format!("Failed to start the check: {}", e))),
+                }
+            });
+        })
+    };
+
+    // Opens the delete dialog and counts what purging the monitor would delete
+    let open_remover = |target: EditTarget| {
+        let session = session.clone();
+        let removing = removing.clone();
+        let removal_preview = removal_preview.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |_: MouseEvent| {
+            let session = session.clone();
+            let removal_preview = removal_preview.clone();
+            let error_message = error_message.clone();
+            let target = target.clone();
+            removal_preview.set(None);
+            removing.set(Some(target.clone()));
+            wasm_bindgen_futures::spawn_local(async move {
+                match delete_monitor(
+                    &session,
+                    target.kind(),
+                    target.id(),
+                    Some(MonitorPurge::Preview),
+                )
+                .await
+                {
+                    Ok(preview) => removal_preview.set(preview),
+                    Err(e) => error_message.set(Some(format!(
+                        "Failed to count the videos of the {}: {}",
+                        target.kind(),
+                        e
+                    ))),
                 }
             });
         })
     };
 
+    let open_editor = |target: EditTarget| {
+        let editing = editing.clone();
+        let edit_errors = edit_errors.clone();
+        Callback::from(move |_: MouseEvent| {
+            edit_errors.set(Vec::new());
+            editing.set(Some(target.clone()));
+        })
+    };
+
     html! {
         <div class="min-h-screen bg-gray-700 p-4">
+            {
+                match &*editing {
+                    Some(EditTarget::Channel(channel)) => html! {
+                        <MonitorEditForm
+                            key={channel.channel_id.clone()}
+                            title={format!("Edit channel {}", channel.channel_id)}
+                            name={channel.channel_name.clone()}
+                            active={channel.active}
+                            hidden={Some(channel.hidden)}
+                            enqueue_order={channel.enqueue_order}
+                            discovery_mode={Some(channel.discovery_mode)}
+                            max_duration_seconds={channel.max_duration_seconds}
+                            tags={channel.tags.clone()}
+                            errors={(*edit_errors).clone()}
+                            saving={*saving}
+                            on_save={on_save_edit.clone()}
+                            on_cancel={on_cancel_edit.clone()}
+                        />
+                    },
+                    Some(EditTarget::Playlist(playlist)) => html! {
+                        <MonitorEditForm
+                            key={playlist.playlist_id.clone()}
+                            title={format!("Edit playlist {}", playlist.playlist_id)}
+                            name={playlist.playlist_name.clone()}
+                            active={playlist.active}
+                            hidden={None::<bool>}
+                            enqueue_order={playlist.enqueue_order}
+                            discovery_mode={None::<DiscoveryMode>}
+                            max_duration_seconds={playlist.max_duration_seconds}
+                            tags={playlist.tags.clone()}
+                            errors={(*edit_errors).clone()}
+                            saving={*saving}
+                            on_save={on_save_edit.clone()}
+                            on_cancel={on_cancel_edit.clone()}
+                        />
+                    },
+                    None => html! {},
+                }
+            }
+            if let Some(target) = &*removing {
+                <MonitorDeleteDialog
+                    key={target.id().to_string()}
+                    title={format!("Delete {} {}", target.kind(), target.name())}
+                    is_playlist={matches!(target, EditTarget::Playlist(_))}
+                    preview={(*removal_preview).clone()}
+                    deleting={*removal_busy}
+                    on_confirm={on_confirm_remove.clone()}
+                    on_cancel={on_cancel_remove.clone()}
+                />
+            }
             <div class="max-w-6xl mx-auto">
                 <div class="bg-white rounded-lg shadow-lg p-8">
                     <div class="flex justify-between items-center mb-6">
                         <h1 class="text-3xl font-bold text-gray-800">
                             {"Monitors"}
                         </h1>
-                        <Link<Route> to={Route::Admin} classes="text-blue-600 hover:underline">
+                        <Link<AdminRoute> to={AdminRoute::Admin} classes="text-blue-600 hover:underline">
                             {"← Back to Overview"}
-                        </Link<Route>>
+                        </Link<AdminRoute>>
                     </div>
+                    <TagBar
+                        tags={(*tags).clone()}
+                        selected={selected_tag.clone()}
+                        on_select={on_select_tag}
+                        on_toggle_public={on_toggle_tag_public}
+                        on_check={on_check_tag}
+                    />
                     <div class="bg-white rounded-lg shadow-lg p-8 mt-8">
                         <h2 class="text-3xl font-bold text-gray-800">
                             {"Channels"}
@@ -106,29 +778,55 @@ pub fn admin_monitors_page(_props: &AdminChannelsPageProps) -> Html {
                         <div class="mb-6">
                             <form class="flex gap-4"
                                 onsubmit={
+                                    let session = session.clone();
                                     let new_channel_id = new_channel_id.clone();
                                     let channels = channels.clone();
                                     let error_message = error_message.clone();
+                                    let added_message = added_message.clone();
+                                    let channel_bulk = channel_bulk.clone();
+                                    let channel_results = channel_results.clone();
 
                                     Callback::from(move |e: SubmitEvent| {
+                                        let session = session.clone();
                                         e.prevent_default();
                                         let channel_id = (*new_channel_id).clone();
                                         let channels = channels.clone();
                                         let error_message = error_message.clone();
+                                        let added_message = added_message.clone();
                                         let new_channel_id = new_channel_id.clone();
 
+                                        if *channel_bulk {
+                                            let channel_results = channel_results.clone();
+                                            let inputs: Vec<String> = channel_id
+                                                .lines()
+                                                .map(|line| line.trim().to_string())
+                                                .filter(|line| !line.is_empty())
+                                                .collect();
+                                            wasm_bindgen_futures::spawn_local(async move {
+                                                match add_monitors_bulk(&session, "channel", inputs).await {
+                                                    Ok(results) => {
+                                                        error_message.set(None);
+                                                        channels.set(with_added_channels(&channels, &results));
+                                                        channel_results.set(results);
+                                                        new_channel_id.set(String::new());
+                                                    }
+                                                    Err(e) => {
+                                                        error_message.set(Some(format!("Failed to add channels: {}", e)));
+                                                    }
+                                                }
+                                            });
+                                            return;
+                                        }
+
                                         wasm_bindgen_futures::spawn_local(async move {
-                                            match add_channel(&channel_id).await {
-                                                Ok(_) => {
-                                                    match load_channels().await {
-                                                        Ok(channel_list) => {
-                                                            channels.set(channel_list);
-                                                            new_channel_id.set(String::new());
-                                                        }
-                                                        Err(e) => {
-                                                            error_message.set(Some(format!("Failed to reload channels: {}", e)));
-                                                        }
+                                            match add_channel(&session, &channel_id).await {
+                                                Ok(added) => {
+                                                    error_message.set(None);
+                                                    added_message.set(Some(format!("Added \"{}\" ({})", added.name, added.canonical_url)));
+                                                    if let Some(channel) = added.channel {
+                                                        channels.set(with_channel(&channels, channel));
                                                     }
+                                                    new_channel_id.set(String::new());
                                                 }
                                                 Err(e) => {
                                                     error_message.set(Some(format!("Failed to add channel: {}", e)));
@@ -138,26 +836,73 @@ pub fn admin_monitors_page(_props: &AdminChannelsPageProps) -> Html {
                                     })
                                 }
                             >
-                                <input
-                                    type="text"
-                                    placeholder="Enter YouTube Channel ID"
-                                    class="flex-grow px-4 py-2 border rounded"
-                                    value={(*new_channel_id).clone()}
-                                    onchange={
-                                        let new_channel_id = new_channel_id.clone();
-                                        Callback::from(move |e: Event| {
-                                            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
-                                            new_channel_id.set(input.value());
-                                        })
-                                    }
-                                />
-                                <button
-                                    type="submit"
-                                    class="px-4 py-2 bg-blue-600 text-white rounded hover:bg-blue-700"
-                                >
-                                    {"Add Channel"}
-                                </button>
+                                if *channel_bulk {
+                                    <textarea
+                                        placeholder="One YouTube channel URL per line"
+                                        class="flex-grow px-4 py-2 border rounded h-32"
+                                        value={(*new_channel_id).clone()}
+                                        oninput={
+                                            let new_channel_id = new_channel_id.clone();
+                                            Callback::from(move |e: InputEvent| {
+                                                let input: web_sys::HtmlTextAreaElement = e.target_unchecked_into();
+                                                new_channel_id.set(input.value());
+                                            })
+                                        }
+                                    />
+                                } else {
+                                    <input
+                                        type="text"
+                                        placeholder="Enter YouTube Channel ID"
+                                        class="flex-grow px-4 py-2 border rounded"
+                                        value={(*new_channel_id).clone()}
+                                        onchange={
+                                            let new_channel_id = new_channel_id.clone();
+                                            Callback::from(move |e: Event| {
+                                                let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+                                                new_channel_id.set(input.value());
+                                            })
+                                        }
+                                    />
+                                }
+                                <div class="flex flex-col gap-2">
+                                    <button
+                                        type="submit"
+                                        class="px-4 py-2 bg-blue-600 text-white rounded hover:bg-blue-700"
+                                    >
+                                        {if *channel_bulk { "Add Channels" } else { "Add Channel" }}
+                                    </button>
+                                    <button
+                                        type="button"
+                                        onclick={
+                                            let channel_bulk = channel_bulk.clone();
+                                            Callback::from(move |_| channel_bulk.set(!*channel_bulk))
+                                        }
+                                        class="text-sm text-blue-600 hover:underline"
+                                    >
+                                        {if *channel_bulk { "Single" } else { "Paste multiple" }}
+                                    </button>
+                                </div>
                             </form>
+                            <BulkAddResults
+                                results={(*channel_results).clone()}
+                                on_retry={
+                                    let session = session.clone();
+                                    let channel_results = channel_results.clone();
+                                    let channels = channels.clone();
+                                    Callback::from(move |input: String| {
+                                        let session = session.clone();
+                                        let channel_results = channel_results.clone();
+                                        let channels = channels.clone();
+                                        wasm_bindgen_futures::spawn_local(async move {
+                                            let outcome = add_channel(&session, &input).await;
+                                            if let Ok(MonitorAddResponse { channel: Some(channel), .. }) = &outcome {
+                                                channels.set(with_channel(&channels, channel.clone()));
+                                            }
+                                            channel_results.set(retried_results(&channel_results, input, outcome));
+                                        });
+                                    })
+                                }
+                            />
                         </div>
 
                         {
@@ -172,6 +917,12 @@ pub fn admin_monitors_page(_props: &AdminChannelsPageProps) -> Html {
                             }
                         }
 
+                        if let Some(msg) = &*added_message {
+                            <div class="bg-green-100 border border-green-400 text-green-700 px-4 py-3 rounded mb-4">
+                                { msg }
+                            </div>
+                        }
+
                         {
                             if *loading {
                                 html! {
@@ -188,49 +939,59 @@ pub fn admin_monitors_page(_props: &AdminChannelsPageProps) -> Html {
                                                     <th class="px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider">{"Name"}</th>
                                                     <th class="px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider">{"Indexed Videos"}</th>
                                                     <th class="px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider">{"Active"}</th>
+                                                    <th class="px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider">{"Search"}</th>
                                                     <th class="px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider">{"Actions"}</th>
                                                 </tr>
                                             </thead>
                                             <tbody class="bg-white divide-y divide-gray-200">
                                                 {
-                                                    (*channels).iter().map(|channel| {
+                                                    (*channels).iter().filter(|channel| matches_tag(&channel.tags, selected_tag.as_deref())).map(|channel| {
                                                         let channel_id = channel.channel_id.clone();
-                                                        let on_delete = on_delete_channel.clone();
 
                                                         html! {
-                                                            <tr>
+                                                            <tr id={row_id(&channel.channel_id)} class={classes!(highlight_class(&highlight, &channel.channel_id))}>
                                                                 <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-900">
-                                                                    <div class="max-w-xs truncate"><a href={format!("https://www.youtube.com/channel/{}",&channel.channel_id)} class="text-blue-600 hover:underline">{&channel.channel_name}</a></div>
+                                                                    <div class="max-w-xs truncate">
+                                                                        <a href={format!("https://www.youtube.com/channel/{}",&channel.channel_id)} class="text-blue-600 hover:underline">{&channel.channel_name}</a>
+                                                                        if channel.discovery_mode == DiscoveryMode::Rss {
+                                                                            <span class="ml-2 px-1.5 py-0.5 text-xs rounded bg-orange-100 text-orange-700"
+                                                                                  title="Scheduled checks read the channel's RSS feed">
+                                                                                {"RSS"}
+                                                                            </span>
+                                                                        }
+                                                                        { tag_chips(&channel.tags) }
+                                                                    </div>
                                                                 </td>
                                                                 <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-900">
-                                                                    {&channel.videos_indexed}
+                                                                    {format_count(channel.videos_indexed as i64)}
                                                                     {" / "}
-                                                                    {&channel.videos_uploaded}
+                                                                    {format_count(channel.videos_uploaded)}
+                                                                    <span class="text-gray-500 ml-2">
+                                                                        {format!("({:.1} h)", channel.indexed_hours)}
+                                                                    </span>
+                                                                    if channel.backfilling {
+                                                                        { backfill_progress(channel.videos_indexed as i64, channel.videos_uploaded, channel.backfill_eta_ms) }
+                                                                    }
                                                                 </td>
                                                                 <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-900">
                                                                     <button
                                                                         onclick={
+                                                                            let session = session.clone();
                                                                             let channel_id = channel.channel_id.clone();
                                                                             let current_active = channel.active;
                                                                             let channels = channels.clone();
                                                                             let error_message = error_message.clone();
 
                                                                             Callback::from(move |_| {
+                                                                                let session = session.clone();
                                                                                 let channel_id = channel_id.clone();
                                                                                 let channels = channels.clone();
                                                                                 let error_message = error_message.clone();
 
                                                                                 wasm_bindgen_futures::spawn_local(async move {
-                                                                                    match toggle_channel_active(&channel_id, !current_active).await {
-                                                                                        Ok(_) => {
-                                                                                            match load_channels().await {
-                                                                                                Ok(channel_list) => {
-                                                                                                    channels.set(channel_list);
-                                                                                                }
-                                                                                                Err(e) => {
-                                                                                                    error_message.set(Some(format!("Failed to reload channels: {}", e)));
-                                                                                                }
-                                                                                            }
+                                                                                    match toggle_channel_active(&session, &channel_id, !current_active).await {
+                                                                                        Ok(updated) => {
+                                                                                            channels.set(with_channel(&channels, updated));
                                                                                         }
                                                                                         Err(e) => {
                                                                                             error_message.set(Some(format!("Failed to toggle channel status: {}", e)));
@@ -248,17 +1009,61 @@ pub fn admin_monitors_page(_props: &AdminChannelsPageProps) -> Html {
                                                                         {if channel.active { "Active" } else { "Inactive" }}
                                                                     </button>
                                                                 </td>
+                                                                <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-900">
+                                                                    <button
+                                                                        onclick={
+                                                                            let session = session.clone();
+                                                                            let channel_id = channel.channel_id.clone();
+                                                                            let current_hidden = channel.hidden;
+                                                                            let channels = channels.clone();
+                                                                            let error_message = error_message.clone();
+
+                                                                            Callback::from(move |_| {
+                                                                                let session = session.clone();
+                                                                                let channel_id = channel_id.clone();
+                                                                                let channels = channels.clone();
+                                                                                let error_message = error_message.clone();
+
+                                                                                wasm_bindgen_futures::spawn_local(async move {
+                                                                                    match toggle_channel_hidden(&session, &channel_id, !current_hidden).await {
+                                                                                        Ok(updated) => {
+                                                                                            channels.set(with_channel(&channels, updated));
+                                                                                        }
+                                                                                        Err(e) => {
+                                                                                            error_message.set(Some(format!("Failed to toggle channel visibility: {}", e)));
+                                                                                        }
+                                                                                    }
+                                                                                });
+                                                                            })
+                                                                        }
+                                                                        class={if channel.hidden {
+                                                                            "px-4 py-2 bg-red-600 text-white rounded hover:bg-red-700"
+                                                                        } else {
+                                                                            "px-4 py-2 bg-green-600 text-white rounded hover:bg-green-700"
+                                                                        }}
+                                                                    >
+                                                                        {if channel.hidden { "Hidden" } else { "Visible" }}
+                                                                    </button>
+                                                                </td>
                                                                 <td class="px-6 py-4 whitespace-nowrap text-sm font-medium">
                                                                     <div class="flex gap-2">
+                                                                        <button
+                                                                            onclick={open_editor(EditTarget::Channel(channel.clone()))}
+                                                                            class="text-gray-600 hover:text-gray-900"
+                                                                        >
+                                                                            {"Edit"}
+                                                                        </button>
                                                                         <button
                                                                             onclick={
+                                                                                let session = session.clone();
                                                                                 let channel_id = channel_id.clone();
                                                                                 let error_message = error_message.clone();
                                                                                 Callback::from(move |_| {
+                                                                                    let session = session.clone();
                                                                                     let channel_id = channel_id.clone();
                                                                                     let error_message = error_message.clone();
                                                                                     wasm_bindgen_futures::spawn_local(async move {
-                                                                                        if let Err(e) = force_check_complete_channel(&channel_id).await {
+                                                                                        if let Err(e) = force_check_complete_channel(&session, &channel_id).await {
                                                                                             error_message.set(Some(format!("Failed to check channel: {}", e)));
                                                                                         }
                                                                                     });
@@ -269,13 +1074,7 @@ pub fn admin_monitors_page(_props: &AdminChannelsPageProps) -> Html {
                                                                             {"Check"}
                                                                         </button>
                                                                         <button
-                                                                            onclick={
-                                                                                let channel_id = channel_id.clone();
-                                                                                let on_delete = on_delete.clone();
-                                                                                Callback::from(move |_| {
-                                                                                    on_delete.emit(channel_id.clone());
-                                                                                })
-                                                                            }
+                                                                            onclick={open_remover(EditTarget::Channel(channel.clone()))}
                                                                             class="text-red-600 hover:text-red-900"
                                                                         >
                                                                             {"Delete"}
@@ -298,29 +1097,55 @@ pub fn admin_monitors_page(_props: &AdminChannelsPageProps) -> Html {
                         <div class="mb-6">
                             <form class="flex gap-4"
                                 onsubmit={
+                                    let session = session.clone();
                                     let new_playlist_id = new_playlist_id.clone();
                                     let playlists = playlists.clone();
                                     let error_message = error_message.clone();
+                                    let added_message = added_message.clone();
+                                    let playlist_bulk = playlist_bulk.clone();
+                                    let playlist_results = playlist_results.clone();
 
                                     Callback::from(move |e: SubmitEvent| {
+                                        let session = session.clone();
                                         e.prevent_default();
                                         let playlist_id = (*new_playlist_id).clone();
                                         let playlists = playlists.clone();
                                         let error_message = error_message.clone();
+                                        let added_message = added_message.clone();
                                         let new_playlist_id = new_playlist_id.clone();
 
+                                        if *playlist_bulk {
+                                            let playlist_results = playlist_results.clone();
+                                            let inputs: Vec<String> = playlist_id
+                                                .lines()
+                                                .map(|line| line.trim().to_string())
+                                                .filter(|line| !line.is_empty())
+                                                .collect();
+                                            wasm_bindgen_futures::spawn_local(async move {
+                                                match add_monitors_bulk(&session, "playlist", inputs).await {
+                                                    Ok(results) => {
+                                                        error_message.set(None);
+                                                        playlists.set(with_added_playlists(&playlists, &results));
+                                                        playlist_results.set(results);
+                                                        new_playlist_id.set(String::new());
+                                                    }
+                                                    Err(e) => {
+                                                        error_message.set(Some(format!("Failed to add playlists: {}", e)));
+                                                    }
+                                                }
+                                            });
+                                            return;
+                                        }
+
                                         wasm_bindgen_futures::spawn_local(async move {
-                                            match add_playlist(&playlist_id).await {
-                                                Ok(_) => {
-                                                    match load_playlists().await {
-                                                        Ok(playlist_list) => {
-                                                            playlists.set(playlist_list);
-                                                            new_playlist_id.set(String::new());
-                                                        }
-                                                        Err(e) => {
-                                                            error_message.set(Some(format!("Failed to reload playlists: {}", e)));
-                                                        }
+                                            match add_playlist(&session, &playlist_id).await {
+                                                Ok(added) => {
+                                                    error_message.set(None);
+                                                    added_message.set(Some(format!("Added \"{}\" ({})", added.name, added.canonical_url)));
+                                                    if let Some(playlist) = added.playlist {
+                                                        playlists.set(with_playlist(&playlists, playlist));
                                                     }
+                                                    new_playlist_id.set(String::new());
                                                 }
                                                 Err(e) => {
                                                     error_message.set(Some(format!("Failed to add playlist: {}", e)));
@@ -330,26 +1155,73 @@ pub fn admin_monitors_page(_props: &AdminChannelsPageProps) -> Html {
                                     })
                                 }
                             >
-                                <input
-                                    type="text"
-                                    placeholder="Enter YouTube Playlist ID"
-                                    class="flex-grow px-4 py-2 border rounded"
-                                    value={(*new_playlist_id).clone()}
-                                    onchange={
-                                        let new_playlist_id = new_playlist_id.clone();
-                                        Callback::from(move |e: Event| {
-                                            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
-                                            new_playlist_id.set(input.value());
-                                        })
-                                    }
-                                />
-                                <button
-                                    type="submit"
-                                    class="px-4 py-2 bg-blue-600 text-white rounded hover:bg-blue-700"
-                                >
-                                    {"Add Playlist"}
-                                </button>
+                                if *playlist_bulk {
+                                    <textarea
+                                        placeholder="One YouTube playlist URL per line"
+                                        class="flex-grow px-4 py-2 border rounded h-32"
+                                        value={(*new_playlist_id).clone()}
+                                        oninput={
+                                            let new_playlist_id = new_playlist_id.clone();
+                                            Callback::from(move |e: InputEvent| {
+                                                let input: web_sys::HtmlTextAreaElement = e.target_unchecked_into();
+                                                new_playlist_id.set(input.value());
+                                            })
+                                        }
+                                    />
+                                } else {
+                                    <input
+                                        type="text"
+                                        placeholder="Enter YouTube Playlist ID"
+                                        class="flex-grow px-4 py-2 border rounded"
+                                        value={(*new_playlist_id).clone()}
+                                        onchange={
+                                            let new_playlist_id = new_playlist_id.clone();
+                                            Callback::from(move |e: Event| {
+                                                let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+                                                new_playlist_id.set(input.value());
+                                            })
+                                        }
+                                    />
+                                }
+                                <div class="flex flex-col gap-2">
+                                    <button
+                                        type="submit"
+                                        class="px-4 py-2 bg-blue-600 text-white rounded hover:bg-blue-700"
+                                    >
+                                        {if *playlist_bulk { "Add Playlists" } else { "Add Playlist" }}
+                                    </button>
+                                    <button
+                                        type="button"
+                                        onclick={
+                                            let playlist_bulk = playlist_bulk.clone();
+                                            Callback::from(move |_| playlist_bulk.set(!*playlist_bulk))
+                                        }
+                                        class="text-sm text-blue-600 hover:underline"
+                                    >
+                                        {if *playlist_bulk { "Single" } else { "Paste multiple" }}
+                                    </button>
+                                </div>
                             </form>
+                            <BulkAddResults
+                                results={(*playlist_results).clone()}
+                                on_retry={
+                                    let session = session.clone();
+                                    let playlist_results = playlist_results.clone();
+                                    let playlists = playlists.clone();
+                                    Callback::from(move |input: String| {
+                                        let session = session.clone();
+                                        let playlist_results = playlist_results.clone();
+                                        let playlists = playlists.clone();
+                                        wasm_bindgen_futures::spawn_local(async move {
+                                            let outcome = add_playlist(&session, &input).await;
+                                            if let Ok(MonitorAddResponse { playlist: Some(playlist), .. }) = &outcome {
+                                                playlists.set(with_playlist(&playlists, playlist.clone()));
+                                            }
+                                            playlist_results.set(retried_results(&playlist_results, input, outcome));
+                                        });
+                                    })
+                                }
+                            />
                         </div>
 
                         <div class="overflow-x-auto">
@@ -364,44 +1236,45 @@ pub fn admin_monitors_page(_props: &AdminChannelsPageProps) -> Html {
                                 </thead>
                                 <tbody class="bg-white divide-y divide-gray-200">
                                     {
-                                        (*playlists).iter().map(|playlist| {
+                                        (*playlists).iter().filter(|playlist| matches_tag(&playlist.tags, selected_tag.as_deref())).map(|playlist| {
                                             let playlist_id = playlist.playlist_id.clone();
                                             let playlist_link = format!("https://www.youtube.com/playlist?list={}", &playlist.playlist_id);
 
                                             html! {
-                                                <tr>
+                                                <tr id={row_id(&playlist.playlist_id)} class={classes!(highlight_class(&highlight, &playlist.playlist_id))}>
                                                     <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-900">
-                                                        <div class="max-w-xs truncate"><a href={playlist_link} class="text-blue-600 hover:underline">{&playlist.playlist_name}</a></div>
+                                                        <div class="max-w-xs truncate">
+                                                            <a href={playlist_link} class="text-blue-600 hover:underline">{&playlist.playlist_name}</a>
+                                                            { tag_chips(&playlist.tags) }
+                                                        </div>
                                                     </td>
                                                     <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-900">
-                                                        {&playlist.videos_indexed}
+                                                        {format_count(playlist.videos_indexed as i64)}
                                                         {" / "}
                                                         {&playlist.videos_added}
+                                                        if playlist.backfilling {
+                                                            { backfill_progress(playlist.videos_indexed as i64, playlist.videos_added, playlist.backfill_eta_ms) }
+                                                        }
                                                     </td>
                                                     <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-900">
                                                         <button
                                                             onclick={
+                                                                let session = session.clone();
                                                                 let playlist_id = playlist.playlist_id.clone();
                                                                 let current_active = playlist.active;
                                                                 let playlists = playlists.clone();
                                                                 let error_message = error_message.clone();
 
                                                                 Callback::from(move |_| {
+                                                                    let session = session.clone();
                                                                     let playlist_id = playlist_id.clone();
                                                                     let playlists = playlists.clone();
                                                                     let error_message = error_message.clone();
 
                                                                     wasm_bindgen_futures::spawn_local(async move {
-                                                                        match toggle_playlist_active(&playlist_id, !current_active).await {
-                                                                            Ok(_) => {
-                                                                                match load_playlists().await {
-                                                                                    Ok(playlist_list) => {
-                                                                                        playlists.set(playlist_list);
-                                                                                    }
-                                                                                    Err(e) => {
-                                                                                        error_message.set(Some(format!("Failed to reload playlists: {}", e)));
-                                                                                    }
-                                                                                }
+                                                                        match toggle_playlist_active(&session, &playlist_id, !current_active).await {
+                                                                            Ok(updated) => {
+                                                                                playlists.set(with_playlist(&playlists, updated));
                                                                             }
                                                                             Err(e) => {
                                                                                 error_message.set(Some(format!("Failed to toggle playlist status: {}", e)));
@@ -421,15 +1294,23 @@ pub fn admin_monitors_page(_props: &AdminChannelsPageProps) -> Html {
                                                     </td>
                                                     <td class="px-6 py-4 whitespace-nowrap text-sm font-medium">
                                                         <div class="flex gap-2">
+                                                            <button
+                                                                onclick={open_editor(EditTarget::Playlist(playlist.clone()))}
+                                                                class="text-gray-600 hover:text-gray-900"
+                                                            >
+                                                                {"Edit"}
+                                                            </button>
                                                             <button
                                                                 onclick={
+                                                                    let session = session.clone();
                                                                     let playlist_id = playlist_id.clone();
                                                                     let error_message = error_message.clone();
                                                                     Callback::from(move |_| {
+                                                                        let session = session.clone();
                                                                         let playlist_id = playlist_id.clone();
                                                                         let error_message = error_message.clone();
                                                                         wasm_bindgen_futures::spawn_local(async move {
-                                                                            if let Err(e) = force_check_complete_playlist(&playlist_id).await {
+                                                                            if let Err(e) = force_check_complete_playlist(&session, &playlist_id).await {
                                                                                 error_message.set(Some(format!("Failed to check playlist: {}", e)));
                                                                             }
                                                                         });
@@ -440,31 +1321,7 @@ pub fn admin_monitors_page(_props: &AdminChannelsPageProps) -> Html {
                                                                 {"Check"}
                                                             </button>
                                                             <button
-                                                                onclick={
-                                                                    let playlist_id = playlist_id.clone();
-                                                                    let playlists = playlists.clone();
-                                                                    let error_message = error_message.clone();
-                                                                    Callback::from(move |_| {
-                                                                        let playlist_id = playlist_id.clone();
-                                                                        let playlists = playlists.clone();
-                                                                        let error_message = error_message.clone();
-                                                                        wasm_bindgen_futures::spawn_local(async move {
-                                                                            match delete_playlist(&playlist_id).await {
-                                                                                Ok(_) => {
-                                                                                    let current_playlists = (*playlists).clone();
-                                                                                    let updated_playlists: Vec<MonitoredPlaylistStats> = current_playlists
-                                                                                        .into_iter()
-                                                                                        .filter(|p| p.playlist_id != playlist_id)
-                                                                                        .collect();
-                                                                                    playlists.set(updated_playlists);
-                                                                                }
-                                                                                Err(e) => {
-                                                                                    error_message.set(Some(format!("Failed to delete playlist: {}", e)));
-                                                                                }
-                                                                            }
-                                                                        });
-                                                                    })
-                                                                }
+                                                                onclick={open_remover(EditTarget::Playlist(playlist.clone()))}
                                                                 class="text-red-600 hover:text-red-900"
                                                             >
                                                                 {"Delete"}
@@ -479,24 +1336,19 @@ pub fn admin_monitors_page(_props: &AdminChannelsPageProps) -> Html {
                             </table>
                         </div>
                     </div>
+                    <OptOutPanel />
                 </div>
             </div>
         </div>
     }
 }
 
-async fn load_channels() -> Result<Vec<MonitoredChannelStats>, String> {
+async fn load_channels(session: &AdminSession) -> Result<Vec<MonitoredChannelStats>, String> {
     let backend_url = &*BACKEND_URL;
     let url = format!("{}/monitor/channel", backend_url);
 
-    let token = window()
-        .and_then(|w| w.session_storage().ok())
-        .and_then(|s| s.and_then(|storage| storage.get_item("admin_token").ok()))
-        .flatten()
-        .ok_or("No admin token found")?;
-
     let response = Request::get(&url)
-        .header("Authorization", &format!("Bearer {}", token))
+        .header("Authorization", &format!("Bearer {}", session.token))
         .send()
         .await
         .map_err(|e| format!("Network error: {}", e))?;
@@ -511,27 +1363,16 @@ async fn load_channels() -> Result<Vec<MonitoredChannelStats>, String> {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct NewChannel {
-    input: String,
-}
-
-async fn add_channel(input: &str) -> Result<(), String> {
+async fn add_channel(session: &AdminSession, input: &str) -> Result<MonitorAddResponse, String> {
     let backend_url = &*BACKEND_URL;
     let url = format!("{}/monitor/channel", backend_url);
 
-    let token = window()
-        .and_then(|w| w.session_storage().ok())
-        .and_then(|s| s.and_then(|storage| storage.get_item("admin_token").ok()))
-        .flatten()
-        .ok_or("No admin token found")?;
-
     let new_channel = NewChannel {
         input: input.to_string(),
     };
 
     let response = Request::post(&url)
-        .header("Authorization", &format!("Bearer {}", token))
+        .header("Authorization", &format!("Bearer {}", session.token))
         .header("Content-Type", "application/json")
         .json(&new_channel)
         .map_err(|e| format!("Failed to serialize: {}", e))?
@@ -540,47 +1381,57 @@ async fn add_channel(input: &str) -> Result<(), String> {
         .map_err(|e| format!("Network error: {}", e))?;
 
     if response.ok() {
-        Ok(())
+        response
+            .json::<MonitorAddResponse>()
+            .await
+            .map_err(|e| format!("JSON parse error: {}", e))
     } else {
         Err(format!("HTTP error: {}", response.status()))
     }
 }
 
-async fn delete_channel(channel_id: &str) -> Result<(), String> {
+/// Deletes a monitor, with `purge` also its videos. Returns what was or would be purged.
+async fn delete_monitor(
+    session: &AdminSession,
+    kind: &str,
+    id: &str,
+    purge: Option<MonitorPurge>,
+) -> Result<Option<MonitorPurgeResponse>, String> {
     let backend_url = &*BACKEND_URL;
-    let url = format!("{}/monitor/channel/{}", backend_url, channel_id);
-
-    let token = window()
-        .and_then(|w| w.session_storage().ok())
-        .and_then(|s| s.and_then(|storage| storage.get_item("admin_token").ok()))
-        .flatten()
-        .ok_or("No admin token found")?;
+    let url = format!("{}/monitor/{}/{}", backend_url, kind, id);
 
-    let response = Request::delete(&url)
-        .header("Authorization", &format!("Bearer {}", token))
+    let mut request = Request::delete(&url);
+    if let Some(purge) = purge {
+        request = request.query([("purge", purge.key())]);
+    }
+    let response = request
+        .header("Authorization", &format!("Bearer {}", session.token))
         .send()
         .await
         .map_err(|e| format!("Network error: {}", e))?;
 
-    if response.ok() {
-        Ok(())
-    } else {
+    if !response.ok() {
         Err(format!("HTTP error: {}", response.status()))
+    } else if purge.is_some() {
+        response
+            .json::<MonitorPurgeResponse>()
+            .await
+            .map(Some)
+            .map_err(|e| format!("JSON parse error: {}", e))
+    } else {
+        Ok(None)
     }
 }
 
-async fn force_check_complete_channel(channel_id: &str) -> Result<(), String> {
+async fn force_check_complete_channel(
+    session: &AdminSession,
+    channel_id: &str,
+) -> Result<(), String> {
     let backend_url = &*BACKEND_URL;
     let url = format!("{}/monitor/channel/{}/check", backend_url, channel_id);
 
-    let token = window()
-        .and_then(|w| w.session_storage().ok())
-        .and_then(|s| s.and_then(|storage| storage.get_item("admin_token").ok()))
-        .flatten()
-        .ok_or("No admin token found")?;
-
     let response = Request::post(&url)
-        .header("Authorization", &format!("Bearer {}", token))
+        .header("Authorization", &format!("Bearer {}", session.token))
         .send()
         .await
         .map_err(|e| format!("Network error: {}", e))?;
@@ -592,18 +1443,12 @@ async fn force_check_complete_channel(channel_id: &str) -> Result<(), String> {
     }
 }
 
-async fn load_playlists() -> Result<Vec<MonitoredPlaylistStats>, String> {
+async fn load_playlists(session: &AdminSession) -> Result<Vec<MonitoredPlaylistStats>, String> {
     let backend_url = &*BACKEND_URL;
     let url = format!("{}/monitor/playlist", backend_url);
 
-    let token = window()
-        .and_then(|w| w.session_storage().ok())
-        .and_then(|s| s.and_then(|storage| storage.get_item("admin_token").ok()))
-        .flatten()
-        .ok_or("No admin token found")?;
-
     let response = Request::get(&url)
-        .header("Authorization", &format!("Bearer {}", token))
+        .header("Authorization", &format!("Bearer {}", session.token))
         .send()
         .await
         .map_err(|e| format!("Network error: {}", e))?;
@@ -618,22 +1463,16 @@ async fn load_playlists() -> Result<Vec<MonitoredPlaylistStats>, String> {
     }
 }
 
-async fn add_playlist(input: &str) -> Result<(), String> {
+async fn add_playlist(session: &AdminSession, input: &str) -> Result<MonitorAddResponse, String> {
     let backend_url = &*BACKEND_URL;
     let url = format!("{}/monitor/playlist", backend_url);
 
-    let token = window()
-        .and_then(|w| w.session_storage().ok())
-        .and_then(|s| s.and_then(|storage| storage.get_item("admin_token").ok()))
-        .flatten()
-        .ok_or("No admin token found")?;
-
-    let new_playlist = NewChannel {
+    let new_playlist = NewPlaylist {
         input: input.to_string(),
     };
 
     let response = Request::post(&url)
-        .header("Authorization", &format!("Bearer {}", token))
+        .header("Authorization", &format!("Bearer {}", session.token))
         .header("Content-Type", "application/json")
         .json(&new_playlist)
         .map_err(|e| format!("Failed to serialize: {}", e))?
@@ -642,24 +1481,24 @@ async fn add_playlist(input: &str) -> Result<(), String> {
         .map_err(|e| format!("Network error: {}", e))?;
 
     if response.ok() {
-        Ok(())
+        response
+            .json::<MonitorAddResponse>()
+            .await
+            .map_err(|e| format!("JSON parse error: {}", e))
     } else {
         Err(format!("HTTP error: {}", response.status()))
     }
 }
 
-async fn delete_playlist(playlist_id: &str) -> Result<(), String> {
+async fn force_check_complete_playlist(
+    session: &AdminSession,
+    playlist_id: &str,
+) -> Result<(), String> {
     let backend_url = &*BACKEND_URL;
-    let url = format!("{}/monitor/playlist/{}", backend_url, playlist_id);
-
-    let token = window()
-        .and_then(|w| w.session_storage().ok())
-        .and_then(|s| s.and_then(|storage| storage.get_item("admin_token").ok()))
-        .flatten()
-        .ok_or("No admin token found")?;
+    let url = format!("{}/monitor/playlist/{}/check", backend_url, playlist_id);
 
-    let response = Request::delete(&url)
-        .header("Authorization", &format!("Bearer {}", token))
+    let response = Request::post(&url)
+        .header("Authorization", &format!("Bearer {}", session.token))
         .send()
         .await
         .map_err(|e| format!("Network error: {}", e))?;
@@ -671,80 +1510,153 @@ async fn delete_playlist(playlist_id: &str) -> Result<(), String> {
     }
 }
 
-async fn force_check_complete_playlist(playlist_id: &str) -> Result<(), String> {
+async fn toggle_playlist_active(
+    session: &AdminSession,
+    playlist_id: &str,
+    active: bool,
+) -> Result<MonitoredPlaylistStats, String> {
     let backend_url = &*BACKEND_URL;
-    let url = format!("{}/monitor/playlist/{}/check", backend_url, playlist_id);
-
-    let token = window()
-        .and_then(|w| w.session_storage().ok())
-        .and_then(|s| s.and_then(|storage| storage.get_item("admin_token").ok()))
-        .flatten()
-        .ok_or("No admin token found")?;
+    let url = format!(
+        "{}/monitor/playlist/{}/{}",
+        backend_url,
+        playlist_id,
+        if active { "activate" } else { "deactivate" }
+    );
 
     let response = Request::post(&url)
-        .header("Authorization", &format!("Bearer {}", token))
+        .header("Authorization", &format!("Bearer {}", session.token))
         .send()
         .await
         .map_err(|e| format!("Network error: {}", e))?;
 
     if response.ok() {
-        Ok(())
+        response
+            .json::<MonitoredPlaylistStats>()
+            .await
+            .map_err(|e| format!("JSON parse error: {}", e))
     } else {
         Err(format!("HTTP error: {}", response.status()))
     }
 }
 
-async fn toggle_playlist_active(playlist_id: &str, active: bool) -> Result<(), String> {
+async fn toggle_channel_active(
+    session: &AdminSession,
+    channel_id: &str,
+    active: bool,
+) -> Result<MonitoredChannelStats, String> {
     let backend_url = &*BACKEND_URL;
     let url = format!(
-        "{}/monitor/playlist/{}/{}",
+        "{}/monitor/channel/{}/{}",
         backend_url,
-        playlist_id,
+        channel_id,
         if active { "activate" } else { "deactivate" }
     );
 
-    let token = window()
-        .and_then(|w| w.session_storage().ok())
-        .and_then(|s| s.and_then(|storage| storage.get_item("admin_token").ok()))
-        .flatten()
-        .ok_or("No admin token found")?;
-
     let response = Request::post(&url)
-        .header("Authorization", &format!("Bearer {}", token))
+        .header("Authorization", &format!("Bearer {}", session.token))
         .send()
         .await
         .map_err(|e| format!("Network error: {}", e))?;
 
     if response.ok() {
-        Ok(())
+        response
+            .json::<MonitoredChannelStats>()
+            .await
+            .map_err(|e| format!("JSON parse error: {}", e))
     } else {
         Err(format!("HTTP error: {}", response.status()))
     }
 }
 
-async fn toggle_channel_active(channel_id: &str, active: bool) -> Result<(), String> {
+async fn toggle_channel_hidden(
+    session: &AdminSession,
+    channel_id: &str,
+    hidden: bool,
+) -> Result<MonitoredChannelStats, String> {
     let backend_url = &*BACKEND_URL;
     let url = format!(
         "{}/monitor/channel/{}/{}",
         backend_url,
         channel_id,
-        if active { "activate" } else { "deactivate" }
+        if hidden { "hide" } else { "unhide" }
     );
 
-    let token = window()
-        .and_then(|w| w.session_storage().ok())
-        .and_then(|s| s.and_then(|storage| storage.get_item("admin_token").ok()))
-        .flatten()
-        .ok_or("No admin token found")?;
+    let response = Request::post(&url)
+        .header("Authorization", &format!("Bearer {}", session.token))
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+
+    if response.ok() {
+        response
+            .json::<MonitoredChannelStats>()
+            .await
+            .map_err(|e| format!("JSON parse error: {}", e))
+    } else {
+        Err(format!("HTTP error: {}", response.status()))
+    }
+}
+
+/// Returns the monitor as the backend stored it, `T` being the stats of `kind`
+async fn patch_monitor<T: DeserializeOwned>(
+    session: &AdminSession,
+    kind: &str,
+    id: &str,
+    update: &MonitorUpdate,
+) -> Result<T, SaveError> {
+    let backend_url = &*BACKEND_URL;
+    let url = format!("{}/monitor/{}/{}", backend_url, kind, id);
+
+    let response = Request::patch(&url)
+        .header("Authorization", &format!("Bearer {}", session.token))
+        .header("Content-Type", "application/json")
+        .json(update)
+        .map_err(|e| SaveError::Other(format!("Failed to serialize: {}", e)))?
+        .send()
+        .await
+        .map_err(|e| SaveError::Other(format!("Network error: {}", e)))?;
+
+    if response.ok() {
+        response
+            .json::<T>()
+            .await
+            .map_err(|e| SaveError::Other(format!("JSON parse error: {}", e)))
+    } else if response.status() == 422 {
+        Err(response
+            .json::<Vec<FieldError>>()
+            .await
+            .map(SaveError::Fields)
+            .unwrap_or_else(|e| SaveError::Other(format!("JSON parse error: {}", e))))
+    } else {
+        Err(SaveError::Other(format!(
+            "HTTP error: {}",
+            response.status()
+        )))
+    }
+}
+
+async fn add_monitors_bulk(
+    session: &AdminSession,
+    kind: &str,
+    inputs: Vec<String>,
+) -> Result<Vec<BulkMonitorResult>, String> {
+    let backend_url = &*BACKEND_URL;
+    let url = format!("{}/monitor/{}/bulk", backend_url, kind);
 
     let response = Request::post(&url)
-        .header("Authorization", &format!("Bearer {}", token))
+        .header("Authorization", &format!("Bearer {}", session.token))
+        .header("Content-Type", "application/json")
+        .json(&BulkMonitorRequest { inputs })
+        .map_err(|e| format!("Failed to serialize: {}", e))?
         .send()
         .await
         .map_err(|e| format!("Network error: {}", e))?;
 
     if response.ok() {
-        Ok(())
+        response
+            .json::<Vec<BulkMonitorResult>>()
+            .await
+            .map_err(|e| format!("JSON parse error: {}", e))
     } else {
         Err(format!("HTTP error: {}", response.status()))
     }
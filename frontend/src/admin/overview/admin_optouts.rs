@@ -0,0 +1,245 @@
+use crate::admin::utils::AdminSession;
+use crate::utils::{format_count, format_relative_time};
+use starchive_api_types::{OptOutChannel, OptOutRequest, OptOutResponse};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+/// Channels that asked to be removed. Opting out previews the channel's indexed content
+/// first, deleting it is confirmed with those counts in view.
+#[function_component(OptOutPanel)]
+pub fn opt_out_panel() -> Html {
+    let session = use_context::<AdminSession>().expect("rendered inside AdminGuard");
+    let opt_outs = use_state(Vec::<OptOutChannel>::new);
+    let channel_id = use_state(String::new);
+    let reason = use_state(String::new);
+    let delete_content = use_state(|| false);
+    // Dry run of the request in the form, cleared whenever the form changes
+    let preview = use_state(|| None::<OptOutResponse>);
+    let message = use_state(|| None::<String>);
+    let error_message = use_state(|| None::<String>);
+
+    {
+        let session = session.clone();
+        let opt_outs = opt_outs.clone();
+        let error_message = error_message.clone();
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                match session.client().opt_outs().await {
+                    Ok(list) => opt_outs.set(list),
+                    Err(e) => error_message.set(Some(format!("Failed to load opt-outs: {}", e))),
+                }
+            });
+            || ()
+        });
+    }
+
+    let request = OptOutRequest {
+        channel_id: channel_id.trim().to_string(),
+        reason: (*reason).clone(),
+        delete_content: *delete_content,
+    };
+
+    let on_submit = {
+        let session = session.clone();
+        let opt_outs = opt_outs.clone();
+        let channel_id = channel_id.clone();
+        let reason = reason.clone();
+        let preview = preview.clone();
+        let message = message.clone();
+        let error_message = error_message.clone();
+        let request = request.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let session = session.clone();
+            let opt_outs = opt_outs.clone();
+            let channel_id = channel_id.clone();
+            let reason = reason.clone();
+            let preview = preview.clone();
+            let message = message.clone();
+            let error_message = error_message.clone();
+            let request = request.clone();
+            // The first submit is the dry run, the second one opts out for real
+            let dry_run = preview.is_none();
+            wasm_bindgen_futures::spawn_local(async move {
+                match session.client().add_opt_out(&request, dry_run).await {
+                    Ok(response) if response.dry_run => {
+                        error_message.set(None);
+                        preview.set(Some(response));
+                    }
+                    Ok(response) => {
+                        error_message.set(None);
+                        preview.set(None);
+                        channel_id.set(String::new());
+                        reason.set(String::new());
+                        message.set(Some(match response.task_id {
+                            Some(_) => format!(
+                                "Opted out {}, deleting {} videos and {} captions",
+                                response.channel_id,
+                                format_count(response.videos),
+                                format_count(response.captions)
+                            ),
+                            None => format!("Opted out {}", response.channel_id),
+                        }));
+                        if let Ok(list) = session.client().opt_outs().await {
+                            opt_outs.set(list);
+                        }
+                    }
+                    Err(e) => error_message.set(Some(format!("Failed to opt out: {}", e))),
+                }
+            });
+        })
+    };
+
+    let on_remove = {
+        let opt_outs = opt_outs.clone();
+        let error_message = error_message.clone();
+        Callback::from(move |removed: String| {
+            let session = session.clone();
+            let opt_outs = opt_outs.clone();
+            let error_message = error_message.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match session.client().remove_opt_out(&removed).await {
+                    Ok(()) => opt_outs.set(
+                        opt_outs
+                            .iter()
+                            .filter(|opt_out| opt_out.channel_id != removed)
+                            .cloned()
+                            .collect(),
+                    ),
+                    Err(e) => error_message.set(Some(format!("Failed to remove opt-out: {}", e))),
+                }
+            });
+        })
+    };
+
+    let on_channel_input = {
+        let channel_id = channel_id.clone();
+        let preview = preview.clone();
+        Callback::from(move |e: InputEvent| {
+            channel_id.set(e.target_unchecked_into::<HtmlInputElement>().value());
+            preview.set(None);
+        })
+    };
+    let on_reason_input = {
+        let reason = reason.clone();
+        Callback::from(move |e: InputEvent| {
+            reason.set(e.target_unchecked_into::<HtmlInputElement>().value());
+        })
+    };
+    let on_delete_toggle = {
+        let delete_content = delete_content.clone();
+        let preview = preview.clone();
+        Callback::from(move |_| {
+            delete_content.set(!*delete_content);
+            preview.set(None);
+        })
+    };
+
+    html! {
+        <div class="mt-8">
+            <h2 class="text-2xl font-semibold text-gray-800 mb-2">{"Opted-out Channels"}</h2>
+            <p class="text-sm text-gray-500 mb-4">
+                {"Videos of these channels are never indexed, whether enqueued manually, by a playlist or by a channel monitor."}
+            </p>
+            <form onsubmit={on_submit} class="flex flex-wrap gap-2 items-center mb-4">
+                <input
+                    type="text"
+                    placeholder="Channel ID (UC...)"
+                    value={(*channel_id).clone()}
+                    oninput={on_channel_input}
+                    class="px-3 py-2 border border-gray-300 rounded"
+                />
+                <input
+                    type="text"
+                    placeholder="Reason"
+                    value={(*reason).clone()}
+                    oninput={on_reason_input}
+                    class="flex-1 px-3 py-2 border border-gray-300 rounded"
+                />
+                <label class="text-sm text-gray-700">
+                    <input type="checkbox" checked={*delete_content} onclick={on_delete_toggle} class="mr-1" />
+                    {"Delete indexed content"}
+                </label>
+                <button
+                    type="submit"
+                    disabled={request.channel_id.is_empty()}
+                    class={if preview.is_some() {
+                        "px-4 py-2 bg-red-600 text-white rounded hover:bg-red-700"
+                    } else {
+                        "px-4 py-2 bg-gray-600 text-white rounded hover:bg-gray-700"
+                    }}
+                >
+                    {if preview.is_some() { "Confirm opt-out" } else { "Preview" }}
+                </button>
+            </form>
+            {
+                if let Some(preview) = &*preview {
+                    html! {
+                        <p class="text-sm text-gray-700 mb-4">
+                            {format!(
+                                "{} has {} indexed videos with {} captions. {}",
+                                preview.channel_id,
+                                format_count(preview.videos),
+                                format_count(preview.captions),
+                                if *delete_content { "They will be deleted." } else { "They stay indexed." }
+                            )}
+                        </p>
+                    }
+                } else {
+                    html! {}
+                }
+            }
+            {
+                if let Some(msg) = &*message {
+                    html! { <p class="text-sm text-green-700 mb-4">{msg}</p> }
+                } else {
+                    html! {}
+                }
+            }
+            {
+                if let Some(msg) = &*error_message {
+                    html! { <p class="text-sm text-red-600 mb-4">{msg}</p> }
+                } else {
+                    html! {}
+                }
+            }
+            <table class="min-w-full bg-white border border-gray-300">
+                <thead class="bg-gray-50">
+                    <tr>
+                        <th class="px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider">{"Channel"}</th>
+                        <th class="px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider">{"Reason"}</th>
+                        <th class="px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider">{"Opted out"}</th>
+                        <th class="px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider">{"Actions"}</th>
+                    </tr>
+                </thead>
+                <tbody class="bg-white divide-y divide-gray-200">
+                    {
+                        opt_outs.iter().map(|opt_out| {
+                            let on_remove = on_remove.clone();
+                            let removed = opt_out.channel_id.clone();
+                            html! {
+                                <tr key={opt_out.channel_id.clone()}>
+                                    <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-900">
+                                        <a href={format!("https://www.youtube.com/channel/{}", opt_out.channel_id)} class="text-blue-600 hover:underline">
+                                            {&opt_out.channel_id}
+                                        </a>
+                                    </td>
+                                    <td class="px-6 py-4 text-sm text-gray-900">{&opt_out.reason}</td>
+                                    <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-500">{format_relative_time(opt_out.created_at_ms)}</td>
+                                    <td class="px-6 py-4 whitespace-nowrap text-sm">
+                                        <button
+                                            onclick={Callback::from(move |_| on_remove.emit(removed.clone()))}
+                                            class="text-red-600 hover:text-red-900"
+                                        >
+                                            {"Remove"}
+                                        </button>
+                                    </td>
+                                </tr>
+                            }
+                        }).collect::<Html>()
+                    }
+                </tbody>
+            </table>
+        </div>
+    }
+}
@@ -1,23 +1,34 @@
-use crate::env_variable_utils::BACKEND_URL;
-use crate::router::Route;
-use gloo_net::http::Request;
-use serde::{Deserialize, Serialize};
-use web_sys::{window, HtmlInputElement};
+use crate::admin::router::AdminRoute;
+use crate::admin::utils::{
+    highlight_class, row_id, use_highlighted_row, use_table_state, AdminSession,
+};
+use crate::search::search_options::event_value;
+use crate::utils::{format_local_datetime, format_relative_time};
+use starchive_api_types::client::ClientError;
+use starchive_api_types::QueueItem;
+use web_sys::HtmlInputElement;
 use yew::prelude::*;
 use yew_router::prelude::*;
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-pub struct QueueItem {
-    pub id: String,
-    pub status: String,
-    pub added_at: String,
-    pub processed_at: Option<String>,
-    pub error_message: Option<String>,
+/// The queue is listed in full, so its table state only uses the filter and the sort
+const QUEUE_PER_PAGE: i64 = 0;
+
+const QUEUE_STATUSES: &[&str] = &["pending", "processing", "failed"];
+
+/// Header actions on every failed queue item, completed items leave the queue on their own
+#[derive(Clone, Copy, PartialEq)]
+enum BatchAction {
+    RetryFailed,
+    ClearFailed,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AddUrlRequest {
-    pub url: String,
+impl BatchAction {
+    fn confirmation(&self, count: usize) -> String {
+        match self {
+            BatchAction::RetryFailed => format!("Reset {count} failed items to pending?"),
+            BatchAction::ClearFailed => format!("Remove {count} failed items from the queue?"),
+        }
+    }
 }
 
 #[derive(Properties, PartialEq)]
@@ -30,9 +41,40 @@ pub fn admin_queue_page(_props: &AdminQueuePageProps) -> Html {
     let error_message = use_state(|| None::<String>);
     let success_message = use_state(|| None::<String>);
     let new_url = use_state(String::new);
+    let session = use_context::<AdminSession>().expect("rendered inside AdminGuard");
+    let highlight = use_highlighted_row(!*loading && !queue_items.is_empty());
+    let table = use_table_state(QUEUE_PER_PAGE);
+
+    let status_filter = table.filter("status").map(str::to_string);
+    let mut visible_items: Vec<&QueueItem> = queue_items
+        .iter()
+        .filter(|item| {
+            status_filter
+                .as_ref()
+                .is_none_or(|status| item.status == *status)
+        })
+        .collect();
+    match table.sort.as_deref() {
+        Some("newest") => visible_items.sort_by_key(|item| std::cmp::Reverse(item.added_at_ms)),
+        Some("oldest") => visible_items.sort_by_key(|item| item.added_at_ms),
+        _ => {} // queue order
+    }
+
+    let on_status_change = {
+        let table = table.clone();
+        Callback::from(move |e: Event| table.set_filter("status", event_value(&e)))
+    };
+
+    let on_sort_change = {
+        let table = table.clone();
+        Callback::from(move |e: Event| {
+            table.set_sort(event_value(&e).filter(|sort| !sort.is_empty()))
+        })
+    };
 
     // Load queue items on component mount
     {
+        let session = session.clone();
         let queue_items = queue_items.clone();
         let loading = loading.clone();
         let error_message = error_message.clone();
@@ -40,7 +82,7 @@ pub fn admin_queue_page(_props: &AdminQueuePageProps) -> Html {
         use_effect_with((), move |_| {
             loading.set(true);
             wasm_bindgen_futures::spawn_local(async move {
-                match load_queue_items().await {
+                match load_queue_items(&session).await {
                     Ok(items) => {
                         queue_items.set(items);
                     }
@@ -63,6 +105,7 @@ pub fn admin_queue_page(_props: &AdminQueuePageProps) -> Html {
     };
 
     let on_add_url = {
+        let session = session.clone();
         let new_url = new_url.clone();
         let queue_items = queue_items.clone();
         let error_message = error_message.clone();
@@ -81,18 +124,19 @@ pub fn admin_queue_page(_props: &AdminQueuePageProps) -> Html {
                 return;
             }
 
+            let session = session.clone();
             let new_url = new_url.clone();
             let queue_items = queue_items.clone();
             let error_message = error_message.clone();
             let success_message = success_message.clone();
 
             wasm_bindgen_futures::spawn_local(async move {
-                match add_url_to_queue(&url).await {
+                match add_url_to_queue(&session, &url).await {
                     Ok(_) => {
                         new_url.set(String::new());
                         success_message.set(Some("URL added to queue successfully!".to_string()));
                         // Reload queue items
-                        match load_queue_items().await {
+                        match load_queue_items(&session).await {
                             Ok(items) => {
                                 queue_items.set(items);
                             }
@@ -109,12 +153,105 @@ pub fn admin_queue_page(_props: &AdminQueuePageProps) -> Html {
         })
     };
 
+    let on_run_crawl = {
+        let session = session.clone();
+        let error_message = error_message.clone();
+        let success_message = success_message.clone();
+
+        Callback::from(move |_| {
+            let session = session.clone();
+            let error_message = error_message.clone();
+            let success_message = success_message.clone();
+
+            // Clear previous messages
+            error_message.set(None);
+            success_message.set(None);
+
+            wasm_bindgen_futures::spawn_local(async move {
+                match run_crawl_now(&session).await {
+                    Ok(attempting) => {
+                        success_message.set(Some(format!(
+                            "Crawl started for up to {} queued videos",
+                            attempting
+                        )));
+                    }
+                    Err(e) => {
+                        error_message.set(Some(format!("Failed to start crawl: {}", e)));
+                    }
+                }
+            });
+        })
+    };
+
+    let status_count = |status: &str| {
+        queue_items
+            .iter()
+            .filter(|item| item.status == status)
+            .count()
+    };
+    let failed_count = status_count("failed");
+
+    let on_batch_action = {
+        let session = session.clone();
+        let queue_items = queue_items.clone();
+        let error_message = error_message.clone();
+        let success_message = success_message.clone();
+
+        Callback::from(move |action: BatchAction| {
+            let count = queue_items
+                .iter()
+                .filter(|item| item.status == "failed")
+                .count();
+            if count == 0 || !confirm(&action.confirmation(count)) {
+                return;
+            }
+
+            let session = session.clone();
+            let queue_items = queue_items.clone();
+            let error_message = error_message.clone();
+            let success_message = success_message.clone();
+
+            // Clear previous messages
+            error_message.set(None);
+            success_message.set(None);
+
+            wasm_bindgen_futures::spawn_local(async move {
+                let result = match action {
+                    BatchAction::RetryFailed => session.client().retry_failed(None).await,
+                    BatchAction::ClearFailed => session.client().clear_queue("failed").await,
+                };
+                match result {
+                    Ok(response) => {
+                        success_message.set(Some(match action {
+                            BatchAction::RetryFailed => {
+                                format!("{} failed items are pending again", response.affected)
+                            }
+                            BatchAction::ClearFailed => {
+                                format!("Removed {} failed items", response.affected)
+                            }
+                        }));
+                        match load_queue_items(&session).await {
+                            Ok(items) => queue_items.set(items),
+                            Err(e) => {
+                                error_message.set(Some(format!("Failed to reload queue: {}", e)));
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        error_message.set(Some(format!("Batch operation failed: {}", e)));
+                    }
+                }
+            });
+        })
+    };
+
     let on_delete_item = {
         let queue_items = queue_items.clone();
         let error_message = error_message.clone();
         let success_message = success_message.clone();
 
         Callback::from(move |item_id: String| {
+            let session = session.clone();
             let queue_items = queue_items.clone();
             let error_message = error_message.clone();
             let success_message = success_message.clone();
@@ -124,7 +261,7 @@ pub fn admin_queue_page(_props: &AdminQueuePageProps) -> Html {
             success_message.set(None);
 
             wasm_bindgen_futures::spawn_local(async move {
-                match delete_queue_item(&item_id).await {
+                match delete_queue_item(&session, &item_id).await {
                     Ok(_) => {
                         success_message.set(Some("Item deleted successfully!".to_string()));
                         // Remove item from list
@@ -151,9 +288,31 @@ pub fn admin_queue_page(_props: &AdminQueuePageProps) -> Html {
                         <h1 class="text-3xl font-bold text-gray-800">
                             {"Download Queue"}
                         </h1>
-                        <Link<Route> to={Route::Admin} classes="text-blue-600 hover:underline">
-                            {"← Back to Overview"}
-                        </Link<Route>>
+                        <div class="flex items-center gap-4">
+                            <button
+                                onclick={on_batch_action.reform(|_| BatchAction::RetryFailed)}
+                                disabled={failed_count == 0}
+                                class="bg-yellow-500 text-white px-4 py-2 rounded hover:bg-yellow-600 disabled:opacity-50"
+                            >
+                                {format!("Retry failed ({failed_count})")}
+                            </button>
+                            <button
+                                onclick={on_batch_action.reform(|_| BatchAction::ClearFailed)}
+                                disabled={failed_count == 0}
+                                class="bg-red-600 text-white px-4 py-2 rounded hover:bg-red-700 disabled:opacity-50"
+                            >
+                                {format!("Clear failed ({failed_count})")}
+                            </button>
+                            <button
+                                onclick={on_run_crawl}
+                                class="bg-purple-600 text-white px-4 py-2 rounded hover:bg-purple-700"
+                            >
+                                {"Run Crawl Now"}
+                            </button>
+                            <Link<AdminRoute> to={AdminRoute::Admin} classes="text-blue-600 hover:underline">
+                                {"← Back to Overview"}
+                            </Link<AdminRoute>>
+                        </div>
                     </div>
 
                     {
@@ -200,6 +359,20 @@ pub fn admin_queue_page(_props: &AdminQueuePageProps) -> Html {
                         </form>
                     </div>
 
+                    <div class="flex gap-4 mb-4">
+                        <select class="px-3 py-2 border border-gray-300 rounded" onchange={on_status_change}>
+                            <option value="" selected={status_filter.is_none()}>{"All statuses"}</option>
+                            { for QUEUE_STATUSES.iter().map(|status| html! {
+                                <option value={*status} selected={status_filter.as_deref() == Some(*status)}>{*status}</option>
+                            }) }
+                        </select>
+                        <select class="px-3 py-2 border border-gray-300 rounded" onchange={on_sort_change}>
+                            <option value="" selected={table.sort.is_none()}>{"Queue order"}</option>
+                            <option value="newest" selected={table.sort.as_deref() == Some("newest")}>{"Newest first"}</option>
+                            <option value="oldest" selected={table.sort.as_deref() == Some("oldest")}>{"Oldest first"}</option>
+                        </select>
+                    </div>
+
                     {
                         if *loading {
                             html! {
@@ -221,12 +394,12 @@ pub fn admin_queue_page(_props: &AdminQueuePageProps) -> Html {
                                         </thead>
                                         <tbody class="bg-white divide-y divide-gray-200">
                                             {
-                                                (*queue_items).iter().map(|item| {
+                                                visible_items.iter().map(|item| {
                                                     let item_id = item.id.clone();
                                                     let on_delete = on_delete_item.clone();
 
                                                     html! {
-                                                        <tr key={item.id.clone()}>
+                                                        <tr key={item.id.clone()} id={row_id(&item.id)} class={classes!(highlight_class(&highlight, &item.id))}>
                                                             <td class="px-6 py-4 whitespace-nowrap">
                                                                 <span class={format!("px-2 inline-flex text-xs leading-5 font-semibold rounded-full {}",
                                                                     match item.status.as_str() {
@@ -239,12 +412,20 @@ pub fn admin_queue_page(_props: &AdminQueuePageProps) -> Html {
                                                                 )}>
                                                                     {&item.status}
                                                                 </span>
+                                                                if let Some(kind) = &item.failure_kind {
+                                                                    <span class="ml-2 text-xs text-gray-500" title={item.error_message.clone()}>{kind}</span>
+                                                                }
+                                                                if item.retries > 0 {
+                                                                    <span class="ml-2 text-xs text-gray-500">{format!("retry {}", item.retries)}</span>
+                                                                }
                                                             </td>
-                                                            <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-900">
-                                                                {&item.added_at}
+                                                            <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-900"
+                                                                title={format_local_datetime(item.added_at_ms)}>
+                                                                {format_relative_time(item.added_at_ms)}
                                                             </td>
-                                                            <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-900">
-                                                                {item.processed_at.as_deref().unwrap_or("N/A")}
+                                                            <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-900"
+                                                                title={item.processed_at_ms.map(format_local_datetime)}>
+                                                                {item.processed_at_ms.map(format_relative_time).unwrap_or_else(|| "N/A".to_string())}
                                                             </td>
                                                             <td class="px-6 py-4 whitespace-nowrap text-sm font-medium">
                                                                 <button
@@ -276,88 +457,30 @@ pub fn admin_queue_page(_props: &AdminQueuePageProps) -> Html {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct QueueResponse {
-    success: bool,
-    message: String,
-    items: Vec<QueueItem>,
+async fn load_queue_items(session: &AdminSession) -> Result<Vec<QueueItem>, String> {
+    Ok(session.client().queue().await?.items)
 }
 
-async fn load_queue_items() -> Result<Vec<QueueItem>, String> {
-    let backend_url = &*BACKEND_URL;
-    let url = format!("{}/admin/queue", backend_url);
-
-    let token = window()
-        .and_then(|w| w.session_storage().ok())
-        .and_then(|s| s.and_then(|storage| storage.get_item("admin_token").ok()))
-        .flatten()
-        .ok_or("No admin token found")?;
-
-    let response = Request::get(&url)
-        .header("Authorization", &format!("Bearer {}", token))
-        .send()
-        .await
-        .map_err(|e| format!("Network error: {}", e))?;
-
-    if response.ok() {
-        let queue_response = response
-            .json::<QueueResponse>()
-            .await
-            .map_err(|e| format!("JSON parse error: {}", e))?;
-        Ok(queue_response.items)
-    } else {
-        Err(format!("HTTP error: {}", response.status()))
-    }
+async fn add_url_to_queue(session: &AdminSession, url: &str) -> Result<(), String> {
+    session.client().enqueue(url).await?;
+    Ok(())
 }
 
-async fn add_url_to_queue(url: &str) -> Result<(), String> {
-    let backend_url = &*BACKEND_URL;
-    let api_url = format!("{}/admin/queue", backend_url);
-
-    let token = window()
-        .and_then(|w| w.session_storage().ok())
-        .and_then(|s| s.and_then(|storage| storage.get_item("admin_token").ok()))
-        .flatten()
-        .ok_or("No admin token found")?;
-
-    let request_body = AddUrlRequest {
-        url: url.to_string(),
-    };
-
-    let response = Request::post(&api_url)
-        .header("Authorization", &format!("Bearer {}", token))
-        .json(&request_body)
-        .map_err(|e| format!("Request error: {}", e))?
-        .send()
-        .await
-        .map_err(|e| format!("Network error: {}", e))?;
-
-    if response.ok() {
-        Ok(())
-    } else {
-        Err(format!("HTTP error: {}", response.status()))
+async fn run_crawl_now(session: &AdminSession) -> Result<usize, String> {
+    match session.client().run_crawl().await {
+        Ok(response) => Ok(response.attempting),
+        Err(ClientError::Http(409)) => Err("A crawl pass is already running".to_string()),
+        Err(e) => Err(e.into()),
     }
 }
 
-async fn delete_queue_item(item_id: &str) -> Result<(), String> {
-    let backend_url = &*BACKEND_URL;
-    let url = format!("{}/admin/queue/{}", backend_url, item_id);
-
-    let token = window()
-        .and_then(|w| w.session_storage().ok())
-        .and_then(|s| s.and_then(|storage| storage.get_item("admin_token").ok()))
-        .flatten()
-        .ok_or("No admin token found")?;
-
-    let response = Request::delete(&url)
-        .header("Authorization", &format!("Bearer {}", token))
-        .send()
-        .await
-        .map_err(|e| format!("Network error: {}", e))?;
-
-    if response.ok() {
-        Ok(())
-    } else {
-        Err(format!("HTTP error: {}", response.status()))
-    }
+fn confirm(message: &str) -> bool {
+    web_sys::window()
+        .and_then(|window| window.confirm_with_message(message).ok())
+        .unwrap_or(false)
+}
+
+async fn delete_queue_item(session: &AdminSession, item_id: &str) -> Result<(), String> {
+    session.client().remove_queue_item(item_id).await?;
+    Ok(())
 }
@@ -0,0 +1,332 @@
+//! Compares how the current and a candidate relevance config rank the videos of a query
+//! before the boosts are changed for good. Videos that moved are highlighted.
+
+use crate::admin::models::{
+    RankMove, RankedVideo, RelevanceConfig, RelevancePreview, RelevancePreviewRequest,
+};
+use crate::admin::router::AdminRoute;
+use crate::admin::utils::AdminSession;
+use crate::search::search_options::event_value;
+use starchive_api_types::client::ClientError;
+use std::collections::HashMap;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+/// Editable fields of the candidate config, by label
+const CONFIG_FIELDS: &[(&str, &str)] = &[
+    ("exact_phrase_boost", "Exact phrase"),
+    ("phrase_boost", "Phrase"),
+    ("stemmed_phrase_boost", "Stemmed phrase"),
+    ("phrase_slop", "Phrase slop (wide)"),
+    ("all_terms_boost", "All terms (wide)"),
+    ("fuzzy_all_terms_boost", "Fuzzy all terms (wide)"),
+    ("partial_terms_boost", "Most terms (wide)"),
+    ("fuzzy_partial_terms_boost", "Fuzzy most terms (wide)"),
+    ("synonym_boost", "Synonym variants"),
+];
+
+fn config_value(config: &RelevanceConfig, field: &str) -> f64 {
+    match field {
+        "exact_phrase_boost" => config.exact_phrase_boost,
+        "phrase_boost" => config.phrase_boost,
+        "stemmed_phrase_boost" => config.stemmed_phrase_boost,
+        "phrase_slop" => config.phrase_slop as f64,
+        "all_terms_boost" => config.all_terms_boost,
+        "fuzzy_all_terms_boost" => config.fuzzy_all_terms_boost,
+        "partial_terms_boost" => config.partial_terms_boost,
+        "fuzzy_partial_terms_boost" => config.fuzzy_partial_terms_boost,
+        "synonym_boost" => config.synonym_boost,
+        _ => 0.0,
+    }
+}
+
+fn set_config_value(config: &mut RelevanceConfig, field: &str, value: f64) {
+    let value = value.max(0.0);
+    match field {
+        "exact_phrase_boost" => config.exact_phrase_boost = value,
+        "phrase_boost" => config.phrase_boost = value,
+        "stemmed_phrase_boost" => config.stemmed_phrase_boost = value,
+        "phrase_slop" => config.phrase_slop = value.round() as u32,
+        "all_terms_boost" => config.all_terms_boost = value,
+        "fuzzy_all_terms_boost" => config.fuzzy_all_terms_boost = value,
+        "partial_terms_boost" => config.partial_terms_boost = value,
+        "fuzzy_partial_terms_boost" => config.fuzzy_partial_terms_boost = value,
+        "synonym_boost" => config.synonym_boost = value,
+        _ => {}
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct AdminRelevancePageProps {}
+
+#[function_component(AdminRelevancePage)]
+pub fn admin_relevance_page(_props: &AdminRelevancePageProps) -> Html {
+    let query = use_state(String::new);
+    let search_type = use_state(|| "natural".to_string());
+    let candidate = use_state(RelevanceConfig::default);
+    let preview = use_state(|| None::<RelevancePreview>);
+    let loading = use_state(|| false);
+    let error_message = use_state(|| None::<String>);
+    let session = use_context::<AdminSession>().expect("rendered inside AdminGuard");
+
+    let on_query_input = {
+        let query = query.clone();
+        Callback::from(move |e: InputEvent| {
+            query.set(e.target_unchecked_into::<HtmlInputElement>().value());
+        })
+    };
+
+    let on_search_type_change = {
+        let search_type = search_type.clone();
+        Callback::from(move |e: Event| {
+            if let Some(value) = event_value(&e) {
+                search_type.set(value);
+            }
+        })
+    };
+
+    let on_field_input = {
+        let candidate = candidate.clone();
+        Callback::from(move |(field, value): (&'static str, String)| {
+            if let Ok(value) = value.trim().parse::<f64>() {
+                let mut config = (*candidate).clone();
+                set_config_value(&mut config, field, value);
+                candidate.set(config);
+            }
+        })
+    };
+
+    let on_reset = {
+        let candidate = candidate.clone();
+        Callback::from(move |_| candidate.set(RelevanceConfig::default()))
+    };
+
+    let on_submit = {
+        let query = query.clone();
+        let search_type = search_type.clone();
+        let candidate = candidate.clone();
+        let preview = preview.clone();
+        let loading = loading.clone();
+        let error_message = error_message.clone();
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            if query.trim().is_empty() {
+                error_message.set(Some("Please enter a query".to_string()));
+                return;
+            }
+            let request = RelevancePreviewRequest {
+                query: query.trim().to_string(),
+                search_type: Some((*search_type).clone()),
+                candidate: (*candidate).clone(),
+            };
+            let session = session.clone();
+            let preview = preview.clone();
+            let loading = loading.clone();
+            let error_message = error_message.clone();
+
+            error_message.set(None);
+            loading.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                match load_preview(&session, &request).await {
+                    Ok(result) => preview.set(Some(result)),
+                    Err(e) => error_message.set(Some(format!("Failed to preview: {}", e))),
+                }
+                loading.set(false);
+            });
+        })
+    };
+
+    html! {
+        <div class="min-h-screen bg-gray-700 p-4">
+            <div class="max-w-6xl mx-auto">
+                <div class="bg-white rounded-lg shadow-lg p-8">
+                    <div class="flex justify-between items-center mb-6">
+                        <h1 class="text-3xl font-bold text-gray-800">
+                            {"Relevance Preview"}
+                        </h1>
+                        <Link<AdminRoute> to={AdminRoute::Admin} classes="text-blue-600 hover:underline">
+                            {"← Back to Overview"}
+                        </Link<AdminRoute>>
+                    </div>
+
+                    if let Some(msg) = &*error_message {
+                        <div class="bg-red-100 border border-red-400 text-red-700 px-4 py-3 rounded mb-4">
+                            { msg }
+                        </div>
+                    }
+
+                    <form onsubmit={on_submit} class="mb-6 bg-gray-50 p-4 rounded-lg">
+                        <div class="flex gap-4 mb-4">
+                            <input
+                                type="text"
+                                class="flex-1 p-3 border border-gray-300 rounded focus:outline-none focus:ring-2 focus:ring-blue-500"
+                                placeholder="Query to rank..."
+                                value={(*query).clone()}
+                                oninput={on_query_input}
+                            />
+                            <select class="px-3 py-2 border border-gray-300 rounded" onchange={on_search_type_change}>
+                                <option value="natural" selected={*search_type == "natural"}>{"Natural"}</option>
+                                <option value="wide" selected={*search_type == "wide"}>{"Wide"}</option>
+                            </select>
+                            <button
+                                type="submit"
+                                disabled={*loading}
+                                class="bg-blue-600 text-white px-6 py-3 rounded hover:bg-blue-700 disabled:opacity-50"
+                            >
+                                {if *loading { "Ranking…" } else { "Compare" }}
+                            </button>
+                        </div>
+                        <div class="grid grid-cols-2 md:grid-cols-4 gap-3 text-sm">
+                            { for CONFIG_FIELDS.iter().map(|(field, label)| {
+                                let current = RelevanceConfig::default();
+                                let changed = config_value(&candidate, field) != config_value(&current, field);
+                                let on_input = on_field_input.reform(move |e: InputEvent| {
+                                    (*field, e.target_unchecked_into::<HtmlInputElement>().value())
+                                });
+                                html! {
+                                    <label class="flex flex-col gap-1">
+                                        <span class={classes!("text-gray-600", changed.then_some("font-semibold"))}>
+                                            {*label}
+                                            <span class="ml-1 text-xs text-gray-400">
+                                                {format!("(now {})", config_value(&current, field))}
+                                            </span>
+                                        </span>
+                                        <input
+                                            type="number"
+                                            min="0"
+                                            step={if *field == "phrase_slop" { "1" } else { "0.1" }}
+                                            class="px-2 py-1 border border-gray-300 rounded"
+                                            value={config_value(&candidate, field).to_string()}
+                                            oninput={on_input}
+                                        />
+                                    </label>
+                                }
+                            }) }
+                        </div>
+                        <button type="button" onclick={on_reset} class="mt-3 text-sm text-blue-600 hover:underline">
+                            {"Reset to the current config"}
+                        </button>
+                    </form>
+
+                    if let Some(preview) = &*preview {
+                        <RankingComparison preview={preview.clone()} />
+                    }
+                </div>
+            </div>
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+struct RankingComparisonProps {
+    preview: RelevancePreview,
+}
+
+#[function_component(RankingComparison)]
+fn ranking_comparison(props: &RankingComparisonProps) -> Html {
+    let preview = &props.preview;
+    let moves: HashMap<&str, &RankMove> = preview
+        .moves
+        .iter()
+        .map(|rank_move| (rank_move.video_id.as_str(), rank_move))
+        .collect();
+    let dropped = preview
+        .moves
+        .iter()
+        .filter(|rank_move| rank_move.candidate_rank.is_none())
+        .count();
+
+    html! {
+        <div>
+            <p class="text-sm text-gray-600 mb-4">
+                {format!(
+                    "{} videos kept their rank, {} moved, {} dropped out of the top {}.",
+                    preview.unchanged,
+                    preview.moves.len() - dropped,
+                    dropped,
+                    preview.current.len().max(preview.candidate.len())
+                )}
+            </p>
+            <div class="grid grid-cols-1 md:grid-cols-2 gap-6">
+                <RankingColumn title="Current" ranking={preview.current.clone()} moves={moves_of(&moves, &preview.current)} />
+                <RankingColumn title="Candidate" ranking={preview.candidate.clone()} moves={moves_of(&moves, &preview.candidate)} />
+            </div>
+        </div>
+    }
+}
+
+/// The moves of the videos of a ranking, in its order
+fn moves_of(moves: &HashMap<&str, &RankMove>, ranking: &[RankedVideo]) -> Vec<Option<RankMove>> {
+    ranking
+        .iter()
+        .map(|video| moves.get(video.video_id.as_str()).map(|m| (*m).clone()))
+        .collect()
+}
+
+#[derive(Properties, PartialEq)]
+struct RankingColumnProps {
+    title: AttrValue,
+    ranking: Vec<RankedVideo>,
+    moves: Vec<Option<RankMove>>, // by position in `ranking`
+}
+
+#[function_component(RankingColumn)]
+fn ranking_column(props: &RankingColumnProps) -> Html {
+    html! {
+        <div>
+            <h2 class="text-lg font-semibold text-gray-800 mb-2">{props.title.clone()}</h2>
+            if props.ranking.is_empty() {
+                <p class="text-gray-500">{"No matches."}</p>
+            }
+            <ol class="divide-y divide-gray-200 border border-gray-300 rounded">
+                { for props.ranking.iter().zip(&props.moves).enumerate().map(|(i, (video, rank_move))| {
+                    let badge = rank_move.as_ref().map(move_badge);
+                    html! {
+                        <li class={classes!("p-2", "flex", "gap-2", "text-sm", rank_move.is_some().then_some("bg-yellow-50"))}>
+                            <span class="w-6 text-right text-gray-500">{i + 1}</span>
+                            <div class="flex-1 min-w-0">
+                                <a href={format!("https://www.youtube.com/watch?v={}", video.video_id)}
+                                   target="_blank"
+                                   class="block truncate text-blue-600 hover:underline">
+                                    {video.title.clone().unwrap_or_else(|| video.video_id.clone())}
+                                </a>
+                                <span class="text-xs text-gray-500">
+                                    {format!("avg {:.2} · max {:.2} · {} matches", video.avg_score, video.max_score, video.match_count)}
+                                </span>
+                            </div>
+                            if let Some(badge) = badge {
+                                <span class="text-xs font-semibold whitespace-nowrap">{badge}</span>
+                            }
+                        </li>
+                    }
+                }) }
+            </ol>
+        </div>
+    }
+}
+
+/// How the candidate config moves a video: "▲ 3", "▼ 2", "new" or "out"
+fn move_badge(rank_move: &RankMove) -> String {
+    match (rank_move.current_rank, rank_move.candidate_rank) {
+        (Some(current), Some(candidate)) if candidate < current => {
+            format!("▲ {}", current - candidate)
+        }
+        (Some(current), Some(candidate)) => format!("▼ {}", candidate - current),
+        (None, Some(_)) => "new".to_string(),
+        (Some(_), None) => "out".to_string(),
+        (None, None) => String::new(),
+    }
+}
+
+async fn load_preview(
+    session: &AdminSession,
+    request: &RelevancePreviewRequest,
+) -> Result<RelevancePreview, String> {
+    match session.client().preview_relevance(request).await {
+        Ok(preview) => Ok(preview),
+        Err(ClientError::Http(422)) => Err("The query or search type is invalid".to_string()),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,205 @@
+use crate::admin::models::VideoReportCount;
+use crate::admin::router::AdminRoute;
+use crate::admin::utils::{format_unix_time_since, AdminSession};
+use crate::utils::format_count;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+/// Videos with at least this many reports get a recrawl action
+const HEAVILY_REPORTED_THRESHOLD: i64 = 3;
+
+#[derive(Properties, PartialEq)]
+pub struct AdminReportsPageProps {}
+
+#[function_component(AdminReportsPage)]
+pub fn admin_reports_page(_props: &AdminReportsPageProps) -> Html {
+    let reports = use_state(Vec::<VideoReportCount>::new);
+    let loading = use_state(|| false);
+    let error_message = use_state(|| None::<String>);
+    let success_message = use_state(|| None::<String>);
+    let session = use_context::<AdminSession>().expect("rendered inside AdminGuard");
+
+    // Load reports on component mount
+    {
+        let session = session.clone();
+        let reports = reports.clone();
+        let loading = loading.clone();
+        let error_message = error_message.clone();
+
+        use_effect_with((), move |_| {
+            loading.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                match load_reports(&session).await {
+                    Ok(items) => {
+                        reports.set(items);
+                    }
+                    Err(e) => {
+                        error_message.set(Some(format!("Failed to load reports: {}", e)));
+                    }
+                }
+                loading.set(false);
+            });
+            || ()
+        });
+    }
+
+    let on_recrawl = {
+        let error_message = error_message.clone();
+        let success_message = success_message.clone();
+
+        Callback::from(move |video_id: String| {
+            let session = session.clone();
+            let error_message = error_message.clone();
+            let success_message = success_message.clone();
+
+            error_message.set(None);
+            success_message.set(None);
+
+            wasm_bindgen_futures::spawn_local(async move {
+                match recrawl_video(&session, &video_id).await {
+                    Ok(_) => {
+                        success_message.set(Some(format!("Video {} queued for recrawl", video_id)));
+                    }
+                    Err(e) => {
+                        error_message.set(Some(format!("Failed to queue recrawl: {}", e)));
+                    }
+                }
+            });
+        })
+    };
+
+    html! {
+        <div class="min-h-screen bg-gray-700 p-4">
+            <div class="max-w-6xl mx-auto">
+                <div class="bg-white rounded-lg shadow-lg p-8">
+                    <div class="flex justify-between items-center mb-6">
+                        <h1 class="text-3xl font-bold text-gray-800">
+                            {"Reported Captions"}
+                        </h1>
+                        <Link<AdminRoute> to={AdminRoute::Admin} classes="text-blue-600 hover:underline">
+                            {"← Back to Overview"}
+                        </Link<AdminRoute>>
+                    </div>
+
+                    {
+                        if let Some(msg) = &*success_message {
+                            html! {
+                                <div class="bg-green-100 border border-green-400 text-green-700 px-4 py-3 rounded mb-4">
+                                    { msg }
+                                </div>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
+
+                    {
+                        if let Some(msg) = &*error_message {
+                            html! {
+                                <div class="bg-red-100 border border-red-400 text-red-700 px-4 py-3 rounded mb-4">
+                                    { msg }
+                                </div>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
+
+                    {
+                        if *loading {
+                            html! {
+                                <div class="text-center py-8">
+                                    <p>{"Loading reports..."}</p>
+                                </div>
+                            }
+                        } else if reports.is_empty() {
+                            html! {
+                                <p class="text-center text-gray-500 py-8">{"No captions have been reported."}</p>
+                            }
+                        } else {
+                            html! {
+                                <div class="overflow-x-auto">
+                                    <table class="min-w-full bg-white border border-gray-300">
+                                        <thead class="bg-gray-50">
+                                            <tr>
+                                                <th class="px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider">{"Video"}</th>
+                                                <th class="px-6 py-3 text-center text-xs font-medium text-gray-500 uppercase tracking-wider">{"Reports"}</th>
+                                                <th class="px-6 py-3 text-center text-xs font-medium text-gray-500 uppercase tracking-wider">{"Captions"}</th>
+                                                <th class="px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider">{"Last Report"}</th>
+                                                <th class="px-6 py-3 text-left text-xs font-medium text-gray-500 uppercase tracking-wider">{"Actions"}</th>
+                                            </tr>
+                                        </thead>
+                                        <tbody class="bg-white divide-y divide-gray-200">
+                                            {
+                                                (*reports).iter().map(|report| {
+                                                    let video_id = report.video_id.clone();
+                                                    let on_recrawl = on_recrawl.clone();
+
+                                                    html! {
+                                                        <tr key={report.video_id.clone()}>
+                                                            <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-900">
+                                                                <div class="max-w-xs truncate">
+                                                                    <a href={format!("https://www.youtube.com/watch?v={}", report.video_id)} class="text-blue-600 hover:underline">
+                                                                        {report.title.clone().unwrap_or_else(|| report.video_id.clone())}
+                                                                    </a>
+                                                                </div>
+                                                            </td>
+                                                            <td class="px-6 py-4 whitespace-nowrap text-center text-sm text-gray-900">
+                                                                {format_count(report.report_count)}
+                                                            </td>
+                                                            <td class="px-6 py-4 whitespace-nowrap text-center text-sm text-gray-900">
+                                                                {report.reported_captions}
+                                                            </td>
+                                                            <td class="px-6 py-4 whitespace-nowrap text-sm text-gray-900">
+                                                                {
+                                                                    match report.last_reported_at {
+                                                                        Some(ts) => format_unix_time_since(ts as u64),
+                                                                        None => "N/A".to_string(),
+                                                                    }
+                                                                }
+                                                            </td>
+                                                            <td class="px-6 py-4 whitespace-nowrap text-sm font-medium">
+                                                                {
+                                                                    if report.report_count >= HEAVILY_REPORTED_THRESHOLD {
+                                                                        html! {
+                                                                            <button
+                                                                                onclick={
+                                                                                    let video_id = video_id.clone();
+                                                                                    Callback::from(move |_| {
+                                                                                        on_recrawl.emit(video_id.clone());
+                                                                                    })
+                                                                                }
+                                                                                class="text-blue-600 hover:text-blue-900"
+                                                                            >
+                                                                                {"Recrawl Captions"}
+                                                                            </button>
+                                                                        }
+                                                                    } else {
+                                                                        html! {}
+                                                                    }
+                                                                }
+                                                            </td>
+                                                        </tr>
+                                                    }
+                                                }).collect::<Html>()
+                                            }
+                                        </tbody>
+                                    </table>
+                                </div>
+                            }
+                        }
+                    }
+                </div>
+            </div>
+        </div>
+    }
+}
+
+async fn load_reports(session: &AdminSession) -> Result<Vec<VideoReportCount>, String> {
+    Ok(session.client().reports().await?)
+}
+
+async fn recrawl_video(session: &AdminSession, video_id: &str) -> Result<(), String> {
+    session.client().recrawl_video(video_id).await?;
+    Ok(())
+}
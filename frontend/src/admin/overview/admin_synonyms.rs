@@ -0,0 +1,160 @@
+//! Edits the synonym set searches expand queries with. Each line is a group of terms
+//! separated by commas, e.g. "rsi, roberts space industries".
+
+use crate::admin::models::SynonymSet;
+use crate::admin::router::AdminRoute;
+use crate::admin::utils::AdminSession;
+use web_sys::HtmlTextAreaElement;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+fn groups_to_text(synonyms: &SynonymSet) -> String {
+    synonyms
+        .groups
+        .iter()
+        .map(|group| group.join(", "))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn text_to_groups(text: &str) -> SynonymSet {
+    SynonymSet {
+        groups: text
+            .lines()
+            .map(|line| {
+                line.split(',')
+                    .map(|term| term.trim().to_string())
+                    .collect()
+            })
+            .collect(),
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct AdminSynonymsPageProps {}
+
+#[function_component(AdminSynonymsPage)]
+pub fn admin_synonyms_page(_props: &AdminSynonymsPageProps) -> Html {
+    let text = use_state(String::new);
+    let group_count = use_state(|| None::<usize>); // None until loaded
+    let saving = use_state(|| false);
+    let error_message = use_state(|| None::<String>);
+    let success_message = use_state(|| None::<String>);
+    let session = use_context::<AdminSession>().expect("rendered inside AdminGuard");
+
+    {
+        let session = session.clone();
+        let text = text.clone();
+        let group_count = group_count.clone();
+        let error_message = error_message.clone();
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                match session.client().synonyms().await {
+                    Ok(synonyms) => {
+                        text.set(groups_to_text(&synonyms));
+                        group_count.set(Some(synonyms.groups.len()));
+                    }
+                    Err(e) => error_message.set(Some(format!("Failed to load synonyms: {}", e))),
+                }
+            });
+            || ()
+        });
+    }
+
+    let on_input = {
+        let text = text.clone();
+        Callback::from(move |e: InputEvent| {
+            text.set(e.target_unchecked_into::<HtmlTextAreaElement>().value());
+        })
+    };
+
+    let on_submit = {
+        let text = text.clone();
+        let group_count = group_count.clone();
+        let saving = saving.clone();
+        let error_message = error_message.clone();
+        let success_message = success_message.clone();
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let synonyms = text_to_groups(&text);
+            let session = session.clone();
+            let text = text.clone();
+            let group_count = group_count.clone();
+            let saving = saving.clone();
+            let error_message = error_message.clone();
+            let success_message = success_message.clone();
+
+            error_message.set(None);
+            success_message.set(None);
+            saving.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                match session.client().update_synonyms(&synonyms).await {
+                    Ok(stored) => {
+                        text.set(groups_to_text(&stored));
+                        group_count.set(Some(stored.groups.len()));
+                        success_message.set(Some(format!(
+                            "Saved {} synonym groups, searches use them right away",
+                            stored.groups.len()
+                        )));
+                    }
+                    Err(e) => error_message.set(Some(format!("Failed to save synonyms: {}", e))),
+                }
+                saving.set(false);
+            });
+        })
+    };
+
+    html! {
+        <div class="min-h-screen bg-gray-700 p-4">
+            <div class="max-w-4xl mx-auto">
+                <div class="bg-white rounded-lg shadow-lg p-8">
+                    <div class="flex justify-between items-center mb-6">
+                        <h1 class="text-3xl font-bold text-gray-800">
+                            {"Synonyms"}
+                        </h1>
+                        <Link<AdminRoute> to={AdminRoute::Admin} classes="text-blue-600 hover:underline">
+                            {"← Back to Overview"}
+                        </Link<AdminRoute>>
+                    </div>
+
+                    if let Some(msg) = &*error_message {
+                        <div class="bg-red-100 border border-red-400 text-red-700 px-4 py-3 rounded mb-4">
+                            { msg }
+                        </div>
+                    }
+                    if let Some(msg) = &*success_message {
+                        <div class="bg-green-100 border border-green-400 text-green-700 px-4 py-3 rounded mb-4">
+                            { msg }
+                        </div>
+                    }
+
+                    <p class="text-sm text-gray-600 mb-4">
+                        {"One group per line, terms separated by commas. A search containing one term of a group also finds the others, ranked below matches of the query itself."}
+                    </p>
+                    <form onsubmit={on_submit}>
+                        <textarea
+                            rows="16"
+                            class="w-full p-3 border border-gray-300 rounded font-mono text-sm focus:outline-none focus:ring-2 focus:ring-blue-500"
+                            placeholder="rsi, roberts space industries"
+                            value={(*text).clone()}
+                            oninput={on_input}
+                        />
+                        <div class="flex items-center justify-between mt-3">
+                            <span class="text-sm text-gray-500">
+                                {group_count.map(|count| format!("{} groups stored", count)).unwrap_or_default()}
+                            </span>
+                            <button
+                                type="submit"
+                                disabled={*saving || group_count.is_none()}
+                                class="bg-blue-600 text-white px-6 py-2 rounded hover:bg-blue-700 disabled:opacity-50"
+                            >
+                                {if *saving { "Saving…" } else { "Save" }}
+                            </button>
+                        </div>
+                    </form>
+                </div>
+            </div>
+        </div>
+    }
+}
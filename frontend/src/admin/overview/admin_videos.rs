@@ -1,10 +1,15 @@
-use crate::env_variable_utils::BACKEND_URL;
-use crate::models::VideoMetadata;
-use crate::router::Route;
-use crate::utils::{format_duration, format_number, format_unix_date};
-use gloo_net::http::Request;
-use serde::{Deserialize, Serialize};
-use web_sys::window;
+use crate::admin::router::AdminRoute;
+use crate::admin::utils::{
+    highlight_class, row_id, use_highlighted_row, use_table_state, AdminSession, VIDEOS_PER_PAGE,
+};
+use crate::models::{CrawlHistoryEntry, VideoMetadata};
+use crate::utils::{format_count, format_duration, format_relative_time, format_unix_date};
+use gloo_timers::future::TimeoutFuture;
+use starchive_api_types::{
+    AdminDeleteVideoResponse, AdminTaskStatus, AdminVideoDetail, AdminVideoListResponse,
+    CheckSource, IndexingLogEntry,
+};
+use std::collections::HashMap;
 use yew::prelude::*;
 use yew_router::prelude::*;
 
@@ -13,31 +18,39 @@ pub struct AdminVideosPageProps {}
 
 #[function_component(AdminVideosPage)]
 pub fn admin_videos_page(_props: &AdminVideosPageProps) -> Html {
-    let videos = use_state(|| Vec::<VideoMetadata>::new());
+    let videos = use_state(Vec::<VideoMetadata>::new);
     let loading = use_state(|| false);
     let error_message = use_state(|| None::<String>);
-    let current_page = use_state(|| 1);
+    let table = use_table_state(VIDEOS_PER_PAGE);
     let total_items = use_state(|| 0);
-    let per_page = use_state(|| 10);
+    let expanded_video = use_state(|| None::<String>);
+    let detail = use_state(|| None::<AdminVideoDetail>);
+    let session = use_context::<AdminSession>().expect("rendered inside AdminGuard");
+    let highlight = use_highlighted_row(!*loading && !videos.is_empty());
+    // Video id -> progress of its running deletion, updated by the polling tasks
+    let deletions = use_mut_ref(HashMap::<String, f64>::new);
+    let rerender = use_force_update();
 
     // Clone states for pagination
-    let current_page_display = current_page.clone();
-    let per_page_display = per_page.clone();
+    let current_page_display = table.page;
+    let per_page_display = table.per_page;
     let total_items_display = total_items.clone();
 
-    // Load videos on component mount
+    // Load videos whenever the page changes, including back/forward navigation
     {
+        let session = session.clone();
         let videos = videos.clone();
         let loading = loading.clone();
         let error_message = error_message.clone();
         let total_items = total_items.clone();
 
-        use_effect_with(*current_page, move |_| {
+        use_effect_with((table.page, table.per_page), move |(page, per_page)| {
+            let (page, per_page) = (*page, *per_page);
             loading.set(true);
             wasm_bindgen_futures::spawn_local(async move {
-                match load_videos(*current_page, *per_page).await {
+                match load_videos(&session, page, per_page).await {
                     Ok(response) => {
-                        videos.set(response.videos);
+                        videos.set(response.items);
                         total_items.set(response.total);
                     }
                     Err(e) => {
@@ -51,15 +64,40 @@ pub fn admin_videos_page(_props: &AdminVideosPageProps) -> Html {
     }
 
     let on_delete_video = {
+        let session = session.clone();
         let videos = videos.clone();
         let error_message = error_message.clone();
+        let deletions = deletions.clone();
+        let rerender = rerender.clone();
 
         Callback::from(move |video_id: String| {
+            if deletions.borrow().contains_key(&video_id) {
+                return;
+            }
+            let session = session.clone();
             let videos = videos.clone();
             let error_message = error_message.clone();
+            let deletions = deletions.clone();
+            let rerender = rerender.clone();
+
+            deletions.borrow_mut().insert(video_id.clone(), 0.0);
+            rerender.force_update();
 
             wasm_bindgen_futures::spawn_local(async move {
-                match delete_video(&video_id).await {
+                // Captions are deleted in the background, poll until the task is done
+                let result = match delete_video(&session, &video_id).await {
+                    Ok(response) => {
+                        wait_for_task(&session, &response.task_id, |progress| {
+                            deletions.borrow_mut().insert(video_id.clone(), progress);
+                            rerender.force_update();
+                        })
+                        .await
+                    }
+                    Err(e) => Err(e),
+                };
+                deletions.borrow_mut().remove(&video_id);
+
+                match result {
                     Ok(_) => {
                         // Remove video from list
                         let current_videos = (*videos).clone();
@@ -71,6 +109,35 @@ pub fn admin_videos_page(_props: &AdminVideosPageProps) -> Html {
                     }
                     Err(e) => {
                         error_message.set(Some(format!("Failed to delete video: {}", e)));
+                        rerender.force_update();
+                    }
+                }
+            });
+        })
+    };
+
+    let on_toggle_detail = {
+        let expanded_video = expanded_video.clone();
+        let detail = detail.clone();
+        let error_message = error_message.clone();
+
+        Callback::from(move |video_id: String| {
+            if expanded_video.as_deref() == Some(video_id.as_str()) {
+                expanded_video.set(None);
+                return;
+            }
+
+            expanded_video.set(Some(video_id.clone()));
+            detail.set(None);
+
+            let session = session.clone();
+            let detail = detail.clone();
+            let error_message = error_message.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match session.client().video_detail(&video_id).await {
+                    Ok(video_detail) => detail.set(Some(video_detail)),
+                    Err(e) => {
+                        error_message.set(Some(format!("Failed to load video detail: {}", e)));
                     }
                 }
             });
@@ -85,9 +152,9 @@ pub fn admin_videos_page(_props: &AdminVideosPageProps) -> Html {
                         <h1 class="text-3xl font-bold text-gray-800">
                             {"Videos"}
                         </h1>
-                        <Link<Route> to={Route::Admin} classes="text-blue-600 hover:underline">
+                        <Link<AdminRoute> to={AdminRoute::Admin} classes="text-blue-600 hover:underline">
                             {"← Back to Overview"}
-                        </Link<Route>>
+                        </Link<AdminRoute>>
                     </div>
 
                     {
@@ -124,6 +191,7 @@ pub fn admin_videos_page(_props: &AdminVideosPageProps) -> Html {
                                                 <th class="px-6 py-3 text-center text-xs font-medium text-gray-500 uppercase tracking-wider">{"👍"}</th>
                                                 <th class="px-6 py-3 text-center text-xs font-medium text-gray-500 uppercase tracking-wider">{"💬"}</th>
                                                 <th class="px-6 py-3 text-center text-xs font-medium text-gray-500 uppercase tracking-wider">{"📄"}</th>
+                                                <th class="px-6 py-3 text-center text-xs font-medium text-gray-500 uppercase tracking-wider">{"📈"}</th>
                                                 <th class="px-6 py-3 text-center text-xs font-medium text-gray-500 uppercase tracking-wider">{"🔧"}</th>
                                             </tr>
                                         </thead>
@@ -132,11 +200,26 @@ pub fn admin_videos_page(_props: &AdminVideosPageProps) -> Html {
                                                 (*videos).iter().map(|video| {
                                                     let video_id = video.video_id.clone();
                                                     let on_delete = on_delete_video.clone();
+                                                    let on_toggle = on_toggle_detail.clone();
+                                                    let is_expanded = expanded_video.as_deref() == Some(video.video_id.as_str());
+                                                    let deletion_progress = deletions.borrow().get(&video.video_id).copied();
 
                                                     html! {
-                                                        <tr key={video.video_id.clone()}>
+                                                        <>
+                                                        <tr id={row_id(&video.video_id)} class={classes!(highlight_class(&highlight, &video.video_id))}>
                                                             <td class="px-6 py-3 whitespace-nowrap text-sm text-gray-900">
-                                                                <div class="max-w-xs truncate"><a href={format!("https://www.youtube.com/watch?v={}", video.video_id)} class="text-blue-600 hover:underline">{&video.title}</a></div>
+                                                                <div class="max-w-xs truncate">
+                                                                    <a href={format!("https://www.youtube.com/watch?v={}", video.video_id)} class="text-blue-600 hover:underline">{&video.title}</a>
+                                                                    if video.hidden {
+                                                                        <span class="ml-2 px-2 py-0.5 text-xs rounded bg-red-100 text-red-700" title="Channel is hidden from public search">{"Hidden"}</span>
+                                                                    }
+                                                                    if video.is_renamed() {
+                                                                        <span class="ml-2 px-2 py-0.5 text-xs rounded bg-yellow-100 text-yellow-800"
+                                                                              title={format!("Previously: {}", video.previous.as_ref().map(|p| p.title.as_str()).unwrap_or_default())}>
+                                                                            {"renamed"}
+                                                                        </span>
+                                                                    }
+                                                                </div>
                                                             </td>
                                                             <td class="px-6 py-3 whitespace-nowrap text-sm text-gray-900">
                                                                 <a href={format!("https://www.youtube.com/channel/{}",&video.channel_id)} class="text-blue-600 hover:underline">{&video.channel_name}</a>
@@ -151,32 +234,81 @@ pub fn admin_videos_page(_props: &AdminVideosPageProps) -> Html {
                                                                 {format_duration(video.duration)}
                                                             </td>
                                                             <td class="px-6 py-3 whitespace-nowrap text-right text-sm text-gray-900">
-                                                                {format_number(video.views)}
+                                                                {format_count(video.views)}
                                                             </td>
                                                             <td class="px-6 py-3 whitespace-nowrap text-right text-sm text-gray-900">
-                                                                {format_number(video.likes)}
+                                                                {format_count(video.likes)}
                                                             </td>
                                                             <td class="px-6 py-3 whitespace-nowrap text-right text-sm text-gray-900">
-                                                                {format_number(video.comment_count)}
+                                                                {format_count(video.comment_count)}
                                                             </td>
                                                             <td class="px-6 py-3 whitespace-nowrap text-center text-sm text-gray-900">
-                                                                {if video.has_captions { "✅" } else { "❌" }}
+                                                                {
+                                                                    match video.skip_reason.as_deref() {
+                                                                        Some("too_long") => html! {
+                                                                            <span title="Longer than the maximum duration, captions were skipped">{"⏭️ too long"}</span>
+                                                                        },
+                                                                        Some(reason) => html! { <span>{format!("⏭️ {}", reason)}</span> },
+                                                                        None => html! { {if video.has_captions { "✅" } else { "❌" }} },
+                                                                    }
+                                                                }
                                                             </td>
-                                                            <td class="px-6 py-3 whitespace-nowrap text-sm font-medium">
+                                                            <td class="px-6 py-3 whitespace-nowrap text-center text-sm text-gray-900">
                                                                 <button
                                                                     onclick={
                                                                         let video_id = video_id.clone();
-                                                                        let on_delete = on_delete.clone();
                                                                         Callback::from(move |_| {
-                                                                            on_delete.emit(video_id.clone());
+                                                                            on_toggle.emit(video_id.clone());
                                                                         })
                                                                     }
-                                                                    class="text-red-600 hover:text-red-900"
+                                                                    title="Details"
+                                                                    class="text-blue-600 hover:text-blue-900"
                                                                 >
-                                                                    {"Delete"}
+                                                                    {if is_expanded { "▼" } else { "▶" }}
                                                                 </button>
                                                             </td>
+                                                            <td class="px-6 py-3 whitespace-nowrap text-sm font-medium">
+                                                                if let Some(progress) = deletion_progress {
+                                                                    <div class="w-24" title="Deleting captions">
+                                                                        <div class="h-2 bg-gray-200 rounded">
+                                                                            <div class="h-2 bg-red-500 rounded" style={format!("width: {:.0}%", progress * 100.0)}></div>
+                                                                        </div>
+                                                                        <span class="text-xs text-gray-500">{format!("Deleting {:.0}%", progress * 100.0)}</span>
+                                                                    </div>
+                                                                } else {
+                                                                    <button
+                                                                        onclick={
+                                                                            let video_id = video_id.clone();
+                                                                            let on_delete = on_delete.clone();
+                                                                            Callback::from(move |_| {
+                                                                                on_delete.emit(video_id.clone());
+                                                                            })
+                                                                        }
+                                                                        class="text-red-600 hover:text-red-900"
+                                                                    >
+                                                                        {"Delete"}
+                                                                    </button>
+                                                                }
+                                                            </td>
                                                         </tr>
+                                                        {
+                                                            if is_expanded {
+                                                                html! {
+                                                                    <tr class="bg-gray-50">
+                                                                        <td colspan="11" class="px-6 py-3">
+                                                                            <VideoDetailDrawer
+                                                                                detail={(*detail).clone()}
+                                                                                on_delete={on_delete_video.clone()}
+                                                                                deleting={deletion_progress.is_some()}
+                                                                            />
+                                                                        </td>
+                                                                    </tr>
+                                                                }
+                                                            } else {
+                                                                html! {}
+                                                            }
+                                                        }
+                                                        </>
                                                     }
                                                 }).collect::<Html>()
                                             }
@@ -185,22 +317,22 @@ pub fn admin_videos_page(_props: &AdminVideosPageProps) -> Html {
                                     <div class="mt-4 flex justify-between items-center">
                                         <div class="text-sm text-gray-700">
                                             {format!("Showing {} to {} of {} results",
-                                                ((*current_page_display - 1) * *per_page_display + 1),
-                                                (*current_page_display * *per_page_display).min(*total_items_display),
-                                                *total_items_display
+                                                ((current_page_display - 1) * per_page_display + 1),
+                                                (current_page_display * per_page_display).min(*total_items_display),
+                                                format_count(*total_items_display)
                                             )}
                                         </div>
                                         <div class="flex space-x-2">
                                             <button
                                                 onclick={
-                                                    let current_page = current_page_display.clone();
+                                                    let table = table.clone();
                                                     Callback::from(move |_| {
-                                                        if *current_page > 1 {
-                                                            current_page.set(*current_page - 1);
+                                                        if table.page > 1 {
+                                                            table.set_page(table.page - 1);
                                                         }
                                                     })
                                                 }
-                                                disabled={*current_page_display <= 1}
+                                                disabled={current_page_display <= 1}
                                                 class="px-3 py-3 border rounded-md disabled:opacity-50"
                                             >
                                                 {"Previous"}
@@ -209,18 +341,17 @@ pub fn admin_videos_page(_props: &AdminVideosPageProps) -> Html {
                                                 <input
                                                     type="number"
                                                     min="1"
-                                                    max={((*total_items_display as f64 / *per_page_display as f64).ceil() as i64).to_string()}
+                                                    max={((*total_items_display as f64 / per_page_display as f64).ceil() as i64).to_string()}
                                                     value={current_page_display.to_string()}
                                                     onchange={
-                                                        let current_page = current_page_display.clone();
+                                                        let table = table.clone();
                                                         let total_items = total_items_display.clone();
-                                                        let per_page = per_page_display.clone();
                                                         Callback::from(move |e: Event| {
                                                             let input: web_sys::HtmlInputElement = e.target_unchecked_into();
                                                             if let Ok(page) = input.value().parse::<i64>() {
-                                                                let max_page = (*total_items as f64 / *per_page as f64).ceil() as i64;
+                                                                let max_page = (*total_items as f64 / table.per_page as f64).ceil() as i64;
                                                                 if page >= 1 && page <= max_page {
-                                                                    current_page.set(page);
+                                                                    table.set_page(page);
                                                                 }
                                                             }
                                                         })
@@ -228,19 +359,18 @@ pub fn admin_videos_page(_props: &AdminVideosPageProps) -> Html {
                                                     class="w-16 px-2 py-1 border rounded-md mx-2 text-center"
                                                 />
                                             </div>
-                                            <div class="flex items-center">{format!("/ {}",{(*total_items_display as f64 / *per_page_display as f64).ceil() as i64})}</div>
+                                            <div class="flex items-center">{format!("/ {}",{(*total_items_display as f64 / per_page_display as f64).ceil() as i64})}</div>
                                             <button
                                                 onclick={
-                                                    let current_page = current_page_display.clone();
-                                                    let per_page = per_page_display.clone();
+                                                    let table = table.clone();
                                                     let total_items = total_items_display.clone();
                                                     Callback::from(move |_| {
-                                                        if (*current_page * *per_page) < *total_items {
-                                                            current_page.set(*current_page + 1);
+                                                        if (table.page * table.per_page) < *total_items {
+                                                            table.set_page(table.page + 1);
                                                         }
                                                     })
                                                 }
-                                                disabled={(*current_page_display * *per_page_display) >= *total_items}
+                                                disabled={(current_page_display * per_page_display) >= *total_items}
                                                 class="px-3 py-3 border rounded-md disabled:opacity-50"
                                             >
                                                 {"Next"}
@@ -257,62 +387,259 @@ pub fn admin_videos_page(_props: &AdminVideosPageProps) -> Html {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct VideosResponse {
-    videos: Vec<VideoMetadata>,
-    total: i64,
-    page: i64,
-    per_page: i64,
+#[derive(Properties, PartialEq)]
+struct VideoDetailDrawerProps {
+    detail: Option<AdminVideoDetail>,
+    on_delete: Callback<String>,
+    deleting: bool,
 }
 
-async fn load_videos(page: i64, per_page: i64) -> Result<VideosResponse, String> {
-    let backend_url = &*BACKEND_URL;
-    let url = format!(
-        "{}/admin/videos?page={}&per_page={}",
-        backend_url, page, per_page
-    );
-
-    let token = window()
-        .and_then(|w| w.session_storage().ok())
-        .and_then(|s| s.and_then(|storage| storage.get_item("admin_token").ok()))
-        .flatten()
-        .ok_or("No admin token found")?;
-
-    let response = Request::get(&url)
-        .header("Authorization", &format!("Bearer {}", token))
-        .send()
-        .await
-        .map_err(|e| format!("Network error: {}", e))?;
-
-    if response.ok() {
-        response
-            .json::<VideosResponse>()
-            .await
-            .map_err(|e| format!("JSON parse error: {}", e))
+/// Where a video came from, what references it and what was indexed of it, with the
+/// actions an admin usually takes next
+#[function_component(VideoDetailDrawer)]
+fn video_detail_drawer(props: &VideoDetailDrawerProps) -> Html {
+    let session = use_context::<AdminSession>().expect("rendered inside AdminGuard");
+    let recrawl_message = use_state(|| None::<String>);
+
+    let Some(detail) = &props.detail else {
+        return html! { <p class="text-sm text-gray-500">{"Loading video details..."}</p> };
+    };
+    let video_id = detail.video.video_id.clone();
+
+    let on_recrawl = {
+        let video_id = video_id.clone();
+        let recrawl_message = recrawl_message.clone();
+        Callback::from(move |_| {
+            let session = session.clone();
+            let video_id = video_id.clone();
+            let recrawl_message = recrawl_message.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                recrawl_message.set(Some(
+                    match session.client().recrawl_video(&video_id).await {
+                        Ok(response) => response.message,
+                        Err(e) => format!("Failed to enqueue recrawl: {}", e),
+                    },
+                ));
+            });
+        })
+    };
+    let on_delete = {
+        let on_delete = props.on_delete.clone();
+        let video_id = video_id.clone();
+        Callback::from(move |_| on_delete.emit(video_id.clone()))
+    };
+
+    let source = match (&detail.source, &detail.source_name) {
+        (Some(CheckSource::Channel(id)), name) => {
+            format!("Channel monitor {}", name.as_deref().unwrap_or(id))
+        }
+        (Some(CheckSource::Playlist(id)), name) => {
+            format!("Playlist monitor {}", name.as_deref().unwrap_or(id))
+        }
+        (None, _) => "Enqueued manually".to_string(),
+    };
+    let channel_monitor = match detail.channel_monitor_active {
+        Some(true) => "monitored",
+        Some(false) => "monitored, paused",
+        None => "not monitored",
+    };
+    let languages = if detail.caption_languages.is_empty() {
+        "-".to_string()
     } else {
-        Err(format!("HTTP error: {}", response.status()))
+        detail.caption_languages.join(", ")
+    };
+
+    html! {
+        <div>
+            <dl class="grid grid-cols-2 gap-x-6 gap-y-1 text-sm mb-3">
+                <dt class="text-gray-500">{"Source"}</dt>
+                <dd class="text-gray-900">{source}</dd>
+                <dt class="text-gray-500">{"Channel"}</dt>
+                <dd class="text-gray-900">{format!("{} ({})", detail.video.channel_name, channel_monitor)}</dd>
+                <dt class="text-gray-500">{"Playlists"}</dt>
+                <dd class="text-gray-900">
+                    if detail.playlists.is_empty() {
+                        {"-"}
+                    } else {
+                        {
+                            detail.playlists.iter().map(|playlist| html! {
+                                <a
+                                    href={format!("https://www.youtube.com/playlist?list={}", playlist.playlist_id)}
+                                    class="mr-3 text-blue-600 hover:underline"
+                                    title={if playlist.playlist_name.is_some() { "Monitored" } else { "Not monitored" }}
+                                >
+                                    {playlist.playlist_name.clone().unwrap_or_else(|| playlist.playlist_id.clone())}
+                                </a>
+                            }).collect::<Html>()
+                        }
+                    }
+                </dd>
+                <dt class="text-gray-500">{"Captions"}</dt>
+                <dd class="text-gray-900">{format!("{} ({})", format_count(detail.caption_count), languages)}</dd>
+            </dl>
+            <div class="flex items-center gap-4 text-sm mb-3">
+                <button onclick={on_recrawl} class="text-blue-600 hover:text-blue-900">{"Recrawl"}</button>
+                <a href={format!("/video/{}", video_id)} target="_blank" class="text-blue-600 hover:text-blue-900">
+                    {"View transcript"}
+                </a>
+                <button onclick={on_delete} disabled={props.deleting} class="text-red-600 hover:text-red-900 disabled:opacity-50">
+                    {"Delete"}
+                </button>
+                if let Some(msg) = &*recrawl_message {
+                    <span class="text-gray-500">{msg}</span>
+                }
+            </div>
+            <CrawlHistoryTable history={Some(detail.video.crawl_history.clone())} />
+            <IndexingLogTable entries={Some(detail.indexing_log.clone())} />
+        </div>
     }
 }
 
-async fn delete_video(video_id: &str) -> Result<(), String> {
-    let backend_url = &*BACKEND_URL;
-    let url = format!("{}/admin/video/{}", backend_url, video_id);
+#[derive(Properties, PartialEq)]
+struct CrawlHistoryTableProps {
+    history: Option<Vec<CrawlHistoryEntry>>,
+}
 
-    let token = window()
-        .and_then(|w| w.session_storage().ok())
-        .and_then(|s| s.and_then(|storage| storage.get_item("admin_token").ok()))
-        .flatten()
-        .ok_or("No admin token found")?;
+#[function_component(CrawlHistoryTable)]
+fn crawl_history_table(props: &CrawlHistoryTableProps) -> Html {
+    let Some(history) = &props.history else {
+        return html! { <p class="text-sm text-gray-500">{"Loading crawl history..."}</p> };
+    };
 
-    let response = Request::delete(&url)
-        .header("Authorization", &format!("Bearer {}", token))
-        .send()
-        .await
-        .map_err(|e| format!("Network error: {}", e))?;
+    if history.is_empty() {
+        return html! { <p class="text-sm text-gray-500">{"No crawl history recorded yet."}</p> };
+    }
 
-    if response.ok() {
-        Ok(())
-    } else {
-        Err(format!("HTTP error: {}", response.status()))
+    html! {
+        <table class="min-w-full text-sm">
+            <thead>
+                <tr class="text-xs text-gray-500 uppercase">
+                    <th class="px-4 py-1 text-left">{"Crawl 📅"}</th>
+                    <th class="px-4 py-1 text-right">{"👁️"}</th>
+                    <th class="px-4 py-1 text-right">{"Δ 👁️"}</th>
+                    <th class="px-4 py-1 text-right">{"👍"}</th>
+                    <th class="px-4 py-1 text-right">{"📄"}</th>
+                </tr>
+            </thead>
+            <tbody>
+                {
+                    // Newest first, with the view growth since the previous crawl
+                    history.iter().enumerate().rev().map(|(i, entry)| {
+                        let view_delta = if i > 0 {
+                            let delta = entry.views - history[i - 1].views;
+                            if delta >= 0 {
+                                format!("+{}", format_count(delta))
+                            } else {
+                                format_count(delta)
+                            }
+                        } else {
+                            "-".to_string()
+                        };
+                        html! {
+                            <tr>
+                                <td class="px-4 py-1">{format_unix_date(entry.crawl_date)}</td>
+                                <td class="px-4 py-1 text-right">{format_count(entry.views)}</td>
+                                <td class="px-4 py-1 text-right text-gray-500">{view_delta}</td>
+                                <td class="px-4 py-1 text-right">{format_count(entry.likes)}</td>
+                                <td class="px-4 py-1 text-right">{format_count(entry.caption_count)}</td>
+                            </tr>
+                        }
+                    }).collect::<Html>()
+                }
+            </tbody>
+        </table>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+struct IndexingLogTableProps {
+    entries: Option<Vec<IndexingLogEntry>>,
+}
+
+/// What the crawl worker did with the video each time it was processed
+#[function_component(IndexingLogTable)]
+fn indexing_log_table(props: &IndexingLogTableProps) -> Html {
+    let Some(entries) = &props.entries else {
+        return html! { <p class="text-sm text-gray-500 mt-3">{"Loading indexing log..."}</p> };
+    };
+
+    if entries.is_empty() {
+        return html! { <p class="text-sm text-gray-500 mt-3">{"No indexing log recorded yet."}</p> };
+    }
+
+    html! {
+        <table class="min-w-full text-sm mt-3">
+            <thead>
+                <tr class="text-xs text-gray-500 uppercase">
+                    <th class="px-4 py-1 text-left">{"Processed"}</th>
+                    <th class="px-4 py-1 text-left">{"Status"}</th>
+                    <th class="px-4 py-1 text-left">{"Source"}</th>
+                    <th class="px-4 py-1 text-right">{"📄"}</th>
+                    <th class="px-4 py-1 text-right">{"⏱️"}</th>
+                </tr>
+            </thead>
+            <tbody>
+                {
+                    entries.iter().map(|entry| {
+                        let source = match &entry.source {
+                            Some(CheckSource::Channel(id)) => format!("channel {}", id),
+                            Some(CheckSource::Playlist(id)) => format!("playlist {}", id),
+                            None => "manual".to_string(),
+                        };
+                        let (status, status_class) = match (&entry.failure_kind, &entry.note) {
+                            (Some(kind), _) => (format!("failed: {}", kind), "px-4 py-1 text-red-600"),
+                            (None, Some(note)) => (note.clone(), "px-4 py-1 text-yellow-700"),
+                            (None, None) => (entry.status.clone(), "px-4 py-1 text-green-700"),
+                        };
+                        html! {
+                            <tr>
+                                <td class="px-4 py-1">{format_relative_time(entry.finished_at)}</td>
+                                <td class={status_class}>{status}</td>
+                                <td class="px-4 py-1 text-gray-500">{source}</td>
+                                <td class="px-4 py-1 text-right">{format_count(entry.captions_indexed)}</td>
+                                <td class="px-4 py-1 text-right">{format!("{:.1} s", entry.processing_ms as f64 / 1000.0)}</td>
+                            </tr>
+                        }
+                    }).collect::<Html>()
+                }
+            </tbody>
+        </table>
+    }
+}
+
+async fn load_videos(
+    session: &AdminSession,
+    page: i64,
+    per_page: i64,
+) -> Result<AdminVideoListResponse, String> {
+    Ok(session.client().videos(page, per_page).await?)
+}
+
+async fn delete_video(
+    session: &AdminSession,
+    video_id: &str,
+) -> Result<AdminDeleteVideoResponse, String> {
+    Ok(session.client().delete_video(video_id).await?)
+}
+
+/// Interval between two status requests of a background task
+const TASK_POLL_MS: u32 = 1000;
+
+/// Polls a background task until it completes, reporting its progress between 0 and 1
+async fn wait_for_task(
+    session: &AdminSession,
+    task_id: &str,
+    mut on_progress: impl FnMut(f64),
+) -> Result<AdminTaskStatus, String> {
+    loop {
+        TimeoutFuture::new(TASK_POLL_MS).await;
+        let status = session.client().task_status(task_id).await?;
+        on_progress(status.progress());
+        if status.completed {
+            return match status.error {
+                Some(e) => Err(e),
+                None => Ok(status),
+            };
+        }
     }
 }
@@ -0,0 +1,59 @@
+use crate::admin::components::AdminGuard;
+use crate::admin::overview::admin_captions::AdminCaptionsPage;
+use crate::admin::overview::admin_monitor::AdminMonitorsPage;
+use crate::admin::overview::admin_queue::AdminQueuePage;
+use crate::admin::overview::admin_relevance::AdminRelevancePage;
+use crate::admin::overview::admin_reports::AdminReportsPage;
+use crate::admin::overview::admin_synonyms::AdminSynonymsPage;
+use crate::admin::overview::admin_videos::AdminVideosPage;
+use crate::admin::overview::AdminPage;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+/// Routes of the admin app, which is built and served separately under /admin so the
+/// public search never downloads it
+#[derive(Clone, Routable, PartialEq)]
+pub enum AdminRoute {
+    #[at("/admin")]
+    Admin,
+    #[at("/admin/videos")]
+    AdminVideos,
+    #[at("/admin/captions")]
+    AdminCaptions,
+    #[at("/admin/monitors")]
+    AdminMonitors,
+    #[at("/admin/queue")]
+    AdminQueue,
+    #[at("/admin/reports")]
+    AdminReports,
+    #[at("/admin/relevance")]
+    AdminRelevance,
+    #[at("/admin/synonyms")]
+    AdminSynonyms,
+    #[not_found]
+    #[at("/admin/404")]
+    NotFound,
+}
+
+pub fn switch_admin(routes: AdminRoute) -> Html {
+    match routes {
+        AdminRoute::Admin => html! { <AdminPage /> },
+        AdminRoute::AdminVideos => html! { <AdminGuard><AdminVideosPage /></AdminGuard> },
+        AdminRoute::AdminCaptions => html! { <AdminGuard><AdminCaptionsPage /></AdminGuard> },
+        AdminRoute::AdminMonitors => html! { <AdminGuard><AdminMonitorsPage /></AdminGuard> },
+        AdminRoute::AdminQueue => html! { <AdminGuard><AdminQueuePage /></AdminGuard> },
+        AdminRoute::AdminReports => html! { <AdminGuard><AdminReportsPage /></AdminGuard> },
+        AdminRoute::AdminRelevance => html! { <AdminGuard><AdminRelevancePage /></AdminGuard> },
+        AdminRoute::AdminSynonyms => html! { <AdminGuard><AdminSynonymsPage /></AdminGuard> },
+        AdminRoute::NotFound => html! {
+            <div class="min-h-screen flex items-center justify-center bg-gray-700">
+                <div class="bg-white p-8 rounded-lg shadow-lg text-center">
+                    <h1 class="text-2xl font-bold text-gray-800 mb-4">{"404 - Page Not Found"}</h1>
+                    <Link<AdminRoute> to={AdminRoute::Admin} classes="text-blue-600 hover:underline">
+                        {"Go back to admin"}
+                    </Link<AdminRoute>>
+                </div>
+            </div>
+        },
+    }
+}
@@ -1,4 +1,14 @@
+use crate::admin::router::AdminRoute;
+use crate::env_variable_utils::BACKEND_URL;
+use starchive_api_types::client::ApiClient;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Deref;
 use web_sys::window;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+/// Page size of the admin video list, lookup hits link to pages of this size
+pub const VIDEOS_PER_PAGE: i64 = 10;
 
 pub fn get_stored_admin_token() -> Option<String> {
     window()
@@ -7,6 +17,207 @@ pub fn get_stored_admin_token() -> Option<String> {
         .flatten()
 }
 
+/// Admin token of the logged in session, provided as context by `AdminGuard`
+#[derive(Clone, PartialEq)]
+pub struct AdminSession {
+    pub token: String,
+}
+
+impl AdminSession {
+    /// Backend client authorized with the session token
+    pub fn client(&self) -> ApiClient {
+        ApiClient::new(&BACKEND_URL).with_token(&self.token)
+    }
+}
+
+/// Remembers that the admin was sent to the login page from a guarded page
+pub fn set_login_required_notice() {
+    if let Some(Ok(Some(storage))) = window().map(|w| w.session_storage()) {
+        let _ = storage.set_item("admin_login_required", "1");
+    }
+}
+
+/// Returns whether a login notice was pending and clears it
+pub fn take_login_required_notice() -> bool {
+    let Some(Ok(Some(storage))) = window().map(|w| w.session_storage()) else {
+        return false;
+    };
+    let pending = storage
+        .get_item("admin_login_required")
+        .ok()
+        .flatten()
+        .is_some();
+    if pending {
+        let _ = storage.remove_item("admin_login_required");
+    }
+    pending
+}
+
+/// Value of a query parameter of the current location, updated on navigation
+#[hook]
+pub fn use_query_param(name: &str) -> Option<String> {
+    use_location()
+        .and_then(|location| location.query::<HashMap<String, String>>().ok())
+        .and_then(|params| params.get(name).cloned())
+}
+
+/// Query parameters of a table that are not filters
+const TABLE_PARAMS: &[&str] = &["page", "per_page", "sort", "highlight"];
+
+/// Page, page size, sort and filters of an admin table. Kept in the query string, so
+/// they survive leaving the page and back/forward moves through earlier states.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableState {
+    pub page: i64, // 1-based
+    pub per_page: i64,
+    pub sort: Option<String>,
+    pub filters: BTreeMap<String, String>,
+}
+
+impl TableState {
+    fn from_query(params: &HashMap<String, String>, default_per_page: i64) -> Self {
+        let number = |name: &str| {
+            params
+                .get(name)
+                .and_then(|value| value.parse::<i64>().ok())
+                .filter(|value| *value >= 1)
+        };
+        TableState {
+            page: number("page").unwrap_or(1),
+            per_page: number("per_page").unwrap_or(default_per_page),
+            sort: params.get("sort").filter(|sort| !sort.is_empty()).cloned(),
+            filters: params
+                .iter()
+                .filter(|(name, value)| !TABLE_PARAMS.contains(&name.as_str()) && !value.is_empty())
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+        }
+    }
+
+    /// Query parameters of the state, defaults are left out to keep URLs short
+    fn to_query(&self, default_per_page: i64) -> BTreeMap<String, String> {
+        let mut params = self.filters.clone();
+        if self.page > 1 {
+            params.insert("page".to_string(), self.page.to_string());
+        }
+        if self.per_page != default_per_page {
+            params.insert("per_page".to_string(), self.per_page.to_string());
+        }
+        if let Some(sort) = &self.sort {
+            params.insert("sort".to_string(), sort.clone());
+        }
+        params
+    }
+
+    pub fn filter(&self, name: &str) -> Option<&str> {
+        self.filters.get(name).map(String::as_str)
+    }
+}
+
+/// Table state of the current location, changes push a new history entry
+#[derive(Clone, PartialEq)]
+pub struct UseTableStateHandle {
+    state: TableState,
+    navigate: Callback<TableState>,
+}
+
+impl UseTableStateHandle {
+    pub fn set(&self, state: TableState) {
+        self.navigate.emit(state);
+    }
+
+    pub fn set_page(&self, page: i64) {
+        self.set(TableState {
+            page: page.max(1),
+            ..self.state.clone()
+        });
+    }
+
+    /// Changing the sort starts over at the first page
+    pub fn set_sort(&self, sort: Option<String>) {
+        self.set(TableState {
+            page: 1,
+            sort,
+            ..self.state.clone()
+        });
+    }
+
+    /// Sets or, with None, clears a filter and starts over at the first page
+    pub fn set_filter(&self, name: &str, value: Option<String>) {
+        let mut filters = self.state.filters.clone();
+        match value.filter(|value| !value.is_empty()) {
+            Some(value) => filters.insert(name.to_string(), value),
+            None => filters.remove(name),
+        };
+        self.set(TableState {
+            page: 1,
+            filters,
+            ..self.state.clone()
+        });
+    }
+}
+
+impl Deref for UseTableStateHandle {
+    type Target = TableState;
+
+    fn deref(&self) -> &TableState {
+        &self.state
+    }
+}
+
+/// State of the admin table on the current page, read from and written to the query
+/// string. Pages using it restore their table when navigated back to.
+#[hook]
+pub fn use_table_state(default_per_page: i64) -> UseTableStateHandle {
+    let params = use_location()
+        .and_then(|location| location.query::<HashMap<String, String>>().ok())
+        .unwrap_or_default();
+    let navigator = use_navigator();
+    let route = use_route::<AdminRoute>();
+
+    let navigate = Callback::from(move |state: TableState| {
+        if let (Some(navigator), Some(route)) = (&navigator, &route) {
+            let _ = navigator.push_with_query(route, &state.to_query(default_per_page));
+        }
+    });
+
+    UseTableStateHandle {
+        state: TableState::from_query(&params, default_per_page),
+        navigate,
+    }
+}
+
+/// Element id of the table row showing `id`
+pub fn row_id(id: &str) -> String {
+    format!("row-{}", id)
+}
+
+/// Id of the row to highlight, from the `highlight` parameter set by the admin lookup.
+/// Scrolls the row into view once the table is `ready`.
+#[hook]
+pub fn use_highlighted_row(ready: bool) -> Option<String> {
+    let highlight = use_query_param("highlight");
+
+    use_effect_with((highlight.clone(), ready), |(highlight, ready)| {
+        if let (Some(id), true) = (highlight, *ready) {
+            if let Some(element) = window()
+                .and_then(|w| w.document())
+                .and_then(|d| d.get_element_by_id(&row_id(id)))
+            {
+                element.scroll_into_view();
+            }
+        }
+        || ()
+    });
+
+    highlight
+}
+
+/// Row classes marking the row the admin lookup pointed to
+pub fn highlight_class(highlight: &Option<String>, id: &str) -> Option<&'static str> {
+    (highlight.as_deref() == Some(id)).then_some("bg-yellow-100")
+}
+
 pub fn store_admin_token(token: &str) -> Result<(), String> {
     if let Some(window) = window() {
         if let Ok(Some(storage)) = window.session_storage() {
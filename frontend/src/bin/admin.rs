@@ -0,0 +1,23 @@
+use frontend::admin::router::{switch_admin, AdminRoute};
+use frontend::connectivity::ConnectivityProvider;
+use frontend::env_variable_utils::get_backend_url;
+use web_sys::console;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+#[function_component(App)]
+pub fn app() -> Html {
+    html! {
+        <ConnectivityProvider>
+            <BrowserRouter>
+                <Switch<AdminRoute> render={switch_admin} />
+            </BrowserRouter>
+        </ConnectivityProvider>
+    }
+}
+
+fn main() {
+    yew::Renderer::<App>::new().render();
+
+    console::log_1(&format!("API: \"{}\"", get_backend_url()).into());
+}
@@ -0,0 +1,18 @@
+use crate::env_variable_utils::BACKEND_URL;
+use crate::models::{PublicChannel, VideoMetadata};
+use starchive_api_types::client::ApiClient;
+
+/// The channel's entry of the public directory, None if it has no public videos
+pub async fn load_channel(channel_id: &str) -> Result<Option<PublicChannel>, String> {
+    Ok(ApiClient::new(&BACKEND_URL)
+        .public_channels()
+        .await?
+        .into_iter()
+        .find(|channel| channel.channel_id == channel_id))
+}
+
+pub async fn load_channel_videos(channel_id: &str) -> Result<Vec<VideoMetadata>, String> {
+    Ok(ApiClient::new(&BACKEND_URL)
+        .channel_videos(channel_id)
+        .await?)
+}
@@ -0,0 +1,242 @@
+use crate::channel::api::{load_channel, load_channel_videos};
+use crate::models::{PublicChannel, UploadMonth, VideoMetadata};
+use crate::router::Route;
+use crate::utils::{format_compact, format_duration, format_unix_date};
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+const SPARKLINE_WIDTH: f64 = 240.0;
+const SPARKLINE_HEIGHT: f64 = 40.0;
+const SPARKLINE_PADDING: f64 = 2.0;
+
+/// Points of the sparkline, one run per stretch of months with uploads. Months without
+/// uploads end a run, so they show as gaps instead of dips to zero.
+fn sparkline_runs(months: &[UploadMonth]) -> Vec<Vec<(f64, f64)>> {
+    let max_count = months
+        .iter()
+        .map(|month| month.count)
+        .max()
+        .unwrap_or(0)
+        .max(1) as f64;
+    let step = (SPARKLINE_WIDTH - 2.0 * SPARKLINE_PADDING) / (months.len().max(2) - 1) as f64;
+    let plot_height = SPARKLINE_HEIGHT - 2.0 * SPARKLINE_PADDING;
+
+    let mut runs: Vec<Vec<(f64, f64)>> = Vec::new();
+    let mut previous_empty = true;
+    for (i, month) in months.iter().enumerate() {
+        if month.count == 0 {
+            previous_empty = true;
+            continue;
+        }
+        let point = (
+            SPARKLINE_PADDING + i as f64 * step,
+            SPARKLINE_PADDING + plot_height * (1.0 - month.count as f64 / max_count),
+        );
+        match runs.last_mut() {
+            Some(run) if !previous_empty => run.push(point),
+            _ => runs.push(vec![point]),
+        }
+        previous_empty = false;
+    }
+    runs
+}
+
+#[derive(Properties, PartialEq)]
+pub struct SparklineProps {
+    pub months: Vec<UploadMonth>,
+}
+
+/// Uploads per month as an inline SVG line
+#[function_component(Sparkline)]
+pub fn sparkline(props: &SparklineProps) -> Html {
+    let (Some(first), Some(last)) = (props.months.first(), props.months.last()) else {
+        return html! {};
+    };
+    let busiest = props
+        .months
+        .iter()
+        .max_by_key(|month| month.count)
+        .expect("months are not empty");
+    let title = format!(
+        "Uploads per month from {} to {}, most in {} ({})",
+        first.month, last.month, busiest.month, busiest.count
+    );
+    let baseline = SPARKLINE_HEIGHT - SPARKLINE_PADDING;
+
+    html! {
+        <figure class="flex items-center gap-2 text-xs text-gray-500">
+            <span>{&first.month}</span>
+            <svg
+                width={SPARKLINE_WIDTH.to_string()}
+                height={SPARKLINE_HEIGHT.to_string()}
+                viewBox={format!("0 0 {} {}", SPARKLINE_WIDTH, SPARKLINE_HEIGHT)}
+                role="img"
+                aria-label={title.clone()}
+            >
+                <title>{title}</title>
+                <line
+                    x1={SPARKLINE_PADDING.to_string()}
+                    y1={baseline.to_string()}
+                    x2={(SPARKLINE_WIDTH - SPARKLINE_PADDING).to_string()}
+                    y2={baseline.to_string()}
+                    class="stroke-gray-200"
+                    stroke-width="1"
+                />
+                { for sparkline_runs(&props.months).into_iter().map(|run| match run.as_slice() {
+                    [(x, y)] => html! {
+                        <circle cx={x.to_string()} cy={y.to_string()} r="1.5" class="fill-blue-600" />
+                    },
+                    points => html! {
+                        <polyline
+                            points={points.iter().map(|(x, y)| format!("{x:.1},{y:.1}")).collect::<Vec<_>>().join(" ")}
+                            fill="none"
+                            class="stroke-blue-600"
+                            stroke-width="1.5"
+                            stroke-linejoin="round"
+                        />
+                    },
+                })}
+            </svg>
+            <span>{&last.month}</span>
+        </figure>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct VideoBadgesProps {
+    pub video: VideoMetadata,
+}
+
+/// Duration, views and caption coverage of a video
+#[function_component(VideoBadges)]
+pub fn video_badges(props: &VideoBadgesProps) -> Html {
+    let video = &props.video;
+    let badge = "px-2 py-0.5 rounded bg-gray-100 text-gray-700 text-xs tabular-nums";
+
+    html! {
+        <div class="flex flex-wrap gap-1">
+            <span class={badge} title="Duration">{format_duration(video.duration)}</span>
+            <span class={badge} title={format!("{} views", video.views)}>
+                {format!("{} views", format_compact(video.views))}
+            </span>
+            if !video.has_captions {
+                <span class="px-2 py-0.5 rounded bg-yellow-100 text-yellow-800 text-xs">{"No captions"}</span>
+            } else if let Some(coverage) = video.caption_coverage {
+                <span class={badge} title="Share of the video covered by captions">
+                    {format!("{:.0}% captioned", coverage * 100.0)}
+                </span>
+            }
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ChannelPageProps {
+    pub channel_id: String,
+}
+
+/// Public videos of a channel with their badges, and its uploads per month
+#[function_component(ChannelPage)]
+pub fn channel_page(props: &ChannelPageProps) -> Html {
+    let channel = use_state(|| None::<PublicChannel>);
+    let videos = use_state(Vec::<VideoMetadata>::new);
+    let loading = use_state(|| true);
+    let error_message = use_state(|| None::<String>);
+
+    {
+        let channel = channel.clone();
+        let videos = videos.clone();
+        let loading = loading.clone();
+        let error_message = error_message.clone();
+        use_effect_with(props.channel_id.clone(), move |channel_id| {
+            let channel_id = channel_id.clone();
+            loading.set(true);
+            error_message.set(None);
+            wasm_bindgen_futures::spawn_local(async move {
+                match load_channel(&channel_id).await {
+                    Ok(entry) => channel.set(entry),
+                    Err(e) => error_message.set(Some(format!("Failed to load the channel: {}", e))),
+                }
+                match load_channel_videos(&channel_id).await {
+                    Ok(list) => videos.set(list),
+                    Err(e) => error_message.set(Some(format!("Failed to load the videos: {}", e))),
+                }
+                loading.set(false);
+            });
+            || ()
+        });
+    }
+
+    let title = channel
+        .as_ref()
+        .map(|channel| channel.channel_name.clone())
+        .or_else(|| videos.first().map(|video| video.channel_name.clone()))
+        .unwrap_or_else(|| props.channel_id.clone());
+    let youtube_link = format!("https://www.youtube.com/channel/{}", props.channel_id);
+
+    html! {
+        <div class="min-h-screen bg-gray-700 p-4">
+            <div class="max-w-4xl mx-auto">
+                <div class="bg-white rounded-lg shadow-lg p-8">
+                    <div class="flex justify-between items-start gap-4 mb-6">
+                        <div>
+                            <h1 class="text-2xl font-bold text-gray-800">{title}</h1>
+                            if let Some(channel) = &*channel {
+                                <div class="text-sm text-gray-500 mb-2">
+                                    {format!("{} videos", channel.video_count)}
+                                    { for channel.tags.iter().map(|tag| html! {
+                                        <span class="ml-2 px-2 py-0.5 rounded bg-blue-100 text-blue-800 text-xs">{tag}</span>
+                                    })}
+                                </div>
+                                <Sparkline months={channel.upload_histogram.clone()} />
+                            }
+                        </div>
+                        <div class="flex flex-col items-end gap-1 shrink-0">
+                            <Link<Route> to={Route::Home} classes="text-blue-600 hover:underline">
+                                {"← Back to Search"}
+                            </Link<Route>>
+                            <a href={youtube_link} target="_blank" class="text-blue-600 hover:underline text-sm">
+                                {"Open on YouTube ↗"}
+                            </a>
+                        </div>
+                    </div>
+
+                    if let Some(msg) = &*error_message {
+                        <div class="bg-red-100 border border-red-400 text-red-700 px-4 py-3 rounded mb-4">
+                            { msg }
+                        </div>
+                    }
+
+                    {
+                        if *loading {
+                            html! { <p class="text-center py-8">{"Loading videos..."}</p> }
+                        } else if videos.is_empty() {
+                            html! { <p class="text-center py-8 text-gray-500">{"No videos of this channel are archived."}</p> }
+                        } else {
+                            html! {
+                                <ul class="divide-y divide-gray-200">
+                                    { for videos.iter().map(|video| html! {
+                                        <li key={video.video_id.clone()} class="py-3 flex justify-between items-start gap-4">
+                                            <div class="min-w-0">
+                                                <Link<Route>
+                                                    to={Route::Video { id: video.video_id.clone() }}
+                                                    classes="text-blue-600 hover:underline font-medium"
+                                                >
+                                                    {&video.title}
+                                                </Link<Route>>
+                                                <div class="text-xs text-gray-500">{format_unix_date(video.upload_date)}</div>
+                                            </div>
+                                            <div class="shrink-0">
+                                                <VideoBadges video={video.clone()} />
+                                            </div>
+                                        </li>
+                                    })}
+                                </ul>
+                            }
+                        }
+                    }
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,116 @@
+//! Watches whether the backend is reachable. A request that gets no response at all
+//! marks it offline; from then on `/health` is pinged with a growing delay until it
+//! answers again. `ConnectivityProvider` shows a banner meanwhile and tells pages
+//! about the recovery, so they can retry what failed.
+
+use crate::env_variable_utils::BACKEND_URL;
+use gloo_timers::future::TimeoutFuture;
+use starchive_api_types::client::{self, ApiClient};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use yew::prelude::*;
+
+const FIRST_RETRY_MS: u32 = 2_000;
+const MAX_RETRY_MS: u32 = 30_000;
+
+thread_local! {
+    static PINGING: Cell<bool> = const { Cell::new(false) };
+    static DISPATCH: RefCell<Option<Callback<ConnectivityAction>>> = const { RefCell::new(None) };
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Connectivity {
+    pub offline: bool,
+    /// Counts the recoveries, pages retry failed requests when it changes
+    pub recoveries: u32,
+}
+
+pub enum ConnectivityAction {
+    Lost,
+    Restored,
+}
+
+impl Reducible for Connectivity {
+    type Action = ConnectivityAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        match action {
+            ConnectivityAction::Lost if self.offline => self,
+            ConnectivityAction::Lost => Rc::new(Connectivity {
+                offline: true,
+                recoveries: self.recoveries,
+            }),
+            ConnectivityAction::Restored => Rc::new(Connectivity {
+                offline: false,
+                recoveries: self.recoveries + 1,
+            }),
+        }
+    }
+}
+
+fn dispatch(action: ConnectivityAction) {
+    if let Some(callback) = DISPATCH.with(|cell| cell.borrow().clone()) {
+        callback.emit(action);
+    }
+}
+
+/// Marks the backend unreachable and starts pinging it, a no-op while already pinging
+fn report_failure() {
+    if PINGING.with(|pinging| pinging.replace(true)) {
+        return;
+    }
+    dispatch(ConnectivityAction::Lost);
+    wasm_bindgen_futures::spawn_local(async {
+        let client = ApiClient::new(&BACKEND_URL);
+        let mut delay = FIRST_RETRY_MS;
+        loop {
+            TimeoutFuture::new(delay).await;
+            if client.health().await.is_ok() {
+                break;
+            }
+            delay = (delay * 2).min(MAX_RETRY_MS);
+        }
+        PINGING.with(|pinging| pinging.set(false));
+        dispatch(ConnectivityAction::Restored);
+    });
+}
+
+/// Reachability of the backend, always online outside of a `ConnectivityProvider`
+#[hook]
+pub fn use_connectivity() -> Connectivity {
+    use_context::<Connectivity>().unwrap_or_default()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ConnectivityProviderProps {
+    pub children: Html,
+}
+
+/// Provides the `Connectivity` of the app and shows a banner while the backend is
+/// unreachable. Requests of the API client report into it on their own.
+#[function_component(ConnectivityProvider)]
+pub fn connectivity_provider(props: &ConnectivityProviderProps) -> Html {
+    let connectivity = use_reducer(Connectivity::default);
+
+    {
+        let dispatcher = connectivity.dispatcher();
+        use_effect_with((), move |_| {
+            DISPATCH.with(|cell| {
+                *cell.borrow_mut() = Some(Callback::from(move |action| dispatcher.dispatch(action)))
+            });
+            client::on_network_error(report_failure);
+            || DISPATCH.with(|cell| *cell.borrow_mut() = None)
+        });
+    }
+
+    html! {
+        <ContextProvider<Connectivity> context={(*connectivity).clone()}>
+            if connectivity.offline {
+                <div class="fixed top-0 inset-x-0 z-50 bg-yellow-100 border-b border-yellow-400 text-yellow-800 px-4 py-2 text-center text-sm">
+                    {"Backend unreachable — retrying…"}
+                </div>
+            }
+            { props.children.clone() }
+        </ContextProvider<Connectivity>>
+    }
+}
@@ -32,14 +32,3 @@ pub fn get_env_var(key: &str) -> Option<String> {
 pub fn get_backend_url() -> String {
     get_env_var("BACKEND_URL").unwrap_or_else(|| "http://localhost:8000".to_string())
 }
-
-pub fn get_app_name() -> String {
-    get_env_var("APP_NAME").unwrap_or_else(|| "Paulsenik's StarCitizen Content Search".to_string())
-}
-
-pub fn is_debug_mode() -> bool {
-    get_env_var("DEBUG_MODE")
-        .unwrap_or_else(|| "false".to_string())
-        .parse()
-        .unwrap_or(false)
-}
@@ -0,0 +1,13 @@
+//! Code shared by the public search app (`main.rs`) and the admin app (`bin/admin.rs`).
+//! Each app only links what its routes use, so admin pages stay out of the public bundle.
+
+pub mod admin;
+pub mod channel;
+pub mod connectivity;
+pub mod env_variable_utils;
+pub mod models;
+pub mod public_config;
+pub mod router;
+pub mod search;
+pub mod transcript;
+pub mod utils;
@@ -1,35 +1,42 @@
-mod admin;
-mod env_variable_utils;
-mod models;
-mod router;
-mod search;
-mod utils;
-
-use crate::env_variable_utils::{get_app_name, get_backend_url, is_debug_mode};
-use crate::router::{switch, Route};
+use frontend::connectivity::ConnectivityProvider;
+use frontend::env_variable_utils::get_backend_url;
+use frontend::models::PublicConfig;
+use frontend::public_config::load_public_config;
+use frontend::router::{switch, Route};
 use web_sys::console;
 use yew::prelude::*;
 use yew_router::prelude::*;
 
 #[function_component(App)]
 pub fn app() -> Html {
+    let public_config = use_state(PublicConfig::default);
+
+    {
+        let public_config = public_config.clone();
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                match load_public_config().await {
+                    Ok(config) => public_config.set(config),
+                    Err(e) => console::error_1(&format!("Failed to load config: {}", e).into()),
+                }
+            });
+            || ()
+        });
+    }
+
     html! {
-        <BrowserRouter>
-            <Switch<Route> render={switch} />
-        </BrowserRouter>
+        <ConnectivityProvider>
+            <ContextProvider<PublicConfig> context={(*public_config).clone()}>
+                <BrowserRouter>
+                    <Switch<Route> render={switch} />
+                </BrowserRouter>
+            </ContextProvider<PublicConfig>>
+        </ConnectivityProvider>
     }
 }
 
 fn main() {
     yew::Renderer::<App>::new().render();
 
-    console::log_1(
-        &format!(
-            "NAME: \"{}\", API: \"{}\" DEBUG: \"{}\"",
-            get_app_name(),
-            get_backend_url(),
-            is_debug_mode()
-        )
-        .into(),
-    );
+    console::log_1(&format!("API: \"{}\"", get_backend_url()).into());
 }
@@ -1,62 +1,25 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
-pub struct VideoMetadata {
-    pub title: String,
-    pub channel_name: String,
-    pub channel_id: String,
-    pub upload_date: i64,
-    pub crawl_date: i64,
-    pub duration: i64,
-    pub likes: i64,
-    pub views: i64,
-    pub comment_count: i64,
-    pub has_captions: bool,
-    pub tags: Vec<String>,
-    pub video_id: String,
-    pub playlists: Vec<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SearchResponse {
-    pub results: Vec<SearchResult>,
-    pub total_videos: usize,
-    pub total_captions: usize,
-    pub page: usize,
-    pub page_size: usize,
-    pub total_pages: usize,
-}
-
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct SearchResult {
-    pub video_id: String,
-    pub start_time: f64,
-    pub end_time: f64,
-    pub snippet_html: String,
-}
+// Wire types shared with the backend
+pub use starchive_api_types::{
+    caption_segment, BatchVideoRequest, BatchVideoResponse, CaptionReportRequest,
+    CrawlHistoryEntry, FeedbackAction, MonitoredChannelStats, MonitoredPlaylistStats,
+    PhraseFrequencyResponse, PublicChannel, PublicConfig, RandomQuote, SearchFeedbackRequest,
+    SearchResponse, SearchResult, TranscriptLine, TrendingVideo, UploadMonth, VideoMetadata,
+    VideoTranscript,
+};
 
 pub struct FilterParameter {
     pub search_type: String,
+    pub exact_terms: bool,
+    pub exclude_channels: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-pub struct MonitoredChannelStats {
-    pub channel_id: String,
-    pub channel_name: String,
-    pub active: bool,
-    pub created_at: String,
-    pub videos_indexed: i32,
-    pub videos_uploaded: i64,
-}
-
-#[derive(Serialize, Deserialize, Clone)]
-pub struct MonitoredPlaylistStats {
-    pub playlist_id: String,
-    pub playlist_name: String,
-    pub active: bool,
-    pub created_at: String,
-    pub videos_indexed: i32,
-    pub videos_added: i64,
+/// Video group and snippet a shared search link points to
+#[derive(Clone, PartialEq)]
+pub struct ResultAnchor {
+    pub video_id: String,
+    pub hit: Option<f64>, // start time of the snippet
 }
 
 #[derive(Serialize, Deserialize)]
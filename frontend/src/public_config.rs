@@ -0,0 +1,23 @@
+use crate::env_variable_utils::BACKEND_URL;
+use crate::models::PublicConfig;
+use starchive_api_types::client::ApiClient;
+use yew::prelude::*;
+
+pub async fn load_public_config() -> Result<PublicConfig, String> {
+    Ok(ApiClient::new(&BACKEND_URL).public_config().await?)
+}
+
+/// Backend settings, empty and with all features disabled until the config has loaded
+#[hook]
+pub fn use_public_config() -> PublicConfig {
+    use_context::<PublicConfig>().unwrap_or_default()
+}
+
+/// Thumbnail through the backend proxy if it is enabled, hotlinked from YouTube otherwise
+pub fn thumbnail_src(config: &PublicConfig, video_id: &str) -> String {
+    if config.features.thumbnail_proxy {
+        format!("{}/video/{}/thumbnail", &*BACKEND_URL, video_id)
+    } else {
+        format!("https://i.ytimg.com/vi/{}/mqdefault.jpg", video_id)
+    }
+}
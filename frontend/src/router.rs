@@ -1,31 +1,30 @@
-use crate::admin::overview::admin_captions::AdminCaptionsPage;
-use crate::admin::overview::admin_monitor::AdminMonitorsPage;
-use crate::admin::overview::admin_queue::AdminQueuePage;
-use crate::admin::overview::admin_videos::AdminVideosPage;
-use crate::admin::overview::AdminPage;
-use crate::env_variable_utils::get_app_name;
+use crate::channel::components::ChannelPage;
+use crate::connectivity::use_connectivity;
 use crate::models::SearchResult;
+use crate::public_config::use_public_config;
 use crate::search::api::execute_search;
-use crate::search::components::{ResultsList, SearchBar};
-use crate::search::search_options::{SortBy, SortOrder};
-use crate::search::utils::{get_filter_param, get_query_param};
+use crate::search::components::{
+    ChannelFilter, FrequentlyFound, PageStateCache, PageUiState, ResultsList, SearchBar, SurpriseMe,
+};
+use crate::search::search_options::{SortBy, SortOrder, ViewMode};
+use crate::search::utils::{
+    get_filter_param, get_page_param, get_query_param, get_result_anchor, get_view_param,
+    store_view_mode,
+};
+use crate::transcript::components::TranscriptPage;
+use std::collections::HashMap;
 use yew::prelude::*;
 use yew_router::prelude::*;
 
+/// Routes of the public app, /admin is a separate app (see `admin::router`)
 #[derive(Clone, Routable, PartialEq)]
 pub enum Route {
     #[at("/")]
     Home,
-    #[at("/admin")]
-    Admin,
-    #[at("/admin/videos")]
-    AdminVideos,
-    #[at("/admin/captions")]
-    AdminCaptions,
-    #[at("/admin/monitors")]
-    AdminMonitors,
-    #[at("/admin/queue")]
-    AdminQueue,
+    #[at("/video/:id")]
+    Video { id: String },
+    #[at("/channel/:id")]
+    Channel { id: String },
     #[not_found]
     #[at("/404")]
     NotFound,
@@ -34,11 +33,8 @@ pub enum Route {
 pub fn switch(routes: Route) -> Html {
     match routes {
         Route::Home => html! { <SearchApp /> },
-        Route::Admin => html! { <AdminPage /> },
-        Route::AdminVideos => html! { <AdminVideosPage /> },
-        Route::AdminCaptions => html! { <AdminCaptionsPage /> },
-        Route::AdminMonitors => html! { <AdminMonitorsPage /> },
-        Route::AdminQueue => html! { <AdminQueuePage /> },
+        Route::Video { id } => html! { <TranscriptPage video_id={id} /> },
+        Route::Channel { id } => html! { <ChannelPage channel_id={id} /> },
         Route::NotFound => html! {
             <div class="min-h-screen flex items-center justify-center bg-gray-700">
                 <div class="bg-white p-8 rounded-lg shadow-lg text-center">
@@ -52,7 +48,17 @@ pub fn switch(routes: Route) -> Html {
     }
 }
 
-fn update_url_params(query: &str, search_type: &str, sort_by: &SortBy, sort_order: &SortOrder) {
+#[allow(clippy::too_many_arguments)]
+fn update_url_params(
+    query: &str,
+    search_type: &str,
+    exact_terms: bool,
+    exclude_channels: &[String],
+    sort_by: &SortBy,
+    sort_order: &SortOrder,
+    view: &ViewMode,
+    page: usize,
+) {
     if let Some(window) = web_sys::window() {
         let location = window.location();
         let url = web_sys::Url::new(&location.href().unwrap()).unwrap();
@@ -61,9 +67,26 @@ fn update_url_params(query: &str, search_type: &str, sort_by: &SortBy, sort_orde
         search_params.set("q", query);
 
         search_params.set("t", search_type);
+        search_params.set("exact", &exact_terms.to_string());
+        if exclude_channels.is_empty() {
+            search_params.delete("exclude_channels");
+        } else {
+            search_params.set("exclude_channels", &exclude_channels.join(","));
+        }
 
         search_params.set("sort_by", &format!("{:?}", sort_by));
         search_params.set("sort_order", &format!("{:?}", sort_order));
+        search_params.set("view", view.key());
+        if page > 0 {
+            search_params.set("page", &(page + 1).to_string());
+        } else {
+            search_params.delete("page");
+        }
+        // A new search or page no longer shows what a shared link pointed to,
+        // ResultsList writes it back once it restored it
+        search_params.delete("expanded");
+        search_params.delete("hit");
+        search_params.delete("quote");
 
         // Update the URL without reloading the page
         if let Ok(history) = window.history() {
@@ -88,6 +111,7 @@ fn get_sort_params() -> (SortBy, SortOrder) {
                         "Views" => SortBy::Views,
                         "Likes" => SortBy::Likes,
                         "CaptionMatches" => SortBy::CaptionMatches,
+                        "ViewVelocity" => SortBy::ViewVelocity,
                         _ => SortBy::Relevance,
                     })
                     .unwrap_or(SortBy::Relevance);
@@ -109,69 +133,142 @@ fn get_sort_params() -> (SortBy, SortOrder) {
 
 #[function_component(SearchApp)]
 pub fn search_app() -> Html {
+    let public_config = use_public_config();
+    let connectivity = use_connectivity();
     let search_query = use_state(|| get_query_param().unwrap_or_default());
     let search_results = use_state(Vec::<SearchResult>::default);
     let total_results = use_state(|| None::<(usize, usize)>);
     let loading = use_state(|| false);
     let error_message = use_state(Option::<String>::default);
+    let warnings = use_state(Vec::<String>::new);
+    let unavailable = use_state(|| false);
     let init_done = use_state(|| false);
-    let current_page = use_state(|| 0usize);
+    let current_page = use_state(get_page_param);
+    // Video group of a shared link, applied once the first results arrive
+    let result_anchor = use_state(get_result_anchor);
 
     let filter_param = get_filter_param();
-    let is_wide_search = use_state(|| filter_param.unwrap().search_type == "wide");
+    let is_wide_search = use_state(|| {
+        filter_param
+            .as_ref()
+            .is_some_and(|param| param.search_type == "wide")
+    });
+    let is_exact_terms = use_state(|| filter_param.as_ref().is_some_and(|param| param.exact_terms));
+    let excluded_channels = use_state(|| {
+        filter_param
+            .as_ref()
+            .map(|param| param.exclude_channels.clone())
+            .unwrap_or_default()
+    });
 
     // Add sort options state
     let initial_sort = get_sort_params();
     let sort_by = use_state(|| initial_sort.0);
     let sort_order = use_state(|| initial_sort.1);
 
+    let view_mode = use_state(get_view_param);
+
+    // Expanded videos and scroll offsets of the visited pages of the current search
+    let page_states = use_mut_ref(HashMap::<usize, PageUiState>::new);
+
     let on_wide_search_toggle = {
         let is_wide_search = is_wide_search.clone();
         let current_page = current_page.clone();
+        let page_states = page_states.clone();
         Callback::from(move |_| {
+            page_states.borrow_mut().clear();
             is_wide_search.set(!*is_wide_search);
             current_page.set(0);
         })
     };
 
+    let on_exact_terms_toggle = {
+        let is_exact_terms = is_exact_terms.clone();
+        let current_page = current_page.clone();
+        let page_states = page_states.clone();
+        Callback::from(move |_| {
+            page_states.borrow_mut().clear();
+            is_exact_terms.set(!*is_exact_terms);
+            current_page.set(0);
+        })
+    };
+
+    let on_excluded_channels_change = {
+        let excluded_channels = excluded_channels.clone();
+        let current_page = current_page.clone();
+        let page_states = page_states.clone();
+        Callback::from(move |channels: Vec<String>| {
+            page_states.borrow_mut().clear();
+            excluded_channels.set(channels);
+            current_page.set(0);
+        })
+    };
+
     // Helper function to execute search with current parameters
     let execute_current_search = {
         let search_results = search_results.clone();
         let total_results = total_results.clone();
         let loading = loading.clone();
         let error_message = error_message.clone();
+        let warnings = warnings.clone();
+        let unavailable = unavailable.clone();
         let is_wide_search = is_wide_search.clone();
+        let is_exact_terms = is_exact_terms.clone();
+        let excluded_channels = excluded_channels.clone();
         let sort_by = sort_by.clone();
         let sort_order = sort_order.clone();
+        let current_page = current_page.clone();
 
-        move |query: String, page: usize| {
+        move |query: String, page: usize, view: ViewMode| {
+            let current_page = current_page.clone();
             let search_results = search_results.clone();
             let total_results = total_results.clone();
             let loading = loading.clone();
             let error_message = error_message.clone();
+            let warnings = warnings.clone();
+            let unavailable = unavailable.clone();
             let sort_by = sort_by.clone();
             let sort_order = sort_order.clone();
 
             loading.set(true);
             error_message.set(None);
+            warnings.set(Vec::new());
+            unavailable.set(false);
 
             let is_wide = *is_wide_search;
             let search_type = if is_wide { "wide" } else { "natural" };
+            let exact_terms = *is_exact_terms;
+            let exclude_channels = (*excluded_channels).clone();
             let current_sort_by = (*sort_by).clone();
             let current_sort_order = (*sort_order).clone();
 
-            update_url_params(&query, search_type, &current_sort_by, &current_sort_order);
+            update_url_params(
+                &query,
+                search_type,
+                exact_terms,
+                &exclude_channels,
+                &current_sort_by,
+                &current_sort_order,
+                &view,
+                page,
+            );
 
             wasm_bindgen_futures::spawn_local(async move {
                 execute_search(
                     query,
                     search_type,
+                    exact_terms,
+                    exclude_channels,
+                    view == ViewMode::Compact, // compact view only needs the best hit per video
                     current_sort_by,
                     current_sort_order,
                     page,
+                    current_page,
                     search_results,
                     total_results,
                     error_message,
+                    warnings,
+                    unavailable,
                     loading,
                 )
                 .await;
@@ -183,13 +280,15 @@ pub fn search_app() -> Html {
     {
         let search_query = search_query.clone();
         let init_done = init_done.clone();
+        let view_mode = view_mode.clone();
+        let current_page = current_page.clone();
         let execute_search_fn = execute_current_search.clone();
 
         use_effect(move || {
             if !*init_done {
                 if let Some(query) = get_query_param() {
                     search_query.set(query.clone());
-                    execute_search_fn(query, 0);
+                    execute_search_fn(query, *current_page, (*view_mode).clone());
                 }
                 init_done.set(true);
             }
@@ -197,28 +296,76 @@ pub fn search_app() -> Html {
         });
     }
 
+    // Runs the search again that failed while the backend was unreachable
+    {
+        let search_query = search_query.clone();
+        let error_message = error_message.clone();
+        let current_page = current_page.clone();
+        let view_mode = view_mode.clone();
+        let execute_search_fn = execute_current_search.clone();
+
+        use_effect_with(connectivity.recoveries, move |recoveries| {
+            if *recoveries > 0 && error_message.is_some() && !search_query.is_empty() {
+                execute_search_fn((*search_query).clone(), *current_page, (*view_mode).clone());
+            }
+            || ()
+        });
+    }
+
     let on_search = {
         let search_query = search_query.clone();
         let current_page = current_page.clone();
+        let view_mode = view_mode.clone();
+        let page_states = page_states.clone();
+        let result_anchor = result_anchor.clone();
         let execute_search_fn = execute_current_search.clone();
 
         Callback::from(move |query: String| {
+            page_states.borrow_mut().clear();
+            result_anchor.set(None);
             search_query.set(query.clone());
             current_page.set(0);
-            execute_search_fn(query, 0);
+            execute_search_fn(query, 0, (*view_mode).clone());
+        })
+    };
+
+    let on_view_change = {
+        let search_query = search_query.clone();
+        let current_page = current_page.clone();
+        let view_mode = view_mode.clone();
+        let page_states = page_states.clone();
+        let result_anchor = result_anchor.clone();
+        let execute_search_fn = execute_current_search.clone();
+
+        Callback::from(move |view: ViewMode| {
+            page_states.borrow_mut().clear();
+            result_anchor.set(None);
+            store_view_mode(&view);
+            view_mode.set(view.clone());
+            current_page.set(0);
+
+            // Both views request different results, so search again
+            let query = (*search_query).clone();
+            if !query.is_empty() {
+                execute_search_fn(query, 0, view);
+            }
         })
     };
 
     let on_sort_by_change = {
         let sort_by = sort_by.clone();
+        let page_states = page_states.clone();
         Callback::from(move |new_sort_by: SortBy| {
+            page_states.borrow_mut().clear();
             sort_by.set(new_sort_by);
         })
     };
 
     let on_sort_order_change = {
         let sort_order = sort_order.clone();
+        let page_states = page_states.clone();
         Callback::from(move |new_sort_order: SortOrder| {
+            page_states.borrow_mut().clear();
             sort_order.set(new_sort_order);
         })
     };
@@ -226,26 +373,35 @@ pub fn search_app() -> Html {
     let on_page_change = {
         let search_query = search_query.clone();
         let current_page = current_page.clone();
+        let view_mode = view_mode.clone();
+        let result_anchor = result_anchor.clone();
         let execute_search_fn = execute_current_search.clone();
 
         Callback::from(move |page: usize| {
+            result_anchor.set(None);
             current_page.set(page);
             let query = (*search_query).clone();
-            execute_search_fn(query, page);
+            execute_search_fn(query, page, (*view_mode).clone());
         })
     };
 
+    let on_anchor_applied = {
+        let result_anchor = result_anchor.clone();
+        Callback::from(move |_| result_anchor.set(None))
+    };
+
     html! {
         <div class="min-h-screen flex flex-col items-center justify-center bg-gray-700 p-4">
             <div class="bg-white p-8 rounded-lg shadow-lg w-full max-w-2xl">
                 <h1 class="text-3xl font-bold text-center text-gray-800 mb-6">
-                    {get_app_name()}
+                    {&public_config.app_name}
                 </h1>
 
                 <div class="text-center mb-4">
-                    <Link<Route> to={Route::Admin} classes="text-blue-600 hover:underline text-sm">
+                    // The admin panel is a separate app, so this is a full page load
+                    <a href="/admin" class="text-blue-600 hover:underline text-sm">
                         {"Admin Panel"}
-                    </Link<Route>>
+                    </a>
                 </div>
 
                 <SearchBar
@@ -258,7 +414,7 @@ pub fn search_app() -> Html {
                     on_sort_order_change={on_sort_order_change}
                 />
 
-                <div class="flex items-center justify-center mb-4">
+                <div class="flex items-center justify-center gap-6 mb-4">
                     <label class="inline-flex items-center">
                         <input
                             type="checkbox"
@@ -268,10 +424,38 @@ pub fn search_app() -> Html {
                         />
                         <span class="ml-2 text-gray-700">{"Enable wide search"}</span>
                     </label>
+                    <label
+                        class="inline-flex items-center"
+                        title="Match words exactly as typed, without stemming. Prefix a single word with + to only match that word exactly."
+                    >
+                        <input
+                            type="checkbox"
+                            class="form-checkbox h-5 w-5 text-blue-600"
+                            checked={*is_exact_terms}
+                            onchange={on_exact_terms_toggle}
+                        />
+                        <span class="ml-2 text-gray-700">{"Exact words"}</span>
+                    </label>
                 </div>
 
+                <ChannelFilter
+                    excluded={(*excluded_channels).clone()}
+                    on_change={on_excluded_channels_change}
+                />
+
+                if search_query.is_empty() {
+                    <SurpriseMe />
+                    <FrequentlyFound />
+                }
+
                 {
-                    if let Some(msg) = &*error_message {
+                    if let (true, Some(msg)) = (*unavailable, &*error_message) {
+                        html! {
+                            <div class="bg-yellow-100 border border-yellow-400 text-yellow-800 px-4 py-3 rounded mb-4 text-center">
+                                { msg }
+                            </div>
+                        }
+                    } else if let Some(msg) = &*error_message {
                         html! {
                             <p class="text-red-600 text-center mb-4">{ format!("Error: {msg}") }</p>
                         }
@@ -284,10 +468,16 @@ pub fn search_app() -> Html {
                     results={(*search_results).clone()}
                     loading={*loading}
                     error={(*error_message).clone()}
+                    warnings={(*warnings).clone()}
                     query={(*search_query).clone()}
                     on_page_change={on_page_change}
                     current_page={*current_page}
                     total_results={*total_results}
+                    view={(*view_mode).clone()}
+                    on_view_change={on_view_change}
+                    page_states={PageStateCache(page_states.clone())}
+                    anchor={(*result_anchor).clone()}
+                    on_anchor_applied={on_anchor_applied}
                 />
             </div>
         </div>
@@ -1,21 +1,129 @@
 use crate::env_variable_utils::BACKEND_URL;
-use crate::models::{ErrorResponse, SearchResponse, SearchResult, VideoMetadata};
+use crate::models::{
+    BatchVideoRequest, BatchVideoResponse, CaptionReportRequest, ErrorResponse, FeedbackAction,
+    PhraseFrequencyResponse, PublicChannel, RandomQuote, SearchFeedbackRequest, SearchResponse,
+    SearchResult, TrendingVideo, VideoMetadata,
+};
+use crate::search::components::RESULTS_PER_PAGE;
 use crate::search::search_options::{SortBy, SortOrder};
-use gloo_net::http::Request;
-use serde::{Deserialize, Serialize};
+use crate::search::utils::get_filter_param;
+use gloo_net::http::{Request, Response};
+use starchive_api_types::client::{self, ApiClient};
+use starchive_api_types::GENERATION_HEADER;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use yew::prelude::*;
 
-pub async fn get_raw_video_metadata(
-    video_id: &str,
-) -> Result<gloo_net::http::Response, gloo_net::Error> {
+/// Video metadata already loaded by result lists, valid for one archive generation
+#[derive(Default)]
+struct MetadataCache {
+    generation: Option<String>,
+    videos: HashMap<String, Option<VideoMetadata>>,
+}
+
+thread_local! {
+    static METADATA_CACHE: RefCell<MetadataCache> = RefCell::new(MetadataCache::default());
+}
+
+/// Drops the cached metadata once the backend reports a new archive generation, i.e.
+/// videos were crawled or deleted since it was loaded
+fn observe_generation(response: &Response) {
+    let Some(generation) = response.headers().get(GENERATION_HEADER) else {
+        return;
+    };
+    METADATA_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.generation.as_deref() != Some(generation.as_str()) {
+            cache.videos.clear();
+            cache.generation = Some(generation);
+        }
+    });
+}
+
+pub async fn get_raw_video_metadata(video_id: &str) -> Result<Response, gloo_net::Error> {
     let backend_url = &*BACKEND_URL;
     let url = format!("{backend_url}/video/{video_id}");
-    Request::get(&url).send().await
+    let response = Request::get(&url).send().await?;
+    observe_generation(&response);
+    Ok(response)
+}
+
+/// Metadata of several videos, in the order of `video_ids`. Only videos that aren't
+/// cached yet are requested, in one request. Videos that failed to load are listed in
+/// the response's `errors` and not cached.
+pub async fn get_videos_metadata(video_ids: &[String]) -> Result<BatchVideoResponse, String> {
+    let cached: HashMap<String, Option<VideoMetadata>> = METADATA_CACHE.with(|cache| {
+        let cache = cache.borrow();
+        video_ids
+            .iter()
+            .filter_map(|id| Some((id.clone(), cache.videos.get(id)?.clone())))
+            .collect()
+    });
+    let mut missing: Vec<String> = Vec::new();
+    for id in video_ids {
+        if !cached.contains_key(id) && !missing.contains(id) {
+            missing.push(id.clone());
+        }
+    }
+    if missing.is_empty() {
+        return Ok(BatchVideoResponse {
+            videos: video_ids.iter().map(|id| cached[id].clone()).collect(),
+            errors: Vec::new(),
+        });
+    }
+
+    let fetched = fetch_videos_metadata(&missing).await?;
+    let failed: Vec<&str> = fetched
+        .errors
+        .iter()
+        .map(|error| error.video_id.as_str())
+        .collect();
+    let fetched_by_id: HashMap<String, Option<VideoMetadata>> =
+        missing.into_iter().zip(fetched.videos).collect();
+    METADATA_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        for (id, video) in &fetched_by_id {
+            if !failed.contains(&id.as_str()) {
+                cache.videos.insert(id.clone(), video.clone());
+            }
+        }
+    });
+
+    let videos = video_ids
+        .iter()
+        .map(|id| match cached.get(id) {
+            Some(video) => video.clone(),
+            None => fetched_by_id.get(id).cloned().flatten(),
+        })
+        .collect();
+    Ok(BatchVideoResponse {
+        videos,
+        errors: fetched.errors,
+    })
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct BatchVideoRequest {
-    pub video_ids: Vec<String>,
+async fn fetch_videos_metadata(video_ids: &[String]) -> Result<BatchVideoResponse, String> {
+    let url = format!("{}/video/batch", &*BACKEND_URL);
+    let request_body = BatchVideoRequest {
+        video_ids: video_ids.to_vec(),
+    };
+
+    let response = Request::post(&url)
+        .json(&request_body)
+        .map_err(|e| format!("Request error: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+    observe_generation(&response);
+
+    if response.ok() {
+        response
+            .json::<BatchVideoResponse>()
+            .await
+            .map_err(|e| format!("Failed to parse video metadata: {}", e))
+    } else {
+        Err(format!("HTTP error: {}", response.status()))
+    }
 }
 
 pub async fn get_video_metadata(
@@ -50,15 +158,22 @@ pub async fn get_video_metadata(
     loading.set(false);
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn execute_search(
     query: String,
     search_type: &str,
+    exact_terms: bool,
+    exclude_channels: Vec<String>,
+    collapse: bool,
     sort_by: SortBy,
     sort_order: SortOrder,
     page: usize,
+    current_page: UseStateHandle<usize>,
     search_results: UseStateHandle<Vec<SearchResult>>,
     total_results: UseStateHandle<Option<(usize, usize)>>, // (videos, captions)
     error_message: UseStateHandle<Option<String>>,
+    warnings: UseStateHandle<Vec<String>>,
+    unavailable: UseStateHandle<bool>,
     loading: UseStateHandle<bool>,
 ) {
     let sort_by_str = match sort_by {
@@ -68,6 +183,7 @@ pub async fn execute_search(
         SortBy::Views => "views",
         SortBy::Likes => "likes",
         SortBy::CaptionMatches => "caption_matches",
+        SortBy::ViewVelocity => "view_velocity",
     };
 
     let order_by_str = match sort_order {
@@ -75,27 +191,39 @@ pub async fn execute_search(
         SortOrder::Desc => "desc",
     };
 
-    let url = format!(
-        "{}/search/?query={}&type={}&sort={}&order={}&page={}",
+    let mut url = format!(
+        "{}/search/?query={}&type={}&exact_terms={}&collapse={}&sort={}&order={}&page={}&page_size={}",
         &*BACKEND_URL,
         urlencoding::encode(&query),
         search_type,
+        exact_terms,
+        collapse,
         sort_by_str,
         order_by_str,
-        page
+        page,
+        RESULTS_PER_PAGE
     );
+    if !exclude_channels.is_empty() {
+        url.push_str(&format!("&exclude_channels={}", exclude_channels.join(",")));
+    }
 
     match Request::get(&url).send().await {
         Ok(response) => {
+            observe_generation(&response);
             if response.ok() {
                 match response.json::<SearchResponse>().await {
                     Ok(search_response) => {
+                        // Pages past the last one are answered with the last one
+                        if search_response.clamped {
+                            current_page.set(search_response.page);
+                        }
                         search_results.set(search_response.results);
                         total_results.set(Some((
                             search_response.total_videos,
                             search_response.total_captions,
                         )));
                         error_message.set(None);
+                        warnings.set(search_response.warnings);
                     }
                     Err(e) => {
                         error_message.set(Some(format!("Failed to parse response: {}", e)));
@@ -103,6 +231,8 @@ pub async fn execute_search(
                 }
             } else {
                 let status = response.status();
+                // The backend can't reach its search cluster, shown as a notice not an error
+                unavailable.set(status == 503);
                 match response.text().await {
                     Ok(error_text) => {
                         // Try to parse as structured error response first
@@ -126,6 +256,7 @@ pub async fn execute_search(
             }
         }
         Err(e) => {
+            client::report_network_error();
             error_message.set(Some(format!("Network error: {}", e)));
         }
     }
@@ -133,6 +264,83 @@ pub async fn execute_search(
     loading.set(false);
 }
 
+pub async fn report_caption(video_id: &str, start_time: f64, query: &str) -> Result<(), String> {
+    let url = format!("{}/search/report", &*BACKEND_URL);
+
+    let request_body = CaptionReportRequest {
+        video_id: video_id.to_string(),
+        start_time,
+        query: query.to_string(),
+    };
+
+    let response = Request::post(&url)
+        .json(&request_body)
+        .map_err(|e| format!("Request error: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+
+    match response.status() {
+        200 | 201 => Ok(()),
+        429 => Err("Too many reports, please try again later".to_string()),
+        status => Err(format!("HTTP error: {}", status)),
+    }
+}
+
+/// Videos that often matched the popular queries of the last week
+pub async fn get_trending_matches(limit: usize) -> Result<Vec<TrendingVideo>, String> {
+    Ok(ApiClient::new(&BACKEND_URL).trending_matches(limit).await?)
+}
+
+/// How often `query` was said per `interval` of upload dates
+pub async fn get_phrase_frequency(
+    query: &str,
+    interval: &str,
+) -> Result<PhraseFrequencyResponse, String> {
+    Ok(ApiClient::new(&BACKEND_URL)
+        .phrase_frequency(query, interval)
+        .await?)
+}
+
+/// A random quote of the archive, the one of `seed` if given
+pub async fn get_random_quote(seed: Option<u64>) -> Result<RandomQuote, String> {
+    Ok(ApiClient::new(&BACKEND_URL)
+        .random_quote(None, seed)
+        .await?)
+}
+
+/// Channels that can be filtered out of the results
+pub async fn get_channels() -> Result<Vec<PublicChannel>, String> {
+    Ok(ApiClient::new(&BACKEND_URL).public_channels().await?)
+}
+
+/// Reports a view, click or expand of a result for the click-through stats.
+/// Fire and forget, a lost event is not worth bothering the user about.
+pub fn send_feedback(query: &str, video_id: &str, rank: usize, action: FeedbackAction) {
+    if query.trim().is_empty() {
+        return;
+    }
+    let request_body = SearchFeedbackRequest {
+        query: query.to_string(),
+        search_type: get_filter_param()
+            .map(|filter| filter.search_type)
+            .unwrap_or_else(|| "natural".to_string()),
+        video_id: video_id.to_string(),
+        rank,
+        action,
+    };
+    wasm_bindgen_futures::spawn_local(async move {
+        let url = format!("{}/search/feedback", &*BACKEND_URL);
+        let sent = match Request::post(&url).json(&request_body) {
+            Ok(request) => request.send().await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = sent {
+            web_sys::console::warn_1(&format!("Failed to send search feedback: {e}").into());
+        }
+    });
+}
+
 fn handle_error(error_message: &UseStateHandle<Option<String>>, error: String) {
     error_message.set(Some(error.clone()));
     web_sys::console::error_1(&error.into());
@@ -1,9 +1,27 @@
-use crate::models::SearchResult;
-use crate::search::api::get_video_metadata;
-use crate::search::search_options::{SearchOptionsDropdowns, SortBy, SortOrder};
-use crate::utils::{format_duration, format_number, format_unix_date};
-use web_sys::HtmlInputElement;
+use crate::models::{
+    FeedbackAction, PhraseFrequencyResponse, PublicChannel, RandomQuote, ResultAnchor,
+    SearchResult, TrendingVideo, VideoMetadata,
+};
+use crate::public_config::{thumbnail_src, use_public_config};
+use crate::router::Route;
+use crate::search::api::{
+    get_channels, get_phrase_frequency, get_random_quote, get_trending_matches, get_video_metadata,
+    get_videos_metadata, report_caption, send_feedback,
+};
+use crate::search::search_options::{SearchOptionsDropdowns, SortBy, SortOrder, ViewMode};
+use crate::search::utils::{
+    get_quote_param, get_result_anchor, set_quote_param, set_result_anchor_params,
+};
+use crate::transcript::components::{open_transcript, transcript_link};
+use crate::utils::{format_count, format_duration, format_unix_date};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, HtmlElement, HtmlInputElement};
 use yew::prelude::*;
+use yew_router::components::Link;
+use yew_router::hooks::use_navigator;
 
 #[derive(Properties, PartialEq)]
 pub struct SearchBarProps {
@@ -19,25 +37,177 @@ pub struct SearchBarProps {
 #[derive(Properties, PartialEq)]
 pub struct SearchResultItemProps {
     pub result: SearchResult,
+    pub query: String,
+    pub rank: usize, // of the video, for search feedback
 }
 
+#[derive(Properties, PartialEq)]
+pub struct CompactResultRowProps {
+    pub result: SearchResult,
+    pub query: String,
+    pub rank: usize,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct MatchTimelineProps {
+    pub results: Vec<SearchResult>,
+    pub duration: i64, // in seconds, 0 if unknown
+}
+
+#[derive(Properties, PartialEq)]
+pub struct PhraseTrendProps {
+    pub query: String,
+}
+
+/// Videos with captions on less of their duration get a warning, a quote may be missing
+const LOW_CAPTION_COVERAGE: f64 = 0.8;
+
 #[derive(Properties, PartialEq)]
 pub struct VideoResultsProps {
     pub video_id: String,
     pub results: Vec<SearchResult>,
+    pub query: String,
+    pub rank: usize, // 1-based position of the video across all pages
+    pub expanded: bool,
+    pub on_toggle: Callback<String>, // video_id
+    pub metadata: MetadataStatus,
+    pub on_retry_metadata: Callback<()>,
+}
+
+/// Metadata of a result group, loaded for the whole page at once
+#[derive(Clone, PartialEq)]
+pub enum MetadataStatus {
+    Loading,
+    Loaded(Box<VideoMetadata>),
+    /// The backend doesn't know the video (anymore) or it is hidden
+    Unavailable,
+    Failed,
+}
+
+impl MetadataStatus {
+    fn metadata(&self) -> Option<&VideoMetadata> {
+        match self {
+            MetadataStatus::Loaded(metadata) => Some(metadata),
+            _ => None,
+        }
+    }
+}
+
+pub const RESULTS_PER_PAGE: usize = 10;
+
+/// UI state of a visited result page, restored when paginating back to it
+#[derive(Clone, Default)]
+pub struct PageUiState {
+    pub expanded: HashSet<String>, // video ids
+    pub scroll_y: f64,
 }
 
-const RESULTS_PER_PAGE: usize = 10;
+/// Page number -> UI state of the current search. Mutated in place without
+/// re-rendering, compared by identity so it never causes a re-render either.
+#[derive(Clone)]
+pub struct PageStateCache(pub Rc<RefCell<HashMap<usize, PageUiState>>>);
+
+impl PartialEq for PageStateCache {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
 
 #[derive(Properties, PartialEq)]
 pub struct ResultsListProps {
     pub results: Vec<SearchResult>,
     pub loading: bool,
     pub error: Option<String>,
+    /// Degraded parts of the search, shown as a notice above the results
+    pub warnings: Vec<String>,
     pub query: String,
     pub on_page_change: Callback<usize>,
     pub current_page: usize,
     pub total_results: Option<(usize, usize)>, // (total_videos, total_captions)
+    pub view: ViewMode,
+    pub on_view_change: Callback<ViewMode>,
+    pub page_states: PageStateCache,
+    /// Video group of a shared link, expanded and scrolled to once the results are there
+    pub anchor: Option<ResultAnchor>,
+    pub on_anchor_applied: Callback<()>,
+}
+
+/// Readable text of a search warning, unknown ones are shown as they are
+fn describe_warning(warning: &str) -> String {
+    match warning {
+        "metadata unavailable" => {
+            "Video details are unavailable right now, results are sorted by relevance.".to_string()
+        }
+        _ => warning.to_string(),
+    }
+}
+
+/// Attribute marking the focusable row of every video, in both view modes
+const RESULT_ROW_ATTR: &str = "data-result-row";
+
+/// Moves the focus to the previous/next result row on ArrowUp/ArrowDown.
+/// Returns false for any other key.
+fn move_row_focus(e: &KeyboardEvent) -> bool {
+    let step: i64 = match e.key().as_str() {
+        "ArrowDown" => 1,
+        "ArrowUp" => -1,
+        _ => return false,
+    };
+    e.prevent_default();
+
+    let current = e.target_unchecked_into::<Element>();
+    let Some(rows) = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.query_selector_all(&format!("[{}]", RESULT_ROW_ATTR)).ok())
+    else {
+        return true;
+    };
+
+    let position = (0..rows.length()).find(|&i| {
+        rows.get(i)
+            .is_some_and(|row| row.is_same_node(Some(current.as_ref())))
+    });
+    if let Some(position) = position {
+        let target = position as i64 + step;
+        if target >= 0 {
+            if let Some(row) = rows
+                .get(target as u32)
+                .and_then(|row| row.dyn_into::<HtmlElement>().ok())
+            {
+                let _ = row.focus();
+            }
+        }
+    }
+    true
+}
+
+/// Upper bound of ticks on a timeline, more matches are merged into buckets
+const MAX_TIMELINE_TICKS: usize = 100;
+
+/// DOM id of the `SearchResultItem` of a match, used by the timeline to scroll to it
+fn result_item_id(result: &SearchResult) -> String {
+    format!(
+        "result-{}-{}",
+        result.video_id,
+        (result.start_time * 1000.0) as i64
+    )
+}
+
+/// DOM id of the `VideoResults` group of a video
+fn video_group_id(video_id: &str) -> String {
+    format!("group-{}", video_id)
+}
+
+fn open_video(video_id: &str, start_time: f64) {
+    if let Some(window) = web_sys::window() {
+        let _ = window.open_with_url_and_target(
+            &format!(
+                "https://www.youtube.com/watch?v={}&t={}s",
+                video_id, start_time as i64
+            ),
+            "_blank",
+        );
+    }
 }
 
 #[function_component(SearchBar)]
@@ -96,88 +266,284 @@ pub fn search_bar(props: &SearchBarProps) -> Html {
 
 #[function_component(SearchResultItem)]
 pub fn search_result_item(props: &SearchResultItemProps) -> Html {
+    let reported = use_state(|| false);
+    let report_error = use_state(|| None::<String>);
+    let navigator = use_navigator();
+
+    let on_open_transcript = {
+        let video_id = props.result.video_id.clone();
+        let start_time = props.result.start_time;
+        Callback::from(move |e: MouseEvent| {
+            // Modified clicks open the link in a new tab as usual
+            if e.ctrl_key() || e.meta_key() || e.shift_key() {
+                return;
+            }
+            if let Some(navigator) = &navigator {
+                e.prevent_default();
+                open_transcript(navigator, &video_id, start_time);
+            }
+        })
+    };
+
+    let on_open_video = {
+        let query = props.query.clone();
+        let video_id = props.result.video_id.clone();
+        let rank = props.rank;
+        Callback::from(move |_: MouseEvent| {
+            send_feedback(&query, &video_id, rank, FeedbackAction::Click)
+        })
+    };
+
+    let on_report = {
+        let reported = reported.clone();
+        let report_error = report_error.clone();
+        let video_id = props.result.video_id.clone();
+        let start_time = props.result.start_time;
+        let query = props.query.clone();
+
+        Callback::from(move |_: MouseEvent| {
+            if *reported {
+                return;
+            }
+            let reported = reported.clone();
+            let report_error = report_error.clone();
+            let video_id = video_id.clone();
+            let query = query.clone();
+
+            wasm_bindgen_futures::spawn_local(async move {
+                match report_caption(&video_id, start_time, &query).await {
+                    Ok(_) => {
+                        reported.set(true);
+                        report_error.set(None);
+                    }
+                    Err(e) => report_error.set(Some(e)),
+                }
+            });
+        })
+    };
+
     html! {
-        <div class="p-4 bg-white">
-            <p class="text-sm text-gray-500 mb-1">
+        <div id={result_item_id(&props.result)} class="p-4 bg-white flex items-start gap-2 scroll-mt-4">
+            <p class="text-sm text-gray-500 mb-1 flex-grow">
                 <a href={format!("https://www.youtube.com/watch?v={}&t={}s", props.result.video_id, props.result.start_time)}
                    target="_blank"
+                   onclick={on_open_video}
                    class="ml-2 text-blue-600 hover:underline">
                 {format!("{} ↗ ", format_duration(props.result.start_time as i64))}
                 </a>
+                <a href={transcript_link(&props.result.video_id, props.result.start_time)}
+                   onclick={on_open_transcript}
+                   title="Show in transcript"
+                   class="mr-2 text-blue-600 hover:underline">
+                {"¶"}
+                </a>
             { Html::from_html_unchecked(AttrValue::from(props.result.snippet_html.clone())) }
             </p>
+            <button
+                onclick={on_report}
+                disabled={*reported}
+                title={(*report_error).clone().unwrap_or_else(|| {
+                    if *reported { "Reported, thank you!".to_string() } else { "Report a misleading transcript".to_string() }
+                })}
+                class={if report_error.is_some() {
+                    "text-xs text-red-600"
+                } else if *reported {
+                    "text-xs text-gray-300 cursor-default"
+                } else {
+                    "text-xs text-gray-400 hover:text-red-600"
+                }}
+            >
+                {"⚑"}
+            </button>
         </div>
     }
 }
 
-#[function_component(VideoResults)]
-pub fn video_results(props: &VideoResultsProps) -> Html {
-    let expanded = use_state(|| false);
-    let video_metadata = use_state(|| None);
-    let error_message = use_state(|| None);
-    let loading = use_state(|| false);
+#[function_component(MatchTimeline)]
+pub fn match_timeline(props: &MatchTimelineProps) -> Html {
+    // Fall back to the last match if the duration is unknown
+    let length = if props.duration > 0 {
+        props.duration as f64
+    } else {
+        props.results.iter().map(|r| r.end_time).fold(0.0, f64::max)
+    };
+    if length <= 0.0 || props.results.is_empty() {
+        return html! {};
+    }
 
-    {
-        let video_id = props.video_id.clone();
-        let video_metadata = video_metadata.clone();
-        let error_message = error_message.clone();
-        let loading = loading.clone();
-        let prev_video_id = use_state(|| String::new());
+    // Buckets of (first match, match count) in timeline order
+    let bucket_count = props.results.len().min(MAX_TIMELINE_TICKS);
+    let mut buckets: Vec<Option<(&SearchResult, usize)>> = vec![None; bucket_count];
+    for result in &props.results {
+        let position = (result.start_time / length).clamp(0.0, 1.0);
+        let index = ((position * bucket_count as f64) as usize).min(bucket_count - 1);
+        match &mut buckets[index] {
+            Some((first, count)) => {
+                if result.start_time < first.start_time {
+                    *first = result;
+                }
+                *count += 1;
+            }
+            slot => *slot = Some((result, 1)),
+        }
+    }
 
-        use_effect(move || {
-            if *prev_video_id != video_id {
-                prev_video_id.set(video_id.clone());
-                loading.set(true);
-                error_message.set(None);
+    html! {
+        <div class="relative h-3 mx-4 my-3 bg-gray-300 rounded" aria-label="Match positions">
+            { for buckets.into_iter().flatten().map(|(result, count)| {
+                let left = (result.start_time / length * 100.0).clamp(0.0, 100.0);
+                let target_id = result_item_id(result);
+                let anchor = ResultAnchor {
+                    video_id: result.video_id.clone(),
+                    hit: Some(result.start_time),
+                };
+                let onclick = move |_: MouseEvent| {
+                    set_result_anchor_params(Some(&anchor));
+                    if let Some(element) = web_sys::window()
+                        .and_then(|w| w.document())
+                        .and_then(|d| d.get_element_by_id(&target_id))
+                    {
+                        element.scroll_into_view();
+                    }
+                };
+                html! {
+                    <button
+                        {onclick}
+                        class="group absolute top-0 h-3 w-1 -ml-0.5 bg-blue-600 hover:bg-blue-800 rounded-sm"
+                        style={format!("left: {:.3}%", left)}
+                    >
+                        <span class="hidden group-hover:block absolute bottom-4 left-1/2 -translate-x-1/2 w-64 z-10 p-2 text-xs text-left text-gray-700 bg-white border border-gray-300 rounded shadow">
+                            <span class="block text-gray-500 mb-1">
+                                { format_duration(result.start_time as i64) }
+                                { if count > 1 { format!(" · {} matches", count) } else { String::new() } }
+                            </span>
+                            { Html::from_html_unchecked(AttrValue::from(result.snippet_html.clone())) }
+                        </span>
+                    </button>
+                }
+            })}
+        </div>
+    }
+}
 
-                wasm_bindgen_futures::spawn_local(async move {
-                    get_video_metadata(video_id, video_metadata, error_message, loading).await;
-                });
-            }
-            || ()
-        });
+/// Matches of a video as counted by the backend, the shown hits if it didn't count them
+fn match_count_label(results: &[SearchResult]) -> String {
+    let count = results
+        .iter()
+        .find_map(|result| result.match_count)
+        .unwrap_or(results.len() as i64);
+    if count == 1 {
+        "1 match".to_string()
+    } else {
+        format!("{} matches", format_count(count))
     }
+}
 
+#[function_component(VideoResults)]
+pub fn video_results(props: &VideoResultsProps) -> Html {
+    let toggle_expanded = {
+        let on_toggle = props.on_toggle.clone();
+        let video_id = props.video_id.clone();
+        move || on_toggle.emit(video_id.clone())
+    };
+    let on_open_video = {
+        let query = props.query.clone();
+        let video_id = props.video_id.clone();
+        let rank = props.rank;
+        move |_: MouseEvent| send_feedback(&query, &video_id, rank, FeedbackAction::Click)
+    };
     html! {
-        <div class="bg-gray-100 rounded-lg overflow-hidden">
-            <div class="bg-gray-200 p-4 flex justify-between items-center cursor-pointer"
-                 onclick={let expanded = expanded.clone(); move |_| expanded.set(!*expanded)}>
+        <div id={video_group_id(&props.video_id)} class="bg-gray-100 rounded-lg overflow-hidden scroll-mt-4">
+            <div class="bg-gray-200 p-4 flex justify-between items-center cursor-pointer focus:outline-none focus:ring-2 focus:ring-blue-500"
+                 tabindex="0"
+                 data-result-row="true"
+                 onclick={let toggle_expanded = toggle_expanded.clone(); move |_| toggle_expanded()}
+                 onkeydown={
+                     move |e: KeyboardEvent| {
+                         if move_row_focus(&e) {
+                             return;
+                         }
+                         if e.key() == "Enter" || e.key() == " " {
+                             e.prevent_default();
+                             toggle_expanded();
+                         }
+                     }
+                 }>
                 <h3 class="text-lg font-semibold text-gray-800">
                     <a href={format!("https://www.youtube.com/watch?v={}", props.video_id)}
                        target="_blank"
+                       onclick={on_open_video}
                        class="text-blue-600 hover:underline">
-                        { if let Some(metadata) = &*video_metadata {
-                            &metadata.title
-                        } else {
-                            &props.video_id
+                        { match &props.metadata {
+                            MetadataStatus::Loaded(metadata) => metadata.title.clone(),
+                            MetadataStatus::Unavailable => {
+                                "Video unavailable — may have been deleted".to_string()
+                            }
+                            _ => props.video_id.clone(),
                         }}
                     </a>
+                    if let Some(coverage) = props
+                        .metadata
+                        .metadata()
+                        .and_then(|metadata| metadata.caption_coverage)
+                        .filter(|coverage| *coverage < LOW_CAPTION_COVERAGE)
+                    {
+                        <span
+                            class="ml-2 text-sm cursor-help"
+                            title={format!("Captions cover ~{:.0}% of this video", coverage * 100.0)}
+                        >
+                            {"⚠️"}
+                        </span>
+                    }
+                    if props.metadata == MetadataStatus::Failed {
+                        <span class="ml-2 text-sm font-normal text-red-600">
+                            {"Couldn't load video details. "}
+                            <button
+                                class="underline hover:text-red-800"
+                                onclick={
+                                    let on_retry = props.on_retry_metadata.clone();
+                                    move |e: MouseEvent| {
+                                        // Don't toggle the group
+                                        e.stop_propagation();
+                                        on_retry.emit(());
+                                    }
+                                }
+                            >
+                                {"Retry"}
+                            </button>
+                        </span>
+                    }
                 </h3>
-                <span class="text-gray-600">
-                    {if *expanded { "▼" } else { "▶" }}
+                <span class="text-gray-600 flex items-center gap-3">
+                    <span class="text-sm">{match_count_label(&props.results)}</span>
+                    {if props.expanded { "▼" } else { "▶" }}
                 </span>
             </div>
             {
-                if *expanded {
+                if props.expanded {
                     html! {
                         <div>
-                            { if let Some(metadata) = &*video_metadata {
+                            { if let Some(metadata) = props.metadata.metadata() {
                                 html! {
                                     <div class="bg-gray-50 p-4 text-sm flex flex-wrap gap-4">
                                         <p class="flex items-center">{"📺 "}<a href={format!("https://www.youtube.com/channel/{}",&metadata.channel_id)} class="text-blue-600 hover:underline">{&metadata.channel_name}</a></p>
                                         <p class="flex items-center">{"📅 "}<span>{format_unix_date(metadata.upload_date)}</span></p>
                                         <p class="flex items-center">{"⏱️ "}<span>{format_duration(metadata.duration)}</span></p>
-                                        <p class="flex items-center">{"👁️ "}<span>{format_number(metadata.views)}</span></p>
-                                        <p class="flex items-center">{"👍 "}<span>{format_number(metadata.likes)}</span></p>
-                                        <p class="flex items-center">{"💬 "}<span>{format_number(metadata.comment_count)}</span></p>
+                                        <p class="flex items-center">{"👁️ "}<span>{format_count(metadata.views)}</span></p>
+                                        <p class="flex items-center">{"👍 "}<span>{format_count(metadata.likes)}</span></p>
+                                        <p class="flex items-center">{"💬 "}<span>{format_count(metadata.comment_count)}</span></p>
                                     </div>
                                 }
                             } else {
                                 html! {}
                             }}
+                            <MatchTimeline
+                                results={props.results.clone()}
+                                duration={props.metadata.metadata().map(|m| m.duration).unwrap_or(0)}
+                            />
                             <div class="divide-y divide-gray-200">
                                 { for props.results.iter().map(|result| html! {
-                                    <SearchResultItem result={result.clone()} />
+                                    <SearchResultItem result={result.clone()} query={props.query.clone()} rank={props.rank} />
                                 })}
                             </div>
                         </div>
@@ -190,15 +556,425 @@ pub fn video_results(props: &VideoResultsProps) -> Html {
     }
 }
 
+#[function_component(CompactResultRow)]
+pub fn compact_result_row(props: &CompactResultRowProps) -> Html {
+    let video_metadata = use_state(|| None);
+    let error_message = use_state(|| None);
+    let loading = use_state(|| false);
+    let public_config = use_public_config();
+
+    {
+        let video_id = props.result.video_id.clone();
+        let video_metadata = video_metadata.clone();
+        let error_message = error_message.clone();
+        let loading = loading.clone();
+
+        use_effect_with(video_id, move |video_id| {
+            let video_id = video_id.clone();
+            loading.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                get_video_metadata(video_id, video_metadata, error_message, loading).await;
+            });
+            || ()
+        });
+    }
+
+    let result = &props.result;
+    let send_click = {
+        let query = props.query.clone();
+        let video_id = result.video_id.clone();
+        let rank = props.rank;
+        move || send_feedback(&query, &video_id, rank, FeedbackAction::Click)
+    };
+    let on_keydown = {
+        let video_id = result.video_id.clone();
+        let start_time = result.start_time;
+        let send_click = send_click.clone();
+        move |e: KeyboardEvent| {
+            if move_row_focus(&e) {
+                return;
+            }
+            if e.key() == "Enter" {
+                send_click();
+                open_video(&video_id, start_time);
+            }
+        }
+    };
+    let on_open_video = Callback::from(move |_: MouseEvent| send_click());
+
+    html! {
+        <div class="flex items-center gap-3 p-2 bg-gray-100 rounded-lg focus:outline-none focus:ring-2 focus:ring-blue-500"
+             tabindex="0"
+             data-result-row="true"
+             onkeydown={on_keydown}>
+            <a href={format!("https://www.youtube.com/watch?v={}&t={}s", result.video_id, result.start_time as i64)}
+               target="_blank"
+               tabindex="-1"
+               onclick={on_open_video.clone()}
+               class="flex-shrink-0">
+                <img src={thumbnail_src(&public_config, &result.video_id)}
+                     alt=""
+                     loading="lazy"
+                     class="w-32 h-18 object-cover rounded" />
+            </a>
+            <div class="min-w-0 flex-grow">
+                <div class="flex items-center gap-2">
+                    <a href={format!("https://www.youtube.com/watch?v={}", result.video_id)}
+                       target="_blank"
+                       tabindex="-1"
+                       onclick={on_open_video}
+                       class="font-semibold text-blue-600 hover:underline truncate">
+                        { if let Some(metadata) = &*video_metadata {
+                            metadata.title.clone()
+                        } else {
+                            result.video_id.clone()
+                        }}
+                    </a>
+                    {
+                        if let Some(count) = result.match_count {
+                            html! {
+                                <span class="flex-shrink-0 px-2 py-0.5 text-xs rounded-full bg-blue-100 text-blue-700"
+                                      title="Matching captions in this video">
+                                    {count}
+                                </span>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
+                </div>
+                {
+                    if let Some(metadata) = &*video_metadata {
+                        html! {
+                            <p class="text-xs text-gray-500 truncate">
+                                {format!("{} · {}", metadata.channel_name, format_unix_date(metadata.upload_date))}
+                            </p>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+                <p class="text-sm text-gray-700 truncate">
+                    <span class="text-gray-500 mr-1">{format_duration(result.start_time as i64)}</span>
+                    { Html::from_html_unchecked(AttrValue::from(result.snippet_html.clone())) }
+                </p>
+            </div>
+        </div>
+    }
+}
+
+/// Results grouped by video, videos in the order of their first result. Hits of a video
+/// that aren't next to each other still end up in one group.
+fn group_by_video(results: &[SearchResult]) -> Vec<(String, Vec<&SearchResult>)> {
+    let mut groups: Vec<(String, Vec<&SearchResult>)> = Vec::new();
+    let mut group_of: HashMap<&str, usize> = HashMap::new();
+    for result in results {
+        match group_of.get(result.video_id.as_str()) {
+            Some(&group) => groups[group].1.push(result),
+            None => {
+                group_of.insert(&result.video_id, groups.len());
+                groups.push((result.video_id.clone(), vec![result]));
+            }
+        }
+    }
+    groups
+}
+
 #[function_component(ResultsList)]
 pub fn results_list(props: &ResultsListProps) -> Html {
+    let show_trend = use_state(|| false);
+
+    // Pagination scrolls to the top right away, a revisited page gets its
+    // offset back once its results have loaded
+    {
+        let page_states = props.page_states.clone();
+        use_effect_with(
+            (props.current_page, props.loading),
+            move |(page, loading)| {
+                if !*loading {
+                    let scroll_y = page_states.0.borrow().get(page).map(|state| state.scroll_y);
+                    if let (Some(scroll_y), Some(window)) = (scroll_y, web_sys::window()) {
+                        window.scroll_to_with_x_and_y(0.0, scroll_y);
+                    }
+                }
+                || ()
+            },
+        );
+    }
+
+    // Remembers the scroll offset of the page that is being left
+    let leave_page = {
+        let page_states = props.page_states.clone();
+        let current_page = props.current_page;
+        move || {
+            if let Some(window) = web_sys::window() {
+                let scroll_y = window.scroll_y().unwrap_or(0.0);
+                page_states
+                    .0
+                    .borrow_mut()
+                    .entry(current_page)
+                    .or_default()
+                    .scroll_y = scroll_y;
+                window.scroll_to_with_x_and_y(0.0, 0.0);
+            }
+        }
+    };
+
+    // Videos of this page in backend order
+    let grouped_videos = group_by_video(&props.results);
+    let video_ids: Vec<String> = grouped_videos
+        .iter()
+        .map(|(video_id, _)| video_id.clone())
+        .collect();
+
+    // Ranks count videos across all pages, starting at 1
+    let first_rank = props.current_page * RESULTS_PER_PAGE + 1;
+    let rank_of = {
+        let video_ids = video_ids.clone();
+        move |video_id: &str| {
+            first_rank + video_ids.iter().position(|id| id == video_id).unwrap_or(0)
+        }
+    };
+
+    // A shown page counts as an impression of every rank up to its last one
+    {
+        let last_rank = (first_rank + video_ids.len()).saturating_sub(1);
+        use_effect_with(
+            (
+                props.query.clone(),
+                props.current_page,
+                props.loading,
+                last_rank,
+            ),
+            move |(query, _, loading, last_rank)| {
+                if !*loading && *last_rank >= first_rank {
+                    send_feedback(query, "", *last_rank, FeedbackAction::View);
+                }
+                || ()
+            },
+        );
+    }
+
+    // One batch request for the group headers instead of one per video.
+    // The compact view rows load their own metadata.
+    let video_metadata = use_state(HashMap::<String, Option<VideoMetadata>>::new);
+    let metadata_failed = use_state(|| false);
+    let metadata_attempt = use_state(|| 0u32);
+    {
+        let video_metadata = video_metadata.clone();
+        let metadata_failed = metadata_failed.clone();
+        let ids = if props.view == ViewMode::Detailed {
+            video_ids.clone()
+        } else {
+            Vec::new()
+        };
+        use_effect_with((ids, *metadata_attempt), move |(ids, _)| {
+            // A response for ids that are no longer shown must not overwrite newer ones
+            let stale = Rc::new(Cell::new(false));
+            let ids = ids.clone();
+            if !ids.is_empty() {
+                let stale = stale.clone();
+                metadata_failed.set(false);
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = get_videos_metadata(&ids).await;
+                    if stale.get() {
+                        return;
+                    }
+                    match result {
+                        Ok(response) => {
+                            // Failed ids stay out of the map so their groups offer a retry
+                            let failed: HashSet<String> = response
+                                .errors
+                                .into_iter()
+                                .map(|error| error.video_id)
+                                .collect();
+                            metadata_failed.set(!failed.is_empty());
+                            video_metadata.set(
+                                ids.into_iter()
+                                    .zip(response.videos)
+                                    .filter(|(id, _)| !failed.contains(id))
+                                    .collect(),
+                            );
+                        }
+                        Err(e) => {
+                            web_sys::console::error_1(&e.into());
+                            metadata_failed.set(true);
+                        }
+                    }
+                });
+            }
+            move || stale.set(true)
+        });
+    }
+    let on_retry_metadata = {
+        let metadata_attempt = metadata_attempt.clone();
+        Callback::from(move |_| metadata_attempt.set(*metadata_attempt + 1))
+    };
+    let metadata_status = |video_id: &str| match video_metadata.get(video_id) {
+        Some(Some(metadata)) => MetadataStatus::Loaded(Box::new(metadata.clone())),
+        Some(None) => MetadataStatus::Unavailable,
+        None if *metadata_failed => MetadataStatus::Failed,
+        None => MetadataStatus::Loading,
+    };
+
+    // The expanded videos live in the page state cache, which doesn't
+    // re-render on its own
+    let rerender = use_force_update();
+    let set_expanded = {
+        let page_states = props.page_states.clone();
+        let current_page = props.current_page;
+        move |video_ids: Vec<String>, expanded: bool| {
+            {
+                let mut states = page_states.0.borrow_mut();
+                let state = states.entry(current_page).or_default();
+                for video_id in video_ids {
+                    if expanded {
+                        state.expanded.insert(video_id);
+                    } else {
+                        state.expanded.remove(&video_id);
+                    }
+                }
+            }
+            rerender.force_update();
+        }
+    };
+    let expanded_videos = props
+        .page_states
+        .0
+        .borrow()
+        .get(&props.current_page)
+        .map(|state| state.expanded.clone())
+        .unwrap_or_default();
+    let on_toggle_video = {
+        let set_expanded = set_expanded.clone();
+        let expanded_videos = expanded_videos.clone();
+        let query = props.query.clone();
+        let rank_of = rank_of.clone();
+        Callback::from(move |video_id: String| {
+            let expanded = !expanded_videos.contains(&video_id);
+            if expanded {
+                send_feedback(
+                    &query,
+                    &video_id,
+                    rank_of(&video_id),
+                    FeedbackAction::Expand,
+                );
+            }
+            // The URL points at the last expanded group so it can be shared
+            if expanded {
+                set_result_anchor_params(Some(&ResultAnchor {
+                    video_id: video_id.clone(),
+                    hit: None,
+                }));
+            } else if get_result_anchor().is_some_and(|anchor| anchor.video_id == video_id) {
+                set_result_anchor_params(None);
+            }
+            set_expanded(vec![video_id], expanded);
+        })
+    };
+    let all_expanded = video_ids.iter().all(|id| expanded_videos.contains(id));
+    let on_expand_all = {
+        let video_ids = video_ids.clone();
+        let set_expanded = set_expanded.clone();
+        move |_| set_expanded(video_ids.clone(), !all_expanded)
+    };
+
+    // A shared link is applied in three steps: the search completes, its group
+    // gets expanded and after that render the page scrolls to the nearest snippet.
+    // None while the results are pending, Some(None) if the video isn't on this page.
+    let anchor_target: Option<Option<String>> = props
+        .anchor
+        .as_ref()
+        .filter(|_| !props.loading && props.total_results.is_some())
+        .map(|anchor| {
+            let mut matches = props
+                .results
+                .iter()
+                .filter(|result| result.video_id == anchor.video_id);
+            match anchor.hit {
+                Some(hit) => matches
+                    .min_by(|a, b| {
+                        (a.start_time - hit)
+                            .abs()
+                            .total_cmp(&(b.start_time - hit).abs())
+                    })
+                    .map(result_item_id),
+                None => matches
+                    .next()
+                    .map(|result| video_group_id(&result.video_id)),
+            }
+        });
+    let scroll_target = use_state(|| None::<String>);
+    let stale_anchor = use_state(|| false);
+    {
+        let anchor = props.anchor.clone();
+        let on_anchor_applied = props.on_anchor_applied.clone();
+        let scroll_target = scroll_target.clone();
+        let stale_anchor = stale_anchor.clone();
+        use_effect_with(anchor_target, move |target| {
+            if let (Some(target), Some(anchor)) = (target, anchor) {
+                match target {
+                    Some(element_id) => {
+                        set_expanded(vec![anchor.video_id.clone()], true);
+                        set_result_anchor_params(Some(&anchor));
+                        scroll_target.set(Some(element_id.clone()));
+                    }
+                    None => stale_anchor.set(true),
+                }
+                on_anchor_applied.emit(());
+            }
+            || ()
+        });
+    }
+    use_effect_with((*scroll_target).clone(), |target| {
+        if let Some(element) = target
+            .as_ref()
+            .and_then(|id| web_sys::window()?.document()?.get_element_by_id(id))
+        {
+            element.scroll_into_view();
+        }
+        || ()
+    });
+    {
+        let stale_anchor = stale_anchor.clone();
+        use_effect_with((props.query.clone(), props.current_page), move |_| {
+            stale_anchor.set(false);
+            || ()
+        });
+    }
+    let warning_notice = if props.warnings.is_empty() {
+        html! {}
+    } else {
+        html! {
+            <p class="mb-4 p-2 bg-gray-50 text-xs text-gray-600 rounded-lg">
+                { for props.warnings.iter().map(|warning| html! {
+                    <span class="block">{ describe_warning(warning) }</span>
+                })}
+            </p>
+        }
+    };
+
+    let stale_anchor_notice = if *stale_anchor {
+        html! {
+            <p class="mb-4 p-3 bg-yellow-50 text-sm text-yellow-800 rounded-lg">
+                {"The linked video isn't on this page of results anymore, it may no longer match the search."}
+            </p>
+        }
+    } else {
+        html! {}
+    };
+
     if props.results.is_empty()
         && !props.loading
         && props.error.is_none()
         && !props.query.is_empty()
     {
         return html! {
-            <p class="text-center text-gray-500">{"No results found."}</p>
+            <>
+                { stale_anchor_notice }
+                { warning_notice }
+                <p class="text-center text-gray-500">{"No results found."}</p>
+            </>
         };
     }
 
@@ -209,55 +985,117 @@ pub fn results_list(props: &ResultsListProps) -> Html {
         1
     };
 
-    let mut last_video = String::new();
-    let mut current_group = Vec::new();
-    let mut grouped_videos = Vec::new();
-
-    // Group results while preserving video order from backend
-    for result in props.results.iter() {
-        if result.video_id != last_video {
-            if !current_group.is_empty() {
-                grouped_videos.push((last_video.clone(), current_group));
-                current_group = Vec::new();
-            }
-            last_video = result.video_id.clone();
-        }
-        current_group.push(result);
-    }
-    if !current_group.is_empty() {
-        grouped_videos.push((last_video, current_group));
-    }
+    let view_toggle = html! {
+        <div class="inline-flex rounded border border-gray-300 overflow-hidden">
+            { for [ViewMode::Detailed, ViewMode::Compact].into_iter().map(|view| {
+                let selected = view == props.view;
+                let on_view_change = props.on_view_change.clone();
+                let onclick = {
+                    let view = view.clone();
+                    move |_| {
+                        if !selected {
+                            on_view_change.emit(view.clone());
+                        }
+                    }
+                };
+                html! {
+                    <button
+                        {onclick}
+                        aria-pressed={selected.to_string()}
+                        class={if selected {
+                            "px-3 py-1 text-xs bg-blue-600 text-white"
+                        } else {
+                            "px-3 py-1 text-xs bg-white text-gray-700 hover:bg-gray-100"
+                        }}
+                    >
+                        { view.display_name() }
+                    </button>
+                }
+            })}
+        </div>
+    };
 
     html! {
         <div class="mt-8">
+            { stale_anchor_notice }
+            { warning_notice }
             // Add results summary
             {
                 if let Some((total_videos, total_captions)) = props.total_results {
                     html! {
-                        <div class="mb-4 p-3 bg-blue-50 rounded-lg text-center">
+                        <div class="mb-4 p-3 bg-blue-50 rounded-lg flex items-center justify-between gap-4">
                             <p class="text-sm text-gray-700">
                                 {format!("Found {} matching videos with {} total caption matches for \"{}\"",
-                                    total_videos, total_captions, props.query)}
+                                    format_count(total_videos as i64), format_count(total_captions as i64), props.query)}
                             </p>
+                            <div class="flex items-center gap-2">
+                                <button
+                                    onclick={
+                                        let show_trend = show_trend.clone();
+                                        move |_| show_trend.set(!*show_trend)
+                                    }
+                                    aria-pressed={show_trend.to_string()}
+                                    class="px-3 py-1 text-xs bg-white text-gray-700 border border-gray-300 rounded hover:bg-gray-100"
+                                >
+                                    {"Trend"}
+                                </button>
+                                if props.view == ViewMode::Detailed && !video_ids.is_empty() {
+                                    <button
+                                        onclick={on_expand_all}
+                                        class="px-3 py-1 text-xs bg-white text-gray-700 border border-gray-300 rounded hover:bg-gray-100"
+                                    >
+                                        {if all_expanded { "Collapse all" } else { "Expand all" }}
+                                    </button>
+                                }
+                                { view_toggle }
+                            </div>
                         </div>
                     }
                 } else {
                     html! {}
                 }
             }
+            if *show_trend {
+                <PhraseTrend query={props.query.clone()} />
+            }
 
-            <div class="space-y-6">
-                { for grouped_videos.clone().into_iter().map(|(video_id, results)| {
-                    let mut sorted_results = results.iter().map(|&r| r.clone()).collect::<Vec<_>>();
-                    sorted_results.sort_by(|a, b| a.start_time.partial_cmp(&b.start_time).unwrap());
+            {
+                if props.view == ViewMode::Compact {
                     html! {
-                        <VideoResults
-                            video_id={video_id}
-                            results={sorted_results}
-                        />
+                        <div class="space-y-2">
+                            { for props.results.iter().map(|result| html! {
+                                <CompactResultRow
+                                    result={result.clone()}
+                                    query={props.query.clone()}
+                                    rank={rank_of(&result.video_id)}
+                                />
+                            })}
+                        </div>
                     }
-                })}
-            </div>
+                } else {
+                    html! {
+                        <div class="space-y-6">
+                            { for grouped_videos.clone().into_iter().map(|(video_id, results)| {
+                                let mut sorted_results = results.iter().map(|&r| r.clone()).collect::<Vec<_>>();
+                                sorted_results.sort_by(|a, b| a.start_time.partial_cmp(&b.start_time).unwrap());
+                                html! {
+                                    <VideoResults
+                                        key={video_id.clone()}
+                                        expanded={expanded_videos.contains(&video_id)}
+                                        on_toggle={on_toggle_video.clone()}
+                                        metadata={metadata_status(&video_id)}
+                                        on_retry_metadata={on_retry_metadata.clone()}
+                                        rank={rank_of(&video_id)}
+                                        video_id={video_id.clone()}
+                                        results={sorted_results}
+                                        query={props.query.clone()}
+                                    />
+                                }
+                            })}
+                        </div>
+                    }
+                }
+            }
 
             // Update pagination to show more detailed info
             <div class="mt-6 flex flex-col items-center gap-2">
@@ -266,13 +1104,12 @@ pub fn results_list(props: &ResultsListProps) -> Html {
                         onclick={
                             let on_page_change = props.on_page_change.clone();
                             let current_page = props.current_page;
+                            let leave_page = leave_page.clone();
                             move |_| {
+                                leave_page();
                                 if current_page > 0 {
                                     on_page_change.emit(current_page - 1);
                                 }
-                                if let Some(window) = web_sys::window() {
-                                    window.scroll_to_with_x_and_y(0.0, 0.0);
-                                }
                             }
                         }
                         disabled={props.current_page == 0 || props.loading}
@@ -288,12 +1125,10 @@ pub fn results_list(props: &ResultsListProps) -> Html {
                             let on_page_change = props.on_page_change.clone();
                             let current_page = props.current_page;
                             move |_| {
+                                leave_page();
                                 if current_page < total_pages.saturating_sub(1) {
                                     on_page_change.emit(current_page + 1);
                                 }
-                                if let Some(window) = web_sys::window() {
-                                    window.scroll_to_with_x_and_y(0.0, 0.0);
-                                }
                             }
                         }
                         disabled={props.current_page >= total_pages.saturating_sub(1) || props.loading}
@@ -317,3 +1152,419 @@ pub fn results_list(props: &ResultsListProps) -> Html {
         </div>
     }
 }
+
+const TREND_INTERVALS: [&str; 3] = ["week", "month", "year"];
+const TREND_WIDTH: f64 = 600.0;
+const TREND_HEIGHT: f64 = 120.0;
+
+/// Line chart of how often the query was said, per week, month or year of the videos'
+/// upload dates
+#[function_component(PhraseTrend)]
+pub fn phrase_trend(props: &PhraseTrendProps) -> Html {
+    let interval = use_state(|| "month");
+    let frequency = use_state(|| None::<PhraseFrequencyResponse>);
+    let error = use_state(|| None::<String>);
+    {
+        let frequency = frequency.clone();
+        let error = error.clone();
+        use_effect_with(
+            (props.query.clone(), *interval),
+            move |(query, interval)| {
+                let query = query.clone();
+                let interval = *interval;
+                frequency.set(None);
+                error.set(None);
+                wasm_bindgen_futures::spawn_local(async move {
+                    match get_phrase_frequency(&query, interval).await {
+                        Ok(response) => frequency.set(Some(response)),
+                        Err(e) => error.set(Some(e)),
+                    }
+                });
+                || ()
+            },
+        );
+    }
+
+    let interval_buttons = html! {
+        <div class="inline-flex rounded border border-gray-300 overflow-hidden">
+            { for TREND_INTERVALS.into_iter().map(|option| {
+                let selected = option == *interval;
+                let onclick = {
+                    let interval = interval.clone();
+                    move |_| interval.set(option)
+                };
+                html! {
+                    <button
+                        {onclick}
+                        aria-pressed={selected.to_string()}
+                        class={if selected {
+                            "px-3 py-1 text-xs bg-blue-600 text-white"
+                        } else {
+                            "px-3 py-1 text-xs bg-white text-gray-700 hover:bg-gray-100"
+                        }}
+                    >
+                        { option }
+                    </button>
+                }
+            })}
+        </div>
+    };
+
+    let chart = match (&*frequency, &*error) {
+        (_, Some(e)) => {
+            html! { <p class="text-sm text-red-600">{format!("Failed to load the trend: {}", e)}</p> }
+        }
+        (None, None) => html! { <p class="text-sm text-gray-500">{"Loading..."}</p> },
+        (Some(frequency), None) if frequency.buckets.is_empty() => {
+            html! { <p class="text-sm text-gray-500">{"No dated matches."}</p> }
+        }
+        (Some(frequency), None) => {
+            let buckets = &frequency.buckets;
+            let max = buckets.iter().map(|b| b.captions).max().unwrap_or(0).max(1) as f64;
+            let step = TREND_WIDTH / (buckets.len().max(2) - 1) as f64;
+            let point = |index: usize, captions: i64| {
+                (
+                    index as f64 * step,
+                    TREND_HEIGHT - captions as f64 / max * TREND_HEIGHT,
+                )
+            };
+            let line = buckets
+                .iter()
+                .enumerate()
+                .map(|(index, bucket)| {
+                    let (x, y) = point(index, bucket.captions);
+                    format!("{:.1},{:.1}", x, y)
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            html! {
+                <>
+                    <svg
+                        viewBox={format!("-4 -4 {} {}", TREND_WIDTH + 8.0, TREND_HEIGHT + 8.0)}
+                        class="w-full h-32"
+                        aria-label="Matches over time"
+                    >
+                        <polyline points={line} fill="none" stroke="#2563eb" stroke-width="2" />
+                        { for buckets.iter().enumerate().map(|(index, bucket)| {
+                            let (x, y) = point(index, bucket.captions);
+                            html! {
+                                <circle cx={format!("{:.1}", x)} cy={format!("{:.1}", y)} r="3" fill="#2563eb">
+                                    <title>
+                                        {format!("{}: {} matches in {} videos",
+                                            format_unix_date(bucket.start),
+                                            format_count(bucket.captions),
+                                            format_count(bucket.videos))}
+                                    </title>
+                                </circle>
+                            }
+                        })}
+                    </svg>
+                    <div class="flex justify-between text-xs text-gray-500">
+                        <span>{ format_unix_date(buckets[0].start) }</span>
+                        <span>{format!("peak: {} matches", format_count(max as i64))}</span>
+                        <span>{ format_unix_date(buckets[buckets.len() - 1].start) }</span>
+                    </div>
+                </>
+            }
+        }
+    };
+
+    html! {
+        <div class="mb-4 p-3 bg-white border border-gray-200 rounded-lg">
+            <div class="flex items-center justify-between mb-2">
+                <p class="text-sm text-gray-700">
+                    {format!("How often \"{}\" was said, by upload date", props.query)}
+                </p>
+                { interval_buttons }
+            </div>
+            { chart }
+        </div>
+    }
+}
+
+/// Videos shown on the home page, fewer if fewer videos have a score
+const FREQUENTLY_FOUND_COUNT: usize = 6;
+
+/// "Frequently found" panel of the home page, videos matching many of last week's
+/// popular queries. Not rendered if the backend doesn't keep a search log.
+#[function_component(FrequentlyFound)]
+pub fn frequently_found() -> Html {
+    let enabled = use_public_config().features.trending_matches;
+    let videos = use_state(Vec::<TrendingVideo>::new);
+    {
+        let videos = videos.clone();
+        use_effect_with(enabled, move |enabled| {
+            if *enabled {
+                wasm_bindgen_futures::spawn_local(async move {
+                    match get_trending_matches(FREQUENTLY_FOUND_COUNT).await {
+                        Ok(list) => videos.set(list),
+                        Err(e) => web_sys::console::warn_1(&e.into()),
+                    }
+                });
+            }
+            || ()
+        });
+    }
+
+    if !enabled || videos.is_empty() {
+        return html! {};
+    }
+    html! {
+        <div class="max-w-2xl mx-auto mt-6 p-4 bg-white rounded-lg shadow">
+            <h2 class="text-sm font-semibold text-gray-600 mb-2">{"Frequently found"}</h2>
+            <ul class="space-y-1">
+                { for videos.iter().map(|video| html! {
+                    <li class="text-sm truncate">
+                        <Link<Route> to={Route::Video { id: video.video_id.clone() }}
+                                     classes="text-blue-600 hover:underline">
+                            { &video.title }
+                        </Link<Route>>
+                        <span class="text-gray-500">{format!(" · {}", video.channel_name)}</span>
+                    </li>
+                })}
+            </ul>
+        </div>
+    }
+}
+
+/// "Surprise me" button of the home page, shows a random quote of the archive. A shared
+/// link with `quote=<seed>` opens with that quote.
+#[function_component(SurpriseMe)]
+pub fn surprise_me() -> Html {
+    let quote = use_state(|| None::<RandomQuote>);
+    let loading = use_state(|| false);
+    let error = use_state(|| None::<String>);
+    let navigator = use_navigator();
+
+    let load = {
+        let quote = quote.clone();
+        let loading = loading.clone();
+        let error = error.clone();
+        Callback::from(move |seed: Option<u64>| {
+            let quote = quote.clone();
+            let loading = loading.clone();
+            let error = error.clone();
+            loading.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                match get_random_quote(seed).await {
+                    Ok(random_quote) => {
+                        set_quote_param(random_quote.seed);
+                        quote.set(Some(random_quote));
+                        error.set(None);
+                    }
+                    Err(e) => error.set(Some(format!("No quote found: {e}"))),
+                }
+                loading.set(false);
+            });
+        })
+    };
+
+    {
+        let load = load.clone();
+        use_effect_with((), move |_| {
+            if let Some(seed) = get_quote_param() {
+                load.emit(Some(seed));
+            }
+            || ()
+        });
+    }
+
+    let on_click = {
+        let load = load.clone();
+        Callback::from(move |_: MouseEvent| load.emit(None))
+    };
+
+    let on_open_transcript = {
+        let quote = quote.clone();
+        Callback::from(move |e: MouseEvent| {
+            if e.ctrl_key() || e.meta_key() || e.shift_key() {
+                return;
+            }
+            if let (Some(navigator), Some(quote)) = (&navigator, &*quote) {
+                e.prevent_default();
+                open_transcript(navigator, &quote.result.video_id, quote.result.start_time);
+            }
+        })
+    };
+
+    html! {
+        <div class="max-w-2xl mx-auto mt-6">
+            <div class="text-center">
+                <button
+                    onclick={on_click}
+                    disabled={*loading}
+                    class="bg-gray-100 text-gray-700 px-4 py-2 rounded hover:bg-gray-200 disabled:opacity-50"
+                >
+                    {if *loading { "Picking…" } else { "Surprise me" }}
+                </button>
+            </div>
+            if let Some(msg) = &*error {
+                <p class="text-sm text-red-600 text-center mt-2">{ msg }</p>
+            }
+            if let Some(quote) = &*quote {
+                <figure class="mt-4 p-4 bg-white rounded-lg shadow">
+                    <blockquote class="text-gray-800 italic">
+                        { Html::from_html_unchecked(AttrValue::from(quote.result.snippet_html.clone())) }
+                    </blockquote>
+                    <figcaption class="text-sm text-gray-500 mt-2">
+                        <a href={transcript_link(&quote.result.video_id, quote.result.start_time)}
+                           onclick={on_open_transcript}
+                           class="text-blue-600 hover:underline">
+                            { &quote.video.title }
+                        </a>
+                        { format!(" · {} · {}", quote.video.channel_name, format_duration(quote.result.start_time as i64)) }
+                    </figcaption>
+                </figure>
+            }
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ChannelFilterProps {
+    pub excluded: Vec<String>,
+    pub on_change: Callback<Vec<String>>,
+}
+
+/// Collapsible list of channels to leave out of the results. Topics the archive made
+/// public narrow the list down to the channels tagged with them.
+#[function_component(ChannelFilter)]
+pub fn channel_filter(props: &ChannelFilterProps) -> Html {
+    let channels = use_state(Vec::<PublicChannel>::new);
+    let topic = use_state(|| None::<String>);
+    {
+        let channels = channels.clone();
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                match get_channels().await {
+                    Ok(list) => channels.set(list),
+                    Err(e) => web_sys::console::warn_1(&e.into()),
+                }
+            });
+            || ()
+        });
+    }
+
+    if channels.is_empty() {
+        return html! {};
+    }
+
+    let toggle = |channel_id: String| {
+        let excluded = props.excluded.clone();
+        let on_change = props.on_change.clone();
+        Callback::from(move |_| {
+            let mut excluded = excluded.clone();
+            match excluded.iter().position(|id| *id == channel_id) {
+                Some(index) => {
+                    excluded.remove(index);
+                }
+                None => excluded.push(channel_id.clone()),
+            }
+            on_change.emit(excluded);
+        })
+    };
+
+    let mut topics: Vec<&String> = channels.iter().flat_map(|c| c.tags.iter()).collect();
+    topics.sort();
+    topics.dedup();
+    let select_topic = |selected: Option<String>| {
+        let topic = topic.clone();
+        Callback::from(move |_| topic.set(selected.clone()))
+    };
+
+    let summary = if props.excluded.is_empty() {
+        "Exclude channels".to_string()
+    } else {
+        format!("Exclude channels ({})", props.excluded.len())
+    };
+
+    html! {
+        <details class="mb-4 text-sm">
+            <summary class="cursor-pointer text-center text-gray-700">{summary}</summary>
+            if !topics.is_empty() {
+                <div class="mt-2 flex flex-wrap gap-1 justify-center">
+                    <button
+                        type="button"
+                        onclick={select_topic(None)}
+                        class={classes!("px-2", "py-0.5", "rounded", if topic.is_none() { "bg-blue-600 text-white" } else { "bg-gray-200 text-gray-700" })}
+                    >
+                        {"All topics"}
+                    </button>
+                    { for topics.iter().map(|tag| html! {
+                        <button
+                            type="button"
+                            onclick={select_topic(Some((*tag).clone()))}
+                            class={classes!("px-2", "py-0.5", "rounded", if topic.as_ref() == Some(*tag) { "bg-blue-600 text-white" } else { "bg-gray-200 text-gray-700" })}
+                        >
+                            {*tag}
+                        </button>
+                    })}
+                </div>
+            }
+            <div class="mt-2 max-h-48 overflow-y-auto border border-gray-200 rounded p-2 grid grid-cols-2 gap-1">
+                { for channels.iter().filter(|channel| topic.as_ref().is_none_or(|topic| channel.tags.contains(topic))).map(|channel| html! {
+                    <label
+                        class="inline-flex items-center min-w-0"
+                        title={format!("{} videos", channel.video_count)}
+                    >
+                        <input
+                            type="checkbox"
+                            class="form-checkbox h-4 w-4 text-blue-600"
+                            checked={props.excluded.contains(&channel.channel_id)}
+                            onchange={toggle(channel.channel_id.clone())}
+                        />
+                        <span class="ml-2 text-gray-700 truncate">{&channel.channel_name}</span>
+                    </label>
+                })}
+            </div>
+        </details>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn result(video_id: &str, start_time: f64) -> SearchResult {
+        SearchResult {
+            video_id: video_id.to_string(),
+            start_time,
+            end_time: start_time + 2.0,
+            snippet_html: format!("{video_id} at {start_time}"),
+            snippet_text: None,
+            match_count: None,
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn interleaved_results_give_one_group_per_video() {
+        let results = vec![
+            result("bbbbbbbbbbb", 10.0),
+            result("aaaaaaaaaaa", 5.0),
+            result("bbbbbbbbbbb", 30.0),
+            result("ccccccccccc", 1.0),
+            result("aaaaaaaaaaa", 50.0),
+            result("bbbbbbbbbbb", 20.0),
+        ];
+
+        let groups: Vec<(String, Vec<f64>)> = group_by_video(&results)
+            .into_iter()
+            .map(|(video_id, hits)| (video_id, hits.iter().map(|hit| hit.start_time).collect()))
+            .collect();
+
+        assert_eq!(
+            groups,
+            [
+                ("bbbbbbbbbbb".to_string(), vec![10.0, 30.0, 20.0]),
+                ("aaaaaaaaaaa".to_string(), vec![5.0, 50.0]),
+                ("ccccccccccc".to_string(), vec![1.0]),
+            ]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn no_results_give_no_groups() {
+        assert!(group_by_video(&[]).is_empty());
+    }
+}
@@ -12,6 +12,7 @@ pub enum SortBy {
     Views,
     Likes,
     CaptionMatches,
+    ViewVelocity,
 }
 
 impl SortBy {
@@ -23,6 +24,7 @@ impl SortBy {
             SortBy::Views => "Views",
             SortBy::Likes => "Likes",
             SortBy::CaptionMatches => "Caption matches",
+            SortBy::ViewVelocity => "Trending",
         }
     }
 
@@ -34,6 +36,7 @@ impl SortBy {
             SortBy::Views,
             SortBy::Likes,
             SortBy::CaptionMatches,
+            SortBy::ViewVelocity,
         ]
     }
 }
@@ -54,6 +57,36 @@ impl SortOrder {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViewMode {
+    Detailed, // every caption hit, grouped per video
+    Compact,  // one row per video with its best hit
+}
+
+impl ViewMode {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ViewMode::Detailed => "Detailed",
+            ViewMode::Compact => "Compact",
+        }
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            ViewMode::Detailed => "detailed",
+            ViewMode::Compact => "compact",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "detailed" => Some(ViewMode::Detailed),
+            "compact" => Some(ViewMode::Compact),
+            _ => None,
+        }
+    }
+}
+
 // Keys used in <option value="..."> so we can reliably map back and forth.
 fn sort_by_key(sb: &SortBy) -> &'static str {
     match sb {
@@ -63,6 +96,7 @@ fn sort_by_key(sb: &SortBy) -> &'static str {
         SortBy::Views => "views",
         SortBy::Likes => "likes",
         SortBy::CaptionMatches => "caption_matches",
+        SortBy::ViewVelocity => "view_velocity",
     }
 }
 
@@ -74,6 +108,7 @@ fn sort_by_from_key(key: &str) -> Option<SortBy> {
         "views" => Some(SortBy::Views),
         "likes" => Some(SortBy::Likes),
         "caption_matches" => Some(SortBy::CaptionMatches),
+        "view_velocity" => Some(SortBy::ViewVelocity),
         _ => None,
     }
 }
@@ -94,7 +129,7 @@ fn sort_order_from_key(key: &str) -> Option<SortOrder> {
 }
 
 // Helper to read "value" from any event target without HtmlSelectElement.
-fn event_value(e: &Event) -> Option<String> {
+pub fn event_value(e: &Event) -> Option<String> {
     let target = e.target()?;
     let js_value = Reflect::get(target.as_ref(), &JsValue::from_str("value")).ok()?;
     js_value.as_string()
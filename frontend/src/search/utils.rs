@@ -1,4 +1,5 @@
-use crate::models::FilterParameter;
+use crate::models::{FilterParameter, ResultAnchor};
+use crate::search::search_options::ViewMode;
 use web_sys::console;
 
 pub fn get_query_param() -> Option<String> {
@@ -19,7 +20,7 @@ pub fn get_filter_param() -> Option<FilterParameter> {
     web_sys::window()
         .and_then(|window| window.location().search().ok())
         .and_then(|search| web_sys::UrlSearchParams::new_with_str(&search).ok())
-        .and_then(|params| {
+        .map(|params| {
             let mut search_type = "natural".to_string();
             match &params.get("t") {
                 Some(val) => {
@@ -32,6 +33,141 @@ pub fn get_filter_param() -> Option<FilterParameter> {
                 }
                 None => console::log_1(&"search-type: Not found".into()),
             }
-            Some(FilterParameter { search_type })
+            let exact_terms = params.get("exact").is_some_and(|val| val == "true");
+            let exclude_channels = params
+                .get("exclude_channels")
+                .map(|val| {
+                    val.split(',')
+                        .filter(|id| !id.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default();
+            FilterParameter {
+                search_type,
+                exact_terms,
+                exclude_channels,
+            }
         })
 }
+
+/// Zero based result page, the URL counts from 1
+pub fn get_page_param() -> usize {
+    web_sys::window()
+        .and_then(|window| window.location().search().ok())
+        .and_then(|search| web_sys::UrlSearchParams::new_with_str(&search).ok())
+        .and_then(|params| params.get("page"))
+        .and_then(|page| page.parse::<usize>().ok())
+        .map(|page| page.saturating_sub(1))
+        .unwrap_or(0)
+}
+
+/// `expanded=<video_id>` and the optional `hit=<start_time>` of a shared link
+pub fn get_result_anchor() -> Option<ResultAnchor> {
+    let params = web_sys::window()
+        .and_then(|window| window.location().search().ok())
+        .and_then(|search| web_sys::UrlSearchParams::new_with_str(&search).ok())?;
+    let video_id = params.get("expanded").filter(|id| !id.is_empty())?;
+    let hit = params.get("hit").and_then(|hit| hit.parse::<f64>().ok());
+    Some(ResultAnchor { video_id, hit })
+}
+
+/// Points the current URL at a video group and snippet, without adding a history entry
+pub fn set_result_anchor_params(anchor: Option<&ResultAnchor>) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let Some(url) = window
+        .location()
+        .href()
+        .ok()
+        .and_then(|href| web_sys::Url::new(&href).ok())
+    else {
+        return;
+    };
+    let search_params = url.search_params();
+
+    match anchor {
+        Some(anchor) => {
+            search_params.set("expanded", &anchor.video_id);
+            match anchor.hit {
+                Some(hit) => search_params.set("hit", &hit.to_string()),
+                None => search_params.delete("hit"),
+            }
+        }
+        None => {
+            search_params.delete("expanded");
+            search_params.delete("hit");
+        }
+    }
+
+    if let Ok(history) = window.history() {
+        let _ = history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&url.href()));
+    }
+}
+
+/// `quote=<seed>` of a shared random quote
+pub fn get_quote_param() -> Option<u64> {
+    web_sys::window()
+        .and_then(|window| window.location().search().ok())
+        .and_then(|search| web_sys::UrlSearchParams::new_with_str(&search).ok())
+        .and_then(|params| params.get("quote"))
+        .and_then(|seed| seed.parse::<u64>().ok())
+}
+
+/// Points the current URL at a random quote, without adding a history entry
+pub fn set_quote_param(seed: u64) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let Some(url) = window
+        .location()
+        .href()
+        .ok()
+        .and_then(|href| web_sys::Url::new(&href).ok())
+    else {
+        return;
+    };
+    url.search_params().set("quote", &seed.to_string());
+
+    if let Ok(history) = window.history() {
+        let _ = history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&url.href()));
+    }
+}
+
+const VIEW_MODE_STORAGE_KEY: &str = "search_view";
+
+/// View mode from the URL, falling back to the last one stored in localStorage
+pub fn get_view_param() -> ViewMode {
+    let window = match web_sys::window() {
+        Some(window) => window,
+        None => return ViewMode::Detailed,
+    };
+
+    let from_url = window
+        .location()
+        .search()
+        .ok()
+        .and_then(|search| web_sys::UrlSearchParams::new_with_str(&search).ok())
+        .and_then(|params| params.get("view"));
+
+    let from_storage = || {
+        window
+            .local_storage()
+            .ok()
+            .flatten()
+            .and_then(|storage| storage.get_item(VIEW_MODE_STORAGE_KEY).ok())
+            .flatten()
+    };
+
+    from_url
+        .or_else(from_storage)
+        .and_then(|key| ViewMode::from_key(&key))
+        .unwrap_or(ViewMode::Detailed)
+}
+
+pub fn store_view_mode(view: &ViewMode) {
+    if let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) {
+        let _ = storage.set_item(VIEW_MODE_STORAGE_KEY, view.key());
+    }
+}
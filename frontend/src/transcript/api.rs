@@ -0,0 +1,20 @@
+use crate::env_variable_utils::BACKEND_URL;
+use crate::models::{VideoMetadata, VideoTranscript};
+use starchive_api_types::client::ApiClient;
+
+/// One segment of the transcript, `language` None for the language with the most captions
+pub async fn load_transcript_segment(
+    video_id: &str,
+    language: Option<&str>,
+    segment: i64,
+) -> Result<Option<VideoTranscript>, String> {
+    Ok(ApiClient::new(&BACKEND_URL)
+        .transcript_segment(video_id, language, segment)
+        .await?)
+}
+
+pub async fn load_video_metadata(video_id: &str) -> Result<Option<VideoMetadata>, String> {
+    Ok(ApiClient::new(&BACKEND_URL)
+        .video_metadata(video_id)
+        .await?)
+}
@@ -0,0 +1,434 @@
+use crate::models::{caption_segment, TranscriptLine, VideoMetadata, VideoTranscript};
+use crate::router::Route;
+use crate::transcript::api::{load_transcript_segment, load_video_metadata};
+use crate::utils::format_duration;
+use js_sys::Reflect;
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+/// Fragment pointing to the caption at `start_time`, e.g. `t=754`. Also the DOM id of
+/// the first caption starting in that second.
+pub fn caption_anchor(start_time: f64) -> String {
+    format!("t={}", start_time as i64)
+}
+
+/// In-app transcript of a video, scrolled to the caption at `start_time`
+pub fn transcript_link(video_id: &str, start_time: f64) -> String {
+    format!("/video/{}#{}", video_id, caption_anchor(start_time))
+}
+
+/// Opens the in-app transcript at `start_time` without reloading the app
+pub fn open_transcript(navigator: &Navigator, video_id: &str, start_time: f64) {
+    navigator.push(&Route::Video {
+        id: video_id.to_string(),
+    });
+    if let Some(window) = web_sys::window() {
+        let _ = window.location().set_hash(&caption_anchor(start_time));
+    }
+}
+
+/// Seconds of the current `#t=754` fragment
+fn anchor_seconds() -> Option<i64> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    hash.trim_start_matches('#')
+        .strip_prefix("t=")?
+        .parse()
+        .ok()
+}
+
+/// Index of the caption an anchor points to: the first caption of the last second
+/// starting at or before it
+fn anchor_target(lines: &[TranscriptLine], seconds: i64) -> Option<usize> {
+    if lines.is_empty() {
+        return None;
+    }
+    let last = lines
+        .iter()
+        .rposition(|line| line.start_time as i64 <= seconds)
+        .unwrap_or(0);
+    let second = lines[last].start_time as i64;
+    lines
+        .iter()
+        .position(|line| line.start_time as i64 == second)
+}
+
+/// Distance to the end of the page below which the next segment is loaded
+const LOAD_AHEAD_PX: f64 = 1500.0;
+
+/// Whether the page is scrolled to within `LOAD_AHEAD_PX` of its end
+fn near_page_end() -> bool {
+    let Some(window) = web_sys::window() else {
+        return false;
+    };
+    let viewport_end = window.scroll_y().unwrap_or(0.0)
+        + window
+            .inner_height()
+            .ok()
+            .and_then(|height| height.as_f64())
+            .unwrap_or(0.0);
+    let page_height = window
+        .document()
+        .and_then(|document| document.document_element())
+        .map_or(0.0, |element| element.scroll_height() as f64);
+    viewport_end >= page_height - LOAD_AHEAD_PX
+}
+
+enum SegmentAction {
+    Reset,
+    Want(i64),
+}
+
+/// Highest transcript segment that should be loaded. Scrolling and anchors only ever
+/// raise it, until the page shows another video.
+#[derive(PartialEq)]
+struct WantedSegment(i64);
+
+impl Reducible for WantedSegment {
+    type Action = SegmentAction;
+
+    fn reduce(self: Rc<Self>, action: SegmentAction) -> Rc<Self> {
+        match action {
+            SegmentAction::Reset => Rc::new(WantedSegment(0)),
+            SegmentAction::Want(segment) if segment > self.0 => Rc::new(WantedSegment(segment)),
+            SegmentAction::Want(_) => self,
+        }
+    }
+}
+
+fn copy_to_clipboard(text: &str) -> bool {
+    let Some(window) = web_sys::window() else {
+        return false;
+    };
+    let written = Reflect::get(&window, &JsValue::from_str("navigator"))
+        .and_then(|navigator| Reflect::get(&navigator, &JsValue::from_str("clipboard")))
+        .and_then(|clipboard| {
+            let write_text = Reflect::get(&clipboard, &JsValue::from_str("writeText"))?
+                .dyn_into::<js_sys::Function>()?;
+            write_text.call1(&clipboard, &JsValue::from_str(text))
+        });
+    written.is_ok()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct TranscriptPageProps {
+    pub video_id: String,
+}
+
+/// All captions of a video, loaded one segment at a time while scrolling down.
+/// `#t=<seconds>` scrolls to and highlights the caption at that time, on load and
+/// whenever the fragment changes.
+#[function_component(TranscriptPage)]
+pub fn transcript_page(props: &TranscriptPageProps) -> Html {
+    // The segments loaded so far, `segment` is the last of them
+    let transcript = use_state(|| None::<VideoTranscript>);
+    let wanted_segment = use_reducer(|| WantedSegment(0));
+    let loading_segment = use_mut_ref(|| false);
+    // Set while the anchor's segment isn't loaded yet
+    let pending_jump = use_mut_ref(|| true);
+    let metadata = use_state(|| None::<VideoMetadata>);
+    let loading = use_state(|| true);
+    let error_message = use_state(|| None::<String>);
+    // Caption index and jump count, a new count restarts the highlight animation
+    let target = use_state(|| None::<(usize, u32)>);
+    let jumps = use_mut_ref(|| 0u32);
+    let copied = use_state(|| None::<usize>);
+
+    {
+        let transcript = transcript.clone();
+        let metadata = metadata.clone();
+        let loading = loading.clone();
+        let error_message = error_message.clone();
+        let wanted_segment = wanted_segment.clone();
+        let pending_jump = pending_jump.clone();
+
+        use_effect_with(props.video_id.clone(), move |video_id| {
+            let video_id = video_id.clone();
+            loading.set(true);
+            wanted_segment.dispatch(SegmentAction::Reset);
+            *pending_jump.borrow_mut() = true;
+            wasm_bindgen_futures::spawn_local(async move {
+                match load_video_metadata(&video_id).await {
+                    Ok(video) => metadata.set(video),
+                    Err(e) => error_message.set(Some(format!("Failed to load video: {}", e))),
+                }
+                match load_transcript_segment(&video_id, None, 0).await {
+                    Ok(first) => transcript.set(first),
+                    Err(e) => error_message.set(Some(format!("Failed to load transcript: {}", e))),
+                }
+                loading.set(false);
+            });
+            || ()
+        });
+    }
+
+    // Appends the next segment until the wanted one is loaded
+    {
+        let transcript = transcript.clone();
+        let loading_segment = loading_segment.clone();
+        let error_message = error_message.clone();
+        let loaded = transcript.as_ref().and_then(|t| t.segment);
+
+        use_effect_with((wanted_segment.0, loaded), move |&(wanted, loaded)| {
+            let next = loaded.map(|loaded| loaded + 1);
+            if let (Some(current), Some(next)) = (&*transcript, next) {
+                if next <= wanted && next < current.segment_count && !*loading_segment.borrow() {
+                    *loading_segment.borrow_mut() = true;
+                    let current = current.clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        let loaded = load_transcript_segment(
+                            &current.video_id,
+                            Some(&current.language_code),
+                            next,
+                        )
+                        .await;
+                        *loading_segment.borrow_mut() = false;
+                        match loaded {
+                            Ok(Some(segment)) => {
+                                let mut lines = current.lines;
+                                lines.extend(segment.lines);
+                                transcript.set(Some(VideoTranscript {
+                                    lines,
+                                    segment: Some(next),
+                                    ..segment
+                                }));
+                            }
+                            Ok(None) => {}
+                            Err(e) => error_message
+                                .set(Some(format!("Failed to load more captions: {}", e))),
+                        }
+                    });
+                }
+            }
+            || ()
+        });
+    }
+
+    // Asks for the next segment once the reader gets close to the end of the loaded ones
+    {
+        let wanted_segment = wanted_segment.clone();
+        let next = transcript
+            .as_ref()
+            .and_then(|t| t.segment)
+            .map(|segment| segment + 1);
+
+        use_effect_with(next, move |&next| {
+            let listener = next.map(|next| {
+                let load_more = move || {
+                    if near_page_end() {
+                        wanted_segment.dispatch(SegmentAction::Want(next));
+                    }
+                };
+                // Short segments may not fill the page, nothing would ever scroll
+                load_more();
+                Closure::<dyn Fn()>::new(load_more)
+            });
+            let window = web_sys::window();
+            if let (Some(window), Some(listener)) = (&window, &listener) {
+                let _ = window
+                    .add_event_listener_with_callback("scroll", listener.as_ref().unchecked_ref());
+            }
+            move || {
+                if let (Some(window), Some(listener)) = (window, listener) {
+                    let _ = window.remove_event_listener_with_callback(
+                        "scroll",
+                        listener.as_ref().unchecked_ref(),
+                    );
+                }
+            }
+        });
+    }
+
+    // Jump to the anchor once its captions are there and on every fragment change
+    {
+        let lines = transcript
+            .as_ref()
+            .map(|t| t.lines.clone())
+            .unwrap_or_default();
+        let loaded = transcript.as_ref().and_then(|t| t.segment);
+        let segment_count = transcript.as_ref().map_or(0, |t| t.segment_count);
+        let target = target.clone();
+        let wanted_segment = wanted_segment.clone();
+
+        use_effect_with(
+            (
+                props.video_id.clone(),
+                transcript.as_ref().map(|t| t.lines.len()),
+                loaded,
+            ),
+            move |_| {
+                let jump = {
+                    let pending_jump = pending_jump.clone();
+                    Rc::new(move || {
+                        let Some(seconds) = anchor_seconds() else {
+                            *pending_jump.borrow_mut() = false;
+                            return;
+                        };
+                        // Links into later parts of the video load everything up to them
+                        let segment = caption_segment(seconds as f64).min(segment_count - 1);
+                        if loaded.is_some_and(|loaded| segment > loaded) {
+                            *pending_jump.borrow_mut() = true;
+                            wanted_segment.dispatch(SegmentAction::Want(segment));
+                            return;
+                        }
+                        if let Some(index) = anchor_target(&lines, seconds) {
+                            *pending_jump.borrow_mut() = false;
+                            *jumps.borrow_mut() += 1;
+                            target.set(Some((index, *jumps.borrow())));
+                        }
+                    })
+                };
+                // Loading further segments while scrolling mustn't jump back up
+                if *pending_jump.borrow() {
+                    jump();
+                }
+
+                let listener = Closure::<dyn Fn()>::new(move || jump());
+                let window = web_sys::window();
+                if let Some(window) = &window {
+                    let _ = window.add_event_listener_with_callback(
+                        "hashchange",
+                        listener.as_ref().unchecked_ref(),
+                    );
+                }
+                move || {
+                    if let Some(window) = window {
+                        let _ = window.remove_event_listener_with_callback(
+                            "hashchange",
+                            listener.as_ref().unchecked_ref(),
+                        );
+                    }
+                }
+            },
+        );
+    }
+
+    // Scroll after the target was rendered
+    {
+        let anchor = (*target).and_then(|(index, jump)| {
+            let line = transcript.as_ref()?.lines.get(index)?;
+            Some((caption_anchor(line.start_time), jump))
+        });
+        use_effect_with(anchor, |anchor| {
+            if let Some(element) = anchor
+                .as_ref()
+                .and_then(|(id, _)| web_sys::window()?.document()?.get_element_by_id(id))
+            {
+                element.scroll_into_view();
+            }
+            || ()
+        });
+    }
+
+    let youtube_link = format!("https://www.youtube.com/watch?v={}", props.video_id);
+    let title = metadata
+        .as_ref()
+        .map(|m| m.title.clone())
+        .unwrap_or_else(|| props.video_id.clone());
+
+    let render_lines = |transcript: &VideoTranscript| -> Html {
+        let mut previous_second = None;
+        transcript
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| {
+                let second = line.start_time as i64;
+                // Only the first caption of a second carries the anchor id
+                let id = (previous_second != Some(second)).then(|| caption_anchor(line.start_time));
+                previous_second = Some(second);
+
+                let (key, flash) = match *target {
+                    Some((target_index, jump)) if target_index == index => {
+                        (format!("{}-{}", index, jump), Some("caption-flash"))
+                    }
+                    _ => (index.to_string(), None),
+                };
+                let on_copy = {
+                    let copied = copied.clone();
+                    let link = transcript_link(&transcript.video_id, line.start_time);
+                    Callback::from(move |_: MouseEvent| {
+                        let origin = web_sys::window()
+                            .and_then(|w| w.location().origin().ok())
+                            .unwrap_or_default();
+                        if copy_to_clipboard(&format!("{}{}", origin, link)) {
+                            copied.set(Some(index));
+                        }
+                    })
+                };
+
+                html! {
+                    <p {key} {id} class={classes!("group", "flex", "gap-3", "px-2", "py-1", "rounded", "scroll-mt-4", flash)}>
+                        <a href={format!("#{}", caption_anchor(line.start_time))}
+                           class="text-sm text-blue-600 hover:underline shrink-0 tabular-nums">
+                            {format_duration(second)}
+                        </a>
+                        <span class="flex-grow text-gray-800">{&line.text}</span>
+                        <button
+                            onclick={on_copy}
+                            title="Copy link to this caption"
+                            class="text-xs text-gray-400 hover:text-blue-600 opacity-0 group-hover:opacity-100 shrink-0"
+                        >
+                            {if *copied == Some(index) { "Copied" } else { "🔗" }}
+                        </button>
+                    </p>
+                }
+            })
+            .collect()
+    };
+
+    html! {
+        <div class="min-h-screen bg-gray-700 p-4">
+            <div class="max-w-4xl mx-auto">
+                <div class="bg-white rounded-lg shadow-lg p-8">
+                    <div class="flex justify-between items-start gap-4 mb-6">
+                        <div>
+                            <h1 class="text-2xl font-bold text-gray-800">{title}</h1>
+                            if let Some(video) = &*metadata {
+                                <Link<Route>
+                                    to={Route::Channel { id: video.channel_id.clone() }}
+                                    classes="text-sm text-gray-500 hover:underline"
+                                >
+                                    {&video.channel_name}
+                                </Link<Route>>
+                            }
+                        </div>
+                        <div class="flex flex-col items-end gap-1 shrink-0">
+                            <Link<Route> to={Route::Home} classes="text-blue-600 hover:underline">
+                                {"← Back to Search"}
+                            </Link<Route>>
+                            <a href={youtube_link} target="_blank" class="text-blue-600 hover:underline text-sm">
+                                {"Watch on YouTube ↗"}
+                            </a>
+                        </div>
+                    </div>
+
+                    if let Some(msg) = &*error_message {
+                        <div class="bg-red-100 border border-red-400 text-red-700 px-4 py-3 rounded mb-4">
+                            { msg }
+                        </div>
+                    }
+
+                    {
+                        if *loading {
+                            html! { <p class="text-center py-8">{"Loading transcript..."}</p> }
+                        } else if let Some(transcript) = &*transcript {
+                            html! {
+                                <>
+                                    { render_lines(transcript) }
+                                    if transcript.segment.is_some_and(|segment| segment + 1 < transcript.segment_count) {
+                                        <p class="text-center py-4 text-gray-500">{"Loading more captions..."}</p>
+                                    }
+                                </>
+                            }
+                        } else {
+                            html! { <p class="text-center py-8 text-gray-500">{"No captions are indexed for this video."}</p> }
+                        }
+                    }
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -1,13 +1,45 @@
 use chrono::DateTime;
 
-// Formats each x1000 step
-pub fn format_number(number: i64) -> String {
-    let num_str = number.to_string();
-    let mut result = String::new();
-    let len = num_str.len();
+/// Full count with the thousands separator of the browser locale, e.g. "1,234,567".
+/// Used in tables and wherever the exact value matters.
+pub fn format_count(number: i64) -> String {
+    intl_format(number, false).unwrap_or_else(|| group_thousands(number))
+}
+
+/// Short count for cards and badges, e.g. "999", "1.2K", "3.4M"
+pub fn format_compact(number: i64) -> String {
+    intl_format(number, true).unwrap_or_else(|| compact_fallback(number))
+}
+
+/// Formats with `Intl.NumberFormat` of the browser's default locale
+#[cfg(target_arch = "wasm32")]
+fn intl_format(number: i64, compact: bool) -> Option<String> {
+    let options = js_sys::Object::new();
+    if compact {
+        js_sys::Reflect::set(&options, &"notation".into(), &"compact".into()).ok()?;
+        js_sys::Reflect::set(&options, &"maximumFractionDigits".into(), &1.into()).ok()?;
+    }
+    let formatter = js_sys::Intl::NumberFormat::new(&js_sys::Array::new(), &options);
+    formatter
+        .format()
+        .call1(&wasm_bindgen::JsValue::NULL, &(number as f64).into())
+        .ok()?
+        .as_string()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn intl_format(_number: i64, _compact: bool) -> Option<String> {
+    None
+}
 
-    for (i, c) in num_str.chars().enumerate() {
-        if i > 0 && (len - i) % 3 == 0 {
+fn group_thousands(number: i64) -> String {
+    let digits = number.unsigned_abs().to_string();
+    let mut result = String::new();
+    if number < 0 {
+        result.push('-');
+    }
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
             result.push(',');
         }
         result.push(c);
@@ -15,6 +47,35 @@ pub fn format_number(number: i64) -> String {
     result
 }
 
+fn compact_fallback(number: i64) -> String {
+    const UNITS: [(f64, &str); 3] = [(1e9, "B"), (1e6, "M"), (1e3, "K")];
+
+    let abs = number.unsigned_abs() as f64;
+    let sign = if number < 0 { "-" } else { "" };
+    let round = |size: f64| (abs / size * 10.0).round() / 10.0;
+    for (i, &(size, suffix)) in UNITS.iter().enumerate() {
+        if abs < size {
+            continue;
+        }
+        // 999,950 reads as 1M rather than 1000K
+        if round(size) >= 1000.0 && i > 0 {
+            let (size, suffix) = UNITS[i - 1];
+            return format!("{sign}{}{suffix}", trim_fraction(round(size)));
+        }
+        return format!("{sign}{}{suffix}", trim_fraction(round(size)));
+    }
+    number.to_string()
+}
+
+/// "1.0" -> "1", "1.2" -> "1.2"
+fn trim_fraction(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
 pub fn format_duration(seconds: i64) -> String {
     let minutes = (seconds as u32) / 60;
     let remaining_seconds = (seconds as u32) % 60;
@@ -26,3 +87,74 @@ pub fn format_unix_date(timestamp: i64) -> String {
         .unwrap_or_else(|| DateTime::from_timestamp(0, 0).unwrap());
     date.format("%Y-%m-%d").to_string()
 }
+
+/// Formats a unix millisecond timestamp relative to now, e.g. "5 min ago"
+pub fn format_relative_time(timestamp_ms: i64) -> String {
+    if timestamp_ms <= 0 {
+        return "N/A".to_string();
+    }
+    let seconds = ((js_sys::Date::now() as i64 - timestamp_ms) / 1000).max(0);
+    match seconds {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{} min ago", seconds / 60),
+        3600..=86399 => format!("{} h ago", seconds / 3600),
+        _ => format!("{} d ago", seconds / 86400),
+    }
+}
+
+/// Formats a unix millisecond timestamp as absolute time in the browser's timezone
+pub fn format_local_datetime(timestamp_ms: i64) -> String {
+    if timestamp_ms <= 0 {
+        return "N/A".to_string();
+    }
+    let date = js_sys::Date::new(&wasm_bindgen::JsValue::from_f64(timestamp_ms as f64));
+    String::from(date.to_locale_string("default", &wasm_bindgen::JsValue::UNDEFINED))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Natively `Intl.NumberFormat` is unavailable, so these check the fallbacks
+
+    #[test]
+    fn counts_group_thousands() {
+        for (number, expected) in [
+            (0, "0"),
+            (999, "999"),
+            (1_000, "1,000"),
+            (12_345, "12,345"),
+            (999_999, "999,999"),
+            (1_000_000, "1,000,000"),
+            (-1, "-1"),
+            (-1_000, "-1,000"),
+            (-1_234_567, "-1,234,567"),
+            (i64::MIN, "-9,223,372,036,854,775,808"),
+        ] {
+            assert_eq!(format_count(number), expected, "{number}");
+        }
+    }
+
+    #[test]
+    fn compact_counts() {
+        for (number, expected) in [
+            (0, "0"),
+            (999, "999"),
+            (1_000, "1K"),
+            (1_049, "1K"),
+            (1_050, "1.1K"),
+            (1_234, "1.2K"),
+            (999_949, "999.9K"),
+            (999_950, "1M"),
+            (1_000_000, "1M"),
+            (1_250_000, "1.3M"),
+            (999_950_000, "1B"),
+            (2_000_000_000, "2B"),
+            (-999, "-999"),
+            (-1_000, "-1K"),
+            (-1_234_567, "-1.2M"),
+        ] {
+            assert_eq!(format_compact(number), expected, "{number}");
+        }
+    }
+}